@@ -251,10 +251,23 @@ def generate_test_data() -> dict[str, str]:
     </data>
 </database>"""
 
+    # Attribute-heavy XML with values that need escaping
+    attr_items = []
+    for i in range(1000):
+        attr_items.append(f'''
+    <entry id="{i}" title="Tom &amp; Jerry's &quot;show&quot; #{i}"
+           query="a &lt; {i} &amp;&amp; b &gt; {i % 7}" path="/srv/data/{i % 13}/index.html"
+           note="plain text without special characters, just long enough {i}"/>''')
+
+    attr_xml = f"""<?xml version="1.0" encoding="utf-8"?>
+<entries>{"".join(attr_items)}
+</entries>"""
+
     return {
         "Small XML (0.3KB)": small_xml,
         "Medium XML (15KB)": medium_xml,
         "Large XML (150KB)": large_xml,
+        "Attr XML (220KB)": attr_xml,
     }
 
 
@@ -0,0 +1,74 @@
+"""Peak memory of parsing large documents, each measured in a fresh process.
+
+The result is kept small by a postprocessor dropping the repeated items, so what is
+measured is the memory parse() holds while reading, not the size of the result.
+"""
+
+import subprocess
+import sys
+import textwrap
+
+ITEMS = [100_000, 1_000_000, 4_000_000]
+
+INPUTS = {
+    "str": "xml",
+    "bytes": "xml.encode()",
+    "file": "io.BytesIO(xml.encode())",
+    "gzip": "gzip.compress(xml.encode())",
+}
+
+SCRIPT = """
+import gzip
+import io
+import resource
+import sys
+
+import xmltodict_rs
+
+
+def drop_items(path, key, value):
+    return None if key == "item" else (key, value)
+
+
+xml = "<root>" + "<item id='1'>text</item>" * {items} + "</root>"
+source = {source}
+before = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+xmltodict_rs.parse(source, postprocessor=drop_items)
+after = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+# ru_maxrss is in bytes on macOS and in KiB elsewhere.
+print(len(xml), (after - before) * (1 if sys.platform == "darwin" else 1024))
+"""
+
+
+def format_size(size: float) -> str:
+    """Formats a size in readable units (power of 2)"""
+    for unit in ["B", "KB", "MB"]:
+        if size < 1024:
+            return f"{size:.1f} {unit}"
+        size /= 1024
+    return f"{size:.1f} GB"
+
+
+def measure(items: int, source: str) -> tuple[int, int]:
+    """Parses a document of `items` elements in a new interpreter, returning the size of
+    the document and how much its peak memory grew while parsing"""
+    script = textwrap.dedent(SCRIPT).format(items=items, source=source)
+    run = subprocess.run(
+        [sys.executable, "-c", script], capture_output=True, text=True, check=True
+    )
+    size, growth = run.stdout.split()
+    return int(size), int(growth)
+
+
+def run_memory_benchmarks() -> None:
+    print("🧠 Peak memory while parsing (result dropped by a postprocessor)")
+    print(f"{'items':>10} {'input':>6} {'document':>10} {'peak growth':>12}")
+    for items in ITEMS:
+        for name, source in INPUTS.items():
+            size, growth = measure(items, source)
+            print(f"{items:>10} {name:>6} {format_size(size):>10} {format_size(growth):>12}")
+
+
+if __name__ == "__main__":
+    run_memory_benchmarks()
+    print("\n✅ Completed!")
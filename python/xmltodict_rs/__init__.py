@@ -1,3 +1,52 @@
 from .xmltodict_rs import *
 
-__all__ = ["parse", "unparse"]
+__all__ = [
+    "DepthLimitExceeded",
+    "EntitiesForbidden",
+    "LimitExceeded",
+    "Node",
+    "Parser",
+    "Serializer",
+    "SizeLimitExceeded",
+    "SoapFault",
+    "XmlEncodingError",
+    "XmlSyntaxError",
+    "XmlValidationError",
+    "build_soap",
+    "diff",
+    "dumps_xmlrpc",
+    "find",
+    "infer_schema",
+    "is_well_formed",
+    "iterparse",
+    "json_to_xml",
+    "loads_xmlrpc",
+    "minify",
+    "parse",
+    "parse_async",
+    "parse_batch",
+    "parse_events",
+    "parse_feed",
+    "parse_file",
+    "parse_in_thread",
+    "parse_many",
+    "parse_soap",
+    "parse_zip_member",
+    "patch",
+    "pretty",
+    "profile",
+    "read_records",
+    "roundtrip",
+    "to_csv",
+    "to_ndjson",
+    "transform",
+    "unparse",
+    "unparse_async",
+    "unparse_batch",
+    "unparse_fragment",
+    "unparse_iter",
+    "validate",
+    "validate_rnc",
+    "xml_to_json",
+    "xml_to_msgpack",
+]
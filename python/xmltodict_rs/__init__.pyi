@@ -4,16 +4,151 @@ This module provides a Rust-based implementation of xmltodict functionality
 with full type annotations for better IDE support and type checking.
 """
 
-from collections.abc import Collection, Generator
-from typing import Any, Callable, Protocol
+import concurrent.futures
+import os
+import xmlrpc.client
+import zipfile
+from collections.abc import (
+    AsyncIterable,
+    Awaitable,
+    Collection,
+    Coroutine,
+    Iterable,
+    Iterator,
+    Mapping,
+    MutableMapping,
+)
+from typing import IO, Any, Callable, Literal, Protocol, TypedDict
+from xml.parsers.expat import ExpatError
+
+from _typeshed import DataclassInstance
 
 class SupportsRead(Protocol):
     def read(self, size: int = ...) -> bytes: ...
 
-XMLInput = str | bytes | bytearray | SupportsRead | Generator[str | bytes, None, None]
+class SupportsWrite(Protocol):
+    def write(self, data: Any, /) -> Any: ...
+
+class SupportsAsyncWrite(Protocol):
+    def write(self, data: bytes, /) -> Awaitable[Any] | Any: ...
+
+class SupportsXml(Protocol):
+    def __xml__(self) -> Any: ...
+
+class SupportsAsdict(Protocol):
+    def _asdict(self) -> Mapping[str, Any]: ...
+
+XMLChunk = str | bytes | bytearray | memoryview
+XMLInput = str | bytes | bytearray | SupportsRead | Iterator[XMLChunk]
 XMLDict = dict[str, Any]
+XMLModel = SupportsXml | DataclassInstance | SupportsAsdict
 PostprocessorFunc = Callable[[list[str], str, Any], tuple[str, Any] | None]
 PreprocessorFunc = Callable[[str, Any], tuple[str, Any] | None]
+Transform = Literal["int", "float", "bool", "datetime", "strip", "drop"]
+
+class ValidationIssue(TypedDict):
+    path: str
+    line: int
+    expected: str
+    found: str | None
+    message: str
+
+class Difference(TypedDict):
+    path: str
+    kind: Literal["added", "removed", "changed"]
+    left: Any
+    right: Any
+
+class Profile(TypedDict):
+    elements: int
+    attributes: int
+    max_depth: int
+    max_siblings: int
+    tags: dict[str, int]
+    attribute_names: dict[str, int]
+    repeated: dict[str, int]
+
+class WellFormednessError(TypedDict):
+    message: str
+    code: int | None
+    line: int | None
+    column: int | None
+    offset: int | None
+
+class XmlSyntaxError(ExpatError):
+    """Raised for malformed documents; an ExpatError with `code`, `lineno` and `offset` attributes."""
+
+class XmlEncodingError(XmlSyntaxError):
+    """Raised when a document cannot be decoded from its encoding."""
+
+class LimitExceeded(XmlSyntaxError):
+    """Raised when a document exceeds one of the limits set for parse()."""
+
+class DepthLimitExceeded(LimitExceeded):
+    """Raised when a document exceeds max_depth or max_entity_depth."""
+
+class SizeLimitExceeded(LimitExceeded):
+    """Raised when a document exceeds max_attributes, max_text_length, max_total_nodes or
+    max_entity_expansion.
+    """
+
+class EntitiesForbidden(ValueError):
+    """Raised when a document contains a DTD or entity declaration forbidden by parse()."""
+
+class XmlValidationError(ValueError):
+    """Raised by parse(validate_against=...) for a document that does not follow the schema."""
+
+    errors: list[ValidationIssue]
+
+class SoapFault(Exception):
+    """Raised by parse_soap() for a Fault body, with `code`, `reason` and `detail` attributes."""
+
+    code: str
+    reason: str
+    detail: Any
+
+class Node:
+    """A node of a document read with parse(node_factory='ordered').
+
+    Unlike the dicts parse() builds, nodes keep attributes, text, comments, processing
+    instructions and child elements in the order they were written. Nodes can be pickled
+    and copied.
+
+    Examples:
+        >>> doc = parse('<p id="1">a<b>c</b>d</p>', node_factory="ordered")
+        >>> p = doc.children[0]
+        >>> p.tag, p.attributes, p.children[0], p.children[1].tag, p.text
+        ('p', [('id', '1')], 'a', 'b', 'ad')
+    """
+
+    def __init__(
+        self,
+        kind: Literal["document", "element", "comment", "pi", "doctype"],
+        tag: str | None = None,
+        attributes: Iterable[tuple[str, str]] = (),
+        children: Iterable[str | Node] = (),
+    ) -> None: ...
+    @property
+    def kind(self) -> Literal["document", "element", "comment", "pi", "doctype"]:
+        """What the node stands for."""
+        ...
+    @property
+    def tag(self) -> str | None:
+        """Name of an element, resolved as dict keys are; None for the other kinds."""
+        ...
+    @property
+    def attributes(self) -> list[tuple[str, str]]:
+        """(name, value) pairs of an element, in document order."""
+        ...
+    @property
+    def children(self) -> list[str | Node]:
+        """Text strings and nodes in document order; the text alone for a comment,
+        processing instruction or DOCTYPE declaration."""
+        ...
+    @property
+    def text(self) -> str:
+        """The text children joined, without the text of descendants."""
+        ...
 
 def parse(
     xml_input: XMLInput,
@@ -28,45 +163,247 @@ def parse(
     force_cdata: bool = False,
     cdata_separator: str = "",
     strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
     force_list: bool | Collection[str] | Callable[[list[str], str, Any], bool] | None = None,
     postprocessor: PostprocessorFunc | None = None,
     item_depth: int = 0,
     comment_key: str = "#comment",
-    namespaces: dict[str, str] | None = None,
+    namespaces: dict[str, str | None] | None = None,
+    max_entity_depth: int = 16,
+    max_entity_expansion: int = 10_000_000,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    preserve_mixed_content: bool = False,
+    process_doctype: bool = False,
+    doctype_key: str = "#doctype",
+    process_pis: bool = False,
+    pi_key: str = "#pi",
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+    attr_dict_key: str | None = None,
+    infer_types: bool | Iterable[str] | None = False,
+    transforms: dict[str, Transform] | None = None,
+    key_map: dict[str, str] | None = None,
+    dict_constructor: Callable[[], MutableMapping[str, Any]] | None = None,
+    only_paths: Iterable[str] | None = None,
+    collect_namespaces: bool = False,
+    namespaces_key: str = "#namespaces",
+    qname_attributes: Iterable[str] | None = None,
+    strip_namespace_prefixes: bool = False,
+    dialect: Literal["xml", "plist"] = "xml",
+    convention: Literal["xmltodict", "badgerfish", "parker", "gdata"] = "xmltodict",
+    xsd: str | bytes | None = None,
+    validate_against: str | bytes | None = None,
+    recover: bool = False,
+    warnings: list[str] | None = None,
+    void_elements: Iterable[str] | None = None,
+    resolve_entities: Literal["strict", "skip", "preserve"] = "strict",
+    decompress: Literal["auto", "none", "gzip", "zlib", "zstd"] = "auto",
+    node_factory: Literal["dict", "ordered"] = "dict",
+    with_spans: bool = False,
+    span_key: str = "#span",
+    raw_paths: Iterable[str] | None = None,
+    on_duplicate: Literal["list", "first", "last", "error"] = "list",
+    index_key: str | None = None,
+    preserve_sibling_order: bool = False,
+    order_key: str = "#order",
 ) -> XMLDict:
     """Parse XML string or bytes into a Python dictionary.
 
+    The document is built as it is read: each batch of events is tokenized with the GIL
+    released, then built, so a large document is never held whole as events. With
+    dialect='plist', xsd or validate_against, node_factory='ordered' or with_spans, the
+    whole document is tokenized before it is built.
+
     Args:
-        xml_input: XML data as string or bytes to parse
-        encoding: Character encoding (for compatibility, not used in Rust implementation)
+        xml_input: XML data as string or bytes, a binary file-like object, or a
+            generator/iterator yielding str/bytes/bytearray/memoryview chunks
+        encoding: Character encoding of byte input (e.g. 'cp1251', 'utf-16'); overrides the
+            XML declaration. Ignored for str input
         process_namespaces: If True, namespace prefixes are processed and expanded
         namespace_separator: Separator character between namespace and tag name (default ':')
-        disable_entities: If True, XML entities are disabled for security (default True)
-        process_comments: If True, XML comments are included in output with comment_key
+        disable_entities: If True, references to entities declared in the DTD are dropped from
+            text content instead of being expanded (default True). Expanded entities stay
+            text: unlike expat, markup in their replacement text, such as
+            <!ENTITY e "<b>x</b>">, is not read as child elements
+        process_comments: If True, XML comments are included in output with comment_key.
+            Comments outside the root element go in the top-level dict; when those before
+            and after it share a key, preserve_sibling_order lists the top-level keys in
+            document order under order_key
         xml_attribs: If True, XML attributes are included in output (default True)
         attr_prefix: Prefix for attribute keys in output dict (default '@')
         cdata_key: Key name for text content in output dict (default '#text')
         force_cdata: If True, text content is always wrapped in dict with cdata_key
         cdata_separator: Separator for multiple text nodes (default '')
         strip_whitespace: If True, whitespace-only text is removed (default True)
+        strip: Which ends of text lose their whitespace: 'both', 'leading', 'trailing' or
+            'none'; overrides strip_whitespace, which means 'both' or 'none' (default None)
+        honor_xml_space: If True, text inside xml:space="preserve" is kept as written, up
+            to an element with xml:space="default", as the XML spec asks (default True)
         force_list: Control when to create lists for repeated elements:
             - None/False: automatic list creation for repeated elements
             - True: always create lists
-            - set/list: create lists for specified tag names
+            - set/list: create lists for specified tag names; a set, frozenset, list or
+              tuple of strings is matched without calling back into Python
+            - paths in such a collection, like 'catalog/items/item' or '*/row', match from
+              the root element; '*' stands for one element name and '**' for any number
             - Callable: custom function (path, key, value) -> bool
         postprocessor: Optional callback to transform parsed data:
             - Called with (path, key, value)
             - Should return (new_key, new_value) tuple or None to skip
+            - Exceptions it raises propagate with a note naming the element path and
+              source line, e.g. 'while postprocessing root/item (line 12)'
         item_depth: Internal parameter for tracking parsing depth
         comment_key: Key name for XML comments in output (default '#comment')
-        namespaces: Optional dict mapping namespace URIs to prefixes
+        namespaces: Optional dict mapping namespace URIs to prefixes ("" or None drops the
+            namespace from names)
+        max_entity_depth: Maximum nesting of entity references inside DTD entity values
+        max_entity_expansion: Maximum number of bytes produced by entity expansion
+            in a single document; guards against "billion laughs" attacks
+        forbid_dtd: If True, any <!DOCTYPE> declaration raises EntitiesForbidden
+        forbid_entities: If True, any <!ENTITY> declaration raises EntitiesForbidden
+        preserve_mixed_content: If True, an element with both text and child elements
+            becomes an ordered list of text strings and single-key dicts, e.g.
+            {'p': ['hello ', {'b': 'world'}, ' again']}; its attributes come first as a dict.
+            Processed comments and processing instructions count as children here
+        process_doctype: If True, the <!DOCTYPE> declaration (without the `<!DOCTYPE ` and
+            `>` delimiters) is stored under `doctype_key` ahead of the root element
+        doctype_key: Key name for the DOCTYPE declaration (default '#doctype')
+        process_pis: If True, processing instructions other than the XML declaration are
+            included in output with pi_key, e.g. 'xml-stylesheet href="a.xsl"'
+        pi_key: Key name for processing instructions in output (default '#pi')
+        max_depth: Maximum element nesting depth, the root being at depth 1 (default None,
+            unlimited)
+        max_attributes: Maximum number of attributes on one element (default None)
+        max_text_length: Maximum length in bytes (UTF-8) of the text of one element,
+            CDATA sections included, however comments or children split it (default None)
+        max_total_nodes: Maximum number of elements in the document (default None)
+        attr_dict_key: Key of a nested dict holding each element's attributes under their
+            bare names, used instead of attr_prefix keys (default None)
+        infer_types: Convert text and attribute values to int, float, bool or None (for
+            'null') before postprocessing; True for all kinds or an iterable of 'int',
+            'float', 'bool' and 'null'. Numbers with leading zeros stay strings
+            (default False)
+        transforms: Conversions applied to entries before postprocessing, keyed by entry
+            key ('price', '@id', '#text') or by path from the root element as for
+            force_list ('catalog/item/@id', '**/price'). Each is 'int', 'float', 'bool'
+            ('true'/'false' or '1'/'0'), 'datetime' (ISO 8601), 'strip' or 'drop'; only
+            text values are converted (default None)
+        key_map: New keys for entries, keyed like transforms by entry key or path; a
+            matching path takes precedence over a plain key. force_list and postprocessor
+            see the new keys (default None)
+        dict_constructor: Mapping class, or any callable returning an empty mutable
+            mapping, used for the result and every dict in it, e.g. collections.OrderedDict;
+            the postprocessor receives these mappings (default None, plain dicts)
+        only_paths: Element paths to build, as for force_list ('rss/channel/item',
+            '**/title'); other elements are checked but not built. Elements leading to a
+            selected one are kept as containers without attributes or text, and the root
+            element is always kept; their namespace declarations still apply with
+            process_namespaces and collect_namespaces. Names are matched as written in the
+            document
+            (default None, everything)
+        collect_namespaces: If True, every prefix to URI declaration in the document is
+            stored as a dict under `namespaces_key` ahead of the root element, the default
+            namespace under '' and the first URI kept for a redeclared prefix, whether or
+            not namespaces are processed
+        namespaces_key: Key name for the collected declarations (default '#namespaces')
+        qname_attributes: Attributes, named as written in the document (e.g. 'xsi:type'),
+            whose `prefix:local` values are resolved like element names when
+            process_namespaces is True (default None)
+        strip_namespace_prefixes: If True, element and attribute names keep only their local
+            part, with or without process_namespaces; xmlns declarations keep their names
+        dialect: 'plist' reads an Apple property list into the value it holds, as
+            plistlib.loads() does: <dict> becomes a dict, <array> a list, <integer> an int,
+            <true/> True and so on. Only encoding, dict_constructor and the entity and
+            limit options apply then (default 'xml')
+        convention: Layout of attributes and text. 'badgerfish' makes every element a dict
+            with '@' attributes, an '@xmlns' dict of namespace declarations and text under
+            '$'; 'gdata' makes every element a dict with plain attribute keys, text under
+            '$t' and '$' for the ':' of prefixed names; 'parker' drops attributes, the text
+            of mixed content and the root element. Replaces attr_prefix, cdata_key,
+            force_cdata and attr_dict_key (default 'xmltodict')
+        xsd: XML Schema document whose declarations give entry conversions as transforms
+            would: built-in integer types to int, xs:decimal, xs:float and xs:double to
+            float, xs:boolean to bool and xs:dateTime to datetime. Elements with a
+            maxOccurs above one are always lists. Entries are found by the local names on
+            their path; transforms take precedence (default None)
+        validate_against: XML Schema document to check the document against before
+            building the result, as validate() does (default None)
+        recover: If True, repair common mistakes instead of raising: references to
+            unknown entities and a stray '&' are kept as text, an end tag closes the
+            elements opened inside the one it names, an end tag naming no open element
+            is ignored, and elements still open at the end of the input are closed
+            (default False)
+        warnings: List to which a message is appended for each mistake recover
+            repaired, e.g. 'unknown entity &nbsp; kept as text (line 3)' (default None)
+        void_elements: Names of elements written without an end tag, as in HTML, such
+            as {'br', 'hr'}; each is read as an empty element, and an end tag for one is
+            ignored. Names are matched as written, with any prefix (default None)
+        resolve_entities: What references to entities that are neither predefined nor
+            declared, such as &nbsp; in XHTML, become: 'strict' raises XmlSyntaxError,
+            'skip' leaves them out and 'preserve' keeps them as written. Applies to text and
+            attribute values (default 'strict')
+        decompress: Compression to undo while reading byte input: 'auto' recognizes gzip,
+            zlib and zstd data by their first bytes, 'none' reads the input as is, and
+            'gzip', 'zlib' or 'zstd' expect that format (default 'auto')
+        node_factory: 'ordered' returns a document Node instead of a dict, keeping
+            attributes, text, comments, processing instructions and children in document
+            order. Names follow the namespace options; options shaping dicts, such as
+            force_list, postprocessor or attr_prefix, do not apply (default 'dict')
+        with_spans: If True, the dict of each element holds a (start, end) tuple under
+            span_key: the byte offsets in the document, as UTF-8, of the '<' of its start
+            tag and of the end of its end tag. Elements whose value is text or None have no
+            dict to hold one; force_cdata gives text-only elements one (default False)
+        span_key: Key name for element spans (default '#span')
+        raw_paths: Element paths, as for only_paths ('soap:Envelope/soap:Body'), whose
+            elements are returned as the markup written in the document, from the '<' of
+            the start tag to the end of the end tag, instead of being built. The markup is
+            checked but kept untouched, entities and whitespace included; it stands as the
+            element's text (default None)
+        on_duplicate: What a second child element of the same name does: 'list' turns
+            the entry into a list of every value, 'first' and 'last' keep one of them and
+            'error' raises ValueError, so an entry never turns from a dict into a list
+            unexpectedly. Elements force_list makes lists still collect every value; the
+            ordered lists of preserve_mixed_content keep every child (default 'list')
+        index_key: Key under which each element other than the root keeps its position
+            among the child elements of its parent, from 0, so that document order can be
+            restored after force_list=True has grouped children by name, as
+            unparse(..., index_key=...) does. Every such element becomes a dict, its text
+            under cdata_key (default None)
+        preserve_sibling_order: If True, the dict of each element with children holds
+            under order_key the keys of its children, comments and processing instructions
+            in document order, e.g. ['a', 'b', 'a'] for <a/><b/><a/>, so that
+            unparse(..., preserve_sibling_order=True) writes them back in that order
+        order_key: Key name for the order of an element's children (default '#order')
 
     Returns:
-        Dictionary representation of the XML structure
+        Dictionary representation of the XML structure, or a Node of kind 'document' with
+        node_factory='ordered'
 
     Raises:
-        ValueError: If XML is malformed or has parsing errors
-        TypeError: If xml_input is not str or bytes
+        XmlSyntaxError: If XML is malformed; as with expat's ExpatError, the exception has
+            `code`, `lineno` (from 1) and `offset` (column, from 0) attributes
+        XmlEncodingError: If byte input cannot be decoded
+        DepthLimitExceeded: If the document exceeds max_depth or max_entity_depth
+        SizeLimitExceeded: If the document exceeds max_attributes, max_text_length,
+            max_total_nodes or max_entity_expansion
+        TypeError: If xml_input is not str or bytes, or void_elements is not an iterable
+            of strings
+        EntitiesForbidden: If forbid_dtd/forbid_entities is set and the document has a
+            DTD or entity declaration
+        LookupError: If encoding is not a known encoding name
+        ValueError: If infer_types or transforms names an unknown conversion, or a
+            transforms value does not convert, or for a malformed property list, or
+            resolve_entities is not 'strict', 'skip' or 'preserve', or decompress is not
+            'auto', 'none', 'gzip', 'zlib' or 'zstd', or node_factory is not 'dict' or
+            'ordered' or is 'ordered' with a dialect or convention, or with_spans is
+            combined with a dialect, convention, only_paths or node_factory='ordered', or
+            on_duplicate is 'error' and an element has two children of the same name
+        XmlValidationError: If the document does not follow validate_against; `errors`
+            holds the issues validate() would return
 
     Examples:
         >>> parse('<root><item>value</item></root>')
@@ -85,9 +422,354 @@ def parse(
     """
     ...
 
+def parse_file(path: str | os.PathLike[str], **kwargs: Any) -> XMLDict:
+    """Parse an XML file into a Python dictionary.
+
+    The file is memory-mapped instead of being read into a Python object, and decoding
+    runs without holding the GIL.
+
+    Args:
+        path: Path to the XML file
+        **kwargs: Same options as parse()
+
+    Raises:
+        OSError: If the file cannot be opened (e.g. FileNotFoundError)
+        ValueError: Same parse errors as parse()
+
+    Examples:
+        >>> parse_file('config.xml', force_list=('item',))
+        {'config': {'item': ['a']}}
+    """
+    ...
+
+def parse_zip_member(
+    archive: str | os.PathLike[str] | IO[bytes],
+    member: str | zipfile.ZipInfo,
+    **kwargs: Any,
+) -> XMLDict:
+    """Parse a member of a zip archive into a Python dictionary.
+
+    The member is read decompressed in chunks, as parse() reads a file-like object, so
+    Office, ODF or XBRL packages can be parsed without extracting them.
+
+    Args:
+        archive: Path to the zip archive, or a binary file-like object holding it
+        member: Name or ZipInfo of the member to parse
+        **kwargs: Same options as parse()
+
+    Raises:
+        OSError: If the archive cannot be opened
+        zipfile.BadZipFile: If the archive is not a zip archive
+        KeyError: If the archive has no such member
+        ValueError: Same parse errors as parse()
+
+    Examples:
+        >>> parse_zip_member('report.docx', 'word/document.xml')
+        {'w:document': {...}}
+    """
+    ...
+
+def parse_async(source: AsyncIterable[XMLChunk], **kwargs: Any) -> Coroutine[Any, Any, XMLDict]:
+    """Parse chunks from an async iterable into a Python dictionary.
+
+    Chunks are awaited on the event loop and tokenized on a worker thread of the loop's
+    default executor as they arrive, so other tasks keep running during a long parse.
+
+    Args:
+        source: Async iterable of str/bytes chunks, e.g. an aiohttp response.content
+        **kwargs: Same options as parse()
+
+    Returns:
+        Coroutine returning the dictionary parse() would
+
+    Raises:
+        TypeError: If source is not an async iterable
+        XmlSyntaxError: Same parse errors as parse(); errors raised by source propagate
+            unchanged
+
+    Examples:
+        >>> await parse_async(response.content.iter_chunked(65536))
+        {'root': {'item': 'value'}}
+    """
+    ...
+
+def parse_in_thread(
+    xml_input: XMLInput,
+    executor: concurrent.futures.Executor | None = None,
+    **kwargs: Any,
+) -> concurrent.futures.Future[XMLDict]:
+    """Parse XML on a worker thread, returning a future for the dictionary.
+
+    Decoding and tokenizing run without holding the GIL; only building the dictionary
+    holds it. On free-threaded Python, parses in separate threads run fully in parallel.
+
+    Args:
+        xml_input: Input as for parse()
+        executor: Executor to submit the parse to; by default a thread pool shared by all
+            calls (default None)
+        **kwargs: Same options as parse()
+
+    Returns:
+        Future whose result is the dictionary parse() would return, or whose exception is
+        the error it would raise
+
+    Examples:
+        >>> parse_in_thread(b'<root><item>value</item></root>').result()
+        {'root': {'item': 'value'}}
+    """
+    ...
+
+class EventHandler(Protocol):
+    """Receives parse_events() callbacks; every method is optional."""
+
+    def start(self, tag: str, attrs: dict[str, str], /) -> Any: ...
+    def end(self, tag: str, /) -> Any: ...
+    def data(self, text: str, /) -> Any: ...
+    def comment(self, text: str, /) -> Any: ...
+    def pi(self, target: str, data: str, /) -> Any: ...
+    def close(self) -> Any: ...
+
+def parse_events(
+    xml_input: XMLInput,
+    handler: EventHandler,
+    encoding: str | None = None,
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> Any:
+    """Parse XML by calling handler methods for each event instead of building a dict.
+
+    The handler is a target in the style of xml.etree.ElementTree.TreeBuilder, which can
+    be passed as is. Methods it does not define are skipped, and comments and processing
+    instructions are only read when it has comment() or pi(). Tag and attribute names are
+    reported as written, with any namespace prefix. The document is tokenized with the GIL
+    released before the first call, so malformed input raises before any callback runs.
+
+    Args:
+        xml_input: Same inputs as parse()
+        handler: Object with any of start(tag, attrs), end(tag), data(text),
+            comment(text), pi(target, data) and close()
+        encoding: Character encoding of byte input, as for parse()
+        strip_whitespace: If True, whitespace-only text is not reported and text is
+            trimmed (default True)
+        strip, honor_xml_space: As for parse()
+        disable_entities, forbid_dtd, forbid_entities, max_depth, max_attributes,
+        max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        The result of handler.close(), or None if it has no close()
+
+    Raises:
+        XmlSyntaxError: If XML is malformed, as for parse()
+        Exception: Whatever a handler method raises, with a note naming the method and
+            source line, e.g. 'while calling end() (line 3)'
+
+    Examples:
+        >>> import xml.etree.ElementTree as ET
+        >>> parse_events('<root><item>1</item></root>', ET.TreeBuilder())
+        <Element 'root' at 0x...>
+    """
+    ...
+
+IterEvent = Literal["start", "end", "data", "comment", "pi"]
+
+class IterParse(Iterator[tuple[IterEvent, tuple[str, ...], Any]]):
+    def __iter__(self) -> IterParse: ...
+    def __next__(self) -> tuple[IterEvent, tuple[str, ...], Any]: ...
+
+def iterparse(
+    source: XMLInput,
+    events: Iterable[IterEvent] | None = None,
+    encoding: str | None = None,
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> IterParse:
+    """Iterate over (event, path, value) tuples, parsing only as far as the caller reads.
+
+    File-like objects and chunk iterators are read as events are requested, so stopping
+    early skips the rest of the document, and syntax errors are raised when reached.
+    The path is a tuple of the open element names from the root, with the element itself
+    for 'start' and 'end'. Values are the attribute dict for 'start', the text for 'data',
+    'comment' and 'pi' (as 'target data'), and None for 'end'. Names are reported as
+    written, with any namespace prefix.
+
+    Args:
+        source: Same inputs as parse()
+        events: Events to yield among 'start', 'end', 'data', 'comment' and 'pi'
+            (default 'start', 'end' and 'data')
+        encoding, strip_whitespace, strip, honor_xml_space, disable_entities, forbid_dtd,
+        forbid_entities, max_depth, max_attributes, max_text_length, max_total_nodes: As
+            for parse_events()
+
+    Raises:
+        XmlSyntaxError: From next() when the document turns out to be malformed
+        ValueError: If events names an unknown event, or if the iterator is advanced
+            while it is reading input (e.g. from inside a chunk generator)
+
+    Examples:
+        >>> for event, path, value in iterparse(open('feed.xml', 'rb'), events=['data']):
+        ...     if path == ('feed', 'title'):
+        ...         break
+    """
+    ...
+
+class ParseMany(Iterator[XMLDict]):
+    def __iter__(self) -> ParseMany: ...
+    def __next__(self) -> XMLDict: ...
+
+def parse_many(source: XMLInput, **kwargs: Any) -> ParseMany:
+    """Iterate over the documents of input holding several back to back, one dict each.
+
+    Log files and streams often hold root elements one after another (`<e/><e/>`),
+    each possibly with its own XML declaration and DOCTYPE. Each document is built as
+    parse() would build it on its own, as soon as its root element closes; entities
+    declared in one DOCTYPE do not apply to later documents, and max_total_nodes counts
+    the elements of each document separately. File-like objects and chunk iterators are
+    read as documents are requested. Spans from with_spans are offsets into the whole
+    source.
+
+    Args:
+        source: Same inputs as parse()
+        **kwargs: Same options as parse()
+
+    Raises:
+        XmlSyntaxError: From next() when a document turns out to be malformed, including
+            input that ends inside one, or input without any element
+        ValueError: If the iterator is advanced while it is reading input (e.g. from
+            inside a chunk generator)
+
+    Examples:
+        >>> list(parse_many('<e id="1"/>\\n<e id="2"/>'))
+        [{'e': {'@id': '1'}}, {'e': {'@id': '2'}}]
+    """
+    ...
+
+def parse_batch(
+    documents: Iterable[str | bytes],
+    threads: int | None = None,
+    **kwargs: Any,
+) -> list[XMLDict]:
+    """Parse many documents into a list of dicts, tokenizing them in parallel.
+
+    Documents are decoded and tokenized on several threads with the GIL released, then
+    their dicts are built one after another, each as parse() would build it. Suits jobs
+    parsing thousands of small documents.
+
+    Args:
+        documents: Documents as str or bytes; bytes may be compressed as for parse()
+        threads: Number of threads to tokenize on (default: the number of CPUs)
+        **kwargs: Same options as parse()
+
+    Returns:
+        One dict per document, in the order given
+
+    Raises:
+        XmlSyntaxError: If a document is malformed; a note names the index of the first
+            such document
+        XmlValidationError: If validate_against is given and a document does not follow
+            the schema; the note names it as for XmlSyntaxError
+        TypeError: If a document is neither str nor bytes
+        ValueError: If threads is not positive
+
+    Examples:
+        >>> parse_batch(['<a>1</a>', b'<a>2</a>'], threads=2)
+        [{'a': '1'}, {'a': '2'}]
+    """
+    ...
+
+class Parser:
+    """Parses any number of documents with the same options.
+
+    The options are checked once, and each parse reuses the read buffer and the key
+    strings of the one before, which saves the setup parse() does on every call. Suits
+    services parsing many small messages. A parser may be shared between threads, and
+    pickling or copying one makes a new parser with the same options, as process pools do.
+
+    Args:
+        **kwargs: Same options as parse(), given by keyword
+
+    Examples:
+        >>> parser = Parser(force_list=("item",))
+        >>> parser.parse('<order><item>a</item></order>')
+        {'order': {'item': ['a']}}
+    """
+
+    def __init__(self, **kwargs: Any) -> None: ...
+    def parse(self, xml_input: XMLInput) -> XMLDict:
+        """Parse a document, accepted as by parse(), with the parser's options."""
+        ...
+
+def find(
+    xml_input: XMLInput,
+    path: str,
+    encoding: str | None = None,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    force_list: bool | Collection[str] | Callable[[list[str], str, Any], bool] | None = None,
+    infer_types: bool | Iterable[str] | None = False,
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> list[XMLDict]:
+    """Find the elements an XPath-like path selects, building only those.
+
+    The path is matched on the token stream, so no Python objects are built for the rest
+    of the document, which is still checked for well-formedness. Each match is returned as
+    parse() would return the element on its own, e.g. {'title': 'Ubik'}.
+
+    Supported syntax, a small subset of XPath:
+        - child steps from the root element: 'catalog/book/title' (a leading '/' is
+          optional)
+        - '*' for any element name: 'catalog/*/title'
+        - attribute predicates: "book[@id='3']", 'book[@lang]'
+        - positions from 1 among the siblings passing the step so far: 'book[2]',
+          "book[@lang='en'][2]"
+
+    Args:
+        xml_input: Same inputs as parse()
+        path: The path to select
+        encoding, attr_prefix, cdata_key, force_list, infer_types, strip_whitespace, strip,
+        honor_xml_space, disable_entities, forbid_dtd, forbid_entities, max_depth,
+        max_attributes, max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        The matches in document order
+
+    Raises:
+        ValueError: If the path uses unsupported syntax, such as '//' or '@id' steps
+        XmlSyntaxError: If XML is malformed, as for parse()
+
+    Examples:
+        >>> find(catalog, "catalog/book[@id='3']/title")
+        [{'title': 'Ubik'}]
+    """
+    ...
+
 def unparse(
-    input_dict: XMLDict,
-    output: str | None = None,
+    input_dict: Mapping[str, Any] | XMLModel,
+    output: SupportsWrite | None = None,
     encoding: str = "utf-8",
     full_document: bool = True,
     short_empty_elements: bool = False,
@@ -97,12 +779,50 @@ def unparse(
     newl: str = "\n",
     indent: str = "\t",
     preprocessor: PreprocessorFunc | None = None,
-) -> str:
+    namespace_separator: str = ":",
+    namespaces: dict[str, str | None] | None = None,
+    preserve_mixed_content: bool = False,
+    doctype: str | None = None,
+    doctype_key: str = "#doctype",
+    escape_control_chars: bool = True,
+    escape_apos: bool = False,
+    invalid_chars: Literal["raise", "strip", "replace"] = "raise",
+    use_cdata: bool = False,
+    cdata_for_keys: Iterable[str] | None = None,
+    comment_key: str = "#comment",
+    pi_key: str = "#pi",
+    attr_dict_key: str | None = None,
+    canonical: bool = False,
+    standalone: bool | None = None,
+    xml_version: str = "1.0",
+    pretty_depth: int | None = None,
+    default: Callable[[Any], Any] | None = None,
+    datetime_format: str | None = None,
+    expand_iter: str | None = None,
+    duplicate_attributes: Literal["raise", "last"] = "raise",
+    dialect: Literal["xml", "plist"] = "xml",
+    convention: Literal["xmltodict", "badgerfish", "parker", "gdata"] = "xmltodict",
+    pydantic_support: bool = False,
+    escape_non_ascii: bool = False,
+    return_bytes: bool = False,
+    initial_buffer_size: int | None = None,
+    preserve_sibling_order: bool = False,
+    order_key: str = "#order",
+    index_key: str | None = None,
+) -> str | bytes | None:
     r"""Convert Python dictionary back to XML string.
 
     Args:
-        input_dict: Dictionary to convert to XML (must have exactly one root key if full_document=True)
-        output: Optional file-like object to write to (for compatibility, returns string anyway)
+        input_dict: Dictionary to convert to XML (must have exactly one root key if full_document=True);
+            any Mapping is written like a dict, and any other iterable except str and bytes,
+            such as a tuple or generator, as repeated elements; bytes are written decoded
+            from UTF-8. Models are converted here and
+            anywhere in the value: an object with __xml__() is written as what it returns, a
+            dataclass instance as a dict of its fields, and a named tuple or other object
+            with _asdict() as what that returns
+        output: Optional file-like object to write to incrementally instead of returning a
+            string. Text streams receive str, other streams receive bytes in `encoding`,
+            as return_bytes gives them, with one byte order mark for 'utf-16'
         encoding: Character encoding for XML declaration (default 'utf-8')
         full_document: If True, includes XML declaration (default True)
         short_empty_elements: If True, empty elements use <tag/> format (default False)
@@ -114,13 +834,116 @@ def unparse(
         preprocessor: Optional callback to transform data before unparsing:
             - Called with (key, value)
             - Should return (new_key, new_value) tuple or None to skip
+        namespace_separator: Separator between namespace URI and name in keys (default ':')
+        namespaces: Optional dict mapping namespace URIs to prefixes (None for the default
+            namespace); `uri:name` keys are written as `prefix:name` and the root element
+            declares every mapped namespace. A `uri:name` key whose URI is not mapped raises
+            ValueError, while `prefix:name` keys are written as they are
+        preserve_mixed_content: If True, a list of both strings and dicts (as produced by
+            parse(..., preserve_mixed_content=True)) is written as the content of a single
+            element instead of repeated elements
+        doctype: Optional DOCTYPE declaration, e.g. 'html', written as `<!DOCTYPE html>`
+            after the XML declaration; overrides a `doctype_key` entry
+        doctype_key: Top-level key whose value is written as the DOCTYPE declaration
+            (default '#doctype'); it does not count as a root element
+        escape_control_chars: If True, newlines, carriage returns and tabs in attribute
+            values are written as `&#10;`, `&#13;` and `&#9;` so they survive reparsing
+            (default True)
+        escape_apos: If True, apostrophes in attribute values are written as `&apos;`,
+            for consumers that require it (default False)
+        invalid_chars: What to do with characters XML 1.0 does not allow (e.g. '\x00',
+            '\x0b') in text and attribute values: 'raise' a ValueError (default), 'strip'
+            them, or 'replace' them with U+FFFD
+        use_cdata: If True, all text content is written as `<![CDATA[...]]>` sections
+            instead of being escaped (default False)
+        cdata_for_keys: Optional element names whose text content is written as CDATA
+            sections; `]]>` inside the text is split across two sections
+        comment_key: Key whose value (a string or list of strings) is written as
+            `<!--...-->` comments, as produced by parse(..., process_comments=True)
+            (default '#comment'); at the top level it does not count as a root element
+        pi_key: Key whose value (a string or list of strings) is written as `<?...?>`
+            processing instructions, as produced by parse(..., process_pis=True)
+            (default '#pi'); at the top level it does not count as a root element
+        attr_dict_key: Key whose value, a dict, is written as the element's attributes, as
+            produced by parse(..., attr_dict_key=...); attr_prefix keys are still written
+            as attributes (default None)
+        canonical: If True, write deterministic output in the spirit of Canonical XML:
+            attributes sorted by name after namespace declarations, empty elements as
+            start-end tag pairs, text instead of CDATA, C14N character escaping and no XML
+            declaration, DOCTYPE or indentation; pretty, short_empty_elements, use_cdata
+            and doctype are ignored (default False)
+        standalone: If True or False, the XML declaration says standalone="yes" or "no";
+            None leaves it out (default None)
+        xml_version: Version written in the XML declaration, '1.' followed by digits
+            (default '1.0')
+        pretty_depth: With pretty=True, only elements up to this many levels below the root
+            go on their own lines; deeper content is written compactly (default None,
+            every level)
+        default: Called, like the default of json.dumps(), with each value that is not a
+            dict, string, number, boolean, date, None, iterable or model, and with enum
+            members; its result is written in place of the value, which is otherwise
+            written with str() (default None)
+        datetime_format: How dates and datetimes are written: 'iso' for isoformat(), or a
+            strftime format; None writes them as str() does, like xmltodict (default None)
+        expand_iter: Key an iterable inside a list is written under, as the content of the
+            list's element, as in xmltodict; without it such iterables are repeated
+            elements too (default None)
+        duplicate_attributes: What to do when an element would get the same attribute
+            twice, as from an attr_prefix key and an attr_dict_key entry: 'raise' a
+            ValueError, or keep the 'last' value with a UserWarning (default 'raise')
+        dialect: 'plist' writes input_dict, or any other plist value, as an Apple property
+            list exactly as plistlib.dumps() does, with sorted keys. Only output, encoding
+            and full_document apply then (default 'xml')
+        convention: Layout of attributes and text in input_dict, as for parse(). Parker
+            values have no attributes or text keys, so input_dict has to include the root
+            element. Replaces attr_prefix, cdata_key and attr_dict_key (default 'xmltodict')
+        pydantic_support: Write pydantic models, such as FastAPI response models, as the
+            dict their model_dump() returns, or dict() in pydantic 1. pydantic is not
+            imported (default False)
+        escape_non_ascii: If True, characters outside ASCII in text and attribute values
+            are written as decimal character references, such as `&#233;`, so that the
+            output can be encoded as ASCII or Latin-1. Text holding such characters is
+            escaped rather than written as CDATA; names, comments and processing
+            instructions are written as they are. Ignored with canonical (default False)
+        return_bytes: If True, return the document encoded in `encoding`, as the XML
+            declaration says, instead of a str. Characters the encoding cannot represent
+            are written as decimal character references in text and attribute values, and
+            CDATA sections end before them; in names, comments, processing instructions
+            and the DOCTYPE, which cannot hold references, they raise ValueError. 'utf-16'
+            output starts with a byte order mark. Cannot be combined with output (default
+            False)
+        initial_buffer_size: Bytes of output to reserve room for up front. By default the
+            buffer is sized to an estimate made from a sample of the dictionary, so that a
+            large document is not copied over and over as the buffer grows
+        preserve_sibling_order: If True, a dict holding a list under order_key, as
+            produced by parse(..., preserve_sibling_order=True), has its children written
+            in that order: each key listed takes the next item of a list, or the value
+            itself. A list that names a child more times than there are, or leaves one
+            out, raises ValueError (default False)
+        order_key: Key name for the order of an element's children (default '#order').
+            The top-level dict is always written in the order listed under it, as parse()
+            records it for comments on both sides of the root element
+        index_key: Key holding the position of an element among its siblings, as
+            produced by parse(..., index_key=...): the key is not written, and the children
+            of each element are written in the order of their positions, those without
+            one, such as comments, last. preserve_sibling_order takes precedence
+            (default None)
 
     Returns:
-        XML string representation of the dictionary
+        XML string representation of the dictionary, bytes with return_bytes=True, or
+        None when `output` is given
 
     Raises:
-        ValueError: If full_document=True and dict doesn't have exactly one root element
-        TypeError: If input_dict is not a dictionary
+        ValueError: If full_document=True and dict doesn't have exactly one root element,
+            if a value holds a character XML does not allow and invalid_chars='raise',
+            if a comment contains '--' or ends with '-', if a processing instruction is
+            empty, starts with whitespace or contains '?>', if xml_version is invalid, if
+            an element gets the same attribute twice and duplicate_attributes='raise', if
+            an element or attribute name is not an XML name, if a key is in a namespace
+            that namespaces does not map, or if return_bytes is combined with output
+        TypeError: If input_dict is not a mapping, or the attr_dict_key value is not a
+            mapping
+        LookupError: If return_bytes=True and encoding is not a known encoding name
 
     Examples:
         >>> unparse({'root': {'item': 'value'}})
@@ -140,4 +963,1280 @@ def unparse(
     """
     ...
 
-__all__ = ["parse", "unparse"]
+def unparse_iter(
+    input_dict: Mapping[str, Any] | XMLModel,
+    chunk_size: int = 65536,
+    encoding: str = "utf-8",
+    full_document: bool = True,
+    short_empty_elements: bool = False,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    pretty: bool = False,
+    newl: str = "\n",
+    indent: str = "\t",
+    preprocessor: PreprocessorFunc | None = None,
+    namespace_separator: str = ":",
+    namespaces: dict[str, str | None] | None = None,
+    preserve_mixed_content: bool = False,
+    doctype: str | None = None,
+    doctype_key: str = "#doctype",
+    escape_control_chars: bool = True,
+    escape_apos: bool = False,
+    invalid_chars: Literal["raise", "strip", "replace"] = "raise",
+    use_cdata: bool = False,
+    cdata_for_keys: Iterable[str] | None = None,
+    comment_key: str = "#comment",
+    pi_key: str = "#pi",
+    attr_dict_key: str | None = None,
+    canonical: bool = False,
+    standalone: bool | None = None,
+    xml_version: str = "1.0",
+    pretty_depth: int | None = None,
+    default: Callable[[Any], Any] | None = None,
+    datetime_format: str | None = None,
+    expand_iter: str | None = None,
+    duplicate_attributes: Literal["raise", "last"] = "raise",
+    pydantic_support: bool = False,
+    escape_non_ascii: bool = False,
+    preserve_sibling_order: bool = False,
+    order_key: str = "#order",
+    index_key: str | None = None,
+) -> Iterator[str]:
+    r"""Convert Python dictionary to XML lazily, yielding string chunks.
+
+    Serialization runs on a background thread and pauses until the previous chunk is
+    consumed, so only about `chunk_size` characters are held in memory at a time.
+    Concatenating all chunks gives the same result as `unparse()`.
+
+    Args:
+        input_dict: Dictionary to convert to XML (see `unparse`)
+        chunk_size: Approximate size of each yielded chunk in characters (default 65536)
+        encoding: Character encoding for XML declaration (default 'utf-8')
+        full_document: If True, includes XML declaration (default True)
+        short_empty_elements: If True, empty elements use <tag/> format (default False)
+        attr_prefix: Prefix used to identify attribute keys (default '@')
+        cdata_key: Key name that contains text content (default '#text')
+        pretty: If True, output is formatted with indentation (default False)
+        newl: Newline character for pretty printing (default '\n')
+        indent: Indentation string for pretty printing (default '\t')
+        preprocessor: Optional callback to transform data before unparsing
+        namespace_separator: Separator between namespace URI and name in keys (default ':')
+        namespaces: Optional dict mapping namespace URIs to prefixes, as for unparse()
+        preserve_mixed_content: Write mixed-content lists as one element, as for unparse()
+        doctype: Optional DOCTYPE declaration, as for unparse()
+        doctype_key: Top-level key holding the DOCTYPE declaration, as for unparse()
+        escape_control_chars: Escape control characters in attributes, as for unparse()
+        escape_apos: Write apostrophes in attributes as `&apos;`, as for unparse()
+        invalid_chars: Handling of characters XML does not allow, as for unparse()
+        use_cdata: Write all text content as CDATA sections, as for unparse()
+        cdata_for_keys: Element names whose text is written as CDATA, as for unparse()
+        comment_key: Key whose values are written as comments, as for unparse()
+        pi_key: Key whose values are written as processing instructions, as for unparse()
+        attr_dict_key: Key whose dict is written as attributes, as for unparse()
+        canonical: Write deterministic, canonical output, as for unparse()
+        standalone: standalone="yes" or "no" in the XML declaration, as for unparse()
+        xml_version: Version written in the XML declaration, as for unparse()
+        pretty_depth: Levels below the root that pretty printing indents, as for unparse()
+        default: Converts values with no natural XML text, as for unparse()
+        datetime_format: How dates and datetimes are written, as for unparse()
+        expand_iter: Key nested iterables are written under, as for unparse()
+        duplicate_attributes: Handling of an attribute written twice, as for unparse()
+        pydantic_support: Write pydantic models as their model_dump(), as for unparse()
+        escape_non_ascii: Write characters outside ASCII as references, as for unparse()
+        preserve_sibling_order: Write children in the order listed under order_key, as for
+            unparse()
+        order_key: Key name for the order of an element's children, as for unparse()
+        index_key: Key holding the position of an element among its siblings, as for
+            unparse()
+
+    Returns:
+        Iterator of XML string chunks
+
+    Raises:
+        ValueError: If full_document=True and dict doesn't have exactly one root element,
+            if chunk_size is not positive or xml_version is invalid, if an element or
+            attribute name is not an XML name, or if a value holds a character XML does not
+            allow and invalid_chars='raise'
+
+    Examples:
+        >>> "".join(unparse_iter({'root': {'item': 'value'}}))
+        '<?xml version="1.0" encoding="utf-8"?>\\n<root><item>value</item></root>'
+    """
+    ...
+
+def unparse_batch(
+    input_dicts: Iterable[Mapping[str, Any] | XMLModel],
+    encoding: str = "utf-8",
+    full_document: bool = True,
+    short_empty_elements: bool = False,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    pretty: bool = False,
+    newl: str = "\n",
+    indent: str = "\t",
+    preprocessor: PreprocessorFunc | None = None,
+    namespace_separator: str = ":",
+    namespaces: dict[str, str | None] | None = None,
+    preserve_mixed_content: bool = False,
+    doctype: str | None = None,
+    doctype_key: str = "#doctype",
+    escape_control_chars: bool = True,
+    escape_apos: bool = False,
+    invalid_chars: Literal["raise", "strip", "replace"] = "raise",
+    use_cdata: bool = False,
+    cdata_for_keys: Iterable[str] | None = None,
+    comment_key: str = "#comment",
+    pi_key: str = "#pi",
+    attr_dict_key: str | None = None,
+    canonical: bool = False,
+    standalone: bool | None = None,
+    xml_version: str = "1.0",
+    pretty_depth: int | None = None,
+    default: Callable[[Any], Any] | None = None,
+    datetime_format: str | None = None,
+    expand_iter: str | None = None,
+    duplicate_attributes: Literal["raise", "last"] = "raise",
+    pydantic_support: bool = False,
+    escape_non_ascii: bool = False,
+    return_bytes: bool = False,
+    initial_buffer_size: int | None = None,
+    preserve_sibling_order: bool = False,
+    order_key: str = "#order",
+    index_key: str | None = None,
+) -> list[str] | list[bytes]:
+    """Convert many Python dictionaries to XML documents in one call.
+
+    The options are read once and one writer serializes every dictionary in turn, which
+    saves the per-call setup that dominates for small payloads such as queue messages.
+    Each document is what unparse() would return for its dictionary.
+
+    Args:
+        input_dicts: Dictionaries or models to convert, each as for unparse()
+        return_bytes: If True, return each document encoded in `encoding`, as unparse()
+            does (default False)
+        Other options: As for unparse()
+
+    Returns:
+        One XML string per dictionary, or bytes with return_bytes=True, in the order given
+
+    Raises:
+        ValueError: Same errors as unparse(); a note names the index of the dictionary
+            that failed
+        TypeError: If an item is not a dictionary or model
+
+    Examples:
+        >>> unparse_batch([{'a': '1'}, {'a': '2'}], full_document=False)
+        ['<a>1</a>', '<a>2</a>']
+    """
+    ...
+
+class Serializer:
+    """Converts any number of Python dictionaries to XML with the same options.
+
+    The options are checked once, and each document is written into the output buffer
+    of the one before, which saves the setup unparse() does on every call. Suits
+    services writing many small messages one at a time. A serializer may be shared
+    between threads, and pickling or copying one makes a new serializer with the same
+    options.
+
+    Args:
+        return_bytes: If True, unparse() returns the document encoded in `encoding`, as
+            the module-level unparse() does (default False)
+        Other options: As for unparse_batch()
+
+    Examples:
+        >>> serializer = Serializer(full_document=False)
+        >>> serializer.unparse({'a': '1'})
+        '<a>1</a>'
+    """
+
+    def __init__(
+        self,
+        *,
+        encoding: str = "utf-8",
+        full_document: bool = True,
+        short_empty_elements: bool = False,
+        attr_prefix: str = "@",
+        cdata_key: str = "#text",
+        pretty: bool = False,
+        newl: str = "\n",
+        indent: str = "\t",
+        preprocessor: PreprocessorFunc | None = None,
+        namespace_separator: str = ":",
+        namespaces: dict[str, str | None] | None = None,
+        preserve_mixed_content: bool = False,
+        doctype: str | None = None,
+        doctype_key: str = "#doctype",
+        escape_control_chars: bool = True,
+        escape_apos: bool = False,
+        invalid_chars: Literal["raise", "strip", "replace"] = "raise",
+        use_cdata: bool = False,
+        cdata_for_keys: Iterable[str] | None = None,
+        comment_key: str = "#comment",
+        pi_key: str = "#pi",
+        attr_dict_key: str | None = None,
+        canonical: bool = False,
+        standalone: bool | None = None,
+        xml_version: str = "1.0",
+        pretty_depth: int | None = None,
+        default: Callable[[Any], Any] | None = None,
+        datetime_format: str | None = None,
+        expand_iter: str | None = None,
+        duplicate_attributes: Literal["raise", "last"] = "raise",
+        pydantic_support: bool = False,
+        escape_non_ascii: bool = False,
+        return_bytes: bool = False,
+        initial_buffer_size: int | None = None,
+        preserve_sibling_order: bool = False,
+        order_key: str = "#order",
+        index_key: str | None = None,
+    ) -> None: ...
+    def unparse(self, input_dict: Mapping[str, Any] | XMLModel) -> str | bytes:
+        """Convert a dictionary or model, as for unparse(), with the serializer's options."""
+        ...
+
+def unparse_async(
+    input_dict: Mapping[str, Any] | XMLModel,
+    output: SupportsAsyncWrite,
+    **kwargs: Any,
+) -> Coroutine[Any, Any, None]:
+    """Write a Python dictionary as XML to an object with an async write().
+
+    The document is serialized on a worker thread of the event loop's default executor,
+    and each chunk of encoded bytes is awaited on the loop before the next is written, so
+    other tasks keep running while a large document is written.
+
+    Args:
+        input_dict: Dictionary or model to write, as for unparse()
+        output: Object whose write() takes bytes and returns an awaitable; a write() that
+            returns anything else is taken as done
+        **kwargs: Same options as unparse(), except output and return_bytes
+
+    Raises:
+        ValueError: Same errors as unparse(); errors raised by write() propagate unchanged
+
+    Examples:
+        >>> await unparse_async({'root': {'item': 'value'}}, response)
+    """
+    ...
+
+def unparse_fragment(
+    value: Any,
+    tag: str,
+    namespaces: Mapping[str, str] | None = None,
+    full_document: bool = False,
+    **kwargs: Any,
+) -> str | bytes:
+    """Convert one element to XML, declaring the namespaces bound where it came from.
+
+    A subtree taken out of a parsed document may use prefixes declared on its ancestors;
+    written alone, it needs those declarations on its own root element to stay
+    well-formed. The bindings given are declared there, except those the element declares
+    itself, so the fragment reads the same on its own.
+
+    Args:
+        value: Content of the element, as a value in the dictionaries unparse() takes; a
+            list, which would write several elements, is rejected
+        tag: Name of the element
+        namespaces: Prefix to URI bindings in scope, such as those
+            parse(collect_namespaces=True) reports, with '' for the default namespace
+            (default None). Expanded names using a bound URI, as
+            parse(process_namespaces=True) gives them, are written with its prefix
+        full_document: If True, write the XML declaration before the element (default False)
+        **kwargs: Same options as unparse(), except namespaces
+
+    Returns:
+        The element as XML, or bytes with return_bytes=True
+
+    Raises:
+        ValueError: If value is a list, or for the errors unparse() raises
+
+    Examples:
+        >>> doc = parse(xml, collect_namespaces=True)
+        >>> body = doc['soap:Envelope']['soap:Body']
+        >>> unparse_fragment(body, 'soap:Body', namespaces=doc['#namespaces'])
+        '<soap:Body xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">...</soap:Body>'
+    """
+    ...
+
+class TransformRules(TypedDict, total=False):
+    drop: Iterable[str]
+    rename: Mapping[str, str]
+    attributes: Mapping[str, Callable[[str], str | None]]
+
+def transform(
+    source: XMLInput,
+    output: SupportsWrite | None = None,
+    rules: TransformRules | None = None,
+    encoding: str | None = None,
+    output_encoding: str = "utf-8",
+    full_document: bool = True,
+    short_empty_elements: bool = False,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> str | None:
+    """Rewrite XML as it is read, without building a dictionary of the document.
+
+    Each event read is written to `output` straight away, changed by the rules, so memory
+    stays flat however large the document is. Text, comments, processing instructions and
+    the DOCTYPE are written back as read; whitespace outside the root element and the
+    original quoting and character references are not kept.
+
+    Args:
+        source: XML input, as for iterparse(): text, bytes, a binary file-like object or an
+            iterator of chunks
+        output: File-like object to write to; text streams receive str, others bytes in
+            output_encoding. If None, the document is returned as a string
+        rules: Changes to make, each optional:
+            'drop': element paths, as for parse(only_paths=...), left out with everything
+            inside them;
+            'rename': new element names by name or path ('item', 'rss/channel/item');
+            'attributes': callbacks by attribute name, taking the value and returning the
+            new one, or None to leave the attribute out
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        output_encoding: Encoding of the output and its XML declaration (default 'utf-8')
+        full_document: If True, write an XML declaration first (default True)
+        short_empty_elements: If True, write elements with no content as <tag/>
+            (default False)
+        disable_entities, forbid_dtd, forbid_entities, max_depth, max_attributes,
+        max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        The rewritten document, or None when written to output
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed; what was written before stays
+            written
+        ValueError: If rules has an unknown key or renames to an invalid name
+        TypeError: If an attribute rule is not callable or returns something other than
+            a string or None
+
+    Examples:
+        >>> transform('<r><a k="1">x</a><b/></r>', rules={'drop': ['r/b']}, full_document=False)
+        '<r><a k="1">x</a></r>'
+    """
+    ...
+
+def patch(
+    xml_template: XMLInput,
+    updates: Mapping[str, Any],
+    output: SupportsWrite | None = None,
+    encoding: str | None = None,
+    output_encoding: str = "utf-8",
+    full_document: bool = True,
+    short_empty_elements: bool = False,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+) -> str | None:
+    """Fill in an XML template with new values, keeping everything else as it is.
+
+    The template is streamed through as transform() does, so elements, text, comments and
+    processing instructions that are not updated keep their content and order. An updated
+    element keeps its attributes and has its content written from the new value as
+    unparse() writes one; elements and attributes the template lacks are added, at the end
+    of their parent.
+
+    Args:
+        xml_template: XML input, as for transform()
+        updates: New values by path from the root element, such as 'order/customer/name',
+            'order/line[2]/qty' for the second line element (positions count from 1, as
+            in find()) or 'order/@id' for an attribute. A value of None empties an element or leaves an attribute out
+        output: File-like object to write to, as for transform(). If None, the document
+            is returned as a string
+        encoding, output_encoding, full_document, short_empty_elements, disable_entities,
+        forbid_dtd, forbid_entities: As for transform()
+
+    Returns:
+        The filled-in document, or None when written to output
+
+    Raises:
+        XmlSyntaxError: If the template is not well-formed
+        ValueError: If a path is invalid, does not start at the root element or lies
+            inside an element another path replaces
+
+    Examples:
+        >>> patch('<order id=""><customer><name/></customer><line>1</line></order>',
+        ...       {'order/@id': 42, 'order/customer/name': 'Ada',
+        ...        'order/customer/email': 'ada@example.com'}, full_document=False)
+        '<order id="42"><customer><name>Ada</name><email>ada@example.com</email></customer><line>1</line></order>'
+    """
+    ...
+
+def roundtrip(xml_input: str | bytes, **kwargs: Any) -> str:
+    """Parse XML and write it back.
+
+    Comments, processing instructions, the DOCTYPE, whitespace inside the root element,
+    mixed content and the order of siblings are kept (preserve_sibling_order is on by
+    default). Not kept: the XML declaration, whitespace outside the root element (a line
+    break follows the DOCTYPE), CDATA sections and character or entity references (written
+    as escaped text, with DTD entities expanded), attribute quoting and self-closing tags.
+
+    Args:
+        xml_input: XML data, as for parse()
+        **kwargs: Options for parse() and unparse(); attr_prefix, attr_dict_key, cdata_key,
+            comment_key, doctype_key, pi_key, namespace_separator, namespaces,
+            preserve_mixed_content, preserve_sibling_order, order_key, dialect and
+            convention are passed to both; max_depth is 512 unless given, since unparse()
+            writes the dict level by level
+
+    Raises:
+        TypeError: If an option is not accepted by parse() or unparse()
+        ValueError: Same errors as parse() and unparse()
+
+    Examples:
+        >>> roundtrip("<?xml version='1.0'?><!--c--><r a='1'><b/>x</r>")
+        '<!--c--><r a="1"><b></b>x</r>'
+    """
+    ...
+
+def pretty(
+    xml_input: XMLInput,
+    indent: str = "  ",
+    newl: str = "\n",
+    encoding: str | None = None,
+) -> str:
+    """Reformat XML text with each element on a line of its own, without parsing it into a dict.
+
+    The document is read token by token and written back as it was, with the whitespace
+    between elements replaced by newl and indent: text and attribute values stay escaped as
+    written, and comments, processing instructions, CDATA sections, the XML declaration and
+    the DOCTYPE are kept. Elements with text before a child element (mixed content) and
+    elements with xml:space="preserve" are written as they are, and entities are not
+    expanded.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        indent: Written once per level of nesting (default two spaces)
+        newl: Written before each line after the first (default '\n')
+        encoding: Encoding of the input, overriding the one it declares (default None)
+
+    Returns:
+        The reformatted document
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+
+    Examples:
+        >>> print(pretty('<r><!--c--><a x="1">t</a><b><![CDATA[<c>]]></b></r>'))
+        <r>
+          <!--c-->
+          <a x="1">t</a>
+          <b><![CDATA[<c>]]></b>
+        </r>
+    """
+    ...
+
+def minify(
+    xml_input: XMLInput,
+    strip_comments: bool = False,
+    encoding: str | None = None,
+) -> str:
+    """Remove the whitespace between elements from XML text, to make a payload smaller.
+
+    The inverse of pretty(): the document is read token by token and written back as it
+    was without the whitespace between elements. Text, mixed content and elements with
+    xml:space="preserve" are kept as they are, so the document means the same.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        strip_comments: Also remove comments (default False)
+        encoding: Encoding of the input, overriding the one it declares (default None)
+
+    Returns:
+        The minified document
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+
+    Examples:
+        >>> minify('<r>\n  <!-- c -->\n  <a> x </a>\n</r>', strip_comments=True)
+        '<r><a> x </a></r>'
+    """
+    ...
+
+def parse_soap(xml_input: XMLInput, **kwargs: Any) -> Any:
+    """Parse a SOAP 1.1 or 1.2 envelope and return the content of its Body.
+
+    Namespaces are always processed, so the Envelope, Header and Body are recognized by
+    their namespace rather than their prefix. Their keys follow the namespaces,
+    namespace_separator and strip_namespace_prefixes options like any other name; the
+    Header is skipped.
+
+    Args:
+        xml_input: XML data, as for parse()
+        **kwargs: Options for parse(); process_namespaces is always True
+
+    Returns:
+        The Body as parse() builds it, or an empty dict for an empty Body
+
+    Raises:
+        SoapFault: If the Body holds a Fault; `code` and `reason` are the faultcode and
+            faultstring (SOAP 1.1) or Code/Value and Reason/Text (SOAP 1.2), and `detail`
+            the detail element, or None
+        ValueError: If the document is not a SOAP envelope or has no Body, and the same
+            errors as parse()
+
+    Examples:
+        >>> parse_soap(response, namespaces={"urn:books": None})
+        {'book': {'title': 'Dune'}}
+    """
+    ...
+
+def build_soap(
+    body: Any,
+    headers: Any | None = None,
+    soap_version: Literal["1.1", "1.2"] = "1.1",
+    **kwargs: Any,
+) -> str:
+    """Write a SOAP envelope around a body.
+
+    The Envelope declares the namespace of `soap_version` with the 'soap' prefix, and
+    holds a Header only when there are headers.
+
+    Args:
+        body: Content of the Body, as for an unparse() element
+        headers: Content of the Header (default None, no Header)
+        soap_version: '1.1' or '1.2' (default '1.1')
+        **kwargs: Options for unparse()
+
+    Raises:
+        ValueError: For an unknown soap_version, and the same errors as unparse()
+
+    Examples:
+        >>> build_soap({"m:GetBook": {"@xmlns:m": "urn:books", "m:id": 7}}, full_document=False)
+        '<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><m:GetBook xmlns:m="urn:books"><m:id>7</m:id></m:GetBook></soap:Body></soap:Envelope>'
+    """
+    ...
+
+def parse_feed(
+    xml_input: XMLInput,
+    encoding: str | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> dict[str, Any]:
+    """Parse an RSS 2.0 or Atom 1.0 feed into one shape for both.
+
+    The feed has `format` ('rss' or 'atom'), `title`, `link`, `description` (the Atom
+    subtitle), `updated` (lastBuildDate or pubDate in RSS) and `entries`, always a list.
+    Each entry has `id`, `title`, `link`, `summary`, `content`, `author`, `published` and
+    `updated`. Missing values are None; dates are timezone-aware datetimes, or the text
+    itself when it is neither ISO 8601 nor RFC 822. Atom elements are recognized by their
+    namespace, and the link is the first one without a `rel` or with rel="alternate".
+
+    Args:
+        xml_input: XML data, as for parse()
+        encoding, disable_entities, forbid_dtd, forbid_entities, max_depth,
+            max_attributes, max_text_length, max_total_nodes: As for parse()
+
+    Raises:
+        ValueError: If the document is not an RSS or Atom feed, and the same errors as
+            parse()
+
+    Examples:
+        >>> feed = parse_feed(rss)
+        >>> feed["entries"][0]["published"]
+        datetime.datetime(2024, 1, 1, 8, 30, tzinfo=datetime.timezone.utc)
+    """
+    ...
+
+def dumps_xmlrpc(
+    params: tuple[Any, ...] | xmlrpc.client.Fault,
+    methodname: str | None = None,
+    methodresponse: bool = False,
+    allow_none: bool = False,
+) -> str:
+    """Write XML-RPC parameters, or a Fault, with the same output as xmlrpc.client.dumps().
+
+    Values map to <int>, <double>, <string>, <boolean>, <array>, <struct>, <base64> and
+    <dateTime.iso8601> as in xmlrpc.client, including its DateTime and Binary wrappers.
+
+    Args:
+        params: Tuple of parameters, or an xmlrpc.client.Fault for a fault response
+        methodname: Write a <methodCall> for this method (default None)
+        methodresponse: Write a <methodResponse> with the single parameter (default False)
+        allow_none: Write None as <nil/> (default False)
+
+    Raises:
+        TypeError: For a value that cannot be written, such as None without allow_none,
+            a non-string struct key or a recursive container
+        OverflowError: For an int outside the 32-bit range of <int>
+        ValueError: For methodresponse with more than one parameter
+
+    Examples:
+        >>> dumps_xmlrpc((1, "a"), "add")
+        "<?xml version='1.0'?>\\n<methodCall>\\n<methodName>add</methodName>\\n..."
+    """
+    ...
+
+def loads_xmlrpc(
+    xml_input: str | bytes | bytearray, encoding: str | None = None
+) -> tuple[tuple[Any, ...], str | None]:
+    """Read an XML-RPC call or response as xmlrpc.client.loads(use_builtin_types=True) does.
+
+    Args:
+        xml_input: XML-RPC document
+        encoding: Encoding of bytes input, overriding the XML declaration (default None)
+
+    Returns:
+        The parameters, and the method name of a <methodCall> or None
+
+    Raises:
+        xmlrpc.client.Fault: For a fault response
+        ValueError: For an unknown tag or a malformed value, and the same errors as parse()
+
+    Examples:
+        >>> loads_xmlrpc(dumps_xmlrpc((1, "a"), "add"))
+        ((1, 'a'), 'add')
+    """
+    ...
+
+def xml_to_json(
+    xml_input: str | bytes,
+    encoding: str | None = None,
+    indent: int | str | None = None,
+    ensure_ascii: bool = True,
+    process_namespaces: bool = False,
+    namespace_separator: str = ":",
+    disable_entities: bool = True,
+    process_comments: bool = False,
+    xml_attribs: bool = True,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    force_cdata: bool = False,
+    cdata_separator: str = "",
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
+    comment_key: str = "#comment",
+    namespaces: dict[str, str | None] | None = None,
+    max_entity_depth: int = 16,
+    max_entity_expansion: int = 10_000_000,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    preserve_mixed_content: bool = False,
+    process_doctype: bool = False,
+    doctype_key: str = "#doctype",
+    process_pis: bool = False,
+    pi_key: str = "#pi",
+    max_depth: int | None = 512,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+    attr_dict_key: str | None = None,
+    collect_namespaces: bool = False,
+    namespaces_key: str = "#namespaces",
+    qname_attributes: Iterable[str] | None = None,
+    strip_namespace_prefixes: bool = False,
+    force_list: bool | str | Iterable[str] | None = None,
+    infer_types: bool | Iterable[str] | None = False,
+    on_duplicate: Literal["list", "first", "last", "error"] = "list",
+) -> str:
+    """Convert XML straight to JSON text without building Python objects.
+
+    The result is what json.dumps(parse(xml_input, ...)) returns: attributes, text and
+    repeated elements are laid out as parse() does, and empty elements are null. Options
+    that call back into Python, such as postprocessor and transforms, are not accepted, nor
+    is a callable force_list.
+
+    Args:
+        xml_input: XML string or bytes
+        encoding: Encoding of bytes input, overriding the XML declaration (default None)
+        indent: As for json.dumps(): spaces or a string per level, None for one line
+        ensure_ascii: Escape characters outside ASCII, as json.dumps() does (default True)
+        process_namespaces, ..., strip_namespace_prefixes: As for parse(); max_depth is 512
+            by default, since the values are built and written level by level
+        force_list: As for parse(), as True, an element name or path, or an iterable of
+            them (default None)
+        infer_types, on_duplicate: As for parse(); inferred values are written as JSON
+            numbers, true, false and null
+
+    Returns:
+        JSON text
+
+    Raises:
+        The same errors as parse()
+
+    Examples:
+        >>> xml_to_json('<a id="1"><b>x</b><b/></a>')
+        '{"a": {"@id": "1", "b": ["x", null]}}'
+    """
+    ...
+
+def xml_to_msgpack(
+    xml_input: str | bytes,
+    encoding: str | None = None,
+    process_namespaces: bool = False,
+    namespace_separator: str = ":",
+    disable_entities: bool = True,
+    process_comments: bool = False,
+    xml_attribs: bool = True,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    force_cdata: bool = False,
+    cdata_separator: str = "",
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    honor_xml_space: bool = True,
+    comment_key: str = "#comment",
+    namespaces: dict[str, str | None] | None = None,
+    max_entity_depth: int = 16,
+    max_entity_expansion: int = 10_000_000,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    preserve_mixed_content: bool = False,
+    process_doctype: bool = False,
+    doctype_key: str = "#doctype",
+    process_pis: bool = False,
+    pi_key: str = "#pi",
+    max_depth: int | None = 512,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+    attr_dict_key: str | None = None,
+    collect_namespaces: bool = False,
+    namespaces_key: str = "#namespaces",
+    qname_attributes: Iterable[str] | None = None,
+    strip_namespace_prefixes: bool = False,
+    force_list: bool | str | Iterable[str] | None = None,
+    infer_types: bool | Iterable[str] | None = False,
+    on_duplicate: Literal["list", "first", "last", "error"] = "list",
+) -> bytes:
+    """Convert XML to MessagePack without building Python objects.
+
+    The result is what msgpack.packb(parse(xml_input, ...)) returns: maps, arrays, strings
+    and nil laid out as parse() lays out dicts, lists, text and None, and booleans, integers
+    and 64-bit floats for values infer_types converts. A worker process can
+    return it to its parent as one bytes object instead of a pickled graph of dicts, and
+    the parent unpacks it with msgpack.unpackb() when it needs the values.
+
+    Args:
+        xml_input: XML string or bytes
+        encoding: Encoding of bytes input, overriding the XML declaration (default None)
+        process_namespaces, ..., strip_namespace_prefixes: As for parse(); max_depth is 512
+            by default, as for xml_to_json()
+        force_list, infer_types, on_duplicate: As for xml_to_json(); an integer beyond 64
+            bits raises ValueError, as MessagePack cannot hold it
+
+    Returns:
+        MessagePack bytes
+
+    Raises:
+        The same errors as parse()
+
+    Examples:
+        >>> xml_to_msgpack('<a id="1"><b>x</b><b/></a>')
+        b'\x81\xa1a\x82\xa3@id\xa11\xa1b\x92\xa1x\xc0'
+    """
+    ...
+
+def json_to_xml(
+    json_input: str,
+    encoding: str = "utf-8",
+    full_document: bool = True,
+    short_empty_elements: bool = False,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    pretty: bool = False,
+    newl: str = "\n",
+    indent: str = "\t",
+    namespace_separator: str = ":",
+    namespaces: dict[str, str | None] | None = None,
+    preserve_mixed_content: bool = False,
+    doctype: str | None = None,
+    doctype_key: str = "#doctype",
+    escape_control_chars: bool = True,
+    escape_apos: bool = False,
+    invalid_chars: Literal["raise", "strip", "replace"] = "raise",
+    use_cdata: bool = False,
+    cdata_for_keys: Iterable[str] | None = None,
+    comment_key: str = "#comment",
+    pi_key: str = "#pi",
+    attr_dict_key: str | None = None,
+    canonical: bool = False,
+    standalone: bool | None = None,
+    xml_version: str = "1.0",
+    pretty_depth: int | None = None,
+    duplicate_attributes: Literal["raise", "last"] = "raise",
+    escape_non_ascii: bool = False,
+) -> str:
+    """Convert JSON text straight to XML without building Python objects.
+
+    The result is what unparse(json.loads(json_input), ...) returns, except that numbers
+    keep their JSON spelling: 1.5e3 is written as 1.5e3. true and false are written as
+    "true" and "false", and null as an empty element. Options that call back into Python,
+    such as preprocessor and default, are not accepted.
+
+    Args:
+        json_input: JSON text holding an object
+        encoding, ..., escape_non_ascii: As for unparse()
+
+    Returns:
+        XML string
+
+    Raises:
+        ValueError: For malformed JSON, a document that is not an object, nesting deeper
+            than 512 levels, and the same errors as unparse()
+
+    Examples:
+        >>> json_to_xml('{"a": {"@id": 1, "b": [true, null]}}', full_document=False)
+        '<a id="1"><b>true</b><b></b></a>'
+    """
+    ...
+
+def diff(
+    xml_a: str | bytes,
+    xml_b: str | bytes,
+    ignore_order_for: Iterable[str] | None = None,
+    encoding: str | None = None,
+    process_namespaces: bool = False,
+    namespace_separator: str = ":",
+    xml_attribs: bool = True,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    process_comments: bool = False,
+    comment_key: str = "#comment",
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+) -> list[Difference]:
+    """Compare two documents as parse() reads them, without building Python dicts.
+
+    Keys are compared in the order of the first document, then those only in the second.
+    Items of a repeated element are compared by position, and an element repeated in one
+    document only is compared as a list of one.
+
+    Args:
+        xml_a: The first XML string or bytes
+        xml_b: The second XML string or bytes
+        ignore_order_for: Paths of repeated elements whose items may come in any order, written
+            as for only_paths; each item is matched with an equal item of the other document,
+            and one without a match is removed or added
+        encoding: Encoding of bytes input, overriding the XML declaration (default None)
+        process_namespaces, ..., forbid_entities: As for parse()
+
+    Returns:
+        A dict per difference: its path from the root element, such as "r/i[2]/@id" for
+        the second i element (positions count from 1, as in find()), its kind ("added",
+        "removed" or "changed"), and the values parse() gives in each document, None on the
+        side a value is missing from
+
+    Raises:
+        The same errors as parse(), and DepthLimitExceeded for elements nested more than
+        512 deep
+
+    Examples:
+        >>> diff('<r id="1"><a>x</a></r>', '<r id="2"><a>x</a><b/></r>')
+        [{'path': 'r/@id', 'kind': 'changed', 'left': '1', 'right': '2'},
+         {'path': 'r/b', 'kind': 'added', 'left': None, 'right': None}]
+    """
+    ...
+
+def profile(
+    xml_input: XMLInput,
+    encoding: str | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> Profile:
+    """Scan a document and report its shape, to choose parse options for an unfamiliar feed.
+
+    The document is streamed and nothing is built for its elements. Names are as written in
+    the document, each dict in the order the names were first found.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        disable_entities, forbid_dtd, forbid_entities, max_depth, max_attributes,
+        max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        A dict with:
+        - elements, attributes: How many the document has
+        - max_depth: Nesting of the deepest element, 1 for the root
+        - max_siblings: The most elements of one name under a single parent
+        - tags: How many elements have each name
+        - attribute_names: How many elements have an attribute of each name
+        - repeated: For each name found more than once under a parent, the most found under
+          one. parse() gives these elements as a list where they repeat, so they are the
+          candidates for force_list
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+
+    Examples:
+        >>> profile('<r><i id="1"/><i/></r>')
+        {'elements': 3, 'attributes': 1, 'max_depth': 2, 'max_siblings': 2,
+         'tags': {'r': 1, 'i': 2}, 'attribute_names': {'id': 1}, 'repeated': {'i': 2}}
+    """
+    ...
+
+def read_records(
+    xml_input: XMLInput,
+    record_path: str,
+    columns: Iterable[str] | None = None,
+    encoding: str | None = None,
+    arrow: bool = False,
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> dict[str, list[str | None]] | Any:
+    """Read the records of a document into columns, without building a dict per record.
+
+    The document is streamed, and each element on record_path adds one value to every
+    column: the text of an element or the value of an attribute, or None where the record
+    has none or the element is empty. Names are matched as written in the document.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        record_path: Path of the record elements, written as for parse(only_paths=...),
+            such as 'rows/row' or '**/row'; records inside records are not read
+        columns: Paths inside a record: 'title' for the text of a child element,
+            'author/name' for that of a descendant, '@id' for an attribute of the record and
+            'author/@id' for one of a descendant. If None, there is a column for each
+            attribute and child element found in the records, in the order found
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        arrow: If True, return a pyarrow.Table of string columns (default False)
+        strip_whitespace, strip, disable_entities, forbid_dtd, forbid_entities, max_depth,
+        max_attributes, max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        A dict from column to the list of its values, or a pyarrow.Table with arrow
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+        ValueError: If a column is empty or given twice
+        ImportError: With arrow, if pyarrow is not installed
+
+    Examples:
+        >>> read_records('<rows><row id="1"><a>x</a></row><row id="2"/></rows>', 'rows/row')
+        {'@id': ['1', '2'], 'a': ['x', None]}
+    """
+    ...
+
+def to_csv(
+    xml_input: XMLInput,
+    record_path: str,
+    fileobj: SupportsWrite,
+    columns: Iterable[str] | None = None,
+    encoding: str | None = None,
+    output_encoding: str = "utf-8",
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> int:
+    """Write the records of a document to a file as CSV rows while reading it.
+
+    Rows are written as each record ends, so no more than one record is held at a time
+    however large the document. Values are read as by read_records(), and rows are written
+    as csv.writer() writes them by default: a header row of column names, fields quoted
+    when needed, empty for None, and lines ending in \\r\\n.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        record_path: Path of the record elements, as for read_records()
+        fileobj: File-like object to write to. Text streams, which should be opened with
+            newline='', receive str; anything else receives bytes in output_encoding
+        columns: Paths inside a record, as for read_records(). If None, there is a column for
+            each attribute and child element of the first record; those only later records
+            have are not written
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        output_encoding: Encoding of bytes written to fileobj (default 'utf-8')
+        strip_whitespace, strip, disable_entities, forbid_dtd, forbid_entities, max_depth,
+        max_attributes, max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        The number of rows written, not counting the header
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+        ValueError: If a column is empty or given twice
+
+    Examples:
+        >>> out = io.StringIO(newline='')
+        >>> to_csv('<rows><row id="1"><a>x, y</a></row></rows>', 'rows/row', out)
+        1
+        >>> out.getvalue()
+        '@id,a\\r\\n1,"x, y"\\r\\n'
+    """
+    ...
+
+def to_ndjson(
+    source: XMLInput,
+    record_path: str,
+    output: SupportsWrite,
+    encoding: str | None = None,
+    output_encoding: str = "utf-8",
+    ensure_ascii: bool = True,
+    xml_attribs: bool = True,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+    force_cdata: bool = False,
+    cdata_separator: str = "",
+    strip_whitespace: bool = True,
+    strip: Literal["both", "leading", "trailing", "none"] | None = None,
+    process_comments: bool = False,
+    comment_key: str = "#comment",
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = 512,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> int:
+    """Write the records of a document to a file as JSON lines while reading it.
+
+    Each element on record_path is parsed as parse() would parse it and written as one line,
+    json.dumps() of its value followed by \\n, as soon as it ends; no more than one record is
+    held at a time however large the document. Records inside records are part of the outer
+    one.
+
+    Args:
+        source: XML input, as for iterparse()
+        record_path: Path of the record elements, as for read_records()
+        output: File-like object to write to. Text streams receive str; anything else
+            receives bytes in output_encoding
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        output_encoding: Encoding of bytes written to output (default 'utf-8')
+        ensure_ascii: Write characters outside ASCII as \\uXXXX escapes, as json.dumps()
+            does (default True)
+        xml_attribs, attr_prefix, cdata_key, force_cdata, cdata_separator, strip_whitespace,
+        strip, process_comments, comment_key, disable_entities, forbid_dtd, forbid_entities,
+        max_depth, max_attributes, max_text_length, max_total_nodes: As for parse(); max_depth
+            is 512 by default, as for xml_to_json()
+
+    Returns:
+        The number of records written
+
+    Raises:
+        XmlSyntaxError: If the input is not well-formed
+
+    Examples:
+        >>> out = io.StringIO()
+        >>> to_ndjson('<log><e id="1">a</e><e>b</e></log>', 'log/e', out)
+        2
+        >>> out.getvalue()
+        '{"@id": "1", "#text": "a"}\\n"b"\\n'
+    """
+    ...
+
+def is_well_formed(
+    xml_input: XMLInput,
+    encoding: str | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+    max_depth: int | None = None,
+    max_attributes: int | None = None,
+    max_text_length: int | None = None,
+    max_total_nodes: int | None = None,
+) -> tuple[bool, WellFormednessError | None]:
+    """Check that a document is well-formed without building anything for it.
+
+    The document is read through with the checks parse() makes, limits included, but no
+    Python objects are created for its elements, so payloads can be rejected at the cost of
+    tokenizing them. Unlike parse(), which reads what follows the root element as the next
+    of several documents, a second root element or text outside the root is an error.
+
+    Args:
+        xml_input: XML input, as for iterparse()
+        encoding: Encoding of the input, overriding the one it declares (default None)
+        disable_entities, forbid_dtd, forbid_entities, max_depth, max_attributes,
+        max_text_length, max_total_nodes: As for parse()
+
+    Returns:
+        (True, None) for a well-formed document, else False and a dict with the error
+        `message` as parse() would raise it, its expat `code` and the `line`, `column` and
+        `offset` where it was found (None when not known). A document that exceeds a limit,
+        or has an entity or DTD that is forbidden, is reported the same way
+
+    Raises:
+        Errors raised while reading a file-like object or iterator
+
+    Examples:
+        >>> is_well_formed('<a><b/></a>')
+        (True, None)
+        >>> is_well_formed('<a><b></a>')[1]['code']
+        7
+    """
+    ...
+
+def validate(
+    xml_input: XMLInput,
+    xsd: XMLInput,
+    encoding: str | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+) -> list[ValidationIssue]:
+    """Check a document against an XML Schema.
+
+    Content models, attributes, built-in types and the enumeration, length and range
+    facets are checked. Names are matched with their namespace: global declarations, and
+    local ones that are qualified, are in the schema's targetNamespace. xs:pattern facets
+    are not checked.
+
+    Args:
+        xml_input: XML data, as for parse()
+        xsd: XML Schema document, as for parse()
+        encoding, ..., forbid_entities: As for parse()
+
+    Returns:
+        The issues in document order, each with the `path` from the root element, its
+        `line`, what was `expected`, what was `found` (None when something is missing)
+        and the whole `message`; empty for a valid document
+
+    Raises:
+        ValueError: If xsd is not an XML Schema, or uses xs:include, xs:import or another
+            construct that is not supported
+        The same errors as parse() for a malformed document or schema
+
+    Examples:
+        >>> xsd = '''<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        ...   <xs:element name="a" type="xs:int"/></xs:schema>'''
+        >>> validate('<a>x</a>', xsd)[0]['message']
+        'a: expected xs:int, found "x" (line 1)'
+    """
+    ...
+
+def validate_rnc(
+    xml_input: XMLInput,
+    rnc_schema: str | bytes,
+    encoding: str | None = None,
+    disable_entities: bool = True,
+    forbid_dtd: bool = False,
+    forbid_entities: bool = False,
+) -> list[ValidationIssue]:
+    """Check a document against a RELAX NG schema in the compact syntax.
+
+    The schema is one pattern or a grammar of named patterns with a start, made of
+    element, attribute, text, empty and mixed patterns, groups (,), choices (|), the ?, *
+    and + repetitions, xsd: datatypes with their length and range parameters, and values.
+    Interleave (&), list, include and except (-) are not supported. Names are matched
+    without namespace prefixes.
+
+    Args:
+        xml_input: XML data, as for parse()
+        rnc_schema: The schema text, or UTF-8 bytes
+        encoding, ..., forbid_entities: As for parse()
+
+    Returns:
+        The issues in document order, as validate() returns them
+
+    Raises:
+        ValueError: If the schema is malformed or uses syntax outside that subset
+        TypeError: If rnc_schema is not str or bytes
+        The same errors as parse() for a malformed document
+
+    Examples:
+        >>> validate_rnc('<a>x</a>', 'element a { xsd:int }')[0]['message']
+        'a: expected xsd:int, found "x" (line 1)'
+    """
+    ...
+
+def infer_schema(
+    samples: XMLInput
+    | dict[str, Any]
+    | list[XMLInput | dict[str, Any]]
+    | tuple[XMLInput | dict[str, Any], ...],
+    encoding: str | None = None,
+    attr_prefix: str = "@",
+    cdata_key: str = "#text",
+) -> str:
+    """Infer a best-effort XML Schema from sample documents.
+
+    Each element is declared where it occurs, with the attributes and child elements seen
+    there. Values take the narrowest of xs:boolean, xs:int, xs:long, xs:integer,
+    xs:decimal, xs:double, xs:date, xs:time and xs:dateTime that all of them belong to,
+    else xs:string. Children seen in one order form a sequence, optional where some
+    occurrences lack them and unbounded where one had several; children seen in varying
+    orders form a repeated choice. Elements are in the targetNamespace of the first
+    sample's root element. The samples are valid against the result.
+
+    Args:
+        samples: One sample or a list of them, each XML data as for parse() or a dict
+            as parse() returns it
+        encoding: As for parse()
+        attr_prefix: Prefix marking attributes in dict samples
+        cdata_key: Key for text content in dict samples
+
+    Returns:
+        The XML Schema document
+
+    Raises:
+        ValueError: If samples is an empty list
+        The same errors as parse() for a malformed document, and as unparse() for a dict
+
+    Examples:
+        >>> print(infer_schema(['<a n="1">x</a>', '<a>y</a>']))
+        <?xml version="1.0" encoding="UTF-8"?>
+        <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+          <xs:element name="a">
+            <xs:complexType>
+              <xs:simpleContent>
+                <xs:extension base="xs:string">
+                  <xs:attribute name="n" type="xs:int"/>
+                </xs:extension>
+              </xs:simpleContent>
+            </xs:complexType>
+          </xs:element>
+        </xs:schema>
+        <BLANKLINE>
+    """
+    ...
+
+__all__ = [
+    "DepthLimitExceeded",
+    "EntitiesForbidden",
+    "LimitExceeded",
+    "SizeLimitExceeded",
+    "SoapFault",
+    "XmlEncodingError",
+    "XmlSyntaxError",
+    "XmlValidationError",
+    "build_soap",
+    "dumps_xmlrpc",
+    "infer_schema",
+    "json_to_xml",
+    "loads_xmlrpc",
+    "parse",
+    "parse_feed",
+    "parse_file",
+    "parse_many",
+    "parse_soap",
+    "roundtrip",
+    "unparse",
+    "unparse_iter",
+    "validate",
+    "validate_rnc",
+    "xml_to_json",
+]
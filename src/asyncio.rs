@@ -0,0 +1,188 @@
+use pyo3::prelude::*;
+use std::sync::{Mutex, PoisonError};
+
+/// Runs `awaitable` on `event_loop` from a worker thread and waits for its result, releasing
+/// the GIL meanwhile.
+fn wait_on<'py>(
+    py: Python<'py>,
+    event_loop: &Py<PyAny>,
+    awaitable: Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let asyncio = py.import("asyncio")?;
+    // `run_coroutine_threadsafe` only takes coroutines, which `wait_for` makes of any awaitable.
+    let coroutine = asyncio.call_method1("wait_for", (awaitable, py.None()))?;
+    asyncio
+        .call_method1("run_coroutine_threadsafe", (coroutine, event_loop))?
+        .call_method0("result")
+}
+
+/// Makes the callable to run on a worker thread, given the event loop it reports to.
+pub type Start = Box<dyn for<'py> FnOnce(&Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> + Send>;
+
+/// A coroutine running a call on the default executor of the event loop awaiting it, which
+/// `asyncio.run` and `asyncio.create_task` take like one from an `async def`.
+#[pyclass(module = "xmltodict_rs")]
+pub struct Offloaded {
+    start: Mutex<Option<Start>>,
+    /// The iterator of the executor's future, once started.
+    waiter: Mutex<Option<Py<PyAny>>>,
+}
+
+impl Offloaded {
+    pub fn new(start: Start) -> Self {
+        Self {
+            start: Mutex::new(Some(start)),
+            waiter: Mutex::new(None),
+        }
+    }
+
+    /// Starts the call on the first step, in the running event loop.
+    fn waiter(&self, py: Python) -> PyResult<Py<PyAny>> {
+        let mut waiter = self.waiter.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(waiter) = waiter.as_ref() {
+            return Ok(waiter.clone_ref(py));
+        }
+        let start = self
+            .start
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "cannot reuse already awaited coroutine",
+                )
+            })?;
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let call = start(&event_loop)?;
+        let started = event_loop
+            .call_method1("run_in_executor", (py.None(), call))?
+            .call_method0("__await__")?
+            .unbind();
+        *waiter = Some(started.clone_ref(py));
+        Ok(started)
+    }
+
+    /// A step raising, if only `StopIteration` with the result, ends the coroutine.
+    fn finish_on_error(&self, step: PyResult<Bound<'_, PyAny>>) -> PyResult<Py<PyAny>> {
+        if step.is_err() {
+            self.close();
+        }
+        step.map(Bound::unbind)
+    }
+}
+
+#[pymethods]
+impl Offloaded {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Py<PyAny>> {
+        self.send(py, &py.None().into_bound(py))
+    }
+
+    fn send(&self, py: Python, value: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+        let waiter = self.waiter(py)?;
+        let step = waiter.bind(py).call_method1("send", (value,));
+        self.finish_on_error(step)
+    }
+
+    #[pyo3(signature = (error, value = None, traceback = None))]
+    fn throw(
+        &self,
+        py: Python,
+        error: &Bound<'_, PyAny>,
+        value: Option<&Bound<'_, PyAny>>,
+        traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let waiter = self
+            .waiter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|waiter| waiter.clone_ref(py));
+        let Some(waiter) = waiter else {
+            self.close();
+            return Err(PyErr::from_value(error.clone()));
+        };
+        let step = waiter
+            .bind(py)
+            .call_method1("throw", (error, value, traceback));
+        self.finish_on_error(step)
+    }
+
+    fn close(&self) {
+        self.start
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        self.waiter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// Yields the chunks of an async iterator to a parse running on a worker thread, awaiting
+/// each one on the event loop.
+#[pyclass(module = "xmltodict_rs")]
+pub struct AsyncChunks {
+    iterator: Py<PyAny>,
+    event_loop: Py<PyAny>,
+}
+
+impl AsyncChunks {
+    pub fn new(iterator: Py<PyAny>, event_loop: &Bound<'_, PyAny>) -> Self {
+        Self {
+            iterator,
+            event_loop: event_loop.clone().unbind(),
+        }
+    }
+}
+
+#[pymethods]
+impl AsyncChunks {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<Py<PyAny>>> {
+        let awaitable = self.iterator.bind(py).call_method0("__anext__")?;
+        match wait_on(py, &self.event_loop, awaitable) {
+            Ok(chunk) => Ok(Some(chunk.unbind())),
+            Err(err) if err.is_instance_of::<pyo3::exceptions::PyStopAsyncIteration>(py) => {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Hands what an unparse running on a worker thread writes to an object whose `write` may
+/// be a coroutine, awaiting each write on the event loop before going on.
+#[pyclass(module = "xmltodict_rs")]
+pub struct AsyncWrite {
+    output: Py<PyAny>,
+    event_loop: Py<PyAny>,
+}
+
+impl AsyncWrite {
+    pub fn new(output: Py<PyAny>, event_loop: &Bound<'_, PyAny>) -> Self {
+        Self {
+            output,
+            event_loop: event_loop.clone().unbind(),
+        }
+    }
+}
+
+#[pymethods]
+impl AsyncWrite {
+    fn write(&self, py: Python, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        let written = self.output.bind(py).call_method1("write", (data,))?;
+        // A plain `write`, as `asyncio.StreamWriter` has, is done once it returns.
+        if written.hasattr("__await__")? {
+            wait_on(py, &self.event_loop, written)?;
+        }
+        Ok(())
+    }
+}
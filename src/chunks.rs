@@ -0,0 +1,86 @@
+use crate::config::UnparseConfig;
+use crate::unparser::{DefaultHook, Sink, XmlWriter};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::mpsc::{sync_channel, Receiver, RecvError};
+use std::sync::{Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+/// Lazily yields serialized chunks from a writer running on a background thread.
+/// The channel holds at most one chunk, so memory stays bounded by `chunk_size`.
+#[pyclass(module = "xmltodict_rs")]
+pub struct UnparseIterator {
+    receiver: Mutex<Option<Receiver<PyResult<String>>>>,
+    producer: Option<JoinHandle<()>>,
+}
+
+impl UnparseIterator {
+    pub fn spawn(
+        config: UnparseConfig,
+        preprocessor: Option<Py<PyAny>>,
+        default: Option<DefaultHook>,
+        input_dict: Py<PyDict>,
+        chunk_size: usize,
+    ) -> Self {
+        let (sender, receiver) = sync_channel(1);
+        let errors = sender.clone();
+        let mut writer = XmlWriter::new(
+            config,
+            preprocessor,
+            default,
+            Sink::Channel { sender, chunk_size },
+        );
+
+        let producer = std::thread::spawn(move || {
+            Python::attach(|py| {
+                let result = writer
+                    .write_document(py, input_dict.bind(py))
+                    .and_then(|()| writer.finish(py));
+                if let Err(err) = result {
+                    // A closed consumer has nobody left to report the error to.
+                    let _ = py.detach(|| errors.send(Err(err)));
+                }
+            });
+        });
+
+        Self {
+            receiver: Mutex::new(Some(receiver)),
+            producer: Some(producer),
+        }
+    }
+}
+
+impl Drop for UnparseIterator {
+    /// Closes the channel and waits for the producer to notice, so it never outlives
+    /// the iterator and tries to reacquire the GIL while the interpreter shuts down.
+    fn drop(&mut self) {
+        self.receiver
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(producer) = self.producer.take() {
+            let _ = Python::attach(|py| py.detach(|| producer.join()));
+        }
+    }
+}
+
+#[pymethods]
+impl UnparseIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<String>> {
+        let received = py.detach(|| {
+            self.receiver
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .map_or(Err(RecvError), Receiver::recv)
+        });
+        match received {
+            Ok(chunk) => chunk.map(Some),
+            Err(RecvError) => Ok(None),
+        }
+    }
+}
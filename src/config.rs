@@ -1,6 +1,12 @@
-use std::collections::HashMap;
+use crate::dtd::EntityLimits;
+use crate::escape::AttrEscapes;
+use crate::path_pattern::ListedElements;
+use encoding_rs::Encoding;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
 
 /// Newtype for attribute prefix (e.g., "@" for "@id", "@class")
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -131,6 +137,181 @@ impl fmt::Display for CommentKey {
     }
 }
 
+/// Newtype for DOCTYPE key (e.g., "#doctype")
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DoctypeKey(String);
+
+impl DoctypeKey {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl Default for DoctypeKey {
+    fn default() -> Self {
+        Self("#doctype".to_owned())
+    }
+}
+
+impl Deref for DoctypeKey {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for DoctypeKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DoctypeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Newtype for processing instruction key (e.g., "#pi")
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PiKey(String);
+
+impl PiKey {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl Default for PiKey {
+    fn default() -> Self {
+        Self("#pi".to_owned())
+    }
+}
+
+impl Deref for PiKey {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PiKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Newtype for the key of collected namespace declarations (e.g., "#namespaces")
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamespacesKey(String);
+
+impl NamespacesKey {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl Default for NamespacesKey {
+    fn default() -> Self {
+        Self("#namespaces".to_owned())
+    }
+}
+
+impl Deref for NamespacesKey {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for NamespacesKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NamespacesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Newtype for the key of element spans (e.g., "#span")
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanKey(String);
+
+impl SpanKey {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl Default for SpanKey {
+    fn default() -> Self {
+        Self("#span".to_owned())
+    }
+}
+
+impl Deref for SpanKey {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SpanKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SpanKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Newtype for the key of the order of an element's children (e.g., "#order")
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderKey(String);
+
+impl OrderKey {
+    pub fn new(s: impl Into<String>) -> Self {
+        Self(s.into())
+    }
+}
+
+impl Default for OrderKey {
+    fn default() -> Self {
+        Self("#order".to_owned())
+    }
+}
+
+impl Deref for OrderKey {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for OrderKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for OrderKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Newtype for namespace separator (e.g., ":")
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NamespaceSeparator(String);
@@ -166,6 +347,81 @@ impl fmt::Display for NamespaceSeparator {
     }
 }
 
+/// Nesting of elements [`DocumentLimits`] allows by default. Values nest as deep as the
+/// elements they are parsed from, and dropping, comparing or writing them takes stack for
+/// each level, so deeper documents are refused unless a larger limit is set.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Bounds on the shape of a parsed document, for untrusted input. `None` means unlimited;
+/// only `max_depth` has a limit by default, [`DEFAULT_MAX_DEPTH`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DocumentLimits {
+    /// Maximum nesting of elements; the root element is at depth 1.
+    pub max_depth: Option<usize>,
+    /// Maximum number of attributes on one element.
+    pub max_attributes: Option<usize>,
+    /// Maximum length in bytes (UTF-8) of the text of one element, CDATA sections included.
+    pub max_text_length: Option<usize>,
+    /// Maximum number of elements in the document.
+    pub max_total_nodes: Option<usize>,
+}
+
+impl Default for DocumentLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_attributes: None,
+            max_text_length: None,
+            max_total_nodes: None,
+        }
+    }
+}
+
+/// Kinds of text values converted to native types. All off by default.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InferTypes {
+    pub int: bool,
+    pub float: bool,
+    pub bool: bool,
+    pub null: bool,
+}
+
+impl InferTypes {
+    pub const ALL: Self = Self {
+        int: true,
+        float: true,
+        bool: true,
+        null: true,
+    };
+
+    /// Enables one kind by name: `"int"`, `"float"`, `"bool"` or `"null"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the accepted kinds when `kind` is not one of them.
+    pub fn enable(&mut self, kind: &str) -> Result<(), String> {
+        let flag = match kind {
+            "int" => &mut self.int,
+            "float" => &mut self.float,
+            "bool" => &mut self.bool,
+            "null" => &mut self.null,
+            _ => {
+                return Err(format!(
+                    "infer_types kinds must be 'int', 'float', 'bool' or 'null', not {kind:?}"
+                ))
+            }
+        };
+        *flag = true;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn any(self) -> bool {
+        self.int || self.float || self.bool || self.null
+    }
+}
+
 /// Configuration for XML parsing.
 /// Some fields are kept for API compatibility with xmltodict but not used in current implementation.
 #[allow(clippy::struct_excessive_bools)]
@@ -173,10 +429,18 @@ impl fmt::Display for NamespaceSeparator {
 pub struct ParseConfig {
     pub xml_attribs: bool,
     pub attr_prefix: AttrPrefix,
+    /// Key of a nested dict holding each element's attributes under their bare names,
+    /// used instead of `attr_prefix` keys.
+    pub attr_dict_key: Option<String>,
     pub cdata_key: CdataKey,
     pub force_cdata: bool,
     pub cdata_separator: String,
-    pub strip_whitespace: bool,
+    /// Whitespace stripped from text, by the reader, so every consumer of its events sees
+    /// the same text.
+    pub strip: Strip,
+    /// Keep the text of elements inside `xml:space="preserve"` as written, until an
+    /// `xml:space="default"` element, as the XML spec asks.
+    pub honor_xml_space: bool,
     pub namespace_separator: NamespaceSeparator,
     pub process_namespaces: bool,
     #[allow(dead_code)]
@@ -184,9 +448,61 @@ pub struct ParseConfig {
     pub comment_key: CommentKey,
     #[allow(dead_code)]
     pub item_depth: usize,
-    #[allow(dead_code)]
     pub disable_entities: bool,
+    pub forbid_dtd: bool,
+    pub forbid_entities: bool,
+    /// Repair common mistakes instead of raising: references to unknown entities and stray
+    /// `&` are kept as text, and end tags that do not match close the elements left open.
+    pub recover: bool,
+    /// References to unknown entities; with `recover`, `Strict` keeps them as text.
+    pub resolve_entities: ResolveEntities,
+    /// Compression undone before the document is read, for byte input and readers.
+    pub decompress: Decompress,
+    /// Names of elements written without an end tag, as in HTML (`<br>`), which are read as
+    /// empty. An end tag for one is ignored.
+    pub void_elements: HashSet<String>,
+    pub entity_limits: EntityLimits,
+    pub limits: DocumentLimits,
     pub namespaces: Option<HashMap<String, String>>,
+    pub preserve_mixed_content: bool,
+    pub process_doctype: bool,
+    pub doctype_key: DoctypeKey,
+    /// Keep processing instructions (other than the XML declaration) under `pi_key`.
+    pub process_pis: bool,
+    pub pi_key: PiKey,
+    /// Keep every prefix to URI declaration of the document under `namespaces_key`, whether
+    /// or not namespaces are processed.
+    pub collect_namespaces: bool,
+    pub namespaces_key: NamespacesKey,
+    /// Attributes, named as written in the document, whose values are `prefix:local` names
+    /// resolved like element names when namespaces are processed (e.g. `xsi:type`).
+    pub qname_attributes: Vec<String>,
+    /// Drop the prefix, or the namespace, from element and attribute names, leaving local
+    /// names. Namespace declarations keep their names, and in-scope bindings are not
+    /// reported under `xmlns`.
+    pub strip_namespace_prefixes: bool,
+    /// Text and attribute values converted to native types.
+    pub infer_types: InferTypes,
+    /// Elements whose first value is already a list of one, as a second one would make it.
+    /// The Python parser takes it from its `force_list` argument instead.
+    pub force_list: ListedElements,
+    /// Record the byte offsets of each element in the UTF-8 text of the document; the
+    /// Python parser keeps them under `span_key` in the element's dict.
+    pub with_spans: bool,
+    pub span_key: SpanKey,
+    /// Paths of elements, matched as `only_paths` are, kept as the markup written in the
+    /// document: from the start tag to the end tag, it stands as the element's text.
+    pub raw_paths: Vec<String>,
+    /// What a second child of the same name does to the entry of the first.
+    pub on_duplicate: OnDuplicate,
+    /// Key under which to keep the position of each element among the child elements of
+    /// its parent, from 0, so order survives grouping by name.
+    pub index_key: Option<String>,
+    /// Keep the keys of each element's children, comments and processing instructions in
+    /// document order under `order_key`, and those of the document when comments or
+    /// processing instructions on both sides of the root element share a key.
+    pub preserve_sibling_order: bool,
+    pub order_key: OrderKey,
 }
 
 impl Default for ParseConfig {
@@ -194,38 +510,84 @@ impl Default for ParseConfig {
         Self {
             xml_attribs: true,
             attr_prefix: AttrPrefix::default(),
+            attr_dict_key: None,
             cdata_key: CdataKey::default(),
             force_cdata: false,
             cdata_separator: String::new(),
-            strip_whitespace: true,
+            strip: Strip::default(),
+            honor_xml_space: true,
             namespace_separator: NamespaceSeparator::default(),
             process_namespaces: false,
             process_comments: false,
             comment_key: CommentKey::default(),
             item_depth: 0,
             disable_entities: true,
+            forbid_dtd: false,
+            forbid_entities: false,
+            recover: false,
+            resolve_entities: ResolveEntities::default(),
+            decompress: Decompress::default(),
+            void_elements: HashSet::new(),
+            entity_limits: EntityLimits::default(),
+            limits: DocumentLimits::default(),
             namespaces: None,
+            preserve_mixed_content: false,
+            process_doctype: false,
+            doctype_key: DoctypeKey::default(),
+            process_pis: false,
+            pi_key: PiKey::default(),
+            collect_namespaces: false,
+            namespaces_key: NamespacesKey::default(),
+            qname_attributes: Vec::new(),
+            strip_namespace_prefixes: false,
+            infer_types: InferTypes::default(),
+            force_list: ListedElements::default(),
+            with_spans: false,
+            span_key: SpanKey::default(),
+            raw_paths: Vec::new(),
+            on_duplicate: OnDuplicate::default(),
+            index_key: None,
+            preserve_sibling_order: false,
+            order_key: OrderKey::default(),
         }
     }
 }
 
-#[allow(dead_code)]
 impl ParseConfig {
     /// Create a new builder for `ParseConfig` with default values.
     #[must_use]
     pub fn builder() -> ParseConfigBuilder {
         ParseConfigBuilder::default()
     }
+
+    /// Joins the text nodes of an element, which the reader has already stripped. A single
+    /// text node, the usual case, is returned without copying it.
+    #[must_use]
+    pub fn join_text(&self, mut parts: Vec<String>) -> Option<String> {
+        match parts.len() {
+            0 => None,
+            1 => parts.pop(),
+            _ => Some(parts.join(&self.cdata_separator)),
+        }
+    }
+
+    /// Prefix of attribute keys; attributes nested under `attr_dict_key` have none.
+    #[must_use]
+    pub fn attribute_prefix(&self) -> &str {
+        if self.attr_dict_key.is_some() {
+            ""
+        } else {
+            &self.attr_prefix
+        }
+    }
 }
 
 /// Builder for `ParseConfig` with fluent API.
-#[allow(dead_code)]
 #[derive(Default)]
 pub struct ParseConfigBuilder {
     config: ParseConfig,
 }
 
-#[allow(dead_code)]
 impl ParseConfigBuilder {
     /// Set whether to include XML attributes in the output.
     #[must_use]
@@ -241,6 +603,13 @@ impl ParseConfigBuilder {
         self
     }
 
+    /// Collect attributes in a nested dict under this key instead of prefixing them.
+    #[must_use]
+    pub fn attr_dict_key(mut self, value: impl Into<String>) -> Self {
+        self.config.attr_dict_key = Some(value.into());
+        self
+    }
+
     /// Set the key for text content (default: "#text").
     #[must_use]
     pub fn cdata_key(mut self, value: impl Into<String>) -> Self {
@@ -262,10 +631,24 @@ impl ParseConfigBuilder {
         self
     }
 
-    /// Set whether to strip whitespace from text content.
+    /// Set whether to strip whitespace from text content, at both ends.
     #[must_use]
     pub fn strip_whitespace(mut self, value: bool) -> Self {
-        self.config.strip_whitespace = value;
+        self.config.strip = Strip::from_flag(value);
+        self
+    }
+
+    /// Set which ends of text content lose their whitespace (default: both).
+    #[must_use]
+    pub fn strip(mut self, value: Strip) -> Self {
+        self.config.strip = value;
+        self
+    }
+
+    /// Set whether `xml:space="preserve"` keeps the whitespace of an element's text.
+    #[must_use]
+    pub fn honor_xml_space(mut self, value: bool) -> Self {
+        self.config.honor_xml_space = value;
         self
     }
 
@@ -311,6 +694,90 @@ impl ParseConfigBuilder {
         self
     }
 
+    /// Set whether any `<!DOCTYPE>` declaration raises `EntitiesForbidden`.
+    #[must_use]
+    pub fn forbid_dtd(mut self, value: bool) -> Self {
+        self.config.forbid_dtd = value;
+        self
+    }
+
+    /// Set whether any `<!ENTITY>` declaration raises `EntitiesForbidden`.
+    #[must_use]
+    pub fn forbid_entities(mut self, value: bool) -> Self {
+        self.config.forbid_entities = value;
+        self
+    }
+
+    /// Set whether unknown entities, stray `&` and mismatched end tags are repaired.
+    #[must_use]
+    pub fn recover(mut self, value: bool) -> Self {
+        self.config.recover = value;
+        self
+    }
+
+    /// Set what happens to references to entities that are neither predefined nor declared.
+    #[must_use]
+    pub fn resolve_entities(mut self, value: ResolveEntities) -> Self {
+        self.config.resolve_entities = value;
+        self
+    }
+
+    /// Set the compression undone before the document is read.
+    #[must_use]
+    pub fn decompress(mut self, value: Decompress) -> Self {
+        self.config.decompress = value;
+        self
+    }
+
+    /// Set the elements read as empty without an end tag, such as `br` and `hr`.
+    #[must_use]
+    pub fn void_elements<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.void_elements = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the maximum nesting depth of entity references in DTD entity values.
+    #[must_use]
+    pub fn max_entity_depth(mut self, value: usize) -> Self {
+        self.config.entity_limits.max_depth = value;
+        self
+    }
+
+    /// Set the maximum number of bytes entity expansion may produce per document.
+    #[must_use]
+    pub fn max_entity_expansion(mut self, value: usize) -> Self {
+        self.config.entity_limits.max_expansion = value;
+        self
+    }
+
+    /// Set the maximum nesting depth of elements, [`DEFAULT_MAX_DEPTH`] unless set.
+    #[must_use]
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.config.limits.max_depth = Some(value);
+        self
+    }
+
+    /// Set the maximum number of attributes on one element.
+    #[must_use]
+    pub fn max_attributes(mut self, value: usize) -> Self {
+        self.config.limits.max_attributes = Some(value);
+        self
+    }
+
+    /// Set the maximum length in bytes of the text of one element.
+    #[must_use]
+    pub fn max_text_length(mut self, value: usize) -> Self {
+        self.config.limits.max_text_length = Some(value);
+        self
+    }
+
+    /// Set the maximum number of elements in the document.
+    #[must_use]
+    pub fn max_total_nodes(mut self, value: usize) -> Self {
+        self.config.limits.max_total_nodes = Some(value);
+        self
+    }
+
     /// Set namespace URI to prefix mappings.
     #[must_use]
     pub fn namespaces(mut self, value: Option<HashMap<String, String>>) -> Self {
@@ -318,6 +785,133 @@ impl ParseConfigBuilder {
         self
     }
 
+    /// Set whether elements mixing text and children become ordered lists of nodes.
+    #[must_use]
+    pub fn preserve_mixed_content(mut self, value: bool) -> Self {
+        self.config.preserve_mixed_content = value;
+        self
+    }
+
+    /// Set whether the DOCTYPE declaration is kept under `doctype_key` in the result.
+    #[must_use]
+    pub fn process_doctype(mut self, value: bool) -> Self {
+        self.config.process_doctype = value;
+        self
+    }
+
+    /// Set the key for the DOCTYPE declaration (default: "#doctype").
+    #[must_use]
+    pub fn doctype_key(mut self, value: impl Into<String>) -> Self {
+        self.config.doctype_key = DoctypeKey::new(value);
+        self
+    }
+
+    /// Set whether to keep processing instructions.
+    #[must_use]
+    pub fn process_pis(mut self, value: bool) -> Self {
+        self.config.process_pis = value;
+        self
+    }
+
+    /// Set the key for processing instructions (default: "#pi").
+    #[must_use]
+    pub fn pi_key(mut self, value: impl Into<String>) -> Self {
+        self.config.pi_key = PiKey::new(value);
+        self
+    }
+
+    /// Set whether the namespace declarations of the document are kept under
+    /// `namespaces_key` in the result.
+    #[must_use]
+    pub fn collect_namespaces(mut self, value: bool) -> Self {
+        self.config.collect_namespaces = value;
+        self
+    }
+
+    /// Set the key for the collected namespace declarations (default: "#namespaces").
+    #[must_use]
+    pub fn namespaces_key(mut self, value: impl Into<String>) -> Self {
+        self.config.namespaces_key = NamespacesKey::new(value);
+        self
+    }
+
+    /// Set the attributes whose values are resolved as qualified names.
+    #[must_use]
+    pub fn qname_attributes<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.config.qname_attributes = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether element and attribute names lose their namespace prefix.
+    #[must_use]
+    pub fn strip_namespace_prefixes(mut self, value: bool) -> Self {
+        self.config.strip_namespace_prefixes = value;
+        self
+    }
+
+    /// Set which text values are converted to native types.
+    #[must_use]
+    pub fn infer_types(mut self, value: InferTypes) -> Self {
+        self.config.infer_types = value;
+        self
+    }
+
+    /// Set the elements always kept as lists.
+    #[must_use]
+    pub fn force_list(mut self, value: ListedElements) -> Self {
+        self.config.force_list = value;
+        self
+    }
+
+    /// Set whether the byte offsets of elements are recorded.
+    #[must_use]
+    pub fn with_spans(mut self, value: bool) -> Self {
+        self.config.with_spans = value;
+        self
+    }
+
+    /// Set the key for element spans (default: "#span").
+    #[must_use]
+    pub fn span_key(mut self, value: impl Into<String>) -> Self {
+        self.config.span_key = SpanKey::new(value);
+        self
+    }
+
+    /// Set the paths of elements kept as the markup written in the document.
+    #[must_use]
+    pub fn raw_paths<S: Into<String>>(mut self, paths: impl IntoIterator<Item = S>) -> Self {
+        self.config.raw_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set what repeated children of the same name become (default: a list).
+    #[must_use]
+    pub fn on_duplicate(mut self, value: OnDuplicate) -> Self {
+        self.config.on_duplicate = value;
+        self
+    }
+
+    /// Keep the position of each element among its siblings under this key.
+    #[must_use]
+    pub fn index_key(mut self, value: impl Into<String>) -> Self {
+        self.config.index_key = Some(value.into());
+        self
+    }
+
+    /// Set whether the order of each element's children is kept under `order_key`.
+    #[must_use]
+    pub fn preserve_sibling_order(mut self, value: bool) -> Self {
+        self.config.preserve_sibling_order = value;
+        self
+    }
+
+    /// Set the key for the order of an element's children (default: "#order").
+    #[must_use]
+    pub fn order_key(mut self, value: impl Into<String>) -> Self {
+        self.config.order_key = OrderKey::new(value);
+        self
+    }
+
     /// Build the final `ParseConfig`.
     #[must_use]
     pub fn build(self) -> ParseConfig {
@@ -325,13 +919,532 @@ impl ParseConfigBuilder {
     }
 }
 
+/// What parsing does with references to entities that are neither predefined nor declared
+/// in the DTD, such as `&nbsp;` in XHTML-flavored content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResolveEntities {
+    /// Fail with an error naming the entity, as expat does.
+    #[default]
+    Strict,
+    /// Leave the reference out.
+    Skip,
+    /// Keep the reference as written, e.g. `&nbsp;`.
+    Preserve,
+}
+
+impl FromStr for ResolveEntities {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "skip" => Ok(Self::Skip),
+            "preserve" => Ok(Self::Preserve),
+            _ => Err(format!(
+                "resolve_entities must be 'strict', 'skip' or 'preserve', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// What parsing does with an entry whose key an element already has, such as a second
+/// `<item>` child. Elements `force_list` makes lists always collect every value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnDuplicate {
+    /// Turn the entry into a list of every value, as xmltodict does.
+    #[default]
+    List,
+    /// Keep the first value.
+    First,
+    /// Keep the last value, in place of the first.
+    Last,
+    /// Fail, naming the key.
+    Error,
+}
+
+impl FromStr for OnDuplicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "list" => Ok(Self::List),
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "on_duplicate must be 'list', 'first', 'last' or 'error', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// Which ends of an element's text lose their whitespace. Whitespace-only text between
+/// child elements is dropped whenever some is stripped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strip {
+    #[default]
+    Both,
+    Leading,
+    Trailing,
+    /// Keep all text as written.
+    None,
+}
+
+impl Strip {
+    /// `Both` when `strip_whitespace` is set, as xmltodict strips, otherwise `None`.
+    #[must_use]
+    pub fn from_flag(strip_whitespace: bool) -> Self {
+        if strip_whitespace {
+            Self::Both
+        } else {
+            Self::None
+        }
+    }
+
+    #[must_use]
+    pub fn leading(self) -> bool {
+        matches!(self, Self::Both | Self::Leading)
+    }
+
+    #[must_use]
+    pub fn trailing(self) -> bool {
+        matches!(self, Self::Both | Self::Trailing)
+    }
+
+    /// Strips the ends of a comment or processing instruction.
+    #[must_use]
+    pub fn apply(self, text: &str) -> &str {
+        match self {
+            Self::Both => text.trim(),
+            Self::Leading => text.trim_start(),
+            Self::Trailing => text.trim_end(),
+            Self::None => text,
+        }
+    }
+}
+
+impl FromStr for Strip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "both" => Ok(Self::Both),
+            "leading" => Ok(Self::Leading),
+            "trailing" => Ok(Self::Trailing),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "strip must be 'both', 'leading', 'trailing' or 'none', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// Compression undone before a document is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Decompress {
+    /// Whichever of the formats below the input's magic bytes name; other input is read as
+    /// is. A well-formed document cannot start like any of them.
+    #[default]
+    Auto,
+    /// None; the input is read as is.
+    Never,
+    Gzip,
+    Zlib,
+    Zstd,
+}
+
+impl FromStr for Decompress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "none" => Ok(Self::Never),
+            "gzip" => Ok(Self::Gzip),
+            "zlib" => Ok(Self::Zlib),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "decompress must be 'auto', 'none', 'gzip', 'zlib' or 'zstd', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// What serialization does with characters XML 1.0 does not allow, such as `\x00` or `\x0b`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvalidChars {
+    /// Fail with an error naming the character.
+    #[default]
+    Raise,
+    /// Leave the character out.
+    Strip,
+    /// Write U+FFFD REPLACEMENT CHARACTER instead.
+    Replace,
+}
+
+impl FromStr for InvalidChars {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raise" => Ok(Self::Raise),
+            "strip" => Ok(Self::Strip),
+            "replace" => Ok(Self::Replace),
+            _ => Err(format!(
+                "invalid_chars must be 'raise', 'strip' or 'replace', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// The characters the encoding of serialized output holds. Text and attribute values write
+/// the others as character references and CDATA sections end before them; names, comments
+/// and processing instructions, which cannot hold references, refuse them.
+#[derive(Clone, Default)]
+pub enum Charset {
+    /// Every character, as in UTF-8 and UTF-16, or for output that stays text.
+    #[default]
+    Unicode,
+    /// Characters up to U+007F.
+    Ascii,
+    /// Characters up to U+00FF, as in ISO-8859-1.
+    Latin1,
+    /// The characters an encoding of the WHATWG Encoding Standard holds.
+    Whatwg(&'static Encoding),
+    /// ASCII and the characters a function accepts, for encodings known elsewhere.
+    Custom(Arc<dyn Fn(char) -> bool + Send + Sync>),
+}
+
+impl fmt::Debug for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unicode => f.write_str("Unicode"),
+            Self::Ascii => f.write_str("Ascii"),
+            Self::Latin1 => f.write_str("Latin1"),
+            Self::Whatwg(encoding) => f.debug_tuple("Whatwg").field(encoding).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// What serialization does with an attribute name written twice on one element, as when a
+/// dict has both `@id` and an `attr_dict_key` entry for `id`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateAttributes {
+    /// Fail with an error naming the attribute.
+    #[default]
+    Raise,
+    /// Keep the last value, in the place of the first; `unparse` also warns.
+    Last,
+}
+
+impl FromStr for DuplicateAttributes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raise" => Ok(Self::Raise),
+            "last" => Ok(Self::Last),
+            _ => Err(format!(
+                "duplicate_attributes must be 'raise' or 'last', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// How `parse` and `unparse` map documents to Python values.
+#[cfg(feature = "python")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Elements become dict entries named after their tags.
+    #[default]
+    Xml,
+    /// Apple property lists: `<dict>`, `<array>`, `<integer>` and the other plist elements
+    /// become the dicts, lists, ints and other values they stand for, as with `plistlib`.
+    Plist,
+}
+
+#[cfg(feature = "python")]
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xml" => Ok(Self::Xml),
+            "plist" => Ok(Self::Plist),
+            _ => Err(format!("dialect must be 'xml' or 'plist', not {s:?}")),
+        }
+    }
+}
+
+/// How `parse` and `unparse` lay out attributes and text in the dicts of the `xml` dialect.
+#[cfg(feature = "python")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Convention {
+    /// `xmltodict`: attributes under `attr_prefix` keys, text under `cdata_key`, text-only
+    /// elements as strings and empty ones as None.
+    #[default]
+    Xmltodict,
+    /// `badgerfish`: every element an object, with attributes under `@` keys, namespace
+    /// declarations in an `@xmlns` object and text under `$`.
+    BadgerFish,
+    /// `parker`: attributes dropped, elements as their text or children, and the root
+    /// element left out.
+    Parker,
+    /// `gdata`: every element an object, with attributes as plain keys, text under `$t`
+    /// and `$` in place of the `:` of prefixed names.
+    GData,
+}
+
+#[cfg(feature = "python")]
+impl FromStr for Convention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xmltodict" => Ok(Self::Xmltodict),
+            "badgerfish" => Ok(Self::BadgerFish),
+            "parker" => Ok(Self::Parker),
+            "gdata" => Ok(Self::GData),
+            _ => Err(format!(
+                "convention must be 'xmltodict', 'badgerfish', 'parker' or 'gdata', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// What `parse` builds for the document.
+#[cfg(feature = "python")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NodeFactory {
+    /// Dicts laid out by the `convention`.
+    #[default]
+    Dict,
+    /// `Node` objects keeping attributes, text, comments and children in document order.
+    Ordered,
+}
+
+#[cfg(feature = "python")]
+impl FromStr for NodeFactory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dict" => Ok(Self::Dict),
+            "ordered" => Ok(Self::Ordered),
+            _ => Err(format!(
+                "node_factory must be 'dict' or 'ordered', not {s:?}"
+            )),
+        }
+    }
+}
+
+/// Checks an `xml_version` for the XML declaration: `1.` followed by digits, as the `VersionNum`
+/// production allows.
+///
+/// # Errors
+///
+/// Returns a message naming the version when it is not of that form.
+#[cfg(feature = "python")]
+pub fn check_xml_version(version: &str) -> Result<(), String> {
+    match version.strip_prefix("1.") {
+        Some(minor) if !minor.is_empty() && minor.bytes().all(|b| b.is_ascii_digit()) => Ok(()),
+        Some(_) | None => Err(format!(
+            "xml_version must be '1.' followed by digits, such as '1.0', not {version:?}"
+        )),
+    }
+}
+
+/// Configuration for XML serialization.
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct UnparseConfig {
     pub encoding: String,
+    /// Version written in the XML declaration; see [`check_xml_version`].
+    pub xml_version: String,
+    /// `standalone="yes"` or `"no"` in the XML declaration; left out when `None`.
+    pub standalone: Option<bool>,
     pub full_document: bool,
     pub short_empty_elements: bool,
     pub attr_prefix: AttrPrefix,
+    /// Key of a nested dict whose entries are written as attributes, alongside `attr_prefix`
+    /// keys.
+    pub attr_dict_key: Option<String>,
     pub cdata_key: CdataKey,
     pub pretty: bool,
+    /// With `pretty`, how many levels below the root are put on their own lines; deeper
+    /// content is written compactly. `None` indents every level.
+    pub pretty_depth: Option<usize>,
     pub newl: String,
     pub indent: String,
+    pub namespace_separator: NamespaceSeparator,
+    /// Namespace URI to prefix mappings, in declaration order. An empty prefix
+    /// maps the URI to the default namespace.
+    pub namespaces: Vec<(String, String)>,
+    /// Write lists mixing text and dicts as the ordered content of a single element.
+    pub preserve_mixed_content: bool,
+    /// DOCTYPE declaration written after the XML declaration, overriding `doctype_key`.
+    pub doctype: Option<String>,
+    /// Top-level key holding the DOCTYPE declaration; it does not count as a root.
+    pub doctype_key: DoctypeKey,
+    /// Write `\n`, `\r` and `\t` in attribute values as character references.
+    pub escape_control_chars: bool,
+    /// Write `'` in attribute values as `&apos;`.
+    pub escape_apos: bool,
+    /// Handling of characters outside the XML 1.0 character range in text and attributes.
+    pub invalid_chars: InvalidChars,
+    /// Write characters outside ASCII in text and attribute values as numeric character
+    /// references, for consumers limited to ASCII or Latin-1.
+    pub escape_non_ascii: bool,
+    /// The characters the encoding the output is stored in holds; see [`Charset`].
+    pub charset: Charset,
+    /// Handling of an attribute name written twice on one element.
+    pub duplicate_attributes: DuplicateAttributes,
+    /// Write all text content as CDATA sections.
+    pub use_cdata: bool,
+    /// Elements whose text content is written as CDATA sections.
+    pub cdata_for_keys: HashSet<String>,
+    /// Key whose values are written as `<!-- ... -->` comments; at the top level it does
+    /// not count as a root.
+    pub comment_key: CommentKey,
+    /// Key whose values are written as `<?target data?>` processing instructions; at the
+    /// top level it does not count as a root.
+    pub pi_key: PiKey,
+    /// How dates and datetimes are written: `"iso"` for `isoformat()`, otherwise a
+    /// `strftime` format. `None` writes them as `str()` does.
+    pub datetime_format: Option<String>,
+    /// Key an iterable inside a list is written under, as the content of the list's
+    /// element: `{"a": [[1, 2]]}` gives `<a><item>1</item><item>2</item></a>` with `"item"`.
+    pub expand_iter: Option<String>,
+    /// Write pydantic models as the dict their `model_dump()`, or `dict()` in pydantic 1,
+    /// returns.
+    pub pydantic_support: bool,
+    /// Write canonical XML, in the manner of C14N: see [`UnparseConfig::canonical_form`].
+    pub canonical: bool,
+    /// Write the children of a dict in the order of the keys listed under `order_key`, an
+    /// entry that is a list giving its next item each time its key is listed. The document
+    /// is written in the order listed there whether or not this is set.
+    pub preserve_sibling_order: bool,
+    pub order_key: OrderKey,
+    /// Key holding the position of an element among its siblings, as parsing with
+    /// `index_key` records it: not written, and children are written in its order.
+    pub index_key: Option<String>,
+}
+
+impl Default for UnparseConfig {
+    fn default() -> Self {
+        Self {
+            encoding: "utf-8".to_owned(),
+            xml_version: "1.0".to_owned(),
+            standalone: None,
+            full_document: true,
+            short_empty_elements: false,
+            attr_prefix: AttrPrefix::default(),
+            attr_dict_key: None,
+            cdata_key: CdataKey::default(),
+            pretty: false,
+            pretty_depth: None,
+            newl: "\n".to_owned(),
+            indent: "\t".to_owned(),
+            namespace_separator: NamespaceSeparator::default(),
+            namespaces: Vec::new(),
+            preserve_mixed_content: false,
+            doctype: None,
+            doctype_key: DoctypeKey::default(),
+            escape_control_chars: true,
+            escape_apos: false,
+            invalid_chars: InvalidChars::default(),
+            escape_non_ascii: false,
+            charset: Charset::Unicode,
+            duplicate_attributes: DuplicateAttributes::default(),
+            use_cdata: false,
+            cdata_for_keys: HashSet::new(),
+            comment_key: CommentKey::default(),
+            pi_key: PiKey::default(),
+            datetime_format: None,
+            expand_iter: None,
+            pydantic_support: false,
+            canonical: false,
+            preserve_sibling_order: false,
+            order_key: OrderKey::default(),
+            index_key: None,
+        }
+    }
+}
+
+impl UnparseConfig {
+    /// The settings `canonical` output is written with: no XML declaration or DOCTYPE, no
+    /// added whitespace, start and end tags for empty elements, no CDATA sections and
+    /// characters outside ASCII written as they are. The writers also sort attributes, after
+    /// the namespace declarations, and escape text and attribute values as C14N does.
+    #[must_use]
+    pub fn canonical_form(self) -> Self {
+        Self {
+            canonical: true,
+            pretty: false,
+            newl: "\n".to_owned(),
+            short_empty_elements: false,
+            doctype: None,
+            use_cdata: false,
+            cdata_for_keys: HashSet::new(),
+            escape_non_ascii: false,
+            ..self
+        }
+    }
+
+    /// Whether the XML declaration is written.
+    #[must_use]
+    pub fn writes_declaration(&self) -> bool {
+        self.full_document && !self.canonical
+    }
+
+    /// The XML declaration, such as `<?xml version="1.0" encoding="utf-8"?>`.
+    #[must_use]
+    pub fn declaration(&self) -> String {
+        let standalone = match self.standalone {
+            Some(true) => r#" standalone="yes""#,
+            Some(false) => r#" standalone="no""#,
+            None => "",
+        };
+        format!(
+            r#"<?xml version="{}" encoding="{}"{standalone}?>"#,
+            self.xml_version, self.encoding
+        )
+    }
+
+    /// Whether pretty printing puts nodes at `indent_level` on their own lines.
+    #[must_use]
+    pub fn indents(&self, indent_level: usize) -> bool {
+        self.pretty && self.pretty_depth.is_none_or(|depth| indent_level <= depth)
+    }
+
+    /// Whether a new line separates the next node written at `indent_level`: when pretty
+    /// printing down to that level, and between top-level nodes of canonical output.
+    #[must_use]
+    pub fn separates_nodes(&self, indent_level: usize) -> bool {
+        self.indents(indent_level) || (self.canonical && indent_level == 0)
+    }
+
+    /// Whether `text`, the content of `tag`, is written as a CDATA section. Text that
+    /// `escape_non_ascii` writes references for never is, as CDATA cannot hold them.
+    #[must_use]
+    pub fn wants_cdata(&self, tag: &str, text: &str) -> bool {
+        (self.use_cdata || self.cdata_for_keys.contains(tag))
+            && (!self.escape_non_ascii || text.is_ascii())
+    }
+
+    /// Whether text and attribute values hold `ch` as it is rather than as a reference.
+    #[must_use]
+    pub fn writes_raw(&self, ch: char) -> bool {
+        self.charset.contains(ch) && (!self.escape_non_ascii || ch.is_ascii())
+    }
+
+    /// Characters of attribute values written as references besides the markup ones.
+    #[must_use]
+    pub fn attr_escapes(&self) -> AttrEscapes {
+        AttrEscapes {
+            control_chars: self.escape_control_chars,
+            apos: self.escape_apos,
+        }
+    }
 }
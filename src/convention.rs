@@ -0,0 +1,311 @@
+use crate::config::Convention;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyMapping, PyString, PyTuple};
+
+/// Attribute prefix and text key of the layout the conventions are converted from and to.
+pub const ATTR_PREFIX: &str = "@";
+pub const CDATA_KEY: &str = "#text";
+
+const XMLNS: &str = "xmlns";
+
+/// Rewrites a document `parse` built with the [`ATTR_PREFIX`] and [`CDATA_KEY`] keys in
+/// `convention`, building mappings with `dict_constructor` when given. Entries for comments,
+/// processing instructions and the DOCTYPE are kept as they are.
+///
+/// # Errors
+///
+/// Returns the errors of `dict_constructor` and of reading the mappings.
+pub fn from_default<'py>(
+    document: &Bound<'py, PyAny>,
+    convention: Convention,
+    dict_constructor: Option<&Py<PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let reader = Reader {
+        convention,
+        dict_constructor,
+    };
+    let Ok(document) = document.downcast::<PyMapping>() else {
+        return Ok(document.clone());
+    };
+    if convention == Convention::Parker {
+        // The root element is left out: the document is what the root holds.
+        for (key, value) in entries(document)? {
+            if !is_special(&key) {
+                return reader.parker(&value);
+            }
+        }
+        return Ok(document.py().None().into_bound(document.py()));
+    }
+    let result = reader.new_map(document.py())?;
+    for (key, value) in entries(document)? {
+        if is_special(&key) {
+            result.set_item(key, value)?;
+        } else {
+            result.set_item(reader.name(&key), reader.element(&value)?)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Rewrites a value in `convention` with the [`ATTR_PREFIX`] and [`CDATA_KEY`] keys, for
+/// `unparse` to write. Parker values have no attributes or text keys and are written as
+/// given, so the root element has to be part of the value.
+///
+/// # Errors
+///
+/// Returns the errors of reading the mappings.
+pub fn to_default<'py>(
+    value: &Bound<'py, PyAny>,
+    convention: Convention,
+) -> PyResult<Bound<'py, PyAny>> {
+    if matches!(convention, Convention::Xmltodict | Convention::Parker) {
+        return Ok(value.clone());
+    }
+    let Ok(document) = value.downcast::<PyMapping>() else {
+        return Ok(value.clone());
+    };
+    let writer = Writer { convention };
+    let result = PyDict::new(value.py());
+    for (key, value) in entries(document)? {
+        if is_special(&key) {
+            result.set_item(key, value)?;
+        } else {
+            result.set_item(writer.name(&key), writer.element(&value)?)?;
+        }
+    }
+    Ok(result.into_any())
+}
+
+fn entries<'py>(
+    mapping: &Bound<'py, PyMapping>,
+) -> PyResult<Vec<(Bound<'py, PyAny>, Bound<'py, PyAny>)>> {
+    mapping.items()?.iter().map(|item| item.extract()).collect()
+}
+
+/// The text of a string key.
+fn key_str<'a>(key: &'a Bound<'_, PyAny>) -> Option<&'a str> {
+    key.downcast::<PyString>().ok()?.to_str().ok()
+}
+
+/// Keys for comments, processing instructions, the DOCTYPE and collected namespaces.
+fn is_special(key: &Bound<'_, PyAny>) -> bool {
+    key_str(key).is_some_and(|key| key.starts_with('#') && key != CDATA_KEY)
+}
+
+fn items<'py>(value: &Bound<'py, PyAny>) -> Option<Vec<Bound<'py, PyAny>>> {
+    if let Ok(list) = value.downcast::<PyList>() {
+        return Some(list.iter().collect());
+    }
+    value
+        .downcast::<PyTuple>()
+        .ok()
+        .map(|tuple| tuple.iter().collect())
+}
+
+fn text_key(convention: Convention) -> &'static str {
+    match convention {
+        Convention::GData => "$t",
+        Convention::BadgerFish | Convention::Xmltodict | Convention::Parker => "$",
+    }
+}
+
+struct Reader<'a> {
+    convention: Convention,
+    dict_constructor: Option<&'a Py<PyAny>>,
+}
+
+impl Reader<'_> {
+    fn new_map<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self.dict_constructor {
+            Some(constructor) => constructor.bind(py).call0(),
+            None => Ok(PyDict::new(py).into_any()),
+        }
+    }
+
+    /// The key for an element name; `gdata` writes `$` for the `:` of a prefix.
+    fn name<'py>(&self, key: &Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+        match key_str(key) {
+            Some(name) if self.convention == Convention::GData && name.contains(':') => {
+                PyString::new(key.py(), &name.replace(':', "$")).into_any()
+            }
+            Some(_) | None => key.clone(),
+        }
+    }
+
+    /// An element in `badgerfish` or `gdata`: always a mapping, with the text under its own
+    /// key.
+    fn element<'py>(&self, value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let py = value.py();
+        if let Some(items) = items(value) {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(self.element(&item)?)?;
+            }
+            return Ok(list.into_any());
+        }
+        let result = self.new_map(py)?;
+        if value.is_none() {
+            return Ok(result);
+        }
+        let Ok(element) = value.downcast::<PyMapping>() else {
+            result.set_item(text_key(self.convention), value)?;
+            return Ok(result);
+        };
+        let mut xmlns: Option<Bound<'py, PyAny>> = None;
+        for (key, value) in entries(element)? {
+            let Some(name) = key_str(&key) else {
+                result.set_item(key, self.element(&value)?)?;
+                continue;
+            };
+            if name == CDATA_KEY {
+                result.set_item(text_key(self.convention), value)?;
+            } else if let Some(attribute) = name.strip_prefix(ATTR_PREFIX) {
+                match self.convention {
+                    Convention::BadgerFish if is_xmlns(attribute) => {
+                        let declarations = if let Some(declarations) = &xmlns {
+                            declarations.clone()
+                        } else {
+                            let declarations = self.new_map(py)?;
+                            result.set_item(format!("{ATTR_PREFIX}{XMLNS}"), &declarations)?;
+                            xmlns = Some(declarations.clone());
+                            declarations
+                        };
+                        let prefix = attribute
+                            .strip_prefix(XMLNS)
+                            .and_then(|rest| rest.strip_prefix(':'))
+                            .unwrap_or("$");
+                        declarations.set_item(prefix, value)?;
+                    }
+                    Convention::GData => {
+                        result.set_item(attribute.replace(':', "$"), value)?;
+                    }
+                    Convention::BadgerFish | Convention::Xmltodict | Convention::Parker => {
+                        result.set_item(key, value)?;
+                    }
+                }
+            } else if is_special(&key) {
+                result.set_item(key, value)?;
+            } else {
+                result.set_item(self.name(&key), self.element(&value)?)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// An element in `parker`: its text, or its children without attributes or the text of
+    /// mixed content.
+    fn parker<'py>(&self, value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let py = value.py();
+        if let Some(items) = items(value) {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(self.parker(&item)?)?;
+            }
+            return Ok(list.into_any());
+        }
+        let Ok(element) = value.downcast::<PyMapping>() else {
+            return Ok(value.clone());
+        };
+        let result = self.new_map(py)?;
+        for (key, value) in entries(element)? {
+            match key_str(&key) {
+                Some(name) if name == CDATA_KEY || name.starts_with(ATTR_PREFIX) => {}
+                Some(_) if is_special(&key) => result.set_item(key, value)?,
+                Some(_) | None => result.set_item(key, self.parker(&value)?)?,
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Whether an attribute name declares a namespace: `xmlns` or `xmlns:prefix`.
+fn is_xmlns(name: &str) -> bool {
+    name == XMLNS
+        || name
+            .strip_prefix(XMLNS)
+            .is_some_and(|rest| rest.starts_with(':'))
+}
+
+struct Writer {
+    convention: Convention,
+}
+
+impl Writer {
+    /// The element name for a key; `gdata` keys have `$` for the `:` of a prefix.
+    fn name<'py>(&self, key: &Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+        match key_str(key) {
+            Some(name) if self.convention == Convention::GData && name.contains('$') => {
+                PyString::new(key.py(), &name.replace('$', ":")).into_any()
+            }
+            Some(_) | None => key.clone(),
+        }
+    }
+
+    fn element<'py>(&self, value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let py = value.py();
+        if let Some(items) = items(value) {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(self.element(&item)?)?;
+            }
+            return Ok(list.into_any());
+        }
+        let Ok(element) = value.downcast::<PyMapping>() else {
+            return Ok(value.clone());
+        };
+        let result = PyDict::new(py);
+        let text = text_key(self.convention);
+        for (key, value) in entries(element)? {
+            let Some(name) = key_str(&key) else {
+                result.set_item(key, self.element(&value)?)?;
+                continue;
+            };
+            if name == text {
+                result.set_item(CDATA_KEY, value)?;
+            } else if is_special(&key) {
+                result.set_item(key, value)?;
+            } else {
+                match self.convention {
+                    Convention::BadgerFish if name == "@xmlns" => {
+                        declarations(&result, &value)?;
+                    }
+                    Convention::BadgerFish if name.starts_with(ATTR_PREFIX) => {
+                        result.set_item(key, value)?;
+                    }
+                    // `gdata` attributes are the keys with plain values; elements are objects
+                    Convention::GData if !is_element(&value) => {
+                        result
+                            .set_item(format!("{ATTR_PREFIX}{}", name.replace('$', ":")), value)?;
+                    }
+                    Convention::BadgerFish
+                    | Convention::GData
+                    | Convention::Xmltodict
+                    | Convention::Parker => {
+                        result.set_item(self.name(&key), self.element(&value)?)?;
+                    }
+                }
+            }
+        }
+        Ok(result.into_any())
+    }
+}
+
+/// Writes the `badgerfish` `@xmlns` object as `xmlns` and `xmlns:prefix` attributes.
+fn declarations(result: &Bound<'_, PyDict>, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    let Ok(declarations) = value.downcast::<PyMapping>() else {
+        return result.set_item(format!("{ATTR_PREFIX}{XMLNS}"), value);
+    };
+    for (prefix, uri) in entries(declarations)? {
+        let name = match key_str(&prefix) {
+            Some("$") => format!("{ATTR_PREFIX}{XMLNS}"),
+            Some(prefix) => format!("{ATTR_PREFIX}{XMLNS}:{prefix}"),
+            None => format!("{ATTR_PREFIX}{XMLNS}:{prefix}"),
+        };
+        result.set_item(name, uri)?;
+    }
+    Ok(())
+}
+
+fn is_element(value: &Bound<'_, PyAny>) -> bool {
+    value.is_none() || items(value).is_some() || value.downcast::<PyMapping>().is_ok()
+}
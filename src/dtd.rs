@@ -0,0 +1,659 @@
+use crate::config::{ParseConfig, ResolveEntities};
+use crate::xmltodict_core::{Limit, SyntaxError};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::BytesText;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Bounds applied while expanding internal entities (billion laughs protection).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntityLimits {
+    /// Maximum nesting of entity references inside entity values.
+    pub max_depth: usize,
+    /// Maximum number of bytes produced by entity expansion over the whole document.
+    pub max_expansion: usize,
+}
+
+impl Default for EntityLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 16,
+            max_expansion: 10_000_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DtdError {
+    Undefined(String),
+    Recursive(String),
+    DepthExceeded(String),
+    ExpansionExceeded,
+    InvalidCharRef(String),
+}
+
+impl From<DtdError> for SyntaxError {
+    fn from(err: DtdError) -> Self {
+        let message = err.to_string();
+        match err {
+            DtdError::Undefined(_) => Self::new(Self::UNDEFINED_ENTITY, message),
+            DtdError::Recursive(_) => Self::new(Self::RECURSIVE_ENTITY_REF, message),
+            DtdError::DepthExceeded(_) => Self::limit_exceeded(Limit::Depth, message),
+            DtdError::ExpansionExceeded => Self::limit_exceeded(Limit::Size, message),
+            DtdError::InvalidCharRef(_) => Self::new(Self::BAD_CHAR_REF, message),
+        }
+    }
+}
+
+impl fmt::Display for DtdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undefined(name) => write!(f, "undefined entity &{name};"),
+            Self::Recursive(name) => write!(f, "recursive entity reference &{name};"),
+            Self::DepthExceeded(name) => {
+                write!(f, "entity nesting depth limit exceeded in &{name};")
+            }
+            Self::ExpansionExceeded => write!(f, "entity expansion size limit exceeded"),
+            Self::InvalidCharRef(reference) => {
+                write!(f, "reference to invalid character number &{reference};")
+            }
+        }
+    }
+}
+
+/// Declared value of a general entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum EntityDecl {
+    /// Replacement text given inline in the DTD. It always stands as text: markup in it,
+    /// such as `<b>x</b>`, is not read as elements, as expat would read it.
+    Internal(String),
+    /// `SYSTEM`/`PUBLIC` entity; never fetched, expands to nothing.
+    External,
+    /// Parameter entity (`<!ENTITY % name ...>`); only usable inside the DTD, so never expanded.
+    Parameter,
+}
+
+/// General entities declared in the document's internal DTD subset.
+#[derive(Default)]
+pub struct EntityTable {
+    first_declared: Option<String>,
+    expanded: HashMap<String, Expansion>,
+    limits: EntityLimits,
+    total: Cell<usize>,
+    error: RefCell<Option<DtdError>>,
+}
+
+impl EntityTable {
+    /// Collects `<!ENTITY>` declarations from the content of a `<!DOCTYPE ...>` event.
+    pub fn from_doctype(doctype: &str, limits: EntityLimits) -> Self {
+        let declarations = parse_entity_declarations(doctype);
+        let mut declared = HashMap::with_capacity(declarations.len());
+        let general = declarations
+            .iter()
+            .filter(|(_, decl)| *decl != EntityDecl::Parameter);
+        for (name, decl) in general.clone() {
+            // The first declaration of an entity is binding.
+            declared.entry(name.as_str()).or_insert(decl);
+        }
+
+        // Expanding in declaration order keeps error messages deterministic.
+        let mut memo = HashMap::with_capacity(declared.len());
+        for (name, _) in general {
+            let _ = expand(&declared, name, &limits, &mut memo, &mut Vec::new());
+        }
+        Self {
+            first_declared: declarations.into_iter().next().map(|(name, _)| name),
+            expanded: memo,
+            limits,
+            total: Cell::new(0),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Loads the entities of a `<!DOCTYPE ...>` event, enforcing `forbid_dtd`/`forbid_entities`.
+    /// The error is the message for the `EntitiesForbidden` failure.
+    pub fn for_doctype(doctype: &str, config: &ParseConfig) -> Result<Self, String> {
+        if config.forbid_dtd {
+            return Err(format!(
+                "DTD is forbidden: <!DOCTYPE {}>",
+                doctype.split_whitespace().next().unwrap_or_default()
+            ));
+        }
+        let table = Self::from_doctype(doctype, config.entity_limits);
+        if config.forbid_entities {
+            if let Some(name) = table.first_declared() {
+                return Err(format!(
+                    "entity declarations are forbidden: <!ENTITY {name}>"
+                ));
+            }
+        }
+        Ok(table)
+    }
+
+    /// Unescapes character data. With `disable_entities`, references to declared
+    /// entities are dropped instead of expanded, as xmltodict does.
+    pub fn unescape_text<'a>(
+        &self,
+        text: &'a BytesText,
+        disable_entities: bool,
+    ) -> Result<Cow<'a, str>, SyntaxError> {
+        text.unescape_with(|name| {
+            if disable_entities && self.is_declared(name) {
+                Some("")
+            } else {
+                self.resolve(name)
+            }
+        })
+        .map_err(|err| self.syntax_error(&err))
+    }
+
+    /// Unescapes an attribute value; declared entities are always expanded, as in expat.
+    pub fn unescape_attribute<'a>(&self, attr: &'a Attribute) -> Result<Cow<'a, str>, SyntaxError> {
+        attr.unescape_value_with(|name| self.resolve(name))
+            .map_err(|err| self.syntax_error(&err))
+    }
+
+    /// Unescapes text or an attribute value as `recover` and `resolve_entities` read it.
+    /// References to unknown entities are skipped or kept as `resolve_entities` says; with
+    /// `recover`, a stray `&` and malformed references are kept as written too, each described
+    /// in `kept`. Without it they fail as in `unescape_text`. Declared entities that fail to
+    /// expand are always errors.
+    pub fn unescape_lenient(
+        &self,
+        raw: &str,
+        disable_entities: bool,
+        config: &ParseConfig,
+        kept: &mut Vec<String>,
+    ) -> Result<String, SyntaxError> {
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(start) = rest.find('&') {
+            out.push_str(rest.get(..start).unwrap_or_default());
+            let after = rest.get(start + 1..).unwrap_or_default();
+            let Some(reference) = after
+                .find(';')
+                .and_then(|end| after.get(..end))
+                .filter(|reference| is_reference(reference))
+            else {
+                if !config.recover {
+                    return Err(self.reference_error(raw, config));
+                }
+                kept.push("stray & kept as text".to_owned());
+                out.push('&');
+                rest = after;
+                continue;
+            };
+            rest = after.get(reference.len() + 1..).unwrap_or_default();
+            let expanded = match reference.strip_prefix('#') {
+                Some(number) => parse_char_ref(number).map(|ch| Cow::Owned(ch.to_string())),
+                None if disable_entities && self.is_declared(reference) => Some(Cow::Borrowed("")),
+                None => predefined_entity(reference)
+                    .or_else(|| self.resolve(reference))
+                    .map(Cow::Borrowed),
+            };
+            if let Some(err) = self.take_error() {
+                return Err(err.into());
+            }
+            let keep = match (expanded, reference.starts_with('#')) {
+                (Some(text), _) => {
+                    out.push_str(&text);
+                    false
+                }
+                (None, true) if config.recover => {
+                    kept.push(format!(
+                        "invalid character reference &{reference}; kept as text"
+                    ));
+                    true
+                }
+                (None, true) => return Err(self.reference_error(raw, config)),
+                (None, false) => match config.resolve_entities {
+                    ResolveEntities::Skip => false,
+                    ResolveEntities::Preserve => true,
+                    ResolveEntities::Strict if config.recover => {
+                        kept.push(format!("unknown entity &{reference}; kept as text"));
+                        true
+                    }
+                    ResolveEntities::Strict => return Err(self.reference_error(raw, config)),
+                },
+            };
+            if keep {
+                out.push('&');
+                out.push_str(reference);
+                out.push(';');
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// The error `unescape_text` reports for the first reference in `raw` that
+    /// `unescape_lenient` cannot take, quick-xml's own rather than one of ours.
+    fn reference_error(&self, raw: &str, config: &ParseConfig) -> SyntaxError {
+        let lenient = config.resolve_entities != ResolveEntities::Strict;
+        quick_xml::escape::unescape_with(raw, |name| {
+            (self.is_declared(name) || lenient && is_reference(name)).then_some("")
+        })
+        .map_or_else(
+            |err| SyntaxError::from_quick_xml(&err.into()),
+            |_| SyntaxError::new(SyntaxError::INVALID_TOKEN, "malformed reference"),
+        )
+    }
+
+    fn syntax_error(&self, err: &quick_xml::Error) -> SyntaxError {
+        self.take_error()
+            .map_or_else(|| SyntaxError::from_quick_xml(err), SyntaxError::from)
+    }
+
+    /// Name of the first entity declared in the DTD, parameter entities included.
+    pub fn first_declared(&self) -> Option<&str> {
+        self.first_declared.as_deref()
+    }
+
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.expanded.contains_key(name)
+    }
+
+    /// Returns the replacement text of a declared entity, charging it against the
+    /// document-wide expansion budget. Failures are kept for `take_error`.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let result = match self.expanded.get(name)? {
+            Ok((value, _)) => {
+                let total = self.total.get().saturating_add(value.len());
+                if total > self.limits.max_expansion {
+                    Err(DtdError::ExpansionExceeded)
+                } else {
+                    self.total.set(total);
+                    Ok(value.as_str())
+                }
+            }
+            Err(err) => Err(err.clone()),
+        };
+        result.map_err(|err| self.error.replace(Some(err))).ok()
+    }
+
+    /// Takes the error recorded by the last failed `resolve` call, if any.
+    pub fn take_error(&self) -> Option<DtdError> {
+        self.error.take()
+    }
+}
+
+/// Replacement text of an entity together with the nesting height of its references.
+type Expansion = Result<(String, usize), DtdError>;
+
+/// Expands entity `name` into `memo`, so shared sub-entities are expanded only once.
+fn expand(
+    declared: &HashMap<&str, &EntityDecl>,
+    name: &str,
+    limits: &EntityLimits,
+    memo: &mut HashMap<String, Expansion>,
+    stack: &mut Vec<String>,
+) -> Result<(), DtdError> {
+    if let Some(done) = memo.get(name) {
+        return done.as_ref().map(|_| ()).map_err(Clone::clone);
+    }
+    if stack.iter().any(|open| open == name) {
+        return Err(DtdError::Recursive(name.to_owned()));
+    }
+
+    let result = match declared.get(name) {
+        None | Some(EntityDecl::Parameter) => Err(DtdError::Undefined(name.to_owned())),
+        Some(EntityDecl::External) => Ok((String::new(), 1)),
+        Some(EntityDecl::Internal(value)) => {
+            stack.push(name.to_owned());
+            let result = expand_value(declared, value, limits, memo, stack);
+            stack.pop();
+            result
+        }
+    };
+    let result = result.and_then(|(text, height)| {
+        if height > limits.max_depth {
+            Err(DtdError::DepthExceeded(name.to_owned()))
+        } else {
+            Ok((text, height))
+        }
+    });
+
+    let status = result.as_ref().map(|_| ()).map_err(Clone::clone);
+    memo.insert(name.to_owned(), result);
+    status
+}
+
+fn expand_value(
+    declared: &HashMap<&str, &EntityDecl>,
+    value: &str,
+    limits: &EntityLimits,
+    memo: &mut HashMap<String, Expansion>,
+    stack: &mut Vec<String>,
+) -> Expansion {
+    let mut out = String::with_capacity(value.len());
+    let mut height = 1;
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(rest.get(..start).unwrap_or_default());
+        let after = rest.get(start + 1..).unwrap_or_default();
+        let Some(end) = after.find(';') else {
+            out.push_str(rest.get(start..).unwrap_or_default());
+            rest = "";
+            break;
+        };
+        let reference = after.get(..end).unwrap_or_default();
+        rest = after.get(end + 1..).unwrap_or_default();
+
+        if let Some(number) = reference.strip_prefix('#') {
+            let ch = parse_char_ref(number)
+                .ok_or_else(|| DtdError::InvalidCharRef(reference.to_owned()))?;
+            out.push(ch);
+        } else if let Some(predefined) = predefined_entity(reference) {
+            out.push_str(predefined);
+        } else {
+            expand(declared, reference, limits, memo, stack)?;
+            if let Some(Ok((text, nested))) = memo.get(reference) {
+                out.push_str(text);
+                height = height.max(nested + 1);
+            }
+        }
+
+        if out.len() > limits.max_expansion {
+            return Err(DtdError::ExpansionExceeded);
+        }
+    }
+    out.push_str(rest);
+
+    if out.len() > limits.max_expansion {
+        return Err(DtdError::ExpansionExceeded);
+    }
+    Ok((out, height))
+}
+
+fn predefined_entity(name: &str) -> Option<&'static str> {
+    match name {
+        "lt" => Some("<"),
+        "gt" => Some(">"),
+        "amp" => Some("&"),
+        "apos" => Some("'"),
+        "quot" => Some("\""),
+        _ => None,
+    }
+}
+
+/// Whether the text between `&` and `;` has the shape of an entity or character reference.
+fn is_reference(text: &str) -> bool {
+    match text.strip_prefix('#') {
+        Some(number) => !number.is_empty() && number.chars().all(|ch| ch.is_ascii_alphanumeric()),
+        None => {
+            !text.is_empty()
+                && text
+                    .chars()
+                    .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-' | '.' | ':'))
+        }
+    }
+}
+
+fn parse_char_ref(number: &str) -> Option<char> {
+    let code = match number.strip_prefix('x') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse::<u32>().ok()?,
+    };
+    char::from_u32(code).filter(|&ch| ch != '\0')
+}
+
+/// Scans the internal subset (`[...]`) for general entity declarations, in document order.
+/// Parameter entities and all other markup declarations are skipped.
+fn parse_entity_declarations(doctype: &str) -> Vec<(String, EntityDecl)> {
+    let mut entities = Vec::new();
+    let Some(open) = doctype.find('[') else {
+        return entities;
+    };
+    let mut rest = doctype.get(open + 1..).unwrap_or_default();
+
+    while let Some(start) = rest.find('<') {
+        rest = rest.get(start..).unwrap_or_default();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = skip_past(comment, "-->");
+        } else if let Some(pi) = rest.strip_prefix("<?") {
+            rest = skip_past(pi, "?>");
+        } else if let Some(decl) = rest.strip_prefix("<!ENTITY") {
+            let (entity, remaining) = parse_entity_decl(decl);
+            entities.extend(entity);
+            rest = remaining;
+        } else {
+            rest = skip_declaration(rest.get(1..).unwrap_or_default());
+        }
+    }
+    entities
+}
+
+fn skip_past<'a>(text: &'a str, terminator: &str) -> &'a str {
+    text.find(terminator)
+        .and_then(|end| text.get(end + terminator.len()..))
+        .unwrap_or_default()
+}
+
+/// Skips to just after the `>` that closes a declaration, ignoring `>` inside quotes.
+fn skip_declaration(text: &str) -> &str {
+    let mut quote = None;
+    for (i, ch) in text.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(q), _) if q == ch => quote = None,
+            (None, '>') => return text.get(i + 1..).unwrap_or_default(),
+            _ => {}
+        }
+    }
+    ""
+}
+
+fn parse_entity_decl(decl: &str) -> (Option<(String, EntityDecl)>, &str) {
+    let body = decl.trim_start();
+    if body.len() == decl.len() {
+        return (None, skip_declaration(decl));
+    }
+    if let Some(parameter) = body.strip_prefix('%') {
+        let name = parameter
+            .trim_start()
+            .split(|ch: char| ch.is_whitespace() || ch == '>')
+            .next()
+            .unwrap_or_default();
+        let entity = (!name.is_empty()).then(|| (name.to_owned(), EntityDecl::Parameter));
+        return (entity, skip_declaration(decl));
+    }
+
+    let name_end = body
+        .find(|ch: char| ch.is_whitespace() || ch == '"' || ch == '\'' || ch == '>')
+        .unwrap_or(body.len());
+    let name = body.get(..name_end).unwrap_or_default();
+    let after_name = body.get(name_end..).unwrap_or_default().trim_start();
+
+    let value = match after_name.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let literal = after_name.get(1..).unwrap_or_default();
+            literal
+                .find(quote)
+                .and_then(|end| literal.get(..end))
+                .map(|value| EntityDecl::Internal(value.to_owned()))
+        }
+        _ if after_name.starts_with("SYSTEM") || after_name.starts_with("PUBLIC") => {
+            Some(EntityDecl::External)
+        }
+        _ => None,
+    };
+
+    let entity = value
+        .filter(|_| !name.is_empty())
+        .map(|value| (name.to_owned(), value));
+    (entity, skip_declaration(decl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    fn table(doctype: &str) -> EntityTable {
+        EntityTable::from_doctype(doctype, EntityLimits::default())
+    }
+
+    #[test]
+    fn test_internal_entities_expand_recursively() {
+        let t = table(r#" r [<!ENTITY e "v&amp;w"><!ENTITY f '[&e;&#33;]'>]"#);
+        assert_eq!(Some("v&w"), t.resolve("e"));
+        assert_eq!(Some("[v&w!]"), t.resolve("f"));
+        assert_eq!(None, t.resolve("missing"));
+        assert_eq!(None, t.take_error());
+    }
+
+    #[test]
+    fn test_skips_comments_parameter_entities_and_other_declarations() {
+        let t = table(
+            r#" r [
+                <!-- <!ENTITY hidden "x"> -->
+                <!ENTITY % param "p">
+                <!ELEMENT r (#PCDATA)>
+                <!ATTLIST r a CDATA "<!ENTITY fake 'y'>">
+                <!ENTITY ext SYSTEM "http://example.com/x">
+                <!ENTITY real "ok">
+            ]"#,
+        );
+        assert!(!t.is_declared("hidden"));
+        assert!(!t.is_declared("param"));
+        assert_eq!(Some("param"), t.first_declared());
+        assert!(!t.is_declared("fake"));
+        assert_eq!(Some(""), t.resolve("ext"));
+        assert_eq!(Some("ok"), t.resolve("real"));
+    }
+
+    #[test]
+    fn test_markup_in_replacement_text_stays_text() {
+        let t = table(r#" r [<!ENTITY e "<b>x</b>">]"#);
+        assert_eq!(Some("<b>x</b>"), t.resolve("e"));
+    }
+
+    #[test]
+    fn test_first_declaration_wins() {
+        let t = table(r#" r [<!ENTITY e "first"><!ENTITY e "second">]"#);
+        assert_eq!(Some("first"), t.resolve("e"));
+    }
+
+    #[test]
+    fn test_recursive_entity_is_rejected() {
+        let t = table(r#" r [<!ENTITY a "&b;"><!ENTITY b "&a;">]"#);
+        assert_eq!(None, t.resolve("a"));
+        assert_eq!(Some(DtdError::Recursive("a".to_owned())), t.take_error());
+    }
+
+    #[test]
+    fn test_billion_laughs_is_bounded() {
+        let declarations = (1..10).fold(String::new(), |mut acc, i| {
+            let refs = format!("&lol{};", i - 1).repeat(10);
+            let _ = write!(acc, "<!ENTITY lol{i} \"{refs}\">");
+            acc
+        });
+        let doctype = format!(r#" lolz [<!ENTITY lol0 "lol">{declarations}]"#);
+
+        let t = table(&doctype);
+        assert_eq!(Some("lol".repeat(10).as_str()), t.resolve("lol1"));
+        assert_eq!(None, t.resolve("lol9"));
+        assert_eq!(Some(DtdError::ExpansionExceeded), t.take_error());
+        let err = SyntaxError::from(DtdError::ExpansionExceeded);
+        assert_eq!(Some(Limit::Size), err.limit);
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = EntityLimits {
+            max_depth: 2,
+            ..EntityLimits::default()
+        };
+        let t = EntityTable::from_doctype(
+            r#" r [<!ENTITY a "&b;"><!ENTITY b "&c;"><!ENTITY c "x">]"#,
+            limits,
+        );
+        assert_eq!(Some("x"), t.resolve("b"));
+        assert_eq!(None, t.resolve("a"));
+        assert_eq!(
+            Some(DtdError::DepthExceeded("a".to_owned())),
+            t.take_error()
+        );
+        let err = SyntaxError::from(DtdError::DepthExceeded("a".to_owned()));
+        assert_eq!(Some(Limit::Depth), err.limit);
+    }
+
+    #[test]
+    fn test_total_expansion_budget() {
+        let limits = EntityLimits {
+            max_expansion: 10,
+            ..EntityLimits::default()
+        };
+        let t = EntityTable::from_doctype(r#" r [<!ENTITY e "abcd">]"#, limits);
+        assert_eq!(Some("abcd"), t.resolve("e"));
+        assert_eq!(Some("abcd"), t.resolve("e"));
+        assert_eq!(None, t.resolve("e"));
+        assert_eq!(Some(DtdError::ExpansionExceeded), t.take_error());
+    }
+
+    #[test]
+    fn test_lenient_unescape_keeps_what_it_cannot_expand() -> Result<(), SyntaxError> {
+        let t = table(r#" r [<!ENTITY e "v"><!ENTITY a "&b;"><!ENTITY b "&a;">]"#);
+        let config = ParseConfig::builder().recover(true).build();
+        let mut kept = Vec::new();
+        let text = t.unescape_lenient(
+            "AT&T &e;&lt;&nbsp;&#x41;&#xZZ; &",
+            false,
+            &config,
+            &mut kept,
+        )?;
+        assert_eq!("AT&T v<&nbsp;A&#xZZ; &", text);
+        assert_eq!(
+            vec![
+                "stray & kept as text",
+                "unknown entity &nbsp; kept as text",
+                "invalid character reference &#xZZ; kept as text",
+                "stray & kept as text",
+            ],
+            kept
+        );
+        assert_eq!("", t.unescape_lenient("&e;", true, &config, &mut kept)?);
+        assert!(t
+            .unescape_lenient("&a;", false, &config, &mut kept)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_entities_skipped_or_preserved() -> Result<(), SyntaxError> {
+        let t = table(r#" r [<!ENTITY e "v">]"#);
+        let mut kept = Vec::new();
+        let raw = "a&nbsp;b &e;&amp;&#65;";
+        let skip = ParseConfig::builder()
+            .resolve_entities(ResolveEntities::Skip)
+            .build();
+        assert_eq!("ab v&A", t.unescape_lenient(raw, false, &skip, &mut kept)?);
+        let preserve = ParseConfig::builder()
+            .resolve_entities(ResolveEntities::Preserve)
+            .build();
+        assert_eq!(
+            "a&nbsp;b v&A",
+            t.unescape_lenient(raw, false, &preserve, &mut kept)?
+        );
+        assert_eq!(
+            "\u{1F600}\u{1F600}\u{10FFFF}",
+            t.unescape_lenient("&#x1F600;&#128512;&#x10FFFF;", false, &skip, &mut kept)?
+        );
+        assert!(kept.is_empty());
+
+        let err = t.unescape_lenient("&nbsp; &", false, &preserve, &mut kept);
+        assert_eq!(
+            Some(SyntaxError::INVALID_TOKEN),
+            err.err().map(|err| err.code)
+        );
+        let err = t.unescape_lenient("&nbsp;", false, &ParseConfig::default(), &mut kept);
+        assert_eq!(
+            Some(SyntaxError::UNDEFINED_ENTITY),
+            err.err().map(|err| err.code)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+use crate::config::Charset;
+#[cfg(feature = "python")]
+use crate::error::encoding_error;
+use encoding_rs::{EncoderResult, Encoding, GB18030, UTF_16BE, UTF_16LE, UTF_8};
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::borrow::Cow;
+
+/// Number of leading bytes inspected when looking for an XML declaration.
+pub const SNIFF_LEN: usize = 1024;
+
+/// Resolves a Python-style encoding name (e.g. `cp1251`, `latin-1`, `utf_16`) to an encoding.
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    let normalized = normalize_label(label);
+    let alias = match normalized.as_str() {
+        "latin-1" | "latin1" | "l1" => "iso-8859-1",
+        "utf-8-sig" | "utf8-sig" => "utf-8",
+        "utf-16-le" => "utf-16le",
+        "utf-16-be" => "utf-16be",
+        "sjis" | "shift-jis" => "shift_jis",
+        other => other,
+    };
+
+    Encoding::for_label(alias.as_bytes()).or_else(|| Encoding::for_label(label.as_bytes()))
+}
+
+fn normalize_label(label: &str) -> String {
+    label.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Encodes a serialized document in the encoding named by `label`, as Python's `str.encode`
+/// with the `xmlcharrefreplace` error handler does: characters the encoding cannot represent
+/// are written as decimal character references. With `bom`, `utf-16` and `utf-8-sig` output
+/// starts with a byte order mark, as in Python; later chunks of a document are encoded
+/// without one. Returns `None` for an unknown label.
+#[cfg(feature = "python")]
+pub fn encode_document<'a>(text: &'a str, label: &str, bom: bool) -> Option<Cow<'a, [u8]>> {
+    let normalized = normalize_label(label);
+    if let Some(limit) = single_byte_limit(&normalized) {
+        return Some(encode_single_bytes(text, limit));
+    }
+    let encoding = encoding_for_label(label)?;
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        // As in Python, only plain `utf-16` starts with a byte order mark.
+        let bom = (bom && normalized == "utf-16").then_some(0xFEFF);
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        for unit in bom.into_iter().chain(text.encode_utf16()) {
+            if encoding == UTF_16BE {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        return Some(Cow::Owned(bytes));
+    }
+    if bom && matches!(normalized.as_str(), "utf-8-sig" | "utf8-sig") {
+        return Some(Cow::Owned([b"\xEF\xBB\xBF", text.as_bytes()].concat()));
+    }
+    let (bytes, _, _) = encoding.encode(text);
+    Some(bytes)
+}
+
+/// The highest character of ASCII and Latin-1, which encode characters as the byte of the
+/// same value. WHATWG labels both as windows-1252, whose bytes 0x80 to 0x9F (and for ASCII,
+/// all bytes from 0x80) mean other characters to readers of those encodings.
+fn single_byte_limit(normalized: &str) -> Option<u8> {
+    match normalized {
+        "ascii" | "us-ascii" | "646" => Some(0x7F),
+        "latin-1" | "latin1" | "latin" | "l1" | "iso-8859-1" | "iso8859-1" | "8859" | "cp819" => {
+            Some(0xFF)
+        }
+        _ => None,
+    }
+}
+
+impl Charset {
+    /// The characters of the encoding named by `label`, as [`encode_document`] encodes it,
+    /// or `None` for a label only Python's codecs may know.
+    #[must_use]
+    pub fn for_label(label: &str) -> Option<Self> {
+        match single_byte_limit(&normalize_label(label)) {
+            Some(0x7F) => return Some(Self::Ascii),
+            Some(_) => return Some(Self::Latin1),
+            None => {}
+        }
+        let encoding = encoding_for_label(label)?;
+        // UTF-16 is written by hand; the rest are written with the encoder of the output
+        // encoding, UTF-8 for UTF-16 and the replacement encoding.
+        Some(match encoding.output_encoding() {
+            output if output == UTF_8 || output == GB18030 => Self::Unicode,
+            output => Self::Whatwg(output),
+        })
+    }
+
+    /// Whether the encoding holds `ch`.
+    #[must_use]
+    pub fn contains(&self, ch: char) -> bool {
+        match self {
+            Self::Unicode => true,
+            Self::Ascii => ch.is_ascii(),
+            Self::Latin1 => u32::from(ch) <= 0xFF,
+            Self::Whatwg(encoding) => ch.is_ascii() || encodes(encoding, ch),
+            Self::Custom(contains) => ch.is_ascii() || contains(ch),
+        }
+    }
+}
+
+fn encodes(encoding: &'static Encoding, ch: char) -> bool {
+    let mut utf8 = [0; 4];
+    // Room for the escape sequences of ISO-2022-JP around the character.
+    let mut bytes = [0; 16];
+    let (result, _, _) = encoding.new_encoder().encode_from_utf8_without_replacement(
+        ch.encode_utf8(&mut utf8),
+        &mut bytes,
+        true,
+    );
+    result == EncoderResult::InputEmpty
+}
+
+/// Encodes characters up to `limit` as the byte of the same value, as ASCII and Latin-1 do.
+#[cfg(feature = "python")]
+fn encode_single_bytes(text: &str, limit: u8) -> Cow<'_, [u8]> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    for ch in text.chars() {
+        match u8::try_from(ch) {
+            Ok(byte) if byte <= limit => bytes.push(byte),
+            Ok(_) | Err(_) => bytes.extend_from_slice(format!("&#{};", u32::from(ch)).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+#[cfg(feature = "python")]
+pub fn lookup_encoding(label: &str) -> PyResult<&'static Encoding> {
+    encoding_for_label(label).ok_or_else(|| {
+        PyErr::new::<pyo3::exceptions::PyLookupError, _>(format!("unknown encoding: {label}"))
+    })
+}
+
+/// Detects the document encoding from a byte order mark or the XML declaration.
+/// Returns `None` when the document should be read as UTF-8 as-is.
+pub fn sniff_encoding(prefix: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+        return Some(encoding);
+    }
+
+    match prefix {
+        [0x3C, 0x00, 0x3F, 0x00, ..] => return Some(UTF_16LE),
+        [0x00, 0x3C, 0x00, 0x3F, ..] => return Some(UTF_16BE),
+        _ => {}
+    }
+
+    let label = declared_encoding(prefix)?;
+    let encoding = std::str::from_utf8(label)
+        .ok()
+        .and_then(encoding_for_label)?;
+
+    // An ASCII-readable declaration cannot describe a UTF-16 document without a BOM.
+    if encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE {
+        return None;
+    }
+    Some(encoding)
+}
+
+/// Extracts the `encoding` pseudo-attribute value from a leading `<?xml ...?>` declaration.
+fn declared_encoding(prefix: &[u8]) -> Option<&[u8]> {
+    let decl = prefix.strip_prefix(b"<?xml")?;
+    let end = memchr::memmem::find(decl, b"?>")?;
+    let decl = decl.get(..end)?;
+
+    let start = memchr::memmem::find(decl, b"encoding")?;
+    let rest = decl.get(start + b"encoding".len()..)?.trim_ascii_start();
+    let rest = rest.strip_prefix(b"=")?.trim_ascii_start();
+    let (&quote, rest) = rest.split_first()?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let close = memchr::memchr(quote, rest)?;
+    rest.get(..close)
+}
+
+/// Returns true once `prefix` holds enough bytes for `sniff_encoding` to decide.
+pub fn sniff_complete(prefix: &[u8]) -> bool {
+    if prefix.len() >= SNIFF_LEN {
+        return true;
+    }
+    if prefix.len() < 4 {
+        return false;
+    }
+    !prefix.starts_with(b"<?xml".get(..prefix.len().min(5)).unwrap_or_default())
+        || memchr::memmem::find(prefix, b"?>").is_some()
+}
+
+/// Decodes a whole document into UTF-8 bytes, sniffing the encoding when none is given.
+/// Errors carry an expat-style message.
+pub fn transcode<'a>(
+    bytes: &'a [u8],
+    encoding: Option<&'static Encoding>,
+) -> Result<Cow<'a, [u8]>, String> {
+    match encoding.or_else(|| sniff_encoding(bytes.get(..SNIFF_LEN).unwrap_or(bytes))) {
+        Some(enc) => decode_to_utf8(bytes, enc),
+        None => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+#[cfg(feature = "python")]
+pub fn decode_document<'a>(
+    py: Python,
+    bytes: &'a [u8],
+    encoding: Option<&'static Encoding>,
+) -> PyResult<Cow<'a, [u8]>> {
+    transcode(bytes, encoding).map_err(|msg| encoding_error(py, msg))
+}
+
+/// Decodes a complete buffer into UTF-8 bytes.
+/// A byte order mark takes precedence over the requested encoding, mirroring expat.
+fn decode_to_utf8<'a>(
+    bytes: &'a [u8],
+    encoding: &'static Encoding,
+) -> Result<Cow<'a, [u8]>, String> {
+    let (encoding, bom_len) = Encoding::for_bom(bytes).unwrap_or((encoding, 0));
+    let body = bytes.get(bom_len..).unwrap_or_default();
+
+    if encoding == UTF_8 {
+        return Ok(Cow::Borrowed(body));
+    }
+
+    match encoding.decode_without_bom_handling_and_without_replacement(body) {
+        Some(Cow::Borrowed(text)) => Ok(Cow::Borrowed(text.as_bytes())),
+        Some(Cow::Owned(text)) => Ok(Cow::Owned(text.into_bytes())),
+        None => Err(invalid_sequence_message(encoding)),
+    }
+}
+
+pub fn invalid_sequence_message(encoding: &'static Encoding) -> String {
+    format!(
+        "not well-formed (invalid {} byte sequence)",
+        encoding.name()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1251, WINDOWS_1252};
+
+    #[test]
+    fn test_sniff_bom() {
+        assert_eq!(Some(UTF_16LE), sniff_encoding(b"\xff\xfe<\x00"));
+        assert_eq!(Some(UTF_16BE), sniff_encoding(b"\xfe\xff\x00<"));
+        assert_eq!(Some(UTF_8), sniff_encoding(b"\xef\xbb\xbf<root/>"));
+    }
+
+    #[test]
+    fn test_sniff_utf16_without_bom() {
+        assert_eq!(Some(UTF_16LE), sniff_encoding(b"<\x00?\x00x\x00"));
+        assert_eq!(Some(UTF_16BE), sniff_encoding(b"\x00<\x00?\x00x"));
+    }
+
+    #[test]
+    fn test_sniff_declaration() {
+        assert_eq!(
+            Some(WINDOWS_1252),
+            sniff_encoding(br#"<?xml version="1.0" encoding="ISO-8859-1"?><a/>"#)
+        );
+        assert_eq!(
+            Some(WINDOWS_1251),
+            sniff_encoding(b"<?xml version='1.0' encoding = 'cp1251' ?><a/>")
+        );
+        assert_eq!(
+            Some(SHIFT_JIS),
+            sniff_encoding(br#"<?xml version="1.0" encoding="shift_jis"?>"#)
+        );
+    }
+
+    #[test]
+    fn test_sniff_defaults_to_utf8() {
+        assert_eq!(None, sniff_encoding(b"<root/>"));
+        assert_eq!(None, sniff_encoding(br#"<?xml version="1.0"?><a/>"#));
+        assert_eq!(None, sniff_encoding(br#"<?xml encoding="utf-8"?><a/>"#));
+        assert_eq!(None, sniff_encoding(br#"<?xml encoding="utf-16"?><a/>"#));
+        assert_eq!(None, sniff_encoding(br#"<?xml encoding="bogus"?><a/>"#));
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn test_encode_document() {
+        let text = "<a>caf\u{E9} \u{20AC}\u{1F600}</a>";
+        let encode = |label| encode_document(text, label, true).map(Cow::into_owned);
+        assert_eq!(Some(text.as_bytes().to_vec()), encode("UTF-8"));
+        assert_eq!(
+            Some(b"<a>caf\xE9 &#8364;&#128512;</a>".to_vec()),
+            encode("iso-8859-1")
+        );
+        assert_eq!(
+            Some(b"<a>caf\xE9 \x80&#128512;</a>".to_vec()),
+            encode("cp1252")
+        );
+        assert_eq!(
+            Some(b"<a>caf&#233; &#8364;&#128512;</a>".to_vec()),
+            encode("us-ascii")
+        );
+        assert_eq!(
+            Some(b"\xFF\xFE<\x00".to_vec()),
+            encode_document("<", "utf-16", true).map(Cow::into_owned)
+        );
+        assert_eq!(
+            Some(b"<\x00".to_vec()),
+            encode_document("<", "utf-16", false).map(Cow::into_owned)
+        );
+        assert_eq!(
+            Some(b"\x00<".to_vec()),
+            encode_document("<", "UTF_16_BE", true).map(Cow::into_owned)
+        );
+        assert_eq!(None, encode("bogus"));
+    }
+
+    #[test]
+    fn test_sniff_complete() {
+        assert!(!sniff_complete(b""));
+        assert!(!sniff_complete(b"\xff\xfe"));
+        assert!(!sniff_complete(b"<?xm"));
+        assert!(!sniff_complete(br#"<?xml version="1.0" enc"#));
+        assert!(sniff_complete(br#"<?xml version="1.0"?>"#));
+        assert!(sniff_complete(b"<root>"));
+    }
+}
@@ -1,7 +1,68 @@
+use crate::xmltodict_core::{Error, Limit, SyntaxError};
+use crate::xsd::{Found, Issue};
 use pyo3::prelude::*;
-use pyo3::types::{PyModule, PyType};
+use pyo3::types::{PyDict, PyList};
 use std::io;
 
+pyo3::import_exception!(xml.parsers.expat, ExpatError);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    XmlSyntaxError,
+    ExpatError,
+    "Raised for malformed documents; an ExpatError with `code`, `lineno` and `offset` attributes."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    XmlEncodingError,
+    XmlSyntaxError,
+    "Raised when a document cannot be decoded from its encoding."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    LimitExceeded,
+    XmlSyntaxError,
+    "Raised when a document exceeds one of the limits set for parse()."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    DepthLimitExceeded,
+    LimitExceeded,
+    "Raised when a document exceeds max_depth or max_entity_depth."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    SizeLimitExceeded,
+    LimitExceeded,
+    "Raised when a document exceeds max_attributes, max_text_length, max_total_nodes or max_entity_expansion."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    EntitiesForbidden,
+    pyo3::exceptions::PyValueError,
+    "Raised when a document contains a DTD or entity declaration that parse() was told to forbid."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    XmlValidationError,
+    pyo3::exceptions::PyValueError,
+    "Raised by parse(validate_against=...) for a document that does not follow the schema, \
+     with the issues validate() returns in `errors`."
+);
+
+pyo3::create_exception!(
+    xmltodict_rs,
+    SoapFault,
+    pyo3::exceptions::PyException,
+    "Raised by parse_soap() for a Fault body, with `code`, `reason` and `detail` attributes."
+);
+
 /// Wrapper to store `PyErr` inside `io::Error` while preserving the original exception type.
 /// `PyErr` is Send but not Sync, so we need unsafe impl Sync.
 /// This is safe because we only access the inner `PyErr` while holding the GIL.
@@ -44,43 +105,102 @@ pub fn pyerr_from_io(err: &io::Error) -> Option<PyErr> {
         .map(|w| Python::attach(|py| w.0.clone_ref(py)))
 }
 
-pub fn expat_error(py: Python, msg: String) -> PyErr {
-    let expat_type = PyModule::import(py, "xml.parsers.expat")
-        .and_then(|m| m.getattr("ExpatError"))
-        .ok()
-        .and_then(|t| t.downcast_into::<PyType>().ok());
-    match expat_type {
-        Some(ty) => PyErr::from_type(ty, msg),
-        None => PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("XML parse error: {msg}")),
+/// Raises an `XmlSyntaxError` subclass matching the error, with the `code`, `lineno`
+/// and `offset` attributes expat sets. Without a known position, `lineno` and `offset` are
+/// left unset.
+pub fn expat_error(py: Python, err: &SyntaxError) -> PyErr {
+    let exc = match (err.limit, err.code) {
+        (Some(Limit::Depth), _) => DepthLimitExceeded::new_err(err.to_string()),
+        (Some(Limit::Size), _) => SizeLimitExceeded::new_err(err.to_string()),
+        (None, SyntaxError::INCORRECT_ENCODING) => XmlEncodingError::new_err(err.to_string()),
+        (None, _) => XmlSyntaxError::new_err(err.to_string()),
+    };
+    let value = exc.value(py);
+    let attrs = value
+        .setattr("code", err.code)
+        .and_then(|()| match err.position {
+            Some(position) => {
+                value.setattr("lineno", position.line)?;
+                value.setattr("offset", position.column)
+            }
+            None => Ok(()),
+        });
+    attrs.err().unwrap_or(exc)
+}
+
+/// The dicts `validate()` returns for schema issues, with `path`, `line`, `expected`,
+/// `found` and the whole `message`. What was found is `None` when something is missing.
+pub fn issue_list<'py>(py: Python<'py>, issues: &[Issue]) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for issue in issues {
+        let dict = PyDict::new(py);
+        dict.set_item("path", &issue.path)?;
+        dict.set_item("line", issue.line)?;
+        dict.set_item("expected", &issue.expected)?;
+        match &issue.found {
+            Found::Nothing => dict.set_item("found", py.None())?,
+            Found::Element(_) | Found::Attribute(_) => {
+                dict.set_item("found", issue.found.to_string())?;
+            }
+            Found::Text(text) => dict.set_item("found", text)?,
+        }
+        dict.set_item("message", issue.to_string())?;
+        list.append(dict)?;
     }
+    Ok(list)
+}
+
+/// Raises `XmlValidationError` for the issues of a document, naming the first.
+pub fn validation_error(py: Python, issues: &[Issue]) -> PyResult<PyErr> {
+    let msg = match issues {
+        [] => "document does not follow the schema".to_owned(),
+        [issue] => issue.to_string(),
+        [issue, rest @ ..] => format!("{issue} (and {} more)", rest.len()),
+    };
+    let err = XmlValidationError::new_err(msg);
+    err.value(py).setattr("errors", issue_list(py, issues)?)?;
+    Ok(err)
+}
+
+/// Raises the error for input that cannot be decoded from its encoding.
+pub fn encoding_error(py: Python, msg: String) -> PyErr {
+    expat_error(py, &SyntaxError::new(SyntaxError::INCORRECT_ENCODING, msg))
 }
 
-pub fn validate_element_name(py: Python, name: &str) -> PyResult<()> {
-    if name.is_empty() || name.chars().any(|x| matches!(x, '<' | '>')) {
-        return Err(expat_error(
-            py,
-            "not well-formed (invalid element name)".to_owned(),
-        ));
+/// Adds a note to an exception with `add_note()`, or to `__notes__` before Python 3.11.
+/// Failures are ignored so the original exception is raised unchanged.
+pub fn add_note(py: Python, err: &PyErr, note: &str) {
+    let value = err.value(py);
+    if value.hasattr("add_note").unwrap_or(false) {
+        let _ = value.call_method1("add_note", (note,));
+        return;
+    }
+    let notes = value
+        .getattr("__notes__")
+        .and_then(|notes| notes.downcast_into::<PyList>().map_err(PyErr::from))
+        .or_else(|_| {
+            let notes = PyList::empty(py);
+            value.setattr("__notes__", &notes).map(|()| notes)
+        });
+    if let Ok(notes) = notes {
+        let _ = notes.append(note);
     }
-    Ok(())
 }
 
-pub fn map_quick_xml_error(py: Python, err: quick_xml::Error) -> PyErr {
+/// Raises tokenizer errors as the matching Python exception. Errors raised by Python
+/// file-like objects and generators come back unchanged.
+pub fn parse_error(py: Python, err: Error) -> PyErr {
     match err {
-        quick_xml::Error::Io(io_err) => {
-            pyerr_from_io(&io_err).unwrap_or_else(|| expat_error(py, io_err.to_string()))
+        Error::Syntax(err) => expat_error(py, &err),
+        Error::EntitiesForbidden(msg) => EntitiesForbidden::new_err(msg),
+        Error::Io(io_err) => pyerr_from_io(&io_err).unwrap_or_else(|| {
+            expat_error(
+                py,
+                &SyntaxError::new(SyntaxError::SYNTAX, io_err.to_string()),
+            )
+        }),
+        Error::Value(msg) | Error::Json(msg) => {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(msg)
         }
-        other @ (quick_xml::Error::NonDecodable(_)
-        | quick_xml::Error::UnexpectedEof(_)
-        | quick_xml::Error::EndEventMismatch { .. }
-        | quick_xml::Error::UnexpectedToken(_)
-        | quick_xml::Error::UnexpectedBang(_)
-        | quick_xml::Error::TextNotFound
-        | quick_xml::Error::XmlDeclWithoutVersion(_)
-        | quick_xml::Error::EmptyDocType
-        | quick_xml::Error::InvalidAttr(_)
-        | quick_xml::Error::EscapeError(_)
-        | quick_xml::Error::UnknownPrefix(_)
-        | quick_xml::Error::InvalidPrefixBind { .. }) => expat_error(py, other.to_string()),
     }
 }
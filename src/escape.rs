@@ -1,107 +1,288 @@
+use crate::config::InvalidChars;
 use std::borrow::Cow;
-use std::slice::from_raw_parts;
-use std::str::from_utf8_unchecked;
+use std::fmt::Write;
 
 const LT: u8 = b'<';
 const GT: u8 = b'>';
 const AMPERSAND: u8 = b'&';
+const QUOTE: u8 = b'"';
+const APOS: u8 = b'\'';
 
 const ESCAPED_AMP: &str = "&amp;";
 const ESCAPED_LT: &str = "&lt;";
 const ESCAPED_GT: &str = "&gt;";
+const ESCAPED_QUOTE: &str = "&quot;";
+const ESCAPED_APOS: &str = "&apos;";
 
-pub fn escape_xml(text: &str) -> Cow<'_, str> {
-    let bytes = text.as_bytes();
-    let len = bytes.len();
+/// Which characters of an attribute value are written as references, besides `&`, `<`, `>`
+/// and `"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AttrEscapes {
+    /// `\n`, `\r` and `\t`, which attribute-value normalization would turn into spaces.
+    pub control_chars: bool,
+    /// `'`, for consumers that copy values into single-quoted attributes.
+    pub apos: bool,
+}
 
-    let need_escape = memchr::memchr3(AMPERSAND, LT, GT, bytes).is_some();
+/// The reference a byte of text content is replaced with, if any.
+fn text_escape(byte: u8) -> Option<&'static str> {
+    match byte {
+        AMPERSAND => Some(ESCAPED_AMP),
+        LT => Some(ESCAPED_LT),
+        GT => Some(ESCAPED_GT),
+        _ => None,
+    }
+}
+
+/// The reference a byte of an attribute value is replaced with, if any.
+fn attr_escape(byte: u8, escapes: AttrEscapes) -> Option<&'static str> {
+    match byte {
+        QUOTE => Some(ESCAPED_QUOTE),
+        APOS if escapes.apos => Some(ESCAPED_APOS),
+        b'\n' if escapes.control_chars => Some("&#10;"),
+        b'\r' if escapes.control_chars => Some("&#13;"),
+        b'\t' if escapes.control_chars => Some("&#9;"),
+        _ => text_escape(byte),
+    }
+}
 
-    if !need_escape {
+/// Replaces single bytes of `text` with references, given in ascending order of position.
+/// Only ASCII bytes are replaced, so the text between them is left whole.
+fn replace_bytes(
+    text: &str,
+    mut references: impl Iterator<Item = (usize, &'static str)>,
+) -> Cow<'_, str> {
+    let Some((first, reference)) = references.next() else {
         return Cow::Borrowed(text);
+    };
+    let mut result = String::with_capacity(text.len() + text.len() / 8 + reference.len());
+    result.push_str(text.get(..first).unwrap_or_default());
+    result.push_str(reference);
+    let mut last_pos = first + 1;
+    for (i, reference) in references {
+        result.push_str(text.get(last_pos..i).unwrap_or_default());
+        result.push_str(reference);
+        last_pos = i + 1;
     }
+    result.push_str(text.get(last_pos..).unwrap_or_default());
+    Cow::Owned(result)
+}
 
-    let mut i = 0;
-    let mut last_pos = 0;
-    let mut result = String::with_capacity(len * 6);
-
-    let ptr = bytes.as_ptr();
-
-    while i < len {
-        // SAFETY: `ptr` comes from `bytes.as_ptr()` which is valid for reads,
-        // and `i` is bounded by `bytes.len()`, so `ptr.add(i)` is within bounds.
-        let byte = unsafe { *ptr.add(i) };
-        match byte {
-            AMPERSAND | LT | GT => {
-                if last_pos < i {
-                    // SAFETY: The slice from `last_pos` to `i` is valid UTF-8 because
-                    // it's a subslice of the original `text` which is guaranteed to be valid UTF-8.
-                    let slice = unsafe {
-                        from_utf8_unchecked(from_raw_parts(ptr.add(last_pos), i - last_pos))
-                    };
-                    result.push_str(slice);
-                }
-
-                let escaped = match byte {
-                    AMPERSAND => ESCAPED_AMP,
-                    LT => ESCAPED_LT,
-                    _ => ESCAPED_GT,
-                };
-                result.push_str(escaped);
-                last_pos = i + 1;
-            }
-            _ => {}
+/// Text content needs only `&`, `<` and `>` escaped, which `memchr` finds many bytes at a
+/// time.
+fn escape_markup(text: &str) -> Cow<'_, str> {
+    let bytes = text.as_bytes();
+    replace_bytes(
+        text,
+        memchr::memchr3_iter(AMPERSAND, LT, GT, bytes)
+            .filter_map(|i| Some((i, text_escape(*bytes.get(i)?)?))),
+    )
+}
+
+/// Attribute values have up to eight characters to escape, more than `memchr` looks for at
+/// once, so their bytes are checked one by one.
+fn escape_attr_markup(text: &str, escapes: AttrEscapes) -> Cow<'_, str> {
+    replace_bytes(
+        text,
+        text.bytes()
+            .enumerate()
+            .filter_map(|(i, byte)| Some((i, attr_escape(byte, escapes)?))),
+    )
+}
+
+/// Escapes text content. Characters XML 1.0 does not allow are handled per `invalid_chars`;
+/// the error names the first one in `Raise` mode.
+pub fn escape_xml(text: &str, invalid_chars: InvalidChars) -> Result<Cow<'_, str>, String> {
+    Ok(match clean_xml_chars(text, invalid_chars)? {
+        Cow::Borrowed(text) => escape_markup(text),
+        Cow::Owned(text) => Cow::Owned(escape_markup(&text).into_owned()),
+    })
+}
+
+/// Escapes a value for a double-quoted attribute, like [`escape_xml`], along with the
+/// characters `escapes` names.
+pub fn escape_xml_attr(
+    text: &str,
+    escapes: AttrEscapes,
+    invalid_chars: InvalidChars,
+) -> Result<Cow<'_, str>, String> {
+    Ok(match clean_xml_chars(text, invalid_chars)? {
+        Cow::Borrowed(text) => escape_attr_markup(text, escapes),
+        Cow::Owned(text) => Cow::Owned(escape_attr_markup(&text, escapes).into_owned()),
+    })
+}
+
+/// Escapes text content as canonical XML does: `&`, `<`, `>` and carriage returns.
+/// Characters XML 1.0 does not allow are handled per `invalid_chars`, as in [`escape_xml`].
+pub fn escape_canonical_text(text: &str, invalid_chars: InvalidChars) -> Result<String, String> {
+    let text = clean_xml_chars(text, invalid_chars)?;
+    Ok(escape_chars(&text, |ch| match ch {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    }))
+}
+
+/// Escapes an attribute value as canonical XML does: `&`, `<`, `"` and whitespace
+/// characters other than spaces.
+pub fn escape_canonical_attr(text: &str, invalid_chars: InvalidChars) -> Result<String, String> {
+    let text = clean_xml_chars(text, invalid_chars)?;
+    Ok(escape_chars(&text, |ch| match ch {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '"' => Some("&quot;"),
+        '\t' => Some("&#x9;"),
+        '\n' => Some("&#xA;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    }))
+}
+
+fn escape_chars(text: &str, escape: impl Fn(char) -> Option<&'static str>) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match escape(ch) {
+            Some(escaped) => result.push_str(escaped),
+            None => result.push(ch),
         }
-        i += 1;
     }
+    result
+}
 
-    if last_pos < len {
-        // SAFETY: The slice from `last_pos` to `bytes.len()` is valid UTF-8 because
-        // it's a subslice of the original `text` which is guaranteed to be valid UTF-8.
-        let slice =
-            unsafe { from_utf8_unchecked(from_raw_parts(ptr.add(last_pos), len - last_pos)) };
-        result.push_str(slice);
+/// Writes the characters of escaped text or an attribute value that `writable` refuses as
+/// decimal character references, as Python's `xmlcharrefreplace` error handler does.
+pub fn escape_unwritable(text: Cow<'_, str>, writable: impl Fn(char) -> bool) -> Cow<'_, str> {
+    if text.is_ascii() || text.chars().all(&writable) {
+        return text;
+    }
+    let mut result = String::with_capacity(text.len() + 16);
+    for ch in text.chars() {
+        if writable(ch) {
+            result.push(ch);
+        } else {
+            let _ = write!(result, "&#{};", u32::from(ch));
+        }
     }
-
     Cow::Owned(result)
 }
 
-pub fn escape_xml_attr(text: &str) -> Cow<'_, str> {
-    let mut result: Option<String> = None;
-    let mut last_pos = 0;
-
-    for (i, ch) in text.char_indices() {
-        match ch {
-            '&' | '<' | '>' | '"' => {
-                let is_first_escape = result.is_none();
-                let s = result.get_or_insert_with(|| {
-                    let mut output = String::with_capacity(text.len() + 20);
-                    output.push_str(&text[..i]);
-                    output
-                });
-                if !is_first_escape {
-                    s.push_str(&text[last_pos..i]);
-                }
-                let escaped = match ch {
-                    '&' => "&amp;",
-                    '<' => "&lt;",
-                    '>' => "&gt;",
-                    _ => "&quot;",
-                };
-                s.push_str(escaped);
-                last_pos = i + ch.len_utf8();
+/// Wraps text in a CDATA section, splitting it wherever it contains `]]>`. A character
+/// `writable` refuses ends the section and follows it as a character reference, as CDATA
+/// cannot hold references. Characters XML 1.0 does not allow are handled per
+/// `invalid_chars`, as in [`escape_xml`].
+pub fn cdata_section(
+    text: &str,
+    invalid_chars: InvalidChars,
+    writable: impl Fn(char) -> bool,
+) -> Result<String, String> {
+    let text = clean_xml_chars(text, invalid_chars)?.replace("]]>", "]]]]><![CDATA[>");
+    let mut result = String::with_capacity(text.len() + 12);
+    let mut open = false;
+    for ch in text.chars() {
+        if writable(ch) {
+            if !open {
+                result.push_str("<![CDATA[");
+                open = true;
+            }
+            result.push(ch);
+        } else {
+            if open {
+                result.push_str("]]>");
+                open = false;
             }
-            _ => {}
+            let _ = write!(result, "&#{};", u32::from(ch));
         }
     }
+    if open {
+        result.push_str("]]>");
+    } else if result.is_empty() {
+        result.push_str("<![CDATA[]]>");
+    }
+    Ok(result)
+}
 
-    match result {
-        None => Cow::Borrowed(text),
-        Some(mut s) => {
-            if last_pos < text.len() {
-                s.push_str(&text[last_pos..]);
-            }
-            Cow::Owned(s)
+/// Checks the text of a comment, which may not contain `--` or end with `-`. Characters
+/// XML 1.0 does not allow are handled per `invalid_chars`, as in [`escape_xml`].
+pub fn comment_text(text: &str, invalid_chars: InvalidChars) -> Result<Cow<'_, str>, String> {
+    let text = clean_xml_chars(text, invalid_chars)?;
+    if text.contains("--") || text.ends_with('-') {
+        return Err(format!(
+            "comment must not contain '--' or end with '-': {text:?}"
+        ));
+    }
+    Ok(text)
+}
+
+/// Checks the text of a processing instruction, which must start with a target and may not
+/// contain `?>`. Characters XML 1.0 does not allow are handled per `invalid_chars`.
+pub fn pi_text(text: &str, invalid_chars: InvalidChars) -> Result<Cow<'_, str>, String> {
+    let text = clean_xml_chars(text, invalid_chars)?;
+    if text.is_empty() || text.starts_with(char::is_whitespace) || text.contains("?>") {
+        return Err(format!(
+            "processing instruction must start with a target and not contain '?>': {text:?}"
+        ));
+    }
+    Ok(text)
+}
+
+/// Markup written from a reserved key instead of as an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    Comment,
+    ProcessingInstruction,
+}
+
+impl Markup {
+    /// Checks `text` and wraps it in `<!--...-->` or `<?...?>`.
+    pub fn render(self, text: &str, invalid_chars: InvalidChars) -> Result<String, String> {
+        let (open, text, close) = match self {
+            Markup::Comment => ("<!--", comment_text(text, invalid_chars)?, "-->"),
+            Markup::ProcessingInstruction => ("<?", pi_text(text, invalid_chars)?, "?>"),
+        };
+        Ok(format!("{open}{text}{close}"))
+    }
+}
+
+/// The XML 1.0 `Char` production, minus the surrogates a `str` cannot hold.
+fn is_xml_char(ch: char) -> bool {
+    matches!(
+        ch,
+        '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..
+    )
+}
+
+fn clean_xml_chars(text: &str, invalid_chars: InvalidChars) -> Result<Cow<'_, str>, String> {
+    // Only C0 controls and U+FFFE/U+FFFF (lead byte 0xEF) can be invalid.
+    let suspect = text
+        .bytes()
+        .any(|b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r')) || b == 0xEF);
+    if !suspect || text.chars().all(is_xml_char) {
+        return Ok(Cow::Borrowed(text));
+    }
+    match invalid_chars {
+        InvalidChars::Raise => {
+            let ch = text
+                .chars()
+                .find(|&ch| !is_xml_char(ch))
+                .unwrap_or_default();
+            Err(format!("invalid XML character U+{:04X}", u32::from(ch)))
         }
+        InvalidChars::Strip => Ok(Cow::Owned(
+            text.chars().filter(|&ch| is_xml_char(ch)).collect(),
+        )),
+        InvalidChars::Replace => Ok(Cow::Owned(
+            text.chars()
+                .map(|ch| {
+                    if is_xml_char(ch) {
+                        ch
+                    } else {
+                        char::REPLACEMENT_CHARACTER
+                    }
+                })
+                .collect(),
+        )),
     }
 }
 
@@ -109,24 +290,159 @@ pub fn escape_xml_attr(text: &str) -> Cow<'_, str> {
 mod tests {
     use super::*;
 
+    const CONTROL: AttrEscapes = AttrEscapes {
+        control_chars: true,
+        apos: false,
+    };
+
     #[test]
     fn test_escape_xml() {
         assert_eq!(
             "Start &amp; then &lt; some &gt; text &amp; more &lt; text &gt; end",
-            escape_xml("Start & then < some > text & more < text > end")
+            escape_xml(
+                "Start & then < some > text & more < text > end",
+                InvalidChars::Raise
+            )
+            .unwrap_or_default()
         );
     }
 
     #[test]
     fn test_escape_xml_no_escape_needed() {
-        assert_eq!("Hello World", escape_xml("Hello World"));
+        assert_eq!(
+            "Hello World",
+            escape_xml("Hello World", InvalidChars::Raise).unwrap_or_default()
+        );
     }
 
     #[test]
     fn test_escape_xml_attr() {
         assert_eq!(
             "value with &quot;quotes&quot; and &amp;",
-            escape_xml_attr("value with \"quotes\" and &")
+            escape_xml_attr("value with \"quotes\" and &", CONTROL, InvalidChars::Raise)
+                .unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn test_escape_xml_attr_control_chars() {
+        let escape = |escapes| {
+            escape_xml_attr("a\nb\rc\td", escapes, InvalidChars::Raise).unwrap_or_default()
+        };
+        assert_eq!("a&#10;b&#13;c&#9;d", escape(CONTROL));
+        assert_eq!("a\nb\rc\td", escape(AttrEscapes::default()));
+    }
+
+    #[test]
+    fn test_escape_xml_attr_apos() {
+        let escapes = AttrEscapes {
+            apos: true,
+            ..AttrEscapes::default()
+        };
+        assert_eq!(
+            "O&apos;Neil &amp; &quot;co&quot; &lt;x&gt;",
+            escape_xml_attr("O'Neil & \"co\" <x>", escapes, InvalidChars::Raise)
+                .unwrap_or_default()
+        );
+        assert_eq!(
+            Ok(Cow::Borrowed("O'Neil")),
+            escape_xml_attr("O'Neil", CONTROL, InvalidChars::Raise)
+        );
+    }
+
+    #[test]
+    fn test_escape_at_the_edges() {
+        let escape = |text| escape_xml(text, InvalidChars::Raise).unwrap_or_default();
+        assert_eq!("&lt;", escape("<"));
+        assert_eq!("&amp;é&gt;", escape("&é>"));
+        assert_eq!("ü &lt;&lt; ö", escape("ü << ö"));
+        assert_eq!("", escape(""));
+        let long = format!("{}&{}", "x".repeat(100), "y".repeat(100));
+        assert_eq!(long.replace('&', "&amp;"), escape(&long));
+    }
+
+    #[test]
+    fn test_cdata_section() {
+        let any = |_| true;
+        assert_eq!(
+            Ok("<![CDATA[a < b & c]]>".to_owned()),
+            cdata_section("a < b & c", InvalidChars::Raise, any)
+        );
+        assert_eq!(
+            Ok("<![CDATA[x]]]]><![CDATA[>y]]>".to_owned()),
+            cdata_section("x]]>y", InvalidChars::Raise, any)
+        );
+        assert_eq!(
+            Ok("<![CDATA[]]>".to_owned()),
+            cdata_section("", InvalidChars::Raise, any)
+        );
+        let ascii = |ch: char| ch.is_ascii();
+        assert_eq!(
+            Ok("<![CDATA[a <]]>&#8364;&#233;<![CDATA[ b]]>".to_owned()),
+            cdata_section("a <\u{20AC}\u{E9} b", InvalidChars::Raise, ascii)
+        );
+        assert_eq!(
+            Ok("&#233;".to_owned()),
+            cdata_section("\u{E9}", InvalidChars::Raise, ascii)
+        );
+    }
+
+    #[test]
+    fn test_comment_text() {
+        assert_eq!(
+            Ok(Cow::Borrowed(" a - b ")),
+            comment_text(" a - b ", InvalidChars::Raise)
+        );
+        assert!(comment_text("a -- b", InvalidChars::Raise).is_err());
+        assert!(comment_text("a-", InvalidChars::Raise).is_err());
+    }
+
+    #[test]
+    fn test_pi_text() {
+        assert_eq!(
+            Ok("<?target data?>".to_owned()),
+            Markup::ProcessingInstruction.render("target data", InvalidChars::Raise)
+        );
+        assert!(pi_text("", InvalidChars::Raise).is_err());
+        assert!(pi_text(" target", InvalidChars::Raise).is_err());
+        assert!(pi_text("a ?> b", InvalidChars::Raise).is_err());
+    }
+
+    #[test]
+    fn test_escape_xml_invalid_chars() {
+        let text = "a\u{0}<b\u{B}\u{FFFE}";
+        assert_eq!(
+            Err("invalid XML character U+0000".to_owned()),
+            escape_xml(text, InvalidChars::Raise)
+        );
+        assert_eq!(
+            "a&lt;b",
+            escape_xml(text, InvalidChars::Strip).unwrap_or_default()
+        );
+        assert_eq!(
+            "a\u{FFFD}&lt;b\u{FFFD}\u{FFFD}",
+            escape_xml(text, InvalidChars::Replace).unwrap_or_default()
+        );
+        assert_eq!(
+            Ok(Cow::Borrowed("\u{FFFD}\u{EFFF}\t")),
+            escape_xml("\u{FFFD}\u{EFFF}\t", InvalidChars::Raise)
+        );
+    }
+
+    #[test]
+    fn test_escape_unwritable() {
+        let ascii = |ch: char| ch.is_ascii();
+        assert_eq!(
+            "caf&#233; &amp; &#128512;",
+            escape_unwritable(Cow::Borrowed("caf\u{E9} &amp; \u{1F600}"), ascii)
+        );
+        assert!(matches!(
+            escape_unwritable(Cow::Borrowed("plain"), ascii),
+            Cow::Borrowed("plain")
+        ));
+        assert_eq!(
+            "caf\u{E9} &#128512;",
+            escape_unwritable(Cow::Borrowed("caf\u{E9} \u{1F600}"), |ch| ch < '\u{100}')
         );
     }
 }
@@ -0,0 +1,971 @@
+use crate::config::{ParseConfig, ResolveEntities, Strip};
+use crate::dtd::EntityTable;
+use crate::names::is_valid_element_name;
+use crate::path_pattern::PathPattern;
+use crate::reader::LineCounter;
+use crate::whitespace::{is_blank, xml_space, TextStripper};
+use crate::xmltodict_core::{Error, Limit, Position, SyntaxError};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
+
+/// A reader event with names checked and entities expanded. Events own their data, so a
+/// batch of them can be tokenized without the GIL and handed to the Python builder later.
+/// Text is copied out of the reader's buffer once, as it is unescaped, since the buffer is
+/// reused for the markup read next; the builders take the string over from there, joining
+/// only the text of elements that have several text nodes.
+#[derive(Debug, PartialEq, Eq)]
+pub enum XmlEvent {
+    /// An element start tag; attributes are empty unless `xml_attribs` is set.
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End(String),
+    /// Character data, from text or a CDATA section, stripped as `strip` says.
+    Text(String),
+    /// With `preserve_mixed_content`, blank text between the children of an element, which
+    /// is not part of its text. Otherwise whitespace ending the text before a child, held
+    /// back until more text of the element followed it, which continues that text.
+    Whitespace(String),
+    /// Content of a `<!DOCTYPE ...>` declaration.
+    DocType(String),
+    /// Only reported with `process_comments`.
+    Comment(String),
+    /// Content of a `<?target data?>` processing instruction, only reported with `process_pis`.
+    ProcessingInstruction(String),
+}
+
+/// A mistake `recover` repaired instead of raising, such as an unknown entity kept as text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Recovery {
+    pub message: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.message, self.line)
+    }
+}
+
+/// Byte offsets of an element in the document, from the `<` of its start tag to just past
+/// the `>` of its end tag.
+pub type Span = (usize, usize);
+
+/// Spans of elements, recorded with `with_spans`.
+#[derive(Default)]
+struct Spans {
+    /// Where each open element starts.
+    starts: Vec<usize>,
+    /// Spans of closed elements, in the order their end events are returned.
+    closed: Vec<Span>,
+}
+
+impl Spans {
+    fn open(&mut self, start: usize) {
+        self.starts.push(start);
+    }
+
+    /// Closes the innermost open element at `end`. `quick_xml` reads `<a/>` as a start tag
+    /// without the `/`, so the start of one whose `empty_tag` it was is a byte earlier.
+    fn close(&mut self, end: usize, empty_tag: bool) {
+        if let Some(start) = self.starts.pop() {
+            self.closed
+                .push((start.saturating_sub(usize::from(empty_tag)), end));
+        }
+    }
+
+    /// A void element is closed with its start tag, read before its end tag may tell that
+    /// it was written `<br/>`.
+    fn close_void(&mut self, start: usize, end: usize) {
+        self.closed.push((start, end));
+    }
+
+    fn void_was_empty_tag(&mut self) {
+        if let Some((start, _)) = self.closed.last_mut() {
+            *start = start.saturating_sub(1);
+        }
+    }
+}
+
+/// Turns `quick_xml` events into [`XmlEvent`]s, expanding entities declared in the DTD.
+/// Syntax errors carry the line and column where they were found.
+pub struct EventReader<'c, R> {
+    config: Cow<'c, ParseConfig>,
+    reader: Reader<LineCounter<R>>,
+    buf: Vec<u8>,
+    entities: EntityTable,
+    depth: usize,
+    elements: usize,
+    seen_root: bool,
+    /// Length of the text read so far in each open element, by depth, for `max_text_length`.
+    text_lengths: Vec<usize>,
+    /// Whether another root element or text outside the root is an error.
+    one_root: bool,
+    line: usize,
+    /// Names of the open elements, kept with `recover` to match end tags against.
+    open: Vec<String>,
+    /// End tags `recover` adds to close elements left open, returned before reading on.
+    pending: VecDeque<XmlEvent>,
+    /// Holds text until it is known where it stands in its element.
+    stripper: TextStripper,
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    recovered: Vec<Recovery>,
+    spans: Spans,
+    raw_paths: Vec<PathPattern>,
+    /// Names of the elements leading to the last one opened, kept with `raw_paths`.
+    names: Vec<String>,
+}
+
+impl<'c, R: BufRead> EventReader<'c, R> {
+    pub fn new(reader: R, config: &'c ParseConfig) -> Self {
+        Self::with_config(reader, Cow::Borrowed(config))
+    }
+
+    /// A reader keeping its own copy of the configuration, for iterators that outlive the
+    /// call creating them.
+    #[cfg(feature = "python")]
+    pub fn owning(reader: R, config: ParseConfig) -> EventReader<'static, R> {
+        EventReader::with_config(reader, Cow::Owned(config))
+    }
+
+    fn with_config(reader: R, config: Cow<'c, ParseConfig>) -> Self {
+        let mut reader = Reader::from_reader(LineCounter::new(reader));
+        reader
+            .check_end_names(!config.recover && config.void_elements.is_empty())
+            .check_comments(true)
+            .expand_empty_elements(true);
+        Self {
+            reader,
+            buf: Vec::with_capacity(128),
+            entities: EntityTable::default(),
+            depth: 0,
+            elements: 0,
+            seen_root: false,
+            text_lengths: Vec::new(),
+            one_root: false,
+            line: 1,
+            open: Vec::new(),
+            pending: VecDeque::new(),
+            stripper: TextStripper::default(),
+            recovered: Vec::new(),
+            spans: Spans::default(),
+            raw_paths: config
+                .raw_paths
+                .iter()
+                .map(|path| PathPattern::new(path))
+                .collect(),
+            names: Vec::new(),
+            config,
+        }
+    }
+
+    /// Reads the next event, or `None` at the end of the input.
+    pub fn next_event(&mut self) -> Result<Option<XmlEvent>, Error> {
+        loop {
+            if let Some((event, line)) = self.stripper.next_ready() {
+                self.line = line;
+                return Ok(Some(event));
+            }
+            if let Some(event) = self.pending.pop_front() {
+                if let XmlEvent::End(_) = event {
+                    self.depth = self.depth.saturating_sub(1);
+                }
+                match self.strip(event, None) {
+                    Some(event) => return Ok(Some(event)),
+                    None => continue,
+                }
+            }
+            if self.config.strip != Strip::None {
+                // Whitespace the stripper would drop is skipped as the markup is read.
+                let drops_blank = self.stripper.drops_blank(&self.config);
+                self.reader.trim_text(drops_blank).trim_text_end(false);
+            }
+            self.buf.clear();
+            self.reader.get_mut().mark();
+            if !self.raw_paths.is_empty() {
+                self.reader.get_mut().record();
+            }
+            let before = self.reader.buffer_position();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event,
+                Err(err) => return Err(self.error_here(err.into())),
+            };
+            let end = self.reader.buffer_position();
+            let start = end.saturating_sub(raw_len(&event));
+            let position = self.reader.get_ref().position(start);
+            // The end event of `<a/>` is made up without reading anything.
+            let empty_tag = matches!(event, Event::End(_)) && end == before;
+            if let Event::Eof = event {
+                if self.config.recover && !self.open.is_empty() {
+                    self.close_all(position, end);
+                    continue;
+                }
+                return self.finish(position);
+            }
+            let mut space = None;
+            if let Event::Start(start) = &event {
+                if self.config.honor_xml_space {
+                    space = xml_space(start);
+                }
+            }
+            let mut kept = Vec::new();
+            let mut event = convert(&event, &self.config, &mut self.entities, &mut kept)
+                .map_err(|err| locate(err, position))?;
+            self.recovered
+                .extend(kept.into_iter().map(|message| Recovery {
+                    message,
+                    line: position.line,
+                }));
+            // The reader stands where the event begins, which `raw_len` only estimates.
+            let begins = self.reader.get_ref().position(before);
+            self.check_content(event.as_ref())
+                .map_err(|err| locate(err, begins))?;
+            match &mut event {
+                Some(XmlEvent::Start { name, attributes }) => {
+                    let void = self.config.void_elements.contains(name);
+                    let raw = !void && !self.raw_paths.is_empty() && self.on_raw_path(name);
+                    self.depth += 1;
+                    self.elements += 1;
+                    self.seen_root = true;
+                    self.open_span(start, end, void);
+                    if void {
+                        self.pending.push_back(XmlEvent::End(name.clone()));
+                    } else if raw {
+                        // The attributes are part of the markup that stands for the element.
+                        attributes.clear();
+                        self.take_raw(name)?;
+                    } else if self.matches_end_tags() {
+                        self.open.push(name.clone());
+                    }
+                    self.check_element_limits()
+                        .map_err(|err| locate(err.into(), position))?;
+                }
+                Some(XmlEvent::End(name)) if self.matches_end_tags() => {
+                    self.line = position.line;
+                    self.close(name, position, end, empty_tag)?;
+                    continue;
+                }
+                Some(XmlEvent::End(_)) => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.config.with_spans {
+                        self.spans.close(end, empty_tag);
+                    }
+                }
+                Some(_) | None => {}
+            }
+            if let Some(event) = event {
+                self.line = position.line;
+                if let Some(event) = self.strip(event, space) {
+                    return Ok(Some(event));
+                }
+            }
+        }
+    }
+
+    /// Closes the elements left open at the end of the input, for `recover`.
+    fn close_all(&mut self, position: Position, end: usize) {
+        self.line = position.line;
+        for name in self.open.drain(..).rev() {
+            if self.config.with_spans {
+                self.spans.close(end, false);
+            }
+            self.recovered.push(Recovery {
+                message: format!("<{name}> closed at the end of the input"),
+                line: position.line,
+            });
+            self.pending.push_back(XmlEvent::End(name));
+        }
+    }
+
+    /// Notes where an element opened between `start` and `end` begins; a void element ends
+    /// there too.
+    fn open_span(&mut self, start: usize, end: usize, void: bool) {
+        if !self.config.with_spans {
+            return;
+        }
+        if void {
+            self.spans.close_void(start, end);
+        } else {
+            self.spans.open(start);
+        }
+    }
+
+    /// Passes an event read to the stripper, which hands it back unless it holds it.
+    fn strip(&mut self, event: XmlEvent, space: Option<bool>) -> Option<XmlEvent> {
+        if self.config.strip == Strip::None {
+            return Some(event);
+        }
+        self.stripper.push(&self.config, event, self.line, space)
+    }
+
+    /// Starts over for the next of several concatenated documents: entities declared in the
+    /// DTD of the last one no longer apply, and its elements stop counting towards limits.
+    #[cfg(feature = "python")]
+    pub fn next_document(&mut self) {
+        self.entities = EntityTable::default();
+        self.elements = 0;
+        self.text_lengths.clear();
+    }
+
+    /// Refuses a second root element and text outside the root, as expat does, instead of
+    /// reading them as the next of several concatenated documents.
+    #[cfg(feature = "python")]
+    pub fn require_one_root(&mut self) {
+        self.one_root = true;
+    }
+
+    /// Checks an event read at the current depth against `max_text_length` and, with
+    /// `one_root`, against what may stand outside the root element.
+    fn check_content(&mut self, event: Option<&XmlEvent>) -> Result<(), Error> {
+        if self.one_root && self.depth == 0 {
+            self.check_outside_root(event)?;
+        }
+        self.count_text(event)?;
+        Ok(())
+    }
+
+    /// Adds text to the length of the element it is in, so that the limit holds for all of
+    /// its text, however CDATA sections, comments or children split it.
+    fn count_text(&mut self, event: Option<&XmlEvent>) -> Result<(), SyntaxError> {
+        let Some(max) = self.config.limits.max_text_length else {
+            return Ok(());
+        };
+        match event {
+            // The element opened has no text yet, whatever an earlier sibling had.
+            Some(XmlEvent::Start { .. }) => self.text_lengths.truncate(self.depth + 1),
+            Some(XmlEvent::Text(text)) => {
+                self.text_lengths.resize(self.depth + 1, 0);
+                if let Some(length) = self.text_lengths.last_mut() {
+                    *length = length.saturating_add(text.len());
+                    if *length > max {
+                        return Err(SyntaxError::limit_exceeded(
+                            Limit::Size,
+                            format!("text length limit exceeded (max_text_length={max})"),
+                        ));
+                    }
+                }
+            }
+            Some(_) | None => {}
+        }
+        Ok(())
+    }
+
+    fn check_outside_root(&self, event: Option<&XmlEvent>) -> Result<(), Error> {
+        let (code, message) = match event {
+            Some(XmlEvent::Start { .. }) if self.seen_root => (
+                SyntaxError::JUNK_AFTER_DOC_ELEMENT,
+                "junk after document element",
+            ),
+            Some(XmlEvent::Text(text)) if !is_blank(text) => {
+                if self.seen_root {
+                    (
+                        SyntaxError::JUNK_AFTER_DOC_ELEMENT,
+                        "junk after document element",
+                    )
+                } else {
+                    (SyntaxError::SYNTAX, "syntax error")
+                }
+            }
+            Some(_) | None => return Ok(()),
+        };
+        Err(SyntaxError::new(code, message).into())
+    }
+
+    /// Whether an element opened inside the current one is on one of the `raw_paths`.
+    fn on_raw_path(&mut self, name: &str) -> bool {
+        self.names.truncate(self.depth);
+        self.names.push(name.to_owned());
+        let path: Vec<&str> = self.names.iter().map(String::as_str).collect();
+        self.raw_paths.iter().any(|pattern| pattern.matches(&path))
+    }
+
+    /// Locates an error at the position the reader has reached.
+    fn error_here(&self, err: Error) -> Error {
+        let position = self
+            .reader
+            .get_ref()
+            .position(self.reader.buffer_position());
+        locate(err, position)
+    }
+
+    /// Queues the markup of an element on one of the `raw_paths` as its text, followed by its
+    /// end tag.
+    fn take_raw(&mut self, name: &str) -> Result<(), Error> {
+        let markup = self.read_raw(name)?;
+        if self.config.with_spans {
+            self.spans.close(self.reader.buffer_position(), false);
+        }
+        self.pending.push_back(XmlEvent::Text(markup));
+        self.pending.push_back(XmlEvent::End(name.to_owned()));
+        Ok(())
+    }
+
+    /// Reads past the end tag of an element on one of the `raw_paths`, whose start tag has
+    /// just been read, and returns its markup as written in the document. The markup inside
+    /// is checked as usual but not converted; with `recover`, input ending inside the
+    /// element ends its markup.
+    fn read_raw(&mut self, name: &str) -> Result<String, Error> {
+        let mut depth = 1_usize;
+        while depth > 0 {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event,
+                Err(err) => return Err(self.error_here(err.into())),
+            };
+            let void = |inner: &[u8]| {
+                std::str::from_utf8(inner)
+                    .is_ok_and(|inner| self.config.void_elements.contains(inner))
+            };
+            match event {
+                Event::Start(e) if !void(e.name().into_inner()) => depth += 1,
+                Event::End(e) if !void(e.name().into_inner()) => depth -= 1,
+                Event::Eof => {
+                    let position = self
+                        .reader
+                        .get_ref()
+                        .position(self.reader.buffer_position());
+                    if !self.config.recover {
+                        return Err(SyntaxError::new(
+                            SyntaxError::NO_ELEMENTS,
+                            "unclosed element(s) found",
+                        )
+                        .at(position)
+                        .into());
+                    }
+                    self.recovered.push(Recovery {
+                        message: format!("<{name}> closed at the end of the input"),
+                        line: position.line,
+                    });
+                    break;
+                }
+                Event::Start(_)
+                | Event::End(_)
+                | Event::Empty(_)
+                | Event::Text(_)
+                | Event::CData(_)
+                | Event::Comment(_)
+                | Event::Decl(_)
+                | Event::PI(_)
+                | Event::DocType(_) => {}
+            }
+        }
+        let recorded = self.reader.get_mut().take_recorded();
+        // Blank text trimmed before the start tag was read with it.
+        let start = memchr::memchr(b'<', &recorded).unwrap_or(0);
+        Ok(String::from_utf8(
+            recorded.get(start..).unwrap_or_default().to_vec(),
+        )?)
+    }
+
+    /// Whether end tags are matched here rather than by `quick_xml`, which knows nothing of
+    /// void elements and raises on mismatched end tags.
+    fn matches_end_tags(&self) -> bool {
+        self.config.recover || !self.config.void_elements.is_empty()
+    }
+
+    /// Matches an end tag against the open elements. Void elements were closed with their
+    /// start tag, so their end tags are dropped. With `recover`, elements opened inside the
+    /// one an end tag names are closed before it, and an end tag naming no open element is
+    /// dropped; otherwise the end tag must close the innermost element. `end` is where the
+    /// end tag ends, and `empty_tag` whether it was made up for `<name/>`.
+    fn close(
+        &mut self,
+        name: &str,
+        position: Position,
+        end: usize,
+        empty_tag: bool,
+    ) -> Result<(), Error> {
+        if self.config.void_elements.contains(name) {
+            if self.config.with_spans && empty_tag {
+                self.spans.void_was_empty_tag();
+            }
+            return Ok(());
+        }
+        let index = self.open.iter().rposition(|open| open == name);
+        if !self.config.recover && index.is_none_or(|index| index + 1 != self.open.len()) {
+            let mismatch = quick_xml::Error::EndEventMismatch {
+                expected: self.open.last().cloned().unwrap_or_default(),
+                found: name.to_owned(),
+            };
+            return Err(locate(
+                SyntaxError::from_quick_xml(&mismatch).into(),
+                position,
+            ));
+        }
+        let line = position.line;
+        let Some(index) = index else {
+            self.recovered.push(Recovery {
+                message: format!("</{name}> without a matching start tag ignored"),
+                line,
+            });
+            return Ok(());
+        };
+        let closed = self.open.split_off(index);
+        for inner in closed.into_iter().skip(1).rev() {
+            self.recovered.push(Recovery {
+                message: format!("<{inner}> closed by </{name}>"),
+                line,
+            });
+            if self.config.with_spans {
+                self.spans.close(end, false);
+            }
+            self.pending.push_back(XmlEvent::End(inner));
+        }
+        if self.config.with_spans {
+            self.spans.close(end, empty_tag);
+        }
+        self.pending.push_back(XmlEvent::End(name.to_owned()));
+        Ok(())
+    }
+
+    /// Reads up to `max` events, each with the line it starts on; none once the document
+    /// has ended.
+    #[cfg(feature = "python")]
+    pub fn next_batch(&mut self, max: usize) -> Result<Vec<(XmlEvent, usize)>, Error> {
+        let mut events = Vec::new();
+        while events.len() < max {
+            let Some(event) = self.next_event()? else {
+                break;
+            };
+            events.push((event, self.line));
+        }
+        Ok(events)
+    }
+
+    /// Exchanges the buffer markup is read through with `buf`, for the buffer of one
+    /// document to be reused by the next.
+    #[cfg(feature = "python")]
+    pub fn swap_buf(&mut self, buf: &mut Vec<u8>) {
+        std::mem::swap(&mut self.buf, buf);
+    }
+
+    /// Takes the mistakes `recover` has repaired so far.
+    #[cfg(feature = "python")]
+    pub fn take_recovered(&mut self) -> Vec<Recovery> {
+        std::mem::take(&mut self.recovered)
+    }
+
+    /// Takes the spans of the elements closed so far, recorded with `with_spans`.
+    #[cfg(feature = "python")]
+    pub fn take_spans(&mut self) -> Vec<Span> {
+        std::mem::take(&mut self.spans.closed)
+    }
+
+    /// The line the last returned event starts on.
+    #[cfg(feature = "python")]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    fn check_element_limits(&self) -> Result<(), SyntaxError> {
+        let limits = &self.config.limits;
+        if let Some(max) = limits.max_depth.filter(|&max| self.depth > max) {
+            return Err(SyntaxError::limit_exceeded(
+                Limit::Depth,
+                format!("element nesting depth limit exceeded (max_depth={max})"),
+            ));
+        }
+        if let Some(max) = limits.max_total_nodes.filter(|&max| self.elements > max) {
+            return Err(SyntaxError::limit_exceeded(
+                Limit::Size,
+                format!("element count limit exceeded (max_total_nodes={max})"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rejects input that ends before the root element is closed, as expat does.
+    fn finish(&self, position: Position) -> Result<Option<XmlEvent>, Error> {
+        let message = match (self.seen_root, self.depth) {
+            (true, 0) => return Ok(None),
+            (true, _) => "unclosed element(s) found",
+            (false, _) => "no element found",
+        };
+        Err(SyntaxError::new(SyntaxError::NO_ELEMENTS, message)
+            .at(position)
+            .into())
+    }
+}
+
+/// The events of a document with the line each starts on, the mistakes `recover` repaired,
+/// and with `with_spans` the spans of its elements in the order of their end events.
+#[cfg(feature = "python")]
+pub type Document = (Vec<(XmlEvent, usize)>, Vec<Recovery>, Vec<Span>);
+
+/// Tokenizes a whole document up front. This is the GIL-free half of the Python parser.
+#[cfg(feature = "python")]
+pub fn collect_document<R: BufRead>(reader: R, config: &ParseConfig) -> Result<Document, Error> {
+    collect_document_into(reader, config, &mut Vec::new())
+}
+
+/// Like [`collect_document`], reading markup through `buf`, which keeps its capacity for the
+/// next document.
+#[cfg(feature = "python")]
+pub fn collect_document_into<R: BufRead>(
+    reader: R,
+    config: &ParseConfig,
+    buf: &mut Vec<u8>,
+) -> Result<Document, Error> {
+    let mut reader = EventReader::new(reader, config);
+    reader.swap_buf(buf);
+    let collected = reader.next_batch(usize::MAX);
+    reader.swap_buf(buf);
+    collected.map(|events| (events, reader.take_recovered(), reader.take_spans()))
+}
+
+/// The events of a document alone, for tests.
+#[cfg(all(test, feature = "python"))]
+pub fn collect_events<R: BufRead>(
+    reader: R,
+    config: &ParseConfig,
+) -> Result<Vec<(XmlEvent, usize)>, Error> {
+    collect_document(reader, config).map(|(events, _, _)| events)
+}
+
+/// Converts one event, or returns `None` for events that are not reported. With `recover`,
+/// references kept as text are described in `kept`.
+fn convert(
+    event: &Event,
+    config: &ParseConfig,
+    entities: &mut EntityTable,
+    kept: &mut Vec<String>,
+) -> Result<Option<XmlEvent>, Error> {
+    let event = match event {
+        Event::Start(e) => start_event(e, config, entities, kept)?,
+        Event::End(e) => XmlEvent::End(element_name(e.name().into_inner())?),
+        Event::Text(e) if reads_leniently(config) => XmlEvent::Text(entities.unescape_lenient(
+            std::str::from_utf8(e)?,
+            config.disable_entities,
+            config,
+            kept,
+        )?),
+        Event::Text(e) => XmlEvent::Text(
+            entities
+                .unescape_text(e, config.disable_entities)?
+                .into_owned(),
+        ),
+        Event::CData(e) => XmlEvent::Text(std::str::from_utf8(e)?.to_owned()),
+        Event::DocType(e) => {
+            let doctype = std::str::from_utf8(e)?;
+            *entities =
+                EntityTable::for_doctype(doctype, config).map_err(Error::EntitiesForbidden)?;
+            XmlEvent::DocType(doctype.to_owned())
+        }
+        Event::Comment(e) if config.process_comments => {
+            XmlEvent::Comment(std::str::from_utf8(e)?.to_owned())
+        }
+        Event::PI(e) if config.process_pis => {
+            XmlEvent::ProcessingInstruction(std::str::from_utf8(e)?.to_owned())
+        }
+        Event::Empty(_) | Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::Eof => {
+            return Ok(None)
+        }
+    };
+    Ok(Some(event))
+}
+
+/// Whether text and attribute values go through `unescape_lenient`, which is slower than
+/// quick-xml's own unescaping and only needed when some references are let through.
+fn reads_leniently(config: &ParseConfig) -> bool {
+    config.recover || config.resolve_entities != ResolveEntities::Strict
+}
+
+/// Approximate length of the markup an event was read from, used to locate errors at its start.
+fn raw_len(event: &Event) -> usize {
+    match event {
+        Event::Start(e) => e.len() + "<>".len(),
+        Event::End(e) => e.len() + "</>".len(),
+        Event::Empty(e) => e.len() + "</>".len(),
+        Event::Text(e) => e.len(),
+        Event::CData(e) => e.len() + "<![CDATA[]]>".len(),
+        Event::Comment(e) => e.len() + "<!---->".len(),
+        Event::Decl(e) => e.len() + "<??>".len(),
+        Event::PI(e) => e.len() + "<??>".len(),
+        Event::DocType(e) => e.len() + "<!DOCTYPE >".len(),
+        Event::Eof => 0,
+    }
+}
+
+fn locate(err: Error, position: Position) -> Error {
+    match err {
+        Error::Syntax(err) => Error::Syntax(err.at(position)),
+        Error::EntitiesForbidden(_) | Error::Io(_) | Error::Value(_) | Error::Json(_) => err,
+    }
+}
+
+fn start_event(
+    e: &BytesStart,
+    config: &ParseConfig,
+    entities: &EntityTable,
+    kept: &mut Vec<String>,
+) -> Result<XmlEvent, Error> {
+    let name = element_name(e.name().into_inner())?;
+    let mut attributes = Vec::new();
+    // Malformed attributes are rejected even when they are not reported.
+    for (count, attr) in e.attributes().enumerate() {
+        if let Some(max) = config.limits.max_attributes.filter(|&max| count >= max) {
+            return Err(SyntaxError::limit_exceeded(
+                Limit::Size,
+                format!("attribute count limit exceeded (max_attributes={max})"),
+            )
+            .into());
+        }
+        let attr = attr.map_err(|err| SyntaxError::from_quick_xml(&err.into()))?;
+        if config.xml_attribs {
+            let value = if reads_leniently(config) {
+                entities.unescape_lenient(std::str::from_utf8(&attr.value)?, false, config, kept)?
+            } else {
+                entities.unescape_attribute(&attr)?.into_owned()
+            };
+            let key = String::from_utf8(attr.key.into_inner().to_vec())?;
+            attributes.push((key, value));
+        }
+    }
+    Ok(XmlEvent::Start { name, attributes })
+}
+
+fn element_name(raw: &[u8]) -> Result<String, Error> {
+    let name = std::str::from_utf8(raw)?;
+    if is_valid_element_name(name) {
+        Ok(name.to_owned())
+    } else {
+        Err(SyntaxError::new(
+            SyntaxError::INVALID_TOKEN,
+            "not well-formed (invalid element name)",
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(xml: &str, config: &ParseConfig) -> Result<Vec<XmlEvent>, Error> {
+        let mut reader = EventReader::new(xml.as_bytes(), config);
+        std::iter::from_fn(|| reader.next_event().transpose()).collect()
+    }
+
+    #[test]
+    fn declared_entities_are_expanded() {
+        let xml = r#"<!DOCTYPE r [<!ENTITY e "x">]><r a="&e;">&e;<![CDATA[&e;]]></r>"#;
+        let config = ParseConfig::builder().disable_entities(false).build();
+        let expected = vec![
+            XmlEvent::DocType(r#"r [<!ENTITY e "x">]"#.to_owned()),
+            XmlEvent::Start {
+                name: "r".to_owned(),
+                attributes: vec![("a".to_owned(), "x".to_owned())],
+            },
+            XmlEvent::Text("x".to_owned()),
+            XmlEvent::Text("&e;".to_owned()),
+            XmlEvent::End("r".to_owned()),
+        ];
+        assert_eq!(expected, events(xml, &config).unwrap_or_default());
+    }
+
+    #[test]
+    fn whitespace_before_a_child_waits_for_more_text() {
+        let start = |name: &str| XmlEvent::Start {
+            name: name.to_owned(),
+            attributes: Vec::new(),
+        };
+        let text = |text: &str| XmlEvent::Text(text.to_owned());
+        let expected = vec![
+            start("a"),
+            text("x"),
+            start("b"),
+            XmlEvent::End("b".to_owned()),
+            XmlEvent::Whitespace(" ".to_owned()),
+            text("  y"),
+            start("c"),
+            XmlEvent::End("c".to_owned()),
+            XmlEvent::End("a".to_owned()),
+        ];
+        let xml = "<a> x <b/>  y <c/> </a>";
+        assert_eq!(
+            expected,
+            events(xml, &ParseConfig::default()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn comments_are_reported_only_when_processed() {
+        let xml = "<r><!-- c --></r>";
+        let plain = events(xml, &ParseConfig::default()).unwrap_or_default();
+        assert!(!plain.iter().any(|e| matches!(e, XmlEvent::Comment(_))));
+        let config = ParseConfig::builder().process_comments(true).build();
+        let processed = events(xml, &config).unwrap_or_default();
+        assert!(processed.contains(&XmlEvent::Comment(" c ".to_owned())));
+    }
+
+    #[test]
+    fn malformed_attributes_are_rejected_without_xml_attribs() {
+        let config = ParseConfig::builder().xml_attribs(false).build();
+        assert!(matches!(
+            events(r#"<r a="1" a="2"/>"#, &config),
+            Err(Error::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn limits_stop_parsing() {
+        let config = ParseConfig::builder().max_depth(2).build();
+        assert!(matches!(
+            events("<a><b><c/></b></a>", &config),
+            Err(Error::Syntax(SyntaxError {
+                limit: Some(Limit::Depth),
+                ..
+            }))
+        ));
+        let config = ParseConfig::builder().max_text_length(3).build();
+        assert!(events("<a>abc</a>", &config).is_ok());
+        assert!(matches!(
+            events("<a>abcd</a>", &config),
+            Err(Error::Syntax(SyntaxError {
+                limit: Some(Limit::Size),
+                ..
+            }))
+        ));
+        assert!(events("<a><b>abc</b><b>abc</b></a>", &config).is_ok());
+        for xml in [
+            "<a>ab<![CDATA[cd]]></a>",
+            "<a>ab<!--c-->cd</a>",
+            "<a>ab<b/>cd</a>",
+        ] {
+            assert!(events(xml, &config).is_err(), "{xml}");
+        }
+    }
+
+    #[test]
+    fn recover_closes_mismatched_and_unclosed_elements() -> Result<(), Error> {
+        let config = ParseConfig::builder().recover(true).build();
+        let mut reader = EventReader::new(&b"<a><b><c>x</b></d>\n<e>"[..], &config);
+        let mut names = Vec::new();
+        while let Some(event) = reader.next_event()? {
+            match event {
+                XmlEvent::Start { name, .. } => names.push(format!("<{name}>")),
+                XmlEvent::End(name) => names.push(format!("</{name}>")),
+                XmlEvent::Text(_)
+                | XmlEvent::Whitespace(_)
+                | XmlEvent::DocType(_)
+                | XmlEvent::Comment(_)
+                | XmlEvent::ProcessingInstruction(_) => {}
+            }
+        }
+        assert_eq!(
+            vec!["<a>", "<b>", "<c>", "</c>", "</b>", "<e>", "</e>", "</a>"],
+            names
+        );
+        let recovered: Vec<String> = reader.recovered.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "<c> closed by </b> (line 1)",
+                "</d> without a matching start tag ignored (line 1)",
+                "<e> closed at the end of the input (line 2)",
+                "<a> closed at the end of the input (line 2)",
+            ],
+            recovered
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn recover_keeps_unknown_references_as_text() -> Result<(), Error> {
+        let config = ParseConfig::builder().recover(true).build();
+        assert_eq!(
+            vec![
+                XmlEvent::Start {
+                    name: "a".to_owned(),
+                    attributes: vec![("q".to_owned(), "x&y".to_owned())],
+                },
+                XmlEvent::Text("AT&T &nbsp;<".to_owned()),
+                XmlEvent::End("a".to_owned()),
+            ],
+            events(r#"<a q="x&y">AT&T &nbsp;&lt;</a>"#, &config)?
+        );
+        assert!(events("<a>&nbsp;</a>", &ParseConfig::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn void_elements_are_closed_with_their_start_tag() -> Result<(), Error> {
+        let config = ParseConfig::builder().void_elements(["br"]).build();
+        let br = || XmlEvent::Start {
+            name: "br".to_owned(),
+            attributes: Vec::new(),
+        };
+        assert_eq!(
+            vec![
+                XmlEvent::Start {
+                    name: "p".to_owned(),
+                    attributes: Vec::new(),
+                },
+                br(),
+                XmlEvent::End("br".to_owned()),
+                br(),
+                XmlEvent::End("br".to_owned()),
+                XmlEvent::End("p".to_owned()),
+            ],
+            events("<p><br><br></br></p>", &config)?
+        );
+        assert!(matches!(
+            events("<p><b></p>", &config),
+            Err(Error::Syntax(SyntaxError {
+                code: SyntaxError::TAG_MISMATCH,
+                ..
+            }))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn spans_cover_start_to_end_tag() -> Result<(), Error> {
+        let config = ParseConfig::builder().with_spans(true).build();
+        let xml = r#"<r><a k="1">x</a><b/></r>"#;
+        let mut reader = EventReader::new(xml.as_bytes(), &config);
+        while reader.next_event()?.is_some() {}
+        let slices: Vec<&str> = reader
+            .spans
+            .closed
+            .iter()
+            .filter_map(|&(start, end)| xml.get(start..end))
+            .collect();
+        assert_eq!(vec![r#"<a k="1">x</a>"#, "<b/>", xml], slices);
+        Ok(())
+    }
+
+    #[test]
+    fn raw_paths_keep_markup_as_text() -> Result<(), Error> {
+        let config = ParseConfig::builder().raw_paths(["r/b"]).build();
+        let start = |name: &str| XmlEvent::Start {
+            name: name.to_owned(),
+            attributes: Vec::new(),
+        };
+        assert_eq!(
+            vec![
+                start("r"),
+                start("a"),
+                XmlEvent::Text("<".to_owned()),
+                XmlEvent::End("a".to_owned()),
+                start("b"),
+                XmlEvent::Text(r#"<b k="1"> <c>&lt;</c><b/> </b>"#.to_owned()),
+                XmlEvent::End("b".to_owned()),
+                start("b"),
+                XmlEvent::Text("<b/>".to_owned()),
+                XmlEvent::End("b".to_owned()),
+                XmlEvent::End("r".to_owned()),
+            ],
+            events(
+                r#"<r><a>&lt;</a> <b k="1"> <c>&lt;</c><b/> </b><b/></r>"#,
+                &config
+            )?
+        );
+        assert!(events("<r><b><c></b></r>", &config).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::transforms::parse_datetime;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use std::collections::HashMap;
+
+/// Namespace of Atom 1.0, used by Atom feeds and for the `atom:link` of RSS channels.
+const ATOM: &str = "http://www.w3.org/2005/Atom";
+/// Namespace of Dublin Core, used by RSS feeds for `dc:creator` and `dc:date`.
+const DUBLIN_CORE: &str = "http://purl.org/dc/elements/1.1/";
+/// Namespace of the RSS content module, used for `content:encoded`.
+const CONTENT: &str = "http://purl.org/rss/1.0/modules/content/";
+
+/// Elements a feed is parsed with as lists, whether or not they repeat.
+pub const LISTS: [&str; 5] = ["item", "link", "atom:entry", "atom:link", "atom:author"];
+
+/// The `namespaces` a feed is parsed with, giving each its usual prefix whatever the document
+/// declares.
+pub fn namespaces() -> HashMap<String, String> {
+    HashMap::from([
+        (ATOM.to_owned(), "atom".to_owned()),
+        (DUBLIN_CORE.to_owned(), "dc".to_owned()),
+        (CONTENT.to_owned(), "content".to_owned()),
+    ])
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Rss,
+    Atom,
+}
+
+/// The common shape of an RSS 2.0 or Atom feed parsed with `namespaces()` and `LISTS`:
+/// `format`, `title`, `link`, `description`, `updated` and `entries`, each entry with `id`,
+/// `title`, `link`, `summary`, `content`, `author`, `published` and `updated`. Missing values
+/// are None.
+///
+/// # Errors
+///
+/// Raises `ValueError` when the root element is neither `rss` nor an Atom `feed`, or an RSS
+/// feed has no channel.
+pub fn normalize<'py>(parsed: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyDict>> {
+    let py = parsed.py();
+    let (format, channel) = if let Some(rss) = child(parsed, "rss")? {
+        let Some(channel) = child(&rss, "channel")? else {
+            return Err(PyErr::new::<PyValueError, _>("RSS feed has no channel"));
+        };
+        (Format::Rss, channel)
+    } else if let Some(feed) = child(parsed, "atom:feed")? {
+        (Format::Atom, feed)
+    } else {
+        return Err(PyErr::new::<PyValueError, _>(
+            "not an RSS 2.0 or Atom feed: the root element must be rss or an Atom feed",
+        ));
+    };
+    let result = PyDict::new(py);
+    let (name, title, links, description, updated, entries) = match format {
+        Format::Rss => (
+            "rss",
+            child(&channel, "title")?,
+            match child(&channel, "link")? {
+                Some(link) => Some(link),
+                None => child(&channel, "atom:link")?,
+            },
+            child(&channel, "description")?,
+            match child(&channel, "lastBuildDate")? {
+                Some(date) => Some(date),
+                None => child(&channel, "pubDate")?,
+            },
+            child(&channel, "item")?,
+        ),
+        Format::Atom => (
+            "atom",
+            child(&channel, "atom:title")?,
+            child(&channel, "atom:link")?,
+            child(&channel, "atom:subtitle")?,
+            child(&channel, "atom:updated")?,
+            child(&channel, "atom:entry")?,
+        ),
+    };
+    result.set_item("format", name)?;
+    result.set_item("title", text(title)?)?;
+    result.set_item("link", link(links)?)?;
+    result.set_item("description", text(description)?)?;
+    result.set_item("updated", date(py, updated)?)?;
+    let list = PyList::empty(py);
+    if let Some(entries) = entries {
+        for item in entries.try_iter()? {
+            list.append(entry(format, &item?)?)?;
+        }
+    }
+    result.set_item("entries", list)?;
+    Ok(result)
+}
+
+fn entry<'py>(format: Format, item: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyDict>> {
+    let py = item.py();
+    let (id, title, links, summary, content, author, published, updated) = match format {
+        Format::Rss => (
+            child(item, "guid")?,
+            child(item, "title")?,
+            child(item, "link")?,
+            child(item, "description")?,
+            child(item, "content:encoded")?,
+            match child(item, "author")? {
+                Some(author) => Some(author),
+                None => child(item, "dc:creator")?,
+            },
+            child(item, "pubDate")?,
+            child(item, "dc:date")?,
+        ),
+        Format::Atom => {
+            let author = match child(item, "atom:author")? {
+                Some(authors) => child(&first(authors)?, "atom:name")?,
+                None => None,
+            };
+            (
+                child(item, "atom:id")?,
+                child(item, "atom:title")?,
+                child(item, "atom:link")?,
+                child(item, "atom:summary")?,
+                child(item, "atom:content")?,
+                author,
+                child(item, "atom:published")?,
+                child(item, "atom:updated")?,
+            )
+        }
+    };
+    let result = PyDict::new(py);
+    result.set_item("id", text(id)?)?;
+    result.set_item("title", text(title)?)?;
+    result.set_item("link", link(links)?)?;
+    result.set_item("summary", text(summary)?)?;
+    result.set_item("content", text(content)?)?;
+    result.set_item("author", text(author)?)?;
+    result.set_item("published", date(py, published)?)?;
+    result.set_item("updated", date(py, updated)?)?;
+    Ok(result)
+}
+
+fn child<'py>(parent: &Bound<'py, PyAny>, key: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+    match parent.downcast::<PyDict>() {
+        Ok(parent) => parent.get_item(key),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The first of repeated elements, or the element itself.
+fn first(value: Bound<'_, PyAny>) -> PyResult<Bound<'_, PyAny>> {
+    match value.downcast::<PyList>() {
+        Ok(items) if !items.is_empty() => items.get_item(0),
+        Ok(_) | Err(_) => Ok(value),
+    }
+}
+
+/// The text of an element: a string, or the text of an element with attributes.
+fn text(value: Option<Bound<'_, PyAny>>) -> PyResult<Option<String>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let value = first(value)?;
+    if value.is_instance_of::<PyString>() {
+        return Ok(Some(value.extract()?));
+    }
+    text(child(&value, "#text")?)
+}
+
+/// The URL of an RSS `link`, or of the first Atom `link` without a `rel` or with
+/// `rel="alternate"`.
+fn link(links: Option<Bound<'_, PyAny>>) -> PyResult<Option<String>> {
+    let Some(links) = links else {
+        return Ok(None);
+    };
+    for link in links.try_iter()? {
+        let link = link?;
+        if link.is_instance_of::<PyString>() {
+            return Ok(Some(link.extract()?));
+        }
+        let rel = text(child(&link, "@rel")?)?;
+        if matches!(rel.as_deref(), None | Some("alternate")) {
+            if let Some(href) = text(child(&link, "@href")?)? {
+                return Ok(Some(href));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// An ISO 8601 (Atom, `dc:date`) or RFC 822 (RSS) date as a datetime, or the text itself
+/// when it is neither.
+fn date<'py>(
+    py: Python<'py>,
+    value: Option<Bound<'py, PyAny>>,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Some(text) = text(value)? else {
+        return Ok(None);
+    };
+    let text = text.trim();
+    match parse_datetime(py, text) {
+        Ok(date) => return Ok(Some(date)),
+        Err(err) if err.is_instance_of::<PyValueError>(py) => {}
+        Err(err) => return Err(err),
+    }
+    let parsed = py
+        .import("email.utils")?
+        .call_method1("parsedate_to_datetime", (text,));
+    match parsed {
+        Ok(date) => Ok(Some(date)),
+        Err(err) if err.is_instance_of::<PyValueError>(py) => {
+            Ok(Some(PyString::new(py, text).into_any()))
+        }
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::add_note;
+use crate::events::XmlEvent;
+use pyo3::call::PyCallArgs;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// The callbacks of a `parse_events` handler, in the style of an `ElementTree.TreeBuilder`
+/// target. Events the handler has no method for are not sent.
+pub struct Handler<'py> {
+    py: Python<'py>,
+    start: Option<Bound<'py, PyAny>>,
+    end: Option<Bound<'py, PyAny>>,
+    data: Option<Bound<'py, PyAny>>,
+    comment: Option<Bound<'py, PyAny>>,
+    pi: Option<Bound<'py, PyAny>>,
+    close: Option<Bound<'py, PyAny>>,
+}
+
+impl<'py> Handler<'py> {
+    pub fn new(handler: &Bound<'py, PyAny>) -> PyResult<Self> {
+        Ok(Self {
+            py: handler.py(),
+            start: handler.getattr_opt("start")?,
+            end: handler.getattr_opt("end")?,
+            data: handler.getattr_opt("data")?,
+            comment: handler.getattr_opt("comment")?,
+            pi: handler.getattr_opt("pi")?,
+            close: handler.getattr_opt("close")?,
+        })
+    }
+
+    /// Comments are only tokenized for handlers that take them.
+    pub fn wants_comments(&self) -> bool {
+        self.comment.is_some()
+    }
+
+    pub fn wants_pis(&self) -> bool {
+        self.pi.is_some()
+    }
+
+    /// Sends the events in document order, then returns what `close()` returns, or `None`
+    /// without one.
+    pub fn feed(&self, events: Vec<(XmlEvent, usize)>) -> PyResult<Py<PyAny>> {
+        for (event, line) in events {
+            let method = method_name(&event);
+            self.send(event).inspect_err(|err| {
+                add_note(
+                    self.py,
+                    err,
+                    &format!("while calling {method}() (line {line})"),
+                );
+            })?;
+        }
+        match &self.close {
+            Some(close) => Ok(close.call0()?.unbind()),
+            None => Ok(self.py.None()),
+        }
+    }
+
+    fn send(&self, event: XmlEvent) -> PyResult<()> {
+        match event {
+            XmlEvent::Start { name, attributes } => {
+                if let Some(start) = &self.start {
+                    let attrs = PyDict::new(self.py);
+                    for (key, value) in attributes {
+                        attrs.set_item(key, value)?;
+                    }
+                    start.call1((name, attrs))?;
+                }
+            }
+            XmlEvent::End(name) => call(self.end.as_ref(), (name,))?,
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                call(self.data.as_ref(), (text,))?;
+            }
+            XmlEvent::Comment(text) => call(self.comment.as_ref(), (text,))?,
+            XmlEvent::ProcessingInstruction(pi) => {
+                let (target, data) = pi.split_once(char::is_whitespace).unwrap_or((&pi, ""));
+                call(self.pi.as_ref(), (target, data.trim_start()))?;
+            }
+            XmlEvent::DocType(_) => {}
+        }
+        Ok(())
+    }
+}
+
+fn method_name(event: &XmlEvent) -> &'static str {
+    match event {
+        XmlEvent::Start { .. } => "start",
+        XmlEvent::End(_) => "end",
+        XmlEvent::Text(_) | XmlEvent::Whitespace(_) => "data",
+        XmlEvent::Comment(_) => "comment",
+        XmlEvent::ProcessingInstruction(_) => "pi",
+        XmlEvent::DocType(_) => "doctype",
+    }
+}
+
+fn call<'py>(method: Option<&Bound<'py, PyAny>>, args: impl PyCallArgs<'py>) -> PyResult<()> {
+    if let Some(method) = method {
+        method.call1(args)?;
+    }
+    Ok(())
+}
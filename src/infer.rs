@@ -0,0 +1,155 @@
+use crate::config::InferTypes;
+
+/// A text value recognised as a native type by [`infer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// An integer too large for `i64`; its text is kept as written.
+    BigInt,
+    Float(f64),
+}
+
+/// Recognises `null`, `true`/`false` (any case), integers and finite decimal floats, among
+/// the kinds enabled. Anything else stays text, so values like `"007"`, `"1_000"` or `"nan"`
+/// are not converted.
+#[must_use]
+pub fn infer(text: &str, kinds: InferTypes) -> Option<Scalar> {
+    if kinds.null && text.eq_ignore_ascii_case("null") {
+        return Some(Scalar::Null);
+    }
+    if kinds.bool {
+        if text.eq_ignore_ascii_case("true") {
+            return Some(Scalar::Bool(true));
+        }
+        if text.eq_ignore_ascii_case("false") {
+            return Some(Scalar::Bool(false));
+        }
+    }
+    if kinds.int && is_integer(text) {
+        return Some(text.parse().map_or(Scalar::BigInt, Scalar::Int));
+    }
+    if kinds.float && is_decimal(text) {
+        return text
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .map(Scalar::Float);
+    }
+    None
+}
+
+/// Leading zeros, as in zip codes or identifiers, mean the text is not a number.
+pub fn has_leading_zero(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    matches!(digits.as_bytes(), [b'0', next, ..] if next.is_ascii_digit())
+}
+
+fn is_integer(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) && !has_leading_zero(text)
+}
+
+/// Only digits, signs, points and exponents, so `inf` and `nan` stay text; `f64` parsing
+/// checks the rest of the syntax.
+fn is_decimal(text: &str) -> bool {
+    text.bytes().any(|b| b.is_ascii_digit())
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.' | b'e' | b'E'))
+        && !has_leading_zero(text)
+}
+
+/// A float spelled as Python's `repr()` spells it: the shortest digits that read back as
+/// the same value, in exponent form below 1e-4 and from 1e16, and otherwise with a fraction.
+#[must_use]
+pub fn float_repr(value: f64) -> String {
+    if value == 0.0 {
+        return if value.is_sign_negative() {
+            "-0.0"
+        } else {
+            "0.0"
+        }
+        .to_owned();
+    }
+    // `{:e}` gives the shortest digits, as `d.ddde±x`.
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    if !(-4..16).contains(&exponent) {
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        let exponent = exponent.unsigned_abs();
+        return format!("{sign}{first}{fraction}e{exponent_sign}{exponent:02}");
+    }
+    let point = usize::try_from(exponent + 1).unwrap_or(0);
+    if point == 0 {
+        let zeros = "0".repeat(usize::try_from(-exponent - 1).unwrap_or(0));
+        return format!("{sign}0.{zeros}{digits}");
+    }
+    if digits.len() <= point {
+        let zeros = "0".repeat(point - digits.len());
+        return format!("{sign}{digits}{zeros}.0");
+    }
+    let (whole, fraction) = digits.split_at(point);
+    format!("{sign}{whole}.{fraction}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infers_enabled_kinds_only() {
+        let all = InferTypes::ALL;
+        assert_eq!(Some(Scalar::Null), infer("null", all));
+        assert_eq!(Some(Scalar::Bool(true)), infer("True", all));
+        assert_eq!(Some(Scalar::Int(-12)), infer("-12", all));
+        assert_eq!(Some(Scalar::BigInt), infer("123456789012345678901234", all));
+        assert_eq!(Some(Scalar::Float(1500.0)), infer("1.5e3", all));
+        let ints = InferTypes {
+            int: true,
+            ..InferTypes::default()
+        };
+        assert_eq!(None, infer("1.5", ints));
+        assert_eq!(None, infer("true", ints));
+    }
+
+    #[test]
+    fn floats_are_spelled_as_python_does() {
+        for (value, repr) in [
+            (1500.0, "1500.0"),
+            (0.5, "0.5"),
+            (-2.25, "-2.25"),
+            (0.0001, "0.0001"),
+            (0.000_015, "1.5e-05"),
+            (1e15, "1000000000000000.0"),
+            (1e16, "1e+16"),
+            (1.25e100, "1.25e+100"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (-0.0, "-0.0"),
+        ] {
+            assert_eq!(repr, float_repr(value), "{value}");
+        }
+    }
+
+    #[test]
+    fn ambiguous_text_is_kept() {
+        for text in [
+            "", "007", "00.5", "1_000", "nan", "inf", "1e999", "1.2.3", "e5", "-", " 1",
+        ] {
+            assert_eq!(None, infer(text, InferTypes::ALL), "{text:?}");
+        }
+    }
+}
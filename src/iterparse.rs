@@ -0,0 +1,238 @@
+use crate::config::ParseConfig;
+use crate::error::parse_error;
+use crate::events::{Document, EventReader, XmlEvent};
+use crate::python::{Kept, ParseSetup};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::io::BufRead;
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+/// The events `iterparse` yields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EventKinds {
+    pub start: bool,
+    pub end: bool,
+    pub data: bool,
+    pub comment: bool,
+    pub pi: bool,
+}
+
+impl EventKinds {
+    pub const DEFAULT: Self = Self {
+        start: true,
+        end: true,
+        data: true,
+        comment: false,
+        pi: false,
+    };
+
+    /// Turns on the event named `kind`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message when `kind` is not an event name.
+    pub fn enable(&mut self, kind: &str) -> Result<(), String> {
+        match kind {
+            "start" => self.start = true,
+            "end" => self.end = true,
+            "data" => self.data = true,
+            "comment" => self.comment = true,
+            "pi" => self.pi = true,
+            _ => {
+                return Err(format!(
+                    "events must be 'start', 'end', 'data', 'comment' or 'pi', not {kind:?}"
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The input of an iterator, owned so it can be read between `__next__` calls.
+pub type Input = Box<dyn BufRead + Send>;
+
+/// Reading a file-like object or chunk iterator calls back into Python, which may run
+/// another thread; like a generator, an iterator refuses to be re-entered.
+fn lock<'a, T>(state: &'a Mutex<T>, function: &str) -> PyResult<MutexGuard<'a, T>> {
+    match state.try_lock() {
+        Ok(state) => Ok(state),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("{function} iterator already executing"),
+        )),
+    }
+}
+
+struct State {
+    reader: EventReader<'static, Input>,
+    /// Names of the open elements, from the root.
+    path: Vec<String>,
+}
+
+/// Lazily yields `(event, path, value)` tuples, reading the input only as far as needed for
+/// the next one. The reader is dropped at the end of the document or on the first error.
+#[pyclass(module = "xmltodict_rs")]
+pub struct IterParse {
+    kinds: EventKinds,
+    state: Mutex<Option<State>>,
+}
+
+impl IterParse {
+    pub fn new(input: Input, config: ParseConfig, kinds: EventKinds) -> Self {
+        Self {
+            kinds,
+            state: Mutex::new(Some(State {
+                reader: EventReader::owning(input, config),
+                path: Vec::new(),
+            })),
+        }
+    }
+
+    /// The tuple for `event`, if it is one of the kinds asked for.
+    fn item<'py>(
+        &self,
+        py: Python<'py>,
+        path: &mut Vec<String>,
+        event: XmlEvent,
+    ) -> PyResult<Option<Bound<'py, PyTuple>>> {
+        let item = match event {
+            XmlEvent::Start { name, attributes } => {
+                path.push(name);
+                if !self.kinds.start {
+                    return Ok(None);
+                }
+                let attrs = PyDict::new(py);
+                for (key, value) in attributes {
+                    attrs.set_item(key, value)?;
+                }
+                ("start", PyTuple::new(py, &*path)?, attrs.into_any())
+            }
+            XmlEvent::End(_) if self.kinds.end => {
+                let item = ("end", PyTuple::new(py, &*path)?, py.None().into_bound(py));
+                path.pop();
+                item
+            }
+            XmlEvent::End(_) => {
+                path.pop();
+                return Ok(None);
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) if self.kinds.data => (
+                "data",
+                PyTuple::new(py, &*path)?,
+                text.into_pyobject(py)?.into_any(),
+            ),
+            XmlEvent::Comment(text) if self.kinds.comment => (
+                "comment",
+                PyTuple::new(py, &*path)?,
+                text.into_pyobject(py)?.into_any(),
+            ),
+            XmlEvent::ProcessingInstruction(text) if self.kinds.pi => (
+                "pi",
+                PyTuple::new(py, &*path)?,
+                text.into_pyobject(py)?.into_any(),
+            ),
+            XmlEvent::Text(_)
+            | XmlEvent::Whitespace(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::ProcessingInstruction(_)
+            | XmlEvent::DocType(_) => return Ok(None),
+        };
+        Ok(Some(item.into_pyobject(py)?))
+    }
+}
+
+#[pymethods]
+impl IterParse {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyTuple>>> {
+        let mut state = lock(&self.state, "iterparse")?;
+        while let Some(State { reader, path }) = state.as_mut() {
+            match reader.next_event() {
+                Ok(Some(event)) => {
+                    if let Some(item) = self.item(py, path, event)? {
+                        return Ok(Some(item));
+                    }
+                }
+                Ok(None) => *state = None,
+                Err(err) => {
+                    *state = None;
+                    return Err(parse_error(py, err));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Lazily yields a dict for each of several concatenated documents, reading the input only
+/// as far as the end of the next root element. The reader is dropped at the end of the
+/// input or on the first error.
+#[pyclass(module = "xmltodict_rs")]
+pub struct ParseMany {
+    setup: Py<ParseSetup>,
+    reader: Mutex<Option<EventReader<'static, Input>>>,
+    /// The builder the last document was made with.
+    kept: Mutex<Kept>,
+}
+
+impl ParseMany {
+    pub fn new(input: Input, setup: Py<ParseSetup>) -> Self {
+        let config = setup.get().reader_config().clone();
+        Self {
+            setup,
+            reader: Mutex::new(Some(EventReader::owning(input, config))),
+            kept: Mutex::new(Kept::default()),
+        }
+    }
+
+    fn build(&self, py: Python, document: Document) -> PyResult<Py<PyAny>> {
+        let mut kept = lock(&self.kept, "parse_many")?;
+        self.setup.get().build(py, document, &mut kept)
+    }
+}
+
+#[pymethods]
+impl ParseMany {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<Py<PyAny>>> {
+        let mut reader = lock(&self.reader, "parse_many")?;
+        // Whatever comes before a root element belongs to its document, such as a DOCTYPE.
+        let mut events = Vec::new();
+        let mut depth = 0usize;
+        while let Some(current) = reader.as_mut() {
+            match current.next_event() {
+                Ok(Some(event)) => {
+                    match &event {
+                        XmlEvent::Start { .. } => depth += 1,
+                        XmlEvent::End(_) => depth = depth.saturating_sub(1),
+                        XmlEvent::Text(_)
+                        | XmlEvent::Whitespace(_)
+                        | XmlEvent::DocType(_)
+                        | XmlEvent::Comment(_)
+                        | XmlEvent::ProcessingInstruction(_) => {}
+                    }
+                    let closes_root = depth == 0 && matches!(event, XmlEvent::End(_));
+                    events.push((event, current.line()));
+                    if closes_root {
+                        let document = (events, current.take_recovered(), current.take_spans());
+                        current.next_document();
+                        return self.build(py, document).map(Some);
+                    }
+                }
+                Ok(None) => *reader = None,
+                Err(err) => {
+                    *reader = None;
+                    return Err(parse_error(py, err));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
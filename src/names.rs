@@ -0,0 +1,326 @@
+use crate::config::{DuplicateAttributes, ParseConfig, UnparseConfig};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Represents an XML namespace prefix.
+/// Default namespace has empty string as key in the namespace map.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NamespacePrefix {
+    /// Default namespace (no prefix, represented as empty string)
+    Default,
+    /// Named namespace with explicit prefix
+    Named(String),
+}
+
+impl NamespacePrefix {
+    /// Returns the string representation of the prefix.
+    /// Default namespace returns empty string, named returns the prefix.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Default => "",
+            Self::Named(s) => s,
+        }
+    }
+}
+
+impl From<&str> for NamespacePrefix {
+    fn from(s: &str) -> Self {
+        if s.is_empty() {
+            Self::Default
+        } else {
+            Self::Named(s.to_owned())
+        }
+    }
+}
+
+impl From<String> for NamespacePrefix {
+    fn from(s: String) -> Self {
+        if s.is_empty() {
+            Self::Default
+        } else {
+            Self::Named(s)
+        }
+    }
+}
+
+/// Rejects the element names quick-xml lets through but expat does not.
+#[must_use]
+pub fn is_valid_element_name(name: &str) -> bool {
+    !name.is_empty() && !name.chars().any(|x| matches!(x, '<' | '>'))
+}
+
+/// Whether `name` is an XML `Name`: a letter, `_` or `:` followed by letters, digits and
+/// `-._:`, with the other characters XML 1.0 allows in names.
+#[must_use]
+pub fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+/// Whether `name` is an XML `Name` without colons, as prefixes and local names are.
+#[must_use]
+pub fn is_ncname(name: &str) -> bool {
+    !name.contains(':') && is_xml_name(name)
+}
+
+fn is_name_start_char(ch: char) -> bool {
+    matches!(ch,
+        ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+fn is_name_char(ch: char) -> bool {
+    is_name_start_char(ch)
+        || matches!(ch,
+            '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// The prefix an `xmlns` attribute declares, `""` for the default namespace.
+#[must_use]
+pub fn declared_prefix(name: &str) -> Option<&str> {
+    match name.strip_prefix("xmlns")? {
+        "" => Some(""),
+        rest => rest.strip_prefix(':'),
+    }
+}
+
+/// Adds the namespace declarations among `attributes` to `collected`, keeping the first URI
+/// declared for each prefix.
+pub fn collect_declarations(
+    collected: &mut Vec<(String, String)>,
+    attributes: &[(String, String)],
+) {
+    for (name, uri) in attributes {
+        if let Some(prefix) = declared_prefix(name) {
+            if !collected.iter().any(|(known, _)| known == prefix) {
+                collected.push((prefix.to_owned(), uri.clone()));
+            }
+        }
+    }
+}
+
+/// Start tag attributes after namespace declarations have been split off.
+pub struct ScannedAttributes {
+    /// Regular attributes as `(raw name, value)`.
+    pub attributes: Vec<(String, String)>,
+    /// Prefix to URI bindings in scope for the element.
+    pub namespaces: HashMap<String, String>,
+    /// Whether the in-scope bindings should be reported under the `xmlns` attribute key.
+    pub report_xmlns: bool,
+}
+
+/// Splits `xmlns` declarations from regular attributes, tracking the bindings in scope.
+pub fn scan_attributes(
+    config: &ParseConfig,
+    parent: Option<&HashMap<String, String>>,
+    raw: Vec<(String, String)>,
+) -> ScannedAttributes {
+    let mut namespaces = parent.cloned().unwrap_or_default();
+    let mut report_xmlns = false;
+    let mut attributes = Vec::with_capacity(raw.len());
+    let is_unmapped = |uri: &str| {
+        config
+            .namespaces
+            .as_ref()
+            .is_none_or(|mapping| !mapping.contains_key(uri))
+    };
+
+    for (key, value) in raw {
+        if config.process_namespaces {
+            if key == "xmlns" {
+                namespaces.insert(NamespacePrefix::Default.as_str().to_owned(), value);
+                continue;
+            }
+            if let Some(prefix) = key.strip_prefix("xmlns:") {
+                if !report_xmlns && config.namespaces.is_some() {
+                    report_xmlns = is_unmapped(&value);
+                }
+                namespaces.insert(prefix.to_owned(), value);
+                continue;
+            }
+            if !report_xmlns {
+                if let Some((prefix, _)) = key.split_once(':') {
+                    report_xmlns = namespaces.get(prefix).is_some_and(|uri| is_unmapped(uri));
+                }
+            }
+        }
+        attributes.push((key, value));
+    }
+
+    ScannedAttributes {
+        attributes,
+        namespaces,
+        // Stripped names refer to no namespace, so the bindings have nothing to explain.
+        report_xmlns: report_xmlns && !config.strip_namespace_prefixes,
+    }
+}
+
+/// The part of a raw `prefix:local` name after the prefix.
+fn local_name(full_name: &str) -> &str {
+    full_name
+        .split_once(':')
+        .map_or(full_name, |(_, local)| local)
+}
+
+/// Resolves a raw `prefix:local` name against the bindings in scope when namespaces
+/// are processed, producing `uri<sep>local` (or the configured short prefix). With
+/// `strip_namespace_prefixes`, only `local` is kept.
+pub fn qualified_name(
+    config: &ParseConfig,
+    namespaces: Option<&HashMap<String, String>>,
+    full_name: &str,
+) -> String {
+    if config.strip_namespace_prefixes {
+        return local_name(full_name).to_owned();
+    }
+    if !config.process_namespaces {
+        return full_name.to_owned();
+    }
+
+    let Some(ns_map) = namespaces else {
+        return full_name.to_owned();
+    };
+    let ns_sep = &config.namespace_separator;
+    let (prefix, name) = full_name
+        .split_once(':')
+        .unwrap_or((NamespacePrefix::Default.as_str(), full_name));
+    if let Some(uri) = ns_map.get(prefix) {
+        let mapped = config
+            .namespaces
+            .as_ref()
+            .and_then(|m| m.get(uri))
+            .unwrap_or(uri);
+        if mapped.is_empty() {
+            return name.to_owned();
+        }
+        return format!("{mapped}{ns_sep}{name}");
+    }
+    full_name.to_owned()
+}
+
+/// An attribute value, resolved with [`qualified_name`] when the attribute is one of the
+/// `qname_attributes`.
+pub fn attribute_value(
+    config: &ParseConfig,
+    namespaces: Option<&HashMap<String, String>>,
+    name: &str,
+    value: String,
+) -> String {
+    if config.process_namespaces && config.qname_attributes.iter().any(|known| known == name) {
+        qualified_name(config, namespaces, value.trim())
+    } else {
+        value
+    }
+}
+
+/// Builds the dictionary key for an attribute: prefixed, or bare when attributes are
+/// collected under `attr_dict_key`.
+pub fn attribute_key(
+    config: &ParseConfig,
+    namespaces: Option<&HashMap<String, String>>,
+    name: &str,
+) -> String {
+    format!(
+        "{}{}",
+        config.attribute_prefix(),
+        attribute_name(config, namespaces, name)
+    )
+}
+
+/// An attribute name without any key prefix: local with `strip_namespace_prefixes`, or
+/// resolved like an element name when namespaces are processed.
+pub fn attribute_name<'a>(
+    config: &ParseConfig,
+    namespaces: Option<&HashMap<String, String>>,
+    name: &'a str,
+) -> Cow<'a, str> {
+    if config.strip_namespace_prefixes && declared_prefix(name).is_none() {
+        Cow::Borrowed(local_name(name))
+    } else if config.process_namespaces && name.contains(config.namespace_separator.as_ref()) {
+        Cow::Owned(qualified_name(config, namespaces, name))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Rewrites an expanded `uri<sep>local` name into `prefix:local` using the namespace map.
+/// A `prefix:local` name, as parsed without processing namespaces, is written unchanged.
+///
+/// # Errors
+///
+/// Returns the URI of a name whose namespace is not mapped, which would not make a valid
+/// name as written.
+pub fn prefixed_name<'a>(config: &UnparseConfig, name: &'a str) -> Result<Cow<'a, str>, &'a str> {
+    let Some((uri, local)) = name.rsplit_once(&*config.namespace_separator) else {
+        return Ok(Cow::Borrowed(name));
+    };
+    match config.namespaces.iter().find(|(known, _)| known == uri) {
+        Some((_, prefix)) if prefix.is_empty() => Ok(Cow::Borrowed(local)),
+        Some((_, prefix)) => Ok(Cow::Owned(format!("{prefix}:{local}"))),
+        None if &*config.namespace_separator == ":" && is_ncname(uri) => Ok(Cow::Borrowed(name)),
+        None => Err(uri),
+    }
+}
+
+/// Sort key putting attributes in canonical order: namespace declarations first, the
+/// default namespace before prefixes, then the other attributes, each by name.
+#[must_use]
+pub fn canonical_attribute_order(name: &str) -> (bool, &str) {
+    match name.strip_prefix("xmlns") {
+        Some("") => (false, ""),
+        Some(prefixed) if prefixed.starts_with(':') => (false, prefixed),
+        Some(_) | None => (true, name),
+    }
+}
+
+/// Leaves one value for each attribute name, in the place of its first occurrence: the
+/// last value, with [`DuplicateAttributes::Last`]. Returns the names that were repeated.
+///
+/// # Errors
+///
+/// Returns the first repeated name with [`DuplicateAttributes::Raise`].
+pub fn dedupe_attributes<'a>(
+    attributes: &mut Vec<(&'a str, &'a str)>,
+    policy: DuplicateAttributes,
+) -> Result<Vec<&'a str>, &'a str> {
+    let mut repeated = Vec::new();
+    if attributes.len() < 2 {
+        return Ok(repeated);
+    }
+    let mut unique: Vec<(&str, &str)> = Vec::with_capacity(attributes.len());
+    for &(name, value) in attributes.iter() {
+        match unique.iter_mut().find(|(kept, _)| *kept == name) {
+            None => unique.push((name, value)),
+            Some(_) if policy == DuplicateAttributes::Raise => return Err(name),
+            Some(kept) => {
+                kept.1 = value;
+                repeated.push(name);
+            }
+        }
+    }
+    *attributes = unique;
+    Ok(repeated)
+}
+
+/// The attribute declaring a namespace prefix: `xmlns` for the default namespace (an empty
+/// prefix), `xmlns:prefix` otherwise.
+#[must_use]
+pub fn declaration_name(prefix: &str) -> String {
+    if prefix.is_empty() {
+        "xmlns".to_owned()
+    } else {
+        format!("xmlns:{prefix}")
+    }
+}
+
+/// `xmlns` attributes declaring the namespace map, as `(attribute name, uri)`.
+pub fn namespace_declarations(config: &UnparseConfig) -> impl Iterator<Item = (String, &str)> {
+    config
+        .namespaces
+        .iter()
+        .map(|(uri, prefix)| (declaration_name(prefix), uri.as_str()))
+}
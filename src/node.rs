@@ -0,0 +1,252 @@
+use crate::config::ParseConfig;
+use crate::error::expat_error;
+use crate::events::XmlEvent;
+use crate::names::{attribute_name, attribute_value, qualified_name, scan_attributes};
+use crate::xmltodict_core::SyntaxError;
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use std::collections::HashMap;
+
+/// A node of a document read with `parse(node_factory="ordered")`: the document itself, an
+/// element, a comment, a processing instruction or the DOCTYPE declaration. Unlike the dicts
+/// `parse` builds, it keeps attributes, text and children in the order they were written.
+#[pyclass(frozen, module = "xmltodict_rs")]
+pub struct Node {
+    /// `"document"`, `"element"`, `"comment"`, `"pi"` or `"doctype"`.
+    #[pyo3(get)]
+    kind: &'static str,
+    /// Name of an element, resolved as dict keys are; `None` for the other kinds.
+    #[pyo3(get)]
+    tag: Option<String>,
+    /// `(name, value)` tuples of an element, in document order.
+    #[pyo3(get)]
+    attributes: Py<PyList>,
+    /// Text strings and nodes in document order. A comment, processing instruction or
+    /// DOCTYPE declaration holds its text alone.
+    #[pyo3(get)]
+    children: Py<PyList>,
+}
+
+/// The kinds of node there are.
+const KINDS: [&str; 5] = ["document", "element", "comment", "pi", "doctype"];
+
+#[pymethods]
+impl Node {
+    /// Builds a node from its parts, which is how pickling and copying recreate one.
+    #[new]
+    #[pyo3(signature = (kind, tag = None, attributes = Vec::new(), children = Vec::new()))]
+    fn new(
+        py: Python,
+        kind: &str,
+        tag: Option<String>,
+        attributes: Vec<(String, String)>,
+        children: Vec<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let Some(kind) = KINDS.into_iter().find(|known| *known == kind) else {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "kind must be one of {}, not {kind:?}",
+                KINDS.join(", ")
+            )));
+        };
+        for child in &children {
+            if !child.is_instance_of::<PyString>() && !child.is_instance_of::<Node>() {
+                return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                    "children must be strings or nodes, not {}",
+                    child.get_type().name()?
+                )));
+            }
+        }
+        Ok(Self {
+            kind,
+            tag,
+            attributes: PyList::new(py, attributes)?.unbind(),
+            children: PyList::new(py, children)?.unbind(),
+        })
+    }
+
+    /// The text children joined, without the text of descendants.
+    #[getter]
+    fn text(&self, py: Python) -> PyResult<String> {
+        let mut text = String::new();
+        for child in self.children.bind(py) {
+            if let Ok(part) = child.downcast::<PyString>() {
+                text.push_str(part.to_str()?);
+            }
+        }
+        Ok(text)
+    }
+
+    fn __getnewargs__(&self, py: Python) -> (&'static str, Option<String>, Py<PyList>, Py<PyList>) {
+        (
+            self.kind,
+            self.tag.clone(),
+            self.attributes.clone_ref(py),
+            self.children.clone_ref(py),
+        )
+    }
+
+    /// `<Node element 'p' attributes=1 children=3>`, or the text of a comment, processing
+    /// instruction or DOCTYPE declaration.
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        let children = self.children.bind(py).len();
+        Ok(match self.kind {
+            "element" => format!(
+                "<Node element {} attributes={} children={children}>",
+                PyString::new(py, self.tag.as_deref().unwrap_or_default()).repr()?,
+                self.attributes.bind(py).len(),
+            ),
+            "document" => format!("<Node document children={children}>"),
+            kind => format!(
+                "<Node {kind} {}>",
+                PyString::new(py, &self.text(py)?).repr()?
+            ),
+        })
+    }
+}
+
+/// Content of a node until it is complete.
+enum Child {
+    Text(String),
+    Node(Py<Node>),
+}
+
+/// The document, or an element whose end tag has not been read yet.
+struct Frame {
+    tag: Option<String>,
+    attributes: Vec<(String, String)>,
+    children: Vec<Child>,
+}
+
+impl Frame {
+    /// Appends text, joining it to text just before it.
+    fn add_text(&mut self, text: String, separator: &str) {
+        if let Some(Child::Text(last)) = self.children.last_mut() {
+            last.push_str(separator);
+            last.push_str(&text);
+        } else {
+            self.children.push(Child::Text(text));
+        }
+    }
+
+    fn into_node(self, py: Python, kind: &'static str) -> PyResult<Py<Node>> {
+        let children = PyList::empty(py);
+        for child in self.children {
+            match child {
+                Child::Text(text) => children.append(text)?,
+                Child::Node(node) => children.append(node)?,
+            }
+        }
+        let node = Node {
+            kind,
+            tag: self.tag,
+            attributes: PyList::new(py, self.attributes)?.unbind(),
+            children: children.unbind(),
+        };
+        Py::new(py, node)
+    }
+}
+
+fn syntax_error(py: Python, code: u32, msg: &str) -> PyErr {
+    expat_error(py, &SyntaxError::new(code, msg))
+}
+
+/// Builds the `Node` tree of a tokenized document. Names follow the namespace options as
+/// dict keys do; the options shaping dicts, such as `force_list`, do not apply.
+pub fn build_tree(
+    py: Python,
+    events: Vec<(XmlEvent, usize)>,
+    config: &ParseConfig,
+) -> PyResult<Py<Node>> {
+    let mut frames = vec![Frame {
+        tag: None,
+        attributes: Vec::new(),
+        children: Vec::new(),
+    }];
+    let mut namespace_stack: Vec<HashMap<String, String>> = Vec::new();
+    let mut has_root = false;
+
+    for (event, _) in events {
+        let (kind, text) = match event {
+            XmlEvent::Start { name, attributes } => {
+                let scanned = scan_attributes(config, namespace_stack.last(), attributes);
+                namespace_stack.push(scanned.namespaces);
+                let namespaces = namespace_stack.last();
+                let attributes = scanned
+                    .attributes
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = attribute_value(config, namespaces, &name, value);
+                        (
+                            attribute_name(config, namespaces, &name).into_owned(),
+                            value,
+                        )
+                    })
+                    .collect();
+                frames.push(Frame {
+                    tag: Some(qualified_name(config, namespaces, &name)),
+                    attributes,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            XmlEvent::End(_) => {
+                let (Some(frame), Some(_)) = (frames.pop(), namespace_stack.pop()) else {
+                    return Err(syntax_error(
+                        py,
+                        SyntaxError::TAG_MISMATCH,
+                        "unexpected closing tag",
+                    ));
+                };
+                let node = frame.into_node(py, "element")?;
+                let Some(parent) = frames.last_mut() else {
+                    return Err(syntax_error(
+                        py,
+                        SyntaxError::TAG_MISMATCH,
+                        "unexpected closing tag",
+                    ));
+                };
+                parent.children.push(Child::Node(node));
+                has_root |= namespace_stack.is_empty();
+                continue;
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                // Outside the root element there is no text to keep.
+                if let Some(frame) = frames.last_mut().filter(|_| !namespace_stack.is_empty()) {
+                    frame.add_text(text, &config.cdata_separator);
+                }
+                continue;
+            }
+            XmlEvent::DocType(doctype) if config.process_doctype => {
+                ("doctype", doctype.trim().to_owned())
+            }
+            XmlEvent::DocType(_) => continue,
+            XmlEvent::Comment(comment) => ("comment", config.strip.apply(&comment).to_owned()),
+            XmlEvent::ProcessingInstruction(pi) => ("pi", config.strip.apply(&pi).to_owned()),
+        };
+        let node = Frame {
+            tag: None,
+            attributes: Vec::new(),
+            children: vec![Child::Text(text)],
+        }
+        .into_node(py, kind)?;
+        if let Some(frame) = frames.last_mut() {
+            frame.children.push(Child::Node(node));
+        }
+    }
+
+    if frames.len() > 1 {
+        return Err(syntax_error(
+            py,
+            SyntaxError::NO_ELEMENTS,
+            "unclosed element(s) found",
+        ));
+    }
+    match frames.pop() {
+        Some(document) if has_root => document.into_node(py, "document"),
+        Some(_) | None => Err(syntax_error(
+            py,
+            SyntaxError::NO_ELEMENTS,
+            "no element found",
+        )),
+    }
+}
@@ -1,403 +1,448 @@
 use crate::config::ParseConfig;
-use crate::error::expat_error;
+use crate::error::{add_note, parse_error};
+use crate::events::Span;
+use crate::infer::{infer, Scalar};
+use crate::path_pattern::{ListedElements, PathMap};
+use crate::transforms::Transforms;
+use crate::xmltodict_core::{Error, Held, TreeBuilder, Values};
+use crate::xsd::Schema;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3::types::{PyDict, PyFrozenSet, PyInt, PyList, PySet, PyString, PyTuple};
 use pyo3::IntoPyObjectExt;
-use quick_xml::name::PrefixDeclaration;
 use std::collections::HashMap;
 
-/// Represents an XML namespace prefix.
-/// Default namespace has empty string as key in the namespace map.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub enum NamespacePrefix {
-    /// Default namespace (no prefix, represented as empty string)
-    Default,
-    /// Named namespace with explicit prefix
-    Named(String),
+/// Which elements `force_list` always makes lists.
+pub enum ForceList {
+    /// `True` or `False`, or strings copied out of a set, frozenset, list or tuple, so
+    /// checking an element needs no Python call.
+    Native(ListedElements),
+    /// Any other container, checked with `in`, or a `(path, key, value)` callable.
+    Python(Py<PyAny>),
 }
 
-impl NamespacePrefix {
-    /// Returns the string representation of the prefix.
-    /// Default namespace returns empty string, named returns the prefix.
-    #[must_use]
-    pub fn as_str(&self) -> &str {
+impl ForceList {
+    pub fn new(force_list: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(force_list) = force_list else {
+            return Ok(Self::Native(ListedElements::all(false)));
+        };
+        Ok(match native_force_list(force_list)? {
+            Some(listed) => Self::Native(listed),
+            None => Self::Python(force_list.clone().unbind()),
+        })
+    }
+
+    fn clone_ref(&self, py: Python) -> Self {
         match self {
-            Self::Default => "",
-            Self::Named(s) => s,
+            Self::Native(listed) => Self::Native(listed.clone()),
+            Self::Python(force_list) => Self::Python(force_list.clone_ref(py)),
         }
     }
 }
 
-impl From<&str> for NamespacePrefix {
-    fn from(s: &str) -> Self {
-        if s.is_empty() {
-            Self::Default
-        } else {
-            Self::Named(s.to_owned())
+/// A `force_list` of `True`, `False` or element names in a set, frozenset, list or tuple,
+/// or `None` for anything else.
+pub fn native_force_list(force_list: &Bound<'_, PyAny>) -> PyResult<Option<ListedElements>> {
+    if let Ok(value) = force_list.extract::<bool>() {
+        return Ok(Some(ListedElements::all(value)));
+    }
+    // Subclasses may override `__contains__`, so only the built-in types are copied.
+    if force_list.is_exact_instance_of::<PySet>()
+        || force_list.is_exact_instance_of::<PyFrozenSet>()
+        || force_list.is_exact_instance_of::<PyList>()
+        || force_list.is_exact_instance_of::<PyTuple>()
+    {
+        if let Ok(names) = force_list
+            .try_iter()?
+            .map(|name| name?.extract::<String>())
+            .collect::<PyResult<Vec<_>>>()
+        {
+            return Ok(Some(ListedElements::named(names)));
         }
     }
+    Ok(None)
+}
+
+/// The `parse()` options applied to entries as the Python result is built.
+pub struct Hooks {
+    pub force_list: ForceList,
+    pub transforms: Transforms,
+    /// New keys for entries, by their key or path in the document.
+    pub key_map: PathMap<String>,
+    pub postprocessor: Option<Py<PyAny>>,
+    /// Mapping type for the result; `None` keeps plain dicts.
+    pub dict_constructor: Option<Py<PyAny>>,
+    /// Types and lists from an `xsd`, used where `transforms` and `force_list` say nothing.
+    pub schema: Schema,
 }
 
-impl From<String> for NamespacePrefix {
-    fn from(s: String) -> Self {
-        if s.is_empty() {
-            Self::Default
-        } else {
-            Self::Named(s)
+impl Hooks {
+    /// The same options, for another builder.
+    #[must_use]
+    pub fn clone_ref(&self, py: Python) -> Self {
+        Self {
+            force_list: self.force_list.clone_ref(py),
+            transforms: self.transforms.clone(),
+            key_map: self.key_map.clone(),
+            postprocessor: self.postprocessor.as_ref().map(|hook| hook.clone_ref(py)),
+            dict_constructor: self
+                .dict_constructor
+                .as_ref()
+                .map(|constructor| constructor.clone_ref(py)),
+            schema: self.schema.clone(),
         }
     }
 }
 
+/// Builds the dicts `parse()` returns from reader events, with the rules every parser shares
+/// and the options only Python has.
 pub struct XmlParser {
     config: ParseConfig,
-    force_list: Option<Py<PyAny>>,
-    postprocessor: Option<Py<PyAny>>,
-    pub stack: Vec<Py<PyAny>>,
-    pub path: Vec<String>,
-    pub text_stack: Vec<Vec<String>>,
-    pub namespace_stack: Vec<HashMap<String, String>>,
+    hooks: Hooks,
+    builder: TreeBuilder<Py<PyDict>>,
+    /// Source line of the event being handled, reported when a postprocessor fails.
+    pub line: usize,
+    /// Python strings already made for keys, so that repeated elements and attributes
+    /// share one string instead of each holding a copy.
+    keys: HashMap<String, Py<PyString>>,
 }
 
+/// Keys a parser kept between documents remembers before starting over, so that documents
+/// with ever new element names do not grow it without end.
+const MAX_KEPT_KEYS: usize = 4096;
+
 impl XmlParser {
     #[must_use]
-    pub fn new(
-        config: ParseConfig,
-        force_list: Option<Py<PyAny>>,
-        postprocessor: Option<Py<PyAny>>,
-    ) -> Self {
+    pub fn new(config: ParseConfig, hooks: Hooks) -> Self {
         Self {
             config,
-            force_list,
-            postprocessor,
-            stack: Vec::new(),
-            path: Vec::new(),
-            text_stack: Vec::new(),
-            namespace_stack: Vec::new(),
+            hooks,
+            builder: TreeBuilder::default(),
+            line: 1,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Forgets the document being built, keeping the options and the keys made so far for
+    /// the next document.
+    pub fn reset(&mut self) {
+        self.builder.reset();
+        self.line = 1;
+        if self.keys.len() > MAX_KEPT_KEYS {
+            self.keys.clear();
         }
     }
 
-    fn should_force_list(&self, py: Python, key: &str, value: &Bound<'_, PyAny>) -> PyResult<bool> {
-        let Some(force_list) = &self.force_list else {
-            return Ok(false);
+    /// Takes the spans the reader recorded for the document about to be built.
+    pub fn set_spans(&mut self, spans: Vec<Span>) {
+        self.builder.set_spans(spans);
+    }
+
+    /// The objects of this parser's options for the event being handled.
+    fn objects<'a, 'py>(
+        &'a mut self,
+        py: Python<'py>,
+    ) -> (&'a mut TreeBuilder<Py<PyDict>>, Objects<'a, 'py>) {
+        let objects = Objects {
+            py,
+            config: &self.config,
+            hooks: &self.hooks,
+            keys: &mut self.keys,
+            line: self.line,
         };
+        (&mut self.builder, objects)
+    }
 
-        if let Ok(val) = force_list.extract::<bool>(py) {
-            return Ok(val);
-        }
+    /// Keeps the DOCTYPE declaration with `process_doctype`.
+    pub fn doctype(&mut self, doctype: &str) {
+        self.builder.doctype(&self.config, doctype);
+    }
 
-        if let Ok(val) = force_list
-            .call_method1(py, "__contains__", (key,))
-            .and_then(|x| x.extract::<bool>(py))
-        {
-            return Ok(val);
-        }
+    pub fn start_element(
+        &mut self,
+        py: Python,
+        name: &str,
+        attributes: Vec<(String, String)>,
+    ) -> PyResult<()> {
+        let (builder, mut objects) = self.objects(py);
+        builder.start_element(&mut objects, name, attributes)
+    }
 
-        if let Ok(path_list) = PyList::new(py, &self.path) {
-            let callable_result = force_list.call1(py, (path_list, key, value))?;
-            let bool_val = callable_result.extract::<bool>(py)?;
-            return Ok(bool_val);
-        }
+    pub fn end_element(&mut self, py: Python) -> PyResult<()> {
+        let (builder, mut objects) = self.objects(py);
+        builder.end_element(&mut objects)
+    }
 
-        Ok(false)
+    pub fn characters(&mut self, data: String) {
+        self.builder.characters(&self.config, data);
     }
 
-    #[inline]
-    fn apply_postprocessor<'py>(
-        &self,
-        py: Python<'py>,
-        key: &str,
-        data: &Bound<'py, PyAny>,
-    ) -> PyResult<Option<(String, Bound<'py, PyAny>)>> {
-        let mut final_key = key.to_owned();
-        let mut final_value = data.clone();
+    pub fn whitespace(&mut self, data: String) {
+        self.builder.whitespace(&self.config, data);
+    }
 
-        if let Some(proc) = &self.postprocessor {
-            let path_list = PyList::new(py, &self.path)?;
-            let result = proc.call1(py, (path_list, key, data))?;
+    pub fn comment(&mut self, py: Python, comment: &str) -> PyResult<()> {
+        let (builder, mut objects) = self.objects(py);
+        let config = objects.config;
+        builder.add_entry(&mut objects, &config.comment_key, comment)
+    }
 
-            if result.is_none(py) {
-                return Ok(None);
-            }
+    pub fn processing_instruction(&mut self, py: Python, pi: &str) -> PyResult<()> {
+        let (builder, mut objects) = self.objects(py);
+        let config = objects.config;
+        builder.add_entry(&mut objects, &config.pi_key, pi)
+    }
 
-            let tuple = result.bind(py).downcast::<PyTuple>()?;
-            final_key = tuple.get_item(0)?.extract::<String>()?;
-            final_value = tuple.get_item(1)?;
+    /// The document, once every element is closed.
+    pub fn finish(&mut self, py: Python) -> PyResult<Py<PyAny>> {
+        let (builder, mut objects) = self.objects(py);
+        let document = builder.finish(&mut objects)?;
+        Ok(objects.finish(document)?.unbind())
+    }
+}
+
+/// Makes Python objects for a [`TreeBuilder`], applying the `parse()` options that call
+/// into Python.
+struct Objects<'a, 'py> {
+    py: Python<'py>,
+    config: &'a ParseConfig,
+    hooks: &'a Hooks,
+    keys: &'a mut HashMap<String, Py<PyString>>,
+    line: usize,
+}
+
+impl<'py> Objects<'_, 'py> {
+    /// The Python string for the key `name`, made on its first use.
+    fn key(&mut self, name: &str) -> Bound<'py, PyString> {
+        if let Some(key) = self.keys.get(name) {
+            return key.bind(self.py).clone();
         }
+        let key = PyString::new(self.py, name);
+        self.keys.insert(name.to_owned(), key.clone().unbind());
+        key
+    }
 
-        Ok(Some((final_key, final_value)))
+    /// Notes where in the document an entry failed to convert, without changing the
+    /// exception.
+    fn note_entry_error(&self, err: &PyErr, action: &str, path: &[&str]) {
+        let note = format!("while {action} {} (line {})", path.join("/"), self.line);
+        add_note(self.py, err, &note);
     }
+}
 
-    fn push_data(
-        &self,
-        py: Python,
-        item: &Bound<'_, PyDict>,
-        key: &str,
-        data: &Bound<'_, PyAny>,
-    ) -> PyResult<()> {
-        let Some((final_key, final_value)) = self.apply_postprocessor(py, key, data)? else {
-            return Ok(());
-        };
+/// Names from the root element down to the entry `key`, which belongs to the element `owner`
+/// for attributes and text, or to the innermost open element otherwise.
+fn entry_path<'a>(path: &'a [String], owner: Option<&'a str>, key: &'a str) -> Vec<&'a str> {
+    path.iter()
+        .map(String::as_str)
+        .chain(owner)
+        .chain([key])
+        .collect()
+}
 
-        match item.get_item(final_key.as_str())? {
-            Some(existing) => {
-                if let Ok(list) = existing.downcast::<PyList>() {
-                    list.append(data.clone())?;
-                } else {
-                    let new_list = PyList::new(py, [existing.clone(), final_value.clone()])?;
-                    item.set_item(final_key, &new_list)?;
-                }
-            }
-            None => {
-                if self.should_force_list(py, final_key.as_str(), final_value.as_ref())? {
-                    let new_list = PyList::new(py, [final_value.clone()])?;
-                    item.set_item(final_key, &new_list)?;
-                } else {
-                    item.set_item(final_key, final_value)?;
-                }
-            }
-        }
+impl<'a, 'py> Values<'a> for Objects<'a, 'py> {
+    type Value = Bound<'py, PyAny>;
+    type Map = Py<PyDict>;
+    type Error = PyErr;
 
-        Ok(())
+    fn config(&self) -> &'a ParseConfig {
+        self.config
     }
 
-    fn build_name(&self, full_name: &str) -> String {
-        if !self.config.process_namespaces {
-            return full_name.to_owned();
-        }
+    fn none(&mut self) -> Bound<'py, PyAny> {
+        self.py.None().into_bound(self.py)
+    }
 
-        let Some(ns_map) = self.namespace_stack.last() else {
-            return full_name.to_owned();
+    /// Converted to a native type when `infer_types` recognises it.
+    fn scalar(&mut self, text: String) -> PyResult<Bound<'py, PyAny>> {
+        let py = self.py;
+        let Some(scalar) = infer(&text, self.config.infer_types) else {
+            return text.into_bound_py_any(py);
         };
-        let ns_sep = &self.config.namespace_separator;
-        let (prefix, name) = full_name
-            .split_once(':')
-            .unwrap_or((NamespacePrefix::Default.as_str(), full_name));
-        if let Some(uri) = ns_map.get(prefix) {
-            let mapped = self
-                .config
-                .namespaces
-                .as_ref()
-                .and_then(|m| m.get(uri))
-                .unwrap_or(uri);
-            if mapped.is_empty() {
-                return name.to_owned();
-            }
-            return format!("{mapped}{ns_sep}{name}");
+        match scalar {
+            Scalar::Null => Ok(self.none()),
+            Scalar::Bool(value) => value.into_bound_py_any(py),
+            Scalar::Int(value) => value.into_bound_py_any(py),
+            Scalar::BigInt => py.get_type::<PyInt>().call1((text,)),
+            Scalar::Float(value) => value.into_bound_py_any(py),
         }
-        full_name.to_owned()
     }
 
-    pub fn start_element(
-        &mut self,
-        py: Python,
-        name: &str,
-        attrs: &[quick_xml::events::attributes::Attribute],
-    ) -> PyResult<()> {
-        let mut current_ns_map = self.namespace_stack.last().cloned().unwrap_or_default();
-
-        let element_dict = PyDict::new(py);
-        let mut set_xmlns_item = false;
-        let mut normal_attrs: Vec<(String, String)> = Vec::new();
-
-        if self.config.xml_attribs && !attrs.is_empty() {
-            for attr in attrs {
-                let key = &attr.key;
-                let value_string = attr
-                    .unescape_value()
-                    .map_err(|e| expat_error(py, e.to_string()))?
-                    .into_owned();
-
-                if self.config.process_namespaces {
-                    if let Some(ns) = key.as_namespace_binding() {
-                        match ns {
-                            PrefixDeclaration::Default => {
-                                current_ns_map.insert(
-                                    NamespacePrefix::Default.as_str().to_owned(),
-                                    value_string,
-                                );
-                            }
-                            PrefixDeclaration::Named(name) => {
-                                let key_string = String::from_utf8(name.to_vec())?;
-                                if !set_xmlns_item {
-                                    if let Some(m) = self.config.namespaces.as_ref() {
-                                        set_xmlns_item = !m.contains_key(&value_string);
-                                    }
-                                }
-                                current_ns_map.insert(key_string, value_string);
-                            }
-                        }
-                        continue;
-                    }
-                }
-
-                let key_str = String::from_utf8(key.into_inner().to_vec())?;
-                if self.config.process_namespaces && !set_xmlns_item && key_str.contains(':') {
-                    if let Some((prefix, _)) = key_str.split_once(':') {
-                        if let Some(uri) = current_ns_map.get(prefix) {
-                            if let Some(m) = self.config.namespaces.as_ref() {
-                                if !m.contains_key(uri) {
-                                    set_xmlns_item = true;
-                                }
-                            } else {
-                                set_xmlns_item = true;
-                            }
-                        }
-                    }
-                }
-                normal_attrs.push((key_str, value_string));
-            }
-        }
+    fn string(&mut self, text: String) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyString::new(self.py, &text).into_any())
+    }
 
-        if self.config.xml_attribs && set_xmlns_item {
-            let ns_py = PyDict::new(py);
-            for (key, value) in &current_ns_map {
-                ns_py.set_item(key, value)?;
-            }
-            let xmlns_key = format!("{}xmlns", self.config.attr_prefix);
-            element_dict.set_item(xmlns_key, ns_py)?;
-        }
+    fn index(&mut self, index: usize) -> PyResult<Bound<'py, PyAny>> {
+        index.into_bound_py_any(self.py)
+    }
 
-        self.namespace_stack.push(current_ns_map);
+    fn span(&mut self, span: Span) -> PyResult<Bound<'py, PyAny>> {
+        span.into_bound_py_any(self.py)
+    }
 
-        if self.config.xml_attribs {
-            for (key, value) in normal_attrs {
-                let attr_local_name = if self.config.process_namespaces
-                    && key.contains(self.config.namespace_separator.as_ref())
-                {
-                    self.build_name(&key)
-                } else {
-                    key
-                };
+    fn list(&mut self, items: Vec<Bound<'py, PyAny>>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyList::new(self.py, items)?.into_any())
+    }
 
-                let prefixed_key = format!("{}{}", self.config.attr_prefix, attr_local_name);
-                let Some((final_key, final_value)) = self.apply_postprocessor(
-                    py,
-                    prefixed_key.as_str(),
-                    value.into_py_any(py)?.bind(py),
-                )?
-                else {
-                    continue;
-                };
-                element_dict.set_item(final_key, final_value)?;
-            }
-        }
+    fn map(&mut self) -> Py<PyDict> {
+        PyDict::new(self.py).unbind()
+    }
 
-        let element_name = if self.config.process_namespaces {
-            self.build_name(name)
-        } else {
-            name.to_owned()
-        };
+    fn is_empty(&self, map: &Py<PyDict>) -> bool {
+        map.bind(self.py).is_empty()
+    }
 
-        self.stack.push(element_dict.into());
-        self.path.push(element_name);
-        self.text_stack.push(Vec::new());
+    fn copy(&mut self, map: &Py<PyDict>) -> PyResult<Py<PyDict>> {
+        Ok(map.bind(self.py).copy()?.unbind())
+    }
 
-        Ok(())
+    fn share(&mut self, value: &Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+        value.clone()
     }
 
-    pub fn end_element(&mut self, py: Python, name: &str) -> PyResult<()> {
-        let element_name = self.build_name(name);
+    fn held(&mut self, map: &Py<PyDict>, key: &str) -> PyResult<Held> {
+        let key = self.key(key);
+        Ok(match map.bind(self.py).get_item(key)? {
+            None => Held::Nothing,
+            Some(existing) if existing.is_instance_of::<PyList>() => Held::List,
+            Some(_) => Held::Other,
+        })
+    }
 
-        let Some(current_element) = self.stack.pop() else {
-            return Err(expat_error(py, "unexpected closing tag".to_owned()));
-        };
-        let Some(text_parts) = self.text_stack.pop() else {
-            return Err(expat_error(py, "unexpected closing tag".to_owned()));
-        };
-        let Some(_) = self.path.pop() else {
-            return Err(expat_error(py, "unexpected closing tag".to_owned()));
-        };
+    fn set(&mut self, map: &mut Py<PyDict>, key: &str, value: Bound<'py, PyAny>) -> PyResult<()> {
+        let key = self.key(key);
+        map.bind(self.py).set_item(key, value)
+    }
 
-        let text_content = if text_parts.is_empty() {
-            None
-        } else {
-            let joined = text_parts.join(&self.config.cdata_separator);
-            if self.config.strip_whitespace && joined.trim().is_empty() {
-                None
-            } else {
-                Some(joined)
-            }
+    fn push(&mut self, map: &mut Py<PyDict>, key: &str, value: Bound<'py, PyAny>) -> PyResult<()> {
+        let key = self.key(key);
+        match map.bind(self.py).get_item(key)? {
+            Some(list) => list.downcast_into::<PyList>()?.append(value),
+            None => Ok(()),
+        }
+    }
+
+    fn pair(&mut self, map: &mut Py<PyDict>, key: &str, value: Bound<'py, PyAny>) -> PyResult<()> {
+        let key = self.key(key);
+        let map = map.bind(self.py);
+        let Some(existing) = map.get_item(&key)? else {
+            return Ok(());
         };
+        map.set_item(key, PyList::new(self.py, [existing, value])?)
+    }
 
-        let element_dict = current_element.downcast_bound::<PyDict>(py)?;
-        let has_attrs = !element_dict.is_empty();
-
-        let final_value = match (has_attrs, text_content) {
-            (false, None) => py.None(),
-            (false, Some(text)) => {
-                if self.config.force_cdata {
-                    let dict = PyDict::new(py);
-                    if let Some((final_key, final_value)) = self.apply_postprocessor(
-                        py,
-                        &self.config.cdata_key,
-                        text.into_py_any(py)?.bind(py),
-                    )? {
-                        dict.set_item(final_key, final_value)?;
-                    }
-                    dict.into()
-                } else {
-                    text.into_pyobject(py)?.into_any().unbind()
-                }
-            }
-            (true, Some(text)) => {
-                if let Some((final_key, final_value)) = self.apply_postprocessor(
-                    py,
-                    &self.config.cdata_key,
-                    text.into_py_any(py)?.bind(py),
-                )? {
-                    element_dict.set_item(final_key, final_value)?;
-                }
-                current_element
-            }
-            (true, None) => current_element,
+    /// A finished dict as the result type: itself, or its entries copied into a new
+    /// `dict_constructor()` mapping.
+    fn finish(&mut self, map: Py<PyDict>) -> PyResult<Bound<'py, PyAny>> {
+        let dict = map.into_bound(self.py);
+        let Some(dict_constructor) = &self.hooks.dict_constructor else {
+            return Ok(dict.into_any());
         };
+        let mapping = dict_constructor.bind(self.py).call0()?;
+        for (key, value) in dict {
+            mapping.set_item(key, value)?;
+        }
+        Ok(mapping)
+    }
+
+    /// Errors in entries say on which line they are.
+    fn error(&self, err: Error) -> PyErr {
+        if let Error::Value(msg) = &err {
+            return PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{msg} (line {})",
+                self.line
+            ));
+        }
+        parse_error(self.py, err)
+    }
+
+    /// Applies `transforms` and `key_map`, then the postprocessor.
+    fn entry(
+        &mut self,
+        path: &[String],
+        owner: Option<&str>,
+        key: &str,
+        value: Bound<'py, PyAny>,
+    ) -> PyResult<Option<(String, Bound<'py, PyAny>)>> {
+        let py = self.py;
+        let hooks = self.hooks;
+        let mut final_key = key.to_owned();
+        let mut final_value = value;
 
-        if self.stack.is_empty() {
-            let result_dict = PyDict::new(py);
-            let Some((final_key, final_value)) =
-                self.apply_postprocessor(py, element_name.as_str(), final_value.bind(py))?
-            else {
-                return Ok(());
-            };
-            if self.should_force_list(py, final_key.as_str(), final_value.as_ref())? {
-                let new_list = PyList::new(py, [final_value.clone()])?;
-                result_dict.set_item(final_key, &new_list)?;
-            } else {
-                result_dict.set_item(final_key, final_value)?;
+        if !hooks.transforms.is_empty()
+            || !hooks.schema.transforms.is_empty()
+            || !hooks.key_map.is_empty()
+        {
+            let entry = entry_path(path, owner, key);
+            let transform = hooks
+                .transforms
+                .find(&entry)
+                .or_else(|| hooks.schema.transforms.find(&entry));
+            if let Some(transform) = transform {
+                let Some(value) = transform
+                    .apply(&final_value)
+                    .inspect_err(|err| self.note_entry_error(err, "transforming", &entry))?
+                else {
+                    return Ok(None);
+                };
+                final_value = value;
+            }
+            if let Some(new_key) = hooks.key_map.find(&entry) {
+                final_key.clone_from(new_key);
             }
-            self.stack.push(result_dict.into());
-        } else {
-            let Some(parent) = self.stack.last() else {
-                return Err(expat_error(py, "unexpected closing tag".to_owned()));
-            };
-            let parent_dict = parent.downcast_bound::<PyDict>(py)?;
-
-            self.push_data(py, parent_dict, &element_name, final_value.bind(py))?;
         }
 
-        let Some(_) = self.namespace_stack.pop() else {
-            return Err(expat_error(py, "unexpected closing tag".to_owned()));
-        };
+        if let Some(proc) = &hooks.postprocessor {
+            let path_list = PyList::new(py, path)?;
+            let result = proc
+                .bind(py)
+                .call1((path_list, &final_key, &final_value))
+                .inspect_err(|err| {
+                    self.note_entry_error(err, "postprocessing", &entry_path(path, None, key));
+                })?;
 
-        Ok(())
-    }
+            if result.is_none() {
+                return Ok(None);
+            }
 
-    pub fn characters(&mut self, data: &str) {
-        if let Some(current_text) = self.text_stack.last_mut() {
-            current_text.push(data.to_owned());
+            let tuple = result.downcast::<PyTuple>()?;
+            final_key = tuple.get_item(0)?.extract::<String>()?;
+            final_value = tuple.get_item(1)?;
         }
+
+        Ok(Some((final_key, final_value)))
     }
 
-    pub fn comment(&self, py: Python, comment: &str) -> PyResult<()> {
-        let Some(parent) = self.stack.last() else {
-            return Ok(());
-        };
-        let parent_dict = parent.downcast_bound::<PyDict>(py)?;
-        let comment_py = if self.config.strip_whitespace {
-            comment.trim().into_pyobject(py)?
-        } else {
-            comment.into_pyobject(py)?
+    fn force_list(
+        &mut self,
+        path: &[String],
+        key: &str,
+        value: &Bound<'py, PyAny>,
+    ) -> PyResult<bool> {
+        let hooks = self.hooks;
+        if !hooks.schema.lists.is_empty() {
+            let entry = entry_path(path, None, key);
+            if hooks
+                .schema
+                .lists
+                .iter()
+                .any(|pattern| pattern.matches(&entry))
+            {
+                return Ok(true);
+            }
+        }
+        let force_list = match &hooks.force_list {
+            ForceList::Native(listed) => return Ok(listed.contains(path, key)),
+            ForceList::Python(force_list) => force_list.bind(self.py),
         };
-        self.push_data(py, parent_dict, &self.config.comment_key, &comment_py)
+
+        if let Ok(val) = force_list
+            .call_method1("__contains__", (key,))
+            .and_then(|x| x.extract::<bool>())
+        {
+            return Ok(val);
+        }
+
+        let path_list = PyList::new(self.py, path)?;
+        force_list.call1((path_list, key, value))?.extract::<bool>()
     }
 }
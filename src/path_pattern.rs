@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+/// An element path such as `catalog/items/item`, matched from the root element. A `*`
+/// segment matches any one element name and `**` matches any number of them, so `*/row`
+/// is a `row` directly under the root and `**/items/item` is an `item` in any `items`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    segments: Vec<String>,
+}
+
+impl PathPattern {
+    /// Whether `text` is meant as a path rather than a plain element name.
+    #[must_use]
+    pub fn is_pattern(text: &str) -> bool {
+        text.contains(['/', '*'])
+    }
+
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        let text = text.strip_prefix('/').unwrap_or(text);
+        Self {
+            segments: text.split('/').map(str::to_owned).collect(),
+        }
+    }
+
+    /// Whether the names from the root element down to an entry follow this path.
+    #[must_use]
+    pub fn matches(&self, path: &[&str]) -> bool {
+        segments_match(&self.segments, path)
+    }
+
+    /// Whether some element below `path` could match, so the elements on it must be kept.
+    #[must_use]
+    pub fn leads_to(&self, path: &[&str]) -> bool {
+        segments_lead_to(&self.segments, path)
+    }
+}
+
+/// Elements named by key anywhere in the document, or by path from the root element for
+/// names containing `/` or `*`; or every element.
+#[derive(Clone, Debug, Default)]
+pub struct ListedElements {
+    all: bool,
+    names: HashSet<String>,
+    patterns: Vec<PathPattern>,
+}
+
+impl ListedElements {
+    /// Every element when `all`, none otherwise.
+    #[must_use]
+    pub fn all(all: bool) -> Self {
+        Self {
+            all,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn named<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: HashSet<String> = names.into_iter().map(Into::into).collect();
+        let patterns = names
+            .iter()
+            .filter(|name| PathPattern::is_pattern(name))
+            .map(|name| PathPattern::new(name))
+            .collect();
+        Self {
+            all: false,
+            names,
+            patterns,
+        }
+    }
+
+    /// Whether the entry `key`, under the open elements of `path`, is listed.
+    #[must_use]
+    pub fn contains(&self, path: &[String], key: &str) -> bool {
+        self.all
+            || self.names.contains(key)
+            || (!self.patterns.is_empty() && {
+                let entry: Vec<&str> = path.iter().map(String::as_str).chain([key]).collect();
+                self.patterns.iter().any(|pattern| pattern.matches(&entry))
+            })
+    }
+}
+
+/// Values looked up by an entry's key (`price`, `@id`) or, for keys written as paths, by the
+/// names from the root element down to the entry. Paths, being more specific, are looked up
+/// before keys.
+#[derive(Clone)]
+pub struct PathMap<T> {
+    keys: HashMap<String, T>,
+    patterns: Vec<(PathPattern, T)>,
+}
+
+impl<T> Default for PathMap<T> {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::new(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> PathMap<T> {
+    /// Adds `value` under `key`; a key containing `/` or `*` is also matched as a path.
+    pub fn insert(&mut self, key: String, value: T) {
+        if PathPattern::is_pattern(&key) {
+            self.patterns.push((PathPattern::new(&key), value.clone()));
+        }
+        self.keys.insert(key, value);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The value for an entry at `path`, whose last name is the entry's key.
+    #[must_use]
+    pub fn find(&self, path: &[&str]) -> Option<&T> {
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, value)| value)
+            .or_else(|| self.keys.get(*path.last()?))
+    }
+}
+
+fn segments_match(pattern: &[String], path: &[&str]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return path.is_empty();
+    };
+    if first == "**" {
+        return (0..=path.len()).any(|skip| segments_match(rest, path.get(skip..).unwrap_or(&[])));
+    }
+    match path.split_first() {
+        Some((name, path)) => (first == "*" || first == name) && segments_match(rest, path),
+        None => false,
+    }
+}
+
+fn segments_lead_to(pattern: &[String], path: &[&str]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return false;
+    };
+    if first == "**" {
+        return rest.is_empty()
+            || (0..=path.len())
+                .any(|skip| segments_lead_to(rest, path.get(skip..).unwrap_or(&[])));
+    }
+    match path.split_first() {
+        Some((name, path)) => (first == "*" || first == name) && segments_lead_to(rest, path),
+        // The pattern goes on below the path.
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        let path: Vec<&str> = path.split('/').collect();
+        PathPattern::new(pattern).matches(&path)
+    }
+
+    #[test]
+    fn paths_match_from_the_root() {
+        assert!(matches("catalog/items/item", "catalog/items/item"));
+        assert!(matches("/catalog/items/item", "catalog/items/item"));
+        assert!(!matches("items/item", "catalog/items/item"));
+        assert!(!matches("catalog/items", "catalog/items/item"));
+    }
+
+    #[test]
+    fn wildcards_match_names() {
+        assert!(matches("*/row", "table/row"));
+        assert!(!matches("*/row", "db/table/row"));
+        assert!(matches("**/row", "row"));
+        assert!(matches("**/row", "db/table/row"));
+        assert!(matches("db/**/row", "db/a/b/row"));
+        assert!(!matches("db/**/row", "db/a/b/col"));
+        assert!(matches("**", "a/b"));
+    }
+
+    #[test]
+    fn leading_paths() {
+        let leads_to = |pattern: &str, path: &str| {
+            let path: Vec<&str> = path.split('/').collect();
+            PathPattern::new(pattern).leads_to(&path)
+        };
+        assert!(leads_to("rss/channel/item", "rss/channel"));
+        assert!(leads_to("rss/*/item", "rss/other"));
+        assert!(leads_to("**/item", "a/b/c"));
+        assert!(!leads_to("rss/channel/item", "rss/channel/item"));
+        assert!(!leads_to("rss/channel/item", "rss/other"));
+        assert!(leads_to("**/item/title", "rss/channel/item"));
+    }
+}
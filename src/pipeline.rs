@@ -0,0 +1,484 @@
+use crate::error::parse_error;
+use crate::escape::Markup;
+use crate::events::{EventReader, XmlEvent};
+use crate::iterparse::Input;
+use crate::names::{is_valid_element_name, is_xml_name};
+use crate::path_pattern::{PathMap, PathPattern};
+use crate::unparser::XmlWriter;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use std::collections::HashMap;
+
+/// What `transform` and `patch` do to the elements and attributes streamed through them.
+#[derive(Default)]
+pub struct Rules {
+    /// Elements left out, with everything inside them.
+    drop: Vec<PathPattern>,
+    /// New names of elements, by name or path.
+    rename: PathMap<String>,
+    /// Callbacks taking the value of an attribute, by name, and returning its new value or
+    /// `None` to leave it out.
+    attributes: HashMap<String, Py<PyAny>>,
+    /// Values `patch` puts into the document, from its root.
+    updates: Update,
+}
+
+impl Rules {
+    /// Reads the `rules` dict of `transform`: `drop` paths, `rename` names and `attributes`
+    /// callbacks.
+    pub fn new(rules: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut result = Self::default();
+        for (key, value) in rules.into_iter().flatten() {
+            match key.extract::<String>()?.as_str() {
+                "drop" => {
+                    result.drop = value
+                        .extract::<Vec<String>>()?
+                        .iter()
+                        .map(|path| PathPattern::new(path))
+                        .collect();
+                }
+                "rename" => {
+                    for (name, new_name) in value.downcast::<PyDict>()? {
+                        let new_name: String = new_name.extract()?;
+                        if !is_xml_name(&new_name) {
+                            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                                "cannot rename to {new_name:?}, not an element name"
+                            )));
+                        }
+                        result.rename.insert(name.extract()?, new_name);
+                    }
+                }
+                "attributes" => {
+                    for (name, callback) in value.downcast::<PyDict>()? {
+                        if !callback.is_callable() {
+                            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                                "the rule for attribute {name} must be callable"
+                            )));
+                        }
+                        result.attributes.insert(name.extract()?, callback.unbind());
+                    }
+                }
+                other => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "rules must be 'drop', 'rename' or 'attributes', not {other:?}"
+                    )))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Reads the `updates` dict of `patch`, from paths such as `order/line[2]/qty` or
+    /// `order/@id` to new values.
+    pub fn patch(updates: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut result = Self::default();
+        for (path, value) in updates {
+            let path: String = path.extract()?;
+            let steps: Vec<&str> = path.split('/').collect();
+            if steps.first().is_some_and(|step| step.starts_with('@')) {
+                return Err(invalid_path(&path));
+            }
+            result.updates.insert(&steps, &path, value.unbind())?;
+        }
+        Ok(result)
+    }
+
+    /// The value an attribute is written with, or `None` to leave it out.
+    fn attribute(&self, py: Python, name: &str, value: String) -> PyResult<Option<String>> {
+        match self.attributes.get(name) {
+            Some(callback) => callback.call1(py, (value,))?.extract(py),
+            None => Ok(Some(value)),
+        }
+    }
+}
+
+/// The updates `patch` makes to an element and its descendants.
+#[derive(Default)]
+struct Update {
+    /// Content replacing that of the element, written as `unparse` writes a value.
+    value: Option<Py<PyAny>>,
+    /// New attribute values in the order given; `None` leaves an attribute out.
+    attributes: Vec<(String, Py<PyAny>)>,
+    /// Updates to children, by name and position among the children of that name.
+    children: Vec<(String, usize, Update)>,
+}
+
+impl Update {
+    /// Adds the update at the path made of `steps`, below this element.
+    fn insert(&mut self, steps: &[&str], path: &str, value: Py<PyAny>) -> PyResult<()> {
+        let Some((step, rest)) = steps.split_first() else {
+            if !self.children.is_empty() {
+                return Err(overlapping_path(path));
+            }
+            self.value = Some(value);
+            return Ok(());
+        };
+        if let Some(name) = step.strip_prefix('@') {
+            if !rest.is_empty() || name.is_empty() {
+                return Err(invalid_path(path));
+            }
+            self.attributes.push((name.to_owned(), value));
+            return Ok(());
+        }
+        if self.value.is_some() {
+            return Err(overlapping_path(path));
+        }
+        let (name, position) = parse_step(step, path)?;
+        if let Some((_, _, child)) = self
+            .children
+            .iter_mut()
+            .find(|(child, at, _)| child == name && *at == position)
+        {
+            return child.insert(rest, path, value);
+        }
+        let mut child = Self::default();
+        child.insert(rest, path, value)?;
+        self.children.push((name.to_owned(), position, child));
+        Ok(())
+    }
+}
+
+/// Splits a step of an update path into the element name and its index among the children
+/// of that name: `0` unless a position from 1 is given in brackets, as `find` takes it in
+/// `line[2]`.
+fn parse_step<'s>(step: &'s str, path: &str) -> PyResult<(&'s str, usize)> {
+    let (name, index) = match step.strip_suffix(']').and_then(|step| step.split_once('[')) {
+        Some((name, position)) => {
+            let position: usize = position.parse().map_err(|_err| invalid_path(path))?;
+            let index = position.checked_sub(1).ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid update path {path:?}: positions start at 1"
+                ))
+            })?;
+            (name, index)
+        }
+        None => (step, 0),
+    };
+    if !is_valid_element_name(name) {
+        return Err(invalid_path(path));
+    }
+    Ok((name, index))
+}
+
+fn invalid_path(path: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("invalid update path {path:?}"))
+}
+
+fn overlapping_path(path: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+        "update path {path:?} is inside an element another update replaces"
+    ))
+}
+
+/// The updates to an open element and how many of its children of each name have been read,
+/// which tells the position of the next one.
+struct Patching<'r> {
+    update: Option<&'r Update>,
+    counts: HashMap<String, usize>,
+    /// Which of the updated children have been read.
+    found: Vec<bool>,
+}
+
+impl<'r> Patching<'r> {
+    fn new(update: Option<&'r Update>) -> Self {
+        Self {
+            update,
+            counts: HashMap::new(),
+            found: vec![false; update.map_or(0, |update| update.children.len())],
+        }
+    }
+
+    /// The updates to the child just read, called `name`.
+    fn child(&mut self, name: &str) -> Option<&'r Update> {
+        let update = self.update?;
+        let count = self.counts.entry(name.to_owned()).or_default();
+        let position = *count;
+        *count += 1;
+        let index = update
+            .children
+            .iter()
+            .position(|(child, at, _)| child == name && *at == position)?;
+        if let Some(found) = self.found.get_mut(index) {
+            *found = true;
+        }
+        update.children.get(index).map(|(_, _, child)| child)
+    }
+
+    /// Updated children the template does not have, which are added at the end.
+    fn missing(&self) -> impl Iterator<Item = (&'r str, &'r Update)> + '_ {
+        self.update
+            .into_iter()
+            .flat_map(|update| &update.children)
+            .zip(&self.found)
+            .filter(|(_, found)| !**found)
+            .map(|((name, _, child), _)| (name.as_str(), child))
+    }
+}
+
+/// Writes the events of a document as they are read, applying [`Rules`] on the way, so no
+/// more than one event is held at a time.
+pub struct Pipeline<'r> {
+    rules: &'r Rules,
+    writer: XmlWriter,
+    /// Names of the open elements as written, from the root, which rules are matched against.
+    path: Vec<String>,
+    /// Names the open elements outside dropped ones are written under.
+    tags: Vec<String>,
+    /// Updates to the document and the open elements outside dropped ones.
+    patching: Vec<Patching<'r>>,
+    /// How many of the open elements are in one that is dropped, or replaced by an update.
+    dropping: usize,
+    /// Whether the `>` of the last start tag is still to be written, which waits for the
+    /// next event so an empty element can be written `<a/>`.
+    in_start_tag: bool,
+    root_written: bool,
+}
+
+impl<'r> Pipeline<'r> {
+    pub fn new(rules: &'r Rules, writer: XmlWriter) -> Self {
+        Self {
+            rules,
+            writer,
+            path: Vec::new(),
+            tags: Vec::new(),
+            patching: vec![Patching::new(Some(&rules.updates))],
+            dropping: 0,
+            in_start_tag: false,
+            root_written: false,
+        }
+    }
+
+    /// Streams the whole document into the writer, returning the text for a buffer sink.
+    pub fn run(
+        mut self,
+        py: Python,
+        mut reader: EventReader<'_, Input>,
+    ) -> PyResult<Option<String>> {
+        self.writer.write_header();
+        while let Some(event) = reader.next_event().map_err(|err| parse_error(py, err))? {
+            self.write_event(py, event)?;
+        }
+        self.writer.finish(py)
+    }
+
+    fn write_event(&mut self, py: Python, event: XmlEvent) -> PyResult<()> {
+        match event {
+            XmlEvent::Start { name, attributes } => {
+                self.path.push(name);
+                if self.dropping == 0 && !self.drops_current() {
+                    self.write_start_tag(py, attributes)?;
+                } else {
+                    self.dropping += 1;
+                }
+            }
+            XmlEvent::End(_) => {
+                self.path.pop();
+                if self.dropping > 0 {
+                    self.dropping -= 1;
+                } else {
+                    self.write_end_tag(py)?;
+                }
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                // Outside the root element there is no text to keep.
+                if self.dropping == 0 {
+                    if let Some(tag) = self.tags.last() {
+                        let escaped = self.writer.escape_text(tag, &text)?.into_owned();
+                        self.close_start_tag(py)?;
+                        self.writer.write_raw(py, &escaped)?;
+                    }
+                }
+            }
+            XmlEvent::Comment(text) => self.write_markup(py, Markup::Comment, &text)?,
+            XmlEvent::ProcessingInstruction(text) => {
+                self.write_markup(py, Markup::ProcessingInstruction, &text)?;
+            }
+            XmlEvent::DocType(doctype) => {
+                self.writer.check_encodable("DOCTYPE", &doctype)?;
+                let newl = &self.writer.config().newl;
+                let markup = format!("<!DOCTYPE {doctype}>{newl}");
+                self.writer.write_raw(py, &markup)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn drops_current(&self) -> bool {
+        let path: Vec<&str> = self.path.iter().map(String::as_str).collect();
+        self.rules.drop.iter().any(|pattern| pattern.matches(&path))
+    }
+
+    /// Writes `<tag` and the attributes of the element just opened, renamed and rewritten
+    /// as the rules say, leaving the start tag open. An element whose content an update
+    /// replaces is written whole, and what the document has inside it is skipped.
+    fn write_start_tag(
+        &mut self,
+        py: Python,
+        mut attributes: Vec<(String, String)>,
+    ) -> PyResult<()> {
+        self.close_start_tag(py)?;
+        let path: Vec<&str> = self.path.iter().map(String::as_str).collect();
+        let name = path.last().copied().unwrap_or_default();
+        if self.tags.is_empty() {
+            let updates = &self.rules.updates.children;
+            if let Some((start, _, _)) = updates
+                .iter()
+                .find(|(start, at, _)| start != name || *at > 0)
+            {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "update paths must start at the root element <{name}>, not <{start}>"
+                )));
+            }
+        }
+        let update = self
+            .patching
+            .last_mut()
+            .and_then(|patching| patching.child(name));
+        let tag = match self.rules.rename.find(&path) {
+            Some(tag) => tag.clone(),
+            None => name.to_owned(),
+        };
+        if let Some(update) = update {
+            for (key, value) in &update.attributes {
+                let value = value.bind(py);
+                if value.is_none() {
+                    attributes.retain(|(attribute, _)| attribute != key);
+                    continue;
+                }
+                let text = self.writer.text_value(value)?;
+                match attributes
+                    .iter_mut()
+                    .find(|(attribute, _)| attribute == key)
+                {
+                    Some((_, old)) => *old = text,
+                    None => attributes.push((key.clone(), text)),
+                }
+            }
+        }
+        let mut kept = Vec::with_capacity(attributes.len());
+        for (name, value) in attributes {
+            if let Some(value) = self.rules.attribute(py, &name, value)? {
+                kept.push((name, value));
+            }
+        }
+        if let Some(value) = update.and_then(|update| update.value.as_ref()) {
+            let attributes = kept
+                .into_iter()
+                .map(|(name, value)| (name, PyString::new(py, &value).into_any()));
+            let content = self.element_value(py, attributes, Some(value.bind(py)))?;
+            self.writer.write_element(py, &tag, &content, false)?;
+            self.root_written |= self.tags.is_empty();
+            self.dropping += 1;
+            return Ok(());
+        }
+        self.writer.check_encodable("element name", &tag)?;
+        let mut markup = format!("<{tag}");
+        for (name, value) in kept {
+            XmlWriter::push_attribute(&mut markup, self.writer.config(), &name, &value)?;
+        }
+        self.writer.write_raw(py, &markup)?;
+        self.tags.push(tag);
+        self.patching.push(Patching::new(update));
+        self.in_start_tag = true;
+        Ok(())
+    }
+
+    /// Writes the end tag of an element, after the children updates add to it.
+    fn write_end_tag(&mut self, py: Python) -> PyResult<()> {
+        let tag = self.tags.pop().unwrap_or_default();
+        let patching = self.patching.pop();
+        let missing: Vec<_> = patching.iter().flat_map(Patching::missing).collect();
+        if self.in_start_tag && missing.is_empty() && self.writer.config().short_empty_elements {
+            self.in_start_tag = false;
+            self.writer.write_raw(py, "/>")?;
+        } else {
+            self.close_start_tag(py)?;
+            for (name, update) in missing {
+                let content = self.created_value(py, update)?;
+                self.writer.write_element(py, name, &content, false)?;
+            }
+            self.writer.write_raw(py, &format!("</{tag}>"))?;
+        }
+        self.root_written |= self.tags.is_empty();
+        Ok(())
+    }
+
+    /// The value `unparse` writes an element from: `value` alone, or with `attributes` a
+    /// dict holding them and `value` as text or, for a dict, its entries.
+    fn element_value<'py>(
+        &self,
+        py: Python<'py>,
+        attributes: impl IntoIterator<Item = (String, Bound<'py, PyAny>)>,
+        value: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let config = self.writer.config();
+        let dict = PyDict::new(py);
+        for (name, attribute) in attributes {
+            dict.set_item(format!("{}{name}", &*config.attr_prefix), attribute)?;
+        }
+        if dict.is_empty() {
+            return Ok(value.cloned().unwrap_or_else(|| py.None().into_bound(py)));
+        }
+        if let Some(value) = value {
+            if let Ok(entries) = value.downcast::<PyDict>() {
+                dict.update(entries.as_mapping())?;
+            } else if !value.is_none() {
+                dict.set_item(&*config.cdata_key, value)?;
+            }
+        }
+        Ok(dict.into_any())
+    }
+
+    /// The value of an element the template does not have, made of its updates.
+    fn created_value<'py>(&self, py: Python<'py>, update: &Update) -> PyResult<Bound<'py, PyAny>> {
+        let attributes = update
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.clone(), value.bind(py).clone()))
+            .filter(|(_, value)| !value.is_none());
+        let value = update.value.as_ref().map(|value| value.bind(py).clone());
+        let content = self.element_value(py, attributes, value.as_ref())?;
+        if update.children.is_empty() {
+            return Ok(content);
+        }
+        let dict = content
+            .downcast_into::<PyDict>()
+            .unwrap_or_else(|_| PyDict::new(py));
+        for (name, _, child) in &update.children {
+            let child = self.created_value(py, child)?;
+            match dict.get_item(name)? {
+                None => dict.set_item(name, child)?,
+                Some(siblings) => match siblings.downcast::<PyList>() {
+                    Ok(siblings) => siblings.append(child)?,
+                    Err(_) => dict.set_item(name, PyList::new(py, [siblings, child])?)?,
+                },
+            }
+        }
+        Ok(dict.into_any())
+    }
+
+    fn close_start_tag(&mut self, py: Python) -> PyResult<()> {
+        if self.in_start_tag {
+            self.in_start_tag = false;
+            self.writer.write_raw(py, ">")?;
+        }
+        Ok(())
+    }
+
+    /// Writes a comment or processing instruction; outside the root element, each is put on
+    /// a line of its own.
+    fn write_markup(&mut self, py: Python, markup: Markup, text: &str) -> PyResult<()> {
+        if self.dropping > 0 {
+            return Ok(());
+        }
+        let rendered = self.writer.render_markup(markup, text)?;
+        let newl = &self.writer.config().newl;
+        let rendered = match (self.tags.is_empty(), self.root_written) {
+            (false, _) => rendered,
+            (true, false) => format!("{rendered}{newl}"),
+            (true, true) => format!("{newl}{rendered}"),
+        };
+        self.close_start_tag(py)?;
+        self.writer.write_raw(py, &rendered)
+    }
+}
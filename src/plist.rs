@@ -0,0 +1,367 @@
+use crate::config::InvalidChars;
+use crate::escape::escape_xml;
+use crate::events::XmlEvent;
+use crate::unparser::Numbers;
+use pyo3::exceptions::{PyOverflowError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{
+    IntoPyDict, PyBool, PyByteArray, PyBytes, PyDateAccess, PyDateTime, PyDict, PyFloat, PyInt,
+    PyList, PyMapping, PyString, PyTimeAccess, PyTuple,
+};
+
+const DOCTYPE: &str = "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+                       \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n";
+
+/// Writes property lists as `plistlib.dumps()` does, with sorted dict keys.
+#[derive(Default)]
+pub struct PlistWriter {
+    output: String,
+    numbers: Numbers,
+    indent_level: usize,
+    /// Addresses of the lists and dicts being written, to refuse recursive ones.
+    open: Vec<usize>,
+}
+
+impl PlistWriter {
+    /// A `<plist>` document holding `value`, after an XML declaration naming `encoding`
+    /// with `full_document`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `TypeError` for values plists have no type for and for dict keys other than
+    /// strings, `OverflowError` for integers outside 64 bits, and `ValueError` for strings
+    /// with control characters.
+    pub fn dumps(
+        mut self,
+        value: &Bound<'_, PyAny>,
+        full_document: bool,
+        encoding: &str,
+    ) -> PyResult<String> {
+        if full_document {
+            self.output.push_str("<?xml version=\"1.0\" encoding=\"");
+            self.output.push_str(&encoding.to_uppercase());
+            self.output.push_str("\"?>\n");
+        }
+        self.output.push_str(DOCTYPE);
+        self.output.push_str("<plist version=\"1.0\">\n");
+        self.value(value)?;
+        self.output.push_str("</plist>\n");
+        Ok(self.output)
+    }
+
+    fn value(&mut self, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        if let Ok(text) = value.downcast::<PyString>() {
+            self.element("string", Some(&text.to_cow()?))?;
+        } else if let Ok(flag) = value.downcast::<PyBool>() {
+            self.element(if flag.is_true() { "true" } else { "false" }, None)?;
+        } else if value.is_instance_of::<PyInt>() {
+            let text = match value.extract::<i64>() {
+                Ok(int) => int.to_string(),
+                Err(_) => value
+                    .extract::<u64>()
+                    .map_err(|_err| PyOverflowError::new_err("int exceeds plist limits"))?
+                    .to_string(),
+            };
+            self.element("integer", Some(&text))?;
+        } else if value.is_instance_of::<PyFloat>() {
+            let text = match self.numbers.format(value) {
+                Some(text) => text.to_owned(),
+                None => value.repr()?.to_string(),
+            };
+            self.element("real", Some(&text))?;
+        } else if let Ok(mapping) = value.downcast::<PyMapping>() {
+            let mut items = Vec::new();
+            for item in mapping.items()? {
+                let (key, member) = item.extract::<(Bound<'_, PyAny>, Bound<'_, PyAny>)>()?;
+                let key = key
+                    .downcast::<PyString>()
+                    .map_err(|_err| PyTypeError::new_err("keys must be strings"))?
+                    .to_string();
+                items.push((key, member));
+            }
+            if items.is_empty() {
+                return self.element("dict", None);
+            }
+            items.sort_by(|(left, _), (right, _)| left.cmp(right));
+            self.enter(value, "dict", "dictionaries")?;
+            for (key, member) in items {
+                self.element("key", Some(&key))?;
+                self.value(&member)?;
+            }
+            self.leave("dict");
+        } else if value.is_instance_of::<PyBytes>() || value.is_instance_of::<PyByteArray>() {
+            self.data(value)?;
+        } else if let Ok(datetime) = value.downcast::<PyDateTime>() {
+            let text = format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                datetime.get_year(),
+                datetime.get_month(),
+                datetime.get_day(),
+                datetime.get_hour(),
+                datetime.get_minute(),
+                datetime.get_second()
+            );
+            self.element("date", Some(&text))?;
+        } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            if value.is_empty()? {
+                return self.element("array", None);
+            }
+            self.enter(value, "array", "lists")?;
+            for item in value.try_iter()? {
+                self.value(&item?)?;
+            }
+            self.leave("array");
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "unsupported type: {}",
+                value.get_type().repr()?
+            )));
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.indent_level {
+            self.output.push('\t');
+        }
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    /// An element holding `text`, or an empty one.
+    fn element(&mut self, tag: &str, text: Option<&str>) -> PyResult<()> {
+        let line = match text {
+            Some(text) => format!("<{tag}>{}</{tag}>", escape(text)?),
+            None => format!("<{tag}/>"),
+        };
+        self.line(&line);
+        Ok(())
+    }
+
+    fn enter(&mut self, container: &Bound<'_, PyAny>, tag: &str, kind: &str) -> PyResult<()> {
+        let address = container.as_ptr() as usize;
+        if self.open.contains(&address) {
+            return Err(PyTypeError::new_err(format!(
+                "cannot write recursive {kind}"
+            )));
+        }
+        self.open.push(address);
+        self.line(&format!("<{tag}>"));
+        self.indent_level += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self, tag: &str) {
+        self.indent_level -= 1;
+        self.line(&format!("</{tag}>"));
+        self.open.pop();
+    }
+
+    /// Base64 lines at the level of the `<data>` tags, as long as the indentation allows
+    /// with tabs counted as eight columns.
+    fn data(&mut self, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = data.py();
+        let bytes = py
+            .get_type::<PyBytes>()
+            .call1((data,))?
+            .downcast_into::<PyBytes>()?;
+        let line_length = 76_usize.saturating_sub(8 * self.indent_level).max(16);
+        let binascii = py.import("binascii")?;
+        self.line("<data>");
+        for chunk in bytes.as_bytes().chunks(line_length / 4 * 3) {
+            let encoded = binascii.call_method(
+                "b2a_base64",
+                (PyBytes::new(py, chunk),),
+                Some(&[("newline", false)].into_py_dict(py)?),
+            )?;
+            self.line(&String::from_utf8_lossy(
+                encoded.downcast::<PyBytes>()?.as_bytes(),
+            ));
+        }
+        self.line("</data>");
+        Ok(())
+    }
+}
+
+/// Escapes a string, which plists keep free of control characters, with line breaks
+/// normalized to `\n`.
+fn escape(text: &str) -> PyResult<String> {
+    if text
+        .chars()
+        .any(|ch| ch < ' ' && !matches!(ch, '\t' | '\n' | '\r'))
+    {
+        return Err(PyValueError::new_err(
+            "strings can't contain control characters; use bytes instead",
+        ));
+    }
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    escape_xml(&text, InvalidChars::Raise)
+        .map(std::borrow::Cow::into_owned)
+        .map_err(PyValueError::new_err)
+}
+
+/// Reads a property list as `plistlib.loads()` does, building dicts with `dict_constructor`.
+///
+/// # Errors
+///
+/// Raises `ValueError` for unknown tags, values that do not convert, dicts without
+/// alternating keys and values, and documents not holding exactly one value.
+pub fn loads<'py>(
+    py: Python<'py>,
+    events: Vec<(XmlEvent, usize)>,
+    dict_constructor: Option<&Py<PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    // The values read so far, each marked when it is a dict key.
+    let mut values: Vec<(bool, Bound<'py, PyAny>)> = Vec::new();
+    // Where each open array or dict starts in `values`.
+    let mut marks: Vec<usize> = Vec::new();
+    let mut data = String::new();
+
+    for (event, line) in events {
+        match event {
+            XmlEvent::Start { name, .. } => {
+                if !is_tag(&name) {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown plist tag {name:?} (line {line})"
+                    )));
+                }
+                if name == "array" || name == "dict" {
+                    marks.push(values.len());
+                }
+                data.clear();
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => data.push_str(&text),
+            XmlEvent::End(name) => {
+                let bad_value =
+                    || PyValueError::new_err(format!("bad {name} value {data:?} (line {line})"));
+                let value = match name.as_str() {
+                    "key" | "string" => PyString::new(py, &data).into_any(),
+                    "true" => PyBool::new(py, true).to_owned().into_any(),
+                    "false" => PyBool::new(py, false).to_owned().into_any(),
+                    "integer" => {
+                        let text = data.trim();
+                        let int = py.get_type::<PyInt>();
+                        match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                            Some(hex) => int.call1((hex, 16)),
+                            None => int.call1((text,)),
+                        }
+                        .map_err(|_err| bad_value())?
+                    }
+                    "real" => py
+                        .get_type::<PyFloat>()
+                        .call1((data.as_str(),))
+                        .map_err(|_err| bad_value())?,
+                    "date" => date(py, &data).ok_or_else(bad_value)??,
+                    "data" => py
+                        .import("binascii")?
+                        .call_method1("a2b_base64", (data.as_str(),))
+                        .map_err(|_err| bad_value())?,
+                    "array" => {
+                        let start = marks.pop().unwrap_or_default();
+                        let mut items = Vec::new();
+                        for (is_key, item) in values.drain(start..) {
+                            if is_key {
+                                return Err(PyValueError::new_err(format!(
+                                    "unexpected key in array (line {line})"
+                                )));
+                            }
+                            items.push(item);
+                        }
+                        PyList::new(py, items)?.into_any()
+                    }
+                    "dict" => {
+                        let start = marks.pop().unwrap_or_default();
+                        let dict = match dict_constructor {
+                            Some(constructor) => constructor.bind(py).call0()?,
+                            None => PyDict::new(py).into_any(),
+                        };
+                        let mut drained = values.drain(start..);
+                        while let Some((is_key, key)) = drained.next() {
+                            let member = match drained.next() {
+                                Some((false, member)) if is_key => member,
+                                Some(_) | None => {
+                                    return Err(PyValueError::new_err(format!(
+                                        "dict entries must be a key followed by a value \
+                                         (line {line})"
+                                    )))
+                                }
+                            };
+                            dict.set_item(key, member)?;
+                        }
+                        drop(drained);
+                        dict
+                    }
+                    _ => continue,
+                };
+                values.push((name == "key", value));
+                data.clear();
+            }
+            XmlEvent::DocType(_) | XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction(_) => {}
+        }
+    }
+
+    match values.pop() {
+        Some((false, value)) if values.is_empty() => Ok(value),
+        Some(_) | None => Err(PyValueError::new_err("a plist must hold exactly one value")),
+    }
+}
+
+fn is_tag(name: &str) -> bool {
+    matches!(
+        name,
+        "plist"
+            | "dict"
+            | "key"
+            | "array"
+            | "string"
+            | "integer"
+            | "real"
+            | "true"
+            | "false"
+            | "date"
+            | "data"
+    )
+}
+
+/// A `YYYY-MM-DDTHH:MM:SSZ` date as a naive `datetime`, where everything after the year may
+/// be left out, or `None` when it is not one.
+fn date<'py>(py: Python<'py>, text: &str) -> Option<PyResult<Bound<'py, PyAny>>> {
+    let text = text.trim().strip_suffix('Z')?;
+    let mut fields = [1_u32, 1, 1, 0, 0, 0];
+    let mut rest = text;
+    for (index, field) in fields.iter_mut().enumerate() {
+        let width = if index == 0 { 4 } else { 2 };
+        let digits = rest.get(..width)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        *field = digits.parse().ok()?;
+        rest = rest.get(width..)?;
+        let separator = match index {
+            0 | 1 => '-',
+            2 => 'T',
+            _ => ':',
+        };
+        match rest.strip_prefix(separator) {
+            Some(next) if index < 5 => rest = next,
+            Some(_) | None if rest.is_empty() => break,
+            Some(_) | None => return None,
+        }
+    }
+    let [year, month, day, hour, minute, second] = fields;
+    let small = |value: u32| u8::try_from(value).ok();
+    Some(
+        PyDateTime::new(
+            py,
+            i32::try_from(year).ok()?,
+            small(month)?,
+            small(day)?,
+            small(hour)?,
+            small(minute)?,
+            small(second)?,
+            0,
+            None,
+        )
+        .map(Bound::into_any),
+    )
+}
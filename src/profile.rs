@@ -0,0 +1,131 @@
+use crate::events::{EventReader, XmlEvent};
+use crate::xmltodict_core::Error;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Names with a number each, in the order the names were first found.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    index: HashMap<String, usize>,
+    pub entries: Vec<(String, usize)>,
+}
+
+impl Tally {
+    fn add(&mut self, name: &str) {
+        self.update(name, |count| *count += 1);
+    }
+
+    fn max(&mut self, name: &str, value: usize) {
+        self.update(name, |count| *count = (*count).max(value));
+    }
+
+    fn update(&mut self, name: &str, update: impl FnOnce(&mut usize)) {
+        let position = if let Some(&position) = self.index.get(name) {
+            position
+        } else {
+            let position = self.entries.len();
+            self.index.insert(name.to_owned(), position);
+            self.entries.push((name.to_owned(), 0));
+            position
+        };
+        if let Some((_, count)) = self.entries.get_mut(position) {
+            update(count);
+        }
+    }
+}
+
+/// What `profile` reports about the shape of a document, gathered from its events without
+/// building anything for its elements. Names are as written in the document.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub elements: usize,
+    pub attributes: usize,
+    /// Nesting of the deepest element, 1 for the root.
+    pub max_depth: usize,
+    /// The most elements of one name under a single parent.
+    pub max_siblings: usize,
+    /// How many elements have each name.
+    pub tags: Tally,
+    /// How many elements have an attribute of each name.
+    pub attribute_names: Tally,
+    /// For each name found more than once under a parent, the most found under one; these
+    /// are the elements `parse` may give as a list.
+    pub repeated: Tally,
+}
+
+impl Profile {
+    /// Profiles a whole document.
+    pub fn read<R: BufRead>(mut events: EventReader<'_, R>) -> Result<Self, Error> {
+        let mut profile = Self::default();
+        // How many children of each name the open elements, and the document, have so far.
+        let mut children = vec![Tally::default()];
+        while let Some(event) = events.next_event()? {
+            match event {
+                XmlEvent::Start { name, attributes } => {
+                    profile.elements += 1;
+                    profile.attributes += attributes.len();
+                    for (attribute, _) in &attributes {
+                        profile.attribute_names.add(attribute);
+                    }
+                    profile.tags.add(&name);
+                    if let Some(siblings) = children.last_mut() {
+                        siblings.add(&name);
+                    }
+                    profile.max_depth = profile.max_depth.max(children.len());
+                    children.push(Tally::default());
+                }
+                XmlEvent::End(_) => {
+                    if let Some(siblings) = children.pop() {
+                        profile.count_siblings(siblings);
+                    }
+                }
+                XmlEvent::Text(_)
+                | XmlEvent::Whitespace(_)
+                | XmlEvent::DocType(_)
+                | XmlEvent::Comment(_)
+                | XmlEvent::ProcessingInstruction(_) => {}
+            }
+        }
+        for siblings in children {
+            profile.count_siblings(siblings);
+        }
+        Ok(profile)
+    }
+
+    fn count_siblings(&mut self, siblings: Tally) {
+        for (name, count) in siblings.entries {
+            self.max_siblings = self.max_siblings.max(count);
+            if count > 1 {
+                self.repeated.max(&name, count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParseConfig;
+
+    #[test]
+    fn counts_elements_attributes_and_repeated_siblings() {
+        let xml = r#"<r a="1"><i b="2"/><i/><g><i/><i/><i/><x b="3"/></g></r>"#;
+        let config = ParseConfig::default();
+        let profile = Profile::read(EventReader::new(xml.as_bytes(), &config));
+        let profile = profile.unwrap_or_default();
+        assert_eq!(8, profile.elements);
+        assert_eq!(3, profile.attributes);
+        assert_eq!(3, profile.max_depth);
+        assert_eq!(3, profile.max_siblings);
+        let entries = |tally: &Tally| {
+            tally
+                .entries
+                .iter()
+                .map(|(name, count)| format!("{name}={count}"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["r=1", "i=5", "g=1", "x=1"], entries(&profile.tags));
+        assert_eq!(vec!["a=1", "b=2"], entries(&profile.attribute_names));
+        assert_eq!(vec!["i=3"], entries(&profile.repeated));
+    }
+}
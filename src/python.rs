@@ -0,0 +1,3589 @@
+use crate::asyncio::{AsyncChunks, AsyncWrite, Offloaded};
+use crate::chunks::UnparseIterator;
+use crate::config::{
+    check_xml_version, AttrPrefix, CdataKey, Charset, CommentKey, Convention, Decompress, Dialect,
+    DoctypeKey, DocumentLimits, InferTypes, NamespaceSeparator, NamespacesKey, NodeFactory,
+    OrderKey, ParseConfig, PiKey, SpanKey, Strip, UnparseConfig, DEFAULT_MAX_DEPTH,
+};
+use crate::convention;
+use crate::dtd::EntityLimits;
+use crate::encoding::{decode_document, lookup_encoding, transcode};
+use crate::error::{
+    add_note, issue_list, parse_error, pyerr_from_io, validation_error, DepthLimitExceeded,
+    EntitiesForbidden, LimitExceeded, SizeLimitExceeded, SoapFault, XmlEncodingError,
+    XmlSyntaxError, XmlValidationError,
+};
+use crate::events::{collect_document, collect_document_into, Document, EventReader, XmlEvent};
+use crate::feed;
+use crate::handler::Handler;
+use crate::iterparse::{EventKinds, Input, IterParse, ParseMany};
+use crate::node::{self, Node};
+use crate::parser::{native_force_list, ForceList, Hooks, XmlParser};
+use crate::path_pattern::{ListedElements, PathMap, PathPattern};
+use crate::pipeline::{Pipeline, Rules};
+use crate::plist::{self, PlistWriter};
+use crate::profile::{Profile, Tally};
+use crate::query::Query;
+use crate::reader::{
+    compression, decompressing, DecodingRead, Decompressed, MappedFile, PyFileLikeRead,
+    PyGeneratorRead,
+};
+use crate::records::{write_csv_row, Records};
+use crate::reformat::{reformat, Layout};
+use crate::reusable::{Parser, Serializer};
+use crate::selection::Selection;
+use crate::soap;
+use crate::transforms::{extract_transforms, Transforms};
+use crate::unparser::{
+    as_dict, encode_output, model_value, output_charset, DefaultHook, Sink, StreamWriter, XmlWriter,
+};
+use crate::xmlrpc::{self, Marshaller};
+use crate::xmltodict_core::{self, JsonFormat, SyntaxError, XmlMap};
+use crate::xsd::{self, Model, Schema};
+
+use encoding_rs::Encoding;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyIterator, PyList, PyModule, PyString, PyTuple};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::num::NonZeroUsize;
+use std::panic::resume_unwind;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Generators and any other iterator (`iter(chunks)`, `map(...)`) are consumed chunk by chunk.
+fn is_chunk_iterator(xml_input: &Bound<'_, PyAny>) -> bool {
+    xml_input.downcast::<PyIterator>().is_ok()
+}
+
+fn extract_hashmap(py: Python, dict_input: &Py<PyAny>) -> PyResult<HashMap<String, String>> {
+    let dict = dict_input.downcast_bound::<PyDict>(py).map_err(|_err| {
+        PyErr::new::<pyo3::exceptions::PyTypeError, _>("namespaces must be a dictionary")
+    })?;
+
+    let mut hashmap = HashMap::with_capacity(dict.len());
+
+    for (key, value) in dict {
+        let key_str = key.downcast::<PyString>().map_err(|_err| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("namespace keys must be strings")
+        })?;
+
+        // `None` drops the namespace from names, as an empty prefix does.
+        let value_str = if value.is_none() {
+            String::new()
+        } else {
+            value
+                .downcast::<PyString>()
+                .map_err(|_err| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                        "namespace values must be strings or None",
+                    )
+                })?
+                .to_string()
+        };
+
+        hashmap.insert(key_str.to_string(), value_str);
+    }
+
+    Ok(hashmap)
+}
+
+/// Namespace maps for unparse keep their order and allow `None` for the default namespace.
+pub fn extract_namespace_map(
+    namespaces: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<(String, String)>> {
+    let Some(namespaces) = namespaces else {
+        return Ok(Vec::new());
+    };
+    let dict = namespaces.downcast::<PyDict>().map_err(|_err| {
+        PyErr::new::<pyo3::exceptions::PyTypeError, _>("namespaces must be a dictionary")
+    })?;
+
+    let mut map = Vec::with_capacity(dict.len());
+    for (key, value) in dict {
+        let uri = key.downcast::<PyString>().map_err(|_err| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("namespace keys must be strings")
+        })?;
+        let prefix = if value.is_none() {
+            String::new()
+        } else {
+            value
+                .downcast::<PyString>()
+                .map_err(|_err| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                        "namespace values must be strings or None",
+                    )
+                })?
+                .to_string()
+        };
+        map.push((uri.to_string(), prefix));
+    }
+    Ok(map)
+}
+
+/// Element names given as any iterable of strings; a bare string is rejected rather than
+/// split into characters.
+pub fn extract_key_set(keys: Option<&Bound<'_, PyAny>>, option: &str) -> PyResult<HashSet<String>> {
+    let Some(keys) = keys else {
+        return Ok(HashSet::new());
+    };
+    if keys.is_instance_of::<PyString>() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "{option} must be an iterable of strings, not a string"
+        )));
+    }
+    keys.try_iter()?
+        .map(|key| {
+            key?.downcast_into::<PyString>()
+                .map(|key| key.to_string())
+                .map_err(|_err| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                        "{option} must contain strings"
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// `infer_types` is either a bool enabling every kind or an iterable of kind names.
+pub fn extract_infer_types(kinds: Option<&Bound<'_, PyAny>>) -> PyResult<InferTypes> {
+    let Some(kinds) = kinds else {
+        return Ok(InferTypes::default());
+    };
+    if let Ok(enabled) = kinds.downcast::<PyBool>() {
+        return Ok(if enabled.is_true() {
+            InferTypes::ALL
+        } else {
+            InferTypes::default()
+        });
+    }
+    if kinds.is_instance_of::<PyString>() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "infer_types must be a bool or an iterable of strings, not a string",
+        ));
+    }
+    let mut infer_types = InferTypes::default();
+    for kind in kinds.try_iter()? {
+        infer_types
+            .enable(&kind?.extract::<String>()?)
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    }
+    Ok(infer_types)
+}
+
+/// `strip` names the ends stripped; without it, `strip_whitespace` strips both or neither.
+pub fn extract_strip(strip: Option<&str>, strip_whitespace: bool) -> PyResult<Strip> {
+    match strip {
+        Some(strip) => strip
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>),
+        None => Ok(Strip::from_flag(strip_whitespace)),
+    }
+}
+
+/// `events` names the `iterparse` events to yield, by default starts, ends and text.
+fn extract_event_kinds(events: Option<&Bound<'_, PyAny>>) -> PyResult<EventKinds> {
+    let Some(events) = events else {
+        return Ok(EventKinds::DEFAULT);
+    };
+    if events.is_instance_of::<PyString>() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "events must be an iterable of strings, not a string",
+        ));
+    }
+    let mut kinds = EventKinds::default();
+    for kind in events.try_iter()? {
+        kinds
+            .enable(&kind?.extract::<String>()?)
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    }
+    Ok(kinds)
+}
+
+/// `key_map` maps entry keys or paths to the keys they are stored under.
+fn extract_key_map(key_map: Option<&Bound<'_, PyDict>>) -> PyResult<PathMap<String>> {
+    let mut result = PathMap::default();
+    for (key, new_key) in key_map.into_iter().flatten() {
+        result.insert(key.extract()?, new_key.extract()?);
+    }
+    Ok(result)
+}
+
+/// Reads a schema document, given as any input `parse` accepts.
+fn read_model(py: Python, xsd: &Bound<'_, PyAny>) -> PyResult<Model> {
+    read_events(py, xsd, None, &ParseConfig::default())
+        .and_then(|events| Model::new(events).map_err(|err| parse_error(py, err)))
+        .inspect_err(|err| add_note(py, err, "while reading the xsd"))
+}
+
+/// Tokenizes the document with the GIL released.
+fn tokenize<R: BufRead + Send>(py: Python, reader: R, config: &ParseConfig) -> PyResult<Document> {
+    py.detach(|| collect_document(reader, config))
+        .map_err(|err| parse_error(py, err))
+}
+
+/// Streamed inputs are transcoded to UTF-8 chunk by chunk, sniffing the encoding if not given.
+fn tokenize_stream<R: Read + Send>(
+    py: Python,
+    raw: R,
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> PyResult<Document> {
+    let reader = match encoding {
+        Some(enc) => DecodingRead::new(raw, enc),
+        None => DecodingRead::sniffing(raw),
+    };
+    tokenize(py, BufReader::new(reader), config)
+}
+
+/// Tokenizes any input `parse` accepts: text, bytes, a binary file-like object, an iterator
+/// of chunks or a buffer.
+fn read_events(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    config: &ParseConfig,
+) -> PyResult<Vec<(XmlEvent, usize)>> {
+    read_document(py, xml_input, encoding, config).map(|(events, _, _)| events)
+}
+
+fn read_document(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    config: &ParseConfig,
+) -> PyResult<Document> {
+    let encoding = encoding.map(lookup_encoding).transpose()?;
+    read_input(py, xml_input, encoding, config)
+}
+
+/// Tokenizes any input `parse` accepts, with its encoding already looked up.
+pub fn read_input(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> PyResult<Document> {
+    if let Ok(xml_str) = xml_input.downcast::<PyString>() {
+        let text = xml_str.to_str()?;
+        return tokenize(py, text.as_bytes(), config);
+    }
+
+    if let Ok(xml_bytes) = xml_input.downcast::<PyBytes>() {
+        return tokenize_bytes(py, xml_bytes.as_bytes(), encoding, config);
+    }
+
+    if let Ok(read_attr) = xml_input.getattr("read") {
+        if read_attr.is_callable() {
+            let raw = PyFileLikeRead::new(xml_input.clone().unbind());
+            let raw = decompressing(raw, config.decompress);
+            return tokenize_stream(py, raw, encoding, config);
+        }
+    }
+
+    if is_chunk_iterator(xml_input) {
+        let raw = PyGeneratorRead::new(xml_input.clone().unbind());
+        let raw = decompressing(raw, config.decompress);
+        return tokenize_stream(py, raw, encoding, config);
+    }
+
+    if let Ok(mapped) = xml_input.downcast::<MappedFile>() {
+        return tokenize_bytes(py, mapped.get().bytes(), encoding, config);
+    }
+    tokenize_bytes(py, xml_input.extract::<&[u8]>()?, encoding, config)
+}
+
+/// Like `read_input`, reading text and bytes through `buf`, which keeps its capacity for the
+/// next document.
+fn read_document_into(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+    buf: &mut Vec<u8>,
+) -> PyResult<Document> {
+    if let Ok(text) = xml_input.downcast::<PyString>() {
+        let text = text.to_str()?;
+        return py
+            .detach(|| collect_document_into(text.as_bytes(), config, buf))
+            .map_err(|err| parse_error(py, err));
+    }
+    if let Ok(bytes) = xml_input.downcast::<PyBytes>() {
+        let bytes = bytes.as_bytes();
+        return py
+            .detach(|| collect_bytes_into(bytes, encoding, config, buf))
+            .map_err(|err| parse_error(py, err));
+    }
+    read_input(py, xml_input, encoding, config)
+}
+
+/// Bytes are transcoded as well as tokenized without holding the GIL, so that large
+/// documents, such as files from `parse_file()`, leave other threads free to run meanwhile.
+fn tokenize_bytes(
+    py: Python,
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> PyResult<Document> {
+    py.detach(|| collect_bytes(bytes, encoding, config))
+        .map_err(|err| parse_error(py, err))
+}
+
+/// Tokenizes encoded bytes, which need no Python objects to read. Compressed bytes are
+/// decompressed and transcoded as they are tokenized, rather than held in memory
+/// decompressed.
+fn collect_bytes(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> Result<Document, xmltodict_core::Error> {
+    collect_bytes_into(bytes, encoding, config, &mut Vec::new())
+}
+
+/// Like `collect_bytes`, reading markup through `buf`, which keeps its capacity for the next
+/// document.
+pub fn collect_bytes_into(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+    buf: &mut Vec<u8>,
+) -> Result<Document, xmltodict_core::Error> {
+    collect_document_into(bytes_reader(bytes, encoding, config)?, config, buf)
+}
+
+/// Reads encoded bytes as UTF-8, decompressing them as they are read if they are compressed.
+fn bytes_reader<'a>(
+    bytes: &'a [u8],
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> Result<Box<dyn BufRead + Send + 'a>, xmltodict_core::Error> {
+    if let Some(format) = compression(config.decompress, bytes) {
+        let raw = Decompressed::new(bytes, Some(format)).map_err(xmltodict_core::Error::Io)?;
+        let reader = match encoding {
+            Some(enc) => DecodingRead::new(raw, enc),
+            None => DecodingRead::sniffing(raw),
+        };
+        return Ok(Box::new(BufReader::new(reader)));
+    }
+    let utf8 = transcode(bytes, encoding)
+        .map_err(|msg| SyntaxError::new(SyntaxError::INCORRECT_ENCODING, msg))?;
+    Ok(Box::new(Cursor::new(utf8)))
+}
+
+fn io_error(py: Python, err: std::io::Error) -> PyErr {
+    parse_error(py, xmltodict_core::Error::Io(err))
+}
+
+/// An owned reader over any input `parse` accepts, for tokenizing it lazily. Text and bytes
+/// are copied; file-like objects and chunk iterators are read as events are requested.
+fn lazy_input(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    decompress: Decompress,
+) -> PyResult<Input> {
+    let encoding = encoding.map(lookup_encoding).transpose()?;
+    lazy_encoded_input(py, xml_input, encoding, decompress)
+}
+
+/// Like `lazy_input`, with the encoding already looked up.
+fn lazy_encoded_input(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&'static Encoding>,
+    decompress: Decompress,
+) -> PyResult<Input> {
+    if let Ok(xml_str) = xml_input.downcast::<PyString>() {
+        return Ok(Box::new(Cursor::new(xml_str.to_str()?.as_bytes().to_vec())));
+    }
+
+    if let Ok(read_attr) = xml_input.getattr("read") {
+        if read_attr.is_callable() {
+            let raw = PyFileLikeRead::new(xml_input.clone().unbind());
+            let raw = decompressing(raw, decompress);
+            return Ok(decoding_input(raw, encoding));
+        }
+    }
+
+    if is_chunk_iterator(xml_input) {
+        let raw = PyGeneratorRead::new(xml_input.clone().unbind());
+        let raw = decompressing(raw, decompress);
+        return Ok(decoding_input(raw, encoding));
+    }
+
+    let bytes = xml_input.extract::<&[u8]>()?;
+    if let Some(format) = compression(decompress, bytes) {
+        let raw = Decompressed::new(Cursor::new(bytes.to_vec()), Some(format))
+            .map_err(|err| io_error(py, err))?;
+        return Ok(decoding_input(raw, encoding));
+    }
+    Ok(Box::new(Cursor::new(
+        decode_document(py, bytes, encoding)?.into_owned(),
+    )))
+}
+
+fn decoding_input<R: Read + Send + 'static>(raw: R, encoding: Option<&'static Encoding>) -> Input {
+    let reader = match encoding {
+        Some(enc) => DecodingRead::new(raw, enc),
+        None => DecodingRead::sniffing(raw),
+    };
+    Box::new(BufReader::new(reader))
+}
+
+/// Events tokenized at a time, with the GIL released, by `parse` before it builds them.
+const BATCH_EVENTS: usize = 4096;
+
+/// Builds the Python objects for a tokenized document.
+pub fn build_dict(
+    py: Python,
+    events: Vec<(XmlEvent, usize)>,
+    config: &ParseConfig,
+    hooks: Hooks,
+) -> PyResult<Py<PyAny>> {
+    build_with(py, &mut XmlParser::new(config.clone(), hooks), events)
+}
+
+/// Builds the Python objects for a tokenized document with a builder kept between documents,
+/// which is left empty for the next one.
+pub fn build_with(
+    py: Python,
+    parser: &mut XmlParser,
+    events: Vec<(XmlEvent, usize)>,
+) -> PyResult<Py<PyAny>> {
+    let built = build_events(py, parser, events);
+    parser.reset();
+    built
+}
+
+fn build_events(
+    py: Python,
+    parser: &mut XmlParser,
+    events: Vec<(XmlEvent, usize)>,
+) -> PyResult<Py<PyAny>> {
+    add_events(py, parser, events)?;
+    parser.finish(py)
+}
+
+/// Hands events to the builder, which may not have the whole document yet.
+fn add_events(py: Python, parser: &mut XmlParser, events: Vec<(XmlEvent, usize)>) -> PyResult<()> {
+    for (event, line) in events {
+        parser.line = line;
+        match event {
+            XmlEvent::Start { name, attributes } => parser.start_element(py, &name, attributes)?,
+            XmlEvent::End(_) => parser.end_element(py)?,
+            XmlEvent::Text(text) => parser.characters(text),
+            XmlEvent::Whitespace(text) => parser.whitespace(text),
+            XmlEvent::DocType(doctype) => parser.doctype(&doctype),
+            XmlEvent::Comment(comment) => parser.comment(py, &comment)?,
+            XmlEvent::ProcessingInstruction(pi) => parser.processing_instruction(py, &pi)?,
+        }
+    }
+    Ok(())
+}
+
+/// Reads one of the names an option takes.
+fn choice<T: FromStr<Err = String>>(value: &str) -> PyResult<T> {
+    value
+        .parse()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)
+}
+
+/// The dialect, convention and node factory `parse` builds its result with, refused where
+/// they or the options depending on them don't go together.
+fn output_options(
+    dialect: &str,
+    convention: &str,
+    node_factory: &str,
+    with_spans: bool,
+    only_paths: bool,
+) -> PyResult<(Dialect, Convention, NodeFactory)> {
+    let dialect: Dialect = choice(dialect)?;
+    let convention: Convention = choice(convention)?;
+    let node_factory: NodeFactory = choice(node_factory)?;
+    if node_factory == NodeFactory::Ordered
+        && (dialect != Dialect::Xml || convention != Convention::Xmltodict)
+    {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "node_factory='ordered' builds nodes, which have no dialect or convention",
+        ));
+    }
+    if with_spans
+        && (dialect != Dialect::Xml
+            || convention != Convention::Xmltodict
+            || node_factory != NodeFactory::Dict
+            || only_paths)
+    {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "with_spans only applies to the dicts of the xmltodict convention, \
+             without only_paths",
+        ));
+    }
+    Ok((dialect, convention, node_factory))
+}
+
+/// The schema given as `xsd`, whose attribute and text keys are those of the dicts built.
+fn read_schema(
+    py: Python,
+    xsd: Option<&Bound<'_, PyAny>>,
+    attr_prefix: &str,
+    cdata_key: &str,
+) -> PyResult<Schema> {
+    Ok(match xsd {
+        Some(xsd) => Schema::new(&read_model(py, xsd)?, attr_prefix, cdata_key),
+        None => Schema::default(),
+    })
+}
+
+/// The options property lists are read with: only those for reading the document apply,
+/// and strings keep their whitespace.
+fn plist_config(config: &ParseConfig) -> ParseConfig {
+    ParseConfig {
+        strip: Strip::None,
+        disable_entities: config.disable_entities,
+        forbid_dtd: config.forbid_dtd,
+        forbid_entities: config.forbid_entities,
+        entity_limits: config.entity_limits,
+        limits: config.limits,
+        decompress: config.decompress,
+        ..ParseConfig::default()
+    }
+}
+
+/// Parse XML string/bytes into a Python dictionary
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    process_namespaces = false,
+    namespace_separator = ":",
+    disable_entities = true,
+    process_comments = false,
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_cdata = false,
+    cdata_separator = "",
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    force_list = None,
+    postprocessor = None,
+    item_depth = 0,
+    comment_key = "#comment",
+    namespaces = None,
+    max_entity_depth = 16,
+    max_entity_expansion = 10_000_000,
+    forbid_dtd = false,
+    forbid_entities = false,
+    preserve_mixed_content = false,
+    process_doctype = false,
+    doctype_key = "#doctype",
+    process_pis = false,
+    pi_key = "#pi",
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+    attr_dict_key = None,
+    infer_types = None,
+    transforms = None,
+    key_map = None,
+    dict_constructor = None,
+    only_paths = None,
+    collect_namespaces = false,
+    namespaces_key = "#namespaces",
+    qname_attributes = None,
+    strip_namespace_prefixes = false,
+    dialect = "xml",
+    convention = "xmltodict",
+    xsd = None,
+    validate_against = None,
+    recover = false,
+    warnings = None,
+    void_elements = None,
+    resolve_entities = "strict",
+    decompress = "auto",
+    node_factory = "dict",
+    with_spans = false,
+    span_key = "#span",
+    raw_paths = None,
+    on_duplicate = "list",
+    index_key = None,
+    preserve_sibling_order = false,
+    order_key = "#order",
+))]
+fn parse(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    process_namespaces: bool,
+    namespace_separator: &str,
+    disable_entities: bool,
+    process_comments: bool,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_cdata: bool,
+    cdata_separator: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    force_list: Option<&Bound<'_, PyAny>>,
+    postprocessor: Option<Py<PyAny>>,
+    item_depth: usize,
+    comment_key: &str,
+    namespaces: Option<Py<PyAny>>,
+    max_entity_depth: usize,
+    max_entity_expansion: usize,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    preserve_mixed_content: bool,
+    process_doctype: bool,
+    doctype_key: &str,
+    process_pis: bool,
+    pi_key: &str,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+    attr_dict_key: Option<String>,
+    infer_types: Option<&Bound<'_, PyAny>>,
+    transforms: Option<&Bound<'_, PyDict>>,
+    key_map: Option<&Bound<'_, PyDict>>,
+    dict_constructor: Option<Py<PyAny>>,
+    only_paths: Option<Vec<String>>,
+    collect_namespaces: bool,
+    namespaces_key: &str,
+    qname_attributes: Option<Vec<String>>,
+    strip_namespace_prefixes: bool,
+    dialect: &str,
+    convention: &str,
+    xsd: Option<&Bound<'_, PyAny>>,
+    validate_against: Option<&Bound<'_, PyAny>>,
+    recover: bool,
+    warnings: Option<&Bound<'_, PyList>>,
+    void_elements: Option<&Bound<'_, PyAny>>,
+    resolve_entities: &str,
+    decompress: &str,
+    node_factory: &str,
+    with_spans: bool,
+    span_key: &str,
+    raw_paths: Option<Vec<String>>,
+    on_duplicate: &str,
+    index_key: Option<String>,
+    preserve_sibling_order: bool,
+    order_key: &str,
+) -> PyResult<Py<PyAny>> {
+    parse_options(
+        py,
+        encoding,
+        process_namespaces,
+        namespace_separator,
+        disable_entities,
+        process_comments,
+        xml_attribs,
+        attr_prefix,
+        cdata_key,
+        force_cdata,
+        cdata_separator,
+        strip_whitespace,
+        strip,
+        honor_xml_space,
+        force_list,
+        postprocessor,
+        item_depth,
+        comment_key,
+        namespaces,
+        max_entity_depth,
+        max_entity_expansion,
+        forbid_dtd,
+        forbid_entities,
+        preserve_mixed_content,
+        process_doctype,
+        doctype_key,
+        process_pis,
+        pi_key,
+        max_depth,
+        max_attributes,
+        max_text_length,
+        max_total_nodes,
+        attr_dict_key,
+        infer_types,
+        transforms,
+        key_map,
+        dict_constructor,
+        only_paths,
+        collect_namespaces,
+        namespaces_key,
+        qname_attributes,
+        strip_namespace_prefixes,
+        dialect,
+        convention,
+        xsd,
+        validate_against,
+        recover,
+        warnings,
+        void_elements,
+        resolve_entities,
+        decompress,
+        node_factory,
+        with_spans,
+        span_key,
+        raw_paths,
+        on_duplicate,
+        index_key,
+        preserve_sibling_order,
+        order_key,
+    )?
+    .parse(py, xml_input, &mut Kept::default())
+}
+
+/// What parsing one document leaves for the next with the same options.
+#[derive(Default)]
+pub struct Kept {
+    /// Markup is read through this buffer, which keeps the capacity it grew to.
+    buf: Vec<u8>,
+    /// Builds the dicts, remembering the Python strings made for keys.
+    builder: Option<XmlParser>,
+}
+
+/// The options of `parse`, checked once, for parsing any number of documents with them.
+#[pyclass(frozen, module = "xmltodict_rs")]
+pub struct ParseSetup {
+    encoding: Option<&'static Encoding>,
+    config: ParseConfig,
+    /// What documents are tokenized with: all attributes for validating them, and only the
+    /// options for reading the document for property lists.
+    tokenized: ParseConfig,
+    hooks: Hooks,
+    dialect: Dialect,
+    convention: Convention,
+    node_factory: NodeFactory,
+    only_paths: Option<Vec<String>>,
+    /// The schema of `validate_against`, read once.
+    model: Option<Model>,
+    warnings: Option<Py<PyList>>,
+}
+
+impl ParseSetup {
+    /// Parses a document, reading it through the buffer and builder the last one kept.
+    /// Unless the whole document is needed before anything is built, it is read a batch of
+    /// events at a time, so that a large one is never held as events whole.
+    pub fn parse(
+        &self,
+        py: Python,
+        xml_input: &Bound<'_, PyAny>,
+        kept: &mut Kept,
+    ) -> PyResult<Py<PyAny>> {
+        let whole = self.dialect == Dialect::Plist
+            || self.model.is_some()
+            || self.node_factory == NodeFactory::Ordered
+            || self.config.with_spans;
+        if whole {
+            let document =
+                read_document_into(py, xml_input, self.encoding, &self.tokenized, &mut kept.buf)?;
+            return self.build(py, document, kept);
+        }
+        if let Ok(text) = xml_input.downcast::<PyString>() {
+            return self.build_batches(py, text.to_str()?.as_bytes(), kept);
+        }
+        let is_stream = xml_input
+            .getattr("read")
+            .is_ok_and(|read| read.is_callable())
+            || is_chunk_iterator(xml_input);
+        if is_stream {
+            let decompress = self.tokenized.decompress;
+            let input = lazy_encoded_input(py, xml_input, self.encoding, decompress)?;
+            return self.build_batches(py, input, kept);
+        }
+        let bytes = if let Ok(bytes) = xml_input.downcast::<PyBytes>() {
+            bytes.as_bytes()
+        } else if let Ok(mapped) = xml_input.downcast::<MappedFile>() {
+            mapped.get().bytes()
+        } else {
+            xml_input.extract::<&[u8]>()?
+        };
+        let reader = py
+            .detach(|| bytes_reader(bytes, self.encoding, &self.tokenized))
+            .map_err(|err| parse_error(py, err))?;
+        self.build_batches(py, reader, kept)
+    }
+
+    /// Builds a document as it is read, tokenizing each batch of events with the GIL
+    /// released. Nothing needs the whole document: `only_paths` selects as it goes, and
+    /// what `recover` repaired is only added to `warnings` at the end.
+    fn build_batches<R: BufRead + Send>(
+        &self,
+        py: Python,
+        reader: R,
+        kept: &mut Kept,
+    ) -> PyResult<Py<PyAny>> {
+        let config = &self.config;
+        let mut reader = EventReader::new(reader, &self.tokenized);
+        reader.swap_buf(&mut kept.buf);
+        let mut selection = self.only_paths.as_ref().map(|paths| {
+            Selection::new(
+                paths,
+                config.process_namespaces || config.collect_namespaces,
+            )
+        });
+        let builder = kept
+            .builder
+            .get_or_insert_with(|| XmlParser::new(config.clone(), self.hooks.clone_ref(py)));
+        let built = loop {
+            let events = match py.detach(|| reader.next_batch(BATCH_EVENTS)) {
+                Ok(events) if events.is_empty() => break builder.finish(py),
+                Ok(events) => events,
+                Err(err) => break Err(parse_error(py, err)),
+            };
+            let events = match selection.as_mut() {
+                Some(selection) => selection.select_batch(events),
+                None => events,
+            };
+            if let Err(err) = add_events(py, builder, events) {
+                break Err(err);
+            }
+        };
+        builder.reset();
+        reader.swap_buf(&mut kept.buf);
+        if let Some(warnings) = &self.warnings {
+            for recovery in reader.take_recovered() {
+                warnings.bind(py).append(recovery.to_string())?;
+            }
+        }
+        let parsed = built?;
+        if self.convention == Convention::Xmltodict {
+            return Ok(parsed);
+        }
+        let constructor = self.hooks.dict_constructor.as_ref();
+        Ok(convention::from_default(parsed.bind(py), self.convention, constructor)?.unbind())
+    }
+
+    /// Tokenizes a document given as text or as encoded bytes, which needs no Python objects,
+    /// so several may be tokenized at once on other threads.
+    pub fn tokenize(
+        &self,
+        bytes: &[u8],
+        text: bool,
+        buf: &mut Vec<u8>,
+    ) -> Result<Document, xmltodict_core::Error> {
+        if text {
+            collect_document_into(bytes, &self.tokenized, buf)
+        } else {
+            collect_bytes_into(bytes, self.encoding, &self.tokenized, buf)
+        }
+    }
+
+    /// The options documents are tokenized with, for readers over several of them.
+    #[must_use]
+    pub fn reader_config(&self) -> &ParseConfig {
+        &self.tokenized
+    }
+
+    /// Builds the result for a tokenized document, validating it against the schema and
+    /// adding what `recover` repaired to `warnings` first.
+    pub fn build(&self, py: Python, document: Document, kept: &mut Kept) -> PyResult<Py<PyAny>> {
+        let (mut events, recovered, spans) = document;
+        let config = &self.config;
+        let constructor = self.hooks.dict_constructor.as_ref();
+        if self.dialect == Dialect::Plist {
+            return Ok(plist::loads(py, events, constructor)?.unbind());
+        }
+        if let Some(model) = &self.model {
+            let issues = py.detach(|| xsd::validate(model, &events));
+            if !issues.is_empty() {
+                return Err(validation_error(py, &issues)?);
+            }
+            if !config.xml_attribs {
+                for (event, _) in &mut events {
+                    if let XmlEvent::Start { attributes, .. } = event {
+                        attributes.clear();
+                    }
+                }
+            }
+        }
+        if let Some(warnings) = &self.warnings {
+            for recovery in recovered {
+                warnings.bind(py).append(recovery.to_string())?;
+            }
+        }
+        if let Some(paths) = &self.only_paths {
+            let declarations = config.process_namespaces || config.collect_namespaces;
+            events = Selection::new(paths, declarations).select(events);
+        }
+        if self.node_factory == NodeFactory::Ordered {
+            return Ok(node::build_tree(py, events, config)?.into_any());
+        }
+        let builder = kept
+            .builder
+            .get_or_insert_with(|| XmlParser::new(config.clone(), self.hooks.clone_ref(py)));
+        builder.set_spans(spans);
+        let parsed = build_with(py, builder, events)?;
+        if self.convention == Convention::Xmltodict {
+            return Ok(parsed);
+        }
+        Ok(convention::from_default(parsed.bind(py), self.convention, constructor)?.unbind())
+    }
+}
+
+/// Checks the options of `parse`, for `Parser`, `parse_batch` and `parse_many` to take with
+/// the same names and defaults.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    encoding = None,
+    process_namespaces = false,
+    namespace_separator = ":",
+    disable_entities = true,
+    process_comments = false,
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_cdata = false,
+    cdata_separator = "",
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    force_list = None,
+    postprocessor = None,
+    item_depth = 0,
+    comment_key = "#comment",
+    namespaces = None,
+    max_entity_depth = 16,
+    max_entity_expansion = 10_000_000,
+    forbid_dtd = false,
+    forbid_entities = false,
+    preserve_mixed_content = false,
+    process_doctype = false,
+    doctype_key = "#doctype",
+    process_pis = false,
+    pi_key = "#pi",
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+    attr_dict_key = None,
+    infer_types = None,
+    transforms = None,
+    key_map = None,
+    dict_constructor = None,
+    only_paths = None,
+    collect_namespaces = false,
+    namespaces_key = "#namespaces",
+    qname_attributes = None,
+    strip_namespace_prefixes = false,
+    dialect = "xml",
+    convention = "xmltodict",
+    xsd = None,
+    validate_against = None,
+    recover = false,
+    warnings = None,
+    void_elements = None,
+    resolve_entities = "strict",
+    decompress = "auto",
+    node_factory = "dict",
+    with_spans = false,
+    span_key = "#span",
+    raw_paths = None,
+    on_duplicate = "list",
+    index_key = None,
+    preserve_sibling_order = false,
+    order_key = "#order",
+))]
+pub fn parse_options(
+    py: Python,
+    encoding: Option<&str>,
+    process_namespaces: bool,
+    namespace_separator: &str,
+    disable_entities: bool,
+    process_comments: bool,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_cdata: bool,
+    cdata_separator: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    force_list: Option<&Bound<'_, PyAny>>,
+    postprocessor: Option<Py<PyAny>>,
+    item_depth: usize,
+    comment_key: &str,
+    namespaces: Option<Py<PyAny>>,
+    max_entity_depth: usize,
+    max_entity_expansion: usize,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    preserve_mixed_content: bool,
+    process_doctype: bool,
+    doctype_key: &str,
+    process_pis: bool,
+    pi_key: &str,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+    attr_dict_key: Option<String>,
+    infer_types: Option<&Bound<'_, PyAny>>,
+    transforms: Option<&Bound<'_, PyDict>>,
+    key_map: Option<&Bound<'_, PyDict>>,
+    dict_constructor: Option<Py<PyAny>>,
+    only_paths: Option<Vec<String>>,
+    collect_namespaces: bool,
+    namespaces_key: &str,
+    qname_attributes: Option<Vec<String>>,
+    strip_namespace_prefixes: bool,
+    dialect: &str,
+    convention: &str,
+    xsd: Option<&Bound<'_, PyAny>>,
+    validate_against: Option<&Bound<'_, PyAny>>,
+    recover: bool,
+    warnings: Option<&Bound<'_, PyList>>,
+    void_elements: Option<&Bound<'_, PyAny>>,
+    resolve_entities: &str,
+    decompress: &str,
+    node_factory: &str,
+    with_spans: bool,
+    span_key: &str,
+    raw_paths: Option<Vec<String>>,
+    on_duplicate: &str,
+    index_key: Option<String>,
+    preserve_sibling_order: bool,
+    order_key: &str,
+) -> PyResult<ParseSetup> {
+    let (dialect, convention, node_factory) = output_options(
+        dialect,
+        convention,
+        node_factory,
+        with_spans,
+        only_paths.is_some(),
+    )?;
+    // Conventions are converted from the default keys, which they replace.
+    let (attr_prefix, cdata_key, force_cdata, attr_dict_key) = match convention {
+        Convention::Xmltodict => (attr_prefix, cdata_key, force_cdata, attr_dict_key),
+        Convention::BadgerFish | Convention::Parker | Convention::GData => {
+            (convention::ATTR_PREFIX, convention::CDATA_KEY, false, None)
+        }
+    };
+
+    let config = ParseConfig {
+        xml_attribs: xml_attribs && convention != Convention::Parker,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        force_cdata,
+        cdata_separator: cdata_separator.to_owned(),
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        process_namespaces,
+        process_comments,
+        comment_key: CommentKey::new(comment_key),
+        item_depth,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        recover,
+        resolve_entities: choice(resolve_entities)?,
+        void_elements: extract_key_set(void_elements, "void_elements")?,
+        decompress: choice(decompress)?,
+        entity_limits: EntityLimits {
+            max_depth: max_entity_depth,
+            max_expansion: max_entity_expansion,
+        },
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        namespaces: namespaces
+            .map(|dict_py| extract_hashmap(py, &dict_py))
+            .transpose()?,
+        preserve_mixed_content,
+        process_doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        process_pis,
+        pi_key: PiKey::new(pi_key),
+        collect_namespaces,
+        namespaces_key: NamespacesKey::new(namespaces_key),
+        qname_attributes: qname_attributes.unwrap_or_default(),
+        strip_namespace_prefixes,
+        infer_types: extract_infer_types(infer_types)?,
+        // Built into `hooks`, as it may be a Python callable.
+        force_list: ListedElements::default(),
+        with_spans,
+        span_key: SpanKey::new(span_key),
+        raw_paths: raw_paths.unwrap_or_default(),
+        on_duplicate: choice(on_duplicate)?,
+        index_key,
+        preserve_sibling_order,
+        order_key: OrderKey::new(order_key),
+    };
+    let dict_constructor =
+        dict_constructor.filter(|constructor| !constructor.is(py.get_type::<PyDict>()));
+    let hooks = Hooks {
+        force_list: ForceList::new(force_list)?,
+        transforms: extract_transforms(transforms)?,
+        key_map: extract_key_map(key_map)?,
+        postprocessor,
+        dict_constructor,
+        schema: read_schema(py, xsd, attr_prefix, cdata_key)?,
+    };
+    let tokenized = if dialect == Dialect::Plist {
+        plist_config(&config)
+    } else {
+        ParseConfig {
+            xml_attribs: config.xml_attribs || validate_against.is_some(),
+            ..config.clone()
+        }
+    };
+    Ok(ParseSetup {
+        encoding: encoding.map(lookup_encoding).transpose()?,
+        config,
+        tokenized,
+        hooks,
+        dialect,
+        convention,
+        node_factory,
+        only_paths,
+        model: validate_against
+            .map(|xsd| read_model(py, xsd))
+            .transpose()?,
+        warnings: warnings.map(|warnings| warnings.clone().unbind()),
+    })
+}
+
+/// Parse XML, calling the handler's `start(tag, attrs)`, `end(tag)`, `data(text)`,
+/// `comment(text)` and `pi(target, data)` methods for each event instead of building a dict
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    handler,
+    encoding = None,
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn parse_events(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    handler: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Py<PyAny>> {
+    let handler = Handler::new(handler)?;
+    let config = ParseConfig {
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        process_comments: handler.wants_comments(),
+        process_pis: handler.wants_pis(),
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    handler.feed(events)
+}
+
+/// Iterate over `(event, path, value)` tuples, parsing the document only as far as the
+/// caller reads
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    events = None,
+    encoding = None,
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn iterparse(
+    py: Python,
+    source: &Bound<'_, PyAny>,
+    events: Option<&Bound<'_, PyAny>>,
+    encoding: Option<&str>,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<IterParse> {
+    let kinds = extract_event_kinds(events)?;
+    let config = ParseConfig {
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        process_comments: kinds.comment,
+        process_pis: kinds.pi,
+        ..ParseConfig::default()
+    };
+    Ok(IterParse::new(
+        lazy_input(py, source, encoding, config.decompress)?,
+        config,
+        kinds,
+    ))
+}
+
+/// Iterate over the documents of input holding several concatenated ones, such as a log of
+/// `<e/>` records, yielding each as `parse` would
+#[pyfunction]
+#[pyo3(signature = (source, **options))]
+fn parse_many(
+    py: Python,
+    source: &Bound<'_, PyAny>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<ParseMany> {
+    let setup = wrap_pyfunction!(parse_options, py)?
+        .call((), options)?
+        .downcast_into::<ParseSetup>()?;
+    let input = {
+        let setup = setup.get();
+        lazy_encoded_input(py, source, setup.encoding, setup.tokenized.decompress)?
+    };
+    Ok(ParseMany::new(input, setup.unbind()))
+}
+
+/// Parse a list of documents into a list of dictionaries, tokenizing them on several threads
+/// without holding the GIL, then building the dictionaries one after another
+#[pyfunction]
+#[pyo3(signature = (documents, threads = None, **options))]
+fn parse_batch<'py>(
+    py: Python<'py>,
+    documents: &Bound<'py, PyAny>,
+    threads: Option<usize>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyList>> {
+    if threads == Some(0) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "threads must be positive",
+        ));
+    }
+    let setup = wrap_pyfunction!(parse_options, py)?
+        .call((), options)?
+        .downcast_into::<ParseSetup>()?;
+    let setup = setup.get();
+    let documents = documents.try_iter()?.collect::<PyResult<Vec<_>>>()?;
+    let inputs = documents
+        .iter()
+        .map(|document| {
+            if let Ok(text) = document.downcast::<PyString>() {
+                return Ok((text.to_str()?.as_bytes(), true));
+            }
+            if let Ok(bytes) = document.downcast::<PyBytes>() {
+                return Ok((bytes.as_bytes(), false));
+            }
+            let type_name = document.get_type().name()?;
+            Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "documents must be str or bytes, not '{type_name}'"
+            )))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let threads = threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get));
+    let tokenized = py.detach(|| {
+        let per_thread = inputs.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = inputs
+                .chunks(per_thread)
+                .map(|part| {
+                    scope.spawn(move || {
+                        let mut buf = Vec::new();
+                        part.iter()
+                            .map(|(bytes, text)| setup.tokenize(bytes, *text, &mut buf))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|err| resume_unwind(err)))
+                .collect::<Vec<_>>()
+        })
+    });
+    let parsed = PyList::empty(py);
+    let mut kept = Kept::default();
+    for (index, document) in tokenized.into_iter().enumerate() {
+        let built = document
+            .map_err(|err| parse_error(py, err))
+            .and_then(|document| setup.build(py, document, &mut kept));
+        match built {
+            Ok(value) => parsed.append(value)?,
+            Err(err) => {
+                add_note(py, &err, &format!("in document {index}"));
+                return Err(err);
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Find the elements a path in a small subset of `XPath` selects, returning each as `parse`
+/// would, e.g. `[{'title': 'A'}]`
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    path,
+    encoding = None,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_list = None,
+    infer_types = None,
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn find<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    path: &str,
+    encoding: Option<&str>,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_list: Option<&Bound<'py, PyAny>>,
+    infer_types: Option<&Bound<'py, PyAny>>,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Bound<'py, PyList>> {
+    let query: Query = path
+        .parse()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let config = ParseConfig {
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        cdata_key: CdataKey::new(cdata_key),
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        infer_types: extract_infer_types(infer_types)?,
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    let found = PyList::empty(py);
+    for element in query.matches(events) {
+        let hooks = Hooks {
+            force_list: ForceList::new(force_list)?,
+            transforms: Transforms::default(),
+            key_map: PathMap::default(),
+            postprocessor: None,
+            dict_constructor: None,
+            schema: Schema::default(),
+        };
+        found.append(build_dict(py, element, &config, hooks)?)?;
+    }
+    Ok(found)
+}
+
+/// Parse an RSS 2.0 or Atom feed into one shape for both, with `entries` always a list and
+/// dates read as datetimes
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn parse_feed<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let config = ParseConfig {
+        process_namespaces: true,
+        namespaces: Some(feed::namespaces()),
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    let lists = PyTuple::new(py, feed::LISTS)?;
+    let hooks = Hooks {
+        force_list: ForceList::new(Some(lists.as_any()))?,
+        transforms: Transforms::default(),
+        key_map: PathMap::default(),
+        postprocessor: None,
+        dict_constructor: None,
+        schema: Schema::default(),
+    };
+    let parsed = build_dict(py, events, &config, hooks)?;
+    feed::normalize(parsed.bind(py))
+}
+
+/// Parse an XML file into a Python dictionary, memory-mapping it instead of reading it
+#[pyfunction]
+#[pyo3(signature = (path, **kwargs))]
+fn parse_file(
+    py: Python,
+    path: PathBuf,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyAny>> {
+    let mapped = py.detach(move || MappedFile::open(&path))?;
+    let parse = wrap_pyfunction!(parse, py)?;
+    Ok(parse.call((mapped,), kwargs)?.unbind())
+}
+
+/// Parse a member of a zip archive into a Python dictionary, reading it decompressed in
+/// chunks instead of extracting it
+#[pyfunction]
+#[pyo3(signature = (archive, member, **kwargs))]
+fn parse_zip_member(
+    py: Python,
+    archive: &Bound<'_, PyAny>,
+    member: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyAny>> {
+    let parse = wrap_pyfunction!(parse, py)?;
+    let archive = py
+        .import("zipfile")?
+        .getattr("ZipFile")?
+        .call1((archive,))?;
+    let parsed = archive.call_method1("open", (member,)).and_then(|file| {
+        let parsed = parse.call((&file,), kwargs);
+        file.call_method0("close")?;
+        parsed
+    });
+    archive.call_method0("close")?;
+    Ok(parsed?.unbind())
+}
+
+/// Parse chunks from an async iterable into a Python dictionary, tokenizing them on a worker
+/// thread as they arrive so the event loop is not blocked
+#[pyfunction]
+#[pyo3(signature = (source, **kwargs))]
+fn parse_async(
+    source: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Offloaded> {
+    if !source.hasattr("__aiter__")? {
+        let type_name = source.get_type().name()?;
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "source must be an async iterable, not '{type_name}'"
+        )));
+    }
+    let iterator = source.call_method0("__aiter__")?.unbind();
+    let kwargs = kwargs.map(|kwargs| kwargs.clone().unbind());
+    Ok(Offloaded::new(Box::new(move |event_loop| {
+        let py = event_loop.py();
+        let chunks = Bound::new(py, AsyncChunks::new(iterator, event_loop))?;
+        let parse = wrap_pyfunction!(parse, py)?;
+        py.import("functools")?.getattr("partial")?.call(
+            (parse, chunks),
+            kwargs.as_ref().map(|kwargs| kwargs.bind(py)),
+        )
+    })))
+}
+
+/// Shared by `parse_in_thread` calls that name no executor of their own.
+static EXECUTOR: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Parse XML on a worker thread, returning a `concurrent.futures.Future` for the dictionary
+#[pyfunction]
+#[pyo3(signature = (xml_input, executor = None, **kwargs))]
+fn parse_in_thread<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    executor: Option<&Bound<'py, PyAny>>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let executor = match executor {
+        Some(executor) => executor.clone(),
+        None => EXECUTOR
+            .get_or_try_init(py, || {
+                let pool = py
+                    .import("concurrent.futures")?
+                    .getattr("ThreadPoolExecutor")?;
+                let options = PyDict::new(py);
+                options.set_item("thread_name_prefix", "xmltodict_rs")?;
+                pool.call((), Some(&options)).map(Bound::unbind)
+            })?
+            .bind(py)
+            .clone(),
+    };
+    let parse = wrap_pyfunction!(parse, py)?;
+    executor.call_method("submit", (parse, xml_input), kwargs)
+}
+
+/// The DOCTYPE, comment, processing instruction and order entries sit next to the root
+/// element and do not count as one.
+pub fn check_document_roots(
+    input_dict: &Bound<'_, PyDict>,
+    config: &UnparseConfig,
+) -> PyResult<()> {
+    let roots = input_dict.len()
+        - usize::from(input_dict.contains(&*config.doctype_key)?)
+        - usize::from(input_dict.contains(&*config.comment_key)?)
+        - usize::from(input_dict.contains(&*config.pi_key)?)
+        - usize::from(input_dict.contains(&*config.order_key)?);
+    if config.full_document && roots != 1 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Document must have exactly one root",
+        ));
+    }
+    Ok(())
+}
+
+/// Convert Python dictionary back to XML string
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    input_dict,
+    output = None,
+    encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    pretty = false,
+    newl = "\n",
+    indent = "\t",
+    preprocessor = None,
+    namespace_separator = ":",
+    namespaces = None,
+    preserve_mixed_content = false,
+    doctype = None,
+    doctype_key = "#doctype",
+    escape_control_chars = true,
+    escape_apos = false,
+    invalid_chars = "raise",
+    use_cdata = false,
+    cdata_for_keys = None,
+    comment_key = "#comment",
+    pi_key = "#pi",
+    attr_dict_key = None,
+    canonical = false,
+    standalone = None,
+    xml_version = "1.0",
+    pretty_depth = None,
+    default = None,
+    datetime_format = None,
+    expand_iter = None,
+    duplicate_attributes = "raise",
+    dialect = "xml",
+    convention = "xmltodict",
+    pydantic_support = false,
+    escape_non_ascii = false,
+    return_bytes = false,
+    initial_buffer_size = None,
+    preserve_sibling_order = false,
+    order_key = "#order",
+    index_key = None,
+))]
+fn unparse(
+    py: Python,
+    input_dict: &Bound<'_, PyAny>,
+    output: Option<&Bound<'_, PyAny>>,
+    encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    pretty: bool,
+    newl: &str,
+    indent: &str,
+    preprocessor: Option<Py<PyAny>>,
+    namespace_separator: &str,
+    namespaces: Option<&Bound<'_, PyAny>>,
+    preserve_mixed_content: bool,
+    doctype: Option<String>,
+    doctype_key: &str,
+    escape_control_chars: bool,
+    escape_apos: bool,
+    invalid_chars: &str,
+    use_cdata: bool,
+    cdata_for_keys: Option<&Bound<'_, PyAny>>,
+    comment_key: &str,
+    pi_key: &str,
+    attr_dict_key: Option<String>,
+    canonical: bool,
+    standalone: Option<bool>,
+    xml_version: &str,
+    pretty_depth: Option<usize>,
+    default: Option<Py<PyAny>>,
+    datetime_format: Option<String>,
+    expand_iter: Option<String>,
+    duplicate_attributes: &str,
+    dialect: &str,
+    convention: &str,
+    pydantic_support: bool,
+    escape_non_ascii: bool,
+    return_bytes: bool,
+    initial_buffer_size: Option<usize>,
+    preserve_sibling_order: bool,
+    order_key: &str,
+    index_key: Option<String>,
+) -> PyResult<Py<PyAny>> {
+    if return_bytes && output.is_some() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "return_bytes cannot be combined with output",
+        ));
+    }
+    let dialect: Dialect = dialect
+        .parse()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let convention: Convention = convention
+        .parse()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    // Conventions are converted to the default keys, which they replace.
+    let (attr_prefix, cdata_key, attr_dict_key) = match convention {
+        Convention::Xmltodict => (attr_prefix, cdata_key, attr_dict_key),
+        Convention::BadgerFish | Convention::Parker | Convention::GData => {
+            (convention::ATTR_PREFIX, convention::CDATA_KEY, None)
+        }
+    };
+    check_xml_version(xml_version).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let sink = match output {
+        Some(stream) => Sink::stream(py, stream, encoding)?,
+        None => Sink::Buffer,
+    };
+    let config = UnparseConfig {
+        encoding: encoding.to_owned(),
+        xml_version: xml_version.to_owned(),
+        standalone,
+        full_document,
+        short_empty_elements,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        pretty,
+        pretty_depth,
+        newl: newl.to_owned(),
+        indent: indent.to_owned(),
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        namespaces: extract_namespace_map(namespaces)?,
+        preserve_mixed_content,
+        doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        escape_control_chars,
+        escape_apos,
+        invalid_chars: invalid_chars
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        escape_non_ascii,
+        charset: output_charset(py, sink.encoding().or(return_bytes.then_some(encoding)))?,
+        duplicate_attributes: duplicate_attributes
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        use_cdata,
+        cdata_for_keys: extract_key_set(cdata_for_keys, "cdata_for_keys")?,
+        comment_key: CommentKey::new(comment_key),
+        pi_key: PiKey::new(pi_key),
+        datetime_format,
+        expand_iter,
+        pydantic_support,
+        canonical,
+        preserve_sibling_order,
+        order_key: OrderKey::new(order_key),
+        index_key,
+    };
+    let config = if canonical {
+        config.canonical_form()
+    } else {
+        config
+    };
+
+    let writer = if dialect == Dialect::Plist {
+        // The value is written as a whole plist; only the output options apply.
+        let document = PlistWriter::default().dumps(input_dict, full_document, encoding)?;
+        let mut writer = XmlWriter::new(config, None, None, sink)
+            .with_capacity(initial_buffer_size.unwrap_or(0));
+        writer.write_raw(py, &document)?;
+        writer
+    } else {
+        let input_dict = &model_value(input_dict, pydantic_support)?;
+        let input_dict = &as_dict(&convention::to_default(input_dict, convention)?)?;
+        check_document_roots(input_dict, &config)?;
+        let default = default
+            .map(|callback| DefaultHook::new(py, callback))
+            .transpose()?;
+        let mut writer = XmlWriter::new(config, preprocessor, default, sink)
+            .with_capacity(initial_buffer_size.unwrap_or(0));
+        writer.write_document(py, input_dict)?;
+        writer
+    };
+
+    match writer.finish(py)? {
+        Some(result) if return_bytes => Ok(encode_output(py, &result, encoding, true)?.unbind()),
+        Some(result) => Ok(result.into_pyobject(py)?.into_any().unbind()),
+        None => Ok(py.None()),
+    }
+}
+
+/// Convert Python dictionary to XML lazily, yielding string chunks
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    input_dict,
+    chunk_size = 65536,
+    encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    pretty = false,
+    newl = "\n",
+    indent = "\t",
+    preprocessor = None,
+    namespace_separator = ":",
+    namespaces = None,
+    preserve_mixed_content = false,
+    doctype = None,
+    doctype_key = "#doctype",
+    escape_control_chars = true,
+    escape_apos = false,
+    invalid_chars = "raise",
+    use_cdata = false,
+    cdata_for_keys = None,
+    comment_key = "#comment",
+    pi_key = "#pi",
+    attr_dict_key = None,
+    canonical = false,
+    standalone = None,
+    xml_version = "1.0",
+    pretty_depth = None,
+    default = None,
+    datetime_format = None,
+    expand_iter = None,
+    duplicate_attributes = "raise",
+    pydantic_support = false,
+    escape_non_ascii = false,
+    preserve_sibling_order = false,
+    order_key = "#order",
+    index_key = None,
+))]
+fn unparse_iter(
+    input_dict: &Bound<'_, PyAny>,
+    chunk_size: usize,
+    encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    pretty: bool,
+    newl: &str,
+    indent: &str,
+    preprocessor: Option<Py<PyAny>>,
+    namespace_separator: &str,
+    namespaces: Option<&Bound<'_, PyAny>>,
+    preserve_mixed_content: bool,
+    doctype: Option<String>,
+    doctype_key: &str,
+    escape_control_chars: bool,
+    escape_apos: bool,
+    invalid_chars: &str,
+    use_cdata: bool,
+    cdata_for_keys: Option<&Bound<'_, PyAny>>,
+    comment_key: &str,
+    pi_key: &str,
+    attr_dict_key: Option<String>,
+    canonical: bool,
+    standalone: Option<bool>,
+    xml_version: &str,
+    pretty_depth: Option<usize>,
+    default: Option<Py<PyAny>>,
+    datetime_format: Option<String>,
+    expand_iter: Option<String>,
+    duplicate_attributes: &str,
+    pydantic_support: bool,
+    escape_non_ascii: bool,
+    preserve_sibling_order: bool,
+    order_key: &str,
+    index_key: Option<String>,
+) -> PyResult<UnparseIterator> {
+    if chunk_size == 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "chunk_size must be positive",
+        ));
+    }
+
+    check_xml_version(xml_version).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let config = UnparseConfig {
+        encoding: encoding.to_owned(),
+        xml_version: xml_version.to_owned(),
+        standalone,
+        full_document,
+        short_empty_elements,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        pretty,
+        pretty_depth,
+        newl: newl.to_owned(),
+        indent: indent.to_owned(),
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        namespaces: extract_namespace_map(namespaces)?,
+        preserve_mixed_content,
+        doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        escape_control_chars,
+        escape_apos,
+        invalid_chars: invalid_chars
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        escape_non_ascii,
+        charset: Charset::Unicode,
+        duplicate_attributes: duplicate_attributes
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        use_cdata,
+        cdata_for_keys: extract_key_set(cdata_for_keys, "cdata_for_keys")?,
+        comment_key: CommentKey::new(comment_key),
+        pi_key: PiKey::new(pi_key),
+        datetime_format,
+        expand_iter,
+        pydantic_support,
+        canonical,
+        preserve_sibling_order,
+        order_key: OrderKey::new(order_key),
+        index_key,
+    };
+    let config = if canonical {
+        config.canonical_form()
+    } else {
+        config
+    };
+
+    let input_dict = &as_dict(&model_value(input_dict, pydantic_support)?)?;
+    check_document_roots(input_dict, &config)?;
+
+    let default = default
+        .map(|callback| DefaultHook::new(input_dict.py(), callback))
+        .transpose()?;
+    Ok(UnparseIterator::spawn(
+        config,
+        preprocessor,
+        default,
+        input_dict.clone().unbind(),
+        chunk_size,
+    ))
+}
+
+/// Convert a list of Python dictionaries to a list of XML documents, sharing one
+/// configuration between them
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    input_dicts,
+    encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    pretty = false,
+    newl = "\n",
+    indent = "\t",
+    preprocessor = None,
+    namespace_separator = ":",
+    namespaces = None,
+    preserve_mixed_content = false,
+    doctype = None,
+    doctype_key = "#doctype",
+    escape_control_chars = true,
+    escape_apos = false,
+    invalid_chars = "raise",
+    use_cdata = false,
+    cdata_for_keys = None,
+    comment_key = "#comment",
+    pi_key = "#pi",
+    attr_dict_key = None,
+    canonical = false,
+    standalone = None,
+    xml_version = "1.0",
+    pretty_depth = None,
+    default = None,
+    datetime_format = None,
+    expand_iter = None,
+    duplicate_attributes = "raise",
+    pydantic_support = false,
+    escape_non_ascii = false,
+    return_bytes = false,
+    initial_buffer_size = None,
+    preserve_sibling_order = false,
+    order_key = "#order",
+    index_key = None,
+))]
+fn unparse_batch<'py>(
+    py: Python<'py>,
+    input_dicts: &Bound<'py, PyAny>,
+    encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    pretty: bool,
+    newl: &str,
+    indent: &str,
+    preprocessor: Option<Py<PyAny>>,
+    namespace_separator: &str,
+    namespaces: Option<&Bound<'_, PyAny>>,
+    preserve_mixed_content: bool,
+    doctype: Option<String>,
+    doctype_key: &str,
+    escape_control_chars: bool,
+    escape_apos: bool,
+    invalid_chars: &str,
+    use_cdata: bool,
+    cdata_for_keys: Option<&Bound<'_, PyAny>>,
+    comment_key: &str,
+    pi_key: &str,
+    attr_dict_key: Option<String>,
+    canonical: bool,
+    standalone: Option<bool>,
+    xml_version: &str,
+    pretty_depth: Option<usize>,
+    default: Option<Py<PyAny>>,
+    datetime_format: Option<String>,
+    expand_iter: Option<String>,
+    duplicate_attributes: &str,
+    pydantic_support: bool,
+    escape_non_ascii: bool,
+    return_bytes: bool,
+    initial_buffer_size: Option<usize>,
+    preserve_sibling_order: bool,
+    order_key: &str,
+    index_key: Option<String>,
+) -> PyResult<Bound<'py, PyList>> {
+    check_xml_version(xml_version).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let config = UnparseConfig {
+        encoding: encoding.to_owned(),
+        xml_version: xml_version.to_owned(),
+        standalone,
+        full_document,
+        short_empty_elements,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        pretty,
+        pretty_depth,
+        newl: newl.to_owned(),
+        indent: indent.to_owned(),
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        namespaces: extract_namespace_map(namespaces)?,
+        preserve_mixed_content,
+        doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        escape_control_chars,
+        escape_apos,
+        invalid_chars: invalid_chars
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        escape_non_ascii,
+        charset: output_charset(py, return_bytes.then_some(encoding))?,
+        duplicate_attributes: duplicate_attributes
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        use_cdata,
+        cdata_for_keys: extract_key_set(cdata_for_keys, "cdata_for_keys")?,
+        comment_key: CommentKey::new(comment_key),
+        pi_key: PiKey::new(pi_key),
+        datetime_format,
+        expand_iter,
+        pydantic_support,
+        canonical,
+        preserve_sibling_order,
+        order_key: OrderKey::new(order_key),
+        index_key,
+    };
+    let config = if canonical {
+        config.canonical_form()
+    } else {
+        config
+    };
+
+    let default = default
+        .map(|callback| DefaultHook::new(py, callback))
+        .transpose()?;
+    let mut writer = XmlWriter::new(config, preprocessor, default, Sink::Buffer)
+        .with_capacity(initial_buffer_size.unwrap_or(0));
+    let documents = PyList::empty(py);
+    for (index, input_dict) in input_dicts.try_iter()?.enumerate() {
+        let written = input_dict.and_then(|input_dict| {
+            let input_dict = &as_dict(&model_value(&input_dict, pydantic_support)?)?;
+            check_document_roots(input_dict, writer.config())?;
+            let document = writer.buffer_document(py, input_dict)?;
+            if return_bytes {
+                encode_output(py, document, encoding, true)
+            } else {
+                Ok(PyString::new(py, document).into_any())
+            }
+        });
+        match written {
+            Ok(document) => documents.append(document)?,
+            Err(err) => {
+                add_note(py, &err, &format!("in document {index}"));
+                return Err(err);
+            }
+        }
+    }
+    Ok(documents)
+}
+
+/// Write a Python dictionary as XML to an object with an async `write`, serializing on a
+/// worker thread and awaiting each write on the event loop
+#[pyfunction]
+#[pyo3(signature = (input_dict, output, **kwargs))]
+fn unparse_async(
+    input_dict: &Bound<'_, PyAny>,
+    output: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Offloaded> {
+    let py = input_dict.py();
+    let input_dict = input_dict.clone().unbind();
+    let output = output.clone().unbind();
+    let kwargs = kwargs
+        .map_or_else(|| Ok(PyDict::new(py)), PyDictMethods::copy)?
+        .unbind();
+    Ok(Offloaded::new(Box::new(move |event_loop| {
+        let py = event_loop.py();
+        let kwargs = kwargs.bind(py);
+        kwargs.set_item("output", AsyncWrite::new(output, event_loop))?;
+        let unparse = wrap_pyfunction!(unparse, py)?;
+        py.import("functools")?
+            .getattr("partial")?
+            .call((unparse, input_dict), Some(kwargs))
+    })))
+}
+
+/// Write one element as XML, declaring on it the namespace bindings in scope where it was
+/// taken from; the other keyword arguments go to `unparse()`
+#[pyfunction]
+#[pyo3(signature = (value, tag, namespaces = None, full_document = false, **kwargs))]
+fn unparse_fragment(
+    py: Python,
+    value: &Bound<'_, PyAny>,
+    tag: &str,
+    namespaces: Option<&Bound<'_, PyDict>>,
+    full_document: bool,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyAny>> {
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "a fragment is a single element; its value cannot be a list",
+        ));
+    }
+    let unparse_kwargs = match kwargs {
+        Some(kwargs) => kwargs.copy()?,
+        None => PyDict::new(py),
+    };
+    unparse_kwargs.set_item("full_document", full_document)?;
+    // `unparse()` declares its namespace map, from URI to prefix, on the root element, and
+    // writes expanded names bound to one with its prefix.
+    let uris = PyDict::new(py);
+    for (prefix, uri) in namespaces.into_iter().flatten() {
+        if !uris.contains(&uri)? {
+            uris.set_item(&uri, &prefix)?;
+        }
+    }
+    unparse_kwargs.set_item("namespaces", uris)?;
+    let document = PyDict::new(py);
+    document.set_item(tag, value)?;
+    Ok(wrap_pyfunction!(unparse, py)?
+        .call((document,), Some(&unparse_kwargs))?
+        .unbind())
+}
+
+/// Rewrite XML into `output` as it is read, dropping and renaming elements and rewriting
+/// attribute values by `rules`, without building a dict of the document
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    output = None,
+    rules = None,
+    encoding = None,
+    output_encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn transform(
+    py: Python,
+    source: &Bound<'_, PyAny>,
+    output: Option<&Bound<'_, PyAny>>,
+    rules: Option<&Bound<'_, PyDict>>,
+    encoding: Option<&str>,
+    output_encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Option<String>> {
+    let rules = Rules::new(rules)?;
+    // Text is written back as it was read, comments and processing instructions included.
+    let config = ParseConfig {
+        strip: Strip::None,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        process_comments: true,
+        process_pis: true,
+        ..ParseConfig::default()
+    };
+    let unparse_config = UnparseConfig {
+        encoding: output_encoding.to_owned(),
+        full_document,
+        short_empty_elements,
+        ..UnparseConfig::default()
+    };
+    run_pipeline(
+        py,
+        &rules,
+        source,
+        output,
+        encoding,
+        &config,
+        unparse_config,
+    )
+}
+
+/// Fill in an XML template: write it back into `output` with the values of `updates` put in
+/// at their paths, adding the elements and attributes it lacks
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_template,
+    updates,
+    output = None,
+    encoding = None,
+    output_encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+))]
+fn patch(
+    py: Python,
+    xml_template: &Bound<'_, PyAny>,
+    updates: &Bound<'_, PyDict>,
+    output: Option<&Bound<'_, PyAny>>,
+    encoding: Option<&str>,
+    output_encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+) -> PyResult<Option<String>> {
+    let rules = Rules::patch(updates)?;
+    // Whatever is not updated is written back as it was read.
+    let config = ParseConfig {
+        strip: Strip::None,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        process_comments: true,
+        process_pis: true,
+        ..ParseConfig::default()
+    };
+    let unparse_config = UnparseConfig {
+        encoding: output_encoding.to_owned(),
+        full_document,
+        short_empty_elements,
+        ..UnparseConfig::default()
+    };
+    run_pipeline(
+        py,
+        &rules,
+        xml_template,
+        output,
+        encoding,
+        &config,
+        unparse_config,
+    )
+}
+
+/// Streams `source` through a [`Pipeline`] applying `rules`, into `output` or a string that
+/// is returned.
+fn run_pipeline(
+    py: Python,
+    rules: &Rules,
+    source: &Bound<'_, PyAny>,
+    output: Option<&Bound<'_, PyAny>>,
+    encoding: Option<&str>,
+    config: &ParseConfig,
+    mut unparse_config: UnparseConfig,
+) -> PyResult<Option<String>> {
+    let input = lazy_input(py, source, encoding, config.decompress)?;
+    let sink = match output {
+        Some(stream) => Sink::stream(py, stream, &unparse_config.encoding)?,
+        None => Sink::Buffer,
+    };
+    unparse_config.charset = output_charset(py, sink.encoding())?;
+    let writer = XmlWriter::new(unparse_config, None, None, sink);
+    Pipeline::new(rules, writer).run(py, EventReader::new(input, config))
+}
+
+/// Keyword arguments `roundtrip()` passes to both `parse()` and `unparse()`.
+const ROUNDTRIP_SHARED_KWARGS: &[&str] = &[
+    "attr_prefix",
+    "attr_dict_key",
+    "cdata_key",
+    "comment_key",
+    "doctype_key",
+    "pi_key",
+    "namespace_separator",
+    "namespaces",
+    "preserve_mixed_content",
+    "preserve_sibling_order",
+    "order_key",
+    "index_key",
+    "dialect",
+    "convention",
+];
+
+/// Keyword arguments `roundtrip()` passes only to `unparse()`; the rest go to `parse()`.
+const ROUNDTRIP_UNPARSE_KWARGS: &[&str] = &[
+    "full_document",
+    "canonical",
+    "standalone",
+    "xml_version",
+    "short_empty_elements",
+    "pretty",
+    "pretty_depth",
+    "newl",
+    "indent",
+    "preprocessor",
+    "default",
+    "datetime_format",
+    "expand_iter",
+    "duplicate_attributes",
+    "doctype",
+    "escape_control_chars",
+    "escape_apos",
+    "invalid_chars",
+    "escape_non_ascii",
+    "return_bytes",
+    "initial_buffer_size",
+    "use_cdata",
+    "cdata_for_keys",
+];
+
+/// Parse XML and write it back, keeping comments, processing instructions, the DOCTYPE,
+/// mixed content and the order of siblings
+#[pyfunction]
+#[pyo3(signature = (xml_input, **kwargs))]
+fn roundtrip(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Py<PyAny>> {
+    let parse_kwargs = PyDict::new(py);
+    for key in [
+        "process_comments",
+        "process_pis",
+        "process_doctype",
+        "preserve_mixed_content",
+    ] {
+        parse_kwargs.set_item(key, true)?;
+    }
+    parse_kwargs.set_item("strip_whitespace", false)?;
+    parse_kwargs.set_item("disable_entities", false)?;
+    parse_kwargs.set_item("max_depth", DEFAULT_MAX_DEPTH)?;
+    parse_kwargs.set_item("preserve_sibling_order", true)?;
+    let unparse_kwargs = PyDict::new(py);
+    unparse_kwargs.set_item("full_document", false)?;
+    unparse_kwargs.set_item("preserve_mixed_content", true)?;
+    unparse_kwargs.set_item("preserve_sibling_order", true)?;
+
+    for (key, value) in kwargs.into_iter().flatten() {
+        let name = key.str()?;
+        let name = name.to_str()?;
+        if ROUNDTRIP_SHARED_KWARGS.contains(&name) {
+            parse_kwargs.set_item(&key, &value)?;
+            unparse_kwargs.set_item(&key, &value)?;
+        } else if ROUNDTRIP_UNPARSE_KWARGS.contains(&name) {
+            unparse_kwargs.set_item(&key, &value)?;
+        } else {
+            parse_kwargs.set_item(&key, &value)?;
+        }
+    }
+
+    let parsed = wrap_pyfunction!(parse, py)?.call((xml_input,), Some(&parse_kwargs))?;
+    Ok(wrap_pyfunction!(unparse, py)?
+        .call((parsed,), Some(&unparse_kwargs))?
+        .unbind())
+}
+
+/// Reformat XML text with each element on a line of its own, without parsing it into a dict:
+/// markup is written back as it was read, comments, processing instructions and CDATA
+/// sections included
+#[pyfunction]
+#[pyo3(signature = (xml_input, indent = "  ", newl = "\n", encoding = None))]
+fn pretty(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    indent: &str,
+    newl: &str,
+    encoding: Option<&str>,
+) -> PyResult<String> {
+    let layout = Layout {
+        indent: indent.to_owned(),
+        newl: newl.to_owned(),
+        comments: true,
+    };
+    let input = lazy_input(py, xml_input, encoding, Decompress::Auto)?;
+    py.detach(|| reformat(input, &layout))
+        .map_err(|err| parse_error(py, err))
+}
+
+/// Remove the whitespace between elements from XML text, and with `strip_comments` its
+/// comments, writing everything else back as it was read
+#[pyfunction]
+#[pyo3(signature = (xml_input, strip_comments = false, encoding = None))]
+fn minify(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    strip_comments: bool,
+    encoding: Option<&str>,
+) -> PyResult<String> {
+    let layout = Layout {
+        indent: String::new(),
+        newl: String::new(),
+        comments: !strip_comments,
+    };
+    let input = lazy_input(py, xml_input, encoding, Decompress::Auto)?;
+    py.detach(|| reformat(input, &layout))
+        .map_err(|err| parse_error(py, err))
+}
+
+/// Parse a SOAP 1.1 or 1.2 envelope and return the content of its Body, raising `SoapFault`
+/// for a Fault; the keyword arguments go to `parse()`, which processes namespaces
+#[pyfunction]
+#[pyo3(signature = (xml_input, **kwargs))]
+fn parse_soap<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let parse_kwargs = match kwargs {
+        Some(kwargs) => kwargs.copy()?,
+        None => PyDict::new(py),
+    };
+    parse_kwargs.set_item("process_namespaces", true)?;
+    let parsed = wrap_pyfunction!(parse, py)?.call((xml_input,), Some(&parse_kwargs))?;
+    soap::body(&parsed, &parse_kwargs)
+}
+
+/// Write a SOAP envelope around a body and optional headers; the keyword arguments go to
+/// `unparse()`
+#[pyfunction]
+#[pyo3(signature = (body, headers = None, soap_version = "1.1", **kwargs))]
+fn build_soap<'py>(
+    py: Python<'py>,
+    body: &Bound<'py, PyAny>,
+    headers: Option<&Bound<'py, PyAny>>,
+    soap_version: &str,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Py<PyAny>> {
+    let document = soap::envelope(body, headers, soap_version, kwargs)?;
+    Ok(wrap_pyfunction!(unparse, py)?
+        .call((document,), kwargs)?
+        .unbind())
+}
+
+/// Write XML-RPC parameters, or a `Fault`, as `xmlrpc.client.dumps()` does
+#[pyfunction]
+#[pyo3(signature = (params, methodname = None, methodresponse = false, allow_none = false))]
+fn dumps_xmlrpc(
+    params: &Bound<'_, PyAny>,
+    methodname: Option<&str>,
+    methodresponse: bool,
+    allow_none: bool,
+) -> PyResult<String> {
+    Marshaller::new(allow_none).dumps(params, methodname, methodresponse)
+}
+
+/// Read an XML-RPC call or response into `(params, methodname)` as
+/// `xmlrpc.client.loads(use_builtin_types=True)` does, raising `xmlrpc.client.Fault` for a fault
+#[pyfunction]
+#[pyo3(signature = (xml_input, encoding = None))]
+fn loads_xmlrpc<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+) -> PyResult<(Bound<'py, PyTuple>, Option<String>)> {
+    let config = ParseConfig {
+        strip: Strip::None,
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    xmlrpc::loads(py, events)
+}
+
+/// The `indent` of `json.dumps()`: a number of spaces or the string itself.
+fn json_indent(indent: Option<&Bound<'_, PyAny>>) -> PyResult<Option<String>> {
+    let Some(indent) = indent else {
+        return Ok(None);
+    };
+    if let Ok(text) = indent.downcast::<PyString>() {
+        return Ok(Some(text.to_string()));
+    }
+    let spaces: isize = indent.extract().map_err(|_err| {
+        PyErr::new::<pyo3::exceptions::PyTypeError, _>("indent must be an int, a str or None")
+    })?;
+    Ok(Some(" ".repeat(spaces.max(0).unsigned_abs())))
+}
+
+/// `force_list` for functions that build no Python objects: a bool, or element names and
+/// paths in a string or an iterable of strings, but not a callable.
+fn extract_listed_elements(force_list: Option<&Bound<'_, PyAny>>) -> PyResult<ListedElements> {
+    let Some(force_list) = force_list else {
+        return Ok(ListedElements::default());
+    };
+    if let Some(listed) = native_force_list(force_list)? {
+        return Ok(listed);
+    }
+    if let Ok(name) = force_list.downcast::<PyString>() {
+        return Ok(ListedElements::named([name.to_str()?]));
+    }
+    if force_list.is_callable() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "force_list cannot be a callable here, as no Python objects are built; \
+             use parse() to call it",
+        ));
+    }
+    Ok(ListedElements::named(extract_key_set(
+        Some(force_list),
+        "force_list",
+    )?))
+}
+
+/// Convert XML straight to JSON text, as `json.dumps(parse(xml))` would but without
+/// building Python objects; takes the `parse()` options that shape the value, except for
+/// those calling back into Python
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    indent = None,
+    ensure_ascii = true,
+    process_namespaces = false,
+    namespace_separator = ":",
+    disable_entities = true,
+    process_comments = false,
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_cdata = false,
+    cdata_separator = "",
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    comment_key = "#comment",
+    namespaces = None,
+    max_entity_depth = 16,
+    max_entity_expansion = 10_000_000,
+    forbid_dtd = false,
+    forbid_entities = false,
+    preserve_mixed_content = false,
+    process_doctype = false,
+    doctype_key = "#doctype",
+    process_pis = false,
+    pi_key = "#pi",
+    max_depth = Some(DEFAULT_MAX_DEPTH),
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+    attr_dict_key = None,
+    collect_namespaces = false,
+    namespaces_key = "#namespaces",
+    qname_attributes = None,
+    strip_namespace_prefixes = false,
+    force_list = None,
+    infer_types = None,
+    on_duplicate = "list",
+))]
+fn xml_to_json(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    indent: Option<&Bound<'_, PyAny>>,
+    ensure_ascii: bool,
+    process_namespaces: bool,
+    namespace_separator: &str,
+    disable_entities: bool,
+    process_comments: bool,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_cdata: bool,
+    cdata_separator: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    comment_key: &str,
+    namespaces: Option<Py<PyAny>>,
+    max_entity_depth: usize,
+    max_entity_expansion: usize,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    preserve_mixed_content: bool,
+    process_doctype: bool,
+    doctype_key: &str,
+    process_pis: bool,
+    pi_key: &str,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+    attr_dict_key: Option<String>,
+    collect_namespaces: bool,
+    namespaces_key: &str,
+    qname_attributes: Option<Vec<String>>,
+    strip_namespace_prefixes: bool,
+    force_list: Option<&Bound<'_, PyAny>>,
+    infer_types: Option<&Bound<'_, PyAny>>,
+    on_duplicate: &str,
+) -> PyResult<String> {
+    let encoding = encoding.map(lookup_encoding).transpose()?;
+    let format = JsonFormat {
+        indent: json_indent(indent)?,
+        ensure_ascii,
+    };
+    let config = ParseConfig {
+        xml_attribs,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        force_cdata,
+        cdata_separator: cdata_separator.to_owned(),
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        process_namespaces,
+        process_comments,
+        comment_key: CommentKey::new(comment_key),
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        entity_limits: EntityLimits {
+            max_depth: max_entity_depth,
+            max_expansion: max_entity_expansion,
+        },
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        namespaces: namespaces
+            .map(|dict_py| extract_hashmap(py, &dict_py))
+            .transpose()?,
+        preserve_mixed_content,
+        process_doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        process_pis,
+        pi_key: PiKey::new(pi_key),
+        collect_namespaces,
+        namespaces_key: NamespacesKey::new(namespaces_key),
+        qname_attributes: qname_attributes.unwrap_or_default(),
+        strip_namespace_prefixes,
+        force_list: extract_listed_elements(force_list)?,
+        infer_types: extract_infer_types(infer_types)?,
+        on_duplicate: choice(on_duplicate)?,
+        ..ParseConfig::default()
+    };
+    let parsed = parse_native(py, xml_input, encoding, &config)?;
+    Ok(py.detach(|| xmltodict_core::to_json(&parsed, &format)))
+}
+
+/// Convert XML straight to `MessagePack`, as `msgpack.packb(parse(xml))` would but without
+/// building Python objects, for results handed between processes; takes the options of
+/// `xml_to_json()` that shape the value
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    process_namespaces = false,
+    namespace_separator = ":",
+    disable_entities = true,
+    process_comments = false,
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_cdata = false,
+    cdata_separator = "",
+    strip_whitespace = true,
+    strip = None,
+    honor_xml_space = true,
+    comment_key = "#comment",
+    namespaces = None,
+    max_entity_depth = 16,
+    max_entity_expansion = 10_000_000,
+    forbid_dtd = false,
+    forbid_entities = false,
+    preserve_mixed_content = false,
+    process_doctype = false,
+    doctype_key = "#doctype",
+    process_pis = false,
+    pi_key = "#pi",
+    max_depth = Some(DEFAULT_MAX_DEPTH),
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+    attr_dict_key = None,
+    collect_namespaces = false,
+    namespaces_key = "#namespaces",
+    qname_attributes = None,
+    strip_namespace_prefixes = false,
+    force_list = None,
+    infer_types = None,
+    on_duplicate = "list",
+))]
+fn xml_to_msgpack<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    process_namespaces: bool,
+    namespace_separator: &str,
+    disable_entities: bool,
+    process_comments: bool,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_cdata: bool,
+    cdata_separator: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    honor_xml_space: bool,
+    comment_key: &str,
+    namespaces: Option<Py<PyAny>>,
+    max_entity_depth: usize,
+    max_entity_expansion: usize,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    preserve_mixed_content: bool,
+    process_doctype: bool,
+    doctype_key: &str,
+    process_pis: bool,
+    pi_key: &str,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+    attr_dict_key: Option<String>,
+    collect_namespaces: bool,
+    namespaces_key: &str,
+    qname_attributes: Option<Vec<String>>,
+    strip_namespace_prefixes: bool,
+    force_list: Option<&Bound<'_, PyAny>>,
+    infer_types: Option<&Bound<'_, PyAny>>,
+    on_duplicate: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let encoding = encoding.map(lookup_encoding).transpose()?;
+    let config = ParseConfig {
+        xml_attribs,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        force_cdata,
+        cdata_separator: cdata_separator.to_owned(),
+        strip: extract_strip(strip, strip_whitespace)?,
+        honor_xml_space,
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        process_namespaces,
+        process_comments,
+        comment_key: CommentKey::new(comment_key),
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        entity_limits: EntityLimits {
+            max_depth: max_entity_depth,
+            max_expansion: max_entity_expansion,
+        },
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        namespaces: namespaces
+            .map(|dict_py| extract_hashmap(py, &dict_py))
+            .transpose()?,
+        preserve_mixed_content,
+        process_doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        process_pis,
+        pi_key: PiKey::new(pi_key),
+        collect_namespaces,
+        namespaces_key: NamespacesKey::new(namespaces_key),
+        qname_attributes: qname_attributes.unwrap_or_default(),
+        strip_namespace_prefixes,
+        force_list: extract_listed_elements(force_list)?,
+        infer_types: extract_infer_types(infer_types)?,
+        on_duplicate: choice(on_duplicate)?,
+        ..ParseConfig::default()
+    };
+    let parsed = parse_native(py, xml_input, encoding, &config)?;
+    let packed = py
+        .detach(|| xmltodict_core::to_msgpack(&parsed))
+        .map_err(|err| parse_error(py, err))?;
+    Ok(PyBytes::new(py, &packed))
+}
+
+/// Parses text or bytes into an [`XmlMap`] with the GIL released, for functions that work
+/// on the document without handing it to Python.
+fn parse_native(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> PyResult<XmlMap> {
+    let parsed = if let Ok(xml_str) = xml_input.downcast::<PyString>() {
+        let text = xml_str.to_str()?;
+        py.detach(|| xmltodict_core::parse_str(text, config))
+    } else {
+        let xml_bytes = xml_input.extract::<&[u8]>()?;
+        py.detach(|| xmltodict_core::parse_transcoded(xml_bytes, encoding, config))
+    };
+    parsed.map_err(|err| parse_error(py, err))
+}
+
+/// Compare two XML documents as `parse()` reads them, returning a dict with the `path`,
+/// `kind`, `left` and `right` of each difference
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_a,
+    xml_b,
+    ignore_order_for = None,
+    encoding = None,
+    process_namespaces = false,
+    namespace_separator = ":",
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    strip_whitespace = true,
+    strip = None,
+    process_comments = false,
+    comment_key = "#comment",
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+))]
+fn diff<'py>(
+    py: Python<'py>,
+    xml_a: &Bound<'py, PyAny>,
+    xml_b: &Bound<'py, PyAny>,
+    ignore_order_for: Option<Vec<String>>,
+    encoding: Option<&str>,
+    process_namespaces: bool,
+    namespace_separator: &str,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    process_comments: bool,
+    comment_key: &str,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let encoding = encoding.map(lookup_encoding).transpose()?;
+    let config = ParseConfig {
+        xml_attribs,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        cdata_key: CdataKey::new(cdata_key),
+        strip: extract_strip(strip, strip_whitespace)?,
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        process_namespaces,
+        process_comments,
+        comment_key: CommentKey::new(comment_key),
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        ..ParseConfig::default()
+    };
+    let left = parse_native(py, xml_a, encoding, &config)?;
+    let right = parse_native(py, xml_b, encoding, &config)?;
+    let ignore_order_for = ignore_order_for.unwrap_or_default();
+    let differences = py.detach(|| xmltodict_core::diff(&left, &right, &ignore_order_for));
+    let result = PyList::empty(py);
+    for difference in differences {
+        let item = PyDict::new(py);
+        item.set_item("path", difference.path)?;
+        item.set_item("kind", difference.kind.as_str())?;
+        item.set_item("left", difference.left.as_ref())?;
+        item.set_item("right", difference.right.as_ref())?;
+        result.append(item)?;
+    }
+    Ok(result)
+}
+
+/// Scan a document and report its shape: element and attribute counts, nesting depth and
+/// repeated siblings, without building anything for its elements
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn profile<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let config = ParseConfig {
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let input = lazy_input(py, xml_input, encoding, config.decompress)?;
+    let profile = py
+        .detach(|| Profile::read(EventReader::new(input, &config)))
+        .map_err(|err| parse_error(py, err))?;
+    let tally = |tally: Tally| -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (name, count) in tally.entries {
+            dict.set_item(name, count)?;
+        }
+        Ok(dict)
+    };
+    let result = PyDict::new(py);
+    result.set_item("elements", profile.elements)?;
+    result.set_item("attributes", profile.attributes)?;
+    result.set_item("max_depth", profile.max_depth)?;
+    result.set_item("max_siblings", profile.max_siblings)?;
+    result.set_item("tags", tally(profile.tags)?)?;
+    result.set_item("attribute_names", tally(profile.attribute_names)?)?;
+    result.set_item("repeated", tally(profile.repeated)?)?;
+    Ok(result)
+}
+
+/// Read the records of a document into columns, without building a dict for each: a list
+/// of values per column, or a `pyarrow.Table` of them with `arrow`
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    record_path,
+    columns = None,
+    encoding = None,
+    arrow = false,
+    strip_whitespace = true,
+    strip = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn read_records<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    record_path: &str,
+    columns: Option<Vec<String>>,
+    encoding: Option<&str>,
+    arrow: bool,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let records = Records::new(record_path, columns)
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let config = ParseConfig {
+        strip: extract_strip(strip, strip_whitespace)?,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let input = lazy_input(py, xml_input, encoding, config.decompress)?;
+    let columns = py
+        .detach(|| records.read(EventReader::new(input, &config)))
+        .map_err(|err| parse_error(py, err))?;
+    let table = PyDict::new(py);
+    for (name, values) in columns.names.into_iter().zip(columns.values) {
+        table.set_item(name, PyList::new(py, values)?)?;
+    }
+    if arrow {
+        return py.import("pyarrow")?.call_method1("table", (table,));
+    }
+    Ok(table.into_any())
+}
+
+/// Write the records of a document to `fileobj` as CSV rows while reading it, holding no
+/// more than one record at a time; returns the number of rows written
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    record_path,
+    fileobj,
+    columns = None,
+    encoding = None,
+    output_encoding = "utf-8",
+    strip_whitespace = true,
+    strip = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn to_csv(
+    py: Python,
+    xml_input: &Bound<'_, PyAny>,
+    record_path: &str,
+    fileobj: &Bound<'_, PyAny>,
+    columns: Option<Vec<String>>,
+    encoding: Option<&str>,
+    output_encoding: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<usize> {
+    let records = Records::new(record_path, columns)
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?
+        .with_first_record_columns();
+    let config = ParseConfig {
+        strip: extract_strip(strip, strip_whitespace)?,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let mut writer = StreamWriter::new(py, fileobj, output_encoding)?;
+    let input = lazy_input(py, xml_input, encoding, config.decompress)?;
+    py.detach(|| {
+        let mut rows = 0;
+        let mut line = String::new();
+        let names = records.read_rows(EventReader::new(input, &config), |names, row| {
+            line.clear();
+            if rows == 0 {
+                write_csv_row(&mut line, names.iter().map(|name| Some(name.as_str())));
+            }
+            write_csv_row(&mut line, row.iter().map(Option::as_deref));
+            rows += 1;
+            writer.write(&line)
+        })?;
+        // Given columns still make a header when no record was found.
+        if rows == 0 && !names.is_empty() {
+            line.clear();
+            write_csv_row(&mut line, names.iter().map(|name| Some(name.as_str())));
+            writer.write(&line)?;
+        }
+        writer.flush()?;
+        Ok(rows)
+    })
+    .map_err(|err| parse_error(py, err))
+}
+
+/// Write each record of a document to `output` as a line of JSON while reading it, holding
+/// no more than one record at a time; returns the number of records written
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    record_path,
+    output,
+    encoding = None,
+    output_encoding = "utf-8",
+    ensure_ascii = true,
+    xml_attribs = true,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    force_cdata = false,
+    cdata_separator = "",
+    strip_whitespace = true,
+    strip = None,
+    process_comments = false,
+    comment_key = "#comment",
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = Some(DEFAULT_MAX_DEPTH),
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn to_ndjson(
+    py: Python,
+    source: &Bound<'_, PyAny>,
+    record_path: &str,
+    output: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    output_encoding: &str,
+    ensure_ascii: bool,
+    xml_attribs: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    force_cdata: bool,
+    cdata_separator: &str,
+    strip_whitespace: bool,
+    strip: Option<&str>,
+    process_comments: bool,
+    comment_key: &str,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<usize> {
+    let config = ParseConfig {
+        xml_attribs,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        cdata_key: CdataKey::new(cdata_key),
+        force_cdata,
+        cdata_separator: cdata_separator.to_owned(),
+        strip: extract_strip(strip, strip_whitespace)?,
+        process_comments,
+        comment_key: CommentKey::new(comment_key),
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let record = PathPattern::new(record_path);
+    let mut writer = StreamWriter::new(py, output, output_encoding)?;
+    let input = lazy_input(py, source, encoding, config.decompress)?;
+    py.detach(|| {
+        let mut records = 0;
+        xmltodict_core::parse_records(input, &record, &config, |value| {
+            records += 1;
+            writer.write(&xmltodict_core::json_line(&value, ensure_ascii))
+        })?;
+        writer.flush()?;
+        Ok(records)
+    })
+    .map_err(|err| parse_error(py, err))
+}
+
+/// Convert JSON text straight to XML, as `unparse(json.loads(text))` would but without
+/// building Python objects; takes the `unparse()` options that do not call back into Python
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    json_input,
+    encoding = "utf-8",
+    full_document = true,
+    short_empty_elements = false,
+    attr_prefix = "@",
+    cdata_key = "#text",
+    pretty = false,
+    newl = "\n",
+    indent = "\t",
+    namespace_separator = ":",
+    namespaces = None,
+    preserve_mixed_content = false,
+    doctype = None,
+    doctype_key = "#doctype",
+    escape_control_chars = true,
+    escape_apos = false,
+    invalid_chars = "raise",
+    use_cdata = false,
+    cdata_for_keys = None,
+    comment_key = "#comment",
+    pi_key = "#pi",
+    attr_dict_key = None,
+    canonical = false,
+    standalone = None,
+    xml_version = "1.0",
+    pretty_depth = None,
+    duplicate_attributes = "raise",
+    escape_non_ascii = false,
+))]
+fn json_to_xml(
+    py: Python,
+    json_input: &str,
+    encoding: &str,
+    full_document: bool,
+    short_empty_elements: bool,
+    attr_prefix: &str,
+    cdata_key: &str,
+    pretty: bool,
+    newl: &str,
+    indent: &str,
+    namespace_separator: &str,
+    namespaces: Option<&Bound<'_, PyAny>>,
+    preserve_mixed_content: bool,
+    doctype: Option<String>,
+    doctype_key: &str,
+    escape_control_chars: bool,
+    escape_apos: bool,
+    invalid_chars: &str,
+    use_cdata: bool,
+    cdata_for_keys: Option<&Bound<'_, PyAny>>,
+    comment_key: &str,
+    pi_key: &str,
+    attr_dict_key: Option<String>,
+    canonical: bool,
+    standalone: Option<bool>,
+    xml_version: &str,
+    pretty_depth: Option<usize>,
+    duplicate_attributes: &str,
+    escape_non_ascii: bool,
+) -> PyResult<String> {
+    check_xml_version(xml_version).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+    let config = UnparseConfig {
+        encoding: encoding.to_owned(),
+        xml_version: xml_version.to_owned(),
+        standalone,
+        full_document,
+        short_empty_elements,
+        attr_prefix: AttrPrefix::new(attr_prefix),
+        attr_dict_key,
+        cdata_key: CdataKey::new(cdata_key),
+        pretty,
+        pretty_depth,
+        newl: newl.to_owned(),
+        indent: indent.to_owned(),
+        namespace_separator: NamespaceSeparator::new(namespace_separator),
+        namespaces: extract_namespace_map(namespaces)?,
+        preserve_mixed_content,
+        doctype,
+        doctype_key: DoctypeKey::new(doctype_key),
+        escape_control_chars,
+        escape_apos,
+        invalid_chars: invalid_chars
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        escape_non_ascii,
+        duplicate_attributes: duplicate_attributes
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        use_cdata,
+        cdata_for_keys: extract_key_set(cdata_for_keys, "cdata_for_keys")?,
+        comment_key: CommentKey::new(comment_key),
+        pi_key: PiKey::new(pi_key),
+        canonical,
+        ..UnparseConfig::default()
+    };
+    py.detach(|| xmltodict_core::json_to_xml(json_input, &config))
+        .map_err(|err| parse_error(py, err))
+}
+
+/// Check that a document is well-formed, reading it through without building anything:
+/// `(True, None)`, or `(False, error)` with the `message`, `code`, `line`, `column` and
+/// `offset` of the first error
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::fn_params_excessive_bools)]
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    encoding = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+    max_depth = None,
+    max_attributes = None,
+    max_text_length = None,
+    max_total_nodes = None,
+))]
+fn is_well_formed<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+    max_depth: Option<usize>,
+    max_attributes: Option<usize>,
+    max_text_length: Option<usize>,
+    max_total_nodes: Option<usize>,
+) -> PyResult<(bool, Option<Bound<'py, PyDict>>)> {
+    let config = ParseConfig {
+        strip: Strip::None,
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        limits: DocumentLimits {
+            max_depth,
+            max_attributes,
+            max_text_length,
+            max_total_nodes,
+        },
+        ..ParseConfig::default()
+    };
+    let input = lazy_input(py, xml_input, encoding, config.decompress)?;
+    let result = py.detach(|| {
+        let mut events = EventReader::new(input, &config);
+        events.require_one_root();
+        while events.next_event()?.is_some() {}
+        Ok(())
+    });
+    let (message, code, position) = match result {
+        Ok(()) => return Ok((true, None)),
+        Err(xmltodict_core::Error::Syntax(err)) => (err.to_string(), Some(err.code), err.position),
+        Err(xmltodict_core::Error::EntitiesForbidden(message)) => (message, None, None),
+        // Corrupt compressed input is not well-formed either, but a failing read raises.
+        Err(xmltodict_core::Error::Io(err)) => match pyerr_from_io(&err) {
+            Some(err) => return Err(err),
+            None => (err.to_string(), Some(SyntaxError::SYNTAX), None),
+        },
+        Err(err @ (xmltodict_core::Error::Value(_) | xmltodict_core::Error::Json(_))) => {
+            return Err(parse_error(py, err));
+        }
+    };
+    let error = PyDict::new(py);
+    error.set_item("message", message)?;
+    error.set_item("code", code)?;
+    error.set_item("line", position.map(|position| position.line))?;
+    error.set_item("column", position.map(|position| position.column))?;
+    error.set_item("offset", position.map(|position| position.offset))?;
+    Ok((false, Some(error)))
+}
+
+/// Check a document against an XML Schema, returning a dict with the `path`, `line`,
+/// `expected`, `found` and `message` of each issue; the list is empty for a valid document
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    xsd,
+    encoding = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+))]
+fn validate<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    xsd: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let model = read_model(py, xsd)?;
+    let config = ParseConfig {
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    let issues = py.detach(|| xsd::validate(&model, &events));
+    issue_list(py, &issues)
+}
+
+/// Infer a best-effort XML Schema from sample documents: XML inputs or dicts from `parse`,
+/// or a list of them
+#[pyfunction]
+#[pyo3(signature = (samples, encoding = None, attr_prefix = "@", cdata_key = "#text"))]
+fn infer_schema(
+    py: Python,
+    samples: &Bound<'_, PyAny>,
+    encoding: Option<&str>,
+    attr_prefix: &str,
+    cdata_key: &str,
+) -> PyResult<String> {
+    let samples = if samples.downcast::<PyList>().is_ok() || samples.downcast::<PyTuple>().is_ok() {
+        samples.try_iter()?.collect::<PyResult<Vec<_>>>()?
+    } else {
+        vec![samples.clone()]
+    };
+    if samples.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "infer_schema needs at least one sample",
+        ));
+    }
+    let unparse_kwargs = PyDict::new(py);
+    unparse_kwargs.set_item("attr_prefix", attr_prefix)?;
+    unparse_kwargs.set_item("cdata_key", cdata_key)?;
+    unparse_kwargs.set_item("full_document", false)?;
+    let config = ParseConfig::default();
+    let mut documents = Vec::with_capacity(samples.len());
+    for sample in &samples {
+        let events = if sample.downcast::<PyDict>().is_ok() {
+            // Dicts are read back as the document `unparse` writes for them.
+            let xml = wrap_pyfunction!(unparse, py)?.call((sample,), Some(&unparse_kwargs))?;
+            read_events(py, &xml, None, &config)?
+        } else {
+            read_events(py, sample, encoding, &config)?
+        };
+        documents.push(events);
+    }
+    Ok(py.detach(|| Model::infer(&documents).to_xsd()))
+}
+
+/// Check a document against a RELAX NG schema in the compact syntax, returning its issues
+/// as `validate` does
+#[pyfunction]
+#[pyo3(signature = (
+    xml_input,
+    rnc_schema,
+    encoding = None,
+    disable_entities = true,
+    forbid_dtd = false,
+    forbid_entities = false,
+))]
+fn validate_rnc<'py>(
+    py: Python<'py>,
+    xml_input: &Bound<'py, PyAny>,
+    rnc_schema: &Bound<'py, PyAny>,
+    encoding: Option<&str>,
+    disable_entities: bool,
+    forbid_dtd: bool,
+    forbid_entities: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let schema = if let Ok(text) = rnc_schema.downcast::<PyString>() {
+        text.to_str()?.to_owned()
+    } else if let Ok(bytes) = rnc_schema.downcast::<PyBytes>() {
+        String::from_utf8(bytes.as_bytes().to_vec())
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?
+    } else {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "rnc_schema must be str or bytes",
+        ));
+    };
+    let model = Model::from_rnc(&schema).map_err(|err| parse_error(py, err))?;
+    let config = ParseConfig {
+        disable_entities,
+        forbid_dtd,
+        forbid_entities,
+        ..ParseConfig::default()
+    };
+    let events = read_events(py, xml_input, encoding, &config)?;
+    let issues = py.detach(|| xsd::validate(&model, &events));
+    issue_list(py, &issues)
+}
+
+#[pymodule(gil_used = false)]
+fn xmltodict_rs(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_file, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_zip_member, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_async, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_in_thread, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_events, m)?)?;
+    m.add_function(wrap_pyfunction!(iterparse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_many, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(find, m)?)?;
+    m.add_function(wrap_pyfunction!(unparse, m)?)?;
+    m.add_function(wrap_pyfunction!(unparse_iter, m)?)?;
+    m.add_function(wrap_pyfunction!(unparse_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(unparse_async, m)?)?;
+    m.add_function(wrap_pyfunction!(unparse_fragment, m)?)?;
+    m.add_function(wrap_pyfunction!(transform, m)?)?;
+    m.add_function(wrap_pyfunction!(patch, m)?)?;
+    m.add_function(wrap_pyfunction!(roundtrip, m)?)?;
+    m.add_function(wrap_pyfunction!(pretty, m)?)?;
+    m.add_function(wrap_pyfunction!(minify, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_soap, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_feed, m)?)?;
+    m.add_function(wrap_pyfunction!(build_soap, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps_xmlrpc, m)?)?;
+    m.add_function(wrap_pyfunction!(loads_xmlrpc, m)?)?;
+    m.add_function(wrap_pyfunction!(xml_to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(json_to_xml, m)?)?;
+    m.add_function(wrap_pyfunction!(xml_to_msgpack, m)?)?;
+    m.add_function(wrap_pyfunction!(diff, m)?)?;
+    m.add_function(wrap_pyfunction!(profile, m)?)?;
+    m.add_function(wrap_pyfunction!(read_records, m)?)?;
+    m.add_function(wrap_pyfunction!(to_csv, m)?)?;
+    m.add_function(wrap_pyfunction!(to_ndjson, m)?)?;
+    m.add_function(wrap_pyfunction!(is_well_formed, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_rnc, m)?)?;
+    m.add_function(wrap_pyfunction!(infer_schema, m)?)?;
+    m.add_class::<Parser>()?;
+    m.add_class::<Serializer>()?;
+    m.add_class::<UnparseIterator>()?;
+    m.add_class::<AsyncChunks>()?;
+    m.add_class::<AsyncWrite>()?;
+    m.add_class::<Offloaded>()?;
+    m.add_class::<IterParse>()?;
+    m.add_class::<ParseMany>()?;
+    m.add_class::<Node>()?;
+    m.add("XmlSyntaxError", m.py().get_type::<XmlSyntaxError>())?;
+    m.add("XmlEncodingError", m.py().get_type::<XmlEncodingError>())?;
+    m.add("LimitExceeded", m.py().get_type::<LimitExceeded>())?;
+    m.add(
+        "DepthLimitExceeded",
+        m.py().get_type::<DepthLimitExceeded>(),
+    )?;
+    m.add("SizeLimitExceeded", m.py().get_type::<SizeLimitExceeded>())?;
+    m.add("EntitiesForbidden", m.py().get_type::<EntitiesForbidden>())?;
+    m.add(
+        "XmlValidationError",
+        m.py().get_type::<XmlValidationError>(),
+    )?;
+    m.add("SoapFault", m.py().get_type::<SoapFault>())?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
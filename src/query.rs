@@ -0,0 +1,298 @@
+use crate::events::XmlEvent;
+use std::str::FromStr;
+
+/// A test on an element beyond its name, written in brackets after a step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Predicate {
+    /// `[@id]`
+    HasAttribute(String),
+    /// `[@id='3']`
+    AttributeEquals(String, String),
+    /// `[2]`, counting from 1 among the siblings passing the earlier tests of the step.
+    Position(usize),
+}
+
+/// One `/`-separated step: an element name, or `*` for any, and its predicates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Step {
+    name: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+impl Step {
+    /// Whether an element passes the step. `positions` counts, per predicate, the earlier
+    /// siblings that reached it.
+    fn accepts(
+        &self,
+        name: &str,
+        attributes: &[(String, String)],
+        positions: &mut [usize],
+    ) -> bool {
+        if self
+            .name
+            .as_deref()
+            .is_some_and(|expected| expected != name)
+        {
+            return false;
+        }
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        for (predicate, position) in self.predicates.iter().zip(positions) {
+            let passed = match predicate {
+                Predicate::HasAttribute(key) => attribute(key).is_some(),
+                Predicate::AttributeEquals(key, value) => attribute(key) == Some(value),
+                Predicate::Position(expected) => {
+                    *position += 1;
+                    position == expected
+                }
+            };
+            if !passed {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A path in a small subset of `XPath`, such as `catalog/book[@id='3']/title`: child steps
+/// from the root element, `*` for any element name, attribute predicates and positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rest = text.strip_prefix('/').unwrap_or(text);
+        let mut steps = Vec::new();
+        loop {
+            let (step, after) = parse_step(rest).map_err(|msg| format!("{msg} in {text:?}"))?;
+            steps.push(step);
+            match after.strip_prefix('/') {
+                Some(next) => rest = next,
+                None => break,
+            }
+        }
+        Ok(Self { steps })
+    }
+}
+
+fn parse_step(text: &str) -> Result<(Step, &str), String> {
+    let end = text.find(['[', '/']).unwrap_or(text.len());
+    let (name, mut rest) = text.split_at(end);
+    let name = match name.trim() {
+        "" if rest.starts_with('/') || rest.is_empty() => {
+            return Err("empty step (descendant steps are not supported)".to_owned())
+        }
+        "" => return Err("missing element name".to_owned()),
+        "*" => None,
+        name if name.starts_with('@') => return Err("attribute steps are not supported".to_owned()),
+        name if name.starts_with('.') => {
+            return Err("parent and self steps are not supported".to_owned())
+        }
+        name if name.contains(|ch: char| ch.is_whitespace() || "]()@='\"".contains(ch)) => {
+            return Err(format!("invalid element name {name:?}"))
+        }
+        name => Some(name.to_owned()),
+    };
+    let mut predicates = Vec::new();
+    while let Some(inner) = rest.strip_prefix('[') {
+        let close = closing_bracket(inner).ok_or("unclosed '['")?;
+        predicates.push(parse_predicate(inner.get(..close).unwrap_or_default())?);
+        rest = inner.get(close + 1..).unwrap_or_default();
+    }
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return Err(format!("unexpected {rest:?}"));
+    }
+    Ok((Step { name, predicates }, rest))
+}
+
+/// The index of the `]` closing a predicate, skipping quoted values.
+fn closing_bracket(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, ch) in text.char_indices() {
+        match (quote, ch) {
+            (None, '\'' | '"') => quote = Some(ch),
+            (Some(open), _) if open == ch => quote = None,
+            (None, ']') => return Some(index),
+            (Some(_) | None, _) => {}
+        }
+    }
+    None
+}
+
+fn parse_predicate(text: &str) -> Result<Predicate, String> {
+    let text = text.trim();
+    if let Ok(position) = text.parse::<usize>() {
+        if position == 0 {
+            return Err("positions start at 1".to_owned());
+        }
+        return Ok(Predicate::Position(position));
+    }
+    let Some(attribute) = text.strip_prefix('@') else {
+        return Err(format!("unsupported predicate [{text}]"));
+    };
+    let Some((key, value)) = attribute.split_once('=') else {
+        return Ok(Predicate::HasAttribute(attribute.trim().to_owned()));
+    };
+    let value = value.trim();
+    let unquoted = ['\'', '"'].into_iter().find_map(|quote| {
+        value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+    });
+    match unquoted {
+        Some(value) => Ok(Predicate::AttributeEquals(
+            key.trim().to_owned(),
+            value.to_owned(),
+        )),
+        None => Err(format!("attribute values must be quoted in [{text}]")),
+    }
+}
+
+/// An open element while matching.
+struct Frame {
+    matched: bool,
+    /// Position counters for the predicates of the children's step.
+    positions: Vec<usize>,
+}
+
+impl Query {
+    /// The events of each element the query selects, from its start tag to its end tag.
+    #[must_use]
+    pub fn matches(&self, events: Vec<(XmlEvent, usize)>) -> Vec<Vec<(XmlEvent, usize)>> {
+        let mut found = Vec::new();
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut root_positions = self.positions(0);
+        let mut capture: Option<(usize, Vec<(XmlEvent, usize)>)> = None;
+
+        for (event, line) in events {
+            match &event {
+                XmlEvent::Start { name, attributes } => {
+                    let depth = frames.len();
+                    let matched = match (frames.last_mut(), self.steps.get(depth)) {
+                        (Some(parent), Some(step)) if parent.matched => {
+                            step.accepts(name, attributes, &mut parent.positions)
+                        }
+                        (None, Some(step)) => step.accepts(name, attributes, &mut root_positions),
+                        (Some(_) | None, _) => false,
+                    };
+                    if matched && capture.is_none() && depth + 1 == self.steps.len() {
+                        capture = Some((depth, Vec::new()));
+                    }
+                    frames.push(Frame {
+                        matched,
+                        positions: self.positions(depth + 1),
+                    });
+                }
+                XmlEvent::End(_) => {
+                    frames.pop();
+                }
+                XmlEvent::Text(_)
+                | XmlEvent::Whitespace(_)
+                | XmlEvent::DocType(_)
+                | XmlEvent::Comment(_)
+                | XmlEvent::ProcessingInstruction(_) => {}
+            }
+            let Some((depth, captured)) = &mut capture else {
+                continue;
+            };
+            let closed = matches!(event, XmlEvent::End(_)) && frames.len() == *depth;
+            captured.push((event, line));
+            if closed {
+                found.push(std::mem::take(captured));
+                capture = None;
+            }
+        }
+        found
+    }
+
+    fn positions(&self, depth: usize) -> Vec<usize> {
+        vec![
+            0;
+            self.steps
+                .get(depth)
+                .map_or(0, |step| step.predicates.len())
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParseConfig;
+    use crate::events::collect_events;
+
+    fn found(xml: &str, query: &str) -> Vec<String> {
+        let config = ParseConfig::default();
+        let events = collect_events(xml.as_bytes(), &config).unwrap_or_default();
+        let query: Query = query.parse().unwrap_or(Query { steps: Vec::new() });
+        query
+            .matches(events)
+            .into_iter()
+            .map(|events| {
+                events
+                    .into_iter()
+                    .filter_map(|(event, _)| match event {
+                        XmlEvent::Text(text) | XmlEvent::Whitespace(text) => Some(text),
+                        XmlEvent::Start { .. }
+                        | XmlEvent::End(_)
+                        | XmlEvent::DocType(_)
+                        | XmlEvent::Comment(_)
+                        | XmlEvent::ProcessingInstruction(_) => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    const CATALOG: &str = r#"<catalog>
+        <book id="1" lang="en"><title>A</title></book>
+        <book id="2"><title>B</title></book>
+        <book id="3" lang="en"><title>C</title><title>C2</title></book>
+        <magazine id="3"><title>M</title></magazine>
+    </catalog>"#;
+
+    #[test]
+    fn selects_by_steps_and_predicates() {
+        assert_eq!(vec!["C"], found(CATALOG, "catalog/book[@id='3']/title[1]"));
+        assert_eq!(
+            vec!["C", "M"],
+            found(CATALOG, "/catalog/*[@id=\"3\"]/title[1]")
+        );
+        assert_eq!(
+            vec!["A", "C", "C2"],
+            found(CATALOG, "catalog/book[@lang]/title")
+        );
+        assert_eq!(
+            vec!["C"],
+            found(CATALOG, "catalog/book[@lang='en'][2]/title[1]")
+        );
+        assert_eq!(vec!["B"], found(CATALOG, "catalog/*[2]/title"));
+        assert!(found(CATALOG, "catalog/book[4]").is_empty());
+    }
+
+    #[test]
+    fn rejects_unsupported_syntax() {
+        for query in [
+            "",
+            "a//b",
+            "a/",
+            "a[",
+            "a[0]",
+            "a[@id=3]",
+            "a[text()]",
+            "a/@id",
+            "a]b",
+            "a/..",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{query:?}");
+        }
+    }
+}
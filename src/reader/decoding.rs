@@ -0,0 +1,154 @@
+use crate::encoding::{invalid_sequence_message, sniff_complete, sniff_encoding};
+use crate::reader::pending::PendingBytes;
+use encoding_rs::{Decoder, DecoderResult, Encoding, UTF_8};
+use std::io::{self, Read};
+
+const CHUNK_SIZE: usize = 8192;
+const UTF8_BOM_LEN: usize = 3;
+
+enum Mode {
+    /// Encoding not known yet: buffer the document prefix until it can be sniffed.
+    Sniff,
+    /// Input is already UTF-8 and is forwarded unchanged.
+    Passthrough,
+    Decode(Decoder),
+}
+
+/// Transcodes a byte stream in an arbitrary encoding into UTF-8 on the fly.
+pub struct DecodingRead<R> {
+    inner: R,
+    mode: Mode,
+    input: Vec<u8>,
+    consumed: usize,
+    decoded: Vec<u8>,
+    pending: PendingBytes,
+    eof: bool,
+    finished: bool,
+}
+
+impl<R: Read> DecodingRead<R> {
+    pub fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self::with_mode(inner, Mode::Decode(encoding.new_decoder()))
+    }
+
+    /// Detects the encoding from the BOM or XML declaration before decoding.
+    pub fn sniffing(inner: R) -> Self {
+        Self::with_mode(inner, Mode::Sniff)
+    }
+
+    fn with_mode(inner: R, mode: Mode) -> Self {
+        Self {
+            inner,
+            mode,
+            input: Vec::with_capacity(CHUNK_SIZE),
+            consumed: 0,
+            decoded: Vec::new(),
+            pending: PendingBytes::default(),
+            eof: false,
+            finished: false,
+        }
+    }
+
+    fn fill_input(&mut self) -> io::Result<()> {
+        self.input.resize(CHUNK_SIZE, 0);
+        let read = self.inner.read(&mut self.input)?;
+        self.input.truncate(read);
+        self.consumed = 0;
+        if read == 0 {
+            self.eof = true;
+        }
+        Ok(())
+    }
+
+    fn sniff(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 256];
+        while !self.eof && !sniff_complete(&self.input) {
+            let read = self.inner.read(&mut chunk)?;
+            if read == 0 {
+                self.eof = true;
+            }
+            self.input
+                .extend_from_slice(chunk.get(..read).unwrap_or_default());
+        }
+
+        self.mode = match sniff_encoding(&self.input) {
+            None => Mode::Passthrough,
+            Some(encoding) if encoding == UTF_8 => {
+                self.consumed = UTF8_BOM_LEN;
+                Mode::Passthrough
+            }
+            Some(encoding) => Mode::Decode(encoding.new_decoder()),
+        };
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecodingRead<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if !self.pending.is_empty() {
+                return Ok(self.pending.copy_into(out));
+            }
+            if self.finished {
+                return Ok(0);
+            }
+
+            match &self.mode {
+                Mode::Sniff => {
+                    self.sniff()?;
+                    continue;
+                }
+                Mode::Passthrough => {
+                    if let Some(buffered) = self.input.get(self.consumed..) {
+                        if !buffered.is_empty() {
+                            self.pending.fill_from_slice(buffered);
+                            self.consumed = self.input.len();
+                            continue;
+                        }
+                    }
+                    return self.inner.read(out);
+                }
+                Mode::Decode(_) => {}
+            }
+
+            if self.consumed >= self.input.len() && !self.eof {
+                self.fill_input()?;
+            }
+            let Mode::Decode(decoder) = &mut self.mode else {
+                continue;
+            };
+
+            let src = self.input.get(self.consumed..).unwrap_or_default();
+            let capacity = decoder
+                .max_utf8_buffer_length_without_replacement(src.len())
+                .unwrap_or(CHUNK_SIZE * 4);
+            self.decoded.resize(capacity, 0);
+
+            let (result, read, written) =
+                decoder.decode_to_utf8_without_replacement(src, &mut self.decoded, self.eof);
+            self.consumed = self.consumed.saturating_add(read);
+
+            match result {
+                DecoderResult::Malformed(_, _) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        invalid_sequence_message(decoder.encoding()),
+                    ));
+                }
+                DecoderResult::InputEmpty => {
+                    if self.eof {
+                        self.finished = true;
+                    }
+                }
+                DecoderResult::OutputFull => {}
+            }
+
+            let utf8 = self.decoded.get(..written).unwrap_or_default();
+            self.pending.fill_from_slice(utf8);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::Decompress;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use ruzstd::decoding::{FrameDecoder, StreamingDecoder};
+use std::io::{self, Chain, Cursor, Read};
+
+/// Longest magic number looked for, that of zstd frames.
+const MAGIC_LEN: usize = 4;
+
+/// The format to undo for input starting with `head`, or `None` to read it as is. `Auto`
+/// goes by the magic bytes of gzip members, zstd frames and zlib streams.
+pub fn compression(decompress: Decompress, head: &[u8]) -> Option<Decompress> {
+    match decompress {
+        Decompress::Auto => match head {
+            [0x1F, 0x8B, ..] => Some(Decompress::Gzip),
+            [0x28, 0xB5, 0x2F, 0xFD, ..] => Some(Decompress::Zstd),
+            // Deflate with a valid header checksum, which `<` and whitespace never start.
+            [cmf, flg, ..]
+                if cmf & 0x0F == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0 =>
+            {
+                Some(Decompress::Zlib)
+            }
+            _ => None,
+        },
+        Decompress::Never => None,
+        Decompress::Gzip | Decompress::Zlib | Decompress::Zstd => Some(decompress),
+    }
+}
+
+/// A reader of input decompressed as it is read.
+pub enum Decompressed<R: Read> {
+    Plain(R),
+    Gzip(MultiGzDecoder<R>),
+    Zlib(ZlibDecoder<R>),
+    Zstd(Box<StreamingDecoder<R, FrameDecoder>>),
+}
+
+impl<R: Read> Decompressed<R> {
+    /// Wraps `raw` in a decoder for `format`, as [`compression`] returns it.
+    pub fn new(raw: R, format: Option<Decompress>) -> io::Result<Self> {
+        Ok(match format {
+            Some(Decompress::Gzip) => Self::Gzip(MultiGzDecoder::new(raw)),
+            Some(Decompress::Zlib) => Self::Zlib(ZlibDecoder::new(raw)),
+            Some(Decompress::Zstd) => {
+                let decoder = StreamingDecoder::new(raw).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid zstd data: {err}"),
+                    )
+                })?;
+                Self::Zstd(Box::new(decoder))
+            }
+            Some(Decompress::Auto | Decompress::Never) | None => Self::Plain(raw),
+        })
+    }
+}
+
+impl<R: Read> Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(raw) => raw.read(buf),
+            Self::Gzip(decoder) => decoder.read(buf),
+            Self::Zlib(decoder) => decoder.read(buf),
+            Self::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+/// A reader of input decompressed as [`decompressing`] sets up, which looks at the first
+/// bytes only once they are read, so that lazy sources stay lazy.
+pub enum Decompressing<R: Read> {
+    Pending(Option<R>, Decompress),
+    Reading(Box<Decompressed<Chain<Cursor<Vec<u8>>, R>>>),
+}
+
+impl<R: Read> Read for Decompressing<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Self::Pending(raw, decompress) = self {
+            let Some(mut raw) = raw.take() else {
+                return Ok(0);
+            };
+            let mut head = Vec::with_capacity(MAGIC_LEN);
+            if *decompress == Decompress::Auto {
+                raw.by_ref().take(MAGIC_LEN as u64).read_to_end(&mut head)?;
+            }
+            let format = compression(*decompress, &head);
+            *self = Self::Reading(Box::new(Decompressed::new(
+                Cursor::new(head).chain(raw),
+                format,
+            )?));
+        }
+        match self {
+            Self::Pending(..) => Ok(0),
+            Self::Reading(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Reads `raw` decompressed as `decompress` says, looking ahead at its first bytes for
+/// `Auto`.
+pub fn decompressing<R: Read>(raw: R, decompress: Decompress) -> Decompressing<R> {
+    Decompressing::Pending(Some(raw), decompress)
+}
@@ -0,0 +1,146 @@
+use crate::xmltodict_core::Position;
+use std::io::{self, BufRead, Read};
+
+/// Counts the lines of the bytes the XML reader consumes, so errors can say where they were
+/// found. Only newlines consumed since the last [`mark`](Self::mark) are remembered.
+pub struct LineCounter<R> {
+    inner: R,
+    consumed: usize,
+    line: usize,
+    line_start: usize,
+    marked_line_start: usize,
+    recent: Vec<usize>,
+    /// Bytes consumed since the last [`record`](Self::record), while recording.
+    recorded: Option<Vec<u8>>,
+}
+
+impl<R> LineCounter<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            consumed: 0,
+            line: 1,
+            line_start: 0,
+            marked_line_start: 0,
+            recent: Vec::new(),
+            recorded: None,
+        }
+    }
+
+    /// Starts keeping the bytes consumed from here on, dropping any kept before.
+    pub fn record(&mut self) {
+        match &mut self.recorded {
+            Some(recorded) => recorded.clear(),
+            None => self.recorded = Some(Vec::new()),
+        }
+    }
+
+    /// Stops recording, returning the bytes consumed since [`record`](Self::record).
+    pub fn take_recorded(&mut self) -> Vec<u8> {
+        self.recorded.take().unwrap_or_default()
+    }
+
+    /// Forgets newlines consumed so far; later positions must not come before this point.
+    pub fn mark(&mut self) {
+        self.recent.clear();
+        self.marked_line_start = self.line_start;
+    }
+
+    /// Line and column of a byte offset at or after the last mark.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.consumed);
+        let after = self
+            .recent
+            .iter()
+            .rev()
+            .take_while(|&&newline| newline >= offset)
+            .count();
+        let before = self.recent.len() - after;
+        let line_start = before
+            .checked_sub(1)
+            .and_then(|last| self.recent.get(last))
+            .map_or(self.marked_line_start, |newline| newline + 1);
+        Position {
+            line: self.line - after,
+            column: offset.saturating_sub(line_start),
+            offset,
+        }
+    }
+}
+
+impl<R: BufRead> Read for LineCounter<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(out.len());
+        if let (Some(dst), Some(src)) = (out.get_mut(..len), available.get(..len)) {
+            dst.copy_from_slice(src);
+        }
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The caller has just filled the buffer, so this does no I/O.
+        if let Ok(buf) = self.inner.fill_buf() {
+            let consumed = buf.get(..amt).unwrap_or(buf);
+            if let Some(recorded) = &mut self.recorded {
+                recorded.extend_from_slice(consumed);
+            }
+            for index in memchr::memchr_iter(b'\n', consumed) {
+                let newline = self.consumed + index;
+                self.recent.push(newline);
+                self.line += 1;
+                self.line_start = newline + 1;
+            }
+        }
+        self.consumed += amt;
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_follow_consumed_lines() {
+        let mut counter = LineCounter::new(&b"ab\ncd\nef"[..]);
+        counter.consume(4);
+        assert_eq!(
+            Position {
+                line: 2,
+                column: 1,
+                offset: 4
+            },
+            counter.position(4)
+        );
+        assert_eq!(1, counter.position(1).line);
+        counter.mark();
+        counter.consume(4);
+        assert_eq!(
+            Position {
+                line: 3,
+                column: 2,
+                offset: 8
+            },
+            counter.position(8)
+        );
+        assert_eq!(2, counter.position(5).line);
+    }
+
+    #[test]
+    fn records_consumed_bytes() {
+        let mut counter = LineCounter::new(&b"<a>x</a>"[..]);
+        counter.consume(3);
+        counter.record();
+        counter.consume(5);
+        assert_eq!(b"x</a>".to_vec(), counter.take_recorded());
+        assert!(counter.take_recorded().is_empty());
+    }
+}
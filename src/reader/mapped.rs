@@ -0,0 +1,33 @@
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Maps a file read-only into memory. Errors mention the path, like Python's `open()`.
+pub fn map_file(path: &Path) -> io::Result<Mmap> {
+    let with_path =
+        |err: io::Error| io::Error::new(err.kind(), format!("{err}: {}", path.display()));
+    let file = File::open(path).map_err(with_path)?;
+    if file.metadata().map_err(with_path)?.is_dir() {
+        return Err(with_path(io::ErrorKind::IsADirectory.into()));
+    }
+    // SAFETY: the mapping is read-only and private to the parse. Like every file-backed
+    // mapping it assumes the file is not truncated or rewritten while it is being parsed.
+    unsafe { Mmap::map(&file) }.map_err(with_path)
+}
+
+/// A memory-mapped document handed from `parse_file()` to `parse()` without copying.
+#[cfg(feature = "python")]
+#[pyo3::pyclass(frozen, module = "xmltodict_rs")]
+pub struct MappedFile(Mmap);
+
+#[cfg(feature = "python")]
+impl MappedFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        map_file(path).map(Self)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
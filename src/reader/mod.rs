@@ -1,6 +1,20 @@
+mod decoding;
+mod decompress;
+#[cfg(feature = "python")]
 mod file_like;
+#[cfg(feature = "python")]
 mod generator;
+mod lines;
+mod mapped;
 mod pending;
 
+pub use decoding::DecodingRead;
+pub use decompress::{compression, decompressing, Decompressed};
+#[cfg(feature = "python")]
 pub use file_like::PyFileLikeRead;
+#[cfg(feature = "python")]
 pub use generator::PyGeneratorRead;
+pub use lines::LineCounter;
+pub use mapped::map_file;
+#[cfg(feature = "python")]
+pub use mapped::MappedFile;
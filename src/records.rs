@@ -0,0 +1,257 @@
+use crate::events::{EventReader, XmlEvent};
+use crate::path_pattern::PathPattern;
+use crate::xmltodict_core::Error;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Values read from the records of a document, a list per column with one entry per record.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Columns {
+    pub names: Vec<String>,
+    /// The text of an element or the value of an attribute; `None` where a record has none.
+    pub values: Vec<Vec<Option<String>>>,
+}
+
+/// Reads the elements on a record path a row at a time, for `read_records` and `to_csv`.
+/// Columns are paths inside a record: `title` for the text of a child, `author/name` for
+/// that of a descendant, `@id` for an attribute of the record and `author/@id` for one of
+/// a descendant. Names are matched as written in the document.
+pub struct Records {
+    record: PathPattern,
+    names: Vec<String>,
+    /// Column of each path inside a record.
+    index: HashMap<String, usize>,
+    /// Whether the attributes and children of records become columns as they are found,
+    /// rather than the columns being given.
+    infer: bool,
+    /// Whether columns are inferred from the first record alone.
+    first_only: bool,
+    /// Values of the record being read, one per column.
+    row: Vec<Option<String>>,
+}
+
+impl Records {
+    /// Reads the records at `record_path`, written as for `only_paths`, into the given
+    /// `columns`, or into a column for each attribute and child element records have.
+    pub fn new(record_path: &str, columns: Option<Vec<String>>) -> Result<Self, String> {
+        let mut records = Self {
+            record: PathPattern::new(record_path),
+            names: Vec::new(),
+            index: HashMap::new(),
+            infer: columns.is_none(),
+            first_only: false,
+            row: Vec::new(),
+        };
+        for column in columns.into_iter().flatten() {
+            if column.split('/').any(str::is_empty) {
+                return Err(format!("invalid column {column:?}"));
+            }
+            if records.index.contains_key(&column) {
+                return Err(format!("column {column:?} is given twice"));
+            }
+            records.add_column(column);
+        }
+        Ok(records)
+    }
+
+    /// Infers columns from the first record only, so that every row has the same columns
+    /// and can be written out before the next record is read.
+    #[must_use]
+    pub fn with_first_record_columns(mut self) -> Self {
+        self.first_only = true;
+        self
+    }
+
+    /// Reads the records of a whole document.
+    pub fn read<R: BufRead>(self, events: EventReader<'_, R>) -> Result<Columns, Error> {
+        let mut values: Vec<Vec<Option<String>>> = Vec::new();
+        let mut rows = 0;
+        let names = self.read_rows(events, |names, row| {
+            // Columns inferred from a later record are empty in the rows before it.
+            values.resize_with(names.len(), || vec![None; rows]);
+            for (values, value) in values.iter_mut().zip(row) {
+                values.push(value);
+            }
+            rows += 1;
+            Ok(())
+        })?;
+        values.resize_with(names.len(), Vec::new);
+        Ok(Columns { names, values })
+    }
+
+    /// Hands each record to `on_row` as it ends, with the columns known so far and a value
+    /// for each; returns the columns.
+    pub fn read_rows<R, F>(
+        mut self,
+        mut events: EventReader<'_, R>,
+        mut on_row: F,
+    ) -> Result<Vec<String>, Error>
+    where
+        R: BufRead,
+        F: FnMut(&[String], Vec<Option<String>>) -> Result<(), Error>,
+    {
+        let mut path: Vec<String> = Vec::new();
+        // Depth of the record being read, and the column of each element open inside it
+        // with the text read for it so far.
+        let mut record: Option<usize> = None;
+        let mut open: Vec<Option<(usize, String)>> = Vec::new();
+        while let Some(event) = events.next_event()? {
+            match event {
+                XmlEvent::Start { name, attributes } => {
+                    path.push(name);
+                    if record.is_none() && self.is_record(&path) {
+                        record = Some(path.len());
+                        self.row = vec![None; self.names.len()];
+                    }
+                    let Some(depth) = record else {
+                        continue;
+                    };
+                    let inside = path.get(depth..).unwrap_or_default().join("/");
+                    let is_child = path.len() == depth + 1;
+                    for (name, value) in attributes {
+                        let key = if inside.is_empty() {
+                            format!("@{name}")
+                        } else {
+                            format!("{inside}/@{name}")
+                        };
+                        if let Some(column) = self.column(key, inside.is_empty()) {
+                            self.set(column, Some(value));
+                        }
+                    }
+                    let column = if inside.is_empty() {
+                        None
+                    } else {
+                        self.column(inside, is_child)
+                    };
+                    open.push(column.map(|column| (column, String::new())));
+                }
+                XmlEvent::End(_) => {
+                    if let Some(depth) = record {
+                        if let Some(Some((column, text))) = open.pop() {
+                            // Elements without text are `None`, as `parse` has them.
+                            self.set(column, Some(text).filter(|text| !text.is_empty()));
+                        }
+                        if path.len() == depth {
+                            record = None;
+                            self.infer &= !self.first_only;
+                            on_row(&self.names, std::mem::take(&mut self.row))?;
+                        }
+                    }
+                    path.pop();
+                }
+                XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                    if let Some(Some((_, buffer))) = open.last_mut() {
+                        buffer.push_str(&text);
+                    }
+                }
+                XmlEvent::DocType(_)
+                | XmlEvent::Comment(_)
+                | XmlEvent::ProcessingInstruction(_) => {}
+            }
+        }
+        Ok(self.names)
+    }
+
+    fn is_record(&self, path: &[String]) -> bool {
+        let names: Vec<&str> = path.iter().map(String::as_str).collect();
+        self.record.matches(&names)
+    }
+
+    fn add_column(&mut self, name: String) -> usize {
+        let column = self.names.len();
+        self.index.insert(name.clone(), column);
+        self.names.push(name);
+        self.row.push(None);
+        column
+    }
+
+    /// The column for a path inside the current record, added if columns are inferred and
+    /// the path is an attribute of the record or a child element.
+    fn column(&mut self, key: String, inferable: bool) -> Option<usize> {
+        match self.index.get(&key) {
+            Some(&column) => Some(column),
+            None if self.infer && inferable => Some(self.add_column(key)),
+            None => None,
+        }
+    }
+
+    /// Sets a value of the current record, unless an element repeated in it set it first.
+    fn set(&mut self, column: usize, value: Option<String>) {
+        if let Some(slot) = self.row.get_mut(column).filter(|slot| slot.is_none()) {
+            *slot = value;
+        }
+    }
+}
+
+/// Appends a CSV row as Python's `csv.writer` writes it by default: fields quoted only when
+/// they hold a comma, quote or line break, quotes doubled, `None` empty and `\r\n` after.
+pub fn write_csv_row<'a>(output: &mut String, fields: impl IntoIterator<Item = Option<&'a str>>) {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        let field = field.unwrap_or_default();
+        if field.contains([',', '"', '\r', '\n']) {
+            output.push('"');
+            output.push_str(&field.replace('"', "\"\""));
+            output.push('"');
+        } else {
+            output.push_str(field);
+        }
+    }
+    output.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParseConfig;
+
+    fn read(xml: &str, columns: Option<Vec<String>>) -> Columns {
+        let config = ParseConfig::default();
+        let events = EventReader::new(xml.as_bytes(), &config);
+        Records::new("rows/row", columns)
+            .map_err(Error::Value)
+            .and_then(|records| records.read(events))
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn infers_columns_from_attributes_and_children() {
+        let xml = r#"<rows><row id="1"><a>x</a></row><row id="2"><b> y </b><a/></row></rows>"#;
+        let columns = read(xml, None);
+        assert_eq!(vec!["@id", "a", "b"], columns.names);
+        assert_eq!(
+            vec![
+                vec![Some("1".to_owned()), Some("2".to_owned())],
+                vec![Some("x".to_owned()), None],
+                vec![None, Some("y".to_owned())],
+            ],
+            columns.values
+        );
+    }
+
+    #[test]
+    fn reads_given_paths_inside_records() {
+        let xml = r#"<rows><row><p id="7"><n>a</n><n>b</n></p></row><other/></rows>"#;
+        let columns = read(xml, Some(vec!["p/n".to_owned(), "p/@id".to_owned()]));
+        assert_eq!(
+            vec![vec![Some("a".to_owned())], vec![Some("7".to_owned())]],
+            columns.values
+        );
+    }
+
+    #[test]
+    fn writes_csv_rows_with_minimal_quoting() {
+        let mut output = String::new();
+        write_csv_row(
+            &mut output,
+            [Some("a"), None, Some("b,c"), Some("say \"hi\"")],
+        );
+        write_csv_row(&mut output, [Some("two\nlines")]);
+        assert_eq!(
+            "a,,\"b,c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\"\r\n",
+            output
+        );
+    }
+}
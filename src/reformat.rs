@@ -0,0 +1,255 @@
+use crate::reader::LineCounter;
+use crate::whitespace::{is_blank, xml_space};
+use crate::xmltodict_core::{Error, SyntaxError};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// How [`reformat`] lays out a document.
+pub struct Layout {
+    /// Written once per level of nesting before an element put on a line of its own.
+    pub indent: String,
+    /// Starts each line after the first; empty for `minify`, which writes no line breaks.
+    pub newl: String,
+    /// Whether comments are kept.
+    pub comments: bool,
+}
+
+/// An open element written with the layout.
+#[derive(Default)]
+struct Scope {
+    /// A child element, comment or processing instruction has been written inside it.
+    children: bool,
+    /// Text other than whitespace has been written inside it.
+    text: bool,
+}
+
+/// Rewrites a document token by token, putting each element, comment and processing
+/// instruction on a line of its own as `layout` says, for `pretty` and `minify`. Whitespace
+/// between elements is replaced; everything else is written as it was read, text and attribute
+/// values still escaped and CDATA sections kept, so nothing is lost. An element with text
+/// before a child element, or with `xml:space="preserve"`, is written as it was.
+pub fn reformat<R: BufRead>(reader: R, layout: &Layout) -> Result<String, Error> {
+    let mut writer = Reformatter {
+        layout,
+        output: String::new(),
+        open: Vec::new(),
+        pending: String::new(),
+        verbatim: 0,
+    };
+    let mut reader = Reader::from_reader(LineCounter::new(reader));
+    reader.check_end_names(true).check_comments(true);
+    let mut buf = Vec::new();
+    let mut seen_root = false;
+    loop {
+        buf.clear();
+        reader.get_mut().mark();
+        let event = match reader.read_event_into(&mut buf) {
+            Ok(event) => event,
+            Err(err) => {
+                let position = reader.get_ref().position(reader.buffer_position());
+                return Err(SyntaxError::from_quick_xml(&err).at(position).into());
+            }
+        };
+        seen_root |= matches!(event, Event::Start(_) | Event::Empty(_));
+        if let Event::Eof = event {
+            if seen_root && writer.open.is_empty() && writer.verbatim == 0 {
+                return Ok(writer.output);
+            }
+            let message = if seen_root {
+                "unclosed element(s) found"
+            } else {
+                "no element found"
+            };
+            let position = reader.get_ref().position(reader.buffer_position());
+            return Err(SyntaxError::new(SyntaxError::NO_ELEMENTS, message)
+                .at(position)
+                .into());
+        }
+        writer.write(&event)?;
+    }
+}
+
+struct Reformatter<'l> {
+    layout: &'l Layout,
+    output: String,
+    open: Vec<Scope>,
+    /// Whitespace read inside an element, written only if text or its end tag follows.
+    pending: String,
+    /// How many of the open elements are inside one written as it was, counting it.
+    verbatim: usize,
+}
+
+impl Reformatter<'_> {
+    fn write(&mut self, event: &Event) -> Result<(), Error> {
+        if self.verbatim > 0 {
+            match event {
+                Event::Start(_) => self.verbatim += 1,
+                Event::End(_) => self.verbatim -= 1,
+                Event::Empty(_)
+                | Event::Text(_)
+                | Event::CData(_)
+                | Event::Comment(_)
+                | Event::Decl(_)
+                | Event::PI(_)
+                | Event::DocType(_)
+                | Event::Eof => {}
+            }
+            return write_raw(&mut self.output, event);
+        }
+        match event {
+            Event::Start(start) | Event::Empty(start) => {
+                let preserve = xml_space(start) == Some(true);
+                let in_text = self.open.last().is_some_and(|scope| scope.text);
+                if in_text {
+                    self.flush_pending();
+                } else {
+                    self.new_line();
+                }
+                if let Some(parent) = self.open.last_mut() {
+                    parent.children = true;
+                }
+                write_raw(&mut self.output, event)?;
+                if let Event::Start(_) = event {
+                    if in_text || preserve {
+                        self.verbatim = 1;
+                    } else {
+                        self.open.push(Scope::default());
+                    }
+                }
+            }
+            Event::End(_) => {
+                let scope = self.open.pop().unwrap_or_default();
+                if scope.children && !scope.text {
+                    self.new_line();
+                } else {
+                    self.flush_pending();
+                }
+                write_raw(&mut self.output, event)?;
+            }
+            Event::Text(text) => {
+                let text = std::str::from_utf8(text)?;
+                if is_blank(text) {
+                    if !self.open.is_empty() {
+                        self.pending.push_str(text);
+                    }
+                } else {
+                    self.write_text(event)?;
+                }
+            }
+            Event::CData(_) => self.write_text(event)?,
+            Event::Comment(_) if !self.layout.comments => {}
+            Event::Comment(_) | Event::PI(_) => match self.open.last_mut() {
+                Some(scope) if scope.text => {
+                    self.flush_pending();
+                    write_raw(&mut self.output, event)?;
+                }
+                Some(scope) => {
+                    scope.children = true;
+                    self.new_line();
+                    write_raw(&mut self.output, event)?;
+                }
+                None => {
+                    self.new_line();
+                    write_raw(&mut self.output, event)?;
+                }
+            },
+            Event::Decl(_) | Event::DocType(_) => {
+                self.new_line();
+                write_raw(&mut self.output, event)?;
+            }
+            Event::Eof => {}
+        }
+        Ok(())
+    }
+
+    fn write_text(&mut self, event: &Event) -> Result<(), Error> {
+        if let Some(scope) = self.open.last_mut() {
+            scope.text = true;
+        }
+        self.flush_pending();
+        write_raw(&mut self.output, event)
+    }
+
+    fn flush_pending(&mut self) {
+        self.output.push_str(&self.pending);
+        self.pending.clear();
+    }
+
+    /// Starts a line indented for the depth of the open elements, dropping whitespace read
+    /// before it; the first line of the document is already started.
+    fn new_line(&mut self) {
+        self.pending.clear();
+        if self.output.is_empty() {
+            return;
+        }
+        self.output.push_str(&self.layout.newl);
+        for _ in 0..self.open.len() {
+            self.output.push_str(&self.layout.indent);
+        }
+    }
+}
+
+/// Writes the markup of an event as it was in the document.
+fn write_raw(output: &mut String, event: &Event) -> Result<(), Error> {
+    let (open, close) = match event {
+        Event::Start(_) => ("<", ">"),
+        Event::End(_) => ("</", ">"),
+        Event::Empty(_) => ("<", "/>"),
+        Event::Text(_) | Event::Eof => ("", ""),
+        Event::CData(_) => ("<![CDATA[", "]]>"),
+        Event::Comment(_) => ("<!--", "-->"),
+        Event::Decl(_) | Event::PI(_) => ("<?", "?>"),
+        Event::DocType(_) => ("<!DOCTYPE ", ">"),
+    };
+    output.push_str(open);
+    output.push_str(std::str::from_utf8(event)?);
+    output.push_str(close);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pretty(xml: &str) -> String {
+        let layout = Layout {
+            indent: "  ".to_owned(),
+            newl: "\n".to_owned(),
+            comments: true,
+        };
+        reformat(xml.as_bytes(), &layout).unwrap_or_else(|err| err.to_string())
+    }
+
+    #[test]
+    fn indents_elements_and_keeps_markup() {
+        let xml = "<?xml version=\"1.0\"?><!--c--><r a=\"&amp;\"> <i>x &lt; y</i>\n<e/>\
+                   <d><![CDATA[<raw>]]></d><?pi data?></r>";
+        assert_eq!(
+            "<?xml version=\"1.0\"?>\n<!--c-->\n<r a=\"&amp;\">\n  <i>x &lt; y</i>\n  <e/>\n  \
+             <d><![CDATA[<raw>]]></d>\n  <?pi data?>\n</r>",
+            pretty(xml)
+        );
+    }
+
+    #[test]
+    fn mixed_and_preserved_content_is_kept() {
+        let xml = r#"<r><p>a <b> x </b> c</p><s xml:space="preserve"> <i/> </s><t> </t></r>"#;
+        let expected = "<r>\n  <p>a <b> x </b> c</p>\n  <s xml:space=\"preserve\"> <i/> </s>\n  \
+                        <t> </t>\n</r>";
+        assert_eq!(expected, pretty(xml));
+        assert!(pretty("<r><i></r>").starts_with("Expecting </i>"));
+    }
+
+    #[test]
+    fn minifies_without_line_breaks_or_comments() {
+        let layout = Layout {
+            indent: String::new(),
+            newl: String::new(),
+            comments: false,
+        };
+        let xml = "<!--c-->\n<r>\n  <a> x </a>\n  <!--c-->\n  <p>a <!--c--><b/></p>\n</r>\n";
+        let minified = reformat(xml.as_bytes(), &layout).unwrap_or_default();
+        assert_eq!("<r><a> x </a><p>a <b/></p></r>", minified);
+    }
+}
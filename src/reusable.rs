@@ -0,0 +1,380 @@
+use crate::config::{
+    check_xml_version, AttrPrefix, CdataKey, CommentKey, DoctypeKey, NamespaceSeparator, OrderKey,
+    PiKey, UnparseConfig,
+};
+use crate::python::{
+    check_document_roots, extract_key_set, extract_namespace_map, parse_options, Kept, ParseSetup,
+};
+use crate::unparser::{
+    as_dict, encode_output, model_value, output_charset, DefaultHook, Sink, XmlWriter,
+};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+use std::sync::{Mutex, PoisonError};
+
+/// The constructor arguments of a [`Parser`] or [`Serializer`] that differ from their
+/// defaults. `repr()` shows them, and pickling and copying pass them back to the constructor.
+struct Options<'py>(Bound<'py, PyDict>);
+
+impl<'py> Options<'py> {
+    fn new(py: Python<'py>) -> Self {
+        Self(PyDict::new(py))
+    }
+
+    fn set<T>(&self, name: &str, value: T, default: &T) -> PyResult<()>
+    where
+        T: PartialEq + IntoPyObject<'py>,
+    {
+        if value != *default {
+            self.0.set_item(name, value)?;
+        }
+        Ok(())
+    }
+
+    fn set_some<T: IntoPyObject<'py>>(&self, name: &str, value: Option<T>) -> PyResult<()> {
+        if let Some(value) = value {
+            self.0.set_item(name, value)?;
+        }
+        Ok(())
+    }
+
+    fn into_dict(self) -> Py<PyDict> {
+        self.0.unbind()
+    }
+}
+
+/// `Parser(force_list=('item',))`: the class and the options it was made with.
+fn options_repr(py: Python, class: &str, options: &Py<PyDict>) -> PyResult<String> {
+    let mut arguments = Vec::new();
+    for (name, value) in options.bind(py) {
+        arguments.push(format!("{name}={}", value.repr()?));
+    }
+    Ok(format!("{class}({})", arguments.join(", ")))
+}
+
+/// The options given to a [`Parser`] that differ from their defaults in `parse`.
+fn given_options<'py>(
+    parse_options: &Bound<'py, PyAny>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Py<PyDict>> {
+    let py = parse_options.py();
+    let given = PyDict::new(py);
+    let parameters = py
+        .import("inspect")?
+        .call_method1("signature", (parse_options,))?
+        .getattr("parameters")?;
+    for (name, value) in options.into_iter().flatten() {
+        let default = parameters.get_item(&name)?.getattr("default")?;
+        if !value.eq(default)? {
+            given.set_item(name, value)?;
+        }
+    }
+    Ok(given.unbind())
+}
+
+/// Parses any number of documents with the same options, which are checked once. Parses
+/// reuse the read buffer and key strings of the last one; one started on another thread
+/// while a parse is running gets its own.
+#[pyclass(module = "xmltodict_rs")]
+pub struct Parser {
+    options: Py<PyDict>,
+    setup: Py<ParseSetup>,
+    kept: Mutex<Option<Kept>>,
+}
+
+#[pymethods]
+impl Parser {
+    /// Takes the options of `parse`, with the same defaults.
+    #[new]
+    #[pyo3(signature = (**options))]
+    fn new(py: Python, options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let parse_options = wrap_pyfunction!(parse_options, py)?;
+        let setup = parse_options
+            .call((), options)?
+            .downcast_into::<ParseSetup>()?;
+        Ok(Self {
+            options: given_options(parse_options.as_any(), options)?,
+            setup: setup.unbind(),
+            kept: Mutex::new(None),
+        })
+    }
+
+    /// Parse XML string/bytes into a Python dictionary with the parser's options.
+    fn parse(&self, py: Python, xml_input: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+        let mut kept = self
+            .kept
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .unwrap_or_default();
+        let parsed = self.setup.get().parse(py, xml_input, &mut kept);
+        *self.kept.lock().unwrap_or_else(PoisonError::into_inner) = Some(kept);
+        parsed
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        options_repr(py, "Parser", &self.options)
+    }
+
+    /// Pickling and copying make a new parser with the same options.
+    fn __getnewargs_ex__(&self, py: Python) -> ((), Py<PyDict>) {
+        ((), self.options.clone_ref(py))
+    }
+}
+
+/// Writes any number of dicts as XML with the same options, which are checked once. Each
+/// document is written into the output buffer of the last one; one started on another thread
+/// while a document is being written gets its own writer.
+#[pyclass(module = "xmltodict_rs")]
+pub struct Serializer {
+    options: Py<PyDict>,
+    config: UnparseConfig,
+    preprocessor: Option<Py<PyAny>>,
+    default: Option<Py<PyAny>>,
+    return_bytes: bool,
+    initial_buffer_size: usize,
+    writer: Mutex<Option<XmlWriter>>,
+}
+
+impl Serializer {
+    fn fresh(&self, py: Python) -> PyResult<XmlWriter> {
+        let default = self
+            .default
+            .as_ref()
+            .map(|callback| DefaultHook::new(py, callback.clone_ref(py)))
+            .transpose()?;
+        let preprocessor = self
+            .preprocessor
+            .as_ref()
+            .map(|preprocessor| preprocessor.clone_ref(py));
+        Ok(
+            XmlWriter::new(self.config.clone(), preprocessor, default, Sink::Buffer)
+                .with_capacity(self.initial_buffer_size),
+        )
+    }
+
+    fn write<'py>(
+        &self,
+        py: Python<'py>,
+        writer: &mut XmlWriter,
+        input_dict: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let input_dict = &as_dict(&model_value(input_dict, self.config.pydantic_support)?)?;
+        check_document_roots(input_dict, &self.config)?;
+        let document = writer.buffer_document(py, input_dict)?;
+        if self.return_bytes {
+            encode_output(py, document, &self.config.encoding, true)
+        } else {
+            Ok(PyString::new(py, document).into_any())
+        }
+    }
+}
+
+#[pymethods]
+impl Serializer {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::fn_params_excessive_bools)]
+    #[pyo3(signature = (
+        *,
+        encoding = "utf-8",
+        full_document = true,
+        short_empty_elements = false,
+        attr_prefix = "@",
+        cdata_key = "#text",
+        pretty = false,
+        newl = "\n",
+        indent = "\t",
+        preprocessor = None,
+        namespace_separator = ":",
+        namespaces = None,
+        preserve_mixed_content = false,
+        doctype = None,
+        doctype_key = "#doctype",
+        escape_control_chars = true,
+        escape_apos = false,
+        invalid_chars = "raise",
+        use_cdata = false,
+        cdata_for_keys = None,
+        comment_key = "#comment",
+        pi_key = "#pi",
+        attr_dict_key = None,
+        canonical = false,
+        standalone = None,
+        xml_version = "1.0",
+        pretty_depth = None,
+        default = None,
+        datetime_format = None,
+        expand_iter = None,
+        duplicate_attributes = "raise",
+        pydantic_support = false,
+        escape_non_ascii = false,
+        return_bytes = false,
+        initial_buffer_size = None,
+        preserve_sibling_order = false,
+        order_key = "#order",
+        index_key = None,
+    ))]
+    fn new(
+        py: Python,
+        encoding: &str,
+        full_document: bool,
+        short_empty_elements: bool,
+        attr_prefix: &str,
+        cdata_key: &str,
+        pretty: bool,
+        newl: &str,
+        indent: &str,
+        preprocessor: Option<Py<PyAny>>,
+        namespace_separator: &str,
+        namespaces: Option<&Bound<'_, PyAny>>,
+        preserve_mixed_content: bool,
+        doctype: Option<String>,
+        doctype_key: &str,
+        escape_control_chars: bool,
+        escape_apos: bool,
+        invalid_chars: &str,
+        use_cdata: bool,
+        cdata_for_keys: Option<&Bound<'_, PyAny>>,
+        comment_key: &str,
+        pi_key: &str,
+        attr_dict_key: Option<String>,
+        canonical: bool,
+        standalone: Option<bool>,
+        xml_version: &str,
+        pretty_depth: Option<usize>,
+        default: Option<Py<PyAny>>,
+        datetime_format: Option<String>,
+        expand_iter: Option<String>,
+        duplicate_attributes: &str,
+        pydantic_support: bool,
+        escape_non_ascii: bool,
+        return_bytes: bool,
+        initial_buffer_size: Option<usize>,
+        preserve_sibling_order: bool,
+        order_key: &str,
+        index_key: Option<String>,
+    ) -> PyResult<Self> {
+        let options = Options::new(py);
+        options.set("encoding", encoding, &"utf-8")?;
+        options.set("full_document", full_document, &true)?;
+        options.set("short_empty_elements", short_empty_elements, &false)?;
+        options.set("attr_prefix", attr_prefix, &"@")?;
+        options.set("cdata_key", cdata_key, &"#text")?;
+        options.set("pretty", pretty, &false)?;
+        options.set("newl", newl, &"\n")?;
+        options.set("indent", indent, &"\t")?;
+        options.set_some("preprocessor", preprocessor.as_ref())?;
+        options.set("namespace_separator", namespace_separator, &":")?;
+        options.set_some("namespaces", namespaces)?;
+        options.set("preserve_mixed_content", preserve_mixed_content, &false)?;
+        options.set_some("doctype", doctype.as_deref())?;
+        options.set("doctype_key", doctype_key, &"#doctype")?;
+        options.set("escape_control_chars", escape_control_chars, &true)?;
+        options.set("escape_apos", escape_apos, &false)?;
+        options.set("invalid_chars", invalid_chars, &"raise")?;
+        options.set("use_cdata", use_cdata, &false)?;
+        options.set_some("cdata_for_keys", cdata_for_keys)?;
+        options.set("comment_key", comment_key, &"#comment")?;
+        options.set("pi_key", pi_key, &"#pi")?;
+        options.set_some("attr_dict_key", attr_dict_key.as_deref())?;
+        options.set("canonical", canonical, &false)?;
+        options.set_some("standalone", standalone)?;
+        options.set("xml_version", xml_version, &"1.0")?;
+        options.set_some("pretty_depth", pretty_depth)?;
+        options.set_some("default", default.as_ref())?;
+        options.set_some("datetime_format", datetime_format.as_deref())?;
+        options.set_some("expand_iter", expand_iter.as_deref())?;
+        options.set("duplicate_attributes", duplicate_attributes, &"raise")?;
+        options.set("pydantic_support", pydantic_support, &false)?;
+        options.set("escape_non_ascii", escape_non_ascii, &false)?;
+        options.set("return_bytes", return_bytes, &false)?;
+        options.set_some("initial_buffer_size", initial_buffer_size)?;
+        options.set("preserve_sibling_order", preserve_sibling_order, &false)?;
+        options.set("order_key", order_key, &"#order")?;
+        options.set_some("index_key", index_key.as_deref())?;
+        check_xml_version(xml_version).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        let config = UnparseConfig {
+            encoding: encoding.to_owned(),
+            xml_version: xml_version.to_owned(),
+            standalone,
+            full_document,
+            short_empty_elements,
+            attr_prefix: AttrPrefix::new(attr_prefix),
+            attr_dict_key,
+            cdata_key: CdataKey::new(cdata_key),
+            pretty,
+            pretty_depth,
+            newl: newl.to_owned(),
+            indent: indent.to_owned(),
+            namespace_separator: NamespaceSeparator::new(namespace_separator),
+            namespaces: extract_namespace_map(namespaces)?,
+            preserve_mixed_content,
+            doctype,
+            doctype_key: DoctypeKey::new(doctype_key),
+            escape_control_chars,
+            escape_apos,
+            invalid_chars: invalid_chars
+                .parse()
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+            escape_non_ascii,
+            charset: output_charset(py, return_bytes.then_some(encoding))?,
+            duplicate_attributes: duplicate_attributes
+                .parse()
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+            use_cdata,
+            cdata_for_keys: extract_key_set(cdata_for_keys, "cdata_for_keys")?,
+            comment_key: CommentKey::new(comment_key),
+            pi_key: PiKey::new(pi_key),
+            datetime_format,
+            expand_iter,
+            pydantic_support,
+            canonical,
+            preserve_sibling_order,
+            order_key: OrderKey::new(order_key),
+            index_key,
+        };
+        Ok(Self {
+            options: options.into_dict(),
+            config: if canonical {
+                config.canonical_form()
+            } else {
+                config
+            },
+            preprocessor,
+            default,
+            return_bytes,
+            initial_buffer_size: initial_buffer_size.unwrap_or(0),
+            writer: Mutex::new(None),
+        })
+    }
+
+    /// Convert a Python dictionary to an XML document with the serializer's options.
+    fn unparse<'py>(
+        &self,
+        py: Python<'py>,
+        input_dict: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let mut writer = match writer {
+            Some(writer) => writer,
+            None => self.fresh(py)?,
+        };
+        let written = self.write(py, &mut writer, input_dict);
+        *self.writer.lock().unwrap_or_else(PoisonError::into_inner) = Some(writer);
+        written
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        options_repr(py, "Serializer", &self.options)
+    }
+
+    /// Pickling and copying make a new serializer with the same options.
+    fn __getnewargs_ex__(&self, py: Python) -> ((), Py<PyDict>) {
+        ((), self.options.clone_ref(py))
+    }
+}
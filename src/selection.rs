@@ -0,0 +1,249 @@
+use crate::events::XmlEvent;
+use crate::path_pattern::PathPattern;
+
+/// How an open element takes part in an `only_paths` parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    /// On a selected path: built whole, with everything inside it.
+    Selected,
+    /// Leads to a selected path: kept as a container for it, without attributes or text
+    /// other than namespace declarations when those are read.
+    Ancestor,
+    /// Neither; dropped with its content.
+    Skipped,
+}
+
+/// An open element; ancestors are only written out once a selected element is found in them.
+struct Open {
+    name: String,
+    role: Role,
+    line: usize,
+    written: bool,
+    /// The namespace declarations to write with the start tag once it is written.
+    declarations: Vec<(String, String)>,
+}
+
+/// Drops the events outside the `only_paths` of a parse, so no Python objects are built for
+/// them. Names are matched as written in the document.
+pub struct Selection {
+    patterns: Vec<PathPattern>,
+    /// Whether `xmlns` attributes are kept on ancestors, for the names of the selected
+    /// elements to be expanded or the declarations collected.
+    declarations: bool,
+    open: Vec<Open>,
+    selected: Vec<(XmlEvent, usize)>,
+}
+
+impl Selection {
+    pub fn new<S: AsRef<str>>(paths: &[S], declarations: bool) -> Self {
+        Self {
+            patterns: paths
+                .iter()
+                .map(|path| PathPattern::new(path.as_ref()))
+                .collect(),
+            declarations,
+            open: Vec::new(),
+            selected: Vec::new(),
+        }
+    }
+
+    /// The events to build from, in document order.
+    pub fn select(mut self, events: Vec<(XmlEvent, usize)>) -> Vec<(XmlEvent, usize)> {
+        self.select_batch(events)
+    }
+
+    /// The events to build from among the next ones read, for a document read in batches.
+    pub fn select_batch(&mut self, events: Vec<(XmlEvent, usize)>) -> Vec<(XmlEvent, usize)> {
+        for (event, line) in events {
+            self.filter(event, line);
+        }
+        std::mem::take(&mut self.selected)
+    }
+
+    fn filter(&mut self, event: XmlEvent, line: usize) {
+        let current = self.open.last().map(|open| open.role);
+        match event {
+            XmlEvent::Start { name, attributes } => {
+                let role = match current {
+                    Some(Role::Selected) => Role::Selected,
+                    Some(Role::Skipped) => Role::Skipped,
+                    Some(Role::Ancestor) | None => self.role_of(&name),
+                };
+                if role == Role::Selected {
+                    self.write_ancestors();
+                }
+                // The root element is always written, so the result keeps its usual shape.
+                let written = role == Role::Selected || current.is_none();
+                let attributes = match role {
+                    Role::Selected => attributes,
+                    Role::Ancestor | Role::Skipped => self.declarations_in(attributes),
+                };
+                let declarations = if written {
+                    let start = XmlEvent::Start {
+                        name: name.clone(),
+                        attributes,
+                    };
+                    self.selected.push((start, line));
+                    Vec::new()
+                } else {
+                    attributes
+                };
+                self.open.push(Open {
+                    name,
+                    role,
+                    line,
+                    written,
+                    declarations,
+                });
+            }
+            XmlEvent::End(name) => {
+                if self.open.pop().is_none_or(|open| open.written) {
+                    self.selected.push((XmlEvent::End(name), line));
+                }
+            }
+            // Outside the root element, the DOCTYPE, comments and processing instructions
+            // are kept as they would be without a selection.
+            XmlEvent::Text(_)
+            | XmlEvent::Whitespace(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::ProcessingInstruction(_)
+                if matches!(current, Some(Role::Selected) | None) =>
+            {
+                self.selected.push((event, line));
+            }
+            XmlEvent::DocType(_) => self.selected.push((event, line)),
+            XmlEvent::Text(_)
+            | XmlEvent::Whitespace(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::ProcessingInstruction(_) => {}
+        }
+    }
+
+    /// Writes the start tags held back for the ancestors of a selected element.
+    fn write_ancestors(&mut self) {
+        for open in self.open.iter_mut().filter(|open| !open.written) {
+            let start = XmlEvent::Start {
+                name: open.name.clone(),
+                attributes: std::mem::take(&mut open.declarations),
+            };
+            self.selected.push((start, open.line));
+            open.written = true;
+        }
+    }
+
+    /// The `xmlns` attributes kept on an element that is not selected.
+    fn declarations_in(&self, attributes: Vec<(String, String)>) -> Vec<(String, String)> {
+        if !self.declarations {
+            return Vec::new();
+        }
+        attributes
+            .into_iter()
+            .filter(|(name, _)| name == "xmlns" || name.starts_with("xmlns:"))
+            .collect()
+    }
+
+    /// The role of a new element under an ancestor, or of the root element.
+    fn role_of(&self, name: &str) -> Role {
+        let mut path: Vec<&str> = self.open.iter().map(|open| open.name.as_str()).collect();
+        path.push(name);
+        if self.patterns.iter().any(|pattern| pattern.matches(&path)) {
+            Role::Selected
+        } else if path.len() == 1 || self.patterns.iter().any(|pattern| pattern.leads_to(&path)) {
+            Role::Ancestor
+        } else {
+            Role::Skipped
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParseConfig;
+    use crate::events::collect_events;
+
+    fn selected(xml: &str, paths: &[&str]) -> Vec<XmlEvent> {
+        let config = ParseConfig::default();
+        let events = collect_events(xml.as_bytes(), &config).unwrap_or_default();
+        Selection::new(paths, false)
+            .select(events)
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect()
+    }
+
+    fn start(name: &str, attributes: &[(&str, &str)]) -> XmlEvent {
+        XmlEvent::Start {
+            name: name.to_owned(),
+            attributes: attributes
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn keeps_selected_subtrees_and_their_ancestors() {
+        let xml = r#"<r v="1">x<a><b k="2">y<c/></b><d/></a><e/></r>"#;
+        assert_eq!(
+            vec![
+                start("r", &[]),
+                start("a", &[]),
+                start("b", &[("k", "2")]),
+                XmlEvent::Text("y".to_owned()),
+                start("c", &[]),
+                XmlEvent::End("c".to_owned()),
+                XmlEvent::End("b".to_owned()),
+                XmlEvent::End("a".to_owned()),
+                XmlEvent::End("r".to_owned()),
+            ],
+            selected(xml, &["r/a/b"])
+        );
+    }
+
+    #[test]
+    fn drops_ancestors_without_selected_elements() {
+        assert_eq!(
+            vec![
+                start("r", &[]),
+                start("b", &[]),
+                start("c", &[]),
+                XmlEvent::End("c".to_owned()),
+                XmlEvent::End("b".to_owned()),
+                XmlEvent::End("r".to_owned()),
+            ],
+            selected("<r><a><x/></a><b><c/></b></r>", &["**/c"])
+        );
+    }
+
+    #[test]
+    fn keeps_namespace_declarations_on_ancestors_when_read() {
+        let xml = r#"<r xmlns="urn:r" v="1"><a xmlns:p="urn:p" w="2"><p:b/></a></r>"#;
+        let config = ParseConfig::default();
+        let events = collect_events(xml.as_bytes(), &config).unwrap_or_default();
+        let events: Vec<XmlEvent> = Selection::new(&["r/a/p:b"], true)
+            .select(events)
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect();
+        assert_eq!(
+            vec![
+                start("r", &[("xmlns", "urn:r")]),
+                start("a", &[("xmlns:p", "urn:p")]),
+                start("p:b", &[]),
+                XmlEvent::End("p:b".to_owned()),
+                XmlEvent::End("a".to_owned()),
+                XmlEvent::End("r".to_owned()),
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn root_is_kept_without_matches() {
+        assert_eq!(
+            vec![start("r", &[]), XmlEvent::End("r".to_owned())],
+            selected("<r><a/></r>", &["x/a"])
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::error::SoapFault;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyMapping, PyString};
+
+/// Envelope namespace of SOAP 1.1.
+pub const SOAP_11: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+/// Envelope namespace of SOAP 1.2.
+pub const SOAP_12: &str = "http://www.w3.org/2003/05/soap-envelope";
+/// Prefix `build_soap()` declares the envelope namespace with.
+pub const PREFIX: &str = "soap";
+
+/// The envelope namespace of a `soap_version`.
+///
+/// # Errors
+///
+/// Raises `ValueError` for versions other than "1.1" and "1.2".
+pub fn envelope_namespace(version: &str) -> PyResult<&'static str> {
+    match version {
+        "1.1" => Ok(SOAP_11),
+        "1.2" => Ok(SOAP_12),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "soap_version must be '1.1' or '1.2', not {version:?}"
+        ))),
+    }
+}
+
+/// Looks up the parts of an envelope parsed with `process_namespaces`, whose names depend on
+/// the `namespaces`, `namespace_separator` and `strip_namespace_prefixes` options. 1.1 fault
+/// fields are unqualified.
+struct Parts {
+    /// What comes before the local name of an envelope element, for either version.
+    qualifiers: Vec<String>,
+    cdata_key: String,
+}
+
+impl Parts {
+    fn new(options: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let option = |name: &str, default: &str| -> PyResult<String> {
+            match options.get_item(name)? {
+                Some(value) if !value.is_none() => value.extract(),
+                Some(_) | None => Ok(default.to_owned()),
+            }
+        };
+        let stripped = match options.get_item("strip_namespace_prefixes")? {
+            Some(value) => value.is_truthy()?,
+            None => false,
+        };
+        let separator = option("namespace_separator", ":")?;
+        let namespaces = options.get_item("namespaces")?;
+        let mut qualifiers = Vec::new();
+        for uri in [SOAP_11, SOAP_12] {
+            let mapped = match &namespaces {
+                Some(namespaces) => entry(namespaces, uri)?,
+                None => None,
+            };
+            qualifiers.push(match mapped {
+                _ if stripped => String::new(),
+                None => format!("{uri}{separator}"),
+                Some(prefix) if prefix.is_none() => String::new(),
+                Some(prefix) => match prefix.extract::<String>()? {
+                    prefix if prefix.is_empty() => prefix,
+                    prefix => format!("{prefix}:"),
+                },
+            });
+        }
+        Ok(Self {
+            qualifiers,
+            cdata_key: option("cdata_key", "#text")?,
+        })
+    }
+
+    /// The entry of a mapping for an envelope element, if any.
+    fn child<'py>(
+        &self,
+        parent: &Bound<'py, PyAny>,
+        local: &str,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        for qualifier in &self.qualifiers {
+            if let Some(value) = entry(parent, &format!("{qualifier}{local}"))? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The text of an element value: a string, the text of an element with attributes, or
+    /// of the first of repeated elements.
+    fn text(&self, value: Option<Bound<'_, PyAny>>) -> PyResult<String> {
+        let Some(value) = value else {
+            return Ok(String::new());
+        };
+        if let Ok(items) = value.downcast::<PyList>() {
+            return self.text(items.iter().next());
+        }
+        if value.is_instance_of::<PyString>() {
+            return Ok(value.str()?.to_string());
+        }
+        self.text(entry(&value, &self.cdata_key)?)
+    }
+}
+
+fn entry<'py>(parent: &Bound<'py, PyAny>, key: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Ok(mapping) = parent.downcast::<PyMapping>() else {
+        return Ok(None);
+    };
+    if mapping.contains(key)? {
+        Ok(Some(mapping.get_item(key)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The content of the Body of an envelope parsed with `options`, or an empty dict for an
+/// empty Body.
+///
+/// # Errors
+///
+/// Raises `ValueError` when there is no SOAP envelope or Body, and `SoapFault` when the
+/// Body holds a Fault.
+pub fn body<'py>(
+    parsed: &Bound<'py, PyAny>,
+    options: &Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = parsed.py();
+    let parts = Parts::new(options)?;
+    let Some(envelope) = parts.child(parsed, "Envelope")? else {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "not a SOAP envelope: the root element must be Envelope in the SOAP 1.1 or 1.2 \
+             namespace",
+        ));
+    };
+    let Some(body) = parts.child(&envelope, "Body")? else {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "SOAP envelope has no Body",
+        ));
+    };
+    if body.is_none() {
+        return Ok(PyDict::new(py).into_any());
+    }
+    if let Some(fault) = parts.child(&body, "Fault")? {
+        return Err(fault_error(py, &parts, &fault)?);
+    }
+    Ok(body)
+}
+
+/// A `SoapFault` for a 1.1 (`faultcode`, `faultstring`, `detail`) or 1.2 (`Code/Value`,
+/// `Reason/Text`, `Detail`) Fault.
+fn fault_error(py: Python, parts: &Parts, fault: &Bound<'_, PyAny>) -> PyResult<PyErr> {
+    let (code, reason, detail) = if let Some(code) = entry(fault, "faultcode")? {
+        (
+            parts.text(Some(code))?,
+            parts.text(entry(fault, "faultstring")?)?,
+            entry(fault, "detail")?,
+        )
+    } else {
+        let code = parts.child(fault, "Code")?;
+        let value = code.map(|code| parts.child(&code, "Value")).transpose()?;
+        let reason = parts.child(fault, "Reason")?;
+        let text = reason
+            .map(|reason| parts.child(&reason, "Text"))
+            .transpose()?;
+        (
+            parts.text(value.flatten())?,
+            parts.text(text.flatten())?,
+            parts.child(fault, "Detail")?,
+        )
+    };
+    let err = SoapFault::new_err(format!("{code}: {reason}"));
+    let value = err.value(py);
+    value.setattr("code", code)?;
+    value.setattr("reason", reason)?;
+    value.setattr("detail", detail)?;
+    Ok(err)
+}
+
+/// The document `build_soap()` writes: an Envelope declaring the namespace of `version`,
+/// holding the Header when there are `headers`, and the Body.
+///
+/// # Errors
+///
+/// Raises `ValueError` for an unknown `version`.
+pub fn envelope<'py>(
+    body: &Bound<'py, PyAny>,
+    headers: Option<&Bound<'py, PyAny>>,
+    version: &str,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let py = body.py();
+    let attr_prefix = match options
+        .map(|options| options.get_item("attr_prefix"))
+        .transpose()?
+    {
+        Some(Some(prefix)) => prefix.extract()?,
+        Some(None) | None => "@".to_owned(),
+    };
+    let envelope = PyDict::new(py);
+    envelope.set_item(
+        format!("{attr_prefix}xmlns:{PREFIX}"),
+        envelope_namespace(version)?,
+    )?;
+    if let Some(headers) = headers {
+        envelope.set_item(format!("{PREFIX}:Header"), headers)?;
+    }
+    envelope.set_item(format!("{PREFIX}:Body"), body)?;
+    let document = PyDict::new(py);
+    document.set_item(format!("{PREFIX}:Envelope"), envelope)?;
+    Ok(document)
+}
@@ -0,0 +1,109 @@
+use crate::path_pattern::PathMap;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyFloat, PyInt, PyString};
+use pyo3::IntoPyObjectExt;
+use std::str::FromStr;
+
+/// A conversion `transforms` applies to an entry before any postprocessor sees it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transform {
+    Int,
+    Float,
+    /// `true`/`false` or `1`/`0`, in any case.
+    Bool,
+    /// An ISO 8601 date and time, as read by `datetime.fromisoformat`.
+    Datetime,
+    Strip,
+    Drop,
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "int" => Ok(Self::Int),
+            "float" => Ok(Self::Float),
+            "bool" => Ok(Self::Bool),
+            "datetime" => Ok(Self::Datetime),
+            "strip" => Ok(Self::Strip),
+            "drop" => Ok(Self::Drop),
+            _ => Err(format!(
+                "transforms values must be 'int', 'float', 'bool', 'datetime', 'strip' or \
+                 'drop', not {s:?}"
+            )),
+        }
+    }
+}
+
+impl Transform {
+    /// Converts a text value, or returns `None` to drop the entry. Values that are not text,
+    /// such as elements with attributes or children, are kept as they are.
+    pub fn apply<'py>(self, value: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if self == Self::Drop {
+            return Ok(None);
+        }
+        let Ok(text) = value.downcast::<PyString>() else {
+            return Ok(Some(value.clone()));
+        };
+        let py = value.py();
+        let text = text.to_str()?.trim();
+        // The Python constructors handle what Rust does not parse, such as `1_000` or
+        // integers wider than 64 bits, and raise their usual errors.
+        let converted = match self {
+            Self::Int => match text.parse::<i64>() {
+                Ok(int) => int.into_bound_py_any(py)?,
+                Err(_) => py.get_type::<PyInt>().call1((text,))?,
+            },
+            Self::Float => match text.parse::<f64>() {
+                Ok(float) => float.into_bound_py_any(py)?,
+                Err(_) => py.get_type::<PyFloat>().call1((text,))?,
+            },
+            Self::Bool => parse_bool(text)?.into_bound_py_any(py)?,
+            Self::Datetime => parse_datetime(py, text)?,
+            Self::Strip => PyString::new(py, text).into_any(),
+            Self::Drop => return Ok(None),
+        };
+        Ok(Some(converted))
+    }
+}
+
+fn parse_bool(text: &str) -> PyResult<bool> {
+    if text == "1" || text.eq_ignore_ascii_case("true") {
+        Ok(true)
+    } else if text == "0" || text.eq_ignore_ascii_case("false") {
+        Ok(false)
+    } else {
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "invalid literal for bool: {text:?}"
+        )))
+    }
+}
+
+pub fn parse_datetime<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyAny>> {
+    // Python 3.10 does not read a `Z` UTC designator.
+    let text = match text.strip_suffix(['Z', 'z']) {
+        Some(local) => format!("{local}+00:00"),
+        None => text.to_owned(),
+    };
+    py.import("datetime")?
+        .getattr("datetime")?
+        .call_method1("fromisoformat", (text,))
+}
+
+/// The `transforms` of a parse, by entry key (`price`, `@id`, `#text`) or by path from the
+/// root element (`catalog/item/@id`, `**/price`).
+pub type Transforms = PathMap<Transform>;
+
+/// Reads the `transforms` dict of conversion names.
+pub fn extract_transforms(transforms: Option<&Bound<'_, PyDict>>) -> PyResult<Transforms> {
+    let mut result = Transforms::default();
+    for (key, transform) in transforms.into_iter().flatten() {
+        let transform = transform
+            .extract::<&str>()?
+            .parse()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        result.insert(key.extract()?, transform);
+    }
+    Ok(result)
+}
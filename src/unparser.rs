@@ -1,92 +1,542 @@
-use crate::config::UnparseConfig;
-use crate::escape::{escape_xml, escape_xml_attr};
+use crate::config::{Charset, InvalidChars, UnparseConfig};
+use crate::encoding::encode_document;
+use crate::error::{parse_error, pyerr_to_io};
+use crate::escape::Markup;
+use crate::xmltodict_core::{
+    check_encodable, escape_text, push_attribute, render_markup, Entries, Error, Node, Tree, Writer,
+};
+use pyo3::exceptions::PyUserWarning;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyString, PyTuple};
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDate, PyDict, PyFloat, PyInt, PyIterator, PyList, PyMapping,
+    PyModule, PyString, PyTuple, PyType,
+};
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
 
+/// Buffered output is handed to a stream once it grows past this many bytes.
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// Values looked at to estimate the size of a document before it is written into a buffer.
+const ESTIMATE_BUDGET: usize = 4096;
+/// Items of a list looked at for the estimate; the rest are taken to be the same size.
+const ESTIMATE_SAMPLE: usize = 16;
+/// The most reserved for a document up front, however large it looks.
+const MAX_RESERVED: usize = 64 * 1024 * 1024;
+
+/// Destination for the serialized document.
+pub enum Sink {
+    /// Accumulate the whole document and return it as a string.
+    Buffer,
+    /// Incrementally `write()` into a Python file-like object.
+    /// Text streams receive `str`, anything else receives bytes in `encoding`.
+    Stream {
+        output: Py<PyAny>,
+        encoding: Option<String>,
+        /// Whether a chunk was written, after which no byte order mark is.
+        started: bool,
+    },
+    /// Hand chunks of at least `chunk_size` bytes to a consumer on another thread.
+    Channel {
+        sender: SyncSender<PyResult<String>>,
+        chunk_size: usize,
+    },
+}
+
+impl Sink {
+    /// Wraps a file-like object the way `xml.sax.saxutils.XMLGenerator` does.
+    pub fn stream(py: Python, output: &Bound<'_, PyAny>, encoding: &str) -> PyResult<Self> {
+        Ok(Self::Stream {
+            output: output.clone().unbind(),
+            encoding: stream_encoding(py, output, encoding)?,
+            started: false,
+        })
+    }
+
+    /// The encoding bytes are written to the stream in, if any.
+    pub fn encoding(&self) -> Option<&str> {
+        match self {
+            Self::Stream { encoding, .. } => encoding.as_deref(),
+            Self::Buffer | Self::Channel { .. } => None,
+        }
+    }
+
+    fn flush_threshold(&self) -> usize {
+        match self {
+            Self::Buffer => usize::MAX,
+            Self::Stream { .. } => FLUSH_THRESHOLD,
+            Self::Channel { chunk_size, .. } => *chunk_size,
+        }
+    }
+}
+
+/// The encoding of what is written to `output`: none for a text stream, which takes `str`.
+fn stream_encoding(
+    py: Python,
+    output: &Bound<'_, PyAny>,
+    encoding: &str,
+) -> PyResult<Option<String>> {
+    let text_io = PyModule::import(py, "io")?.getattr("TextIOBase")?;
+    Ok((!output.is_instance(&text_io)?).then(|| encoding.to_owned()))
+}
+
+/// Text for a Python file-like object, written as [`Sink::stream`] writes it by code running
+/// without the GIL. Writes are buffered and handed over a chunk at a time.
+pub struct StreamWriter {
+    output: Py<PyAny>,
+    encoding: Option<String>,
+    buffer: String,
+    started: bool,
+}
+
+impl StreamWriter {
+    pub fn new(py: Python, output: &Bound<'_, PyAny>, encoding: &str) -> PyResult<Self> {
+        Ok(Self {
+            output: output.clone().unbind(),
+            encoding: stream_encoding(py, output, encoding)?,
+            buffer: String::new(),
+            started: false,
+        })
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), Error> {
+        self.buffer.push_str(text);
+        if self.buffer.len() >= FLUSH_THRESHOLD {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Hands what is buffered to the stream, taking the GIL to do so.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        Python::attach(|py| {
+            let chunk = match &self.encoding {
+                Some(encoding) => encode_output(py, &self.buffer, encoding, !self.started)?,
+                None => PyString::new(py, &self.buffer).into_any(),
+            };
+            self.output.call_method1(py, "write", (chunk,))?;
+            Ok(())
+        })
+        .map_err(|err: PyErr| Error::Io(pyerr_to_io(&err)))?;
+        self.started = true;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Encodes serialized output as [`encode_document`] does, or with Python's codec for
+/// encodings only Python knows; either way unencodable characters become character
+/// references.
+pub fn encode_output<'py>(
+    py: Python<'py>,
+    text: &str,
+    encoding: &str,
+    bom: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    match encode_document(text, encoding, bom) {
+        Some(bytes) => Ok(PyBytes::new(py, &bytes).into_any()),
+        None => PyString::new(py, text).call_method1("encode", (encoding, "xmlcharrefreplace")),
+    }
+}
+
+/// The characters of output encoded in `encoding`, asking Python's codec for encodings only
+/// it knows; `None` for output that stays text.
+pub fn output_charset(py: Python, encoding: Option<&str>) -> PyResult<Charset> {
+    let Some(encoding) = encoding else {
+        return Ok(Charset::Unicode);
+    };
+    if let Some(charset) = Charset::for_label(encoding) {
+        return Ok(charset);
+    }
+    let codec = PyModule::import(py, "codecs")?
+        .call_method1("lookup", (encoding,))?
+        .getattr("encode")?
+        .unbind();
+    Ok(Charset::Custom(Arc::new(move |ch| {
+        Python::attach(|py| codec.call1(py, (ch.to_string(),)).is_ok())
+    })))
+}
+
+/// Roughly how many bytes `value` takes written under a `tag_len`-byte tag, looking at no
+/// more than `budget` values. Values past the budget count as nothing.
+fn estimate_len(value: &Bound<'_, PyAny>, tag_len: usize, budget: &mut usize) -> usize {
+    let Some(left) = budget.checked_sub(1) else {
+        return 0;
+    };
+    *budget = left;
+    // `<tag>` and `</tag>`, and a newline or attribute quotes.
+    let markup = 2 * tag_len + 6;
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        let mut len = markup;
+        for (key, value) in dict {
+            if *budget == 0 {
+                break;
+            }
+            let key_len = key
+                .downcast::<PyString>()
+                .map_or(0, |key| key.to_str().map_or(0, str::len));
+            len = len.saturating_add(estimate_len(&value, key_len, budget));
+        }
+        return len;
+    }
+    if let Ok(list) = value.downcast::<PyList>() {
+        let (mut sampled, mut len) = (0, 0_usize);
+        for item in list.iter().take(ESTIMATE_SAMPLE) {
+            sampled += 1;
+            len = len.saturating_add(estimate_len(&item, tag_len, budget));
+        }
+        return match sampled {
+            0 => 0,
+            sampled => len.saturating_mul(list.len()) / sampled,
+        };
+    }
+    match value.downcast::<PyString>() {
+        Ok(text) => markup + text.to_str().map_or(0, str::len),
+        // Numbers, booleans and the like are short.
+        Err(_) => markup + 8,
+    }
+}
+
+/// The `default` callable of `unparse`, which converts values XML has no natural text for,
+/// as `json.dumps` does.
+pub struct DefaultHook {
+    callback: Py<PyAny>,
+    enum_type: Py<PyAny>,
+    decimal_type: Py<PyAny>,
+}
+
+impl DefaultHook {
+    pub fn new(py: Python, callback: Py<PyAny>) -> PyResult<Self> {
+        Ok(Self {
+            callback,
+            enum_type: PyModule::import(py, "enum")?.getattr("Enum")?.unbind(),
+            decimal_type: PyModule::import(py, "decimal")?
+                .getattr("Decimal")?
+                .unbind(),
+        })
+    }
+
+    /// Whether `value` is passed to the callback: anything but a dict, string, number
+    /// (decimals included), boolean, date, `None` or iterable, and enum members, whose
+    /// `str()` differs between Python versions.
+    fn applies_to(&self, value: &Bound<'_, PyAny>) -> PyResult<bool> {
+        if value.is_instance(self.enum_type.bind(value.py()))? {
+            return Ok(true);
+        }
+        let native = value.is_none()
+            || value.is_instance_of::<PyString>()
+            || value.is_instance_of::<PyBool>()
+            || value.is_instance_of::<PyInt>()
+            || value.is_instance_of::<PyFloat>()
+            || value.is_instance(self.decimal_type.bind(value.py()))?
+            || value.is_instance_of::<PyDate>()
+            || value.is_instance_of::<PyDict>()
+            || repeats(value)
+            || value.downcast::<PyMapping>().is_ok();
+        Ok(!native)
+    }
+}
+
+/// `value` as a dict: a dict itself, or a copy of any other `collections.abc.Mapping`.
+///
+/// # Errors
+///
+/// Raises `TypeError` when `value` is not a mapping.
+pub fn as_dict<'py>(value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyDict>> {
+    if let Ok(dict) = value.downcast::<PyDict>() {
+        return Ok(dict.clone());
+    }
+    let dict = PyDict::new(value.py());
+    dict.update(value.downcast::<PyMapping>()?)?;
+    Ok(dict)
+}
+
+/// The value a model object is written as: what its `__xml__()` returns, the `model_dump()`
+/// of a pydantic model when `pydantic` is set, the fields of a dataclass instance as a dict,
+/// or what its `_asdict()` returns, as for named tuples. Other values come back as they are;
+/// the objects they hold are converted as they are written.
+///
+/// # Errors
+///
+/// Returns the errors raised by `__xml__()`, `model_dump()`, `_asdict()` or reading the
+/// dataclass fields.
+pub fn model_value<'py>(value: &Bound<'py, PyAny>, pydantic: bool) -> PyResult<Bound<'py, PyAny>> {
+    let native = value.is_none()
+        || value.is_instance_of::<PyString>()
+        || value.is_instance_of::<PyDict>()
+        || value.is_instance_of::<PyList>()
+        || value.is_instance_of::<PyInt>()
+        || value.is_instance_of::<PyFloat>();
+    if native {
+        return Ok(value.clone());
+    }
+    let py = value.py();
+    // Looked up on the type, as Python looks up special methods, so classes are not models.
+    let model_type = value.get_type();
+    if model_type.hasattr("__xml__")? {
+        return value.call_method0("__xml__");
+    }
+    if pydantic && is_pydantic_model(&model_type)? {
+        if model_type.hasattr("model_dump")? {
+            return value.call_method0("model_dump");
+        }
+        return value.call_method0("dict");
+    }
+    if model_type.hasattr("__dataclass_fields__")? {
+        let fields = PyDict::new(py);
+        let dataclasses = PyModule::import(py, "dataclasses")?;
+        for field in dataclasses.call_method1("fields", (value,))?.try_iter()? {
+            let name = field?.getattr("name")?;
+            fields.set_item(&name, value.getattr(name.downcast::<PyString>()?)?)?;
+        }
+        return Ok(fields.into_any());
+    }
+    if model_type.hasattr("_asdict")? {
+        return value.call_method0("_asdict");
+    }
+    Ok(value.clone())
+}
+
+/// Whether a type derives from the `BaseModel` of pydantic 2 or of pydantic 1, found in its
+/// MRO so that pydantic is not imported.
+fn is_pydantic_model(model_type: &Bound<'_, PyType>) -> PyResult<bool> {
+    for base in model_type.getattr("__mro__")?.try_iter()? {
+        let base = base?;
+        if base.getattr("__name__")?.extract::<String>()? != "BaseModel" {
+            continue;
+        }
+        let module: String = base.getattr("__module__")?.extract()?;
+        if matches!(module.as_str(), "pydantic.main" | "pydantic.v1.main") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether `value` is written as repeated elements: any iterable but a mapping, which is
+/// written like a dict. Strings and bytes are written as one element, not as a sequence of
+/// characters or integers.
+fn repeats(value: &Bound<'_, PyAny>) -> bool {
+    if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        return true;
+    }
+    !value.is_instance_of::<PyString>()
+        && !value.is_instance_of::<PyBytes>()
+        && !value.is_instance_of::<PyByteArray>()
+        && !value.is_instance_of::<PyDict>()
+        && value.try_iter().is_ok()
+        && value.downcast::<PyMapping>().is_err()
+}
+
+/// Formats `int` and `float` values as `str()` does, without creating a Python string.
+#[derive(Default)]
+pub struct Numbers {
+    ints: itoa::Buffer,
+    floats: ryu::Buffer,
+}
+
+impl Numbers {
+    /// The text of `value`, or `None` for other types, for subclasses, which may override
+    /// `__str__`, for integers beyond `i64` and for floats `str()` does not write in plain
+    /// decimal notation.
+    pub fn format(&mut self, value: &Bound<'_, PyAny>) -> Option<&str> {
+        if value.is_exact_instance_of::<PyInt>() {
+            Some(self.ints.format(value.extract::<i64>().ok()?))
+        } else if let Ok(float) = value.downcast_exact::<PyFloat>() {
+            // Python switches to exponent notation below 1e-4 and from 1e16; ryu's
+            // thresholds differ.
+            let float = float.value();
+            let plain = float.abs() == 0.0 || (1e-4..1e16).contains(&float.abs());
+            plain.then(|| self.floats.format_finite(float))
+        } else {
+            None
+        }
+    }
+}
+
+/// The text of a `bytes` or `bytearray` value, as upstream writes it.
+fn utf8_text(bytes: Vec<u8>) -> PyResult<String> {
+    String::from_utf8(bytes).map_err(|err| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "bytes are written as UTF-8 text: {}",
+            err.utf8_error()
+        ))
+    })
+}
+
+/// The UTF-16 code units of a Python string, lone surrogates included.
+fn utf16_units(text: &Bound<'_, PyString>) -> PyResult<Vec<u16>> {
+    let encoded = text.call_method1("encode", ("utf-16-le", "surrogatepass"))?;
+    Ok(encoded
+        .downcast::<PyBytes>()?
+        .as_bytes()
+        .chunks_exact(2)
+        .filter_map(|pair| pair.try_into().ok().map(u16::from_le_bytes))
+        .collect())
+}
+
+/// Writes the Python objects `unparse()` takes, into a buffer or a [`Sink`].
 pub struct XmlWriter {
     config: UnparseConfig,
-    indent_level: usize,
     output: String,
+    sink: Sink,
     preprocessor: Option<Py<PyAny>>,
+    default: Option<DefaultHook>,
+}
+
+/// Writes buffered output to the stream or channel sink, if any.
+fn flush(py: Python, sink: &mut Sink, output: &mut String) -> PyResult<()> {
+    if output.is_empty() {
+        return Ok(());
+    }
+
+    match sink {
+        Sink::Buffer => return Ok(()),
+        Sink::Stream {
+            output: stream,
+            encoding,
+            started,
+        } => {
+            match encoding {
+                Some(enc) => {
+                    let bytes = encode_output(py, output, enc, !*started)?;
+                    stream.call_method1(py, "write", (bytes,))?;
+                }
+                None => {
+                    stream.call_method1(py, "write", (PyString::new(py, output),))?;
+                }
+            }
+            *started = true;
+        }
+        Sink::Channel { sender, .. } => {
+            let chunk = std::mem::take(output);
+            // Blocks until the consumer takes the previous chunk, so release the GIL meanwhile.
+            py.detach(|| sender.send(Ok(chunk))).map_err(|_err| {
+                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "unparse_iter consumer was closed",
+                )
+            })?;
+        }
+    }
+    output.clear();
+    Ok(())
 }
 
 impl XmlWriter {
-    pub fn new(config: UnparseConfig, preprocessor: Option<Py<PyAny>>) -> Self {
+    pub fn new(
+        config: UnparseConfig,
+        preprocessor: Option<Py<PyAny>>,
+        default: Option<DefaultHook>,
+        sink: Sink,
+    ) -> Self {
         Self {
             config,
-            indent_level: 0,
             output: String::new(),
+            sink,
             preprocessor,
+            default,
         }
     }
 
-    pub fn write_header(&mut self) {
-        if self.config.full_document {
-            self.output.push_str(r#"<?xml version="1.0" encoding=""#);
-            self.output.push_str(&self.config.encoding);
-            self.output.push_str(r#""?>"#);
-            self.output.push_str(&self.config.newl);
-        }
+    /// Reserves room for `capacity` bytes of output up front instead of estimating the size
+    /// of the first document; 0 leaves it to the estimate.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.output.reserve(capacity);
+        self
     }
 
-    fn write_indent(&mut self) {
-        if self.config.pretty {
-            for _ in 0..self.indent_level {
-                self.output.push_str(&self.config.indent);
-            }
-        }
+    /// The writer for the output, and the objects of this writer's options.
+    fn parts<'a, 'py>(&'a mut self, py: Python<'py>) -> (Writer<'a>, Objects<'a, 'py>) {
+        let objects = Objects {
+            py,
+            config: &self.config,
+            preprocessor: self.preprocessor.as_ref(),
+            default: self.default.as_ref(),
+            sink: &mut self.sink,
+        };
+        (Writer::new(&self.config, &mut self.output), objects)
     }
 
-    #[inline]
-    fn apply_preprocessor<'py>(
-        &self,
-        py: Python<'py>,
-        key: &str,
-        data: &Bound<'py, PyAny>,
-    ) -> PyResult<Option<(String, Bound<'py, PyAny>)>> {
-        let mut final_key = key.to_owned();
-        let mut final_value = data.clone();
+    /// Writes the XML declaration, if the configuration asks for one.
+    pub fn write_header(&mut self) {
+        Writer::new(&self.config, &mut self.output).write_header();
+    }
 
-        if let Some(proc) = &self.preprocessor {
-            let result = proc.call1(py, (key, data))?;
+    /// Writes text that is already markup, such as a document another writer built.
+    pub fn write_raw(&mut self, py: Python, markup: &str) -> PyResult<()> {
+        self.output.push_str(markup);
+        let (writer, mut objects) = self.parts(py);
+        objects.written(writer.output)
+    }
 
-            if result.is_none(py) {
-                return Ok(None);
-            }
+    pub fn config(&self) -> &UnparseConfig {
+        &self.config
+    }
 
-            let tuple = result.bind(py).downcast::<PyTuple>()?;
-            final_key = tuple.get_item(0)?.extract::<String>()?;
-            final_value = tuple.get_item(1)?;
+    /// Writes `input_dict` as a document of its own and returns it, leaving the writer ready
+    /// for the next one. The document stays in the buffer, which keeps its capacity, until
+    /// the next one is written.
+    pub fn buffer_document(
+        &mut self,
+        py: Python,
+        input_dict: &Bound<'_, PyDict>,
+    ) -> PyResult<&str> {
+        self.output.clear();
+        self.write_document(py, input_dict)?;
+        Ok(&self.output)
+    }
+
+    /// Writes the XML and DOCTYPE declarations followed by every root element of `input_dict`.
+    pub fn write_document(&mut self, py: Python, input_dict: &Bound<'_, PyDict>) -> PyResult<()> {
+        // A buffer that has never held a document is sized to the estimate once, rather than
+        // doubled over and over as a large document is written into it.
+        if matches!(self.sink, Sink::Buffer) && self.output.capacity() == 0 {
+            let mut budget = ESTIMATE_BUDGET;
+            let estimate = estimate_len(input_dict.as_any(), 0, &mut budget);
+            self.output.reserve(estimate.min(MAX_RESERVED));
         }
+        let entries = dict_entries(input_dict)?;
+        let (mut writer, mut objects) = self.parts(py);
+        writer.write_document(&mut objects, entries)
+    }
 
-        Ok(Some((final_key, final_value)))
+    /// Escapes the text content of `tag`, or wraps it in a CDATA section if configured to.
+    pub fn escape_text<'a>(&self, tag: &str, text: &'a str) -> PyResult<Cow<'a, str>> {
+        escape_text(&self.config, tag, text)
+            .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))
     }
 
-    #[inline]
-    fn push_short_empty_tag(output: &mut String, tag: &str) {
-        output.push('<');
-        output.push_str(tag);
-        output.push_str("/>");
+    /// Refuses a name or DOCTYPE holding a character the encoding of the output does not.
+    pub fn check_encodable(&self, kind: &str, text: &str) -> PyResult<()> {
+        check_encodable(&self.config, kind, text)
+            .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))
     }
 
-    #[inline]
-    fn push_full_empty_tag(output: &mut String, tag: &str) {
-        output.push('<');
-        output.push_str(tag);
-        output.push_str("></");
-        output.push_str(tag);
-        output.push('>');
+    /// Renders a comment or processing instruction, refusing text it cannot hold.
+    pub fn render_markup(&self, markup: Markup, text: &str) -> PyResult<String> {
+        render_markup(&self.config, markup, text)
+            .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))
+    }
+
+    /// Appends ` name="value"`, escaping the value as the configuration says.
+    pub fn push_attribute(
+        output: &mut String,
+        config: &UnparseConfig,
+        name: &str,
+        value: &str,
+    ) -> PyResult<()> {
+        push_attribute(output, config, name, value)
+            .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))
     }
 
-    #[inline]
-    fn push_simple_tag(output: &mut String, tag: &str, value: &str) {
-        output.push('<');
-        output.push_str(tag);
-        output.push('>');
-        output.push_str(value);
-        output.push_str("</");
-        output.push_str(tag);
-        output.push('>');
+    /// Text of an attribute or `cdata_key` value, after `default`.
+    pub fn text_value(&self, value: &Bound<'_, PyAny>) -> PyResult<String> {
+        text_value(&self.config, self.default.as_ref(), value)
     }
 
     pub fn write_element(
@@ -96,157 +546,230 @@ impl XmlWriter {
         value: &Bound<'_, PyAny>,
         needs_newline: bool,
     ) -> PyResult<()> {
-        let Some((final_tag, final_value)) = self.apply_preprocessor(py, tag, value)? else {
-            return Ok(());
-        };
-
-        if self.config.pretty && needs_newline {
-            self.output.push_str(&self.config.newl);
-            self.write_indent();
-        }
+        let (mut writer, mut objects) = self.parts(py);
+        writer.write_element(&mut objects, tag, value.clone(), needs_newline)
+    }
 
-        // Check if value is None (empty element)
-        if final_value.is_none() {
-            if self.config.short_empty_elements {
-                XmlWriter::push_short_empty_tag(&mut self.output, final_tag.as_str());
-            } else {
-                XmlWriter::push_full_empty_tag(&mut self.output, final_tag.as_str());
+    /// Completes the document: returns the text for a buffer sink, or flushes any other sink.
+    pub fn finish(mut self, py: Python) -> PyResult<Option<String>> {
+        match self.sink {
+            Sink::Buffer => Ok(Some(self.output)),
+            Sink::Stream { .. } | Sink::Channel { .. } => {
+                flush(py, &mut self.sink, &mut self.output)?;
+                Ok(None)
             }
-            return Ok(());
         }
+    }
+}
 
-        // Check if value is a dict (element with attributes/children)
-        if let Ok(str) = final_value.downcast::<PyString>() {
-            if str.len()? == 0 {
-                if self.config.short_empty_elements {
-                    XmlWriter::push_short_empty_tag(&mut self.output, final_tag.as_str());
-                } else {
-                    XmlWriter::push_full_empty_tag(&mut self.output, final_tag.as_str());
-                }
-            } else {
-                let val = final_value.str()?.to_string();
-                XmlWriter::push_simple_tag(
-                    &mut self.output,
-                    final_tag.as_str(),
-                    escape_xml(&val).as_ref(),
-                );
-            }
+/// The entries of a dict, by the `str()` of their keys.
+fn dict_entries<'py>(dict: &Bound<'py, PyDict>) -> PyResult<Vec<(String, Bound<'py, PyAny>)>> {
+    dict.iter()
+        .map(|(key, value)| Ok((key.str()?.to_string(), value)))
+        .collect()
+}
 
-            return Ok(());
+/// The value `default` converts `value` to, or `value` itself when there is no `default`
+/// or XML has text for its type.
+fn apply_default<'py>(
+    default: Option<&DefaultHook>,
+    value: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    match default {
+        Some(default) if default.applies_to(value)? => {
+            default.callback.bind(value.py()).call1((value,))
         }
+        Some(_) | None => Ok(value.clone()),
+    }
+}
 
-        if let Ok(dict) = final_value.downcast::<PyDict>() {
-            self.write_dict_element(py, final_tag.as_str(), dict)?;
-        } else if let Ok(iter) = final_value.try_iter() {
-            for (i, item) in iter.enumerate() {
-                self.write_element(py, final_tag.as_str(), &item?, i > 0 || needs_newline)?;
+/// Text of an attribute or `cdata_key` value, after `default`.
+fn text_value(
+    config: &UnparseConfig,
+    default: Option<&DefaultHook>,
+    value: &Bound<'_, PyAny>,
+) -> PyResult<String> {
+    scalar_text(config, &apply_default(default, value)?)
+}
+
+/// Text of a value that is not a container: booleans are written in lowercase, dates and
+/// datetimes in `datetime_format`, bytes decoded from UTF-8, anything else as `str()` gives
+/// it.
+fn scalar_text(config: &UnparseConfig, value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(bool_val) = value.extract::<bool>() {
+        Ok(if bool_val { "true" } else { "false" }.to_owned())
+    } else if let Ok(py_str) = value.downcast::<PyString>() {
+        str_text(config, py_str)
+    } else if let Some(number) = Numbers::default().format(value) {
+        Ok(number.to_owned())
+    } else if let Ok(bytes) = value.downcast::<PyBytes>() {
+        utf8_text(bytes.as_bytes().to_vec())
+    } else if let Ok(bytes) = value.downcast::<PyByteArray>() {
+        utf8_text(bytes.to_vec())
+    } else if value.is_instance_of::<PyDate>() {
+        let text = match config.datetime_format.as_deref() {
+            None => value.str()?.into_any(),
+            Some("iso") => value.call_method0("isoformat")?,
+            Some(format) => value.call_method1("strftime", (format,))?,
+        };
+        text.extract()
+    } else {
+        str_text(config, &value.str()?)
+    }
+}
+
+/// The text of a Python string, whose lone surrogates UTF-8 cannot hold: like the other
+/// characters XML does not allow, they are handled as `invalid_chars` says.
+fn str_text(config: &UnparseConfig, text: &Bound<'_, PyString>) -> PyResult<String> {
+    if let Ok(text) = text.to_str() {
+        return Ok(text.to_owned());
+    }
+    let units = utf16_units(text)?;
+    let mut result = String::with_capacity(units.len());
+    for ch in char::decode_utf16(units) {
+        match (ch, config.invalid_chars) {
+            (Ok(ch), _) => result.push(ch),
+            (Err(err), InvalidChars::Raise) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid XML character U+{:04X}",
+                    err.unpaired_surrogate()
+                )));
             }
-        } else if let Ok(bool_val) = final_value.extract::<bool>() {
-            let bool_text = if bool_val { "true" } else { "false" };
-            XmlWriter::push_simple_tag(&mut self.output, final_tag.as_str(), bool_text);
-        } else {
-            let val = final_value.str()?.to_string();
-            XmlWriter::push_simple_tag(
-                &mut self.output,
-                final_tag.as_str(),
-                escape_xml(&val).as_ref(),
-            );
+            (Err(_), InvalidChars::Strip) => {}
+            (Err(_), InvalidChars::Replace) => result.push(char::REPLACEMENT_CHARACTER),
         }
-
-        Ok(())
     }
+    Ok(result)
+}
+
+/// Python objects for a [`Writer`], with the `unparse()` options that call into Python.
+struct Objects<'a, 'py> {
+    py: Python<'py>,
+    config: &'a UnparseConfig,
+    preprocessor: Option<&'a Py<PyAny>>,
+    default: Option<&'a DefaultHook>,
+    sink: &'a mut Sink,
+}
+
+impl<'py> Tree for Objects<'_, 'py> {
+    type Value = Bound<'py, PyAny>;
+    type Items = Bound<'py, PyIterator>;
+    type Error = PyErr;
 
-    fn write_dict_element(
+    fn preprocess(
         &mut self,
-        py: Python,
         tag: &str,
-        dict: &Bound<'_, PyDict>,
-    ) -> PyResult<()> {
-        let mut attributes = Vec::new();
-        let mut text_content = None;
-        let mut child_elements = Vec::new();
+        value: Bound<'py, PyAny>,
+    ) -> PyResult<Option<(String, Bound<'py, PyAny>)>> {
+        let Some(preprocessor) = self.preprocessor else {
+            return Ok(Some((tag.to_owned(), value)));
+        };
+        let result = preprocessor.bind(self.py).call1((tag, value))?;
+        if result.is_none() {
+            return Ok(None);
+        }
+        let tuple = result.downcast::<PyTuple>()?;
+        Ok(Some((tuple.get_item(0)?.extract()?, tuple.get_item(1)?)))
+    }
 
-        for (key, value) in dict {
-            let key_str = key.str()?.to_string();
-
-            if let Some(attr_name) = key_str.strip_prefix(self.config.attr_prefix.as_ref()) {
-                let attr_value = if let Ok(bool_val) = value.extract::<bool>() {
-                    if bool_val {
-                        "true".to_owned()
-                    } else {
-                        "false".to_owned()
-                    }
-                } else if let Ok(py_str) = value.downcast::<PyString>() {
-                    py_str.to_str()?.to_owned()
-                } else {
-                    value.str()?.to_string()
-                };
-                attributes.push((attr_name.to_owned(), attr_value));
-            } else if key_str == self.config.cdata_key {
-                let text = if let Ok(bool_val) = value.extract::<bool>() {
-                    if bool_val {
-                        "true".to_owned()
-                    } else {
-                        "false".to_owned()
-                    }
-                } else if let Ok(py_str) = value.downcast::<PyString>() {
-                    py_str.to_str()?.to_owned()
-                } else {
-                    value.str()?.to_string()
-                };
-                text_content = Some(text);
-            } else {
-                child_elements.push((key_str, value));
+    /// Models become what they stand for, then `default` converts what XML has no text for.
+    fn convert(&mut self, value: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        apply_default(
+            self.default,
+            &model_value(&value, self.config.pydantic_support)?,
+        )
+    }
+
+    fn node(
+        &mut self,
+        value: Bound<'py, PyAny>,
+    ) -> PyResult<Node<Bound<'py, PyAny>, Bound<'py, PyIterator>>> {
+        if value.is_none() {
+            return Ok(Node::Empty);
+        }
+        // Subclasses, such as string enums, are written as `str()` gives them.
+        if let Ok(text) = value.downcast::<PyString>() {
+            if text.len()? == 0 {
+                return Ok(Node::Empty);
             }
+            return Ok(Node::Text(str_text(self.config, &value.str()?)?));
         }
+        if let Some(entries) = self.entries(&value)? {
+            return Ok(Node::Map(entries));
+        }
+        if let Some(items) = self.items(&value)? {
+            return Ok(Node::List(items));
+        }
+        Ok(Node::Text(scalar_text(self.config, &value)?))
+    }
 
-        self.output.push('<');
-        self.output.push_str(tag);
-        for (attr_name, attr_value) in attributes {
-            self.output.push(' ');
-            self.output.push_str(&attr_name);
-            self.output.push_str("=\"");
-            self.output.push_str(escape_xml_attr(&attr_value).as_ref());
-            self.output.push('"');
+    fn items(&mut self, value: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyIterator>>> {
+        if repeats(value) {
+            return value.try_iter().map(Some);
         }
+        Ok(None)
+    }
 
-        if child_elements.is_empty() && text_content.is_none() {
-            if self.config.short_empty_elements {
-                self.output.push_str("/>");
-            } else {
-                self.output.push_str("></");
-                self.output.push_str(tag);
-                self.output.push('>');
-            }
-        } else {
-            self.output.push('>');
+    fn entries(
+        &mut self,
+        value: &Bound<'py, PyAny>,
+    ) -> PyResult<Option<Vec<(String, Bound<'py, PyAny>)>>> {
+        if let Ok(dict) = value.downcast::<PyDict>() {
+            return dict_entries(dict).map(Some);
+        }
+        if value.downcast::<PyMapping>().is_ok() {
+            return dict_entries(&as_dict(value)?).map(Some);
+        }
+        Ok(None)
+    }
 
-            if let Some(text) = text_content {
-                self.output.push_str(&escape_xml(&text));
-            }
+    fn attribute_map(
+        &mut self,
+        _key: &str,
+        value: &Bound<'py, PyAny>,
+    ) -> PyResult<Entries<Bound<'py, PyAny>>> {
+        dict_entries(&as_dict(value)?)
+    }
 
-            if !child_elements.is_empty() {
-                self.indent_level += 1;
-                for (i, (child_tag, child_value)) in child_elements.into_iter().enumerate() {
-                    self.write_element(py, &child_tag, &child_value, i > 0 || self.config.pretty)?;
-                }
-                self.indent_level -= 1;
+    fn is_none(&self, value: &Bound<'py, PyAny>) -> bool {
+        value.is_none()
+    }
 
-                if self.config.pretty {
-                    self.output.push_str(&self.config.newl);
-                    self.write_indent();
-                }
-            }
+    fn text(&mut self, _key: &str, value: &Bound<'py, PyAny>) -> PyResult<String> {
+        text_value(self.config, self.default, value)
+    }
+
+    fn is_mixed(&self, value: &Bound<'py, PyAny>) -> bool {
+        let Ok(nodes) = value.downcast::<PyList>() else {
+            return false;
+        };
+        nodes.iter().any(|node| node.is_instance_of::<PyString>())
+            && nodes.iter().any(|node| node.is_instance_of::<PyDict>())
+            && !nodes.iter().any(|node| node.is_instance_of::<PyList>())
+    }
 
-            self.output.push_str("</");
-            self.output.push_str(tag);
-            self.output.push('>');
+    fn expand(&mut self, key: &str, item: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        if !repeats(&item) {
+            return Ok(item);
         }
+        let dict = PyDict::new(self.py);
+        dict.set_item(key, item)?;
+        Ok(dict.into_any())
+    }
 
-        Ok(())
+    fn error(&self, err: Error) -> PyErr {
+        parse_error(self.py, err)
+    }
+
+    fn warn(&mut self, message: &str) -> PyResult<()> {
+        let message = CString::new(message)?;
+        PyErr::warn(self.py, &self.py.get_type::<PyUserWarning>(), &message, 1)
     }
 
-    pub fn finish(self) -> String {
-        self.output
+    fn written(&mut self, output: &mut String) -> PyResult<()> {
+        if output.len() >= self.sink.flush_threshold() {
+            flush(self.py, self.sink, output)?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,184 @@
+use crate::config::ParseConfig;
+use crate::events::XmlEvent;
+use quick_xml::events::BytesStart;
+use std::collections::VecDeque;
+
+/// Whitespace as XML defines it, which is narrower than Unicode's.
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+pub fn is_blank(text: &str) -> bool {
+    text.chars().all(is_xml_whitespace)
+}
+
+/// Whether a start tag's `xml:space` asks to preserve whitespace (`Some(true)`), to go back
+/// to the configured stripping (`Some(false)`), or leaves it as its parent has it.
+pub fn xml_space(start: &BytesStart) -> Option<bool> {
+    // Most tags have no `xml:space`, which a scan finds sooner than parsing the attributes.
+    memchr::memmem::find(start.attributes_raw(), b"xml:space")?;
+    let attr = start.try_get_attribute("xml:space").ok().flatten()?;
+    match &*attr.value {
+        b"preserve" => Some(true),
+        b"default" => Some(false),
+        _ => None,
+    }
+}
+
+/// Stripping state of an open element.
+#[derive(Clone, Copy)]
+struct Scope {
+    /// Inside `xml:space="preserve"`, with `honor_xml_space`.
+    preserve: bool,
+    /// Nothing of the element's text has been kept yet. With `preserve_mixed_content`, the
+    /// start also ends at its first child, as whitespace between inline elements counts.
+    at_start: bool,
+    /// Text has been kept since the last child, so blank text continues it rather than
+    /// standing between children.
+    after_text: bool,
+    /// Where the element's whitespace starts in [`TextStripper::held`].
+    held_from: usize,
+}
+
+/// Strips the text of a document's events as [`ParseConfig::strip`] says. The text of an
+/// element is the concatenation of its text nodes, stripped at both ends as xmltodict does
+/// once the element ends. Text is handed on as soon as it is read, but whitespace that may
+/// end the element's text is held back until more text or the end of the element shows
+/// which it is.
+#[derive(Default)]
+pub struct TextStripper {
+    scopes: Vec<Scope>,
+    /// Whitespace only part of an open element's text if more text follows, innermost
+    /// element last: the end of its last text node as [`XmlEvent::Whitespace`], then blank
+    /// text nodes.
+    held: Vec<(XmlEvent, usize)>,
+    /// Events to hand on before the one just taken, with their lines.
+    ready: VecDeque<(XmlEvent, usize)>,
+}
+
+impl TextStripper {
+    /// The next stripped event, with its line, once one is ready.
+    pub fn next_ready(&mut self) -> Option<(XmlEvent, usize)> {
+        self.ready.pop_front()
+    }
+
+    /// Takes the next event read; `xml_space` is what [`xml_space`] found on a start tag.
+    /// The event is handed back unless it is held, or other events are ready before it.
+    pub fn push(
+        &mut self,
+        config: &ParseConfig,
+        event: XmlEvent,
+        line: usize,
+        xml_space: Option<bool>,
+    ) -> Option<XmlEvent> {
+        match &event {
+            XmlEvent::Text(_) => {
+                let XmlEvent::Text(text) = event else {
+                    return None;
+                };
+                return self.text(config, text, line);
+            }
+            XmlEvent::Start { .. } => {
+                let inherited = self.scopes.last().is_some_and(|scope| scope.preserve);
+                self.end_mixed_text(config);
+                self.scopes.push(Scope {
+                    preserve: xml_space.unwrap_or(inherited),
+                    at_start: true,
+                    after_text: false,
+                    held_from: self.held.len(),
+                });
+            }
+            XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction(_) => {
+                self.end_mixed_text(config);
+            }
+            // Whitespace still held ends the element's text.
+            XmlEvent::End(_) => {
+                if let Some(scope) = self.scopes.pop() {
+                    self.held.truncate(scope.held_from);
+                }
+            }
+            XmlEvent::Whitespace(_) | XmlEvent::DocType(_) => {}
+        }
+        self.hand_on(event, line)
+    }
+
+    /// Whether whitespace starting text read now would be dropped, as the indentation of
+    /// most documents is, so the reader can skip it.
+    pub fn drops_blank(&self, config: &ParseConfig) -> bool {
+        self.scopes
+            .last()
+            .is_none_or(|scope| !scope.preserve && scope.at_start && config.strip.leading())
+    }
+
+    /// Whitespace before a node of mixed content is kept, ahead of the node.
+    fn end_mixed_text(&mut self, config: &ParseConfig) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if !config.preserve_mixed_content || scope.preserve {
+            return;
+        }
+        scope.at_start = false;
+        let after_text = std::mem::take(&mut scope.after_text);
+        let held = self.held.drain(scope.held_from..);
+        self.ready.extend(held.map(|(event, line)| match event {
+            XmlEvent::Text(text) if !after_text => (XmlEvent::Whitespace(text), line),
+            other @ (XmlEvent::Start { .. }
+            | XmlEvent::End(_)
+            | XmlEvent::Text(_)
+            | XmlEvent::Whitespace(_)
+            | XmlEvent::DocType(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::ProcessingInstruction(_)) => (other, line),
+        }));
+    }
+
+    fn text(&mut self, config: &ParseConfig, mut text: String, line: usize) -> Option<XmlEvent> {
+        let Some(scope) = self.scopes.last_mut() else {
+            // Outside the root element there is only markup and blank text.
+            return None;
+        };
+        if scope.preserve {
+            return self.hand_on(XmlEvent::Text(text), line);
+        }
+        let strip = config.strip;
+        if is_blank(&text) {
+            if scope.at_start && strip.leading() {
+                return None;
+            }
+            if strip.trailing() {
+                self.held.push((XmlEvent::Text(text), line));
+                return None;
+            }
+            let event = if config.preserve_mixed_content && !scope.after_text {
+                XmlEvent::Whitespace(text)
+            } else {
+                XmlEvent::Text(text)
+            };
+            return self.hand_on(event, line);
+        }
+        if scope.at_start && strip.leading() {
+            text.drain(..text.len() - text.trim_start_matches(is_xml_whitespace).len());
+        }
+        scope.at_start = false;
+        scope.after_text = true;
+        self.ready.extend(self.held.drain(scope.held_from..));
+        if strip.trailing() {
+            let end = text.trim_end_matches(is_xml_whitespace).len();
+            if end < text.len() {
+                let tail = text.split_off(end);
+                self.held.push((XmlEvent::Whitespace(tail), line));
+            }
+        }
+        self.hand_on(XmlEvent::Text(text), line)
+    }
+
+    /// Hands an event back, or queues it after the events already ready.
+    fn hand_on(&mut self, event: XmlEvent, line: usize) -> Option<XmlEvent> {
+        if self.ready.is_empty() {
+            return Some(event);
+        }
+        self.ready.push_back((event, line));
+        None
+    }
+}
@@ -0,0 +1,370 @@
+use crate::config::InvalidChars;
+use crate::escape::escape_xml;
+use crate::events::XmlEvent;
+use crate::unparser::Numbers;
+use pyo3::exceptions::{PyOverflowError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDateAccess, PyDateTime, PyDict, PyFloat, PyInt, PyList,
+    PyMapping, PyString, PyTimeAccess, PyTuple,
+};
+
+pyo3::import_exception!(xmlrpc.client, Fault);
+
+const HEADER: &str = "<?xml version='1.0'?>\n";
+
+/// Writes XML-RPC calls and responses as `xmlrpc.client.dumps()` does.
+#[derive(Default)]
+pub struct Marshaller {
+    output: String,
+    numbers: Numbers,
+    allow_none: bool,
+    /// Addresses of the lists and dicts being written, to refuse recursive ones.
+    open: Vec<usize>,
+}
+
+impl Marshaller {
+    pub fn new(allow_none: bool) -> Self {
+        Self {
+            allow_none,
+            ..Self::default()
+        }
+    }
+
+    /// A `<methodCall>` for a `methodname`, a `<methodResponse>`, or bare `<params>`. A
+    /// `Fault` is written as a response.
+    ///
+    /// # Errors
+    ///
+    /// Raises `TypeError` for values XML-RPC has no type for, `OverflowError` for integers
+    /// outside 32 bits, and `ValueError` for a response without exactly one parameter.
+    pub fn dumps(
+        mut self,
+        params: &Bound<'_, PyAny>,
+        methodname: Option<&str>,
+        methodresponse: bool,
+    ) -> PyResult<String> {
+        let py = params.py();
+        let is_fault = params.is_instance(&py.get_type::<Fault>())?;
+        let methodresponse = methodresponse || is_fault;
+        if let Some(name) = methodname {
+            self.output.push_str(HEADER);
+            self.output.push_str("<methodCall>\n<methodName>");
+            self.output.push_str(&escape(name)?);
+            self.output.push_str("</methodName>\n");
+        } else if methodresponse {
+            self.output.push_str(HEADER);
+            self.output.push_str("<methodResponse>\n");
+        }
+        if is_fault {
+            let fault = PyDict::new(py);
+            fault.set_item("faultCode", params.getattr("faultCode")?)?;
+            fault.set_item("faultString", params.getattr("faultString")?)?;
+            self.output.push_str("<fault>\n");
+            self.value(fault.as_any())?;
+            self.output.push_str("</fault>\n");
+        } else {
+            let params = params.downcast::<PyTuple>().map_err(|_err| {
+                PyTypeError::new_err("params must be a tuple or an xmlrpc.client.Fault")
+            })?;
+            if methodresponse && params.len() != 1 {
+                return Err(PyValueError::new_err(
+                    "a method response must hold exactly one parameter",
+                ));
+            }
+            self.output.push_str("<params>\n");
+            for param in params {
+                self.output.push_str("<param>\n");
+                self.value(&param)?;
+                self.output.push_str("</param>\n");
+            }
+            self.output.push_str("</params>\n");
+        }
+        if methodname.is_some() {
+            self.output.push_str("</methodCall>\n");
+        } else if methodresponse {
+            self.output.push_str("</methodResponse>\n");
+        }
+        Ok(self.output)
+    }
+
+    fn value(&mut self, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        if value.is_none() {
+            if !self.allow_none {
+                return Err(PyTypeError::new_err(
+                    "cannot marshal None unless allow_none is enabled",
+                ));
+            }
+            // Unlike the other values, xmlrpc.client ends nil without a newline.
+            self.output.push_str("<value><nil/></value>");
+            return Ok(());
+        }
+        if let Ok(flag) = value.downcast::<PyBool>() {
+            self.scalar("boolean", if flag.is_true() { "1" } else { "0" });
+        } else if value.is_instance_of::<PyInt>() {
+            let int = value
+                .extract::<i32>()
+                .map_err(|_err| PyOverflowError::new_err("int exceeds XML-RPC limits"))?;
+            self.scalar("int", &int.to_string());
+        } else if value.is_instance_of::<PyFloat>() {
+            let text = match self.numbers.format(value) {
+                Some(text) => text.to_owned(),
+                None => value.repr()?.to_string(),
+            };
+            self.scalar("double", &text);
+        } else if let Ok(text) = value.downcast::<PyString>() {
+            let text = escape(&text.to_cow()?)?.into_owned();
+            self.scalar("string", &text);
+        } else if value.is_instance_of::<PyBytes>() || value.is_instance_of::<PyByteArray>() {
+            self.base64(value)?;
+        } else if let Ok(datetime) = value.downcast::<PyDateTime>() {
+            let text = format!(
+                "{:04}{:02}{:02}T{:02}:{:02}:{:02}",
+                datetime.get_year(),
+                datetime.get_month(),
+                datetime.get_day(),
+                datetime.get_hour(),
+                datetime.get_minute(),
+                datetime.get_second()
+            );
+            self.scalar("dateTime.iso8601", &text);
+        } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            self.enter(value, "lists")?;
+            self.output.push_str("<value><array><data>\n");
+            for item in value.try_iter()? {
+                self.value(&item?)?;
+            }
+            self.output.push_str("</data></array></value>\n");
+            self.open.pop();
+        } else if let Ok(mapping) = value.downcast::<PyMapping>() {
+            self.enter(value, "dictionaries")?;
+            self.output.push_str("<value><struct>\n");
+            for item in mapping.items()? {
+                let (key, member) = item.extract::<(Bound<'_, PyAny>, Bound<'_, PyAny>)>()?;
+                let key = key
+                    .downcast::<PyString>()
+                    .map_err(|_err| PyTypeError::new_err("dictionary key must be string"))?;
+                self.output.push_str("<member>\n<name>");
+                self.output.push_str(&escape(&key.to_cow()?)?);
+                self.output.push_str("</name>\n");
+                self.value(&member)?;
+                self.output.push_str("</member>\n");
+            }
+            self.output.push_str("</struct></value>\n");
+            self.open.pop();
+        } else if is_xmlrpc_type(value, "DateTime")? {
+            let text = escape(&value.getattr("value")?.str()?.to_cow()?)?.into_owned();
+            self.scalar("dateTime.iso8601", &text);
+        } else if is_xmlrpc_type(value, "Binary")? {
+            self.base64(&value.getattr("data")?)?;
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "cannot marshal {} objects",
+                value.get_type().repr()?
+            )));
+        }
+        Ok(())
+    }
+
+    fn scalar(&mut self, kind: &str, text: &str) {
+        self.output.push_str("<value><");
+        self.output.push_str(kind);
+        self.output.push('>');
+        self.output.push_str(text);
+        self.output.push_str("</");
+        self.output.push_str(kind);
+        self.output.push_str("></value>\n");
+    }
+
+    fn base64(&mut self, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = data.py();
+        let encoded = py.import("base64")?.call_method1("encodebytes", (data,))?;
+        self.output.push_str("<value><base64>\n");
+        self.output.push_str(&String::from_utf8_lossy(
+            encoded.downcast::<PyBytes>()?.as_bytes(),
+        ));
+        self.output.push_str("</base64></value>\n");
+        Ok(())
+    }
+
+    fn enter(&mut self, container: &Bound<'_, PyAny>, kind: &str) -> PyResult<()> {
+        let address = container.as_ptr() as usize;
+        if self.open.contains(&address) {
+            return Err(PyTypeError::new_err(format!(
+                "cannot marshal recursive {kind}"
+            )));
+        }
+        self.open.push(address);
+        Ok(())
+    }
+}
+
+fn escape(text: &str) -> PyResult<std::borrow::Cow<'_, str>> {
+    escape_xml(text, InvalidChars::Raise).map_err(PyValueError::new_err)
+}
+
+fn is_xmlrpc_type(value: &Bound<'_, PyAny>, name: &str) -> PyResult<bool> {
+    let client = value.py().import("xmlrpc.client")?;
+    value.is_instance(&client.getattr(name)?)
+}
+
+/// Reads an XML-RPC call or response as `xmlrpc.client.loads(use_builtin_types=True)`
+/// does, returning the parameters and the method name of a call.
+///
+/// # Errors
+///
+/// Raises `xmlrpc.client.Fault` for a fault response, and `ValueError` for unknown tags
+/// and values that do not convert.
+pub fn loads<'py>(
+    py: Python<'py>,
+    events: Vec<(XmlEvent, usize)>,
+) -> PyResult<(Bound<'py, PyTuple>, Option<String>)> {
+    let mut values: Vec<Bound<'py, PyAny>> = Vec::new();
+    // Where each open array or struct starts in `values`.
+    let mut marks: Vec<usize> = Vec::new();
+    let mut data = String::new();
+    // Whether the innermost `<value>` has no type element, making it a string.
+    let mut untyped = false;
+    let mut methodname = None;
+    let mut fault = false;
+
+    for (event, line) in events {
+        match event {
+            XmlEvent::Start { name, .. } => {
+                if untyped && !is_type(&name) {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown XML-RPC tag {name:?} (line {line})"
+                    )));
+                }
+                if name == "array" || name == "struct" {
+                    marks.push(values.len());
+                }
+                data.clear();
+                untyped = name == "value";
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => data.push_str(&text),
+            XmlEvent::End(name) => {
+                let value = match name.as_str() {
+                    "value" if untyped => Some(PyString::new(py, &data).into_any()),
+                    "string" | "name" => Some(PyString::new(py, &data).into_any()),
+                    "boolean" => match data.as_str() {
+                        "0" => Some(PyBool::new(py, false).to_owned().into_any()),
+                        "1" => Some(PyBool::new(py, true).to_owned().into_any()),
+                        _ => {
+                            return Err(PyValueError::new_err(format!(
+                                "bad boolean value {data:?} (line {line})"
+                            )))
+                        }
+                    },
+                    "int" | "i1" | "i2" | "i4" | "i8" | "biginteger" => {
+                        Some(py.get_type::<PyInt>().call1((data.as_str(),))?)
+                    }
+                    "double" | "float" => Some(py.get_type::<PyFloat>().call1((data.as_str(),))?),
+                    "bigdecimal" => Some(
+                        py.import("decimal")?
+                            .getattr("Decimal")?
+                            .call1((data.as_str(),))?,
+                    ),
+                    "nil" => Some(py.None().into_bound(py)),
+                    "base64" => Some(
+                        py.import("base64")?
+                            .call_method1("decodebytes", (PyBytes::new(py, data.as_bytes()),))?,
+                    ),
+                    "dateTime.iso8601" => Some(datetime(py, &data).ok_or_else(|| {
+                        PyValueError::new_err(format!(
+                            "bad dateTime.iso8601 value {data:?} (line {line})"
+                        ))
+                    })??),
+                    "array" => {
+                        let start = marks.pop().unwrap_or_default();
+                        Some(PyList::new(py, values.drain(start..))?.into_any())
+                    }
+                    "struct" => {
+                        let start = marks.pop().unwrap_or_default();
+                        let members = PyDict::new(py);
+                        let mut drained = values.drain(start..);
+                        while let (Some(key), Some(member)) = (drained.next(), drained.next()) {
+                            members.set_item(key, member)?;
+                        }
+                        drop(drained);
+                        Some(members.into_any())
+                    }
+                    "methodName" => {
+                        methodname = Some(std::mem::take(&mut data));
+                        None
+                    }
+                    "fault" => {
+                        fault = true;
+                        None
+                    }
+                    _ => None,
+                };
+                values.extend(value);
+                untyped = false;
+                data.clear();
+            }
+            XmlEvent::DocType(_) | XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction(_) => {}
+        }
+    }
+
+    if fault {
+        let fault = values
+            .first()
+            .and_then(|value| value.downcast::<PyDict>().ok());
+        let field = |key: &str| -> PyResult<Bound<'py, PyAny>> {
+            fault
+                .map(|fault| fault.get_item(key))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| PyValueError::new_err(format!("XML-RPC fault without {key}")))
+        };
+        let args = (field("faultCode")?.unbind(), field("faultString")?.unbind());
+        return Err(Fault::new_err(args));
+    }
+    Ok((PyTuple::new(py, values)?, methodname))
+}
+
+/// The elements allowed directly inside a `<value>`.
+fn is_type(name: &str) -> bool {
+    matches!(
+        name,
+        "string"
+            | "boolean"
+            | "int"
+            | "i1"
+            | "i2"
+            | "i4"
+            | "i8"
+            | "biginteger"
+            | "double"
+            | "float"
+            | "bigdecimal"
+            | "nil"
+            | "base64"
+            | "dateTime.iso8601"
+            | "array"
+            | "struct"
+    )
+}
+
+/// A `YYYYMMDDTHH:MM:SS` timestamp as a naive `datetime`, or `None` when it is not one.
+fn datetime<'py>(py: Python<'py>, text: &str) -> Option<PyResult<Bound<'py, PyAny>>> {
+    let (date, time) = text.trim().split_once('T')?;
+    let number = |digits: &str| {
+        digits
+            .bytes()
+            .all(|byte| byte.is_ascii_digit())
+            .then(|| digits.parse::<u32>().ok())
+            .flatten()
+    };
+    if date.len() != 8 || time.len() != 8 {
+        return None;
+    }
+    let year = i32::try_from(number(date.get(..4)?)?).ok()?;
+    let month = u8::try_from(number(date.get(4..6)?)?).ok()?;
+    let day = u8::try_from(number(date.get(6..)?)?).ok()?;
+    let mut clock = time.split(':');
+    let mut part = || u8::try_from(number(clock.next()?)?).ok();
+    let (hour, minute, second) = (part()?, part()?, part()?);
+    Some(PyDateTime::new(py, year, month, day, hour, minute, second, 0, None).map(Bound::into_any))
+}
@@ -0,0 +1,668 @@
+use super::{Error, SyntaxError, XmlMap, XmlValue};
+use crate::config::{OnDuplicate, ParseConfig};
+use crate::events::Span;
+use crate::infer::{float_repr, infer, Scalar};
+use crate::names::{
+    attribute_key, attribute_value, collect_declarations, qualified_name, scan_attributes,
+};
+use std::collections::{HashMap, HashSet};
+
+/// What a map holds under a key an entry is about to be added with.
+pub(crate) enum Held {
+    Nothing,
+    List,
+    Other,
+}
+
+/// The values a [`TreeBuilder`] makes, and the options applied to each entry as it is
+/// added: the [`XmlValue`] tree, or the Python objects `parse()` returns.
+pub(crate) trait Values<'c> {
+    type Value;
+    type Map;
+    type Error;
+
+    fn config(&self) -> &'c ParseConfig;
+
+    fn none(&mut self) -> Self::Value;
+
+    /// The text of an element or an attribute value, converted as `infer_types` says.
+    fn scalar(&mut self, text: String) -> Result<Self::Value, Self::Error>;
+
+    /// Text kept as it is: comments, namespace URIs and the text of mixed content.
+    fn string(&mut self, text: String) -> Result<Self::Value, Self::Error>;
+
+    /// The position of an element among its siblings, for `index_key`.
+    fn index(&mut self, index: usize) -> Result<Self::Value, Self::Error>;
+
+    fn span(&mut self, span: Span) -> Result<Self::Value, Self::Error>;
+
+    fn list(&mut self, items: Vec<Self::Value>) -> Result<Self::Value, Self::Error>;
+
+    fn map(&mut self) -> Self::Map;
+
+    fn is_empty(&self, map: &Self::Map) -> bool;
+
+    /// A copy of a map that is still being filled in.
+    fn copy(&mut self, map: &Self::Map) -> Result<Self::Map, Self::Error>;
+
+    /// A value that is also kept elsewhere, as the ordered content of a mixed element.
+    fn share(&mut self, value: &Self::Value) -> Self::Value;
+
+    fn held(&mut self, map: &Self::Map, key: &str) -> Result<Held, Self::Error>;
+
+    /// Sets `key` to `value`, in place of what it held.
+    fn set(
+        &mut self,
+        map: &mut Self::Map,
+        key: &str,
+        value: Self::Value,
+    ) -> Result<(), Self::Error>;
+
+    /// Appends `value` to the list `key` holds.
+    fn push(
+        &mut self,
+        map: &mut Self::Map,
+        key: &str,
+        value: Self::Value,
+    ) -> Result<(), Self::Error>;
+
+    /// Makes `key` hold a list of what it held and `value`.
+    fn pair(
+        &mut self,
+        map: &mut Self::Map,
+        key: &str,
+        value: Self::Value,
+    ) -> Result<(), Self::Error>;
+
+    /// A map that is complete, as a value.
+    fn finish(&mut self, map: Self::Map) -> Result<Self::Value, Self::Error>;
+
+    fn error(&self, err: Error) -> Self::Error;
+
+    /// The key and value an entry is stored with, or `None` to leave it out. `path` names
+    /// the open elements and `owner` the element an attribute or text entry belongs to.
+    fn entry(
+        &mut self,
+        _path: &[String],
+        _owner: Option<&str>,
+        key: &str,
+        value: Self::Value,
+    ) -> Result<Option<(String, Self::Value)>, Self::Error> {
+        Ok(Some((key.to_owned(), value)))
+    }
+
+    /// Whether the first entry under `key` is stored as a list of one.
+    fn force_list(
+        &mut self,
+        _path: &[String],
+        _key: &str,
+        _value: &Self::Value,
+    ) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+/// A piece of element content, recorded in document order for `preserve_mixed_content`.
+enum ContentNode<M> {
+    Text(String),
+    /// A child element as a single-entry map.
+    Element(M),
+    /// The element's attributes.
+    Attributes(M),
+    /// A comment or processing instruction as a single-entry map.
+    Entry(M),
+}
+
+/// An element whose end tag has not been read yet, or the document.
+struct Frame<M> {
+    map: M,
+    text: Vec<String>,
+    /// Ordered content, only kept with `preserve_mixed_content`.
+    content: Option<Vec<ContentNode<M>>>,
+    /// Keys whose list value collects repeated elements rather than holding mixed content.
+    repeated: HashSet<String>,
+    /// Child elements so far, for `index_key`.
+    children: usize,
+    /// Keys of the entries so far, in document order, with `preserve_sibling_order`.
+    order: Vec<String>,
+}
+
+impl<M> Frame<M> {
+    fn new(map: M, content: Option<Vec<ContentNode<M>>>) -> Self {
+        Self {
+            map,
+            text: Vec::new(),
+            content,
+            repeated: HashSet::new(),
+            children: 0,
+            order: Vec::new(),
+        }
+    }
+
+    /// Appends text to the ordered content, if kept, joining it to text just before it.
+    fn add_text(&mut self, text: String, separator: &str) {
+        let Some(content) = self.content.as_mut() else {
+            return;
+        };
+        if let Some(ContentNode::Text(last)) = content.last_mut() {
+            last.push_str(separator);
+            last.push_str(&text);
+        } else {
+            content.push(ContentNode::Text(text));
+        }
+    }
+
+    /// Stores an entry whose key and value are final. A key the map already has does as
+    /// `on_duplicate` says, unless its list collects repeated elements.
+    fn store<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+        path: &[String],
+        key: String,
+        value: V::Value,
+        node: fn(M) -> ContentNode<M>,
+    ) -> Result<(), V::Error> {
+        let config = values.config();
+        if config.preserve_sibling_order {
+            self.order.push(key.clone());
+        }
+        if self.content.is_some() {
+            let mut entry = values.map();
+            let shared = values.share(&value);
+            values.set(&mut entry, &key, shared)?;
+            if let Some(content) = self.content.as_mut() {
+                content.push(node(entry));
+            }
+        }
+        match values.held(&self.map, &key)? {
+            // Without ordered content, every list there collects repeated elements.
+            Held::List if self.content.is_none() || self.repeated.contains(&key) => {
+                values.push(&mut self.map, &key, value)?;
+            }
+            Held::Nothing => {
+                if values.force_list(path, &key, &value)? {
+                    let list = values.list(vec![value])?;
+                    values.set(&mut self.map, &key, list)?;
+                    self.repeated.insert(key);
+                } else {
+                    values.set(&mut self.map, &key, value)?;
+                }
+            }
+            Held::List | Held::Other => match config.on_duplicate {
+                OnDuplicate::List => {
+                    values.pair(&mut self.map, &key, value)?;
+                    self.repeated.insert(key);
+                }
+                OnDuplicate::First => {}
+                OnDuplicate::Last => values.set(&mut self.map, &key, value)?,
+                OnDuplicate::Error => {
+                    return Err(values.error(Error::Value(format!(
+                        "{key:?} is repeated in {}",
+                        path.join("/")
+                    ))));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Stores an entry after the options of `values` have had their say about it.
+    fn add<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+        path: &[String],
+        key: &str,
+        value: V::Value,
+        node: fn(M) -> ContentNode<M>,
+    ) -> Result<(), V::Error> {
+        match values.entry(path, None, key, value)? {
+            Some((key, value)) => self.store(values, path, key, value, node),
+            None => Ok(()),
+        }
+    }
+
+    /// Mixed content is an element with both text and child elements, comments or
+    /// processing instructions; whitespace between the children alone does not make it
+    /// mixed.
+    fn mixed_content<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+    ) -> Result<Option<V::Value>, V::Error> {
+        let has_children = self.content.as_ref().is_some_and(|content| {
+            content
+                .iter()
+                .any(|node| matches!(node, ContentNode::Element(_) | ContentNode::Entry(_)))
+        });
+        if self.text.is_empty() || !has_children {
+            return Ok(None);
+        }
+        let mut nodes = Vec::new();
+        for node in self.content.take().unwrap_or_default() {
+            nodes.push(match node {
+                ContentNode::Text(text) => values.string(text)?,
+                ContentNode::Element(map)
+                | ContentNode::Attributes(map)
+                | ContentNode::Entry(map) => values.finish(map)?,
+            });
+        }
+        values.list(nodes).map(Some)
+    }
+}
+
+/// Builds the result of parsing from reader events: which entries elements, attributes,
+/// text and comments become, and how repeated ones are collected. The maps and values
+/// themselves are made by a [`Values`], so the same rules build [`XmlValue`] trees and
+/// Python objects.
+pub(crate) struct TreeBuilder<M> {
+    frames: Vec<Frame<M>>,
+    /// Names of the open elements, from the root down.
+    path: Vec<String>,
+    namespace_stack: Vec<HashMap<String, String>>,
+    doctype: Option<String>,
+    /// Namespace declarations seen so far, with `collect_namespaces`.
+    declarations: Vec<(String, String)>,
+    /// Comments and processing instructions seen before the root element.
+    prolog: Vec<(String, String)>,
+    document: Option<Frame<M>>,
+    /// With `with_spans`, the spans of the elements left to close, in closing order.
+    spans: std::vec::IntoIter<Span>,
+}
+
+impl<M> Default for TreeBuilder<M> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            path: Vec::new(),
+            namespace_stack: Vec::new(),
+            doctype: None,
+            declarations: Vec::new(),
+            prolog: Vec::new(),
+            document: None,
+            spans: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<M> TreeBuilder<M> {
+    /// Forgets the document being built, for the next one.
+    #[cfg(feature = "python")]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Takes the spans the reader recorded for the document about to be built.
+    #[cfg(feature = "python")]
+    pub fn set_spans(&mut self, spans: Vec<Span>) {
+        self.spans = spans.into_iter();
+    }
+
+    /// Keeps the DOCTYPE declaration with `process_doctype`.
+    pub fn doctype(&mut self, config: &ParseConfig, doctype: &str) {
+        if config.process_doctype {
+            self.doctype = Some(doctype.trim().to_owned());
+        }
+    }
+
+    pub fn start_element<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+        name: &str,
+        attributes: Vec<(String, String)>,
+    ) -> Result<(), V::Error> {
+        let config = values.config();
+        if config.collect_namespaces {
+            collect_declarations(&mut self.declarations, &attributes);
+        }
+        let scanned = scan_attributes(config, self.namespace_stack.last(), attributes);
+
+        let mut attrs = values.map();
+        if config.xml_attribs && scanned.report_xmlns {
+            let mut bindings = values.map();
+            for (prefix, uri) in &scanned.namespaces {
+                let uri = values.string(uri.clone())?;
+                values.set(&mut bindings, prefix, uri)?;
+            }
+            let bindings = values.finish(bindings)?;
+            let key = format!("{}xmlns", config.attribute_prefix());
+            values.set(&mut attrs, &key, bindings)?;
+        }
+
+        self.namespace_stack.push(scanned.namespaces);
+        let name = qualified_name(config, self.namespace_stack.last(), name);
+
+        for (key, value) in scanned.attributes {
+            let value = attribute_value(config, self.namespace_stack.last(), &key, value);
+            let key = attribute_key(config, self.namespace_stack.last(), &key);
+            let value = values.scalar(value)?;
+            if let Some((key, value)) = values.entry(&self.path, Some(&name), &key, value)? {
+                values.set(&mut attrs, &key, value)?;
+            }
+        }
+
+        let mut map = match &config.attr_dict_key {
+            Some(key) if !values.is_empty(&attrs) => {
+                let mut map = values.map();
+                let attrs = values.finish(attrs)?;
+                values.set(&mut map, key, attrs)?;
+                map
+            }
+            Some(_) | None => attrs,
+        };
+
+        let content = if config.preserve_mixed_content {
+            Some(if values.is_empty(&map) {
+                Vec::new()
+            } else {
+                vec![ContentNode::Attributes(values.copy(&map)?)]
+            })
+        } else {
+            None
+        };
+
+        // The root element is no one's child, so it has no position.
+        if let Some(key) = &config.index_key {
+            if let Some(parent) = self.frames.last_mut() {
+                let index = values.index(parent.children)?;
+                values.set(&mut map, key, index)?;
+                parent.children += 1;
+            }
+        }
+
+        self.frames.push(Frame::new(map, content));
+        self.path.push(name);
+        Ok(())
+    }
+
+    pub fn end_element<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+    ) -> Result<(), V::Error> {
+        let config = values.config();
+        let span = self.spans.next();
+        let (Some(mut frame), Some(name), Some(_)) = (
+            self.frames.pop(),
+            self.path.pop(),
+            self.namespace_stack.pop(),
+        ) else {
+            return Err(values.error(
+                SyntaxError::new(SyntaxError::TAG_MISMATCH, "unexpected closing tag").into(),
+            ));
+        };
+
+        // Elements without children need no order, and stay text where they have only text.
+        if !frame.order.is_empty() {
+            let keys = std::mem::take(&mut frame.order)
+                .into_iter()
+                .map(|key| values.string(key))
+                .collect::<Result<_, _>>()?;
+            let order = values.list(keys)?;
+            values.set(&mut frame.map, &config.order_key, order)?;
+        }
+
+        let value = match frame.mixed_content(values)? {
+            Some(nodes) => nodes,
+            None => self.element_value(values, &name, frame, span)?,
+        };
+
+        if let Some(parent) = self.frames.last_mut() {
+            return parent.add(values, &self.path, &name, value, ContentNode::Element);
+        }
+        if let Some(document) = self.document.as_mut() {
+            return document.add(values, &self.path, &name, value, ContentNode::Element);
+        }
+        let mut document = self.new_document(values)?;
+        // A root element left out leaves no document.
+        if let Some((key, value)) = values.entry(&self.path, None, &name, value)? {
+            document.store(values, &self.path, key, value, ContentNode::Element)?;
+            self.document = Some(document);
+        }
+        Ok(())
+    }
+
+    /// The result map, made when the first root element closes. A kept DOCTYPE declaration
+    /// is its first entry, followed by the collected namespaces and the prolog.
+    fn new_document<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+    ) -> Result<Frame<M>, V::Error> {
+        let config = values.config();
+        let mut document = Frame::new(values.map(), None);
+        if let Some(doctype) = self.doctype.take() {
+            let doctype = values.string(doctype)?;
+            values.set(&mut document.map, &config.doctype_key, doctype)?;
+        }
+        if config.collect_namespaces {
+            let mut declarations = values.map();
+            for (prefix, uri) in std::mem::take(&mut self.declarations) {
+                let uri = values.string(uri)?;
+                values.set(&mut declarations, &prefix, uri)?;
+            }
+            let declarations = values.finish(declarations)?;
+            values.set(&mut document.map, &config.namespaces_key, declarations)?;
+        }
+        for (key, text) in std::mem::take(&mut self.prolog) {
+            let text = values.string(text)?;
+            document.add(values, &self.path, &key, text, ContentNode::Entry)?;
+        }
+        Ok(document)
+    }
+
+    /// Value of a closed element: `None`, its text, or its map of attributes and children.
+    /// Only a map has room for the element's `span`.
+    fn element_value<'c, V: Values<'c, Map = M>>(
+        &self,
+        values: &mut V,
+        name: &str,
+        frame: Frame<M>,
+        span: Option<Span>,
+    ) -> Result<V::Value, V::Error> {
+        let config = values.config();
+        let text = config.join_text(frame.text);
+        let mut map = frame.map;
+        match (values.is_empty(&map), text) {
+            (true, None) => return Ok(values.none()),
+            (true, Some(text)) if !config.force_cdata => return values.scalar(text),
+            (_, Some(text)) => {
+                let text = values.scalar(text)?;
+                let entry = values.entry(&self.path, Some(name), &config.cdata_key, text)?;
+                if let Some((key, text)) = entry {
+                    values.set(&mut map, &key, text)?;
+                }
+            }
+            (false, None) => {}
+        }
+        if let Some(span) = span {
+            let span = values.span(span)?;
+            values.set(&mut map, &config.span_key, span)?;
+        }
+        values.finish(map)
+    }
+
+    /// Keeps the text of an event for the innermost open element, taking the string as it
+    /// is; it is only copied when `preserve_mixed_content` also records it in order.
+    pub fn characters(&mut self, config: &ParseConfig, data: String) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        if frame.content.is_some() {
+            frame.add_text(data.clone(), &config.cdata_separator);
+        }
+        frame.text.push(data);
+    }
+
+    /// Whitespace between children only goes into the ordered content of mixed elements;
+    /// without them it continues the last text.
+    pub fn whitespace(&mut self, config: &ParseConfig, data: String) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        if frame.content.is_some() {
+            frame.add_text(data, &config.cdata_separator);
+        } else if let Some(last) = frame.text.last_mut() {
+            last.push_str(&data);
+        }
+    }
+
+    /// Adds a comment or processing instruction under `key` to the innermost open element,
+    /// or to the document when it is outside the root element.
+    pub fn add_entry<'c, V: Values<'c, Map = M>>(
+        &mut self,
+        values: &mut V,
+        key: &str,
+        text: &str,
+    ) -> Result<(), V::Error> {
+        let text = values.config().strip.apply(text).to_owned();
+        if let Some(parent) = self.frames.last_mut() {
+            let text = values.string(text)?;
+            return parent.add(values, &self.path, key, text, ContentNode::Entry);
+        }
+        if let Some(document) = self.document.as_mut() {
+            let text = values.string(text)?;
+            return document.add(values, &self.path, key, text, ContentNode::Entry);
+        }
+        self.prolog.push((key.to_owned(), text));
+        Ok(())
+    }
+
+    /// The document map, once every element is closed. With `preserve_sibling_order`, its
+    /// order goes under `order_key` when grouping its entries by key would move some, as it
+    /// does for comments on both sides of the root element.
+    pub fn finish<'c, V: Values<'c, Map = M>>(&mut self, values: &mut V) -> Result<M, V::Error> {
+        if !self.frames.is_empty() {
+            return Err(values.error(
+                SyntaxError::new(SyntaxError::NO_ELEMENTS, "unclosed element(s) found").into(),
+            ));
+        }
+        let Some(mut document) = self.document.take() else {
+            return Err(
+                values.error(SyntaxError::new(SyntaxError::NO_ELEMENTS, "no element found").into())
+            );
+        };
+        if is_scattered(&document.order) {
+            let keys = std::mem::take(&mut document.order)
+                .into_iter()
+                .map(|key| values.string(key))
+                .collect::<Result<_, _>>()?;
+            let order = values.list(keys)?;
+            values.set(&mut document.map, &values.config().order_key, order)?;
+        }
+        Ok(document.map)
+    }
+}
+
+/// Whether a key comes back after other keys, so its entries are not next to each other.
+fn is_scattered(keys: &[String]) -> bool {
+    keys.iter().enumerate().any(|(i, key)| {
+        let mut earlier = keys.iter().take(i).rev();
+        earlier.next().is_some_and(|last| last != key) && earlier.any(|other| other == key)
+    })
+}
+
+/// Builds [`XmlValue`] trees, in which text stays text unless `infer_types` converts it.
+pub(crate) struct Plain<'c>(pub &'c ParseConfig);
+
+impl<'c> Values<'c> for Plain<'c> {
+    type Value = XmlValue;
+    type Map = XmlMap;
+    type Error = Error;
+
+    fn config(&self) -> &'c ParseConfig {
+        self.0
+    }
+
+    fn none(&mut self) -> XmlValue {
+        XmlValue::None
+    }
+
+    fn scalar(&mut self, text: String) -> Result<XmlValue, Error> {
+        let kinds = self.0.infer_types;
+        if !kinds.any() {
+            return Ok(XmlValue::Text(text));
+        }
+        Ok(match infer(&text, kinds) {
+            None => XmlValue::Text(text),
+            Some(Scalar::Null) => XmlValue::None,
+            Some(Scalar::Bool(value)) => XmlValue::Bool(value),
+            Some(Scalar::Int(value)) => XmlValue::Number(value.to_string()),
+            Some(Scalar::BigInt) => {
+                XmlValue::Number(text.strip_prefix('+').unwrap_or(&text).to_owned())
+            }
+            Some(Scalar::Float(value)) => XmlValue::Number(float_repr(value)),
+        })
+    }
+
+    fn force_list(&mut self, path: &[String], key: &str, _value: &XmlValue) -> Result<bool, Error> {
+        Ok(self.0.force_list.contains(path, key))
+    }
+
+    fn string(&mut self, text: String) -> Result<XmlValue, Error> {
+        Ok(XmlValue::Text(text))
+    }
+
+    fn index(&mut self, index: usize) -> Result<XmlValue, Error> {
+        Ok(XmlValue::Text(index.to_string()))
+    }
+
+    fn span(&mut self, (start, end): Span) -> Result<XmlValue, Error> {
+        Ok(XmlValue::List(vec![
+            XmlValue::Text(start.to_string()),
+            XmlValue::Text(end.to_string()),
+        ]))
+    }
+
+    fn list(&mut self, items: Vec<XmlValue>) -> Result<XmlValue, Error> {
+        Ok(XmlValue::List(items))
+    }
+
+    fn map(&mut self) -> XmlMap {
+        XmlMap::new()
+    }
+
+    fn is_empty(&self, map: &XmlMap) -> bool {
+        map.is_empty()
+    }
+
+    fn copy(&mut self, map: &XmlMap) -> Result<XmlMap, Error> {
+        Ok(map.clone())
+    }
+
+    fn share(&mut self, value: &XmlValue) -> XmlValue {
+        value.clone()
+    }
+
+    fn held(&mut self, map: &XmlMap, key: &str) -> Result<Held, Error> {
+        Ok(match map.get(key) {
+            None => Held::Nothing,
+            Some(XmlValue::List(_)) => Held::List,
+            Some(_) => Held::Other,
+        })
+    }
+
+    fn set(&mut self, map: &mut XmlMap, key: &str, value: XmlValue) -> Result<(), Error> {
+        map.insert(key, value);
+        Ok(())
+    }
+
+    fn push(&mut self, map: &mut XmlMap, key: &str, value: XmlValue) -> Result<(), Error> {
+        if let Some(XmlValue::List(items)) = map.get_mut(key) {
+            items.push(value);
+        }
+        Ok(())
+    }
+
+    fn pair(&mut self, map: &mut XmlMap, key: &str, value: XmlValue) -> Result<(), Error> {
+        if let Some(existing) = map.get_mut(key) {
+            let first = std::mem::take(existing);
+            *existing = XmlValue::List(vec![first, value]);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, map: XmlMap) -> Result<XmlValue, Error> {
+        Ok(XmlValue::Map(map))
+    }
+
+    fn error(&self, err: Error) -> Error {
+        err
+    }
+}
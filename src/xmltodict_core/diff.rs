@@ -0,0 +1,256 @@
+use super::{XmlMap, XmlValue};
+use crate::path_pattern::PathPattern;
+use std::fmt;
+
+/// How a value differs between two documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Only in the second document.
+    Added,
+    /// Only in the first document.
+    Removed,
+    /// In both, with different values.
+    Changed,
+}
+
+impl DiffKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+impl fmt::Display for DiffKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A difference between two documents: the value at `path` in the first one is `left`
+/// and in the second `right`, `None` on the side it is missing from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    /// Keys from the root element down, joined with `/`, with the position from 1 of an item
+    /// of a repeated element in brackets, as `find` takes it: `order/line[2]/@qty`.
+    pub path: String,
+    pub kind: DiffKind,
+    pub left: Option<XmlValue>,
+    pub right: Option<XmlValue>,
+}
+
+/// Compares two parsed documents key by key. Items of repeated elements are compared by
+/// position, except for elements on the paths in `ignore_order_for` (written as for
+/// `only_paths`, such as `**/item`), whose items are matched with an equal item anywhere
+/// in the other list; an item without a match is removed or added.
+#[must_use]
+pub fn diff<S: AsRef<str>>(
+    left: &XmlMap,
+    right: &XmlMap,
+    ignore_order_for: &[S],
+) -> Vec<Difference> {
+    let mut differ = Differ {
+        unordered: ignore_order_for
+            .iter()
+            .map(|path| PathPattern::new(path.as_ref()))
+            .collect(),
+        found: Vec::new(),
+    };
+    differ.maps("", &mut Vec::new(), left, right);
+    differ.found
+}
+
+struct Differ {
+    unordered: Vec<PathPattern>,
+    found: Vec<Difference>,
+}
+
+impl Differ {
+    fn push(
+        &mut self,
+        path: String,
+        kind: DiffKind,
+        left: Option<&XmlValue>,
+        right: Option<&XmlValue>,
+    ) {
+        self.found.push(Difference {
+            path,
+            kind,
+            left: left.cloned(),
+            right: right.cloned(),
+        });
+    }
+
+    /// Compares the entries of two maps at `path`; `names` holds the keys leading to them.
+    fn maps<'a>(
+        &mut self,
+        path: &str,
+        names: &mut Vec<&'a str>,
+        left: &'a XmlMap,
+        right: &'a XmlMap,
+    ) {
+        for (key, left_value) in left.iter() {
+            let child = child_path(path, key);
+            match right.get(key) {
+                Some(right_value) => {
+                    names.push(key);
+                    self.values(&child, names, left_value, right_value);
+                    names.pop();
+                }
+                None => self.push(child, DiffKind::Removed, Some(left_value), None),
+            }
+        }
+        for (key, right_value) in right.iter() {
+            if left.get(key).is_none() {
+                self.push(
+                    child_path(path, key),
+                    DiffKind::Added,
+                    None,
+                    Some(right_value),
+                );
+            }
+        }
+    }
+
+    fn values<'a>(
+        &mut self,
+        path: &str,
+        names: &mut Vec<&'a str>,
+        left: &'a XmlValue,
+        right: &'a XmlValue,
+    ) {
+        if left == right {
+            return;
+        }
+        match (left, right) {
+            (XmlValue::Map(left), XmlValue::Map(right)) => self.maps(path, names, left, right),
+            (XmlValue::List(left), XmlValue::List(right)) => self.lists(path, names, left, right),
+            // An element that is repeated in one document only is compared as a list.
+            (XmlValue::List(left), _) => {
+                self.lists(path, names, left, std::slice::from_ref(right));
+            }
+            (_, XmlValue::List(right)) => {
+                self.lists(path, names, std::slice::from_ref(left), right);
+            }
+            (
+                XmlValue::None
+                | XmlValue::Text(_)
+                | XmlValue::Bool(_)
+                | XmlValue::Number(_)
+                | XmlValue::Map(_),
+                _,
+            ) => {
+                self.push(path.to_owned(), DiffKind::Changed, Some(left), Some(right));
+            }
+        }
+    }
+
+    fn lists<'a>(
+        &mut self,
+        path: &str,
+        names: &mut Vec<&'a str>,
+        left: &'a [XmlValue],
+        right: &'a [XmlValue],
+    ) {
+        let item_path = |index: usize| format!("{path}[{}]", index + 1);
+        if self.unordered.iter().any(|pattern| pattern.matches(names)) {
+            let mut matched = vec![false; right.len()];
+            for (index, item) in left.iter().enumerate() {
+                let found = right
+                    .iter()
+                    .zip(&matched)
+                    .position(|(other, &taken)| !taken && other == item);
+                match found.and_then(|found| matched.get_mut(found)) {
+                    Some(taken) => *taken = true,
+                    None => self.push(item_path(index), DiffKind::Removed, Some(item), None),
+                }
+            }
+            for (index, (item, taken)) in right.iter().zip(matched).enumerate() {
+                if !taken {
+                    self.push(item_path(index), DiffKind::Added, None, Some(item));
+                }
+            }
+            return;
+        }
+        for index in 0..left.len().max(right.len()) {
+            match (left.get(index), right.get(index)) {
+                (Some(left), Some(right)) => self.values(&item_path(index), names, left, right),
+                (Some(left), None) => {
+                    self.push(item_path(index), DiffKind::Removed, Some(left), None);
+                }
+                (None, Some(right)) => {
+                    self.push(item_path(index), DiffKind::Added, None, Some(right));
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}/{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ParseConfig;
+    use crate::xmltodict_core::parse_str;
+
+    fn paths(a: &str, b: &str, ignore_order_for: &[&str]) -> Vec<(String, DiffKind)> {
+        let config = ParseConfig::default();
+        let (Ok(a), Ok(b)) = (parse_str(a, &config), parse_str(b, &config)) else {
+            return Vec::new();
+        };
+        diff(&a, &b, ignore_order_for)
+            .into_iter()
+            .map(|difference| (difference.path, difference.kind))
+            .collect()
+    }
+
+    #[test]
+    fn reports_changed_removed_and_added_keys() {
+        let found = paths(
+            r#"<r id="1"><a>x</a><b/><c><d>1</d></c></r>"#,
+            r#"<r id="2"><a>x</a><c><d>2</d></c><e/></r>"#,
+            &[],
+        );
+        assert_eq!(
+            vec![
+                ("r/@id".to_owned(), DiffKind::Changed),
+                ("r/b".to_owned(), DiffKind::Removed),
+                ("r/c/d".to_owned(), DiffKind::Changed),
+                ("r/e".to_owned(), DiffKind::Added),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn repeated_elements_are_compared_by_position_unless_unordered() {
+        let (a, b) = ("<r><i>1</i><i>2</i></r>", "<r><i>2</i><i>1</i><i>3</i></r>");
+        assert_eq!(
+            vec![
+                ("r/i[1]".to_owned(), DiffKind::Changed),
+                ("r/i[2]".to_owned(), DiffKind::Changed),
+                ("r/i[3]".to_owned(), DiffKind::Added),
+            ],
+            paths(a, b, &[])
+        );
+        assert_eq!(
+            vec![("r/i[3]".to_owned(), DiffKind::Added)],
+            paths(a, b, &["**/i"])
+        );
+        assert_eq!(
+            vec![("r/i[2]".to_owned(), DiffKind::Added)],
+            paths("<r><i>1</i></r>", "<r><i>1</i><i>2</i></r>", &[])
+        );
+    }
+}
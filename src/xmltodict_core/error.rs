@@ -0,0 +1,209 @@
+use quick_xml::escape::EscapeError;
+use quick_xml::events::attributes::AttrError;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+/// Where a syntax error was found, counted as expat does: `line` from 1, `column` and
+/// `offset` in bytes from 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+/// The kind of parse limit a [`SyntaxError`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Element nesting or entity nesting.
+    Depth,
+    /// Attribute count, text length, element count or entity expansion size.
+    Size,
+}
+
+/// A malformed document, with an expat-style message and error code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// The matching `xml.parsers.expat.errors` code.
+    pub code: u32,
+    pub message: String,
+    pub position: Option<Position>,
+    /// Set when parsing stopped at a configured limit rather than at malformed input.
+    pub limit: Option<Limit>,
+}
+
+impl SyntaxError {
+    pub const SYNTAX: u32 = 2;
+    pub const NO_ELEMENTS: u32 = 3;
+    pub const INVALID_TOKEN: u32 = 4;
+    pub const UNCLOSED_TOKEN: u32 = 5;
+    pub const TAG_MISMATCH: u32 = 7;
+    pub const DUPLICATE_ATTRIBUTE: u32 = 8;
+    pub const JUNK_AFTER_DOC_ELEMENT: u32 = 9;
+    pub const UNDEFINED_ENTITY: u32 = 11;
+    pub const RECURSIVE_ENTITY_REF: u32 = 12;
+    pub const BAD_CHAR_REF: u32 = 14;
+    pub const INCORRECT_ENCODING: u32 = 19;
+    pub const AMPLIFICATION_LIMIT_BREACH: u32 = 43;
+
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            position: None,
+            limit: None,
+        }
+    }
+
+    /// An error for input that exceeds a parse limit.
+    pub fn limit_exceeded(limit: Limit, message: impl Into<String>) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::new(Self::AMPLIFICATION_LIMIT_BREACH, message)
+        }
+    }
+
+    /// Picks the expat code closest to a `quick_xml` error. I/O errors are transcoding failures
+    /// and, like invalid UTF-8, are reported as `INCORRECT_ENCODING`.
+    pub(crate) fn from_quick_xml(err: &quick_xml::Error) -> Self {
+        let code = match err {
+            quick_xml::Error::Io(io_err) => {
+                return Self::new(Self::INCORRECT_ENCODING, io_err.to_string());
+            }
+            quick_xml::Error::UnexpectedEof(_) => Self::UNCLOSED_TOKEN,
+            quick_xml::Error::EndEventMismatch { .. } => Self::TAG_MISMATCH,
+            quick_xml::Error::InvalidAttr(AttrError::Duplicated(..)) => Self::DUPLICATE_ATTRIBUTE,
+            quick_xml::Error::EscapeError(EscapeError::UnrecognizedSymbol(..)) => {
+                Self::UNDEFINED_ENTITY
+            }
+            quick_xml::Error::EscapeError(
+                EscapeError::EntityWithNull(_)
+                | EscapeError::TooLongHexadecimal
+                | EscapeError::InvalidHexadecimal(_)
+                | EscapeError::TooLongDecimal
+                | EscapeError::InvalidDecimal(_)
+                | EscapeError::InvalidCodepoint(_),
+            ) => Self::BAD_CHAR_REF,
+            quick_xml::Error::NonDecodable(_) => Self::INCORRECT_ENCODING,
+            quick_xml::Error::UnexpectedToken(_)
+            | quick_xml::Error::UnexpectedBang(_)
+            | quick_xml::Error::InvalidAttr(_)
+            | quick_xml::Error::EscapeError(EscapeError::UnterminatedEntity(_))
+            | quick_xml::Error::UnknownPrefix(_)
+            | quick_xml::Error::InvalidPrefixBind { .. } => Self::INVALID_TOKEN,
+            quick_xml::Error::TextNotFound
+            | quick_xml::Error::XmlDeclWithoutVersion(_)
+            | quick_xml::Error::EmptyDocType => Self::SYNTAX,
+        };
+        Self::new(code, err.to_string())
+    }
+
+    /// Sets the position unless one is already known.
+    #[must_use]
+    pub fn at(mut self, position: Position) -> Self {
+        self.position.get_or_insert(position);
+        self
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(Position { line, column, .. }) => {
+                write!(f, "{}: line {line}, column {column}", self.message)
+            }
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Errors produced by the pure-Rust parser and writer.
+#[derive(Debug)]
+pub enum Error {
+    /// Malformed document.
+    Syntax(SyntaxError),
+    /// A DTD or entity declaration rejected by `forbid_dtd`/`forbid_entities`.
+    EntitiesForbidden(String),
+    /// The input could not be read.
+    Io(io::Error),
+    /// The value cannot be serialized as XML or `MessagePack`.
+    Value(String),
+    /// Malformed JSON, or JSON that does not describe an XML document.
+    Json(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(err) => write!(f, "{err}"),
+            Self::EntitiesForbidden(msg) | Self::Value(msg) | Self::Json(msg) => write!(f, "{msg}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::EntitiesForbidden(_) | Self::Value(_) | Self::Json(_) => None,
+        }
+    }
+}
+
+impl From<SyntaxError> for Error {
+    fn from(err: SyntaxError) -> Self {
+        Self::Syntax(err)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(err: quick_xml::Error) -> Self {
+        match err {
+            // Transcoding failures surface as invalid data from the decoding reader.
+            quick_xml::Error::Io(io_err) if io_err.kind() != io::ErrorKind::InvalidData => {
+                Self::Io(
+                    Arc::try_unwrap(io_err)
+                        .unwrap_or_else(|shared| io::Error::new(shared.kind(), shared.to_string())),
+                )
+            }
+            other @ (quick_xml::Error::Io(_)
+            | quick_xml::Error::NonDecodable(_)
+            | quick_xml::Error::UnexpectedEof(_)
+            | quick_xml::Error::EndEventMismatch { .. }
+            | quick_xml::Error::UnexpectedToken(_)
+            | quick_xml::Error::UnexpectedBang(_)
+            | quick_xml::Error::TextNotFound
+            | quick_xml::Error::XmlDeclWithoutVersion(_)
+            | quick_xml::Error::EmptyDocType
+            | quick_xml::Error::InvalidAttr(_)
+            | quick_xml::Error::EscapeError(_)
+            | quick_xml::Error::UnknownPrefix(_)
+            | quick_xml::Error::InvalidPrefixBind { .. }) => {
+                Self::Syntax(SyntaxError::from_quick_xml(&other))
+            }
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::Syntax(SyntaxError::new(
+            SyntaxError::INVALID_TOKEN,
+            err.to_string(),
+        ))
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        Self::Syntax(SyntaxError::new(
+            SyntaxError::INVALID_TOKEN,
+            err.to_string(),
+        ))
+    }
+}
@@ -0,0 +1,577 @@
+use super::parse::parse_records;
+use super::{parse_bytes, unparse, Error, XmlMap, XmlValue};
+use crate::config::{ParseConfig, UnparseConfig};
+use crate::path_pattern::PathPattern;
+use crate::reader::{decompressing, DecodingRead};
+use std::fmt::Write;
+use std::io::{self, BufReader, Read};
+
+/// JSON documents nested deeper than this are rejected rather than overflowing the stack.
+const MAX_NESTING: usize = 512;
+
+/// How [`to_json`] lays out its output; the defaults match Python's `json.dumps()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonFormat {
+    /// Put each member and item on its own line, indented by this string per level.
+    pub indent: Option<String>,
+    /// Write characters outside ASCII as `\uXXXX` escapes.
+    pub ensure_ascii: bool,
+}
+
+impl Default for JsonFormat {
+    fn default() -> Self {
+        Self {
+            indent: None,
+            ensure_ascii: true,
+        }
+    }
+}
+
+/// Converts an XML document straight to JSON text, as `json.dumps(parse(xml))` would.
+///
+/// # Errors
+///
+/// Same as [`parse_bytes`](super::parse_bytes).
+pub fn xml_to_json(xml: &[u8], config: &ParseConfig, format: &JsonFormat) -> Result<String, Error> {
+    Ok(to_json(&parse_bytes(xml, config)?, format))
+}
+
+/// Streams the records of an XML document, the elements on `record_path` (written as for
+/// `only_paths`), into `output` as JSON lines: the value of each record as `parse` has it,
+/// on a line of its own. The document is read as [`parse_reader`](super::parse_reader) reads
+/// it, and only one record is held at a time. Returns the number of records written.
+///
+/// # Errors
+///
+/// Same as [`parse_reader`](super::parse_reader), plus [`Error::Io`] when writing fails.
+/// Records before an error have been written.
+pub fn xml_to_ndjson<R: Read, W: io::Write>(
+    reader: R,
+    record_path: &str,
+    config: &ParseConfig,
+    ensure_ascii: bool,
+    mut output: W,
+) -> Result<usize, Error> {
+    let reader = decompressing(reader, config.decompress);
+    let reader = BufReader::new(DecodingRead::sniffing(reader));
+    let mut records = 0;
+    parse_records(reader, &PathPattern::new(record_path), config, |value| {
+        records += 1;
+        output
+            .write_all(json_line(&value, ensure_ascii).as_bytes())
+            .map_err(Error::Io)
+    })?;
+    output.flush().map_err(Error::Io)?;
+    Ok(records)
+}
+
+/// A value as a line of JSON, ended by a line break.
+pub(crate) fn json_line(value: &XmlValue, ensure_ascii: bool) -> String {
+    let format = JsonFormat {
+        indent: None,
+        ensure_ascii,
+    };
+    let mut writer = JsonWriter {
+        format: &format,
+        output: String::new(),
+        level: 0,
+    };
+    writer.value(value);
+    writer.output.push('\n');
+    writer.output
+}
+
+/// Converts JSON text straight to an XML document, as `unparse(json.loads(text))` would.
+/// Numbers keep their JSON spelling and booleans become `true` and `false`.
+///
+/// # Errors
+///
+/// Returns [`Error::Json`] for malformed JSON or a document that is not an object, and
+/// the errors of [`unparse`](super::unparse).
+pub fn json_to_xml(json: &str, config: &UnparseConfig) -> Result<String, Error> {
+    unparse(&from_json(json)?, config)
+}
+
+/// Writes a value tree as JSON text: maps as objects, lists as arrays, text as strings and
+/// empty elements as `null`.
+#[must_use]
+pub fn to_json(map: &XmlMap, format: &JsonFormat) -> String {
+    let mut writer = JsonWriter {
+        format,
+        output: String::new(),
+        level: 0,
+    };
+    writer.map(map);
+    writer.output
+}
+
+/// Reads a JSON object into a value tree.
+///
+/// # Errors
+///
+/// Returns [`Error::Json`] for malformed JSON, a document that is not an object, or nesting
+/// deeper than 512 levels.
+pub fn from_json(json: &str) -> Result<XmlMap, Error> {
+    let mut reader = JsonReader {
+        text: json,
+        position: 0,
+        depth: 0,
+    };
+    reader.skip_whitespace();
+    if reader.peek() != Some(b'{') {
+        return Err(reader.error("A JSON document for XML must be an object"));
+    }
+    let value = reader.value()?;
+    reader.skip_whitespace();
+    if reader.position < json.len() {
+        return Err(reader.error("Extra data"));
+    }
+    match value {
+        XmlValue::Map(map) => Ok(map),
+        XmlValue::None
+        | XmlValue::Text(_)
+        | XmlValue::Bool(_)
+        | XmlValue::Number(_)
+        | XmlValue::List(_) => Err(reader.error("A JSON document for XML must be an object")),
+    }
+}
+
+struct JsonWriter<'f> {
+    format: &'f JsonFormat,
+    output: String,
+    level: usize,
+}
+
+impl JsonWriter<'_> {
+    fn value(&mut self, value: &XmlValue) {
+        match value {
+            XmlValue::None => self.output.push_str("null"),
+            XmlValue::Text(text) => self.string(text),
+            XmlValue::Bool(value) => self.output.push_str(if *value { "true" } else { "false" }),
+            XmlValue::Number(number) => self.output.push_str(number),
+            XmlValue::List(items) => {
+                if items.is_empty() {
+                    self.output.push_str("[]");
+                    return;
+                }
+                self.output.push('[');
+                self.level += 1;
+                for (index, item) in items.iter().enumerate() {
+                    self.separator(index);
+                    self.value(item);
+                }
+                self.close(']');
+            }
+            XmlValue::Map(map) => self.map(map),
+        }
+    }
+
+    fn map(&mut self, map: &XmlMap) {
+        if map.is_empty() {
+            self.output.push_str("{}");
+            return;
+        }
+        self.output.push('{');
+        self.level += 1;
+        for (index, (key, value)) in map.iter().enumerate() {
+            self.separator(index);
+            self.string(key);
+            self.output.push_str(": ");
+            self.value(value);
+        }
+        self.close('}');
+    }
+
+    /// What comes before the member or item at `index`: a comma after the first, then a
+    /// space, or a new line when indenting.
+    fn separator(&mut self, index: usize) {
+        if index > 0 {
+            self.output.push(',');
+        }
+        match &self.format.indent {
+            Some(indent) => {
+                self.output.push('\n');
+                for _ in 0..self.level {
+                    self.output.push_str(indent);
+                }
+            }
+            None if index > 0 => self.output.push(' '),
+            None => {}
+        }
+    }
+
+    fn close(&mut self, bracket: char) {
+        self.level -= 1;
+        if let Some(indent) = &self.format.indent {
+            self.output.push('\n');
+            for _ in 0..self.level {
+                self.output.push_str(indent);
+            }
+        }
+        self.output.push(bracket);
+    }
+
+    fn string(&mut self, text: &str) {
+        self.output.push('"');
+        for ch in text.chars() {
+            match ch {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                '\u{8}' => self.output.push_str("\\b"),
+                '\u{c}' => self.output.push_str("\\f"),
+                ch if ch < ' ' || (self.format.ensure_ascii && !ch.is_ascii()) => {
+                    let mut units = [0; 2];
+                    for unit in ch.encode_utf16(&mut units) {
+                        let _ = write!(self.output, "\\u{unit:04x}");
+                    }
+                }
+                ch => self.output.push(ch),
+            }
+        }
+        self.output.push('"');
+    }
+}
+
+struct JsonReader<'t> {
+    text: &'t str,
+    /// Byte offset of the next character.
+    position: usize,
+    depth: usize,
+}
+
+impl JsonReader<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    /// An error at the current position, located as Python's `json` module does.
+    fn error(&self, message: &str) -> Error {
+        let before = self.text.get(..self.position).unwrap_or(self.text);
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count())
+            + 1;
+        Error::Json(format!(
+            "{message}: line {line} column {column} (char {})",
+            before.chars().count()
+        ))
+    }
+
+    fn expect(&mut self, byte: u8, message: &str) -> Result<(), Error> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn value(&mut self) -> Result<XmlValue, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(XmlValue::Text(self.string()?)),
+            Some(b't') => self.literal("true", XmlValue::Text("true".to_owned())),
+            Some(b'f') => self.literal("false", XmlValue::Text("false".to_owned())),
+            Some(b'n') => self.literal("null", XmlValue::None),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) | None => Err(self.error("Expecting value")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        read: fn(&mut Self) -> Result<XmlValue, Error>,
+    ) -> Result<XmlValue, Error> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error("JSON nesting is too deep"));
+        }
+        self.depth += 1;
+        self.position += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<XmlValue, Error> {
+        let mut map = XmlMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(XmlValue::Map(map));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expecting property name enclosed in double quotes"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':', "expecting ':' delimiter")?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(XmlValue::Map(map));
+                }
+                Some(_) | None => return Err(self.error("Expecting ',' delimiter")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<XmlValue, Error> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(XmlValue::List(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(XmlValue::List(items));
+                }
+                Some(_) | None => return Err(self.error("Expecting ',' delimiter")),
+            }
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: XmlValue) -> Result<XmlValue, Error> {
+        let rest = self.text.get(self.position..).unwrap_or_default();
+        if rest.starts_with(word) {
+            self.position += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("Expecting value"))
+        }
+    }
+
+    /// A number, kept as written after checking it follows the JSON grammar.
+    fn number(&mut self) -> Result<XmlValue, Error> {
+        let start = self.position;
+        let digits = |reader: &mut Self| {
+            let from = reader.position;
+            while matches!(reader.peek(), Some(b'0'..=b'9')) {
+                reader.position += 1;
+            }
+            reader.position > from
+        };
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        if self.peek() == Some(b'0') {
+            self.position += 1;
+        } else if !digits(self) {
+            return Err(self.error("Expecting value"));
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err(self.error("Expecting digits after '.'"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("Expecting exponent digits"));
+            }
+        }
+        let number = self.text.get(start..self.position).unwrap_or_default();
+        Ok(XmlValue::Text(number.to_owned()))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.position;
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            let rest = self.text.get(self.position..).unwrap_or_default();
+            let Some(stop) = rest.find(|ch: char| ch == '"' || ch == '\\' || ch < ' ') else {
+                self.position = start;
+                return Err(self.error("Unterminated string starting at"));
+            };
+            text.push_str(rest.get(..stop).unwrap_or_default());
+            self.position += stop;
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    self.escape(&mut text)?;
+                }
+                Some(_) | None => return Err(self.error("Invalid control character")),
+            }
+        }
+    }
+
+    fn escape(&mut self, text: &mut String) -> Result<(), Error> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.position += 1;
+                let unit = self.hex_unit()?;
+                let code = if (0xD800..0xDC00).contains(&unit)
+                    && self
+                        .text
+                        .get(self.position..)
+                        .unwrap_or_default()
+                        .starts_with("\\u")
+                {
+                    self.position += 2;
+                    let low = self.hex_unit()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("Invalid \\uXXXX surrogate pair"));
+                    }
+                    0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    unit
+                };
+                let ch = char::from_u32(code).ok_or_else(|| self.error("Lone surrogate"))?;
+                text.push(ch);
+                return Ok(());
+            }
+            Some(_) | None => return Err(self.error("Invalid \\escape")),
+        };
+        self.position += 1;
+        text.push(escaped);
+        Ok(())
+    }
+
+    fn hex_unit(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("Invalid \\uXXXX escape"))?;
+        let unit =
+            u32::from_str_radix(digits, 16).map_err(|_err| self.error("Invalid \\uXXXX escape"))?;
+        self.position += 4;
+        Ok(unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(xml: &str, format: &JsonFormat) -> String {
+        xml_to_json(xml.as_bytes(), &ParseConfig::default(), format)
+            .unwrap_or_else(|err| err.to_string())
+    }
+
+    #[test]
+    fn writes_json_like_python() {
+        let xml = "<r id=\"1\"><a>x \"q\" \u{e9}\u{1f600}</a><a/><b>\t</b></r>";
+        assert_eq!(
+            r#"{"r": {"@id": "1", "a": ["x \"q\" \u00e9\ud83d\ude00", null], "b": null}}"#,
+            json(xml, &JsonFormat::default())
+        );
+        let format = JsonFormat {
+            indent: Some("  ".to_owned()),
+            ensure_ascii: false,
+        };
+        assert_eq!(
+            "{\n  \"r\": {\n    \"a\": [\n      \"\u{e9}\",\n      null\n    ]\n  }\n}",
+            json("<r><a>\u{e9}</a><a></a></r>", &format)
+        );
+    }
+
+    #[test]
+    fn writes_listed_and_inferred_values() {
+        let config = ParseConfig::builder()
+            .force_list(crate::path_pattern::ListedElements::named(["r/a"]))
+            .infer_types(crate::config::InferTypes::ALL)
+            .build();
+        let parsed = xml_to_json(
+            b"<r><a>1e16</a><b>TRUE</b><c>+7</c><d>null</d></r>",
+            &config,
+            &JsonFormat::default(),
+        );
+        assert_eq!(
+            r#"{"r": {"a": [1e+16], "b": true, "c": 7, "d": null}}"#,
+            parsed.unwrap_or_else(|err| err.to_string())
+        );
+    }
+
+    #[test]
+    fn reads_json_into_xml() {
+        let config = UnparseConfig {
+            full_document: false,
+            ..UnparseConfig::default()
+        };
+        let json = r#" {"r": {"@id": 1.5e3, "a": [true, null, "\u00e9\ud83d\ude00\n"], "b": {}}} "#;
+        assert_eq!(
+            "<r id=\"1.5e3\"><a>true</a><a></a><a>\u{e9}\u{1f600}\n</a><b></b></r>",
+            json_to_xml(json, &config).unwrap_or_else(|err| err.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        for (json, message) in [
+            ("[1]", "must be an object: line 1 column 1 (char 0)"),
+            (
+                "{\"a\": 01}",
+                "Expecting ',' delimiter: line 1 column 8 (char 7)",
+            ),
+            (
+                "{\"a\":\n tru}",
+                "Expecting value: line 2 column 2 (char 7)",
+            ),
+            (
+                "{\"a\": \"x}",
+                "Unterminated string starting at: line 1 column 7 (char 6)",
+            ),
+            ("{\"a\": \"\\ud800\"}", "Lone surrogate"),
+            ("{\"a\": 1} x", "Extra data: line 1 column 10 (char 9)"),
+            ("{'a': 1}", "Expecting property name"),
+        ] {
+            let err = from_json(json).map_or_else(|err| err.to_string(), |_| String::new());
+            assert!(err.contains(message), "{json}: {err}");
+        }
+        let deep = format!("{{\"a\": {}{}}}", "[".repeat(600), "]".repeat(600));
+        assert!(from_json(&deep).is_err());
+    }
+
+    #[test]
+    fn writes_a_line_per_record() -> Result<(), Error> {
+        let xml = r#"<log><e id="1"><m>a</m></e><skip/><e>b</e><g><e/></g></log>"#;
+        let mut output = Vec::new();
+        let config = ParseConfig::default();
+        let records = xml_to_ndjson(xml.as_bytes(), "log/e", &config, true, &mut output)?;
+        assert_eq!(2, records);
+        assert_eq!(b"{\"@id\": \"1\", \"m\": \"a\"}\n\"b\"\n".to_vec(), output);
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Pure-Rust XML to value conversion, usable without Python.
+//!
+//! Build the crate with `default-features = false` to drop the `PyO3` bindings:
+//!
+//! ```
+//! use xmltodict_rs::xmltodict_core::{parse_str, unparse, ParseConfig, UnparseConfig, XmlValue};
+//!
+//! let xml = r#"<root id="1"><item>a</item><item>b</item></root>"#;
+//! let doc = parse_str(xml, &ParseConfig::default())?;
+//! let root = doc.get("root").and_then(XmlValue::as_map);
+//! assert_eq!(Some("1"), root.and_then(|r| r.get("@id")).and_then(XmlValue::as_str));
+//!
+//! let config = UnparseConfig { full_document: false, ..UnparseConfig::default() };
+//! assert_eq!(xml, unparse(&doc, &config)?);
+//! # Ok::<(), xmltodict_rs::xmltodict_core::Error>(())
+//! ```
+//!
+//! With the `python` feature, [`XmlValue`] and [`XmlMap`] convert to and from Python objects.
+
+mod build;
+mod diff;
+mod error;
+mod json;
+mod msgpack;
+mod parse;
+mod unparse;
+mod value;
+
+pub use crate::config::{
+    AttrPrefix, CdataKey, Charset, CommentKey, Decompress, DoctypeKey, DocumentLimits, InferTypes,
+    InvalidChars, NamespaceSeparator, NamespacesKey, OnDuplicate, OrderKey, ParseConfig,
+    ParseConfigBuilder, PiKey, ResolveEntities, SpanKey, Strip, UnparseConfig, DEFAULT_MAX_DEPTH,
+};
+pub use crate::dtd::EntityLimits;
+pub use crate::path_pattern::ListedElements;
+#[cfg(feature = "python")]
+pub(crate) use build::{Held, TreeBuilder, Values};
+pub use diff::{diff, DiffKind, Difference};
+pub use error::{Error, Limit, Position, SyntaxError};
+#[cfg(feature = "python")]
+pub(crate) use json::json_line;
+pub use json::{from_json, json_to_xml, to_json, xml_to_json, xml_to_ndjson, JsonFormat};
+pub use msgpack::{to_msgpack, xml_to_msgpack};
+pub use parse::{parse_bytes, parse_file, parse_reader, parse_str};
+#[cfg(feature = "python")]
+pub(crate) use parse::{parse_records, parse_transcoded};
+pub use unparse::unparse;
+#[cfg(feature = "python")]
+pub(crate) use unparse::{
+    check_encodable, escape_text, push_attribute, render_markup, Entries, Node, Tree, Writer,
+};
+pub use value::{XmlMap, XmlValue};
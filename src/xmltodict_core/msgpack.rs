@@ -0,0 +1,196 @@
+use super::{parse_bytes, Error, XmlMap, XmlValue};
+use crate::config::ParseConfig;
+
+/// Converts an XML document straight to `MessagePack`, as `msgpack.packb(parse(xml))` would.
+///
+/// # Errors
+///
+/// Same as [`parse_bytes`](super::parse_bytes) and [`to_msgpack`].
+pub fn xml_to_msgpack(xml: &[u8], config: &ParseConfig) -> Result<Vec<u8>, Error> {
+    to_msgpack(&parse_bytes(xml, config)?)
+}
+
+/// Writes a value tree as `MessagePack`: maps as maps, lists as arrays, text as strings,
+/// empty elements as nil and inferred values as booleans, integers and 64-bit floats, each
+/// in its smallest form.
+///
+/// # Errors
+///
+/// Returns [`Error::Value`] for a string, list or map longer than `MessagePack` can hold,
+/// 2³² − 1 bytes or entries, or an integer outside the 64 bits it has for one.
+pub fn to_msgpack(map: &XmlMap) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    write_map(&mut output, map)?;
+    Ok(output)
+}
+
+fn write_map(output: &mut Vec<u8>, map: &XmlMap) -> Result<(), Error> {
+    write_count(output, map.len(), 0x80, [0xde, 0xdf], "map")?;
+    for (key, value) in map.iter() {
+        write_str(output, key)?;
+        write_value(output, value)?;
+    }
+    Ok(())
+}
+
+fn write_value(output: &mut Vec<u8>, value: &XmlValue) -> Result<(), Error> {
+    match value {
+        XmlValue::None => output.push(0xc0),
+        XmlValue::Text(text) => write_str(output, text)?,
+        XmlValue::Bool(value) => output.push(if *value { 0xc3 } else { 0xc2 }),
+        XmlValue::Number(number) => write_number(output, number)?,
+        XmlValue::List(items) => {
+            write_count(output, items.len(), 0x90, [0xdc, 0xdd], "list")?;
+            for item in items {
+                write_value(output, item)?;
+            }
+        }
+        XmlValue::Map(map) => write_map(output, map)?,
+    }
+    Ok(())
+}
+
+/// Integers as in `msgpack.packb()`: unsigned forms for those that are not negative. Only
+/// floats are spelled with a point or an exponent.
+fn write_number(output: &mut Vec<u8>, number: &str) -> Result<(), Error> {
+    if number.contains(['.', 'e']) {
+        output.push(0xcb);
+        output.extend_from_slice(&number.parse().unwrap_or(f64::NAN).to_be_bytes());
+    } else if let Ok(value) = number.parse::<u64>() {
+        if let Ok(value) = u8::try_from(value) {
+            if value < 0x80 {
+                output.push(value);
+            } else {
+                output.extend_from_slice(&[0xcc, value]);
+            }
+        } else if let Ok(value) = u16::try_from(value) {
+            output.push(0xcd);
+            output.extend_from_slice(&value.to_be_bytes());
+        } else if let Ok(value) = u32::try_from(value) {
+            output.push(0xce);
+            output.extend_from_slice(&value.to_be_bytes());
+        } else {
+            output.push(0xcf);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+    } else if let Ok(value) = number.parse::<i64>() {
+        if let Ok(value) = i8::try_from(value) {
+            if value >= -32 {
+                output.extend_from_slice(&value.to_be_bytes());
+            } else {
+                output.push(0xd0);
+                output.extend_from_slice(&value.to_be_bytes());
+            }
+        } else if let Ok(value) = i16::try_from(value) {
+            output.push(0xd1);
+            output.extend_from_slice(&value.to_be_bytes());
+        } else if let Ok(value) = i32::try_from(value) {
+            output.push(0xd2);
+            output.extend_from_slice(&value.to_be_bytes());
+        } else {
+            output.push(0xd3);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+    } else {
+        return Err(Error::Value(format!(
+            "the integer {number} is too large for MessagePack"
+        )));
+    }
+    Ok(())
+}
+
+fn write_str(output: &mut Vec<u8>, text: &str) -> Result<(), Error> {
+    match u8::try_from(text.len()) {
+        Ok(len) if len < 32 => output.push(0xa0 | len),
+        Ok(len) => output.extend_from_slice(&[0xd9, len]),
+        Err(_) => write_long(output, text.len(), [0xda, 0xdb], "string")?,
+    }
+    output.extend_from_slice(text.as_bytes());
+    Ok(())
+}
+
+/// Writes the type of an array or map of `len` entries: `fixed` with the length in its low
+/// bits for up to 15, else one of `codes` followed by a 16- or 32-bit length.
+fn write_count(
+    output: &mut Vec<u8>,
+    len: usize,
+    fixed: u8,
+    codes: [u8; 2],
+    what: &str,
+) -> Result<(), Error> {
+    match u8::try_from(len) {
+        Ok(len) if len < 16 => {
+            output.push(fixed | len);
+            Ok(())
+        }
+        Ok(_) | Err(_) => write_long(output, len, codes, what),
+    }
+}
+
+fn write_long(
+    output: &mut Vec<u8>,
+    len: usize,
+    [code16, code32]: [u8; 2],
+    what: &str,
+) -> Result<(), Error> {
+    if let Ok(len) = u16::try_from(len) {
+        output.push(code16);
+        output.extend_from_slice(&len.to_be_bytes());
+    } else if let Ok(len) = u32::try_from(len) {
+        output.push(code32);
+        output.extend_from_slice(&len.to_be_bytes());
+    } else {
+        return Err(Error::Value(format!(
+            "a {what} of length {len} is too long for MessagePack"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msgpack(xml: &str) -> Vec<u8> {
+        xml_to_msgpack(xml.as_bytes(), &ParseConfig::default()).unwrap_or_default()
+    }
+
+    #[test]
+    fn writes_the_smallest_forms() {
+        assert_eq!(
+            b"\x81\xa1r\x82\xa3@id\xa11\xa1a\x92\xa1x\xc0".to_vec(),
+            msgpack(r#"<r id="1"><a>x</a><a/></r>"#)
+        );
+    }
+
+    #[test]
+    fn writes_inferred_values_natively() {
+        let config = ParseConfig::builder()
+            .infer_types(crate::config::InferTypes::ALL)
+            .build();
+        let packed = xml_to_msgpack(
+            b"<r><b>true</b><i>-200</i><u>200</u><f>1.5</f></r>",
+            &config,
+        )
+        .unwrap_or_default();
+        let expected =
+            b"\x81\xa1r\x84\xa1b\xc3\xa1i\xd1\xff\x38\xa1u\xcc\xc8\xa1f\xcb\x3f\xf8\0\0\0\0\0\0";
+        assert_eq!(expected.to_vec(), packed);
+        let config = ParseConfig::builder()
+            .infer_types(crate::config::InferTypes::ALL)
+            .build();
+        assert!(xml_to_msgpack(b"<r>123456789012345678901234</r>", &config).is_err());
+    }
+
+    #[test]
+    fn writes_long_strings_and_lists_with_their_length() {
+        let text = "x".repeat(300);
+        let packed = msgpack(&format!("<r>{text}</r>"));
+        assert_eq!(Some(&[0xda, 0x01, 0x2c][..]), packed.get(3..6));
+        let packed = msgpack(&format!("<r>{}</r>", "<i/>".repeat(20)));
+        assert_eq!(
+            Some(&[0xa1, b'i', 0xdc, 0x00, 0x14, 0xc0][..]),
+            packed.get(4..10)
+        );
+    }
+}
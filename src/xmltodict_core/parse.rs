@@ -0,0 +1,414 @@
+use super::build::{Plain, TreeBuilder};
+use super::{Error, SyntaxError, XmlMap, XmlValue};
+use crate::config::ParseConfig;
+use crate::encoding::transcode;
+use crate::events::{EventReader, XmlEvent};
+use crate::path_pattern::PathPattern;
+use crate::reader::{compression, decompressing, map_file, DecodingRead, Decompressed};
+use encoding_rs::Encoding;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Parses an XML document held in a string.
+///
+/// # Errors
+///
+/// Returns [`Error::Syntax`] for malformed documents and [`Error::EntitiesForbidden`]
+/// when a DTD is rejected by the configuration.
+pub fn parse_str(xml: &str, config: &ParseConfig) -> Result<XmlMap, Error> {
+    parse_buffered(xml.as_bytes(), config)
+}
+
+/// Parses an encoded XML document, detecting its encoding from the BOM or declaration.
+/// Compressed documents are decompressed as `config.decompress` says.
+///
+/// # Errors
+///
+/// Same as [`parse_str`], plus [`Error::Syntax`] for undecodable input and [`Error::Io`]
+/// for corrupt compressed input.
+pub fn parse_bytes(xml: &[u8], config: &ParseConfig) -> Result<XmlMap, Error> {
+    parse_transcoded(xml, None, config)
+}
+
+/// Parses an encoded XML document, in `encoding` when given instead of the detected one.
+pub(crate) fn parse_transcoded(
+    xml: &[u8],
+    encoding: Option<&'static Encoding>,
+    config: &ParseConfig,
+) -> Result<XmlMap, Error> {
+    if let Some(format) = compression(config.decompress, xml) {
+        let raw = Decompressed::new(xml, Some(format)).map_err(Error::Io)?;
+        let reader = match encoding {
+            Some(enc) => DecodingRead::new(raw, enc),
+            None => DecodingRead::sniffing(raw),
+        };
+        return parse_buffered(BufReader::new(reader), config);
+    }
+    let utf8 = transcode(xml, encoding)
+        .map_err(|msg| SyntaxError::new(SyntaxError::INCORRECT_ENCODING, msg))?;
+    parse_buffered(utf8.as_ref(), config)
+}
+
+/// Parses an XML file, memory-mapping it rather than reading it into a buffer.
+///
+/// # Errors
+///
+/// Same as [`parse_bytes`], plus [`Error::Io`] when the file cannot be opened or mapped.
+pub fn parse_file(path: impl AsRef<Path>, config: &ParseConfig) -> Result<XmlMap, Error> {
+    let mapped = map_file(path.as_ref()).map_err(Error::Io)?;
+    parse_bytes(&mapped, config)
+}
+
+/// Parses an XML document from a reader, decompressing and transcoding it to UTF-8 as it
+/// is read.
+///
+/// # Errors
+///
+/// Same as [`parse_bytes`], plus [`Error::Io`] when reading fails.
+pub fn parse_reader<R: Read>(reader: R, config: &ParseConfig) -> Result<XmlMap, Error> {
+    let reader = decompressing(reader, config.decompress);
+    parse_buffered(BufReader::new(DecodingRead::sniffing(reader)), config)
+}
+
+fn parse_buffered<R: BufRead>(reader: R, config: &ParseConfig) -> Result<XmlMap, Error> {
+    let mut builder = TreeBuilder::default();
+    let mut events = EventReader::new(reader, config);
+    while let Some(event) = events.next_event()? {
+        build(&mut builder, config, event)?;
+    }
+    builder.finish(&mut Plain(config))
+}
+
+/// Hands one reader event to `builder`.
+fn build(
+    builder: &mut TreeBuilder<XmlMap>,
+    config: &ParseConfig,
+    event: XmlEvent,
+) -> Result<(), Error> {
+    let values = &mut Plain(config);
+    match event {
+        XmlEvent::Start { name, attributes } => builder.start_element(values, &name, attributes)?,
+        XmlEvent::End(_) => builder.end_element(values)?,
+        XmlEvent::Text(text) => builder.characters(config, text),
+        XmlEvent::Whitespace(text) => builder.whitespace(config, text),
+        XmlEvent::DocType(doctype) => builder.doctype(config, &doctype),
+        XmlEvent::Comment(comment) => builder.add_entry(values, &config.comment_key, &comment)?,
+        XmlEvent::ProcessingInstruction(pi) => builder.add_entry(values, &config.pi_key, &pi)?,
+    }
+    Ok(())
+}
+
+/// Parses each element on `record` into a value as its end tag is read, handing it to
+/// `on_record` instead of building the document. Records inside records are
+/// part of the outer one, and namespace prefixes are resolved within the record only.
+pub(crate) fn parse_records<R, F>(
+    reader: R,
+    record: &PathPattern,
+    config: &ParseConfig,
+    mut on_record: F,
+) -> Result<(), Error>
+where
+    R: BufRead,
+    F: FnMut(XmlValue) -> Result<(), Error>,
+{
+    let mut events = EventReader::new(reader, config);
+    let mut path: Vec<String> = Vec::new();
+    // The record being read, with the depth of its element.
+    let mut current: Option<(usize, TreeBuilder<XmlMap>)> = None;
+    while let Some(event) = events.next_event()? {
+        let depth = path.len();
+        match &event {
+            XmlEvent::Start { name, .. } => {
+                path.push(name.clone());
+                if current.is_none() {
+                    let names: Vec<&str> = path.iter().map(String::as_str).collect();
+                    if record.matches(&names) {
+                        current = Some((path.len(), TreeBuilder::default()));
+                    }
+                }
+            }
+            XmlEvent::End(_) => {
+                path.pop();
+            }
+            // A DOCTYPE declaration is not part of any record.
+            XmlEvent::DocType(_) => continue,
+            XmlEvent::Text(_)
+            | XmlEvent::Whitespace(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::ProcessingInstruction(_) => {}
+        }
+        let Some((record_depth, builder)) = current.as_mut() else {
+            continue;
+        };
+        let closes = matches!(event, XmlEvent::End(_)) && depth == *record_depth;
+        build(builder, config, event)?;
+        if closes {
+            if let Some((_, mut builder)) = current.take() {
+                for (_, value) in builder.finish(&mut Plain(config))? {
+                    on_record(value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Decompress, OnDuplicate, DEFAULT_MAX_DEPTH};
+    use crate::xmltodict_core::Limit;
+
+    fn parse(xml: &str) -> XmlMap {
+        parse_str(xml, &ParseConfig::default()).unwrap_or_default()
+    }
+
+    #[test]
+    fn repeated_elements_become_lists() {
+        let doc = parse("<r><a>1</a><b/><a>2</a></r>");
+        let root = doc.get("r").and_then(XmlValue::as_map);
+        let items = root.and_then(|r| r.get("a")).and_then(XmlValue::as_list);
+        assert_eq!(Some(&["1".into(), "2".into()][..]), items);
+        assert_eq!(Some(&XmlValue::None), root.and_then(|r| r.get("b")));
+    }
+
+    #[test]
+    fn repeated_elements_follow_on_duplicate() {
+        let xml = "<r><a>1</a><b/><a>2</a></r>";
+        let first = |on_duplicate| {
+            let config = ParseConfig::builder().on_duplicate(on_duplicate).build();
+            let doc = parse_str(xml, &config)?;
+            let root = doc.get("r").and_then(XmlValue::as_map);
+            Ok::<_, Error>(root.and_then(|r| r.get("a")).cloned())
+        };
+        assert_eq!(Some(Some("1".into())), first(OnDuplicate::First).ok());
+        assert_eq!(Some(Some("2".into())), first(OnDuplicate::Last).ok());
+        let err = first(OnDuplicate::Error).err().map(|err| err.to_string());
+        assert_eq!(Some("\"a\" is repeated in r".to_owned()), err);
+    }
+
+    #[test]
+    fn sibling_positions_and_order_are_kept() {
+        let config = ParseConfig {
+            index_key: Some("#index".to_owned()),
+            preserve_sibling_order: true,
+            ..ParseConfig::default()
+        };
+        let doc = parse_str("<r><a/><b/><a>x</a></r>", &config).unwrap_or_default();
+        let root = doc.get("r").and_then(XmlValue::as_map);
+        let index = |value: &XmlValue| value.as_map().and_then(|a| a.get("#index")).cloned();
+        let items = root.and_then(|r| r.get("a")).and_then(XmlValue::as_list);
+        let indexes: Vec<_> = items.into_iter().flatten().filter_map(index).collect();
+        assert_eq!(vec![XmlValue::from("0"), "2".into()], indexes);
+        let order: XmlValue = vec!["a".into(), "b".into(), "a".into()].into();
+        assert_eq!(Some(&order), root.and_then(|r| r.get("#order")));
+    }
+
+    #[test]
+    fn text_nodes_are_joined() {
+        let doc = parse("<r><a>one</a><b>x<![CDATA[<y>]]>z</b><c> </c></r>");
+        let root = doc.get("r").and_then(XmlValue::as_map);
+        let text = |key: &str| root.and_then(|r| r.get(key)).and_then(XmlValue::as_str);
+        assert_eq!(Some("one"), text("a"));
+        assert_eq!(Some("x<y>z"), text("b"));
+        assert_eq!(None, text("c"));
+    }
+
+    #[test]
+    fn single_text_node_is_moved_not_copied() {
+        let text = "one".to_owned();
+        let data = text.as_ptr();
+        let joined = ParseConfig::default().join_text(vec![text]);
+        assert_eq!(Some(data), joined.as_deref().map(str::as_ptr));
+    }
+
+    #[test]
+    fn attributes_and_text_share_a_map() {
+        let doc = parse(r#"<r id="7">x &amp; y</r>"#);
+        let expected: XmlMap = [
+            ("@id".to_owned(), "7".into()),
+            ("#text".to_owned(), "x & y".into()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(Some(&XmlValue::Map(expected)), doc.get("r"));
+    }
+
+    #[test]
+    fn namespaces_are_expanded() {
+        let config = ParseConfig::builder().process_namespaces(true).build();
+        let doc = parse_str(r#"<a:r xmlns:a="urn:a"><a:x>1</a:x></a:r>"#, &config);
+        let root = doc.ok().and_then(|d| d.get("urn:a:r").cloned());
+        let child = root
+            .as_ref()
+            .and_then(XmlValue::as_map)
+            .and_then(|r| r.get("urn:a:x"));
+        assert_eq!(Some(&"1".into()), child);
+    }
+
+    #[test]
+    fn qname_attribute_values_are_resolved() {
+        let config = ParseConfig::builder()
+            .process_namespaces(true)
+            .qname_attributes(["xsi:type"])
+            .build();
+        let xml = r#"<r xmlns:xsi="urn:xsi" xmlns:t="urn:t" xsi:type=" t:Big " k="t:x"/>"#;
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        let root = doc.get("r").and_then(XmlValue::as_map);
+        let value = |key: &str| root.and_then(|r| r.get(key)).and_then(XmlValue::as_str);
+        assert_eq!(Some("urn:t:Big"), value("@urn:xsi:type"));
+        assert_eq!(Some("t:x"), value("@k"));
+    }
+
+    #[test]
+    fn namespace_prefixes_are_stripped() {
+        let xml = r#"<s:Envelope xmlns:s="urn:s" s:v="1"><s:Body>x</s:Body></s:Envelope>"#;
+        let config = ParseConfig::builder()
+            .strip_namespace_prefixes(true)
+            .build();
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        let envelope = doc.get("Envelope").and_then(XmlValue::as_map);
+        let keys: Vec<&str> = envelope
+            .into_iter()
+            .flat_map(XmlMap::iter)
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(vec!["@xmlns:s", "@v", "Body"], keys);
+        let config = ParseConfig {
+            process_namespaces: true,
+            ..config
+        };
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        let envelope = doc.get("Envelope").and_then(XmlValue::as_map);
+        let keys: Vec<&str> = envelope
+            .into_iter()
+            .flat_map(XmlMap::iter)
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(vec!["@v", "Body"], keys);
+    }
+
+    #[test]
+    fn namespace_declarations_are_collected() {
+        let config = ParseConfig::builder().collect_namespaces(true).build();
+        let xml = r#"<r xmlns="urn:d"><a:x xmlns:a="urn:a"/><a:y xmlns:a="urn:b"/></r>"#;
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        let expected = XmlMap::from_iter([
+            (String::new(), "urn:d".into()),
+            ("a".to_owned(), "urn:a".into()),
+        ]);
+        assert_eq!(Some(&XmlValue::Map(expected)), doc.get("#namespaces"));
+        assert_eq!(
+            Some(&XmlValue::from("urn:d")),
+            doc.get("r")
+                .and_then(XmlValue::as_map)
+                .and_then(|r| r.get("@xmlns"))
+        );
+    }
+
+    #[test]
+    fn bytes_are_transcoded() {
+        let xml = b"<?xml version=\"1.0\" encoding=\"iso-8859-1\"?><r>\xe9</r>";
+        let doc = parse_bytes(xml, &ParseConfig::default()).unwrap_or_default();
+        assert_eq!(Some("\u{e9}"), doc.get("r").and_then(XmlValue::as_str));
+    }
+
+    #[test]
+    fn malformed_documents_are_errors() {
+        for xml in ["", "<r>", "<r></x>"] {
+            assert!(matches!(
+                parse_str(xml, &ParseConfig::default()),
+                Err(Error::Syntax(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn deep_documents_are_refused_by_default() {
+        let depth = 300_000;
+        let xml = format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(matches!(
+            parse_str(&xml, &ParseConfig::default()),
+            Err(Error::Syntax(SyntaxError {
+                limit: Some(Limit::Depth),
+                ..
+            }))
+        ));
+        let depth = DEFAULT_MAX_DEPTH;
+        let xml = format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(parse_str(&xml, &ParseConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn mixed_content_keeps_node_order() {
+        let config = ParseConfig::builder().preserve_mixed_content(true).build();
+        let doc = parse_str("<r><p>a <b>1</b> c</p><p>d <b>2</b></p></r>", &config);
+        let root = doc.ok().and_then(|d| d.get("r").cloned());
+        let paragraphs = root
+            .as_ref()
+            .and_then(XmlValue::as_map)
+            .and_then(|r| r.get("p"));
+        let first: XmlValue = vec![
+            "a ".into(),
+            XmlMap::from_iter([("b".to_owned(), "1".into())]).into(),
+            " c".into(),
+        ]
+        .into();
+        let second: XmlValue = vec![
+            "d ".into(),
+            XmlMap::from_iter([("b".to_owned(), "2".into())]).into(),
+        ]
+        .into();
+        assert_eq!(Some(&XmlValue::List(vec![first, second])), paragraphs);
+    }
+
+    #[test]
+    fn forbidden_dtd_is_reported() {
+        let config = ParseConfig::builder().forbid_dtd(true).build();
+        assert!(matches!(
+            parse_str("<!DOCTYPE r><r/>", &config),
+            Err(Error::EntitiesForbidden(_))
+        ));
+    }
+
+    #[test]
+    fn files_are_parsed_and_missing_files_reported() {
+        let path = std::env::temp_dir().join(format!("xmltodict-rs-{}.xml", std::process::id()));
+        let written = std::fs::write(&path, "<r><a>1</a></r>");
+        let parsed = parse_file(&path, &ParseConfig::default());
+        let removed = std::fs::remove_file(&path);
+        assert!(written.is_ok() && removed.is_ok());
+        assert_eq!(parse("<r><a>1</a></r>"), parsed.unwrap_or_default());
+        assert!(matches!(
+            parse_file(&path, &ParseConfig::default()),
+            Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn compressed_input_is_detected() -> Result<(), Error> {
+        use flate2::write::{GzEncoder, ZlibEncoder};
+        use flate2::Compression;
+        use std::io::Write;
+
+        let xml = b"<a>1</a>";
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(xml).map_err(Error::Io)?;
+        let gzip = gzip.finish().map_err(Error::Io)?;
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(xml).map_err(Error::Io)?;
+        let zlib = zlib.finish().map_err(Error::Io)?;
+        let zstd = b"\x28\xb5\x2f\xfd\x00\x58\x41\x00\x00<a>1</a>";
+        let expected = parse("<a>1</a>");
+        let config = ParseConfig::default();
+        for input in [&gzip[..], &zlib[..], &zstd[..]] {
+            assert_eq!(expected, parse_bytes(input, &config)?);
+            assert_eq!(expected, parse_reader(input, &config)?);
+        }
+        let config = ParseConfig::builder().decompress(Decompress::Never).build();
+        assert!(parse_bytes(&gzip, &config).is_err());
+        let config = ParseConfig::builder().decompress(Decompress::Gzip).build();
+        assert!(matches!(parse_bytes(xml, &config), Err(Error::Io(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,1213 @@
+use super::{Error, XmlMap, XmlValue};
+#[cfg(doc)]
+use crate::config::InvalidChars;
+use crate::config::UnparseConfig;
+use crate::escape::{
+    cdata_section, escape_canonical_attr, escape_canonical_text, escape_unwritable, escape_xml,
+    escape_xml_attr, Markup,
+};
+use crate::names::{
+    canonical_attribute_order, declaration_name, dedupe_attributes, is_xml_name,
+    namespace_declarations, prefixed_name,
+};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Serializes a value tree back to XML, following the same rules as Python's `unparse()`.
+///
+/// # Errors
+///
+/// Returns [`Error::Value`] when `full_document` is set and `input` does not hold exactly
+/// one root, when an attribute or text value is not text, or when text holds a character
+/// XML 1.0 does not allow and `invalid_chars` is
+/// [`InvalidChars::Raise`](super::InvalidChars::Raise).
+pub fn unparse(input: &XmlMap, config: &UnparseConfig) -> Result<String, Error> {
+    let roots = input.len()
+        - usize::from(input.get(&config.doctype_key).is_some())
+        - usize::from(input.get(&config.comment_key).is_some())
+        - usize::from(input.get(&config.pi_key).is_some())
+        - usize::from(input.get(&config.order_key).is_some());
+    if config.full_document && roots != 1 {
+        return Err(Error::Value(
+            "Document must have exactly one root".to_owned(),
+        ));
+    }
+
+    let canonical_config;
+    let config = if config.canonical {
+        canonical_config = config.clone().canonical_form();
+        &canonical_config
+    } else {
+        config
+    };
+    let mut output = String::new();
+    let entries = input
+        .iter()
+        .map(|(key, value)| (key.to_owned(), Cow::Borrowed(value)))
+        .collect();
+    Writer::new(config, &mut output).write_document(&mut Plain(PhantomData), entries)?;
+    Ok(output)
+}
+
+/// Attributes, text and children of an element, by key.
+pub(crate) type Entries<V> = Vec<(String, V)>;
+
+/// What an element value is written as.
+pub(crate) enum Node<V, I> {
+    /// An empty element.
+    Empty,
+    Text(String),
+    Map(Entries<V>),
+    /// One element for each item.
+    List(I),
+}
+
+/// The values a [`Writer`] writes, and the options applied to them that only some inputs
+/// have: [`XmlValue`] trees, or the Python objects `unparse()` takes.
+pub(crate) trait Tree {
+    type Value: Clone;
+    type Items: Iterator<Item = Result<Self::Value, Self::Error>>;
+    type Error;
+
+    /// The key and value an element is written with, or `None` to leave it out.
+    fn preprocess(
+        &mut self,
+        tag: &str,
+        value: Self::Value,
+    ) -> Result<Option<(String, Self::Value)>, Self::Error> {
+        Ok(Some((tag.to_owned(), value)))
+    }
+
+    /// The value an element is written as, for values that stand for others.
+    fn convert(&mut self, value: Self::Value) -> Result<Self::Value, Self::Error> {
+        Ok(value)
+    }
+
+    fn node(&mut self, value: Self::Value) -> Result<Node<Self::Value, Self::Items>, Self::Error>;
+
+    /// The items of a value written as repeated elements, or `None` for other values.
+    fn items(&mut self, value: &Self::Value) -> Result<Option<Self::Items>, Self::Error>;
+
+    /// The entries of a map, or `None` for other values.
+    fn entries(&mut self, value: &Self::Value)
+        -> Result<Option<Entries<Self::Value>>, Self::Error>;
+
+    /// The entries of an `attr_dict_key` value, which must be a map.
+    fn attribute_map(
+        &mut self,
+        key: &str,
+        value: &Self::Value,
+    ) -> Result<Entries<Self::Value>, Self::Error> {
+        match self.entries(value)? {
+            Some(entries) => Ok(entries),
+            None => Err(self.error(Error::Value(format!("value of {key} must be a map")))),
+        }
+    }
+
+    fn is_none(&self, value: &Self::Value) -> bool;
+
+    /// The text of an attribute, text entry, comment or processing instruction. `key`
+    /// names the entry in errors.
+    fn text(&mut self, key: &str, value: &Self::Value) -> Result<String, Self::Error>;
+
+    /// Whether a list of both text and maps (and no nested lists) holds content in order,
+    /// with `preserve_mixed_content`.
+    fn is_mixed(&self, value: &Self::Value) -> bool;
+
+    /// An item of repeated elements that repeats itself, as `{key: item}` for `expand_iter`.
+    fn expand(&mut self, key: &str, item: Self::Value) -> Result<Self::Value, Self::Error>;
+
+    fn error(&self, err: Error) -> Self::Error;
+
+    /// Warns about output that differs from the input, such as attributes left out.
+    fn warn(&mut self, _message: &str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called after each element is written, to hand on output written so far.
+    fn written(&mut self, _output: &mut String) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Writes XML for the values of a [`Tree`]: the rules every writer follows, into `output`.
+pub(crate) struct Writer<'a> {
+    pub config: &'a UnparseConfig,
+    pub output: &'a mut String,
+    indent_level: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(config: &'a UnparseConfig, output: &'a mut String) -> Self {
+        Self {
+            config,
+            output,
+            indent_level: 0,
+        }
+    }
+
+    /// Writes the XML declaration, if the configuration asks for one.
+    pub fn write_header(&mut self) {
+        if self.config.writes_declaration() {
+            self.output.push_str(&self.config.declaration());
+            self.output.push_str(&self.config.newl);
+        }
+    }
+
+    /// Writes the XML and DOCTYPE declarations followed by every root element of `entries`,
+    /// in the order listed under `order_key` if there is one.
+    pub fn write_document<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        entries: Vec<(String, T::Value)>,
+    ) -> Result<(), T::Error> {
+        self.write_header();
+        let config = self.config;
+        let doctype = match &config.doctype {
+            Some(doctype) => Some(doctype.clone()),
+            None => match entries.iter().find(|(key, _)| *key == *config.doctype_key) {
+                Some((key, value)) if !tree.is_none(value) => Some(tree.text(key, value)?),
+                Some(_) | None => None,
+            },
+        };
+        // Canonical XML has no DOCTYPE declaration.
+        if let Some(doctype) = doctype.filter(|doctype| !doctype.is_empty() && !config.canonical) {
+            check_encodable(config, "DOCTYPE", &doctype).map_err(|err| tree.error(err))?;
+            self.output.push_str("<!DOCTYPE ");
+            self.output.push_str(&doctype);
+            self.output.push('>');
+            self.output.push_str(&config.newl);
+        }
+
+        let mut order = None;
+        let mut nodes = Vec::new();
+        for (key, value) in entries {
+            if key == *config.order_key {
+                order = Some(value);
+            } else if key != *config.doctype_key {
+                nodes.push((key, value));
+            }
+        }
+        // Parsing records the order of the document when comments or processing
+        // instructions on both sides of the root element share a key.
+        if let Some(order) = order {
+            nodes = self.in_order(tree, "the document", &order, nodes)?;
+        }
+        for (i, (tag, value)) in nodes.into_iter().enumerate() {
+            self.write_element(tree, &tag, value, i > 0)?;
+            tree.written(self.output)?;
+        }
+        Ok(())
+    }
+
+    fn write_indent(&mut self) {
+        if self.config.pretty {
+            for _ in 0..self.indent_level {
+                self.output.push_str(&self.config.indent);
+            }
+        }
+    }
+
+    /// Starts a new line for a node that does not come first, when the output is indented.
+    fn separate(&mut self, needs_newline: bool) {
+        if needs_newline && self.config.separates_nodes(self.indent_level) {
+            self.output.push_str(&self.config.newl);
+            self.write_indent();
+        }
+    }
+
+    /// Root elements carry the `xmlns` declarations for the namespace map.
+    fn declares_namespaces(&self) -> bool {
+        self.indent_level == 0 && !self.config.namespaces.is_empty()
+    }
+
+    fn is_mixed<T: Tree>(&self, tree: &T, value: &T::Value) -> bool {
+        self.config.preserve_mixed_content && tree.is_mixed(value)
+    }
+
+    fn push_empty_tag(&mut self, tag: &str) {
+        self.output.push('<');
+        self.output.push_str(tag);
+        self.push_empty_end(tag);
+    }
+
+    /// Closes a start tag for an element without content.
+    fn push_empty_end(&mut self, tag: &str) {
+        if self.config.short_empty_elements {
+            self.output.push_str("/>");
+        } else {
+            self.output.push_str("></");
+            self.output.push_str(tag);
+            self.output.push('>');
+        }
+    }
+
+    fn push_end_tag(&mut self, tag: &str) {
+        self.output.push_str("</");
+        self.output.push_str(tag);
+        self.output.push('>');
+    }
+
+    pub fn write_element<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        tag: &str,
+        value: T::Value,
+        needs_newline: bool,
+    ) -> Result<(), T::Error> {
+        if tag == &*self.config.comment_key {
+            return self.write_markup(tree, Markup::Comment, tag, &value, needs_newline);
+        }
+        if tag == &*self.config.pi_key {
+            return self.write_markup(
+                tree,
+                Markup::ProcessingInstruction,
+                tag,
+                &value,
+                needs_newline,
+            );
+        }
+
+        let tag = written_name(self.config, "element", tag).map_err(|err| tree.error(err))?;
+        let Some((tag, value)) = tree.preprocess(&tag, value)? else {
+            return Ok(());
+        };
+        check_name(self.config, "element", &tag).map_err(|err| tree.error(err))?;
+        let value = tree.convert(value)?;
+
+        if self.is_mixed(tree, &value) {
+            self.separate(needs_newline);
+            return self.write_mixed_element(tree, &tag, &value);
+        }
+
+        let node = tree.node(value)?;
+        // Each item of a list starts its own line.
+        if let Node::List(items) = node {
+            for (i, item) in items.enumerate() {
+                let item = self.expand_iter(tree, item?)?;
+                self.write_element(tree, &tag, item, i > 0 || needs_newline)?;
+                tree.written(self.output)?;
+            }
+            return Ok(());
+        }
+
+        self.separate(needs_newline);
+        match node {
+            Node::Map(entries) => self.write_map_element(tree, &tag, entries),
+            Node::List(_) => Ok(()),
+            // A root element needs a start tag with attributes for its declarations.
+            Node::Empty | Node::Text(_) if self.declares_namespaces() => {
+                let text = match node {
+                    Node::Text(text) if !text.is_empty() => Some(text),
+                    Node::Empty | Node::Text(_) | Node::Map(_) | Node::List(_) => None,
+                };
+                self.write_parts(tree, &tag, &[], text, Vec::new())
+            }
+            Node::Empty => {
+                self.push_empty_tag(&tag);
+                Ok(())
+            }
+            Node::Text(text) if text.is_empty() => {
+                self.push_empty_tag(&tag);
+                Ok(())
+            }
+            Node::Text(text) => {
+                let text = escape_text(self.config, &tag, &text).map_err(|err| tree.error(err))?;
+                self.output.push('<');
+                self.output.push_str(&tag);
+                self.output.push('>');
+                self.output.push_str(&text);
+                self.push_end_tag(&tag);
+                Ok(())
+            }
+        }
+    }
+
+    /// With `expand_iter`, an item of repeated elements that repeats itself becomes the
+    /// content of its element.
+    fn expand_iter<T: Tree>(&self, tree: &mut T, item: T::Value) -> Result<T::Value, T::Error> {
+        match &self.config.expand_iter {
+            Some(key) => tree.expand(key, item),
+            None => Ok(item),
+        }
+    }
+
+    /// Writes a comment or processing instruction for a text value, or for each text of a list.
+    fn write_markup<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        markup: Markup,
+        key: &str,
+        value: &T::Value,
+        needs_newline: bool,
+    ) -> Result<(), T::Error> {
+        let items = match tree.items(value)? {
+            Some(items) => items.collect::<Result<Vec<_>, _>>()?,
+            None => vec![value.clone()],
+        };
+        for (i, item) in items.iter().enumerate() {
+            self.separate(i > 0 || needs_newline);
+            let text = if tree.is_none(item) {
+                String::new()
+            } else {
+                tree.text(key, item)?
+            };
+            let markup =
+                render_markup(self.config, markup, &text).map_err(|err| tree.error(err))?;
+            self.output.push_str(&markup);
+        }
+        Ok(())
+    }
+
+    /// Writes text and `{name: value}` maps in order as the content of one element.
+    /// Attribute keys in the maps go on the start tag.
+    fn write_mixed_element<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        tag: &str,
+        nodes: &T::Value,
+    ) -> Result<(), T::Error> {
+        let mut attributes = Vec::new();
+        for node in tree.items(nodes)?.into_iter().flatten() {
+            for (key, value) in tree.entries(&node?)?.into_iter().flatten() {
+                self.collect_attributes(tree, &key, &value, &mut attributes)?;
+            }
+        }
+        self.push_start_tag(tree, tag, &attributes)?;
+        self.output.push('>');
+
+        self.indent_level += 1;
+        for node in tree.items(nodes)?.into_iter().flatten() {
+            let node = node?;
+            if let Some(entries) = tree.entries(&node)? {
+                for (key, value) in entries {
+                    if !self.is_attribute_key(&key) {
+                        self.write_element(tree, &key, value, false)?;
+                    }
+                }
+            } else {
+                let text = tree.text(tag, &node)?;
+                let text = escape_text(self.config, tag, &text).map_err(|err| tree.error(err))?;
+                self.output.push_str(&text);
+            }
+            tree.written(self.output)?;
+        }
+        self.indent_level -= 1;
+
+        self.push_end_tag(tag);
+        Ok(())
+    }
+
+    /// Whether a map key holds attributes rather than content.
+    fn is_attribute_key(&self, key: &str) -> bool {
+        key.starts_with(self.config.attr_prefix.as_ref())
+            || self.config.attr_dict_key.as_deref() == Some(key)
+    }
+
+    /// Adds the attributes a map entry holds: its own value for an `attr_prefix` key, or
+    /// every entry of the `attr_dict_key` map. Returns whether the entry held attributes.
+    fn collect_attributes<T: Tree>(
+        &self,
+        tree: &mut T,
+        key: &str,
+        value: &T::Value,
+        attributes: &mut Vec<(String, String)>,
+    ) -> Result<bool, T::Error> {
+        if self.config.attr_dict_key.as_deref() == Some(key) {
+            if tree.is_none(value) {
+                return Ok(true);
+            }
+            for (name, attr_value) in tree.attribute_map(key, value)? {
+                self.add_attribute(tree, &name, &name, &attr_value, attributes)?;
+            }
+            return Ok(true);
+        }
+        let Some(attr_name) = key.strip_prefix(self.config.attr_prefix.as_ref()) else {
+            return Ok(false);
+        };
+        self.add_attribute(tree, key, attr_name, value, attributes)?;
+        Ok(true)
+    }
+
+    /// Adds one attribute, or a declaration for each binding of an `xmlns` map, as parsing
+    /// with unmapped `namespaces` reports them. `key` names the entry in errors.
+    fn add_attribute<T: Tree>(
+        &self,
+        tree: &mut T,
+        key: &str,
+        name: &str,
+        value: &T::Value,
+        attributes: &mut Vec<(String, String)>,
+    ) -> Result<(), T::Error> {
+        if name == "xmlns" {
+            if let Some(bindings) = tree.entries(value)? {
+                for (prefix, uri) in bindings {
+                    attributes.push((declaration_name(&prefix), tree.text(key, &uri)?));
+                }
+                return Ok(());
+            }
+        }
+        let name = written_name(self.config, "attribute", name)
+            .map_err(|err| tree.error(err))?
+            .into_owned();
+        attributes.push((name, tree.text(key, value)?));
+        Ok(())
+    }
+
+    /// Writes `<tag` with the root namespace declarations and `attributes`, leaving it open.
+    fn push_start_tag<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        tag: &str,
+        attributes: &[(String, String)],
+    ) -> Result<(), T::Error> {
+        self.output.push('<');
+        self.output.push_str(tag);
+        let declarations: Vec<(String, &str)> = if self.declares_namespaces() {
+            namespace_declarations(self.config)
+                .filter(|(name, _)| !attributes.iter().any(|(declared, _)| declared == name))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut all: Vec<(&str, &str)> = declarations
+            .iter()
+            .map(|(name, uri)| (name.as_str(), *uri))
+            .chain(
+                attributes
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            )
+            .collect();
+        let repeated =
+            dedupe_attributes(&mut all, self.config.duplicate_attributes).map_err(|name| {
+                tree.error(Error::Value(format!(
+                    "duplicate attribute {name:?} on <{tag}>"
+                )))
+            })?;
+        for name in repeated {
+            tree.warn(&format!(
+                "duplicate attribute {name:?} on <{tag}>; keeping the last value"
+            ))?;
+        }
+        if self.config.canonical {
+            all.sort_by_key(|&(name, _)| canonical_attribute_order(name));
+        }
+        for (attr_name, attr_value) in all {
+            push_attribute(self.output, self.config, attr_name, attr_value)
+                .map_err(|err| tree.error(err))?;
+        }
+        Ok(())
+    }
+
+    fn write_map_element<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        tag: &str,
+        entries: Vec<(String, T::Value)>,
+    ) -> Result<(), T::Error> {
+        let mut attributes = Vec::new();
+        let mut text = None;
+        let mut children = Vec::new();
+        let mut order = None;
+
+        for (key, value) in entries {
+            if self.collect_attributes(tree, &key, &value, &mut attributes)? {
+                continue;
+            }
+            if key == *self.config.cdata_key {
+                text = Some(tree.text(&key, &value)?);
+            } else if self.config.preserve_sibling_order && key == *self.config.order_key {
+                order = Some(value);
+            } else if self.config.index_key.as_ref() != Some(&key) {
+                children.push((key, value));
+            }
+        }
+        if let Some(order) = order {
+            children = self.in_order(tree, &format!("<{tag}>"), &order, children)?;
+        } else if let Some(index_key) = &self.config.index_key {
+            children = self.by_index(tree, index_key, children)?;
+        }
+        self.write_parts(tree, tag, &attributes, text, children)
+    }
+
+    /// Writes an element from its attributes, text and children.
+    fn write_parts<T: Tree>(
+        &mut self,
+        tree: &mut T,
+        tag: &str,
+        attributes: &[(String, String)],
+        text: Option<String>,
+        children: Vec<(String, T::Value)>,
+    ) -> Result<(), T::Error> {
+        self.push_start_tag(tree, tag, attributes)?;
+        if children.is_empty() && text.is_none() {
+            self.push_empty_end(tag);
+            return Ok(());
+        }
+
+        self.output.push('>');
+        if let Some(text) = text {
+            let text = escape_text(self.config, tag, &text).map_err(|err| tree.error(err))?;
+            self.output.push_str(&text);
+        }
+        if !children.is_empty() {
+            self.indent_level += 1;
+            for (i, (child_tag, child_value)) in children.into_iter().enumerate() {
+                self.write_element(tree, &child_tag, child_value, i > 0 || self.config.pretty)?;
+                tree.written(self.output)?;
+            }
+            self.indent_level -= 1;
+
+            if self.config.indents(self.indent_level + 1) {
+                self.output.push_str(&self.config.newl);
+                self.write_indent();
+            }
+        }
+        self.push_end_tag(tag);
+        Ok(())
+    }
+
+    /// Children as the keys listed in `order` give them, one at a time: each listing of a
+    /// key takes the next item of a repeated entry, or the entry itself the first time. An
+    /// order listing a key more times than it has children, or leaving one out, is refused
+    /// rather than dropping or moving children.
+    fn in_order<T: Tree>(
+        &self,
+        tree: &mut T,
+        owner: &str,
+        order: &T::Value,
+        children: Vec<(String, T::Value)>,
+    ) -> Result<Vec<(String, T::Value)>, T::Error> {
+        let order_key = &self.config.order_key;
+        let mut queues = Vec::with_capacity(children.len());
+        for (key, value) in children {
+            queues.push((key, self.items_of(tree, value)?));
+        }
+        let Some(keys) = tree.items(order)? else {
+            return Err(tree.error(Error::Value(format!(
+                "{order_key} of {owner} must be a list"
+            ))));
+        };
+        let mut ordered = Vec::new();
+        for key in keys {
+            let key = tree.text(order_key, &key?)?;
+            let item = queues
+                .iter_mut()
+                .find(|(name, _)| *name == key)
+                .and_then(|(_, items)| items.pop_front());
+            let Some(item) = item else {
+                return Err(tree.error(Error::Value(format!(
+                    "{order_key} of {owner} lists {key:?} more times than it has children"
+                ))));
+            };
+            ordered.push((key, item));
+        }
+        if let Some((key, _)) = queues.iter().find(|(_, items)| !items.is_empty()) {
+            return Err(tree.error(Error::Value(format!(
+                "{order_key} of {owner} leaves out children {key:?}"
+            ))));
+        }
+        Ok(ordered)
+    }
+
+    /// The items of the children entries, taking one item of a repeated entry at a time.
+    fn items_of<T: Tree>(
+        &self,
+        tree: &mut T,
+        value: T::Value,
+    ) -> Result<VecDeque<T::Value>, T::Error> {
+        match tree.items(&value)? {
+            Some(items) if !self.is_mixed(tree, &value) => {
+                items.map(|item| self.expand_iter(tree, item?)).collect()
+            }
+            Some(_) | None => Ok(VecDeque::from([value])),
+        }
+    }
+
+    /// Children in the order of the positions their maps hold under `index_key`. Children
+    /// without a position, such as comments, follow them in the order given.
+    fn by_index<T: Tree>(
+        &self,
+        tree: &mut T,
+        index_key: &str,
+        children: Vec<(String, T::Value)>,
+    ) -> Result<Vec<(String, T::Value)>, T::Error> {
+        let mut indexed = Vec::new();
+        let mut rest = Vec::new();
+        for (key, value) in children {
+            for item in self.items_of(tree, value)? {
+                let entries = tree.entries(&item)?.unwrap_or_default();
+                match entries.iter().find(|(name, _)| name == index_key) {
+                    Some((name, index)) => {
+                        let text = tree.text(name, index)?;
+                        let Ok(index) = text.trim().parse::<usize>() else {
+                            return Err(tree.error(Error::Value(format!(
+                                "{index_key} of <{key}> must be a position, not {text:?}"
+                            ))));
+                        };
+                        indexed.push((index, key.clone(), item));
+                    }
+                    None => rest.push((key.clone(), item)),
+                }
+            }
+        }
+        indexed.sort_by_key(|&(index, _, _)| index);
+        Ok(indexed
+            .into_iter()
+            .map(|(_, key, item)| (key, item))
+            .chain(rest)
+            .collect())
+    }
+}
+
+/// Escapes the text content of `tag`, or wraps it in a CDATA section if configured to.
+pub(crate) fn escape_text<'t>(
+    config: &UnparseConfig,
+    tag: &str,
+    text: &'t str,
+) -> Result<Cow<'t, str>, Error> {
+    let writable = |ch| config.writes_raw(ch);
+    if config.wants_cdata(tag, text) {
+        return cdata_section(text, config.invalid_chars, writable)
+            .map(Cow::Owned)
+            .map_err(Error::Value);
+    }
+    let text = if config.canonical {
+        Cow::Owned(escape_canonical_text(text, config.invalid_chars).map_err(Error::Value)?)
+    } else {
+        escape_xml(text, config.invalid_chars).map_err(Error::Value)?
+    };
+    Ok(escape_unwritable(text, writable))
+}
+
+/// Appends ` name="value"`, escaping the value as the configuration says.
+pub(crate) fn push_attribute(
+    output: &mut String,
+    config: &UnparseConfig,
+    name: &str,
+    value: &str,
+) -> Result<(), Error> {
+    let value = if config.canonical {
+        escape_canonical_attr(value, config.invalid_chars).map(Cow::Owned)
+    } else {
+        escape_xml_attr(value, config.attr_escapes(), config.invalid_chars)
+    }
+    .map_err(Error::Value)?;
+    let value = escape_unwritable(value, |ch| config.writes_raw(ch));
+    check_name(config, "attribute", name)?;
+    output.push(' ');
+    output.push_str(name);
+    output.push_str("=\"");
+    output.push_str(&value);
+    output.push('"');
+    Ok(())
+}
+
+/// The name an element or attribute is written with, as [`prefixed_name`] gives it.
+fn written_name<'a>(
+    config: &UnparseConfig,
+    kind: &str,
+    name: &'a str,
+) -> Result<Cow<'a, str>, Error> {
+    prefixed_name(config, name).map_err(|uri| {
+        Error::Value(format!(
+            "{kind} {name:?} is in the namespace {uri:?}, which namespaces gives no prefix"
+        ))
+    })
+}
+
+/// Refuses an element or attribute name that is not an XML `Name`, which would not read
+/// back as one, or that [`check_encodable`] refuses.
+fn check_name(config: &UnparseConfig, kind: &str, name: &str) -> Result<(), Error> {
+    if !is_xml_name(name) {
+        return Err(Error::Value(format!(
+            "{kind} name {name:?} is not a valid XML name"
+        )));
+    }
+    check_encodable(config, &format!("{kind} name"), name)
+}
+
+/// Refuses a name, comment or processing instruction holding a character the encoding of
+/// the output does not, as character references cannot stand for it there.
+pub(crate) fn check_encodable(config: &UnparseConfig, kind: &str, text: &str) -> Result<(), Error> {
+    if text.is_ascii() {
+        return Ok(());
+    }
+    match text.chars().find(|&ch| !config.charset.contains(ch)) {
+        Some(ch) => Err(Error::Value(format!(
+            "{kind} {text:?} holds {ch:?}, which {} cannot encode",
+            config.encoding
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Renders a comment or processing instruction, checking its text as
+/// [`Markup::render`] and [`check_encodable`] do.
+pub(crate) fn render_markup(
+    config: &UnparseConfig,
+    markup: Markup,
+    text: &str,
+) -> Result<String, Error> {
+    let kind = match markup {
+        Markup::Comment => "comment",
+        Markup::ProcessingInstruction => "processing instruction",
+    };
+    check_encodable(config, kind, text)?;
+    markup
+        .render(text, config.invalid_chars)
+        .map_err(Error::Value)
+}
+
+/// Writes [`XmlValue`] trees, which hold text only.
+struct Plain<'v>(PhantomData<&'v XmlValue>);
+
+type PlainItems<'v> = Box<dyn Iterator<Item = Result<Cow<'v, XmlValue>, Error>> + 'v>;
+
+impl<'v> Tree for Plain<'v> {
+    type Value = Cow<'v, XmlValue>;
+    type Items = PlainItems<'v>;
+    type Error = Error;
+
+    fn node(&mut self, value: Cow<'v, XmlValue>) -> Result<Node<Self::Value, Self::Items>, Error> {
+        let items = self.items(&value)?;
+        Ok(match (value, items) {
+            (_, Some(items)) => Node::List(items),
+            (Cow::Borrowed(XmlValue::Map(map)), None) => Node::Map(
+                map.iter()
+                    .map(|(key, value)| (key.to_owned(), Cow::Borrowed(value)))
+                    .collect(),
+            ),
+            (Cow::Owned(XmlValue::Map(map)), None) => Node::Map(
+                map.into_iter()
+                    .map(|(key, value)| (key, Cow::Owned(value)))
+                    .collect(),
+            ),
+            (value, None) => match value.into_owned() {
+                XmlValue::Text(text) | XmlValue::Number(text) => Node::Text(text),
+                XmlValue::Bool(value) => Node::Text(value.to_string()),
+                XmlValue::None | XmlValue::List(_) | XmlValue::Map(_) => Node::Empty,
+            },
+        })
+    }
+
+    fn items(&mut self, value: &Cow<'v, XmlValue>) -> Result<Option<PlainItems<'v>>, Error> {
+        Ok(match value {
+            Cow::Borrowed(XmlValue::List(items)) => {
+                Some(Box::new(items.iter().map(|item| Ok(Cow::Borrowed(item)))))
+            }
+            Cow::Owned(XmlValue::List(items)) => Some(Box::new(
+                items.clone().into_iter().map(|item| Ok(Cow::Owned(item))),
+            )),
+            Cow::Borrowed(_) | Cow::Owned(_) => None,
+        })
+    }
+
+    fn entries(
+        &mut self,
+        value: &Cow<'v, XmlValue>,
+    ) -> Result<Option<Vec<(String, Cow<'v, XmlValue>)>>, Error> {
+        Ok(match value {
+            Cow::Borrowed(XmlValue::Map(map)) => Some(
+                map.iter()
+                    .map(|(key, value)| (key.to_owned(), Cow::Borrowed(value)))
+                    .collect(),
+            ),
+            Cow::Owned(XmlValue::Map(map)) => Some(
+                map.iter()
+                    .map(|(key, value)| (key.to_owned(), Cow::Owned(value.clone())))
+                    .collect(),
+            ),
+            Cow::Borrowed(_) | Cow::Owned(_) => None,
+        })
+    }
+
+    fn is_none(&self, value: &Cow<'v, XmlValue>) -> bool {
+        matches!(**value, XmlValue::None)
+    }
+
+    fn text(&mut self, key: &str, value: &Cow<'v, XmlValue>) -> Result<String, Error> {
+        match &**value {
+            XmlValue::Text(text) | XmlValue::Number(text) => Ok(text.clone()),
+            XmlValue::Bool(value) => Ok(value.to_string()),
+            XmlValue::None => Ok(String::new()),
+            XmlValue::List(_) | XmlValue::Map(_) => {
+                Err(Error::Value(format!("value of {key} must be text")))
+            }
+        }
+    }
+
+    fn is_mixed(&self, value: &Cow<'v, XmlValue>) -> bool {
+        let XmlValue::List(nodes) = &**value else {
+            return false;
+        };
+        nodes.iter().any(|node| matches!(node, XmlValue::Text(_)))
+            && nodes.iter().any(|node| matches!(node, XmlValue::Map(_)))
+            && !nodes.iter().any(|node| matches!(node, XmlValue::List(_)))
+    }
+
+    fn expand(&mut self, key: &str, item: Cow<'v, XmlValue>) -> Result<Cow<'v, XmlValue>, Error> {
+        if !matches!(*item, XmlValue::List(_)) {
+            return Ok(item);
+        }
+        let map = XmlMap::from_iter([(key.to_owned(), item.into_owned())]);
+        Ok(Cow::Owned(XmlValue::Map(map)))
+    }
+
+    fn error(&self, err: Error) -> Error {
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Charset, DuplicateAttributes, ParseConfig};
+    use crate::xmltodict_core::parse_str;
+
+    fn fragment() -> UnparseConfig {
+        UnparseConfig {
+            full_document: false,
+            ..UnparseConfig::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_parsed_documents() {
+        let xml = r#"<r a="1&amp;2"><x>t &lt; u</x><x/><y b="2">z</y></r>"#;
+        let doc = parse_str(xml, &ParseConfig::default()).unwrap_or_default();
+        assert_eq!(
+            xml.replace("<x/>", "<x></x>"),
+            unparse(&doc, &fragment()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn writes_header_and_pretty_output() {
+        let doc: XmlMap = [(
+            "r".to_owned(),
+            XmlValue::Map(
+                [("a".to_owned(), vec!["1".into(), "2".into()].into())]
+                    .into_iter()
+                    .collect(),
+            ),
+        )]
+        .into_iter()
+        .collect();
+        let config = UnparseConfig {
+            pretty: true,
+            ..UnparseConfig::default()
+        };
+        assert_eq!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<r>\n\t<a>1</a>\n\t<a>2</a>\n</r>",
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn declaration_carries_version_and_standalone() {
+        let doc = XmlMap::from_iter([("r".to_owned(), XmlValue::None)]);
+        let config = UnparseConfig {
+            xml_version: "1.1".to_owned(),
+            standalone: Some(true),
+            ..UnparseConfig::default()
+        };
+        assert_eq!(
+            "<?xml version=\"1.1\" encoding=\"utf-8\" standalone=\"yes\"?>\n<r></r>",
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn pretty_depth_keeps_deeper_levels_compact() {
+        let doc = parse_str("<r><a><b><c>1</c></b></a><d/></r>", &ParseConfig::default())
+            .unwrap_or_default();
+        let config = UnparseConfig {
+            pretty: true,
+            pretty_depth: Some(1),
+            ..fragment()
+        };
+        assert_eq!(
+            "<r>\n\t<a><b><c>1</c></b></a>\n\t<d></d>\n</r>",
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn duplicate_attributes_raise_or_keep_the_last_value() {
+        let attrs = XmlMap::from_iter([("id".to_owned(), XmlValue::from("2"))]);
+        let r = XmlMap::from_iter([
+            ("@id".to_owned(), XmlValue::from("1")),
+            ("@k".to_owned(), XmlValue::from("v")),
+            ("#attrs".to_owned(), XmlValue::Map(attrs)),
+        ]);
+        let doc = XmlMap::from_iter([("r".to_owned(), XmlValue::Map(r))]);
+        let config = UnparseConfig {
+            attr_dict_key: Some("#attrs".to_owned()),
+            ..fragment()
+        };
+        assert!(matches!(unparse(&doc, &config), Err(Error::Value(_))));
+        let config = UnparseConfig {
+            duplicate_attributes: DuplicateAttributes::Last,
+            ..config
+        };
+        assert_eq!(
+            r#"<r id="2" k="v"></r>"#,
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn full_document_requires_one_root() {
+        let doc: XmlMap = [
+            ("a".to_owned(), XmlValue::None),
+            ("b".to_owned(), XmlValue::None),
+        ]
+        .into_iter()
+        .collect();
+        assert!(matches!(
+            unparse(&doc, &UnparseConfig::default()),
+            Err(Error::Value(_))
+        ));
+        assert_eq!(
+            "<a></a><b></b>",
+            unparse(&doc, &fragment()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn mixed_content_round_trips() {
+        let xml = r#"<p class="x">a <b>bold</b> <i>it</i>.</p>"#;
+        let parse_config = ParseConfig::builder().preserve_mixed_content(true).build();
+        let doc = parse_str(xml, &parse_config).unwrap_or_default();
+        let config = UnparseConfig {
+            preserve_mixed_content: true,
+            ..fragment()
+        };
+        assert_eq!(xml, unparse(&doc, &config).unwrap_or_default());
+    }
+
+    #[test]
+    fn doctype_round_trips() {
+        let xml = "<!DOCTYPE html SYSTEM \"about:legacy-compat\">\n<html></html>";
+        let parse_config = ParseConfig::builder().process_doctype(true).build();
+        let doc = parse_str(xml, &parse_config).unwrap_or_default();
+        assert_eq!(
+            Some("html SYSTEM \"about:legacy-compat\""),
+            doc.get("#doctype").and_then(XmlValue::as_str)
+        );
+        assert_eq!(
+            format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{xml}"),
+            unparse(&doc, &UnparseConfig::default()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn namespace_map_is_declared_on_the_root() {
+        let doc: XmlMap = [("urn:a:r".to_owned(), XmlValue::from("x"))]
+            .into_iter()
+            .collect();
+        let config = UnparseConfig {
+            namespaces: vec![("urn:a".to_owned(), "a".to_owned())],
+            ..fragment()
+        };
+        assert_eq!(
+            r#"<a:r xmlns:a="urn:a">x</a:r>"#,
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn names_must_be_xml_names() {
+        let attribute = XmlMap::from_iter([("@a=b".to_owned(), XmlValue::from("1"))]);
+        for (tag, value) in [
+            ("a b", XmlValue::from("x")),
+            ("1a", XmlValue::from("x")),
+            ("r", XmlValue::Map(attribute)),
+        ] {
+            let doc = XmlMap::from_iter([(tag.to_owned(), value)]);
+            assert!(matches!(unparse(&doc, &fragment()), Err(Error::Value(_))));
+        }
+    }
+
+    #[test]
+    fn xmlns_maps_expand_into_declarations() {
+        let bindings = XmlMap::from_iter([
+            (String::new(), XmlValue::from("urn:d")),
+            ("p".to_owned(), XmlValue::from("urn:p")),
+        ]);
+        let r = XmlMap::from_iter([
+            ("@xmlns".to_owned(), XmlValue::Map(bindings)),
+            ("@p:k".to_owned(), XmlValue::from("v")),
+        ]);
+        let doc = XmlMap::from_iter([("r".to_owned(), XmlValue::Map(r))]);
+        assert_eq!(
+            r#"<r xmlns="urn:d" xmlns:p="urn:p" p:k="v"></r>"#,
+            unparse(&doc, &fragment()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn invalid_chars_are_rejected_or_stripped() {
+        let doc = XmlMap::from_iter([("r".to_owned(), "a\u{0}b".into())]);
+        assert!(matches!(unparse(&doc, &fragment()), Err(Error::Value(_))));
+        let config = UnparseConfig {
+            invalid_chars: crate::config::InvalidChars::Strip,
+            ..fragment()
+        };
+        assert_eq!("<r>ab</r>", unparse(&doc, &config).unwrap_or_default());
+    }
+
+    #[test]
+    fn characters_outside_the_charset_are_referenced_or_refused() {
+        let config = UnparseConfig {
+            encoding: "iso-8859-1".to_owned(),
+            charset: Charset::Latin1,
+            cdata_for_keys: ["c".to_owned()].into(),
+            ..fragment()
+        };
+        let xml = "<r a=\"\u{E9}\u{20AC}\"><t>\u{E9}\u{20AC}</t><c>\u{E9}\u{20AC}</c></r>";
+        let doc = parse_str(xml, &ParseConfig::default()).unwrap_or_default();
+        assert_eq!(
+            "<r a=\"\u{E9}&#8364;\"><t>\u{E9}&#8364;</t><c><![CDATA[\u{E9}]]>&#8364;</c></r>",
+            unparse(&doc, &config).unwrap_or_default()
+        );
+        for xml in [
+            "<r\u{20AC}/>",
+            "<r a\u{20AC}=\"\"/>",
+            "<r><!--\u{20AC}--></r>",
+        ] {
+            let parse_config = ParseConfig::builder().process_comments(true).build();
+            let doc = parse_str(xml, &parse_config).unwrap_or_default();
+            assert!(
+                matches!(unparse(&doc, &config), Err(Error::Value(_))),
+                "{xml}"
+            );
+        }
+    }
+
+    #[test]
+    fn comments_round_trip() {
+        let xml = "<r><!--a--><x>1</x><!--b--></r>";
+        let config = ParseConfig::builder().process_comments(true).build();
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        assert_eq!(
+            "<r><!--a--><!--b--><x>1</x></r>",
+            unparse(&doc, &fragment()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn processing_instructions_round_trip() {
+        let xml = "<?style a?><!--c--><r><?p q?></r>";
+        let config = ParseConfig::builder()
+            .process_comments(true)
+            .process_pis(true)
+            .build();
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        assert_eq!(xml, unparse(&doc, &fragment()).unwrap_or_default());
+    }
+
+    #[test]
+    fn document_level_comments_round_trip() {
+        let config = ParseConfig::builder().process_comments(true).build();
+        let epilogue = parse_str("<r>x</r><!--a--><!--b-->", &config).unwrap_or_default();
+        assert_eq!(
+            Some(&XmlValue::List(vec!["a".into(), "b".into()])),
+            epilogue.get("#comment")
+        );
+        assert_eq!(
+            "<r>x</r><!--a--><!--b-->",
+            unparse(&epilogue, &fragment()).unwrap_or_default()
+        );
+        // Comments on both sides of the root share a list, so the document's order is kept.
+        let config = ParseConfig::builder()
+            .process_comments(true)
+            .preserve_sibling_order(true)
+            .build();
+        let both = parse_str("<!--a--><r>x</r><!--b-->", &config).unwrap_or_default();
+        assert_eq!(
+            Some(&XmlValue::List(vec![
+                "#comment".into(),
+                "r".into(),
+                "#comment".into()
+            ])),
+            both.get("#order")
+        );
+        assert_eq!(
+            "<!--a--><r>x</r><!--b-->",
+            unparse(&both, &fragment()).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn sibling_order_round_trips() {
+        let xml = "<r><a>1</a><b/><a>2</a><!--c--></r>";
+        let parse_config = ParseConfig {
+            preserve_sibling_order: true,
+            process_comments: true,
+            ..ParseConfig::default()
+        };
+        let doc = parse_str(xml, &parse_config).unwrap_or_default();
+        let config = UnparseConfig {
+            preserve_sibling_order: true,
+            short_empty_elements: true,
+            ..fragment()
+        };
+        assert_eq!(xml, unparse(&doc, &config).unwrap_or_default());
+    }
+
+    #[test]
+    fn sibling_positions_round_trip() {
+        let xml = "<r><a>1</a><b><c/></b><a>2</a></r>";
+        let parse_config = ParseConfig::builder().index_key("#index").build();
+        let doc = parse_str(xml, &parse_config).unwrap_or_default();
+        let config = UnparseConfig {
+            index_key: Some("#index".to_owned()),
+            short_empty_elements: true,
+            ..fragment()
+        };
+        assert_eq!(xml, unparse(&doc, &config).unwrap_or_default());
+    }
+
+    #[test]
+    fn attribute_dicts_round_trip() {
+        let xml = r#"<r a="1"><x b="2">t</x><y/></r>"#;
+        let config = ParseConfig::builder().attr_dict_key("#attrs").build();
+        let doc = parse_str(xml, &config).unwrap_or_default();
+        let x = doc
+            .get("r")
+            .and_then(XmlValue::as_map)
+            .and_then(|r| r.get("x"))
+            .and_then(XmlValue::as_map);
+        assert!(x.is_some_and(|x| x.get("#attrs").is_some()));
+        let config = UnparseConfig {
+            attr_dict_key: Some("#attrs".to_owned()),
+            ..fragment()
+        };
+        assert_eq!(
+            xml.replace("<y/>", "<y></y>"),
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn canonical_output_sorts_attributes_and_expands_empty_elements() {
+        let doc = parse_str(
+            "<r z=\"1\" xmlns:b=\"urn:b\" a=\"x&#10;y\" xmlns=\"urn:r\"><e/></r>",
+            &ParseConfig::default(),
+        )
+        .unwrap_or_default();
+        let config = UnparseConfig {
+            canonical: true,
+            pretty: true,
+            ..UnparseConfig::default()
+        };
+        assert_eq!(
+            "<r xmlns=\"urn:r\" xmlns:b=\"urn:b\" a=\"x&#xA;y\" z=\"1\"><e></e></r>",
+            unparse(&doc, &config).unwrap_or_default()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+/// A parsed XML value, shaped like the Python objects `parse()` returns.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum XmlValue {
+    /// Empty element (`None` in Python).
+    #[default]
+    None,
+    Text(String),
+    /// `true` or `false` recognised by `infer_types`.
+    Bool(bool),
+    /// A number recognised by `infer_types`, spelled as Python writes the `int` or `float`
+    /// it becomes, so `1.50` is `1.5` and `+7` is `7`.
+    Number(String),
+    /// Repeated elements with the same name.
+    List(Vec<XmlValue>),
+    /// Element with attributes and/or children.
+    Map(XmlMap),
+}
+
+impl XmlValue {
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::None | Self::Bool(_) | Self::Number(_) | Self::List(_) | Self::Map(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_map(&self) -> Option<&XmlMap> {
+        match self {
+            Self::Map(map) => Some(map),
+            Self::None | Self::Text(_) | Self::Bool(_) | Self::Number(_) | Self::List(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_list(&self) -> Option<&[XmlValue]> {
+        match self {
+            Self::List(items) => Some(items),
+            Self::None | Self::Text(_) | Self::Bool(_) | Self::Number(_) | Self::Map(_) => None,
+        }
+    }
+}
+
+impl From<&str> for XmlValue {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_owned())
+    }
+}
+
+impl From<String> for XmlValue {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<Vec<XmlValue>> for XmlValue {
+    fn from(items: Vec<XmlValue>) -> Self {
+        Self::List(items)
+    }
+}
+
+impl From<XmlMap> for XmlValue {
+    fn from(map: XmlMap) -> Self {
+        Self::Map(map)
+    }
+}
+
+/// Insertion-ordered string-keyed map, mirroring Python's `dict`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct XmlMap(Vec<(String, XmlValue)>);
+
+impl XmlMap {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&XmlValue> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut XmlValue> {
+        self.0.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Inserts a value, replacing (in place) and returning any previous value for `key`.
+    pub fn insert(&mut self, key: impl Into<String>, value: XmlValue) -> Option<XmlValue> {
+        let key = key.into();
+        if let Some(existing) = self.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        self.0.push((key, value));
+        None
+    }
+
+    /// Adds a value the way repeated elements are collected: a second value for the
+    /// same key turns the entry into a list.
+    pub fn append(&mut self, key: impl Into<String>, value: XmlValue) {
+        let key = key.into();
+        match self.get_mut(&key) {
+            Some(XmlValue::List(items)) => items.push(value),
+            Some(existing) => {
+                let first = std::mem::take(existing);
+                *existing = XmlValue::List(vec![first, value]);
+            }
+            None => self.0.push((key, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &XmlValue)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+impl FromIterator<(String, XmlValue)> for XmlMap {
+    fn from_iter<I: IntoIterator<Item = (String, XmlValue)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl IntoIterator for XmlMap {
+    type Item = (String, XmlValue);
+    type IntoIter = std::vec::IntoIter<(String, XmlValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(feature = "python")]
+mod python {
+    use super::{XmlMap, XmlValue};
+    use pyo3::prelude::*;
+    use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+
+    impl<'py> IntoPyObject<'py> for &XmlValue {
+        type Target = PyAny;
+        type Output = Bound<'py, PyAny>;
+        type Error = PyErr;
+
+        fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+            match self {
+                XmlValue::None => Ok(py.None().into_bound(py)),
+                XmlValue::Text(text) => Ok(PyString::new(py, text).into_any()),
+                XmlValue::Bool(value) => Ok(PyBool::new(py, *value).to_owned().into_any()),
+                // Only floats are spelled with a point or an exponent.
+                XmlValue::Number(number) if number.contains(['.', 'e']) => {
+                    Ok(PyFloat::new(py, number.parse().unwrap_or(f64::NAN)).into_any())
+                }
+                XmlValue::Number(number) => py.get_type::<PyInt>().call1((number,)),
+                XmlValue::List(items) => Ok(PyList::new(py, items)?.into_any()),
+                XmlValue::Map(map) => Ok(map.into_pyobject(py)?.into_any()),
+            }
+        }
+    }
+
+    impl<'py> IntoPyObject<'py> for &XmlMap {
+        type Target = PyDict;
+        type Output = Bound<'py, PyDict>;
+        type Error = PyErr;
+
+        fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+            let dict = PyDict::new(py);
+            for (key, value) in self.iter() {
+                dict.set_item(key, value)?;
+            }
+            Ok(dict)
+        }
+    }
+
+    /// Converts the objects `unparse()` accepts: dicts, lists/tuples, `None`, booleans
+    /// (as `"true"`/`"false"`) and anything else via `str()`.
+    impl<'py> FromPyObject<'py> for XmlValue {
+        fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+            if ob.is_none() {
+                return Ok(Self::None);
+            }
+            if let Ok(text) = ob.downcast::<PyString>() {
+                return Ok(Self::Text(text.to_str()?.to_owned()));
+            }
+            if let Ok(dict) = ob.downcast::<PyDict>() {
+                return Ok(Self::Map(dict.extract()?));
+            }
+            if ob.is_instance_of::<PyList>() || ob.is_instance_of::<PyTuple>() {
+                let items = ob
+                    .try_iter()?
+                    .map(|item| item?.extract())
+                    .collect::<PyResult<_>>()?;
+                return Ok(Self::List(items));
+            }
+            if let Ok(flag) = ob.downcast::<PyBool>() {
+                let text = if flag.is_true() { "true" } else { "false" };
+                return Ok(Self::Text(text.to_owned()));
+            }
+            Ok(Self::Text(ob.str()?.to_string()))
+        }
+    }
+
+    impl<'py> FromPyObject<'py> for XmlMap {
+        fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+            let dict = ob.downcast::<PyDict>()?;
+            dict.iter()
+                .map(|(key, value)| Ok((key.str()?.to_string(), value.extract()?)))
+                .collect()
+        }
+    }
+}
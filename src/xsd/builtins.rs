@@ -0,0 +1,179 @@
+use crate::transforms::Transform;
+
+/// Namespace of the XML Schema elements and built-in types.
+pub const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// The conversion for a built-in simple type, by its local name. Types with no Python
+/// counterpart, such as `string`, `date` or `duration`, keep their text.
+pub fn transform(name: &str) -> Option<Transform> {
+    match name {
+        "integer" | "int" | "long" | "short" | "byte" | "nonNegativeInteger"
+        | "positiveInteger" | "nonPositiveInteger" | "negativeInteger" | "unsignedLong"
+        | "unsignedInt" | "unsignedShort" | "unsignedByte" => Some(Transform::Int),
+        "decimal" | "float" | "double" => Some(Transform::Float),
+        "boolean" => Some(Transform::Bool),
+        "dateTime" | "dateTimeStamp" => Some(Transform::Datetime),
+        _ => None,
+    }
+}
+
+/// Whether values of the type keep their whitespace; every other type ignores it around
+/// the value.
+pub fn keeps_whitespace(name: &str) -> bool {
+    matches!(name, "string" | "normalizedString" | "anySimpleType")
+}
+
+/// Whether values of the type are numbers, which bounds such as `maxInclusive` compare.
+pub fn is_numeric(name: &str) -> bool {
+    matches!(transform(name), Some(Transform::Int | Transform::Float))
+}
+
+/// Whether `text` is a value of the built-in type `name`. Types not checked here, such as
+/// `string`, `anyURI` or `duration`, accept any text.
+pub fn is_valid(name: &str, text: &str) -> bool {
+    match name {
+        "integer" => integer(text).is_some(),
+        "nonNegativeInteger" => integer(text).is_some_and(|value| value.sign >= 0),
+        "positiveInteger" => integer(text).is_some_and(|value| value.sign > 0),
+        "nonPositiveInteger" => integer(text).is_some_and(|value| value.sign <= 0),
+        "negativeInteger" => integer(text).is_some_and(|value| value.sign < 0),
+        "long" => in_range(text, i64::MIN.into(), i64::MAX.into()),
+        "int" => in_range(text, i32::MIN.into(), i32::MAX.into()),
+        "short" => in_range(text, i16::MIN.into(), i16::MAX.into()),
+        "byte" => in_range(text, i8::MIN.into(), i8::MAX.into()),
+        "unsignedLong" => in_range(text, 0, u64::MAX.into()),
+        "unsignedInt" => in_range(text, 0, u32::MAX.into()),
+        "unsignedShort" => in_range(text, 0, u16::MAX.into()),
+        "unsignedByte" => in_range(text, 0, u8::MAX.into()),
+        "decimal" => is_decimal(text),
+        "float" | "double" => is_float(text),
+        "boolean" => matches!(text, "true" | "false" | "1" | "0"),
+        "dateTime" => text
+            .split_once('T')
+            .is_some_and(|(date, time)| is_date(date) && is_time(time)),
+        "date" => is_date(text),
+        "time" => is_time(text),
+        "hexBinary" => {
+            text.len().is_multiple_of(2) && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+        }
+        _ => true,
+    }
+}
+
+/// An integer literal: its sign, and its value when it fits in an `i128`.
+struct Integer {
+    sign: i8,
+    value: Option<i128>,
+}
+
+fn integer(text: &str) -> Option<Integer> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if !is_digits(digits) {
+        return None;
+    }
+    let zero = digits.bytes().all(|byte| byte == b'0');
+    let sign = match (zero, negative) {
+        (true, _) => 0,
+        (false, true) => -1,
+        (false, false) => 1,
+    };
+    let value = digits
+        .parse::<i128>()
+        .ok()
+        .map(|value| if negative { -value } else { value });
+    Some(Integer { sign, value })
+}
+
+fn in_range(text: &str, min: i128, max: i128) -> bool {
+    integer(text)
+        .and_then(|integer| integer.value)
+        .is_some_and(|value| (min..=max).contains(&value))
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn is_decimal(text: &str) -> bool {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    match text.split_once('.') {
+        Some((whole, fraction)) => {
+            (whole.is_empty() || is_digits(whole))
+                && (fraction.is_empty() || is_digits(fraction))
+                && !(whole.is_empty() && fraction.is_empty())
+        }
+        None => is_digits(text),
+    }
+}
+
+fn is_float(text: &str) -> bool {
+    if matches!(text, "INF" | "+INF" | "-INF" | "NaN") {
+        return true;
+    }
+    match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            is_decimal(mantissa) && is_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent))
+        }
+        None => is_decimal(text),
+    }
+}
+
+/// Whether `text` is a number of `width` digits between `min` and `max`.
+fn is_number(text: &str, width: usize, min: u32, max: u32) -> bool {
+    text.len() == width
+        && is_digits(text)
+        && text
+            .parse::<u32>()
+            .is_ok_and(|value| (min..=max).contains(&value))
+}
+
+/// Splits a trailing `Z` or `+hh:mm`/`-hh:mm` time zone off a date or time, checking it.
+fn without_zone(text: &str) -> Option<&str> {
+    if let Some(value) = text.strip_suffix('Z') {
+        return Some(value);
+    }
+    let Some(split) = text.len().checked_sub(6) else {
+        return Some(text);
+    };
+    match (text.get(..split), text.get(split..)) {
+        (Some(value), Some(zone))
+            if zone.starts_with(['+', '-']) && zone.get(3..4) == Some(":") =>
+        {
+            let hours = zone.get(1..3)?;
+            let minutes = zone.get(4..)?;
+            (is_number(hours, 2, 0, 14) && is_number(minutes, 2, 0, 59)).then_some(value)
+        }
+        _ => Some(text),
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    let Some(text) = without_zone(text) else {
+        return false;
+    };
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let mut parts = text.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    year.len() >= 4 && is_digits(year) && is_number(month, 2, 1, 12) && is_number(day, 2, 1, 31)
+}
+
+fn is_time(text: &str) -> bool {
+    let Some(text) = without_zone(text) else {
+        return false;
+    };
+    let mut parts = text.splitn(3, ':');
+    let (Some(hours), Some(minutes), Some(seconds)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    is_number(hours, 2, 0, 24)
+        && is_number(minutes, 2, 0, 59)
+        && is_number(seconds, 2, 0, 60)
+        && is_digits(fraction)
+}
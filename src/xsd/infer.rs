@@ -0,0 +1,438 @@
+use super::builtins;
+use super::model::{
+    expanded_name, local_name, Attribute, ComplexType, Content, Element, Kind, Model, Particle,
+    SimpleType, Term, Variety,
+};
+use crate::events::XmlEvent;
+use crate::infer::has_leading_zero;
+use std::collections::{HashMap, HashSet};
+
+/// Built-in types tried for values, narrowest first; values none of them take are strings.
+const TYPES: [&str; 9] = [
+    "boolean", "int", "long", "integer", "decimal", "double", "date", "time", "dateTime",
+];
+
+impl Model {
+    /// A best-effort model of documents like `samples`. Each element is declared where it
+    /// occurs, with the attributes and child elements seen there, and values take the
+    /// narrowest built-in type all of them belong to. Children seen in one order form a
+    /// sequence, optional where some occurrences lack them and repeated where one had
+    /// several; children seen in varying orders form a repeated choice. Elements are in the
+    /// namespace of the first root element.
+    #[must_use]
+    pub fn infer(samples: &[Vec<(XmlEvent, usize)>]) -> Self {
+        let mut observer = Observer::default();
+        for events in samples {
+            for (event, _) in events {
+                observer.event(event);
+            }
+            observer.open.clear();
+        }
+        let namespace = observer.namespace.unwrap_or_default();
+        let mut builder = Builder {
+            observed: &observer.observed,
+            namespace: &namespace,
+            builtin_ids: HashMap::new(),
+            model: Self::default(),
+        };
+        for name in &observer.roots {
+            let id = builder.element(name, name);
+            builder
+                .model
+                .roots
+                .entry(expanded_name(&namespace, local_name(name)).into_owned())
+                .or_insert(id);
+        }
+        builder.model
+    }
+}
+
+/// The text values of an element or attribute, narrowing the types they belong to.
+struct Values {
+    /// The types of [`TYPES`] every value seen belongs to.
+    types: Vec<&'static str>,
+    seen: bool,
+    /// Whether some value was empty, which only strings allow.
+    empty: bool,
+}
+
+impl Default for Values {
+    fn default() -> Self {
+        Self {
+            types: TYPES.to_vec(),
+            seen: false,
+            empty: false,
+        }
+    }
+}
+
+impl Values {
+    fn add(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.empty = true;
+            return;
+        }
+        self.seen = true;
+        // As with `infer_types`, leading zeros mean an identifier rather than a number.
+        let number = !has_leading_zero(text);
+        self.types.retain(|name| match *name {
+            "boolean" => matches!(text, "true" | "false"),
+            "int" | "long" | "integer" | "decimal" | "double" => {
+                number && builtins::is_valid(name, text)
+            }
+            _ => builtins::is_valid(name, text),
+        });
+    }
+
+    fn builtin(&self) -> &'static str {
+        if !self.seen || self.empty {
+            return "string";
+        }
+        self.types.first().copied().unwrap_or("string")
+    }
+}
+
+/// What the occurrences of an element at one path had.
+#[derive(Default)]
+struct Observed {
+    count: usize,
+    text: Values,
+    /// Whether text appeared beside child elements.
+    mixed: bool,
+    /// Attributes by name, with the number of occurrences that had them.
+    attributes: Vec<(String, usize, Values)>,
+    /// Child element names, in the order they appear.
+    order: Vec<String>,
+    /// For each child name, the number of occurrences that had it and the most times one
+    /// occurrence had it.
+    children: HashMap<String, (usize, usize)>,
+    /// Whether some occurrence had its children in another order than `order`.
+    unordered: bool,
+}
+
+impl Observed {
+    fn attribute(&mut self, name: &str, value: &str) {
+        let index = self
+            .attributes
+            .iter()
+            .position(|(other, _, _)| other == name)
+            .unwrap_or_else(|| {
+                self.attributes
+                    .push((name.to_owned(), 0, Values::default()));
+                self.attributes.len() - 1
+            });
+        if let Some((_, present, values)) = self.attributes.get_mut(index) {
+            *present += 1;
+            values.add(value);
+        }
+    }
+
+    /// Records the children of one occurrence, as runs of the same name.
+    fn children(&mut self, runs: &[(String, usize)]) {
+        let mut totals: HashMap<&str, usize> = HashMap::new();
+        for (name, count) in runs {
+            *totals.entry(name).or_default() += count;
+        }
+        for (name, total) in totals {
+            let (present, most) = self.children.entry(name.to_owned()).or_default();
+            *present += 1;
+            *most = (*most).max(total);
+        }
+        let mut seen = HashSet::new();
+        let mut previous: Option<usize> = None;
+        for (index, (name, _)) in runs.iter().enumerate() {
+            if !seen.insert(name) {
+                self.unordered = true;
+            }
+            let position = if let Some(position) = self.order.iter().position(|other| other == name)
+            {
+                position
+            } else {
+                // A new name goes after the one before it, or else before the next known one.
+                let at = previous.map_or_else(
+                    || {
+                        runs.iter()
+                            .skip(index + 1)
+                            .find_map(|(next, _)| self.order.iter().position(|other| other == next))
+                            .unwrap_or(self.order.len())
+                    },
+                    |previous| previous + 1,
+                );
+                self.order.insert(at, name.clone());
+                at
+            };
+            if previous.is_some_and(|previous| position <= previous) {
+                self.unordered = true;
+            }
+            previous = Some(position);
+        }
+    }
+}
+
+/// An element of a sample being read.
+struct Open {
+    path: String,
+    text: String,
+    /// Child element names, with consecutive ones of the same name counted together.
+    runs: Vec<(String, usize)>,
+}
+
+#[derive(Default)]
+struct Observer {
+    /// Root element names, in the order first seen.
+    roots: Vec<String>,
+    /// The namespace URI of the first root element, declared on it.
+    namespace: Option<String>,
+    observed: HashMap<String, Observed>,
+    open: Vec<Open>,
+}
+
+impl Observer {
+    fn event(&mut self, event: &XmlEvent) {
+        match event {
+            XmlEvent::Start { name, attributes } => {
+                let path = if let Some(parent) = self.open.last_mut() {
+                    match parent.runs.last_mut() {
+                        Some((last, count)) if last == name => *count += 1,
+                        Some(_) | None => parent.runs.push((name.clone(), 1)),
+                    }
+                    format!("{}/{name}", parent.path)
+                } else {
+                    if self.namespace.is_none() {
+                        let declaration = match name.split_once(':') {
+                            Some((prefix, _)) => format!("xmlns:{prefix}"),
+                            None => "xmlns".to_owned(),
+                        };
+                        let uri = attributes.iter().find(|(key, _)| *key == declaration);
+                        self.namespace = Some(uri.map(|(_, uri)| uri.clone()).unwrap_or_default());
+                    }
+                    if !self.roots.contains(name) {
+                        self.roots.push(name.clone());
+                    }
+                    name.clone()
+                };
+                let observed = self.observed.entry(path.clone()).or_default();
+                observed.count += 1;
+                for (key, value) in attributes {
+                    if key != "xmlns" && !key.starts_with("xmlns:") && !key.starts_with("xsi:") {
+                        observed.attribute(key, value);
+                    }
+                }
+                self.open.push(Open {
+                    path,
+                    text: String::new(),
+                    runs: Vec::new(),
+                });
+            }
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                if let Some(open) = self.open.last_mut() {
+                    open.text.push_str(text);
+                }
+            }
+            XmlEvent::End(_) => {
+                let Some(open) = self.open.pop() else {
+                    return;
+                };
+                let Some(observed) = self.observed.get_mut(&open.path) else {
+                    return;
+                };
+                if open.runs.is_empty() {
+                    observed.text.add(&open.text);
+                } else {
+                    observed.mixed |= !open.text.trim().is_empty();
+                    observed.children(&open.runs);
+                }
+            }
+            XmlEvent::DocType(_) | XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction(_) => {}
+        }
+    }
+}
+
+struct Builder<'a> {
+    observed: &'a HashMap<String, Observed>,
+    namespace: &'a str,
+    builtin_ids: HashMap<&'static str, usize>,
+    model: Model,
+}
+
+impl<'a> Builder<'a> {
+    fn builtin(&mut self, name: &'static str) -> usize {
+        if let Some(id) = self.builtin_ids.get(name) {
+            return *id;
+        }
+        self.model.simple_types.push(SimpleType {
+            name: format!("xs:{name}"),
+            variety: Variety::Atomic(name.to_owned()),
+            facets: Vec::new(),
+        });
+        let id = self.model.simple_types.len() - 1;
+        self.builtin_ids.insert(name, id);
+        id
+    }
+
+    /// Declares the element at `path`, and those below it.
+    fn element(&mut self, path: &str, name: &str) -> usize {
+        let id = self.model.elements.len();
+        self.model.elements.push(Element {
+            name: local_name(name).to_owned(),
+            namespace: self.namespace.to_owned(),
+            kind: Kind::Any,
+        });
+        let observed: &'a HashMap<String, Observed> = self.observed;
+        let Some(observed) = observed.get(path) else {
+            return id;
+        };
+        let attributes: Vec<Attribute> = observed
+            .attributes
+            .iter()
+            .map(|(name, present, values)| Attribute {
+                name: local_name(name).to_owned(),
+                namespace: String::new(),
+                kind: Some(self.builtin(values.builtin())),
+                required: *present == observed.count,
+            })
+            .collect();
+        let mut particles = Vec::new();
+        for child in &observed.order {
+            let child_id = self.element(&format!("{path}/{child}"), child);
+            let (present, most) = observed.children.get(child).copied().unwrap_or_default();
+            particles.push(if observed.unordered {
+                Particle {
+                    min: 1,
+                    max: Some(1),
+                    term: Term::Element(child_id),
+                }
+            } else {
+                Particle {
+                    min: usize::from(present == observed.count),
+                    max: (most <= 1).then_some(1),
+                    term: Term::Element(child_id),
+                }
+            });
+        }
+        let kind = if particles.is_empty() {
+            let simple = self.builtin(observed.text.builtin());
+            if attributes.is_empty() {
+                Kind::Simple(simple)
+            } else {
+                self.complex(attributes, Content::Simple(simple), false)
+            }
+        } else {
+            let particle = if observed.unordered {
+                Particle {
+                    min: 0,
+                    max: None,
+                    term: Term::Choice(particles),
+                }
+            } else {
+                Particle {
+                    min: 1,
+                    max: Some(1),
+                    term: Term::Sequence(particles),
+                }
+            };
+            self.complex(attributes, Content::Elements(particle), observed.mixed)
+        };
+        if let Some(element) = self.model.elements.get_mut(id) {
+            element.kind = kind;
+        }
+        id
+    }
+
+    fn complex(&mut self, attributes: Vec<Attribute>, content: Content, mixed: bool) -> Kind {
+        self.model.complex_types.push(ComplexType {
+            attributes,
+            any_attribute: false,
+            content,
+            mixed,
+        });
+        Kind::Complex(self.model.complex_types.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::collect_events;
+    use crate::xmltodict_core::{Error, ParseConfig};
+    use crate::xsd::validate;
+
+    fn read(xml: &str) -> Result<Vec<(XmlEvent, usize)>, Error> {
+        collect_events(xml.as_bytes(), &ParseConfig::default())
+    }
+
+    #[test]
+    fn infers_types_occurrences_and_attributes() -> Result<(), Error> {
+        let samples = [
+            read(
+                r#"<order id="1" state="open"><paid>true</paid><line>2</line><line>3</line>
+                   <zip>007</zip><at>2024-01-02T03:04:05Z</at></order>"#,
+            )?,
+            read(r#"<order id="2"><paid>false</paid><line>1.5</line><zip>12</zip></order>"#)?,
+        ];
+        let model = Model::infer(&samples);
+        assert_eq!(
+            model.to_xsd(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+  <xs:element name="order">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="paid" type="xs:boolean"/>
+        <xs:element name="line" maxOccurs="unbounded" type="xs:decimal"/>
+        <xs:element name="zip" type="xs:string"/>
+        <xs:element name="at" minOccurs="0" type="xs:dateTime"/>
+      </xs:sequence>
+      <xs:attribute name="id" type="xs:int" use="required"/>
+      <xs:attribute name="state" type="xs:string"/>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>
+"#
+        );
+        for events in &samples {
+            assert!(validate(&model, events).is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn varying_order_mixed_and_simple_content() -> Result<(), Error> {
+        let samples = [
+            read(r#"<p>a <b>x</b> c <i>y</i><b>z</b><n unit="cm">1</n><e/></p>"#)?,
+            read("<p><i>y</i></p>")?,
+        ];
+        let model = Model::infer(&samples);
+        let xsd = model.to_xsd();
+        assert!(xsd.contains(r#"<xs:complexType mixed="true">"#), "{xsd}");
+        assert!(
+            xsd.contains(r#"<xs:choice minOccurs="0" maxOccurs="unbounded">"#),
+            "{xsd}"
+        );
+        assert!(xsd.contains(r#"<xs:extension base="xs:int">"#), "{xsd}");
+        assert!(
+            xsd.contains(r#"<xs:element name="e" type="xs:string"/>"#),
+            "{xsd}"
+        );
+        for events in &samples {
+            assert!(validate(&model, events).is_empty());
+        }
+        Ok(())
+    }
+    #[test]
+    fn targets_the_namespace_of_the_root() -> Result<(), Error> {
+        let samples = [
+            read(r#"<t:a xmlns:t="urn:t"><t:b>x</t:b></t:a>"#)?,
+            read(r#"<a xmlns="urn:t"><b>y</b></a>"#)?,
+        ];
+        let xsd = Model::infer(&samples).to_xsd();
+        assert!(xsd.contains(r#" targetNamespace="urn:t" "#), "{xsd}");
+        let schema = Model::new(read(&xsd)?)?;
+        for events in &samples {
+            assert!(validate(&schema, events).is_empty());
+        }
+        assert_eq!(validate(&schema, &read("<a><b/></a>")?).len(), 1);
+        Ok(())
+    }
+}
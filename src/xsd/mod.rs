@@ -0,0 +1,481 @@
+//! XML Schema support for `parse()` and `validate()`. A schema is read into a [`Model`] of
+//! its declarations, from which `parse(xsd=...)` takes the built-in types of elements and
+//! attributes as `transforms` and the elements that may occur more than once as lists, and
+//! which documents are validated against.
+//!
+//! Declarations are followed from each global element, which may be the root of the
+//! document. Documents are validated by expanded names: global declarations, and local ones
+//! whose `form` or the schema's `elementFormDefault` or `attributeFormDefault` is
+//! `qualified`, are in the `targetNamespace`, and the document's names are resolved against
+//! its `xmlns` declarations. `parse(xsd=...)` finds entries by the local names on their
+//! path. The schema must be one document: `xs:include`, `xs:import` and other constructs
+//! the model cannot hold, such as substitution groups, raise `ValueError`. Neither
+//! `xs:pattern` facets nor identity constraints are checked.
+//!
+//! RELAX NG schemas in the compact syntax are read into the same [`Model`] by
+//! [`Model::from_rnc`], so `validate_rnc()` reports issues as `validate()` does.
+//!
+//! `infer_schema()` goes the other way: [`Model::infer`] models sample documents, and
+//! [`Model::to_xsd`] writes a model out as a schema.
+
+mod builtins;
+mod infer;
+mod model;
+mod rnc;
+mod validate;
+mod writer;
+
+use crate::path_pattern::PathPattern;
+use crate::transforms::{Transform, Transforms};
+pub use model::Model;
+use model::{Content, Kind, Particle, Term};
+pub use validate::{validate, Found, Issue};
+
+/// What a schema says about the entries of a document.
+#[derive(Clone, Default)]
+pub struct Schema {
+    /// Conversions for elements and attributes of built-in numeric, boolean and date-time
+    /// types, by their path from the root element.
+    pub transforms: Transforms,
+    /// Elements with a `maxOccurs` above one, or inside a repeated group.
+    pub lists: Vec<PathPattern>,
+}
+
+impl Schema {
+    /// Takes the types and lists from a schema. Attribute paths end in `attr_prefix` and the
+    /// attribute name, and the text of elements with attributes is found under `cdata_key`.
+    #[must_use]
+    pub fn new(model: &Model, attr_prefix: &str, cdata_key: &str) -> Self {
+        let mut reader = Reader {
+            model,
+            attr_prefix,
+            cdata_key,
+            path: Vec::new(),
+            open: Vec::new(),
+            recursing: false,
+            schema: Self::default(),
+        };
+        let mut roots: Vec<usize> = model.roots.values().copied().collect();
+        roots.sort_unstable();
+        for root in roots {
+            reader.element(root, false);
+        }
+        reader.schema
+    }
+}
+
+/// Walks the declarations of a schema, down from its global elements.
+struct Reader<'a> {
+    model: &'a Model,
+    attr_prefix: &'a str,
+    cdata_key: &'a str,
+    /// Names of the element being read and its ancestors.
+    path: Vec<String>,
+    /// Complex types being read, with the length of `path` where each was entered, to
+    /// notice recursive declarations.
+    open: Vec<(usize, usize)>,
+    /// Whether a recursive declaration is being read again below a `**` segment.
+    recursing: bool,
+    schema: Schema,
+}
+
+impl Reader<'_> {
+    /// The path of an entry named `key` of the element being read.
+    fn entry_path(&self, key: Option<&str>) -> String {
+        let mut path = String::new();
+        for name in self.path.iter().map(String::as_str).chain(key) {
+            path.push('/');
+            path.push_str(name);
+        }
+        path
+    }
+
+    fn transform(&self, simple: usize) -> Option<Transform> {
+        self.model.builtin(simple).and_then(builtins::transform)
+    }
+
+    /// Converts the text of the element being read, whether it is the element's value or,
+    /// with attributes, its `cdata_key` entry.
+    fn text(&mut self, simple: usize) {
+        if let Some(transform) = self.transform(simple) {
+            let path = self.entry_path(None);
+            self.schema.transforms.insert(path, transform);
+            let path = self.entry_path(Some(self.cdata_key));
+            self.schema.transforms.insert(path, transform);
+        }
+    }
+
+    fn element(&mut self, id: usize, repeated: bool) {
+        let Some(element) = self.model.elements.get(id) else {
+            return;
+        };
+        let complex = match element.kind {
+            Kind::Complex(complex) => Some(complex),
+            Kind::Any | Kind::Simple(_) => None,
+        };
+        let entered = complex
+            .and_then(|complex| self.open.iter().find(|(open, _)| *open == complex))
+            .map(|(_, depth)| *depth);
+        let saved_path = match entered {
+            // A recursive declaration: its content is matched at any depth below where it
+            // was first entered, which is read only once.
+            Some(_) if self.recursing => return,
+            Some(depth) => {
+                let mut path: Vec<String> = self.path.iter().take(depth).cloned().collect();
+                path.push("**".to_owned());
+                self.recursing = true;
+                Some(std::mem::replace(&mut self.path, path))
+            }
+            None => None,
+        };
+        self.path.push(element.name.clone());
+        if repeated {
+            let path = self.entry_path(None);
+            self.schema.lists.push(PathPattern::new(&path));
+        }
+        match element.kind {
+            Kind::Simple(simple) => self.text(simple),
+            Kind::Complex(complex) => {
+                self.open.push((complex, self.path.len()));
+                self.complex_type(complex);
+                self.open.pop();
+            }
+            Kind::Any => {}
+        }
+        self.path.pop();
+        if let Some(path) = saved_path {
+            self.path = path;
+            self.recursing = false;
+        }
+    }
+
+    fn complex_type(&mut self, id: usize) {
+        let Some(complex) = self.model.complex_types.get(id) else {
+            return;
+        };
+        for attribute in &complex.attributes {
+            let transform = attribute.kind.and_then(|simple| self.transform(simple));
+            if let Some(transform) = transform {
+                let key = format!("{}{}", self.attr_prefix, attribute.name);
+                let path = self.entry_path(Some(&key));
+                self.schema.transforms.insert(path, transform);
+            }
+        }
+        match &complex.content {
+            Content::Simple(simple) => self.text(*simple),
+            Content::Elements(particle) => self.particle(particle, false),
+            Content::Empty => {}
+        }
+    }
+
+    fn particle(&mut self, particle: &Particle, in_repeated_group: bool) {
+        let repeated = in_repeated_group || particle.is_repeated();
+        match &particle.term {
+            Term::Element(id) => self.element(*id, repeated),
+            Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => {
+                for particle in particles {
+                    self.particle(particle, repeated);
+                }
+            }
+            Term::Any => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::collect_events;
+    use crate::xmltodict_core::{Error, ParseConfig};
+
+    fn read(xml: &str) -> Result<Vec<(crate::events::XmlEvent, usize)>, Error> {
+        collect_events(xml.as_bytes(), &ParseConfig::default())
+    }
+
+    fn model(body: &str) -> Result<Model, Error> {
+        let xsd = format!(
+            r#"<xs:schema xmlns:xs="{}">{body}</xs:schema>"#,
+            builtins::XSD_NAMESPACE
+        );
+        Model::new(read(&xsd)?)
+    }
+
+    fn schema(body: &str) -> Result<Schema, Error> {
+        Ok(Schema::new(&model(body)?, "@", "#text"))
+    }
+
+    fn issues(body: &str, xml: &str) -> Result<Vec<String>, Error> {
+        let issues = validate(&model(body)?, &read(xml)?);
+        Ok(issues.iter().map(ToString::to_string).collect())
+    }
+
+    fn transform(schema: &Schema, path: &str) -> Option<Transform> {
+        let path: Vec<&str> = path.split('/').collect();
+        schema.transforms.find(&path).copied()
+    }
+
+    fn is_list(schema: &Schema, path: &str) -> bool {
+        let path: Vec<&str> = path.split('/').collect();
+        schema.lists.iter().any(|pattern| pattern.matches(&path))
+    }
+
+    #[test]
+    fn element_and_attribute_types() -> Result<(), Error> {
+        let schema = schema(
+            r#"<xs:element name="a"><xs:complexType><xs:sequence>
+                 <xs:element name="n" type="xs:long"/>
+                 <xs:element name="s" type="xs:string"/>
+               </xs:sequence><xs:attribute name="on" type="xs:boolean"/></xs:complexType>
+               </xs:element>"#,
+        )?;
+        assert_eq!(transform(&schema, "a/n"), Some(Transform::Int));
+        assert_eq!(transform(&schema, "a/n/#text"), Some(Transform::Int));
+        assert_eq!(transform(&schema, "a/@on"), Some(Transform::Bool));
+        assert_eq!(transform(&schema, "a/s"), None);
+        assert_eq!(transform(&schema, "n"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn named_types_and_references() -> Result<(), Error> {
+        let schema = schema(
+            r#"<xs:element name="a" type="A"/>
+               <xs:element name="price"><xs:simpleType>
+                 <xs:restriction base="Money"/>
+               </xs:simpleType></xs:element>
+               <xs:complexType name="A"><xs:sequence>
+                 <xs:element ref="price" maxOccurs="3"/>
+               </xs:sequence></xs:complexType>
+               <xs:simpleType name="Money"><xs:restriction base="xs:decimal"/></xs:simpleType>"#,
+        )?;
+        assert_eq!(transform(&schema, "a/price"), Some(Transform::Float));
+        assert!(is_list(&schema, "a/price"));
+        assert_eq!(transform(&schema, "price"), Some(Transform::Float));
+        assert!(!is_list(&schema, "price"));
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_groups() -> Result<(), Error> {
+        let schema = schema(
+            r#"<xs:element name="a"><xs:complexType>
+                 <xs:choice maxOccurs="unbounded">
+                   <xs:element name="b" type="xs:int"/>
+                   <xs:element name="c"><xs:complexType><xs:sequence>
+                     <xs:element name="d" maxOccurs="1"/>
+                   </xs:sequence></xs:complexType></xs:element>
+                 </xs:choice>
+               </xs:complexType></xs:element>"#,
+        )?;
+        assert!(is_list(&schema, "a/b"));
+        assert!(is_list(&schema, "a/c"));
+        assert!(!is_list(&schema, "a/c/d"));
+        assert!(!is_list(&schema, "a"));
+        Ok(())
+    }
+
+    #[test]
+    fn recursive_types_match_at_any_depth() -> Result<(), Error> {
+        let schema = schema(
+            r#"<xs:element name="tree" type="Node"/>
+               <xs:complexType name="Node"><xs:sequence>
+                 <xs:element name="v" type="xs:int"/>
+                 <xs:element name="node" type="Node" maxOccurs="unbounded"/>
+               </xs:sequence></xs:complexType>"#,
+        )?;
+        assert_eq!(transform(&schema, "tree/v"), Some(Transform::Int));
+        assert_eq!(
+            transform(&schema, "tree/node/node/node/v"),
+            Some(Transform::Int)
+        );
+        assert!(is_list(&schema, "tree/node/node"));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_documents_other_than_schemas() -> Result<(), Error> {
+        assert!(matches!(Model::new(read("<a/>")?), Err(Error::Value(_))));
+        let xsd = r#"<schema><element name="a"><complexType><sequence>
+                       <element name="b" maxOccurs="many"/>
+                     </sequence></complexType></element></schema>"#;
+        assert!(matches!(Model::new(read(xsd)?), Err(Error::Value(_))));
+        Ok(())
+    }
+
+    const ORDER: &str = r#"<xs:element name="order"><xs:complexType>
+          <xs:sequence>
+            <xs:element name="id" type="xs:int"/>
+            <xs:element name="line" maxOccurs="unbounded"><xs:simpleType>
+              <xs:restriction base="xs:decimal"><xs:minExclusive value="0"/></xs:restriction>
+            </xs:simpleType></xs:element>
+            <xs:element name="note" type="xs:string" minOccurs="0"/>
+          </xs:sequence>
+          <xs:attribute name="state" use="required"><xs:simpleType>
+            <xs:restriction base="xs:string">
+              <xs:enumeration value="open"/><xs:enumeration value="paid"/>
+            </xs:restriction>
+          </xs:simpleType></xs:attribute>
+        </xs:complexType></xs:element>"#;
+
+    #[test]
+    fn valid_documents_have_no_issues() -> Result<(), Error> {
+        let xml = r#"<order state="paid"><id> 7 </id><line>1.5</line><line>2</line></order>"#;
+        assert_eq!(issues(ORDER, xml)?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn content_model_issues() -> Result<(), Error> {
+        assert_eq!(
+            issues(ORDER, r#"<order state="open"><id>1</id><note/></order>"#)?,
+            ["order/note: expected <line>, found <note> (line 1)"]
+        );
+        assert_eq!(
+            issues(
+                ORDER,
+                r#"<order state="open"><id>1</id><line>1</line><x/></order>"#
+            )?,
+            ["order/x: expected <line> or <note> or end of <order>, found <x> (line 1)"]
+        );
+        assert_eq!(
+            issues(ORDER, r#"<order state="open"><id>1</id></order>"#)?,
+            ["order: expected <line>, found nothing (line 1)"]
+        );
+        assert_eq!(
+            issues(ORDER, "<invoice/>")?,
+            ["invoice: expected <order>, found <invoice> (line 1)"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn value_and_attribute_issues() -> Result<(), Error> {
+        let xml = "<order state=\"new\" x=\"1\">\n<id>1.5</id><line>0</line>text</order>";
+        assert_eq!(
+            issues(ORDER, xml)?,
+            [
+                r#"order/@state: expected one of "open", "paid", found "new" (line 1)"#,
+                "order/@x: expected one of the attributes state, found attribute x (line 1)",
+                r#"order/id: expected xs:int, found "1.5" (line 2)"#,
+                r#"order/line: expected a value > 0, found "0" (line 2)"#,
+                r#"order: expected no text, found "text" (line 1)"#,
+            ]
+        );
+        assert_eq!(
+            issues(ORDER, "<order><id>1</id><line>1</line></order>")?,
+            ["order/@state: expected attribute state, found nothing (line 1)"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn all_groups_and_wildcards() -> Result<(), Error> {
+        let xsd = r#"<xs:element name="a"><xs:complexType><xs:all>
+                       <xs:element name="b"/><xs:element name="c" minOccurs="0"/>
+                     </xs:all></xs:complexType></xs:element>
+                     <xs:element name="d"><xs:complexType><xs:sequence>
+                       <xs:any maxOccurs="unbounded"/>
+                     </xs:sequence></xs:complexType></xs:element>"#;
+        assert!(issues(xsd, "<a><c/><b/></a>")?.is_empty());
+        assert!(issues(xsd, "<a><b/></a>")?.is_empty());
+        assert_eq!(
+            issues(xsd, "<a><c/><c/></a>")?,
+            ["a/c: expected <b>, found <c> (line 1)"]
+        );
+        assert!(issues(xsd, "<d><x/><a><b/></a></d>")?.is_empty());
+        assert_eq!(
+            issues(xsd, "<d><a/></d>")?,
+            ["d/a: expected <b> or <c>, found nothing (line 1)"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn names_are_matched_with_their_namespace() -> Result<(), Error> {
+        let xsd = format!(
+            r#"<xs:schema xmlns:xs="{}" targetNamespace="urn:t">
+                 <xs:element name="a"><xs:complexType><xs:sequence>
+                   <xs:element name="b"/>
+                   <xs:element name="c" form="qualified"/>
+                 </xs:sequence><xs:attribute name="x"/></xs:complexType></xs:element>
+               </xs:schema>"#,
+            builtins::XSD_NAMESPACE
+        );
+        let model = Model::new(read(&xsd)?)?;
+        let issues = |xml: &str| -> Result<Vec<String>, Error> {
+            let issues = validate(&model, &read(xml)?);
+            Ok(issues.iter().map(ToString::to_string).collect())
+        };
+        assert!(issues(r#"<t:a xmlns:t="urn:t" x="1"><b/><t:c/></t:a>"#)?.is_empty());
+        assert!(issues(r#"<a xmlns="urn:t"><b xmlns=""/><c/></a>"#)?.is_empty());
+        assert_eq!(
+            issues("<a><b/><c/></a>")?,
+            ["a: expected <{urn:t}a>, found <a> (line 1)"]
+        );
+        assert_eq!(
+            issues(r#"<t:a xmlns:t="urn:t" t:x="1"><t:b/><c/></t:a>"#)?,
+            [
+                "t:a/@t:x: expected one of the attributes x, found attribute t:x (line 1)",
+                "t:a/t:b: expected <b>, found <{urn:t}b> (line 1)",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_constructs_that_are_not_supported() {
+        let unsupported = [
+            r#"<xs:include schemaLocation="other.xsd"/>"#,
+            r#"<xs:import namespace="urn:o" schemaLocation="other.xsd"/>"#,
+            r#"<xs:element name="a" substitutionGroup="b"/><xs:element name="b"/>"#,
+            r#"<xs:element name="a"><xs:complexType><xs:sequence>
+                 <xs:element name="b"/><xs:assert test="b"/>
+               </xs:sequence></xs:complexType></xs:element>"#,
+        ];
+        for body in unsupported {
+            assert!(matches!(model(body), Err(Error::Value(_))), "{body}");
+        }
+        assert!(model(
+            r#"<xs:annotation/><xs:element name="a"><xs:complexType><xs:sequence>
+                 <xs:annotation/><xs:element name="b"/>
+               </xs:sequence></xs:complexType></xs:element>"#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn builtin_values() {
+        let valid = [
+            ("int", "-2147483648"),
+            ("unsignedByte", "255"),
+            (
+                "positiveInteger",
+                "+123456789012345678901234567890123456789012",
+            ),
+            ("decimal", "-.5"),
+            ("double", "1.5E-3"),
+            ("float", "-INF"),
+            ("boolean", "0"),
+            ("date", "2024-02-29+05:30"),
+            ("time", "24:00:00"),
+            ("dateTime", "2024-01-02T03:04:05.123Z"),
+        ];
+        for (name, text) in valid {
+            assert!(builtins::is_valid(name, text), "{name} {text}");
+        }
+        let invalid = [
+            ("int", "2147483648"),
+            ("unsignedByte", "-1"),
+            ("positiveInteger", "0"),
+            ("decimal", "."),
+            ("double", "1e"),
+            ("boolean", "yes"),
+            ("date", "2024-13-01"),
+            ("time", "10:00"),
+            ("dateTime", "2024-01-02 03:04:05"),
+        ];
+        for (name, text) in invalid {
+            assert!(!builtins::is_valid(name, text), "{name} {text}");
+        }
+    }
+}
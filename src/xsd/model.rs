@@ -0,0 +1,888 @@
+use super::builtins::XSD_NAMESPACE;
+use crate::events::XmlEvent;
+use crate::xmltodict_core::Error;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// The declarations of an XML Schema, with named types, groups and references resolved.
+/// Declarations refer to each other by their index in the lists, so recursive types are
+/// read once.
+#[derive(Default)]
+pub struct Model {
+    /// Global elements, which may be the root of a document, by [`expanded_name`].
+    pub roots: HashMap<String, usize>,
+    pub elements: Vec<Element>,
+    pub complex_types: Vec<ComplexType>,
+    pub simple_types: Vec<SimpleType>,
+    /// Whether documents are matched by expanded names. Models of RELAX NG schemas and of
+    /// sample documents have no namespaces, and match local names.
+    pub namespaced: bool,
+}
+
+pub struct Element {
+    /// The local name.
+    pub name: String,
+    /// The namespace URI of the name, empty for none.
+    pub namespace: String,
+    pub kind: Kind,
+}
+
+impl Element {
+    #[must_use]
+    pub fn expanded_name(&self) -> Cow<'_, str> {
+        expanded_name(&self.namespace, &self.name)
+    }
+}
+
+/// The type of an element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// `xs:anyType`, or no type given: any attributes and content.
+    Any,
+    Simple(usize),
+    Complex(usize),
+}
+
+#[derive(Clone, Default)]
+pub struct ComplexType {
+    pub attributes: Vec<Attribute>,
+    /// Whether `xs:anyAttribute` allows attributes that are not declared.
+    pub any_attribute: bool,
+    pub content: Content,
+    /// Whether text may appear between child elements.
+    pub mixed: bool,
+}
+
+#[derive(Clone, Default)]
+pub enum Content {
+    #[default]
+    Empty,
+    /// Text of a simple type, with `xs:simpleContent`.
+    Simple(usize),
+    Elements(Particle),
+}
+
+#[derive(Clone)]
+pub struct Attribute {
+    /// The local name.
+    pub name: String,
+    /// The namespace URI of the name, empty for none.
+    pub namespace: String,
+    /// The simple type of the value; `None` accepts any text.
+    pub kind: Option<usize>,
+    pub required: bool,
+}
+
+/// Part of a content model, occurring between `min` and `max` times; `max` is `None` for
+/// `unbounded`.
+#[derive(Clone)]
+pub struct Particle {
+    pub min: usize,
+    pub max: Option<usize>,
+    pub term: Term,
+}
+
+impl Particle {
+    /// Whether the particle may occur more than once.
+    #[must_use]
+    pub fn is_repeated(&self) -> bool {
+        self.max.is_none_or(|max| max > 1)
+    }
+}
+
+#[derive(Clone)]
+pub enum Term {
+    Element(usize),
+    /// `xs:any`: one element of any name.
+    Any,
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+    /// Each particle once, in any order.
+    All(Vec<Particle>),
+}
+
+#[derive(Clone)]
+pub struct SimpleType {
+    /// The type's name, or for an anonymous type a description of it, for messages.
+    pub name: String,
+    pub variety: Variety,
+    /// Facets of the type and of the types it restricts, all of which a value must meet.
+    pub facets: Vec<Facet>,
+}
+
+#[derive(Clone)]
+pub enum Variety {
+    /// Values of a built-in type, by its local name.
+    Atomic(String),
+    /// Whitespace-separated values of a simple type.
+    List(usize),
+    /// Values of any of the simple types.
+    Union(Vec<usize>),
+}
+
+#[derive(Clone)]
+pub enum Facet {
+    Enumeration(Vec<String>),
+    /// `minInclusive`, `maxExclusive` and the like: `limit` as written and as a number.
+    Bound {
+        text: String,
+        limit: f64,
+        min: bool,
+        inclusive: bool,
+    },
+    Length(usize),
+    MinLength(usize),
+    MaxLength(usize),
+}
+
+impl Model {
+    /// Reads the events of a schema document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Value`] if the root is not `xs:schema`, the schema uses a construct
+    /// that is not supported, such as `xs:include` or `xs:import`, or a `minOccurs` or
+    /// `maxOccurs` is not a number.
+    pub fn new(events: Vec<(XmlEvent, usize)>) -> Result<Self, Error> {
+        let root = Node::tree(events).unwrap_or_default();
+        if root.local() != "schema" {
+            return Err(Error::Value(format!(
+                "xsd must have an xs:schema root element, not {:?}",
+                root.name
+            )));
+        }
+        for child in &root.children {
+            match child.local() {
+                "element" | "attribute" | "complexType" | "simpleType" | "group"
+                | "attributeGroup" | "annotation" | "notation" => {}
+                "include" | "import" | "redefine" | "override" => {
+                    return Err(Error::Value(format!(
+                        "xs:{} is not supported in xsd; declarations must be in one schema \
+                         document",
+                        child.local()
+                    )))
+                }
+                other => return Err(unsupported(other)),
+            }
+        }
+        let mut compiler = Compiler::new(&root);
+        for element in root.children("element") {
+            if let Some(name) = element.attribute("name") {
+                compiler.global_element(name)?;
+            }
+        }
+        compiler.model.namespaced = true;
+        Ok(compiler.model)
+    }
+
+    /// The built-in type of the values of a simple type, unless it is a list or union.
+    #[must_use]
+    pub fn builtin(&self, simple: usize) -> Option<&str> {
+        match &self.simple_types.get(simple)?.variety {
+            Variety::Atomic(builtin) => Some(builtin),
+            Variety::List(_) | Variety::Union(_) => None,
+        }
+    }
+}
+
+/// The name without any `prefix:`.
+pub fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+/// A name with its namespace URI, as `{uri}local`, or the local name when it has none.
+#[must_use]
+pub fn expanded_name<'n>(namespace: &str, local: &'n str) -> Cow<'n, str> {
+    if namespace.is_empty() {
+        Cow::Borrowed(local)
+    } else {
+        Cow::Owned(format!("{{{namespace}}}{local}"))
+    }
+}
+
+fn unsupported(construct: &str) -> Error {
+    Error::Value(format!("xs:{construct} is not supported in xsd"))
+}
+
+/// An element of the schema document, with its children in document order.
+#[derive(Default)]
+struct Node {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn tree(events: Vec<(XmlEvent, usize)>) -> Option<Self> {
+        let mut open: Vec<Self> = Vec::new();
+        for (event, _) in events {
+            match event {
+                XmlEvent::Start { name, attributes } => open.push(Self {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                }),
+                XmlEvent::End(_) => {
+                    let node = open.pop()?;
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => return Some(node),
+                    }
+                }
+                XmlEvent::Text(_)
+                | XmlEvent::Whitespace(_)
+                | XmlEvent::DocType(_)
+                | XmlEvent::Comment(_)
+                | XmlEvent::ProcessingInstruction(_) => {}
+            }
+        }
+        None
+    }
+
+    fn local(&self) -> &str {
+        local_name(&self.name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn children<'a>(&'a self, local: &'a str) -> impl Iterator<Item = &'a Self> {
+        self.children
+            .iter()
+            .filter(move |child| child.local() == local)
+    }
+
+    fn child<'a>(&'a self, local: &'a str) -> Option<&'a Self> {
+        self.children(local).next()
+    }
+
+    /// The model group, or reference to one, that gives a complex type its content.
+    fn model_group(&self) -> Option<&Self> {
+        self.children
+            .iter()
+            .find(|child| matches!(child.local(), "sequence" | "choice" | "all" | "group"))
+    }
+
+    fn is_true(&self, name: &str) -> bool {
+        matches!(self.attribute(name).map(str::trim), Some("true" | "1"))
+    }
+}
+
+/// `minOccurs` and `maxOccurs` of a particle.
+fn occurs(node: &Node) -> Result<(usize, Option<usize>), Error> {
+    let number = |name: &str, text: &str| {
+        text.trim().parse::<usize>().map_err(|_err| {
+            Error::Value(format!(
+                "{name} in xsd must be a number or 'unbounded', not {text:?}"
+            ))
+        })
+    };
+    let min = node
+        .attribute("minOccurs")
+        .map_or(Ok(1), |min| number("minOccurs", min))?;
+    let max = match node.attribute("maxOccurs").map(str::trim) {
+        None => Some(1),
+        Some("unbounded") => None,
+        Some(max) => Some(number("maxOccurs", max)?),
+    };
+    Ok((min, max))
+}
+
+/// Builds the [`Model`] from the declarations of a schema document.
+struct Compiler<'a> {
+    /// Namespace URIs by prefix, with `""` for the default namespace.
+    namespaces: HashMap<&'a str, &'a str>,
+    /// The `targetNamespace` of global declarations, empty for none.
+    target_namespace: &'a str,
+    /// `elementFormDefault` and `attributeFormDefault`: whether local declarations are in
+    /// the target namespace unless their `form` says otherwise.
+    elements_qualified: bool,
+    attributes_qualified: bool,
+    elements: HashMap<&'a str, &'a Node>,
+    attributes: HashMap<&'a str, &'a Node>,
+    complex_types: HashMap<&'a str, &'a Node>,
+    simple_types: HashMap<&'a str, &'a Node>,
+    groups: HashMap<&'a str, &'a Node>,
+    attribute_groups: HashMap<&'a str, &'a Node>,
+    /// Indexes of the named declarations already read, or being read.
+    element_ids: HashMap<&'a str, usize>,
+    complex_ids: HashMap<&'a str, usize>,
+    simple_ids: HashMap<&'a str, usize>,
+    builtin_ids: HashMap<&'a str, usize>,
+    /// Group references being followed, to stop at groups containing themselves.
+    open_groups: Vec<&'a str>,
+    model: Model,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(root: &'a Node) -> Self {
+        let namespaces = root
+            .attributes
+            .iter()
+            .filter_map(|(key, value)| {
+                let prefix = key.strip_prefix("xmlns")?;
+                match prefix.strip_prefix(':') {
+                    Some(prefix) => Some((prefix, value.as_str())),
+                    None => prefix.is_empty().then_some(("", value.as_str())),
+                }
+            })
+            .collect();
+        let named = |local: &'a str| {
+            root.children(local)
+                .filter_map(|declaration| Some((declaration.attribute("name")?, declaration)))
+                .collect::<HashMap<_, _>>()
+        };
+        let qualified = |name| root.attribute(name).map(str::trim) == Some("qualified");
+        Self {
+            namespaces,
+            target_namespace: root.attribute("targetNamespace").unwrap_or_default(),
+            elements_qualified: qualified("elementFormDefault"),
+            attributes_qualified: qualified("attributeFormDefault"),
+            elements: named("element"),
+            attributes: named("attribute"),
+            complex_types: named("complexType"),
+            simple_types: named("simpleType"),
+            groups: named("group"),
+            attribute_groups: named("attributeGroup"),
+            element_ids: HashMap::new(),
+            complex_ids: HashMap::new(),
+            simple_ids: HashMap::new(),
+            builtin_ids: HashMap::new(),
+            open_groups: Vec::new(),
+            model: Model::default(),
+        }
+    }
+
+    /// The local name of a type name if it refers to a built-in type.
+    fn builtin_name(&self, qname: &'a str) -> Option<&'a str> {
+        let (prefix, local) = qname.rsplit_once(':').unwrap_or(("", qname));
+        (self.namespaces.get(prefix) == Some(&XSD_NAMESPACE)).then_some(local)
+    }
+
+    fn global_element(&mut self, name: &'a str) -> Result<Option<usize>, Error> {
+        if let Some(id) = self.element_ids.get(name) {
+            return Ok(Some(*id));
+        }
+        let Some(node) = self.elements.get(name).copied() else {
+            return Ok(None);
+        };
+        let id = self.model.elements.len();
+        let namespace = self.target_namespace;
+        self.model.elements.push(Element {
+            name: name.to_owned(),
+            namespace: namespace.to_owned(),
+            kind: Kind::Any,
+        });
+        self.element_ids.insert(name, id);
+        let root = expanded_name(namespace, name).into_owned();
+        self.model.roots.insert(root, id);
+        let element = self.element(node, namespace)?;
+        if let Some(slot) = self.model.elements.get_mut(id) {
+            *slot = element;
+        }
+        Ok(Some(id))
+    }
+
+    /// The namespace of a local declaration, following its `form` or the schema's default.
+    fn local_namespace(&self, node: &Node, qualified: bool) -> &'a str {
+        let qualified = match node.attribute("form").map(str::trim) {
+            Some(form) => form == "qualified",
+            None => qualified,
+        };
+        if qualified {
+            self.target_namespace
+        } else {
+            ""
+        }
+    }
+
+    fn element(&mut self, node: &'a Node, namespace: &str) -> Result<Element, Error> {
+        if node.attribute("substitutionGroup").is_some() {
+            return Err(Error::Value(
+                "substitution groups are not supported in xsd".to_owned(),
+            ));
+        }
+        let name = node.attribute("name").unwrap_or_default().to_owned();
+        let kind = if let Some(type_name) = node.attribute("type") {
+            self.named_type(type_name)?
+        } else if let Some(simple) = node.child("simpleType") {
+            Kind::Simple(self.anonymous_simple_type(simple)?)
+        } else if let Some(complex) = node.child("complexType") {
+            let complex = self.complex_type(complex)?;
+            self.model.complex_types.push(complex);
+            Kind::Complex(self.model.complex_types.len() - 1)
+        } else {
+            Kind::Any
+        };
+        Ok(Element {
+            name,
+            namespace: namespace.to_owned(),
+            kind,
+        })
+    }
+
+    /// The type a `type` or `base` attribute names. Types that are not declared accept
+    /// anything.
+    fn named_type(&mut self, qname: &'a str) -> Result<Kind, Error> {
+        if let Some(builtin) = self.builtin_name(qname) {
+            if builtin == "anyType" {
+                return Ok(Kind::Any);
+            }
+            return Ok(Kind::Simple(self.builtin(builtin)));
+        }
+        let name = local_name(qname);
+        if self.complex_types.contains_key(name) {
+            return self.named_complex_type(name).map(Kind::Complex);
+        }
+        Ok(self
+            .named_simple_type(name)?
+            .map_or(Kind::Any, Kind::Simple))
+    }
+
+    fn simple_type_named(&mut self, qname: &'a str) -> Result<Option<usize>, Error> {
+        match self.named_type(qname)? {
+            Kind::Simple(id) => Ok(Some(id)),
+            Kind::Any | Kind::Complex(_) => Ok(None),
+        }
+    }
+
+    fn builtin(&mut self, name: &'a str) -> usize {
+        if let Some(id) = self.builtin_ids.get(name) {
+            return *id;
+        }
+        let id = self.model.simple_types.len();
+        self.model.simple_types.push(SimpleType {
+            name: format!("xs:{name}"),
+            variety: Variety::Atomic(name.to_owned()),
+            facets: Vec::new(),
+        });
+        self.builtin_ids.insert(name, id);
+        id
+    }
+
+    fn named_complex_type(&mut self, name: &'a str) -> Result<usize, Error> {
+        if let Some(id) = self.complex_ids.get(name) {
+            return Ok(*id);
+        }
+        let id = self.model.complex_types.len();
+        self.model.complex_types.push(ComplexType::default());
+        self.complex_ids.insert(name, id);
+        if let Some(node) = self.complex_types.get(name).copied() {
+            let complex = self.complex_type(node)?;
+            if let Some(slot) = self.model.complex_types.get_mut(id) {
+                *slot = complex;
+            }
+        }
+        Ok(id)
+    }
+
+    fn complex_type(&mut self, node: &'a Node) -> Result<ComplexType, Error> {
+        let mut complex = ComplexType {
+            mixed: node.is_true("mixed"),
+            ..ComplexType::default()
+        };
+        if let Some(content) = node.child("simpleContent") {
+            let derivation = content
+                .child("extension")
+                .or_else(|| content.child("restriction"));
+            if let Some(derivation) = derivation {
+                match derivation
+                    .attribute("base")
+                    .map(|base| self.named_type(base))
+                    .transpose()?
+                {
+                    Some(Kind::Complex(base)) => {
+                        complex = self
+                            .model
+                            .complex_types
+                            .get(base)
+                            .cloned()
+                            .unwrap_or_default();
+                        complex.mixed = false;
+                    }
+                    Some(Kind::Simple(base)) => complex.content = Content::Simple(base),
+                    Some(Kind::Any) | None => {}
+                }
+                let facets = facets(derivation);
+                if let (Content::Simple(base), false) = (&complex.content, facets.is_empty()) {
+                    complex.content = Content::Simple(self.restricted(*base, facets));
+                }
+                self.attributes(derivation, &mut complex)?;
+            }
+        } else if let Some(content) = node.child("complexContent") {
+            complex.mixed |= content.is_true("mixed");
+            if let Some(extension) = content.child("extension") {
+                if let Some(base) = extension.attribute("base") {
+                    if let Kind::Complex(base) = self.named_type(base)? {
+                        let base = self.model.complex_types.get(base).cloned();
+                        let base = base.unwrap_or_default();
+                        complex.attributes = base.attributes;
+                        complex.any_attribute = base.any_attribute;
+                        complex.content = base.content;
+                    }
+                }
+                let own = extension.model_group().map(|group| self.particle(group));
+                if let Some(own) = own.transpose()?.flatten() {
+                    complex.content = match std::mem::take(&mut complex.content) {
+                        Content::Elements(base) => Content::Elements(Particle {
+                            min: 1,
+                            max: Some(1),
+                            term: Term::Sequence(vec![base, own]),
+                        }),
+                        Content::Empty | Content::Simple(_) => Content::Elements(own),
+                    };
+                }
+                self.attributes(extension, &mut complex)?;
+            } else if let Some(restriction) = content.child("restriction") {
+                // A restriction states the whole content it keeps of its base.
+                if let Some(base) = restriction.attribute("base") {
+                    if let Kind::Complex(base) = self.named_type(base)? {
+                        let base = self.model.complex_types.get(base);
+                        complex.attributes =
+                            base.map(|base| base.attributes.clone()).unwrap_or_default();
+                    }
+                }
+                self.content(restriction, &mut complex)?;
+            }
+        } else {
+            self.content(node, &mut complex)?;
+        }
+        Ok(complex)
+    }
+
+    /// Reads the model group and attributes directly inside `parent`.
+    fn content(&mut self, parent: &'a Node, complex: &mut ComplexType) -> Result<(), Error> {
+        if let Some(group) = parent.model_group() {
+            if let Some(particle) = self.particle(group)? {
+                complex.content = Content::Elements(particle);
+            }
+        }
+        self.attributes(parent, complex)
+    }
+
+    fn particle(&mut self, node: &'a Node) -> Result<Option<Particle>, Error> {
+        let (min, max) = occurs(node)?;
+        let term = match node.local() {
+            "element" => {
+                if let Some(reference) = node.attribute("ref") {
+                    match self.global_element(local_name(reference))? {
+                        Some(id) => Term::Element(id),
+                        None => return Ok(None),
+                    }
+                } else {
+                    let namespace = self.local_namespace(node, self.elements_qualified);
+                    let element = self.element(node, namespace)?;
+                    self.model.elements.push(element);
+                    Term::Element(self.model.elements.len() - 1)
+                }
+            }
+            "any" => Term::Any,
+            "annotation" => return Ok(None),
+            "sequence" | "choice" | "all" => {
+                let mut particles = Vec::new();
+                for child in &node.children {
+                    particles.extend(self.particle(child)?);
+                }
+                match node.local() {
+                    "sequence" => Term::Sequence(particles),
+                    "choice" => Term::Choice(particles),
+                    _ => Term::All(particles),
+                }
+            }
+            "group" => {
+                let Some(name) = node.attribute("ref").map(local_name) else {
+                    return Ok(None);
+                };
+                let Some(group) = self.groups.get(name).copied() else {
+                    return Ok(None);
+                };
+                if self.open_groups.contains(&name) {
+                    return Ok(None);
+                }
+                self.open_groups.push(name);
+                let particle = group.model_group().map(|inner| self.particle(inner));
+                self.open_groups.pop();
+                let Some(particle) = particle.transpose()?.flatten() else {
+                    return Ok(None);
+                };
+                particle.term
+            }
+            other => return Err(unsupported(other)),
+        };
+        Ok(Some(Particle { min, max, term }))
+    }
+
+    /// Adds the attributes declared directly inside `parent`, or through attribute groups,
+    /// replacing those of the same name from a base type.
+    fn attributes(&mut self, parent: &'a Node, complex: &mut ComplexType) -> Result<(), Error> {
+        for child in &parent.children {
+            match child.local() {
+                "attribute" => self.attribute(child, complex)?,
+                "attributeGroup" => {
+                    let group = child
+                        .attribute("ref")
+                        .map(local_name)
+                        .and_then(|name| Some((name, self.attribute_groups.get(name).copied()?)));
+                    if let Some((name, group)) = group {
+                        if !self.open_groups.contains(&name) {
+                            self.open_groups.push(name);
+                            let result = self.attributes(group, complex);
+                            self.open_groups.pop();
+                            result?;
+                        }
+                    }
+                }
+                "anyAttribute" => complex.any_attribute = true,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self, node: &'a Node, complex: &mut ComplexType) -> Result<(), Error> {
+        let (declaration, namespace) = match node.attribute("ref") {
+            Some(reference) => match self.attributes.get(local_name(reference)) {
+                Some(global) => (*global, self.target_namespace),
+                None => return Ok(()),
+            },
+            None => (node, self.local_namespace(node, self.attributes_qualified)),
+        };
+        let Some(name) = declaration.attribute("name") else {
+            return Ok(());
+        };
+        complex
+            .attributes
+            .retain(|attribute| attribute.name != name || attribute.namespace != namespace);
+        let usage = node.attribute("use").map(str::trim);
+        if usage == Some("prohibited") {
+            return Ok(());
+        }
+        let kind = match declaration.attribute("type") {
+            Some(type_name) => self.simple_type_named(type_name)?,
+            None => declaration
+                .child("simpleType")
+                .map(|simple| self.anonymous_simple_type(simple))
+                .transpose()?,
+        };
+        complex.attributes.push(Attribute {
+            name: name.to_owned(),
+            namespace: namespace.to_owned(),
+            kind,
+            required: usage == Some("required"),
+        });
+        Ok(())
+    }
+
+    fn named_simple_type(&mut self, name: &'a str) -> Result<Option<usize>, Error> {
+        if let Some(id) = self.simple_ids.get(name) {
+            return Ok(Some(*id));
+        }
+        let Some(node) = self.simple_types.get(name).copied() else {
+            return Ok(None);
+        };
+        // Types restricting each other in a loop are invalid; the loop accepts anything.
+        let id = self.builtin("anySimpleType");
+        self.simple_ids.insert(name, id);
+        let mut simple = self.simple_type(node)?;
+        name.clone_into(&mut simple.name);
+        let id = self.model.simple_types.len();
+        self.model.simple_types.push(simple);
+        self.simple_ids.insert(name, id);
+        Ok(Some(id))
+    }
+
+    fn anonymous_simple_type(&mut self, node: &'a Node) -> Result<usize, Error> {
+        let simple = self.simple_type(node)?;
+        self.model.simple_types.push(simple);
+        Ok(self.model.simple_types.len() - 1)
+    }
+
+    /// A simple type, named after its base, item or member types.
+    fn simple_type(&mut self, node: &'a Node) -> Result<SimpleType, Error> {
+        if let Some(restriction) = node.child("restriction") {
+            let base = match restriction.attribute("base") {
+                Some(base) => self.simple_type_named(base)?,
+                None => restriction
+                    .child("simpleType")
+                    .map(|simple| self.anonymous_simple_type(simple))
+                    .transpose()?,
+            };
+            let base = base.unwrap_or_else(|| self.builtin("anySimpleType"));
+            let id = self.restricted(base, facets(restriction));
+            return Ok(self.cloned_simple_type(id));
+        }
+        if let Some(list) = node.child("list") {
+            let item = match list.attribute("itemType") {
+                Some(item) => self.simple_type_named(item)?,
+                None => list
+                    .child("simpleType")
+                    .map(|simple| self.anonymous_simple_type(simple))
+                    .transpose()?,
+            };
+            let item = item.unwrap_or_else(|| self.builtin("anySimpleType"));
+            return Ok(SimpleType {
+                name: format!("a list of {}", self.simple_name(item)),
+                variety: Variety::List(item),
+                facets: Vec::new(),
+            });
+        }
+        let mut members = Vec::new();
+        if let Some(union) = node.child("union") {
+            for member in union
+                .attribute("memberTypes")
+                .unwrap_or_default()
+                .split_whitespace()
+            {
+                members.extend(self.simple_type_named(member)?);
+            }
+            for simple in union.children("simpleType") {
+                members.push(self.anonymous_simple_type(simple)?);
+            }
+        }
+        if members.is_empty() {
+            let id = self.builtin("anySimpleType");
+            return Ok(self.cloned_simple_type(id));
+        }
+        let names: Vec<&str> = members.iter().map(|id| self.simple_name(*id)).collect();
+        Ok(SimpleType {
+            name: names.join(" or "),
+            variety: Variety::Union(members),
+            facets: Vec::new(),
+        })
+    }
+
+    fn cloned_simple_type(&self, id: usize) -> SimpleType {
+        self.model
+            .simple_types
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| SimpleType {
+                name: "xs:anySimpleType".to_owned(),
+                variety: Variety::Atomic("anySimpleType".to_owned()),
+                facets: Vec::new(),
+            })
+    }
+
+    fn simple_name(&self, id: usize) -> &str {
+        self.model
+            .simple_types
+            .get(id)
+            .map_or("xs:anySimpleType", |simple| simple.name.as_str())
+    }
+
+    /// A type restricting `base` with further facets.
+    fn restricted(&mut self, base: usize, facets: Vec<Facet>) -> usize {
+        if facets.is_empty() {
+            return base;
+        }
+        let Some(mut simple) = self.model.simple_types.get(base).cloned() else {
+            return base;
+        };
+        simple.facets.extend(facets);
+        self.model.simple_types.push(simple);
+        self.model.simple_types.len() - 1
+    }
+}
+
+/// The facets of a restriction; the enumerated values together make up one facet.
+fn facets(restriction: &Node) -> Vec<Facet> {
+    let mut facets = Vec::new();
+    let mut values = Vec::new();
+    for facet in &restriction.children {
+        let Some(value) = facet.attribute("value") else {
+            continue;
+        };
+        if facet.local() == "enumeration" {
+            values.push(value.to_owned());
+        } else {
+            facets.extend(Facet::new(facet.local(), value));
+        }
+    }
+    if !values.is_empty() {
+        facets.push(Facet::Enumeration(values));
+    }
+    facets
+}
+
+impl Facet {
+    /// A bound or length facet by its local name. Other facets, such as `pattern` or
+    /// `totalDigits`, are not checked.
+    #[must_use]
+    pub fn new(name: &str, value: &str) -> Option<Self> {
+        let value = value.trim();
+        let length = || value.parse::<usize>().ok();
+        let bound = |min: bool, inclusive: bool| {
+            value.parse::<f64>().ok().map(|limit| Self::Bound {
+                text: value.to_owned(),
+                limit,
+                min,
+                inclusive,
+            })
+        };
+        match name {
+            "minInclusive" => bound(true, true),
+            "minExclusive" => bound(true, false),
+            "maxInclusive" => bound(false, true),
+            "maxExclusive" => bound(false, false),
+            "length" => length().map(Self::Length),
+            "minLength" => length().map(Self::MinLength),
+            "maxLength" => length().map(Self::MaxLength),
+            _ => None,
+        }
+    }
+
+    /// Whether a value meets the facet. `length` counts characters, or items for lists, and
+    /// bounds only apply to numbers.
+    #[must_use]
+    pub fn allows(&self, value: &str, length: usize, number: Option<f64>) -> bool {
+        match self {
+            Self::Enumeration(values) => values.iter().any(|allowed| allowed == value),
+            Self::Bound {
+                limit,
+                min,
+                inclusive,
+                ..
+            } => number.is_none_or(|number| match (min, inclusive) {
+                (true, true) => number >= *limit,
+                (true, false) => number > *limit,
+                (false, true) => number <= *limit,
+                (false, false) => number < *limit,
+            }),
+            Self::Length(expected) => length == *expected,
+            Self::MinLength(min) => length >= *min,
+            Self::MaxLength(max) => length <= *max,
+        }
+    }
+
+    /// What the facet expects, for messages.
+    #[must_use]
+    pub fn describe(&self, unit: &str) -> String {
+        match self {
+            Self::Enumeration(values) => {
+                let values: Vec<String> = values.iter().map(|value| format!("{value:?}")).collect();
+                format!("one of {}", values.join(", "))
+            }
+            Self::Bound {
+                text,
+                min,
+                inclusive,
+                ..
+            } => {
+                let operator = match (min, inclusive) {
+                    (true, true) => ">=",
+                    (true, false) => ">",
+                    (false, true) => "<=",
+                    (false, false) => "<",
+                };
+                format!("a value {operator} {text}")
+            }
+            Self::Length(length) => format!("exactly {length} {unit}"),
+            Self::MinLength(min) => format!("at least {min} {unit}"),
+            Self::MaxLength(max) => format!("at most {max} {unit}"),
+        }
+    }
+}
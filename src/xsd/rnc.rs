@@ -0,0 +1,1042 @@
+use super::model::{
+    local_name, Attribute, ComplexType, Content, Element, Facet, Kind, Model, Particle, SimpleType,
+    Term, Variety,
+};
+use crate::xmltodict_core::Error;
+use std::collections::HashMap;
+
+/// Datatype library of the XML Schema built-in types, which the `xsd` prefix names.
+const XSD_DATATYPES: &str = "http://www.w3.org/2001/XMLSchema-datatypes";
+
+impl Model {
+    /// Reads a RELAX NG schema in the compact syntax: `element`, `attribute`, `text`,
+    /// `empty`, `mixed`, datatypes and values, groups, choices and the `?`, `*` and `+`
+    /// repetitions, either as one pattern or as a grammar of named patterns with a `start`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Value`] for a malformed schema, a reference to a pattern that is not
+    /// defined, or syntax outside that subset, such as `&`, `list` or `include`.
+    pub fn from_rnc(schema: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            tokens: tokenize(schema)?,
+            position: 0,
+            datatypes: HashMap::from([("xsd".to_owned(), XSD_DATATYPES.to_owned())]),
+        };
+        let grammar = parser.grammar()?;
+        let Some(start) = &grammar.start else {
+            return Err(Error::Value("rnc schema has no start pattern".to_owned()));
+        };
+        let mut compiler = Compiler {
+            definitions: &grammar.definitions,
+            element_ids: HashMap::new(),
+            open_refs: Vec::new(),
+            datatype_ids: HashMap::new(),
+            model: Model::default(),
+        };
+        compiler.roots(start)?;
+        Ok(compiler.model)
+    }
+}
+
+fn syntax(line: usize, message: &str) -> Error {
+    Error::Value(format!("rnc schema line {line}: {message}"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    /// An identifier or keyword, with any `prefix:`, or a `prefix:*` name class.
+    Name(String),
+    /// An identifier written with a backslash, which is never a keyword.
+    Escaped(String),
+    Literal(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Name(name) | Self::Escaped(name) => name.clone(),
+            Self::Literal(text) => format!("\"{}\"", text.escape_debug()),
+            Self::Symbol(symbol) => format!("'{symbol}'"),
+        }
+    }
+}
+
+fn symbol(c: char) -> Option<&'static str> {
+    let symbol = match c {
+        '{' => "{",
+        '}' => "}",
+        '(' => "(",
+        ')' => ")",
+        ',' => ",",
+        '|' => "|",
+        '&' => "&",
+        '?' => "?",
+        '*' => "*",
+        '+' => "+",
+        '=' => "=",
+        '-' => "-",
+        '~' => "~",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Splits a schema into tokens with their lines, dropping whitespace and comments.
+fn tokenize(schema: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = schema.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            '#' => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                continue;
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\n') | None => return Err(syntax(line, "unterminated literal")),
+                        Some(other) => text.push(other),
+                    }
+                }
+                Token::Literal(text)
+            }
+            '|' if chars.next_if_eq(&'=').is_some() => Token::Symbol("|="),
+            '&' if chars.next_if_eq(&'=').is_some() => Token::Symbol("&="),
+            '\\' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                    name.push(c);
+                }
+                Token::Escaped(name)
+            }
+            c if is_name_start(c) => {
+                let mut name = String::from(c);
+                while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                    name.push(c);
+                }
+                if chars.next_if_eq(&':').is_some() {
+                    name.push(':');
+                    if chars.next_if_eq(&'*').is_some() {
+                        name.push('*');
+                    }
+                    while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                        name.push(c);
+                    }
+                }
+                Token::Name(name)
+            }
+            c if c.is_whitespace() => continue,
+            c => match symbol(c) {
+                Some(symbol) => Token::Symbol(symbol),
+                None => return Err(syntax(line, &format!("unexpected character '{c}'"))),
+            },
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Element(NameClass, Box<Pattern>),
+    Attribute(NameClass, Box<Pattern>),
+    Text,
+    Empty,
+    NotAllowed,
+    Data(Datatype),
+    /// A reference to a named pattern, with its line.
+    Ref(String, usize),
+    Group(Vec<Pattern>),
+    Choice(Vec<Pattern>),
+    /// `?`, `*` or `+`: the pattern between `min` and `max` times.
+    Repeat {
+        pattern: Box<Pattern>,
+        min: usize,
+        max: Option<usize>,
+    },
+    Mixed(Box<Pattern>),
+}
+
+#[derive(Debug)]
+enum NameClass {
+    /// Local names; a choice of names stands for one declaration per name.
+    Names(Vec<String>),
+    /// `*` or `prefix:*`.
+    Any,
+}
+
+/// A datatype, such as `xsd:int { minInclusive = "1" }`, or one of its values.
+#[derive(Debug)]
+struct Datatype {
+    /// The name as written, for messages.
+    name: String,
+    /// The local name of the XML Schema built-in type.
+    builtin: String,
+    params: Vec<(String, String)>,
+    value: Option<String>,
+}
+
+#[derive(Default)]
+struct Grammar {
+    start: Option<Pattern>,
+    definitions: HashMap<String, Pattern>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Datatype library URIs by prefix.
+    datatypes: HashMap<String, String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + ahead)
+            .map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    /// The error for something other than `expected` at the next token.
+    fn error(&self, expected: &str) -> Error {
+        let found = self
+            .peek()
+            .map_or_else(|| "the end of the schema".to_owned(), Token::describe);
+        syntax(self.line(), &format!("expected {expected}, found {found}"))
+    }
+
+    fn unsupported(&self, what: &str) -> Error {
+        syntax(self.line(), &format!("{what} is not supported"))
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(symbol)) if *symbol == expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), Error> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{expected}'")))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Name(name)) if name == keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn identifier(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Name(name) | Token::Escaped(name)) if !name.contains(':') => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            Some(Token::Name(_) | Token::Escaped(_) | Token::Literal(_) | Token::Symbol(_))
+            | None => Err(self.error("a name")),
+        }
+    }
+
+    /// A literal, joining any further literals after `~`.
+    fn literal(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some(Token::Literal(part)) => {
+                    text.push_str(part);
+                    self.position += 1;
+                }
+                Some(Token::Name(_) | Token::Escaped(_) | Token::Symbol(_)) | None => {
+                    return Err(self.error("a literal"));
+                }
+            }
+            if !self.eat("~") {
+                return Ok(text);
+            }
+        }
+    }
+
+    fn grammar(&mut self) -> Result<Grammar, Error> {
+        self.declarations()?;
+        let mut grammar = Grammar::default();
+        let is_definition = matches!(self.peek_at(1), Some(Token::Symbol("=" | "|=" | "&=")))
+            || matches!(self.peek(), Some(Token::Name(name)) if name == "div" || name == "include");
+        if is_definition {
+            self.definitions(&mut grammar, false)?;
+        } else {
+            grammar.start = Some(self.pattern()?);
+            if self.peek().is_some() {
+                return Err(self.error("the end of the schema"));
+            }
+        }
+        Ok(grammar)
+    }
+
+    /// `namespace`, `default namespace` and `datatypes` declarations.
+    fn declarations(&mut self) -> Result<(), Error> {
+        loop {
+            let datatypes = if self.keyword("namespace") {
+                false
+            } else if self.keyword("default") {
+                if !self.keyword("namespace") {
+                    return Err(self.error("namespace"));
+                }
+                if matches!(self.peek(), Some(Token::Symbol("="))) {
+                    self.expect("=")?;
+                    self.namespace_uri()?;
+                    continue;
+                }
+                false
+            } else if self.keyword("datatypes") {
+                true
+            } else {
+                return Ok(());
+            };
+            let prefix = self.identifier()?;
+            self.expect("=")?;
+            let uri = self.namespace_uri()?;
+            if datatypes {
+                self.datatypes.insert(prefix, uri);
+            }
+        }
+    }
+
+    fn namespace_uri(&mut self) -> Result<String, Error> {
+        if self.keyword("inherit") {
+            return Ok(String::new());
+        }
+        self.literal()
+    }
+
+    /// Definitions up to the end of the schema, or of a `div` when `in_div`.
+    fn definitions(&mut self, grammar: &mut Grammar, in_div: bool) -> Result<(), Error> {
+        loop {
+            match self.peek() {
+                None if in_div => return Err(self.error("'}'")),
+                None => return Ok(()),
+                Some(Token::Symbol("}")) if in_div => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                Some(Token::Name(name)) if name == "div" => {
+                    self.position += 1;
+                    self.expect("{")?;
+                    self.definitions(grammar, true)?;
+                    continue;
+                }
+                Some(Token::Name(name)) if name == "include" => {
+                    return Err(self.unsupported("include"));
+                }
+                Some(Token::Name(_) | Token::Escaped(_) | Token::Literal(_) | Token::Symbol(_)) => {
+                }
+            }
+            let start = self.keyword("start");
+            let name = if start {
+                None
+            } else {
+                Some(self.identifier()?)
+            };
+            let combine = if self.eat("=") {
+                false
+            } else if self.eat("|=") {
+                true
+            } else if matches!(self.peek(), Some(Token::Symbol("&="))) {
+                return Err(self.unsupported("'&='"));
+            } else {
+                return Err(self.error("'='"));
+            };
+            let line = self.line();
+            let pattern = self.pattern()?;
+            let slot = match name {
+                Some(name) => grammar
+                    .definitions
+                    .entry(name)
+                    .or_insert(Pattern::NotAllowed),
+                None => grammar.start.get_or_insert(Pattern::NotAllowed),
+            };
+            match (std::mem::replace(slot, Pattern::NotAllowed), combine) {
+                (Pattern::NotAllowed, _) => *slot = pattern,
+                (Pattern::Choice(mut choices), true) => {
+                    choices.push(pattern);
+                    *slot = Pattern::Choice(choices);
+                }
+                (previous, true) => *slot = Pattern::Choice(vec![previous, pattern]),
+                (_, false) => return Err(syntax(line, "pattern is defined twice")),
+            }
+        }
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        let first = self.particle()?;
+        let separator = match self.peek() {
+            Some(Token::Symbol(separator @ ("," | "|"))) => *separator,
+            Some(Token::Symbol("&")) => return Err(self.unsupported("'&'")),
+            Some(Token::Name(_) | Token::Escaped(_) | Token::Literal(_) | Token::Symbol(_))
+            | None => return Ok(first),
+        };
+        let mut patterns = vec![first];
+        while self.eat(separator) {
+            patterns.push(self.particle()?);
+        }
+        if matches!(self.peek(), Some(Token::Symbol("," | "|" | "&"))) {
+            return Err(self.error(&format!("'{separator}' or parentheses")));
+        }
+        Ok(if separator == "," {
+            Pattern::Group(patterns)
+        } else {
+            Pattern::Choice(patterns)
+        })
+    }
+
+    fn particle(&mut self) -> Result<Pattern, Error> {
+        let primary = self.primary()?;
+        let (min, max) = if self.eat("?") {
+            (0, Some(1))
+        } else if self.eat("*") {
+            (0, None)
+        } else if self.eat("+") {
+            (1, None)
+        } else {
+            return Ok(primary);
+        };
+        Ok(Pattern::Repeat {
+            pattern: Box::new(primary),
+            min,
+            max,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Pattern, Error> {
+        let line = self.line();
+        let name = match self.peek() {
+            Some(Token::Symbol("(")) => {
+                self.position += 1;
+                let pattern = self.pattern()?;
+                self.expect(")")?;
+                return Ok(pattern);
+            }
+            Some(Token::Literal(_)) => return self.datatype("token".to_owned()),
+            Some(Token::Escaped(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                return Ok(Pattern::Ref(name, line));
+            }
+            Some(Token::Name(name)) => name.clone(),
+            Some(Token::Symbol(_)) | None => return Err(self.error("a pattern")),
+        };
+        self.position += 1;
+        let pattern = match name.as_str() {
+            "element" | "attribute" => {
+                let names = self.name_class()?;
+                let content = Box::new(self.braced()?);
+                if name == "element" {
+                    Pattern::Element(names, content)
+                } else {
+                    Pattern::Attribute(names, content)
+                }
+            }
+            "mixed" => Pattern::Mixed(Box::new(self.braced()?)),
+            "text" => Pattern::Text,
+            "empty" => Pattern::Empty,
+            "notAllowed" => Pattern::NotAllowed,
+            "string" | "token" => self.datatype(name)?,
+            "list" | "grammar" | "external" | "parent" => {
+                self.position -= 1;
+                return Err(self.unsupported(&name));
+            }
+            _ if name.contains(':') => self.datatype(name)?,
+            _ => Pattern::Ref(name, line),
+        };
+        Ok(pattern)
+    }
+
+    /// A pattern in braces, as the content of `element`, `attribute` and `mixed`.
+    fn braced(&mut self) -> Result<Pattern, Error> {
+        self.expect("{")?;
+        let pattern = self.pattern()?;
+        self.expect("}")?;
+        Ok(pattern)
+    }
+
+    fn name_class(&mut self) -> Result<NameClass, Error> {
+        if self.eat("*") {
+            return Ok(NameClass::Any);
+        }
+        if self.eat("(") {
+            let mut names = Vec::new();
+            loop {
+                match self.name_class()? {
+                    NameClass::Names(more) => names.extend(more),
+                    NameClass::Any => return Err(self.unsupported("a choice with a wildcard")),
+                }
+                if !self.eat("|") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            return Ok(NameClass::Names(names));
+        }
+        match self.peek() {
+            Some(Token::Name(name)) if name.ends_with(":*") => {
+                self.position += 1;
+                Ok(NameClass::Any)
+            }
+            Some(Token::Name(name) | Token::Escaped(name)) => {
+                let name = local_name(name).to_owned();
+                self.position += 1;
+                Ok(NameClass::Names(vec![name]))
+            }
+            Some(Token::Literal(_) | Token::Symbol(_)) | None => Err(self.error("a name")),
+        }
+    }
+
+    /// A datatype named `name`, with any parameters, or a value of it.
+    fn datatype(&mut self, name: String) -> Result<Pattern, Error> {
+        let builtin = match name.split_once(':') {
+            None => name.clone(),
+            Some((prefix, local)) => match self.datatypes.get(prefix) {
+                Some(uri) if uri == XSD_DATATYPES => local.to_owned(),
+                // Other datatype libraries accept any text.
+                Some(_) => "anySimpleType".to_owned(),
+                None => {
+                    return Err(syntax(
+                        self.line(),
+                        &format!("datatypes prefix {prefix} is not declared"),
+                    ))
+                }
+            },
+        };
+        let mut datatype = Datatype {
+            name,
+            builtin,
+            params: Vec::new(),
+            value: None,
+        };
+        if matches!(self.peek(), Some(Token::Literal(_))) {
+            datatype.value = Some(self.literal()?);
+            return Ok(Pattern::Data(datatype));
+        }
+        if self.eat("{") {
+            while !self.eat("}") {
+                let param = self.identifier()?;
+                self.expect("=")?;
+                datatype.params.push((param, self.literal()?));
+            }
+        }
+        if matches!(self.peek(), Some(Token::Symbol("-"))) {
+            return Err(self.unsupported("'-'"));
+        }
+        Ok(Pattern::Data(datatype))
+    }
+}
+
+/// What the content of an element says outside its child elements.
+#[derive(Default)]
+struct Summary<'a> {
+    attributes: Vec<Attribute>,
+    any_attribute: bool,
+    text: bool,
+    /// Datatypes and values the text may have.
+    values: Vec<&'a Datatype>,
+}
+
+/// Builds the [`Model`] from the patterns of a schema.
+struct Compiler<'a> {
+    definitions: &'a HashMap<String, Pattern>,
+    /// Elements already read, by the address of their pattern, so that references to an
+    /// element read it once and recursive elements refer to themselves.
+    element_ids: HashMap<*const Pattern, Vec<usize>>,
+    /// References being followed within one element, to stop at patterns containing
+    /// themselves.
+    open_refs: Vec<&'a str>,
+    /// Types of datatypes without parameters, by name.
+    datatype_ids: HashMap<&'a str, usize>,
+    model: Model,
+}
+
+impl<'a> Compiler<'a> {
+    /// Follows a reference, or returns `None` for one already being followed.
+    fn follow<T>(
+        &mut self,
+        name: &'a str,
+        line: usize,
+        read: impl FnOnce(&mut Self, &'a Pattern) -> Result<T, Error>,
+    ) -> Result<Option<T>, Error> {
+        let Some(pattern) = self.definitions.get(name) else {
+            return Err(syntax(line, &format!("{name} is not defined")));
+        };
+        if self.open_refs.contains(&name) {
+            return Ok(None);
+        }
+        self.open_refs.push(name);
+        let result = read(self, pattern);
+        self.open_refs.pop();
+        result.map(Some)
+    }
+
+    /// Adds the elements the start pattern allows as roots.
+    fn roots(&mut self, pattern: &'a Pattern) -> Result<(), Error> {
+        match pattern {
+            Pattern::Element(names, content) => {
+                for id in self.element(pattern, names, content)?.unwrap_or_default() {
+                    if let Some(element) = self.model.elements.get(id) {
+                        self.model.roots.insert(element.name.clone(), id);
+                    }
+                }
+            }
+            Pattern::Choice(patterns) => {
+                for pattern in patterns {
+                    self.roots(pattern)?;
+                }
+            }
+            Pattern::Ref(name, line) => {
+                self.follow(name, *line, Self::roots)?;
+            }
+            Pattern::Attribute(..)
+            | Pattern::Text
+            | Pattern::Empty
+            | Pattern::NotAllowed
+            | Pattern::Data(_)
+            | Pattern::Group(_)
+            | Pattern::Repeat { .. }
+            | Pattern::Mixed(_) => {}
+        }
+        Ok(())
+    }
+
+    /// The elements of an element pattern, one per name, or `None` for any name.
+    fn element(
+        &mut self,
+        pattern: &'a Pattern,
+        names: &'a NameClass,
+        content: &'a Pattern,
+    ) -> Result<Option<Vec<usize>>, Error> {
+        let NameClass::Names(names) = names else {
+            return Ok(None);
+        };
+        let key = std::ptr::from_ref(pattern);
+        if let Some(ids) = self.element_ids.get(&key) {
+            return Ok(Some(ids.clone()));
+        }
+        let first = self.model.elements.len();
+        for name in names {
+            self.model.elements.push(Element {
+                name: name.clone(),
+                namespace: String::new(),
+                kind: Kind::Any,
+            });
+        }
+        let ids: Vec<usize> = (first..self.model.elements.len()).collect();
+        self.element_ids.insert(key, ids.clone());
+        let open_refs = std::mem::take(&mut self.open_refs);
+        let kind = self.kind(content);
+        self.open_refs = open_refs;
+        let kind = kind?;
+        for id in &ids {
+            if let Some(element) = self.model.elements.get_mut(*id) {
+                element.kind = kind;
+            }
+        }
+        Ok(Some(ids))
+    }
+
+    /// The type of an element with the content `pattern`.
+    fn kind(&mut self, pattern: &'a Pattern) -> Result<Kind, Error> {
+        let mut summary = Summary::default();
+        self.summarize(pattern, true, &mut summary)?;
+        let particle = self.particle(pattern)?;
+        let content = match particle {
+            Some(particle) => Content::Elements(particle),
+            None => match self.simple(&summary.values) {
+                Some(simple) => Content::Simple(simple),
+                None if summary.text => Content::Simple(self.text()),
+                None => Content::Empty,
+            },
+        };
+        if let (Content::Simple(simple), true, false) = (
+            &content,
+            summary.attributes.is_empty(),
+            summary.any_attribute,
+        ) {
+            return Ok(Kind::Simple(*simple));
+        }
+        self.model.complex_types.push(ComplexType {
+            attributes: summary.attributes,
+            any_attribute: summary.any_attribute,
+            mixed: summary.text && matches!(content, Content::Elements(_)),
+            content,
+        });
+        Ok(Kind::Complex(self.model.complex_types.len() - 1))
+    }
+
+    /// Collects the attributes, text and values of a pattern, outside its elements.
+    /// Attributes are required when `required` and not in a choice or repetition that
+    /// may leave them out.
+    fn summarize(
+        &mut self,
+        pattern: &'a Pattern,
+        required: bool,
+        summary: &mut Summary<'a>,
+    ) -> Result<(), Error> {
+        match pattern {
+            Pattern::Attribute(NameClass::Names(names), value) => {
+                let kind = self.value(value)?;
+                for name in names {
+                    summary
+                        .attributes
+                        .retain(|attribute| attribute.name != *name);
+                    summary.attributes.push(Attribute {
+                        name: name.clone(),
+                        namespace: String::new(),
+                        kind,
+                        required,
+                    });
+                }
+            }
+            Pattern::Attribute(NameClass::Any, _) => summary.any_attribute = true,
+            Pattern::Text => summary.text = true,
+            Pattern::Mixed(pattern) => {
+                summary.text = true;
+                self.summarize(pattern, required, summary)?;
+            }
+            Pattern::Data(datatype) => summary.values.push(datatype),
+            Pattern::Group(patterns) => {
+                for pattern in patterns {
+                    self.summarize(pattern, required, summary)?;
+                }
+            }
+            Pattern::Choice(patterns) => {
+                for pattern in patterns {
+                    self.summarize(pattern, false, summary)?;
+                }
+            }
+            Pattern::Repeat { pattern, min, .. } => {
+                self.summarize(pattern, required && *min > 0, summary)?;
+            }
+            Pattern::Ref(name, line) => {
+                self.follow(name, *line, |compiler, pattern| {
+                    compiler.summarize(pattern, required, summary)
+                })?;
+            }
+            Pattern::Element(..) | Pattern::Empty | Pattern::NotAllowed => {}
+        }
+        Ok(())
+    }
+
+    /// The type of an attribute value, or `None` when it may be any text.
+    fn value(&mut self, pattern: &'a Pattern) -> Result<Option<usize>, Error> {
+        let mut summary = Summary::default();
+        self.summarize(pattern, true, &mut summary)?;
+        if summary.text {
+            return Ok(None);
+        }
+        Ok(self.simple(&summary.values))
+    }
+
+    /// The content model of the child elements in a pattern, or `None` without any.
+    fn particle(&mut self, pattern: &'a Pattern) -> Result<Option<Particle>, Error> {
+        let once = |term| Particle {
+            min: 1,
+            max: Some(1),
+            term,
+        };
+        let particle = match pattern {
+            Pattern::Element(names, content) => match self.element(pattern, names, content)? {
+                Some(ids) => match ids.as_slice() {
+                    [id] => once(Term::Element(*id)),
+                    _ => once(Term::Choice(
+                        ids.iter().map(|id| once(Term::Element(*id))).collect(),
+                    )),
+                },
+                None => once(Term::Any),
+            },
+            Pattern::Group(patterns) => {
+                let mut particles = Vec::new();
+                for pattern in patterns {
+                    particles.extend(self.particle(pattern)?);
+                }
+                if particles.len() > 1 {
+                    once(Term::Sequence(particles))
+                } else {
+                    return Ok(particles.pop());
+                }
+            }
+            Pattern::Choice(patterns) => {
+                let mut particles = Vec::new();
+                let mut optional = false;
+                for pattern in patterns {
+                    match self.particle(pattern)? {
+                        Some(particle) => particles.push(particle),
+                        None => optional |= !matches!(pattern, Pattern::NotAllowed),
+                    }
+                }
+                if particles.is_empty() {
+                    return Ok(None);
+                }
+                Particle {
+                    min: usize::from(!optional),
+                    max: Some(1),
+                    term: Term::Choice(particles),
+                }
+            }
+            Pattern::Repeat { pattern, min, max } => {
+                let Some(inner) = self.particle(pattern)? else {
+                    return Ok(None);
+                };
+                let term = if inner.min == 1 && inner.max == Some(1) {
+                    inner.term
+                } else {
+                    Term::Sequence(vec![inner])
+                };
+                Particle {
+                    min: *min,
+                    max: *max,
+                    term,
+                }
+            }
+            Pattern::Mixed(pattern) => return self.particle(pattern),
+            Pattern::Ref(name, line) => {
+                return Ok(self.follow(name, *line, Self::particle)?.flatten());
+            }
+            Pattern::Attribute(..)
+            | Pattern::Text
+            | Pattern::Empty
+            | Pattern::NotAllowed
+            | Pattern::Data(_) => return Ok(None),
+        };
+        Ok(Some(particle))
+    }
+
+    /// The simple type of text that may have any of `values`, or `None` without any.
+    fn simple(&mut self, values: &[&'a Datatype]) -> Option<usize> {
+        let mut members = Vec::new();
+        let mut enumerations: Vec<(&str, Vec<String>)> = Vec::new();
+        for datatype in values {
+            match &datatype.value {
+                Some(value) => {
+                    let builtin = datatype.builtin.as_str();
+                    match enumerations.iter_mut().find(|(other, _)| *other == builtin) {
+                        Some((_, values)) => values.push(value.clone()),
+                        None => enumerations.push((builtin, vec![value.clone()])),
+                    }
+                }
+                None => members.push(self.datatype(datatype)),
+            }
+        }
+        for (builtin, values) in enumerations {
+            let facet = Facet::Enumeration(values);
+            members.push(self.push(SimpleType {
+                name: facet.describe("characters"),
+                variety: Variety::Atomic(builtin.to_owned()),
+                facets: vec![facet],
+            }));
+        }
+        match members.as_slice() {
+            [] => None,
+            [member] => Some(*member),
+            _ => {
+                let names: Vec<&str> = members
+                    .iter()
+                    .filter_map(|id| self.model.simple_types.get(*id))
+                    .map(|simple| simple.name.as_str())
+                    .collect();
+                let name = names.join(" or ");
+                Some(self.push(SimpleType {
+                    name,
+                    variety: Variety::Union(members),
+                    facets: Vec::new(),
+                }))
+            }
+        }
+    }
+
+    fn datatype(&mut self, datatype: &'a Datatype) -> usize {
+        if datatype.params.is_empty() {
+            if let Some(id) = self.datatype_ids.get(datatype.name.as_str()) {
+                return *id;
+            }
+        }
+        let id = self.push(SimpleType {
+            name: datatype.name.clone(),
+            variety: Variety::Atomic(datatype.builtin.clone()),
+            facets: datatype
+                .params
+                .iter()
+                .filter_map(|(name, value)| Facet::new(name, value))
+                .collect(),
+        });
+        if datatype.params.is_empty() {
+            self.datatype_ids.insert(&datatype.name, id);
+        }
+        id
+    }
+
+    /// The type of `text`, which accepts anything.
+    fn text(&mut self) -> usize {
+        if let Some(id) = self.datatype_ids.get("text") {
+            return *id;
+        }
+        let id = self.push(SimpleType {
+            name: "text".to_owned(),
+            variety: Variety::Atomic("string".to_owned()),
+            facets: Vec::new(),
+        });
+        self.datatype_ids.insert("text", id);
+        id
+    }
+
+    fn push(&mut self, simple: SimpleType) -> usize {
+        self.model.simple_types.push(simple);
+        self.model.simple_types.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::collect_events;
+    use crate::xmltodict_core::ParseConfig;
+    use crate::xsd::validate;
+
+    const ORDER: &str = r#"
+        # An order with lines
+        datatypes xsd = "http://www.w3.org/2001/XMLSchema-datatypes"
+        start = order
+        order = element order {
+            attribute state { "open" | "paid" },
+            attribute note { text }?,
+            element id { xsd:int },
+            line+,
+            element comment { text }*
+        }
+        line = element line { element sku { xsd:string { minLength = "2" } }, qty? }
+        qty = element qty { xsd:positiveInteger }
+    "#;
+
+    fn issues(schema: &str, xml: &str) -> Result<Vec<String>, Error> {
+        let model = Model::from_rnc(schema)?;
+        let events = collect_events(xml.as_bytes(), &ParseConfig::default())?;
+        let issues = validate(&model, &events);
+        Ok(issues.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn valid_documents_have_no_issues() -> Result<(), Error> {
+        let xml = r#"<order state="open"><id>1</id><line><sku>AB</sku></line>
+                     <line><sku>CD</sku><qty>2</qty></line><comment/><comment>x</comment></order>"#;
+        assert_eq!(issues(ORDER, xml)?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_documents() -> Result<(), Error> {
+        let xml = "<order state=\"new\"><id>x</id><line><sku>A</sku><qty>0</qty></line>\n\
+                   <comment/><line/></order>";
+        assert_eq!(
+            issues(ORDER, xml)?,
+            [
+                r#"order/@state: expected one of "open", "paid", found "new" (line 1)"#,
+                r#"order/id: expected xsd:int, found "x" (line 1)"#,
+                r#"order/line/sku: expected at least 2 characters, found "A" (line 1)"#,
+                r#"order/line/qty: expected xsd:positiveInteger, found "0" (line 1)"#,
+                "order/line: expected <sku>, found nothing (line 2)",
+                "order/line: expected <comment> or end of <order>, found <line> (line 2)",
+            ]
+        );
+        assert_eq!(
+            issues(ORDER, "<order><id>1</id></order>")?,
+            [
+                "order/@state: expected attribute state, found nothing (line 1)",
+                "order: expected <line>, found nothing (line 1)",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn single_patterns_and_recursion() -> Result<(), Error> {
+        let schema = "element (a | b) { mixed { element c { empty }* } }";
+        assert!(issues(schema, "<b>x<c/>y</b>")?.is_empty());
+        assert_eq!(
+            issues(schema, "<a><c>z</c></a>")?,
+            [r#"a/c: expected no text, found "z" (line 1)"#]
+        );
+        let schema = "start = tree  tree = element tree { attribute n { xsd:int }, tree* }";
+        assert!(issues(
+            schema,
+            r#"<tree n="1"><tree n="2"><tree n="3"/></tree></tree>"#
+        )?
+        .is_empty());
+        assert_eq!(
+            issues(schema, r#"<tree n="1"><tree n="x"/></tree>"#)?,
+            [r#"tree/tree/@n: expected xsd:int, found "x" (line 1)"#]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unsupported_and_malformed_schemas() {
+        for (schema, message) in [
+            (
+                "element a { element b { text } & element c { text } }",
+                "'&' is not supported",
+            ),
+            ("element a { list { xsd:int+ } }", "list is not supported"),
+            ("start = a", "line 1: a is not defined"),
+            (
+                "element a { text",
+                "line 1: expected '}', found the end of the schema",
+            ),
+            (
+                "element a { foo:int }",
+                "datatypes prefix foo is not declared",
+            ),
+            ("a = element a { text }", "rnc schema has no start pattern"),
+            (
+                "element a { text }, element b { text } | empty",
+                "expected ',' or parentheses",
+            ),
+        ] {
+            let result = Model::from_rnc(schema);
+            assert!(
+                matches!(result, Err(Error::Value(found)) if found.contains(message)),
+                "{schema}"
+            );
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use super::builtins;
+use super::model::{expanded_name, local_name, Content, Kind, Model, Particle, Term, Variety};
+use crate::events::XmlEvent;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// What a document has where the schema expects something else.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Found {
+    /// Nothing: a required element or attribute is missing.
+    Nothing,
+    Element(String),
+    Attribute(String),
+    Text(String),
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nothing => write!(f, "nothing"),
+            Self::Element(name) => write!(f, "<{name}>"),
+            Self::Attribute(name) => write!(f, "attribute {name}"),
+            Self::Text(text) => write!(f, "\"{}\"", text.escape_debug()),
+        }
+    }
+}
+
+/// A place where a document does not follow its schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// Names from the root element down to the element or attribute, as written in the
+    /// document, with `@` before attribute names.
+    pub path: String,
+    pub line: usize,
+    pub expected: String,
+    pub found: Found,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, found {} (line {})",
+            self.path, self.expected, self.found, self.line
+        )
+    }
+}
+
+/// The namespace the `xml` prefix is bound to.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Checks a document against `model`, returning every issue in document order. Below an
+/// element that is not declared, nothing more is checked.
+#[must_use]
+pub fn validate(model: &Model, events: &[(XmlEvent, usize)]) -> Vec<Issue> {
+    let mut validator = Validator {
+        model,
+        namespaces: Vec::new(),
+        open: Vec::new(),
+        children: HashMap::new(),
+        issues: Vec::new(),
+    };
+    for (event, line) in events {
+        match event {
+            XmlEvent::Start { name, attributes } => validator.start(name, attributes, *line),
+            XmlEvent::End(_) => validator.end(*line),
+            XmlEvent::Text(text) | XmlEvent::Whitespace(text) => {
+                if let Some(open) = validator.open.last_mut() {
+                    open.text.push_str(text);
+                }
+            }
+            XmlEvent::DocType(_) | XmlEvent::Comment(_) | XmlEvent::ProcessingInstruction(_) => {}
+        }
+    }
+    validator.issues
+}
+
+/// An open element of the document.
+struct Open {
+    name: String,
+    path: String,
+    line: usize,
+    /// The declared type, or `None` when the element is not declared.
+    kind: Option<Kind>,
+    /// `xsi:nil="true"` leaves the element without content.
+    nil: bool,
+    /// Child element names as written, with their lines.
+    children: Vec<(String, usize)>,
+    /// The names of the children as the schema's names are matched.
+    child_names: Vec<String>,
+    text: String,
+    /// The length of [`Validator::namespaces`] outside the element.
+    scope: usize,
+}
+
+/// Where a child element is declared in a complex type.
+#[derive(Clone, Copy)]
+enum Declared {
+    Element(usize),
+    Wildcard,
+    Nowhere,
+}
+
+struct Validator<'m> {
+    model: &'m Model,
+    /// Namespace URIs by prefix declared on the open elements, innermost last, with `""`
+    /// for the default namespace.
+    namespaces: Vec<(String, String)>,
+    open: Vec<Open>,
+    /// Elements of each complex type's content, by the name they are matched by, and
+    /// whether it has `xs:any`.
+    children: HashMap<usize, (HashMap<String, usize>, bool)>,
+    issues: Vec<Issue>,
+}
+
+impl Validator<'_> {
+    fn issue(&mut self, path: String, line: usize, expected: String, found: Found) {
+        self.issues.push(Issue {
+            path,
+            line,
+            expected,
+            found,
+        });
+    }
+
+    /// The name matched against the schema: the expanded name when the schema has
+    /// namespaces, or else the local name. Unprefixed attributes have no namespace, and
+    /// names with an undeclared prefix are kept as written, matching nothing.
+    fn matched_name(&self, name: &str, attribute: bool) -> String {
+        if !self.model.namespaced {
+            return local_name(name).to_owned();
+        }
+        let (prefix, local) = name.split_once(':').unwrap_or(("", name));
+        if attribute && prefix.is_empty() {
+            return local.to_owned();
+        }
+        let namespace = if prefix == "xml" {
+            Some(XML_NAMESPACE)
+        } else {
+            self.namespaces
+                .iter()
+                .rev()
+                .find(|(declared, _)| declared == prefix)
+                .map(|(_, uri)| uri.as_str())
+        };
+        match namespace {
+            Some(namespace) => expanded_name(namespace, local).into_owned(),
+            None if prefix.is_empty() => local.to_owned(),
+            None => name.to_owned(),
+        }
+    }
+
+    /// An element found where another was expected, by its expanded name when the schema
+    /// has namespaces, so the namespace it was in shows.
+    fn found(&self, name: &str, matched: &str) -> Found {
+        if self.model.namespaced {
+            Found::Element(matched.to_owned())
+        } else {
+            Found::Element(name.to_owned())
+        }
+    }
+
+    fn start(&mut self, name: &str, attributes: &[(String, String)], line: usize) {
+        let scope = self.namespaces.len();
+        for (key, value) in attributes {
+            if let Some(prefix) = key.strip_prefix("xmlns") {
+                if prefix.is_empty() || prefix.starts_with(':') {
+                    let prefix = prefix.trim_start_matches(':').to_owned();
+                    self.namespaces.push((prefix, value.clone()));
+                }
+            }
+        }
+        let matched = self.matched_name(name, false);
+        let path = match self.open.last_mut() {
+            Some(parent) => {
+                parent.children.push((name.to_owned(), line));
+                parent.child_names.push(matched.clone());
+                format!("{}/{name}", parent.path)
+            }
+            None => name.to_owned(),
+        };
+        let kind = match self.open.last().map(|parent| parent.kind) {
+            None => {
+                let root = self.model.roots.get(matched.as_str()).copied();
+                if root.is_none() {
+                    let mut names: Vec<&str> =
+                        self.model.roots.keys().map(String::as_str).collect();
+                    names.sort_unstable();
+                    self.issue(
+                        path.clone(),
+                        line,
+                        element_list(&names),
+                        self.found(name, &matched),
+                    );
+                }
+                root.and_then(|id| self.element_kind(id))
+            }
+            // Content of undeclared elements is not checked; simple content reports its
+            // children when it ends.
+            Some(None | Some(Kind::Simple(_))) => None,
+            Some(Some(Kind::Any)) => Some(
+                self.model
+                    .roots
+                    .get(matched.as_str())
+                    .and_then(|id| self.element_kind(*id))
+                    .unwrap_or(Kind::Any),
+            ),
+            Some(Some(Kind::Complex(complex))) => match self.declared(complex, &matched) {
+                Declared::Element(id) => self.element_kind(id),
+                Declared::Wildcard => Some(
+                    self.model
+                        .roots
+                        .get(matched.as_str())
+                        .and_then(|id| self.element_kind(*id))
+                        .unwrap_or(Kind::Any),
+                ),
+                // Reported against the parent's content when it ends.
+                Declared::Nowhere => None,
+            },
+        };
+        let nil = attributes.iter().any(|(key, value)| {
+            key.contains(':') && local_name(key) == "nil" && matches!(value.trim(), "true" | "1")
+        });
+        if let Some(kind) = kind {
+            self.attributes(&path, line, kind, attributes);
+        }
+        self.open.push(Open {
+            name: name.to_owned(),
+            path,
+            line,
+            kind,
+            nil,
+            children: Vec::new(),
+            child_names: Vec::new(),
+            text: String::new(),
+            scope,
+        });
+    }
+
+    fn element_kind(&self, id: usize) -> Option<Kind> {
+        self.model.elements.get(id).map(|element| element.kind)
+    }
+
+    fn declared(&mut self, complex: usize, name: &str) -> Declared {
+        let model = self.model;
+        let (names, wildcard) = self.children.entry(complex).or_insert_with(|| {
+            let mut names = HashMap::new();
+            let mut wildcard = false;
+            if let Some(Content::Elements(particle)) = model
+                .complex_types
+                .get(complex)
+                .map(|complex| &complex.content)
+            {
+                collect_elements(model, particle, &mut names, &mut wildcard);
+            }
+            (names, wildcard)
+        });
+        match names.get(name) {
+            Some(id) => Declared::Element(*id),
+            None if *wildcard => Declared::Wildcard,
+            None => Declared::Nowhere,
+        }
+    }
+
+    fn attributes(&mut self, path: &str, line: usize, kind: Kind, attributes: &[(String, String)]) {
+        let complex = match kind {
+            Kind::Any => return,
+            Kind::Simple(_) => None,
+            Kind::Complex(id) => self.model.complex_types.get(id),
+        };
+        let declared = complex.map_or(&[][..], |complex| complex.attributes.as_slice());
+        for (name, value) in attributes {
+            // Namespace declarations and `xsi:` attributes are not declared in schemas.
+            if name == "xmlns" || name.starts_with("xmlns:") || name.starts_with("xsi:") {
+                continue;
+            }
+            let attribute_path = format!("{path}/@{name}");
+            let matched = self.matched_name(name, true);
+            match declared.iter().find(|attribute| {
+                expanded_name(&attribute.namespace, &attribute.name) == matched.as_str()
+            }) {
+                Some(attribute) => {
+                    if let Some(expected) =
+                        attribute.kind.and_then(|simple| self.value(simple, value))
+                    {
+                        self.issue(attribute_path, line, expected, Found::Text(value.clone()));
+                    }
+                }
+                None if complex.is_some_and(|complex| complex.any_attribute) => {}
+                None => {
+                    let names: Vec<Cow<str>> = declared
+                        .iter()
+                        .map(|attribute| expanded_name(&attribute.namespace, &attribute.name))
+                        .collect();
+                    let expected = if names.is_empty() {
+                        "no attributes".to_owned()
+                    } else {
+                        format!("one of the attributes {}", names.join(", "))
+                    };
+                    self.issue(
+                        attribute_path,
+                        line,
+                        expected,
+                        Found::Attribute(name.clone()),
+                    );
+                }
+            }
+        }
+        for attribute in declared.iter().filter(|attribute| attribute.required) {
+            let expected = expanded_name(&attribute.namespace, &attribute.name);
+            let present = attributes
+                .iter()
+                .any(|(name, _)| self.matched_name(name, true) == expected.as_ref());
+            if !present {
+                self.issue(
+                    format!("{path}/@{}", attribute.name),
+                    line,
+                    format!("attribute {expected}"),
+                    Found::Nothing,
+                );
+            }
+        }
+    }
+
+    fn end(&mut self, line: usize) {
+        let Some(open) = self.open.pop() else {
+            return;
+        };
+        self.namespaces.truncate(open.scope);
+        let Some(kind) = open.kind.filter(|_| !open.nil) else {
+            return;
+        };
+        let simple = match kind {
+            Kind::Any => return,
+            Kind::Simple(simple) => Some(simple),
+            Kind::Complex(complex) => match self.model.complex_types.get(complex) {
+                Some(complex) => match &complex.content {
+                    Content::Simple(simple) => Some(*simple),
+                    Content::Empty => {
+                        self.empty(&open, complex.mixed);
+                        return;
+                    }
+                    Content::Elements(particle) => {
+                        self.elements(&open, particle, complex.mixed, line);
+                        return;
+                    }
+                },
+                None => return,
+            },
+        };
+        if let Some(simple) = simple {
+            if let Some((child, child_line)) = open.children.first() {
+                let expected = self.simple_name(simple);
+                self.issue(
+                    format!("{}/{child}", open.path),
+                    *child_line,
+                    expected,
+                    Found::Element(child.clone()),
+                );
+            } else if let Some(expected) = self.value(simple, &open.text) {
+                self.issue(open.path, open.line, expected, Found::Text(open.text));
+            }
+        }
+    }
+
+    /// Checks an element whose type has no content.
+    fn empty(&mut self, open: &Open, mixed: bool) {
+        if let Some((child, line)) = open.children.first() {
+            self.issue(
+                format!("{}/{child}", open.path),
+                *line,
+                format!("end of <{}>", open.name),
+                Found::Element(child.clone()),
+            );
+        } else if !mixed && !open.text.trim().is_empty() {
+            self.issue(
+                open.path.clone(),
+                open.line,
+                "no text".to_owned(),
+                Found::Text(open.text.trim().to_owned()),
+            );
+        }
+    }
+
+    /// Checks the child elements of an element against its content model.
+    fn elements(&mut self, open: &Open, particle: &Particle, mixed: bool, line: usize) {
+        if !mixed && !open.text.trim().is_empty() {
+            self.issue(
+                open.path.clone(),
+                open.line,
+                "no text".to_owned(),
+                Found::Text(open.text.trim().to_owned()),
+            );
+        }
+        let names: Vec<&str> = open.child_names.iter().map(String::as_str).collect();
+        let mut matcher = Matcher {
+            model: self.model,
+            names: &names,
+            furthest: 0,
+            expected: BTreeSet::new(),
+        };
+        let ends = matcher.particle(particle, 0);
+        if ends.contains(&names.len()) {
+            return;
+        }
+        let reached = ends.last().copied().unwrap_or_default();
+        let position = matcher.furthest.max(reached);
+        let mut expected: Vec<String> = if matcher.furthest == position {
+            matcher.expected.into_iter().collect()
+        } else {
+            Vec::new()
+        };
+        if ends.contains(&position) {
+            expected.push(format!("end of <{}>", open.name));
+        }
+        let expected = if expected.is_empty() {
+            "no elements".to_owned()
+        } else {
+            expected.join(" or ")
+        };
+        match open.children.get(position) {
+            Some((child, child_line)) => {
+                let child_name = open.child_names.get(position).map_or("", String::as_str);
+                let found = self.found(child, child_name);
+                self.issue(
+                    format!("{}/{child}", open.path),
+                    *child_line,
+                    expected,
+                    found,
+                );
+            }
+            None => self.issue(open.path.clone(), line, expected, Found::Nothing),
+        }
+    }
+
+    /// What a simple type expects if `text` is not one of its values.
+    fn value(&self, simple: usize, text: &str) -> Option<String> {
+        let simple_type = self.model.simple_types.get(simple)?;
+        let (value, length) = match &simple_type.variety {
+            Variety::Atomic(builtin) => {
+                let value = if builtins::keeps_whitespace(builtin) {
+                    text
+                } else {
+                    text.trim()
+                };
+                if !builtins::is_valid(builtin, value) {
+                    return Some(simple_type.name.clone());
+                }
+                (value, value.chars().count())
+            }
+            Variety::List(item) => {
+                for value in text.split_whitespace() {
+                    if self.value(*item, value).is_some() {
+                        return Some(simple_type.name.clone());
+                    }
+                }
+                (text.trim(), text.split_whitespace().count())
+            }
+            Variety::Union(members) => {
+                if members
+                    .iter()
+                    .all(|member| self.value(*member, text).is_some())
+                {
+                    return Some(simple_type.name.clone());
+                }
+                (text.trim(), text.trim().chars().count())
+            }
+        };
+        let number = self
+            .model
+            .builtin(simple)
+            .filter(|builtin| builtins::is_numeric(builtin))
+            .and_then(|_| value.parse::<f64>().ok());
+        let unit = match simple_type.variety {
+            Variety::List(_) => "items",
+            Variety::Atomic(_) | Variety::Union(_) => "characters",
+        };
+        simple_type
+            .facets
+            .iter()
+            .find(|facet| !facet.allows(value, length, number))
+            .map(|facet| facet.describe(unit))
+    }
+
+    fn simple_name(&self, simple: usize) -> String {
+        self.model
+            .simple_types
+            .get(simple)
+            .map_or_else(|| "text".to_owned(), |simple| simple.name.clone())
+    }
+}
+
+fn element_list(names: &[&str]) -> String {
+    if names.is_empty() {
+        return "no elements".to_owned();
+    }
+    let names: Vec<String> = names.iter().map(|name| format!("<{name}>")).collect();
+    names.join(" or ")
+}
+
+fn collect_elements(
+    model: &Model,
+    particle: &Particle,
+    names: &mut HashMap<String, usize>,
+    wildcard: &mut bool,
+) {
+    match &particle.term {
+        Term::Element(id) => {
+            if let Some(element) = model.elements.get(*id) {
+                names
+                    .entry(element.expanded_name().into_owned())
+                    .or_insert(*id);
+            }
+        }
+        Term::Any => *wildcard = true,
+        Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => {
+            for particle in particles {
+                collect_elements(model, particle, names, wildcard);
+            }
+        }
+    }
+}
+
+/// Matches child element names against a content model, finding every position a particle
+/// can end at when it starts at another.
+struct Matcher<'a> {
+    model: &'a Model,
+    names: &'a [&'a str],
+    /// The furthest position an element was expected at, and what was expected there.
+    furthest: usize,
+    expected: BTreeSet<String>,
+}
+
+impl Matcher<'_> {
+    fn expect(&mut self, position: usize, expected: String) {
+        if position > self.furthest {
+            self.furthest = position;
+            self.expected.clear();
+        }
+        if position == self.furthest {
+            self.expected.insert(expected);
+        }
+    }
+
+    fn particle(&mut self, particle: &Particle, start: usize) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        if particle.min == 0 {
+            ends.insert(start);
+        }
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut frontier = BTreeSet::from([start]);
+        let mut count = 0;
+        while !frontier.is_empty() && particle.max.is_none_or(|max| count < max) {
+            count += 1;
+            let mut next = BTreeSet::new();
+            for position in frontier {
+                next.extend(self.term(&particle.term, position));
+            }
+            if count >= particle.min {
+                ends.extend(next.iter().copied());
+                // Past `min`, positions already reached lead nowhere new.
+                next.retain(|position| seen.insert(*position));
+            }
+            frontier = next;
+        }
+        ends
+    }
+
+    fn term(&mut self, term: &Term, start: usize) -> BTreeSet<usize> {
+        match term {
+            Term::Element(id) => {
+                let name = self
+                    .model
+                    .elements
+                    .get(*id)
+                    .map_or(Cow::Borrowed(""), |element| element.expanded_name());
+                if self.names.get(start) == Some(&name.as_ref()) {
+                    BTreeSet::from([start + 1])
+                } else {
+                    self.expect(start, format!("<{name}>"));
+                    BTreeSet::new()
+                }
+            }
+            Term::Any => {
+                if start < self.names.len() {
+                    BTreeSet::from([start + 1])
+                } else {
+                    self.expect(start, "any element".to_owned());
+                    BTreeSet::new()
+                }
+            }
+            Term::Sequence(particles) => {
+                let mut positions = BTreeSet::from([start]);
+                for particle in particles {
+                    let mut next = BTreeSet::new();
+                    for position in positions {
+                        next.extend(self.particle(particle, position));
+                    }
+                    positions = next;
+                }
+                positions
+            }
+            Term::Choice(particles) => {
+                let mut ends = BTreeSet::new();
+                for particle in particles {
+                    ends.extend(self.particle(particle, start));
+                }
+                ends
+            }
+            Term::All(particles) => self.all(particles, start),
+        }
+    }
+
+    /// Each particle of an `xs:all` group at most once, in any order, and every one with a
+    /// `minOccurs` above zero.
+    fn all(&mut self, particles: &[Particle], start: usize) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        let mut states = vec![(start, vec![false; particles.len()])];
+        let mut seen = HashSet::new();
+        while let Some((position, used)) = states.pop() {
+            if !seen.insert((position, used.clone())) {
+                continue;
+            }
+            let complete = particles
+                .iter()
+                .zip(&used)
+                .all(|(particle, used)| *used || particle.min == 0);
+            if complete {
+                ends.insert(position);
+            }
+            for (index, particle) in particles.iter().enumerate() {
+                if used.get(index).copied().unwrap_or(true) {
+                    continue;
+                }
+                let once = Particle {
+                    min: 1,
+                    max: Some(1),
+                    term: particle.term.clone(),
+                };
+                for end in self.particle(&once, position) {
+                    if end > position {
+                        let mut used = used.clone();
+                        if let Some(slot) = used.get_mut(index) {
+                            *slot = true;
+                        }
+                        states.push((end, used));
+                    }
+                }
+            }
+        }
+        ends
+    }
+}
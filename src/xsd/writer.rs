@@ -0,0 +1,282 @@
+use super::builtins::XSD_NAMESPACE;
+use super::model::{Content, Facet, Kind, Model, Particle, Term, Variety};
+use crate::config::InvalidChars;
+use crate::escape::{escape_xml_attr, AttrEscapes};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+impl Model {
+    /// The model as an XML Schema document. Global elements are written in the order they
+    /// were declared, each with its types inline; where a type contains itself, the inner
+    /// element is written without a type.
+    #[must_use]
+    pub fn to_xsd(&self) -> String {
+        let mut writer = Writer {
+            model: self,
+            output: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            depth: 0,
+            open: Vec::new(),
+        };
+        let mut roots: Vec<usize> = self.roots.values().copied().collect();
+        roots.sort_unstable();
+        let target = roots
+            .first()
+            .and_then(|root| self.elements.get(*root))
+            .filter(|element| !element.namespace.is_empty())
+            .map(|element| format!(" targetNamespace=\"{}\"", quoted(&element.namespace)))
+            .unwrap_or_default();
+        writer.start(&format!(
+            "<xs:schema xmlns:xs=\"{XSD_NAMESPACE}\"{target} elementFormDefault=\"qualified\">"
+        ));
+        for root in roots {
+            writer.element(root, "");
+        }
+        writer.end("xs:schema");
+        writer.output
+    }
+}
+
+/// Attribute values are written with any characters XML does not allow replaced.
+fn quoted(text: &str) -> Cow<'_, str> {
+    let escapes = AttrEscapes {
+        control_chars: true,
+        apos: false,
+    };
+    escape_xml_attr(text, escapes, InvalidChars::Replace).unwrap_or(Cow::Borrowed(text))
+}
+
+/// `minOccurs` and `maxOccurs` attributes, where they differ from one.
+fn occurs(particle: &Particle) -> String {
+    let mut occurs = String::new();
+    if particle.min != 1 {
+        let _ = write!(occurs, " minOccurs=\"{}\"", particle.min);
+    }
+    match particle.max {
+        None => occurs.push_str(" maxOccurs=\"unbounded\""),
+        Some(1) => {}
+        Some(max) => {
+            let _ = write!(occurs, " maxOccurs=\"{max}\"");
+        }
+    }
+    occurs
+}
+
+struct Writer<'m> {
+    model: &'m Model,
+    output: String,
+    depth: usize,
+    /// Complex types being written, so that a type containing itself is written once.
+    open: Vec<usize>,
+}
+
+impl Writer<'_> {
+    fn line(&mut self, markup: &str) {
+        for _ in 0..self.depth {
+            self.output.push_str("  ");
+        }
+        self.output.push_str(markup);
+        self.output.push('\n');
+    }
+
+    fn start(&mut self, markup: &str) {
+        self.line(markup);
+        self.depth += 1;
+    }
+
+    fn end(&mut self, tag: &str) {
+        self.depth = self.depth.saturating_sub(1);
+        self.line(&format!("</{tag}>"));
+    }
+
+    fn element(&mut self, id: usize, occurs: &str) {
+        let model = self.model;
+        let Some(element) = model.elements.get(id) else {
+            return;
+        };
+        let declaration = format!("<xs:element name=\"{}\"{occurs}", quoted(&element.name));
+        match element.kind {
+            Kind::Simple(simple) => {
+                if let Some(type_name) = self.type_name(simple) {
+                    self.line(&format!("{declaration} type=\"{type_name}\"/>"));
+                } else {
+                    self.start(&format!("{declaration}>"));
+                    self.simple_type(simple);
+                    self.end("xs:element");
+                }
+            }
+            Kind::Complex(complex) if !self.open.contains(&complex) => {
+                self.start(&format!("{declaration}>"));
+                self.open.push(complex);
+                self.complex_type(complex);
+                self.open.pop();
+                self.end("xs:element");
+            }
+            Kind::Any | Kind::Complex(_) => self.line(&format!("{declaration}/>")),
+        }
+    }
+
+    /// The name of a built-in type without further facets, which needs no declaration.
+    fn type_name(&self, simple: usize) -> Option<String> {
+        let simple = self.model.simple_types.get(simple)?;
+        match &simple.variety {
+            Variety::Atomic(builtin) if simple.facets.is_empty() => Some(format!("xs:{builtin}")),
+            Variety::Atomic(_) | Variety::List(_) | Variety::Union(_) => None,
+        }
+    }
+
+    fn simple_type(&mut self, id: usize) {
+        let model = self.model;
+        let Some(simple) = model.simple_types.get(id) else {
+            return;
+        };
+        self.start("<xs:simpleType>");
+        match &simple.variety {
+            Variety::Atomic(builtin) => {
+                self.start(&format!("<xs:restriction base=\"xs:{builtin}\">"));
+                for facet in &simple.facets {
+                    self.facet(facet);
+                }
+                self.end("xs:restriction");
+            }
+            Variety::List(item) => {
+                if let Some(type_name) = self.type_name(*item) {
+                    self.line(&format!("<xs:list itemType=\"{type_name}\"/>"));
+                } else {
+                    self.start("<xs:list>");
+                    self.simple_type(*item);
+                    self.end("xs:list");
+                }
+            }
+            Variety::Union(members) => {
+                self.start("<xs:union>");
+                for member in members {
+                    self.simple_type(*member);
+                }
+                self.end("xs:union");
+            }
+        }
+        self.end("xs:simpleType");
+    }
+
+    fn facet(&mut self, facet: &Facet) {
+        let (name, value) = match facet {
+            Facet::Enumeration(values) => {
+                for value in values {
+                    self.line(&format!("<xs:enumeration value=\"{}\"/>", quoted(value)));
+                }
+                return;
+            }
+            Facet::Bound {
+                text,
+                min,
+                inclusive,
+                ..
+            } => {
+                let name = match (min, inclusive) {
+                    (true, true) => "minInclusive",
+                    (true, false) => "minExclusive",
+                    (false, true) => "maxInclusive",
+                    (false, false) => "maxExclusive",
+                };
+                (name, text.clone())
+            }
+            Facet::Length(length) => ("length", length.to_string()),
+            Facet::MinLength(min) => ("minLength", min.to_string()),
+            Facet::MaxLength(max) => ("maxLength", max.to_string()),
+        };
+        self.line(&format!("<xs:{name} value=\"{}\"/>", quoted(&value)));
+    }
+
+    fn complex_type(&mut self, id: usize) {
+        let model = self.model;
+        let Some(complex) = model.complex_types.get(id) else {
+            return;
+        };
+        let mixed = if complex.mixed { " mixed=\"true\"" } else { "" };
+        match &complex.content {
+            Content::Empty if complex.attributes.is_empty() && !complex.any_attribute => {
+                self.line(&format!("<xs:complexType{mixed}/>"));
+                return;
+            }
+            Content::Empty => self.start(&format!("<xs:complexType{mixed}>")),
+            Content::Simple(simple) => {
+                // Extensions name their base, so facets of an anonymous type are left out.
+                let base = model
+                    .builtin(*simple)
+                    .map_or_else(|| "xs:string".to_owned(), |builtin| format!("xs:{builtin}"));
+                self.start("<xs:complexType>");
+                self.start("<xs:simpleContent>");
+                self.start(&format!("<xs:extension base=\"{base}\">"));
+                self.attributes(id);
+                self.end("xs:extension");
+                self.end("xs:simpleContent");
+                self.end("xs:complexType");
+                return;
+            }
+            Content::Elements(particle) => {
+                self.start(&format!("<xs:complexType{mixed}>"));
+                if let Term::Element(_) | Term::Any = particle.term {
+                    // Content is always a model group.
+                    self.start("<xs:sequence>");
+                    self.particle(particle);
+                    self.end("xs:sequence");
+                } else {
+                    self.particle(particle);
+                }
+            }
+        }
+        self.attributes(id);
+        self.end("xs:complexType");
+    }
+
+    fn particle(&mut self, particle: &Particle) {
+        let occurs = occurs(particle);
+        let (tag, particles) = match &particle.term {
+            Term::Element(id) => {
+                self.element(*id, &occurs);
+                return;
+            }
+            Term::Any => {
+                self.line(&format!("<xs:any processContents=\"lax\"{occurs}/>"));
+                return;
+            }
+            Term::Sequence(particles) => ("xs:sequence", particles),
+            Term::Choice(particles) => ("xs:choice", particles),
+            Term::All(particles) => ("xs:all", particles),
+        };
+        self.start(&format!("<{tag}{occurs}>"));
+        for particle in particles {
+            self.particle(particle);
+        }
+        self.end(tag);
+    }
+
+    fn attributes(&mut self, complex: usize) {
+        let model = self.model;
+        let Some(complex) = model.complex_types.get(complex) else {
+            return;
+        };
+        for attribute in &complex.attributes {
+            let mut declaration = format!("<xs:attribute name=\"{}\"", quoted(&attribute.name));
+            let type_name = attribute
+                .kind
+                .map(|simple| (simple, self.type_name(simple)));
+            if let Some((_, Some(type_name))) = &type_name {
+                let _ = write!(declaration, " type=\"{type_name}\"");
+            }
+            if attribute.required {
+                declaration.push_str(" use=\"required\"");
+            }
+            if let Some((simple, None)) = type_name {
+                self.start(&format!("{declaration}>"));
+                self.simple_type(simple);
+                self.end("xs:attribute");
+            } else {
+                self.line(&format!("{declaration}/>"));
+            }
+        }
+        if complex.any_attribute {
+            self.line("<xs:anyAttribute processContents=\"lax\"/>");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+import asyncio
+import io
+
+import pytest
+
+import xmltodict_rs
+
+XML = "<root>" + "<item>a</item>" * 500 + "<name>привет</name></root>"
+
+
+async def chunks(data, size=64):
+    for start in range(0, len(data), size):
+        await asyncio.sleep(0)
+        yield data[start : start + size]
+
+
+class AsyncOutput:
+    def __init__(self):
+        self.chunks = []
+
+    async def write(self, data):
+        await asyncio.sleep(0)
+        self.chunks.append(data)
+
+
+def test_parse_async():
+    result = asyncio.run(xmltodict_rs.parse_async(chunks(XML.encode())))
+    assert result == xmltodict_rs.parse(XML)
+
+
+def test_parse_async_text_chunks_and_options():
+    async def main():
+        return await xmltodict_rs.parse_async(chunks(XML), force_list=("name",), attr_prefix="_")
+
+    assert asyncio.run(main())["root"]["name"] == ["привет"]
+
+
+def test_parse_async_encoding():
+    data = XML.encode("cp1251")
+    result = asyncio.run(xmltodict_rs.parse_async(chunks(data), encoding="cp1251"))
+    assert result["root"]["name"] == "привет"
+
+
+def test_parse_async_does_not_block_the_event_loop():
+    async def main():
+        ticks = 0
+
+        async def ticker():
+            nonlocal ticks
+            while True:
+                ticks += 1
+                await asyncio.sleep(0)
+
+        task = asyncio.create_task(ticker())
+        result = await xmltodict_rs.parse_async(chunks(XML.encode(), size=16))
+        task.cancel()
+        return result, ticks
+
+    result, ticks = asyncio.run(main())
+    assert len(result["root"]["item"]) == 500
+    assert ticks > len(XML) // 16
+
+
+def test_parse_async_errors():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        asyncio.run(xmltodict_rs.parse_async(chunks(b"<a><b></a>")))
+
+    async def failing():
+        yield b"<a>"
+        raise ConnectionResetError("peer closed")
+
+    with pytest.raises(ConnectionResetError, match="peer closed"):
+        asyncio.run(xmltodict_rs.parse_async(failing()))
+
+
+def test_parse_async_requires_async_iterable():
+    async def main():
+        return await xmltodict_rs.parse_async([b"<a/>"])
+
+    with pytest.raises(TypeError, match="source must be an async iterable, not 'list'"):
+        asyncio.run(main())
+
+
+def test_parse_async_is_a_coroutine():
+    coroutine = xmltodict_rs.parse_async(chunks(b"<a>1</a>"))
+    assert asyncio.iscoroutine(coroutine)
+
+    async def main():
+        result = await asyncio.create_task(coroutine)
+        with pytest.raises(RuntimeError, match="cannot reuse already awaited coroutine"):
+            await coroutine
+        return result
+
+    assert asyncio.run(main()) == {"a": "1"}
+
+
+def test_parse_async_with_timeout():
+    async def stalled():
+        yield b"<a>"
+        await asyncio.sleep(10)
+
+    async def main():
+        return await asyncio.wait_for(xmltodict_rs.parse_async(stalled()), 0.05)
+
+    with pytest.raises(asyncio.TimeoutError):
+        asyncio.run(main())
+
+
+DOC = {"root": {"item": ["a"] * 500, "name": "привет"}}
+
+
+def test_unparse_async():
+    output = AsyncOutput()
+    assert asyncio.run(xmltodict_rs.unparse_async(DOC, output)) is None
+    expected = io.BytesIO()
+    xmltodict_rs.unparse(DOC, output=expected)
+    assert b"".join(output.chunks) == expected.getvalue()
+
+
+def test_unparse_async_options():
+    output = AsyncOutput()
+    asyncio.run(xmltodict_rs.unparse_async(DOC, output, encoding="cp1251", pretty=True))
+    text = b"".join(output.chunks).decode("cp1251")
+    assert text == xmltodict_rs.unparse(DOC, encoding="cp1251", pretty=True)
+
+
+def test_unparse_async_plain_write():
+    output = io.BytesIO()
+    asyncio.run(xmltodict_rs.unparse_async({"a": "1"}, output, full_document=False))
+    assert output.getvalue() == b"<a>1</a>"
+
+
+def test_unparse_async_errors():
+    with pytest.raises(ValueError, match="exactly one root"):
+        asyncio.run(xmltodict_rs.unparse_async({"a": "1", "b": "2"}, AsyncOutput()))
+
+    class Failing:
+        async def write(self, data):
+            raise BrokenPipeError("gone")
+
+    with pytest.raises(BrokenPipeError, match="gone"):
+        asyncio.run(xmltodict_rs.unparse_async({"a": "1"}, Failing()))
@@ -0,0 +1,126 @@
+from collections import OrderedDict
+
+import pytest
+
+import xmltodict_rs
+
+XML = (
+    '<feed xmlns="urn:atom" xmlns:os="urn:os">'
+    '<title type="text">Books</title>'
+    "<os:total>2</os:total>"
+    '<entry id="1">Dune</entry>'
+    '<entry id="2"/>'
+    "<empty/>"
+    "</feed>"
+)
+
+
+# BadgerFish
+
+
+def test_parse_badgerfish():
+    assert xmltodict_rs.parse(XML, convention="badgerfish") == {
+        "feed": {
+            "@xmlns": {"$": "urn:atom", "os": "urn:os"},
+            "title": {"@type": "text", "$": "Books"},
+            "os:total": {"$": "2"},
+            "entry": [{"@id": "1", "$": "Dune"}, {"@id": "2"}],
+            "empty": {},
+        }
+    }
+
+
+def test_unparse_badgerfish_roundtrip():
+    parsed = xmltodict_rs.parse(XML, convention="badgerfish")
+    assert xmltodict_rs.unparse(parsed, convention="badgerfish", full_document=False) == (
+        XML.replace('<entry id="2"/>', '<entry id="2"></entry>').replace(
+            "<empty/>", "<empty></empty>"
+        )
+    )
+
+
+def test_badgerfish_replaces_attr_prefix_and_cdata_key():
+    parsed = xmltodict_rs.parse(
+        '<a x="1">t</a>', convention="badgerfish", attr_prefix="_", cdata_key="text"
+    )
+    assert parsed == {"a": {"@x": "1", "$": "t"}}
+    assert xmltodict_rs.unparse(
+        parsed, convention="badgerfish", attr_prefix="_", full_document=False
+    ) == '<a x="1">t</a>'
+
+
+# GData
+
+
+def test_parse_gdata():
+    assert xmltodict_rs.parse(XML, convention="gdata") == {
+        "feed": {
+            "xmlns": "urn:atom",
+            "xmlns$os": "urn:os",
+            "title": {"type": "text", "$t": "Books"},
+            "os$total": {"$t": "2"},
+            "entry": [{"id": "1", "$t": "Dune"}, {"id": "2"}],
+            "empty": {},
+        }
+    }
+
+
+def test_unparse_gdata():
+    document = {"feed": {"xmlns$os": "urn:os", "os$total": {"$t": 2}, "link": [{"href": "a"}]}}
+    assert xmltodict_rs.unparse(document, convention="gdata", full_document=False) == (
+        '<feed xmlns:os="urn:os"><os:total>2</os:total><link href="a"></link></feed>'
+    )
+
+
+# Parker
+
+
+def test_parse_parker():
+    assert xmltodict_rs.parse(XML, convention="parker") == {
+        "title": "Books",
+        "os:total": "2",
+        "entry": ["Dune", None],
+        "empty": None,
+    }
+
+
+def test_parse_parker_text_root_and_mixed_content():
+    assert xmltodict_rs.parse("<a>text</a>", convention="parker") == "text"
+    assert xmltodict_rs.parse("<a>x<b>1</b>y</a>", convention="parker") == {"b": "1"}
+
+
+def test_unparse_parker_writes_elements_only():
+    document = {"root": {"a": ["1", "2"], "b": None}}
+    assert xmltodict_rs.unparse(document, convention="parker", full_document=False) == (
+        "<root><a>1</a><a>2</a><b></b></root>"
+    )
+
+
+# Options
+
+
+def test_convention_keeps_comments_and_doctype():
+    xml = "<!DOCTYPE a><a><!--c--><b>1</b></a>"
+    assert xmltodict_rs.parse(
+        xml, convention="badgerfish", process_comments=True, process_doctype=True
+    ) == {"#doctype": "a", "a": {"#comment": "c", "b": {"$": "1"}}}
+
+
+def test_convention_with_dict_constructor_and_force_list():
+    parsed = xmltodict_rs.parse(
+        "<a><b>1</b></a>", convention="gdata", dict_constructor=OrderedDict, force_list=("b",)
+    )
+    assert parsed == {"a": {"b": [{"$t": "1"}]}}
+    assert type(parsed["a"]) is OrderedDict
+
+
+def test_roundtrip_with_convention():
+    xml = '<a x="1"><b>t</b></a>'
+    assert xmltodict_rs.roundtrip(xml, convention="badgerfish", full_document=False) == xml
+
+
+@pytest.mark.parametrize("function", [xmltodict_rs.parse, xmltodict_rs.unparse])
+def test_rejects_unknown_convention(function):
+    argument = "<a/>" if function is xmltodict_rs.parse else {"a": None}
+    with pytest.raises(ValueError, match="convention must be 'xmltodict', 'badgerfish'"):
+        function(argument, convention="jsonml")
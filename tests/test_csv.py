@@ -0,0 +1,73 @@
+import csv
+import io
+
+import pytest
+
+import xmltodict_rs
+
+XML = """<export>
+  <row id="1"><name>Tea</name><price>3.5</price></row>
+  <row id="2"><name>Coffee, "dark"</name><vendor>Acme</vendor></row>
+  <row id="3"><name>Two
+lines</name><price/></row>
+</export>"""
+
+
+def to_csv(xml, record_path, **kwargs):
+    output = io.StringIO(newline="")
+    rows = xmltodict_rs.to_csv(xml, record_path, output, **kwargs)
+    return rows, output.getvalue()
+
+
+def test_columns_come_from_the_first_record():
+    rows, text = to_csv(XML, "export/row")
+    assert rows == 3
+    assert text == (
+        "@id,name,price\r\n"
+        "1,Tea,3.5\r\n"
+        '2,"Coffee, ""dark""",\r\n'
+        '3,"Two\nlines",\r\n'
+    )
+
+
+def test_output_reads_back_with_the_csv_module():
+    _, text = to_csv(XML, "**/row", columns=["name", "vendor", "@id"])
+    assert list(csv.reader(io.StringIO(text, newline=""))) == [
+        ["name", "vendor", "@id"],
+        ["Tea", "", "1"],
+        ['Coffee, "dark"', "Acme", "2"],
+        ["Two\nlines", "", "3"],
+    ]
+
+
+def test_binary_output_is_encoded():
+    output = io.BytesIO()
+    assert xmltodict_rs.to_csv("<r><i>é</i></r>", "r", output, output_encoding="latin-1") == 1
+    assert output.getvalue() == b"i\r\n\xe9\r\n"
+
+
+def test_large_documents_are_written_in_chunks():
+    class Recorder:
+        def __init__(self):
+            self.chunks = []
+
+        def write(self, data):
+            self.chunks.append(data)
+
+    xml = "<r>" + "<i><v>value</v></i>" * 20_000 + "</r>"
+    output = Recorder()
+    assert xmltodict_rs.to_csv(io.BytesIO(xml.encode()), "r/i", output) == 20_000
+    assert len(output.chunks) > 1
+    assert b"".join(output.chunks) == b"v\r\n" + b"value\r\n" * 20_000
+
+
+def test_no_records():
+    assert to_csv("<r/>", "r/i") == (0, "")
+    assert to_csv("<r/>", "r/i", columns=["a", "@b"]) == (0, "a,@b\r\n")
+
+
+def test_errors():
+    with pytest.raises(ValueError, match="given twice"):
+        to_csv(XML, "**/row", columns=["a", "a"])
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        to_csv("<r><i/><i></r>", "r/i")
@@ -0,0 +1,66 @@
+import pytest
+
+import xmltodict_rs
+
+
+def kinds(differences):
+    return [(difference["path"], difference["kind"]) for difference in differences]
+
+
+def test_equal_documents_have_no_differences():
+    assert xmltodict_rs.diff('<r id="1"><a>x</a></r>', '<r  id="1">\n  <a>x</a>\n</r>') == []
+
+
+def test_changed_removed_and_added_values():
+    differences = xmltodict_rs.diff(
+        '<r id="1"><a>x</a><b>gone</b></r>',
+        '<r id="2"><a>y</a><c><d>new</d></c></r>',
+    )
+    assert differences == [
+        {"path": "r/@id", "kind": "changed", "left": "1", "right": "2"},
+        {"path": "r/a", "kind": "changed", "left": "x", "right": "y"},
+        {"path": "r/b", "kind": "removed", "left": "gone", "right": None},
+        {"path": "r/c", "kind": "added", "left": None, "right": {"d": "new"}},
+    ]
+
+
+def test_repeated_elements_compare_by_position():
+    differences = xmltodict_rs.diff("<r><i>1</i><i>2</i></r>", "<r><i>2</i><i>1</i><i>3</i></r>")
+    assert kinds(differences) == [
+        ("r/i[1]", "changed"),
+        ("r/i[2]", "changed"),
+        ("r/i[3]", "added"),
+    ]
+
+
+def test_ignore_order_for_matches_equal_items():
+    a = '<r><i n="1"/><i n="2"/><i n="2"/></r>'
+    b = '<r><i n="2"/><i n="3"/><i n="1"/></r>'
+    differences = xmltodict_rs.diff(a, b, ignore_order_for=["**/i"])
+    assert differences == [
+        {"path": "r/i[3]", "kind": "removed", "left": {"@n": "2"}, "right": None},
+        {"path": "r/i[2]", "kind": "added", "left": None, "right": {"@n": "3"}},
+    ]
+
+
+def test_element_repeated_in_one_document_only():
+    differences = xmltodict_rs.diff("<r><i>1</i></r>", "<r><i>1</i><i>2</i></r>")
+    assert differences == [{"path": "r/i[2]", "kind": "added", "left": None, "right": "2"}]
+
+
+def test_parse_options_apply_to_both_documents():
+    a = '<x:r xmlns:x="urn:x"><x:a> 1 </x:a></x:r>'
+    b = '<y:r xmlns:y="urn:x"><y:a>1</y:a></y:r>'
+    assert xmltodict_rs.diff(a, b, process_namespaces=True) == []
+    assert xmltodict_rs.diff(b"<r>\xe9</r>", "<r>é</r>", encoding="latin-1") == []
+
+
+def test_malformed_documents_raise():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.diff("<r/>", "<r>")
+
+
+def test_deep_documents_are_refused():
+    deep = "<a>" * 300_000 + "</a>" * 300_000
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.diff(deep, deep)
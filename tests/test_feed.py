@@ -0,0 +1,151 @@
+from datetime import datetime, timedelta, timezone
+
+import pytest
+
+import xmltodict_rs
+
+RSS = """<?xml version="1.0"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"
+     xmlns:dc="http://purl.org/dc/elements/1.1/"
+     xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>Books</title>
+    <atom:link href="https://example.com/feed.xml" rel="self"/>
+    <link>https://example.com/</link>
+    <description>New books</description>
+    <lastBuildDate>Tue, 02 Jan 2024 10:00:00 GMT</lastBuildDate>
+    <item>
+      <title>Dune</title>
+      <link>https://example.com/dune</link>
+      <guid isPermaLink="false">book-1</guid>
+      <description>A desert planet</description>
+      <content:encoded><![CDATA[<p>Arrakis</p>]]></content:encoded>
+      <dc:creator>Frank Herbert</dc:creator>
+      <pubDate>Mon, 01 Jan 2024 08:30:00 +0200</pubDate>
+    </item>
+  </channel>
+</rss>"""
+
+ATOM = """<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="text">Books</title>
+  <subtitle>New books</subtitle>
+  <link rel="self" href="https://example.com/atom.xml"/>
+  <link href="https://example.com/"/>
+  <updated>2024-01-02T10:00:00Z</updated>
+  <entry>
+    <id>urn:book:1</id>
+    <title>Dune</title>
+    <link rel="alternate" href="https://example.com/dune"/>
+    <author><name>Frank Herbert</name></author>
+    <author><name>Brian Herbert</name></author>
+    <summary>A desert planet</summary>
+    <content type="html">&lt;p&gt;Arrakis&lt;/p&gt;</content>
+    <published>2024-01-01T08:30:00+02:00</published>
+    <updated>2024-01-01T09:00:00Z</updated>
+  </entry>
+  <entry><id>urn:book:2</id></entry>
+</feed>"""
+
+UTC = timezone.utc
+PLUS_2 = timezone(timedelta(hours=2))
+
+
+# RSS
+
+
+def test_parse_feed_rss():
+    assert xmltodict_rs.parse_feed(RSS) == {
+        "format": "rss",
+        "title": "Books",
+        "link": "https://example.com/",
+        "description": "New books",
+        "updated": datetime(2024, 1, 2, 10, 0, tzinfo=UTC),
+        "entries": [
+            {
+                "id": "book-1",
+                "title": "Dune",
+                "link": "https://example.com/dune",
+                "summary": "A desert planet",
+                "content": "<p>Arrakis</p>",
+                "author": "Frank Herbert",
+                "published": datetime(2024, 1, 1, 8, 30, tzinfo=PLUS_2),
+                "updated": None,
+            }
+        ],
+    }
+
+
+def test_parse_feed_rss_without_items():
+    feed = xmltodict_rs.parse_feed(
+        "<rss><channel><title>Empty</title><pubDate>soon</pubDate></channel></rss>"
+    )
+    assert feed["entries"] == []
+    assert feed["updated"] == "soon"
+    assert feed["link"] is None
+
+
+def test_parse_feed_single_item_is_a_list():
+    feed = xmltodict_rs.parse_feed("<rss><channel><item><title>A</title></item></channel></rss>")
+    assert [entry["title"] for entry in feed["entries"]] == ["A"]
+
+
+# Atom
+
+
+def test_parse_feed_atom():
+    feed = xmltodict_rs.parse_feed(ATOM.encode())
+    assert feed["format"] == "atom"
+    assert feed["title"] == "Books"
+    assert feed["link"] == "https://example.com/"
+    assert feed["description"] == "New books"
+    assert feed["updated"] == datetime(2024, 1, 2, 10, 0, tzinfo=UTC)
+    assert feed["entries"] == [
+        {
+            "id": "urn:book:1",
+            "title": "Dune",
+            "link": "https://example.com/dune",
+            "summary": "A desert planet",
+            "content": "<p>Arrakis</p>",
+            "author": "Frank Herbert",
+            "published": datetime(2024, 1, 1, 8, 30, tzinfo=PLUS_2),
+            "updated": datetime(2024, 1, 1, 9, 0, tzinfo=UTC),
+        },
+        {
+            "id": "urn:book:2",
+            "title": None,
+            "link": None,
+            "summary": None,
+            "content": None,
+            "author": None,
+            "published": None,
+            "updated": None,
+        },
+    ]
+
+
+def test_parse_feed_atom_requires_namespace():
+    with pytest.raises(ValueError, match="not an RSS 2.0 or Atom feed"):
+        xmltodict_rs.parse_feed("<feed><title>Books</title></feed>")
+
+
+# Errors
+
+
+@pytest.mark.parametrize(
+    ("xml", "message"),
+    [
+        ("<html/>", "not an RSS 2.0 or Atom feed"),
+        ("<rss/>", "RSS feed has no channel"),
+    ],
+)
+def test_parse_feed_rejects_other_documents(xml, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.parse_feed(xml)
+
+
+def test_parse_feed_applies_limits():
+    with pytest.raises(xmltodict_rs.EntitiesForbidden):
+        xmltodict_rs.parse_feed('<!DOCTYPE rss [<!ENTITY x "y">]><rss/>', forbid_entities=True)
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.parse_feed(RSS, max_depth=2)
@@ -0,0 +1,79 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+CATALOG = """<catalog>
+  <book id="1" lang="en"><title>Dune</title><price>9.5</price></book>
+  <book id="2"><title>Solaris</title><price>7</price></book>
+  <book id="3" lang="en"><title>Ubik</title><title>Ubik (reissue)</title></book>
+  <magazine id="3"><title>Wired</title></magazine>
+</catalog>"""
+
+
+# Matches
+
+
+def test_find_attribute_predicate():
+    assert xmltodict_rs.find(CATALOG, "catalog/book[@id='3']/title") == [
+        {"title": "Ubik"},
+        {"title": "Ubik (reissue)"},
+    ]
+
+
+def test_find_returns_elements_as_parse_does():
+    assert xmltodict_rs.find(CATALOG, "/catalog/book[1]") == [
+        xmltodict_rs.parse('<book id="1" lang="en"><title>Dune</title><price>9.5</price></book>')
+    ]
+
+
+@pytest.mark.parametrize(
+    ("path", "titles"),
+    [
+        ("catalog/*[@id='3']/title[1]", ["Ubik", "Wired"]),
+        ('catalog/book[@lang="en"][2]/title[2]', ["Ubik (reissue)"]),
+        ("catalog/book[@lang]/title[1]", ["Dune", "Ubik"]),
+        ("catalog/*[2]/title", ["Solaris"]),
+        ("catalog/book[9]/title", []),
+        ("other/book", []),
+    ],
+)
+def test_find_steps(path, titles):
+    assert [match["title"] for match in xmltodict_rs.find(CATALOG, path)] == titles
+
+
+def test_find_parse_options():
+    result = xmltodict_rs.find(
+        CATALOG,
+        "catalog/book[@id='1']",
+        attr_prefix="",
+        force_list=("title",),
+        infer_types=True,
+    )
+    assert result == [{"book": {"id": 1, "lang": "en", "title": ["Dune"], "price": 9.5}}]
+
+
+def test_find_inputs():
+    data = CATALOG.encode()
+    expected = [{"title": "Wired"}]
+    assert xmltodict_rs.find(data, "catalog/magazine/title") == expected
+    assert xmltodict_rs.find(io.BytesIO(data), "catalog/magazine/title") == expected
+    assert xmltodict_rs.find(iter([data[:40], data[40:]]), "catalog/magazine/title") == expected
+
+
+# Errors
+
+
+@pytest.mark.parametrize(
+    "path",
+    ["", "catalog//title", "catalog/book[@id=3]", "catalog/book[0]", "catalog/book/@id"],
+)
+def test_find_rejects_unsupported_paths(path):
+    with pytest.raises(ValueError):
+        xmltodict_rs.find(CATALOG, path)
+
+
+def test_find_checks_the_whole_document():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.find("<r><a>1</a><b></r>", "r/a")
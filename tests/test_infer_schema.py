@@ -0,0 +1,93 @@
+import pytest
+
+import xmltodict_rs
+
+SAMPLES = [
+    """<order id="1" state="open">
+  <paid>true</paid>
+  <line>2</line>
+  <line>3</line>
+  <zip>007</zip>
+</order>""",
+    '<order id="2"><paid>false</paid><line>1.5</line><zip>12</zip><note/></order>',
+]
+
+
+def test_types_occurrences_and_attributes():
+    xsd = xmltodict_rs.infer_schema(SAMPLES)
+    assert xsd.startswith('<?xml version="1.0" encoding="UTF-8"?>\n<xs:schema')
+    assert '<xs:element name="paid" type="xs:boolean"/>' in xsd
+    assert '<xs:element name="line" maxOccurs="unbounded" type="xs:decimal"/>' in xsd
+    assert '<xs:element name="zip" type="xs:string"/>' in xsd
+    assert '<xs:element name="note" minOccurs="0" type="xs:string"/>' in xsd
+    assert '<xs:attribute name="id" type="xs:int" use="required"/>' in xsd
+    assert '<xs:attribute name="state" type="xs:string"/>' in xsd
+
+
+def test_samples_are_valid_against_the_schema():
+    xsd = xmltodict_rs.infer_schema(SAMPLES)
+    for sample in SAMPLES:
+        assert xmltodict_rs.validate(sample, xsd) == []
+    issues = xmltodict_rs.validate('<order id="x"><paid>yes</paid></order>', xsd)
+    assert [issue["path"] for issue in issues] == ["order/@id", "order/paid", "order"]
+
+
+def test_schema_guides_parse():
+    xsd = xmltodict_rs.infer_schema(SAMPLES)
+    result = xmltodict_rs.parse('<order id="3"><paid>true</paid><line>4</line></order>', xsd=xsd)
+    assert result == {"order": {"@id": 3, "paid": True, "line": [4.0]}}
+
+
+def test_dict_samples():
+    sample = {"a": {"@n": "1", "b": ["x", "y"], "#text": "t"}}
+    xsd = xmltodict_rs.infer_schema(sample)
+    assert '<xs:complexType mixed="true">' in xsd
+    assert '<xs:element name="b" maxOccurs="unbounded" type="xs:string"/>' in xsd
+    assert '<xs:attribute name="n" type="xs:int" use="required"/>' in xsd
+
+
+def test_dict_samples_with_custom_keys():
+    sample = {"a": {"_n": "1", "value": "2"}}
+    xsd = xmltodict_rs.infer_schema([sample], attr_prefix="_", cdata_key="value")
+    assert '<xs:extension base="xs:int">' in xsd
+    assert '<xs:attribute name="n" type="xs:int" use="required"/>' in xsd
+
+
+def test_mixed_samples_and_bytes():
+    xsd = xmltodict_rs.infer_schema((b"<a>1</a>", {"a": "2"}))
+    assert '<xs:element name="a" type="xs:int"/>' in xsd
+
+
+def test_varying_order_becomes_choice():
+    xsd = xmltodict_rs.infer_schema(["<a><b/><c/></a>", "<a><c/><b/></a>"])
+    assert '<xs:choice minOccurs="0" maxOccurs="unbounded">' in xsd
+    assert xmltodict_rs.validate("<a><c/><c/><b/></a>", xsd) == []
+
+
+def test_several_roots():
+    xsd = xmltodict_rs.infer_schema(["<a>1</a>", "<b>x</b>"])
+    assert xmltodict_rs.validate("<a>2</a>", xsd) == []
+    assert xmltodict_rs.validate("<b>y</b>", xsd) == []
+
+
+def test_namespace_of_the_root_is_targeted():
+    sample = '<o:order xmlns:o="urn:o"><o:line>2</o:line></o:order>'
+    xsd = xmltodict_rs.infer_schema(sample)
+    assert 'targetNamespace="urn:o"' in xsd
+    assert xmltodict_rs.validate(sample, xsd) == []
+    assert xmltodict_rs.validate('<order xmlns="urn:o"><line>3</line></order>', xsd) == []
+
+
+def test_recursive_elements():
+    xsd = xmltodict_rs.infer_schema("<node><node><node>x</node></node></node>")
+    assert xmltodict_rs.validate("<node><node><node>y</node></node></node>", xsd) == []
+
+
+def test_no_samples():
+    with pytest.raises(ValueError, match="at least one sample"):
+        xmltodict_rs.infer_schema([])
+
+
+def test_malformed_sample():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.infer_schema("<a><b></a>")
@@ -0,0 +1,183 @@
+import json
+
+import pytest
+
+import xmltodict_rs
+
+XML = """<?xml version="1.0"?>
+<library id="1">
+  <!-- books -->
+  <book lang="en">
+    <title>Dune</title>
+    <author>Frank Herbert</author>
+    <note>tabs\tand "quotes" and \\ backslashes</note>
+  </book>
+  <book lang="ru">
+    <title>Мастер и Маргарита</title>
+    <author/>
+    <note>𝄞 &amp; &lt;tag&gt;</note>
+  </book>
+</library>"""
+
+
+# XML to JSON
+
+
+@pytest.mark.parametrize(
+    "options",
+    [
+        {},
+        {"indent": 2},
+        {"indent": "\t", "ensure_ascii": False},
+        {"indent": 0},
+    ],
+)
+def test_xml_to_json_matches_json_dumps(options):
+    expected = json.dumps(xmltodict_rs.parse(XML), **options)
+    assert xmltodict_rs.xml_to_json(XML, **options) == expected
+
+
+def test_xml_to_json_applies_parse_options():
+    options = {
+        "attr_prefix": "",
+        "cdata_key": "_text",
+        "force_cdata": True,
+        "process_comments": True,
+        "xml_attribs": True,
+    }
+    assert xmltodict_rs.xml_to_json(XML, **options) == json.dumps(
+        xmltodict_rs.parse(XML, **options)
+    )
+
+
+def test_xml_to_json_namespaces():
+    xml = '<a xmlns="http://a.com/" xmlns:b="http://b.com/"><b:x>1</b:x></a>'
+    options = {"process_namespaces": True, "namespaces": {"http://a.com/": None}}
+    assert json.loads(xmltodict_rs.xml_to_json(xml, **options)) == xmltodict_rs.parse(
+        xml, **options
+    )
+
+
+def test_xml_to_json_bytes_and_encoding():
+    xml = '<?xml version="1.0" encoding="latin-1"?><a>café</a>'
+    assert xmltodict_rs.xml_to_json(xml.encode("latin-1")) == '{"a": "caf\\u00e9"}'
+    assert xmltodict_rs.xml_to_json(xml.encode(), encoding="utf-8") == '{"a": "caf\\u00e9"}'
+
+
+@pytest.mark.parametrize(
+    "options",
+    [
+        {"force_list": True},
+        {"force_list": {"book", "title"}},
+        {"force_list": "author"},
+        {"force_list": ["library/book/@lang", "**/note"]},
+        {"infer_types": True, "attr_prefix": ""},
+        {"infer_types": ["bool"]},
+        {"on_duplicate": "last"},
+        {"on_duplicate": "first", "force_list": ("book",)},
+    ],
+)
+def test_xml_to_json_takes_native_value_options(options):
+    assert xmltodict_rs.xml_to_json(XML, **options) == json.dumps(
+        xmltodict_rs.parse(XML, **options)
+    )
+
+
+def test_xml_to_json_infers_types_as_parse_does():
+    xml = (
+        "<a><i>-12</i><p>+7</p><big>123456789012345678901234</big><f>1.50</f>"
+        "<e>1e16</e><s>1.5e-5</s><t>TRUE</t><n>null</n><z>007</z></a>"
+    )
+    assert xmltodict_rs.xml_to_json(xml, infer_types=True) == json.dumps(
+        xmltodict_rs.parse(xml, infer_types=True)
+    )
+
+
+def test_xml_to_json_rejects_hook_options():
+    with pytest.raises(TypeError, match="force_list cannot be a callable"):
+        xmltodict_rs.xml_to_json("<a/>", force_list=lambda path, key, value: True)
+    with pytest.raises(TypeError):
+        xmltodict_rs.xml_to_json("<a/>", postprocessor=lambda path, key, value: (key, value))
+    with pytest.raises(ValueError, match="on_duplicate"):
+        xmltodict_rs.xml_to_json("<a/>", on_duplicate="merge")
+
+
+def test_xml_to_json_errors():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.xml_to_json("<a>")
+    with pytest.raises(xmltodict_rs.EntitiesForbidden):
+        xmltodict_rs.xml_to_json('<!DOCTYPE a [<!ENTITY e "x">]><a/>', forbid_entities=True)
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.xml_to_json(XML, max_depth=2)
+    with pytest.raises(TypeError, match="indent must be"):
+        xmltodict_rs.xml_to_json("<a/>", indent=1.5)
+
+
+# JSON to XML
+
+
+@pytest.mark.parametrize(
+    "document",
+    [
+        {"a": "text"},
+        {"a": {"@id": "1", "#text": "x < y & z"}},
+        {"a": {"b": ["1", None, {"c": "é 𝄞"}], "d": {}}},
+        {"a": {"#comment": "note", "b": "tab\there"}},
+    ],
+)
+def test_json_to_xml_matches_unparse(document):
+    assert xmltodict_rs.json_to_xml(json.dumps(document)) == xmltodict_rs.unparse(document)
+
+
+def test_json_to_xml_scalars():
+    text = '{"a": {"@n": 1.5e3, "i": [10, -0.5, true, false, null]}}'
+    assert xmltodict_rs.json_to_xml(text, full_document=False) == (
+        '<a n="1.5e3"><i>10</i><i>-0.5</i><i>true</i><i>false</i><i></i></a>'
+    )
+
+
+def test_json_to_xml_applies_unparse_options():
+    document = {"r": {"x": {"@k": "v", "#text": "1"}, "y": None}}
+    options = {
+        "pretty": True,
+        "indent": "  ",
+        "short_empty_elements": True,
+        "full_document": False,
+        "namespaces": {"http://ns.com/": "ns"},
+    }
+    assert xmltodict_rs.json_to_xml(json.dumps(document), **options) == xmltodict_rs.unparse(
+        document, **options
+    )
+
+
+def test_json_to_xml_roundtrip():
+    assert xmltodict_rs.json_to_xml(xmltodict_rs.xml_to_json(XML)) == xmltodict_rs.unparse(
+        xmltodict_rs.parse(XML)
+    )
+
+
+@pytest.mark.parametrize(
+    ("text", "message"),
+    [
+        ("[1]", r"must be an object: line 1 column 1 \(char 0\)"),
+        ('{"a": 1', r"Expecting ',' delimiter: line 1 column 8 \(char 7\)"),
+        ('{"a": 1} x', r"Extra data: line 1 column 10 \(char 9\)"),
+        ("{'a': 1}", "Expecting property name enclosed in double quotes"),
+        ('{"a": "x', "Unterminated string starting at"),
+        ('{"a": "\\q"}', "Invalid \\\\escape"),
+        ('{"a": ' + "[" * 600 + "]" * 600 + "}", "nesting is too deep"),
+        ('{"a": 1, "b": 2}', "Document must have exactly one root"),
+    ],
+)
+def test_json_to_xml_rejects_malformed_json(text, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.json_to_xml(text)
+
+
+def test_deep_documents_are_refused_by_default():
+    deep = "<a>" * 300_000 + "</a>" * 300_000
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.xml_to_json(deep)
+    assert xmltodict_rs.xml_to_json("<a>" * 3 + "</a>" * 3, max_depth=3) == (
+        '{"a": {"a": {"a": null}}}'
+    )
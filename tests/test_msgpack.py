@@ -0,0 +1,59 @@
+import pytest
+
+import xmltodict_rs
+
+XML = f"""<library id="1">
+  <book lang="en"><title>Dune</title><author/></book>
+  <book lang="ru"><title>Мастер и Маргарита</title><note>{"x" * 300}</note></book>
+</library>"""
+
+
+def test_small_values_use_fixed_forms():
+    packed = xmltodict_rs.xml_to_msgpack('<a id="1"><b>x</b><b/></a>')
+    assert packed == b"\x81\xa1a\x82\xa3@id\xa11\xa1b\x92\xa1x\xc0"
+
+
+def test_lengths_grow_with_the_value():
+    text = "é" * 40
+    packed = xmltodict_rs.xml_to_msgpack(f"<a>{text}</a>")
+    assert packed == b"\x81\xa1a\xd9\x50" + text.encode()
+    packed = xmltodict_rs.xml_to_msgpack(f"<a>{'<b/>' * 16}</a>")
+    assert packed == b"\x81\xa1a\x81\xa1b\xdc\x00\x10" + b"\xc0" * 16
+
+
+def test_matches_msgpack_packb():
+    msgpack = pytest.importorskip("msgpack")
+    for options in ({}, {"process_comments": True, "attr_prefix": "_", "force_cdata": True}):
+        packed = xmltodict_rs.xml_to_msgpack(XML.encode(), **options)
+        assert packed == msgpack.packb(xmltodict_rs.parse(XML, **options))
+        assert msgpack.unpackb(packed) == xmltodict_rs.parse(XML, **options)
+
+
+def test_errors_match_parse():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.xml_to_msgpack("<a><b></a>")
+    with pytest.raises(TypeError, match="force_list cannot be a callable"):
+        xmltodict_rs.xml_to_msgpack("<a/>", force_list=lambda path, key, value: True)
+
+
+def test_native_value_options_match_msgpack_packb():
+    msgpack = pytest.importorskip("msgpack")
+    xml = "<a><b>1</b><b>-300</b><c>2.5</c><d>true</d><e>null</e><f>x</f></a>"
+    options = {"force_list": ("c",), "infer_types": True, "on_duplicate": "last"}
+    packed = xmltodict_rs.xml_to_msgpack(xml, **options)
+    assert packed == msgpack.packb(xmltodict_rs.parse(xml, **options))
+
+
+def test_inferred_values_use_native_forms():
+    packed = xmltodict_rs.xml_to_msgpack(
+        "<a><b>true</b><i>-200</i><f>1.5</f></a>", infer_types=True
+    )
+    assert packed == b"\x81\xa1a\x83\xa1b\xc3\xa1i\xd1\xff\x38\xa1f\xcb?\xf8" + b"\0" * 6
+    with pytest.raises(ValueError, match="too large for MessagePack"):
+        xmltodict_rs.xml_to_msgpack("<a>123456789012345678901234</a>", infer_types=True)
+
+
+def test_deep_documents_are_refused_by_default():
+    deep = "<a>" * 300_000 + "</a>" * 300_000
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.xml_to_msgpack(deep)
@@ -0,0 +1,80 @@
+import io
+import json
+
+import pytest
+
+import xmltodict_rs
+
+XML = """<log>
+  <event id="1"><type>login</type><user>ada</user></event>
+  <!-- between events -->
+  <event id="2"><type>note</type><text>café "quoted"</text></event>
+  <batch><event>nested</event></batch>
+</log>"""
+
+
+def to_ndjson(source, record_path, **kwargs):
+    output = io.StringIO()
+    records = xmltodict_rs.to_ndjson(source, record_path, output, **kwargs)
+    return records, output.getvalue()
+
+
+def test_each_record_is_a_json_line():
+    records, text = to_ndjson(XML, "log/event")
+    assert records == 2
+    assert text.endswith("\n")
+    lines = text.splitlines()
+    assert lines[0] == '{"@id": "1", "type": "login", "user": "ada"}'
+    assert [json.loads(line) for line in lines] == [
+        xmltodict_rs.parse(XML)["log"]["event"][0],
+        xmltodict_rs.parse(XML)["log"]["event"][1],
+    ]
+
+
+def test_patterns_match_records_anywhere():
+    _, text = to_ndjson(XML, "**/event", ensure_ascii=False)
+    lines = text.splitlines()
+    assert len(lines) == 3
+    assert '"café \\"quoted\\""' in lines[1]
+    assert lines[2] == '"nested"'
+
+
+def test_parse_options_shape_records():
+    _, text = to_ndjson(XML, "log/event", attr_prefix="")
+    assert json.loads(text.splitlines()[0])["id"] == "1"
+    _, text = to_ndjson("<r><e> x <!--c--></e></r>", "r/e", strip="none", process_comments=True)
+    assert json.loads(text) == {"#comment": "c", "#text": " x "}
+
+
+def test_binary_output_and_input():
+    output = io.BytesIO()
+    records = xmltodict_rs.to_ndjson(io.BytesIO(XML.encode()), "log/event", output)
+    assert records == 2
+    assert b"caf\\u00e9" in output.getvalue()
+
+
+def test_large_documents_are_written_in_chunks():
+    class Recorder:
+        def __init__(self):
+            self.chunks = []
+
+        def write(self, data):
+            self.chunks.append(data)
+
+    xml = "<r>" + "<i><v>value</v></i>" * 20_000 + "</r>"
+    output = Recorder()
+    assert xmltodict_rs.to_ndjson(xml, "r/i", output) == 20_000
+    assert len(output.chunks) > 1
+    assert b"".join(output.chunks) == b'{"v": "value"}\n' * 20_000
+
+
+def test_malformed_documents_raise():
+    assert to_ndjson("<r/>", "r/e") == (0, "")
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        to_ndjson("<r><e/><e></r>", "r/e")
+
+
+def test_deep_records_are_refused_by_default():
+    deep = "<r><e>" + "<a>" * 300_000 + "</a>" * 300_000 + "</e></r>"
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        to_ndjson(deep, "r/e")
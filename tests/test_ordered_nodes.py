@@ -0,0 +1,128 @@
+import copy
+import pickle
+
+import pytest
+
+import xmltodict_rs
+
+
+def ordered(xml, **kwargs):
+    return xmltodict_rs.parse(xml, node_factory="ordered", **kwargs)
+
+
+def shape(node):
+    if isinstance(node, str):
+        return node
+    if node.kind == "element":
+        return (node.tag, node.attributes, [shape(child) for child in node.children])
+    return (node.kind, node.text)
+
+
+def test_document_node_holds_the_root():
+    doc = ordered("<r>x</r>")
+    assert isinstance(doc, xmltodict_rs.Node)
+    assert doc.kind == "document"
+    assert doc.tag is None
+    assert [shape(child) for child in doc.children] == [("r", [], ["x"])]
+
+
+def test_attributes_keep_document_order():
+    root = ordered('<r z="1" a="2" m="3"/>').children[0]
+    assert root.attributes == [("z", "1"), ("a", "2"), ("m", "3")]
+
+
+def test_text_comments_and_children_keep_document_order():
+    xml = "<p>one<b>two</b><!--note-->three<?pi data?><i/>four</p>"
+    root = ordered(xml, process_comments=True, process_pis=True).children[0]
+    assert shape(root) == (
+        "p",
+        [],
+        [
+            "one",
+            ("b", [], ["two"]),
+            ("comment", "note"),
+            "three",
+            ("pi", "pi data"),
+            ("i", [], []),
+            "four",
+        ],
+    )
+    assert root.text == "onethreefour"
+
+
+def test_repeated_elements_stay_apart():
+    root = ordered("<r><a>1</a><b/><a>2</a></r>").children[0]
+    assert [child.tag for child in root.children] == ["a", "b", "a"]
+
+
+def test_markup_outside_the_root_element():
+    xml = "<!DOCTYPE r><!--before--><r/><!--after-->"
+    doc = ordered(xml, process_comments=True, process_doctype=True)
+    assert [shape(child) for child in doc.children] == [
+        ("doctype", "r"),
+        ("comment", "before"),
+        ("r", [], []),
+        ("comment", "after"),
+    ]
+
+
+def test_names_follow_namespace_options():
+    xml = '<x:r xmlns:x="urn:x" x:id="1"><x:a/></x:r>'
+    root = ordered(xml, process_namespaces=True).children[0]
+    assert root.tag == "urn:x:r"
+    assert root.attributes == [("urn:x:id", "1")]
+    assert root.children[0].tag == "urn:x:a"
+    root = ordered(xml, strip_namespace_prefixes=True).children[0]
+    assert root.tag == "r"
+    assert root.attributes == [("xmlns:x", "urn:x"), ("id", "1")]
+
+
+def test_dict_options_do_not_apply():
+    root = ordered('<r id="7"><a>1</a></r>', force_list=True, infer_types=True).children[0]
+    assert shape(root) == ("r", [("id", "7")], [("a", [], ["1"])])
+
+
+def test_repr():
+    doc = ordered('<p id="1">a<b/><!--note--></p>', process_comments=True)
+    p = doc.children[0]
+    assert repr(doc) == "<Node document children=1>"
+    assert repr(p) == "<Node element 'p' attributes=1 children=3>"
+    assert repr(p.children[2]) == "<Node comment 'note'>"
+
+
+def test_pickle_and_copy():
+    doc = ordered('<p id="1">a<b>c</b><?pi x?></p>', process_pis=True)
+    for clone in (pickle.loads(pickle.dumps(doc)), copy.copy(doc), copy.deepcopy(doc)):
+        assert clone is not doc
+        assert clone.kind == "document"
+        assert [shape(child) for child in clone.children] == [
+            shape(child) for child in doc.children
+        ]
+
+
+def test_nodes_can_be_built():
+    comment = xmltodict_rs.Node("comment", children=["c"])
+    node = xmltodict_rs.Node("element", "a", [("id", "1")], ["x", comment])
+    assert shape(node) == ("a", [("id", "1")], ["x", ("comment", "c")])
+    with pytest.raises(ValueError, match="kind must be one of"):
+        xmltodict_rs.Node("text")
+    with pytest.raises(TypeError, match="children must be strings or nodes"):
+        xmltodict_rs.Node("element", "a", children=[1])
+
+
+def test_malformed_documents_raise():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        ordered("<r><a></r>")
+
+
+@pytest.mark.parametrize(
+    "kwargs",
+    [
+        {"node_factory": "tree"},
+        {"node_factory": "ordered", "dialect": "plist"},
+        {"node_factory": "ordered", "convention": "badgerfish"},
+    ],
+)
+def test_invalid_node_factory_options(kwargs):
+    with pytest.raises(ValueError, match="node_factory"):
+        xmltodict_rs.parse("<r/>", **kwargs)
@@ -1,3 +1,5 @@
+from xml.parsers.expat import ExpatError
+
 import pytest
 import xmltodict
 
@@ -279,6 +281,23 @@ def test_unicode_content(xml):
     compare_parsers(xml)
 
 
+@pytest.mark.parametrize(
+    "xml",
+    [
+        "<r>&#x1F600; &#128512; &#x10FFFF;</r>",
+        '<r a="&#x1F600;&#65;">&#xe9;&#x00E9;</r>',
+        "<r><![CDATA[&#x1F600;]]>&#x1f600;</r>",
+    ],
+)
+def test_character_references_outside_bmp(xml):
+    compare_parsers(xml)
+    compare_parsers(xml.encode())
+
+
+def test_character_references_decode_to_one_char():
+    assert xmltodict_rs.parse("<r>&#x1F600;&#128512;</r>") == {"r": "\U0001f600" * 2}
+
+
 @pytest.mark.parametrize(
     "xml",
     [
@@ -329,6 +348,55 @@ def test_error_cases(xml):
         xmltodict_rs.parse(xml)
 
 
+@pytest.mark.parametrize(
+    "xml",
+    [
+        "<r>\n  <a></b>\n</r>",
+        '<r>\n<a x="1" x="2"/></r>',
+        "",
+        "<r>\n<a>",
+        "<r>&nope;</r>",
+        '<r a="&#0;"/>',
+    ],
+)
+def test_error_codes_and_lines_match_expat(xml):
+    with pytest.raises(ExpatError) as expected:
+        xmltodict.parse(xml)
+    with pytest.raises(ExpatError) as actual:
+        xmltodict_rs.parse(xml)
+    assert actual.value.code == expected.value.code
+    assert actual.value.lineno == expected.value.lineno
+
+
+def test_error_position():
+    with pytest.raises(ExpatError) as error:
+        xmltodict_rs.parse("<r>\n  <a></b>\n</r>")
+    assert (error.value.lineno, error.value.offset) == (2, 7)
+    assert str(error.value).endswith(": line 2, column 7")
+
+
+def test_error_position_in_streamed_input():
+    chunks = iter([b"<r>\n", b"<a>1</a>\n", b"<b></c>"])
+    with pytest.raises(ExpatError) as error:
+        xmltodict_rs.parse(chunks)
+    assert (error.value.lineno, error.value.offset) == (3, 5)
+
+
+def test_exception_hierarchy():
+    assert issubclass(xmltodict_rs.XmlSyntaxError, ExpatError)
+    assert issubclass(xmltodict_rs.XmlEncodingError, xmltodict_rs.XmlSyntaxError)
+    assert issubclass(xmltodict_rs.DepthLimitExceeded, xmltodict_rs.XmlSyntaxError)
+    assert issubclass(xmltodict_rs.EntitiesForbidden, ValueError)
+
+
+def test_malformed_xml_raises_xml_syntax_error():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="no element found"):
+        xmltodict_rs.parse("")
+    with pytest.raises(xmltodict_rs.XmlSyntaxError) as error:
+        xmltodict_rs.parse("<a></b>")
+    assert type(error.value) is xmltodict_rs.XmlSyntaxError
+
+
 def test_bytes_vs_string_input():
     xml_str = "<root><item>test</item></root>"
     xml_bytes = xml_str.encode("utf-8")
@@ -0,0 +1,108 @@
+import gzip
+
+import pytest
+
+import xmltodict_rs
+
+DOCUMENTS = [f'<order id="{i}"><line>{i}</line><note>n{i}</note></order>' for i in range(200)]
+
+
+def test_results_match_parse_in_order():
+    assert xmltodict_rs.parse_batch(DOCUMENTS) == [xmltodict_rs.parse(doc) for doc in DOCUMENTS]
+
+
+@pytest.mark.parametrize("threads", [1, 3, 8, 1000])
+def test_thread_counts(threads):
+    result = xmltodict_rs.parse_batch(DOCUMENTS, threads=threads)
+    assert [order["order"]["@id"] for order in result] == [str(i) for i in range(200)]
+
+
+def test_str_and_bytes_documents():
+    documents = [
+        "<a>1</a>",
+        b"<a>2</a>",
+        "<a>привет</a>".encode("utf-16"),
+        gzip.compress(b"<a>3</a>"),
+    ]
+    assert xmltodict_rs.parse_batch(iter(documents)) == [
+        {"a": "1"},
+        {"a": "2"},
+        {"a": "привет"},
+        {"a": "3"},
+    ]
+
+
+def test_options():
+    result = xmltodict_rs.parse_batch(
+        DOCUMENTS[:2], attr_prefix="_", force_list=("line",), infer_types=True
+    )
+    assert result == [
+        {"order": {"_id": 0, "line": [0], "note": "n0"}},
+        {"order": {"_id": 1, "line": [1], "note": "n1"}},
+    ]
+
+
+@pytest.mark.parametrize(
+    "options",
+    [
+        {"process_namespaces": True, "namespaces": {"urn:x": "x"}},
+        {"process_comments": True, "xml_attribs": False},
+        {"postprocessor": lambda path, key, value: (key.upper(), value)},
+        {"only_paths": ["order/line"], "transforms": {"line": "int"}},
+        {"on_duplicate": "first", "key_map": {"note": "remark"}},
+        {"convention": "parker"},
+        {"preserve_sibling_order": True, "index_key": "#index"},
+    ],
+)
+def test_every_parse_option_applies(options):
+    documents = [
+        f'<order xmlns="urn:x" id="{i}"><!-- c -->'
+        f"<line>{i}</line><note>n</note><line>9</line></order>"
+        for i in range(20)
+    ]
+    assert xmltodict_rs.parse_batch(documents, threads=3, **options) == [
+        xmltodict_rs.parse(document, **options) for document in documents
+    ]
+
+
+def test_validation_and_warnings():
+    xsd = """<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        <xs:element name="a" type="xs:int"/>
+    </xs:schema>"""
+    warnings = []
+    result = xmltodict_rs.parse_batch(
+        ["<a>1</a>", "<a>2"], recover=True, warnings=warnings, validate_against=xsd
+    )
+    assert result == [{"a": "1"}, {"a": "2"}]
+    assert len(warnings) == 1
+    with pytest.raises(xmltodict_rs.XmlValidationError) as info:
+        xmltodict_rs.parse_batch(["<a>1</a>", "<a>x</a>"], validate_against=xsd)
+    assert "in document 1" in getattr(info.value, "__notes__", [])
+
+
+def test_encoding():
+    documents = ["<a>привет</a>".encode("cp1251")] * 3
+    assert xmltodict_rs.parse_batch(documents, encoding="cp1251") == [{"a": "привет"}] * 3
+
+
+def test_no_documents():
+    assert xmltodict_rs.parse_batch([]) == []
+
+
+def test_error_names_the_document():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError) as info:
+        xmltodict_rs.parse_batch(["<a/>", "<a>", "<b/>"], threads=2)
+    assert "in document 1" in getattr(info.value, "__notes__", [])
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded):
+        xmltodict_rs.parse_batch(["<a><b/><c/></a>"], max_total_nodes=2)
+
+
+def test_invalid_arguments():
+    with pytest.raises(TypeError, match="documents must be str or bytes, not 'int'"):
+        xmltodict_rs.parse_batch(["<a/>", 1])
+    with pytest.raises(ValueError, match="threads must be positive"):
+        xmltodict_rs.parse_batch(["<a/>"], threads=0)
+    with pytest.raises(LookupError):
+        xmltodict_rs.parse_batch([b"<a/>"], encoding="nope")
+    with pytest.raises(ValueError):
+        xmltodict_rs.parse_batch(["<a/>"], dialect="json")
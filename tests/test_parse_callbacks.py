@@ -1,3 +1,5 @@
+import datetime
+
 import pytest
 import xmltodict
 
@@ -101,6 +103,19 @@ def test_postprocessor_exception_propagation():
         xmltodict_rs.parse(xml, postprocessor=post)
 
 
+def test_postprocessor_exception_notes_element_path_and_line():
+    xml = "<root>\n  <a>1</a>\n  <list>\n    <b>2</b>\n  </list>\n</root>"
+
+    def post(path, key, value):
+        if key == "b":
+            raise KeyError("boom")
+        return key, value
+
+    with pytest.raises(KeyError, match="boom") as error:
+        xmltodict_rs.parse(xml, postprocessor=post)
+    assert error.value.__notes__ == ["while postprocessing root/list/b (line 4)"]
+
+
 def test_postprocessor_change_attribute_keys():
     xml = "<root><item id='1' name='test'>value</item></root>"
 
@@ -193,3 +208,108 @@ def test_postprocessor_with_namespaces():
     </root>
     """
     compare_parsers(xml, postprocessor=post, process_namespaces=False)
+
+
+# Transforms
+
+
+def test_transforms_by_key():
+    xml = '<r id=" 7 "><price>1.5</price><n>1_000</n><ok>TRUE</ok><s>  x </s><skip>y</skip></r>'
+    transforms = {
+        "@id": "int",
+        "price": "float",
+        "n": "int",
+        "ok": "bool",
+        "s": "strip",
+        "skip": "drop",
+    }
+    assert xmltodict_rs.parse(xml, transforms=transforms) == {
+        "r": {"@id": 7, "price": 1.5, "n": 1000, "ok": True, "s": "x"}
+    }
+
+
+def test_transforms_by_path():
+    xml = '<r><a v="1">2</a><b><a v="3">4</a></b></r>'
+    transforms = {"r/b/a/@v": "int", "**/a/#text": "float", "r/a/@v": "drop"}
+    assert xmltodict_rs.parse(xml, transforms=transforms) == {
+        "r": {"a": "2", "b": {"a": {"@v": 3, "#text": 4.0}}}
+    }
+
+
+def test_transforms_datetime():
+    result = xmltodict_rs.parse(
+        "<r><t>2024-05-01T12:30:00Z</t><d>2024-05-01</d></r>",
+        transforms={"t": "datetime", "d": "datetime"},
+    )
+    assert result["r"]["t"] == datetime.datetime(2024, 5, 1, 12, 30, tzinfo=datetime.timezone.utc)
+    assert result["r"]["d"] == datetime.datetime(2024, 5, 1)
+
+
+def test_transforms_keep_values_that_are_not_text():
+    xml = '<r><a x="1">2</a><e/></r>'
+    assert xmltodict_rs.parse(xml, transforms={"a": "int", "e": "int"}) == {
+        "r": {"a": {"@x": "1", "#text": "2"}, "e": None}
+    }
+
+
+def test_transforms_run_before_postprocessor():
+    seen = []
+
+    def post(path, key, value):
+        seen.append((key, value))
+        return key, value
+
+    xmltodict_rs.parse("<r><a>1</a><b>2</b></r>", transforms={"a": "int", "b": "drop"}, postprocessor=post)
+    assert ("a", 1) in seen
+    assert all(key != "b" for key, _ in seen)
+
+
+def test_transforms_conversion_errors_note_the_path():
+    xml = "<r>\n<a>x</a></r>"
+    with pytest.raises(ValueError, match="invalid literal") as error:
+        xmltodict_rs.parse(xml, transforms={"a": "int"})
+    assert error.value.__notes__ == ["while transforming r/a (line 2)"]
+    with pytest.raises(ValueError, match="invalid literal for bool"):
+        xmltodict_rs.parse(xml, transforms={"a": "bool"})
+
+
+def test_transforms_rejects_unknown_names():
+    with pytest.raises(ValueError, match="transforms values must be"):
+        xmltodict_rs.parse("<r/>", transforms={"r": "date"})
+
+
+# Key map
+
+
+def test_key_map_renames_elements_and_attributes():
+    xml = '<Order OrderID="5"><CustName>A</CustName><Line>1</Line><Line>2</Line></Order>'
+    key_map = {"Order": "order", "@OrderID": "id", "CustName": "customer", "Line": "lines"}
+    assert xmltodict_rs.parse(xml, key_map=key_map) == {
+        "order": {"id": "5", "customer": "A", "lines": ["1", "2"]}
+    }
+
+
+def test_key_map_with_namespaces_and_paths():
+    xml = '<ns0:Root xmlns:ns0="urn:x"><ns0:Name>a</ns0:Name><ns0:Sub><ns0:Name>b</ns0:Name></ns0:Sub></ns0:Root>'
+    key_map = {"ns0:Name": "name", "ns0:Root/ns0:Sub/ns0:Name": "sub_name"}
+    assert xmltodict_rs.parse(xml, key_map=key_map) == {
+        "ns0:Root": {"@xmlns:ns0": "urn:x", "name": "a", "ns0:Sub": {"sub_name": "b"}}
+    }
+
+
+def test_key_map_runs_with_transforms_and_postprocessor():
+    seen = []
+
+    def post(path, key, value):
+        seen.append((list(path), key, value))
+        return key, value
+
+    result = xmltodict_rs.parse(
+        "<r><Qty>3</Qty></r>",
+        key_map={"Qty": "quantity"},
+        transforms={"Qty": "int"},
+        force_list={"quantity"},
+        postprocessor=post,
+    )
+    assert result == {"r": {"quantity": [3]}}
+    assert (["r"], "quantity", 3) in seen
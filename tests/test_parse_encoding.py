@@ -0,0 +1,158 @@
+import io
+
+import pytest
+import xmltodict
+
+import xmltodict_rs
+
+
+@pytest.mark.parametrize(
+    ("encoding", "text"),
+    [
+        ("cp1251", "Привет"),
+        ("latin-1", "café"),
+        ("iso-8859-1", "naïve"),
+    ],
+)
+def test_encoding_parameter_matches_xmltodict(encoding, text):
+    xml = f"<root><item>{text}</item></root>".encode(encoding)
+    expected = xmltodict.parse(xml, encoding=encoding)
+    assert xmltodict_rs.parse(xml, encoding=encoding) == expected
+    assert expected == {"root": {"item": text}}
+
+
+def test_encoding_parameter_shift_jis():
+    xml = '<?xml version="1.0" encoding="shift_jis"?><root>日本語</root>'.encode("shift_jis")
+    assert xmltodict_rs.parse(xml, encoding="shift_jis") == {"root": "日本語"}
+
+
+@pytest.mark.parametrize("encoding", ["utf-16", "utf-16-le", "utf-16-be"])
+def test_encoding_parameter_utf16(encoding):
+    xml = '<root a="ü">世界</root>'.encode(encoding)
+    assert xmltodict_rs.parse(xml, encoding=encoding) == {"root": {"@a": "ü", "#text": "世界"}}
+
+
+def test_encoding_parameter_with_declaration():
+    xml = '<?xml version="1.0" encoding="windows-1251"?><root>Да</root>'.encode("cp1251")
+    assert xmltodict_rs.parse(xml, encoding="windows-1251") == {"root": "Да"}
+
+
+def test_encoding_parameter_with_file_like():
+    xml = "<root><a>Ёлка</a><b>ёж</b></root>".encode("cp1251")
+    result = xmltodict_rs.parse(io.BytesIO(xml), encoding="cp1251")
+    assert result == {"root": {"a": "Ёлка", "b": "ёж"}}
+
+
+def test_encoding_parameter_with_single_byte_generator_chunks():
+    xml = "<root>日本語テキスト</root>".encode("shift_jis")
+
+    def gen():
+        for byte in xml:
+            yield bytes([byte])
+
+    assert xmltodict_rs.parse(gen(), encoding="shift_jis") == {"root": "日本語テキスト"}
+
+
+def test_encoding_parameter_ignored_for_str_input():
+    assert xmltodict_rs.parse("<root>café</root>", encoding="latin-1") == {"root": "café"}
+
+
+def test_encoding_parameter_utf8_is_passthrough():
+    xml = "<root>世界</root>".encode()
+    assert xmltodict_rs.parse(xml, encoding="utf-8") == {"root": "世界"}
+
+
+def test_invalid_byte_sequence_raises():
+    xml = b"<root>\xff\xfe\xfa</root>"
+    with pytest.raises(Exception, match="invalid"):
+        xmltodict_rs.parse(xml, encoding="shift_jis")
+
+
+def test_invalid_byte_sequence_in_stream_raises():
+    xml = b"<root>\x82</root>"
+    with pytest.raises(Exception, match="invalid Shift_JIS byte sequence"):
+        xmltodict_rs.parse(io.BytesIO(xml), encoding="shift_jis")
+
+
+def test_undecodable_input_raises_xml_encoding_error():
+    with pytest.raises(xmltodict_rs.XmlEncodingError) as error:
+        xmltodict_rs.parse(b"<root>\x82</root>", encoding="shift_jis")
+    assert error.value.code == 19
+    with pytest.raises(xmltodict_rs.XmlEncodingError):
+        xmltodict_rs.parse(b"<root>\xff</root>", encoding="utf-8")
+
+
+def test_unknown_encoding_raises_lookup_error():
+    with pytest.raises(LookupError, match="unknown encoding"):
+        xmltodict_rs.parse(b"<root/>", encoding="no-such-encoding")
+
+
+# Encoding detection from BOM and XML declaration
+
+
+@pytest.mark.parametrize(
+    ("encoding", "text"),
+    [
+        ("iso-8859-1", "café"),
+        ("windows-1251", "Привет"),
+        ("koi8-r", "Привет"),
+    ],
+)
+def test_declared_encoding_matches_xmltodict(encoding, text):
+    xml = f'<?xml version="1.0" encoding="{encoding}"?><root a="{text}">{text}</root>'.encode(
+        encoding
+    )
+    expected = xmltodict.parse(xml)
+    assert xmltodict_rs.parse(xml) == expected
+    assert expected == {"root": {"@a": text, "#text": text}}
+
+
+def test_declared_shift_jis():
+    xml = '<?xml version="1.0" encoding="Shift_JIS"?><root>日本語</root>'.encode("shift_jis")
+    assert xmltodict_rs.parse(xml) == {"root": "日本語"}
+
+
+@pytest.mark.parametrize("encoding", ["utf-16", "utf-16-le", "utf-16-be"])
+def test_utf16_document_detected(encoding):
+    xml = '<?xml version="1.0" encoding="utf-16"?><root><a>世界</a></root>'.encode(encoding)
+    assert xmltodict_rs.parse(xml) == {"root": {"a": "世界"}}
+
+
+def test_utf16_bom_matches_xmltodict():
+    xml = '<?xml version="1.0" encoding="utf-16"?><root>x</root>'.encode("utf-16")
+    assert xmltodict_rs.parse(xml) == xmltodict.parse(xml)
+
+
+def test_utf8_bom_is_skipped():
+    xml = "<root>世界</root>".encode("utf-8-sig")
+    assert xmltodict_rs.parse(xml) == {"root": "世界"}
+
+
+def test_declared_encoding_in_file_like():
+    xml = '<?xml version="1.0" encoding="iso-8859-1"?><root>déjà vu</root>'.encode("latin-1")
+    assert xmltodict_rs.parse(io.BytesIO(xml)) == {"root": "déjà vu"}
+
+
+def test_declared_encoding_in_single_byte_generator():
+    xml = '<?xml version="1.0" encoding="utf-16"?><root>世界</root>'.encode("utf-16")
+
+    def gen():
+        for byte in xml:
+            yield bytes([byte])
+
+    assert xmltodict_rs.parse(gen()) == {"root": "世界"}
+
+
+def test_declared_encoding_ignored_for_str_input():
+    xml = '<?xml version="1.0" encoding="ISO-8859-1"?><root>café</root>'
+    assert xmltodict_rs.parse(xml) == {"root": "café"}
+
+
+def test_unknown_declared_encoding_falls_back_to_utf8():
+    xml = '<?xml version="1.0" encoding="x-unknown"?><root>世界</root>'.encode()
+    assert xmltodict_rs.parse(xml) == {"root": "世界"}
+
+
+def test_encoding_parameter_overrides_declaration():
+    xml = '<?xml version="1.0" encoding="iso-8859-1"?><root>Да</root>'.encode("cp1251")
+    assert xmltodict_rs.parse(xml, encoding="cp1251") == {"root": "Да"}
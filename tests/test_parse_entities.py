@@ -0,0 +1,202 @@
+import io
+from xml.parsers.expat import ExpatError
+
+import pytest
+import xmltodict
+
+import xmltodict_rs
+
+DOCTYPE = '<!DOCTYPE r [<!ENTITY e "v&amp;w"><!ENTITY f "[&e;&#33;]">]>'
+
+
+@pytest.mark.parametrize("disable_entities", [True, False])
+@pytest.mark.parametrize(
+    "xml",
+    [
+        DOCTYPE + '<r a="&f;">x &f; y</r>',
+        DOCTYPE + "<r><a>&e;</a><b>&lt;&e;&#65;</b></r>",
+        DOCTYPE + "<r>&e;</r>",
+        '<!DOCTYPE r SYSTEM "r.dtd"><r>t</r>',
+        '<!DOCTYPE r [<!ENTITY ext SYSTEM "file:///etc/passwd">]><r>a&ext;b</r>',
+        '<!DOCTYPE r [<!-- <!ENTITY e "no"> --><!ENTITY % p "x"><!ENTITY e "yes">]><r>&e;</r>',
+    ],
+)
+def test_entities_match_xmltodict(xml, disable_entities):
+    expected = xmltodict.parse(xml, disable_entities=disable_entities)
+    assert xmltodict_rs.parse(xml, disable_entities=disable_entities) == expected
+
+
+def test_entities_expanded_when_enabled():
+    xml = DOCTYPE + "<r>&f;</r>"
+    assert xmltodict_rs.parse(xml, disable_entities=False) == {"r": "[v&w!]"}
+
+
+def test_entities_dropped_from_text_when_disabled():
+    xml = DOCTYPE + '<r a="&e;">a&f;b</r>'
+    assert xmltodict_rs.parse(xml) == {"r": {"@a": "v&w", "#text": "ab"}}
+
+
+def test_entities_in_bytes_and_stream_input():
+    xml = (DOCTYPE + "<r>&e;</r>").encode()
+    assert xmltodict_rs.parse(xml, disable_entities=False) == {"r": "v&w"}
+    assert xmltodict_rs.parse(io.BytesIO(xml), disable_entities=False) == {"r": "v&w"}
+
+
+def test_undeclared_entity_raises():
+    with pytest.raises(ExpatError):
+        xmltodict_rs.parse("<r>&nope;</r>", disable_entities=False)
+
+
+def test_recursive_entity_raises():
+    xml = '<!DOCTYPE r [<!ENTITY a "&b;"><!ENTITY b "&a;">]><r>&a;</r>'
+    with pytest.raises(ExpatError, match="recursive entity reference"):
+        xmltodict_rs.parse(xml, disable_entities=False)
+
+
+def billion_laughs(levels: int = 10) -> str:
+    entities = ['<!ENTITY lol0 "lol">']
+    for i in range(1, levels):
+        entities.append(f'<!ENTITY lol{i} "{f"&lol{i - 1};" * 10}">')
+    return f"<!DOCTYPE lolz [{''.join(entities)}]><lolz>&lol{levels - 1};</lolz>"
+
+
+def test_billion_laughs_is_rejected():
+    with pytest.raises(ExpatError, match="entity expansion size limit exceeded"):
+        xmltodict_rs.parse(billion_laughs(), disable_entities=False)
+
+
+def test_billion_laughs_in_attribute_is_rejected():
+    xml = billion_laughs().replace("<lolz>&lol9;</lolz>", '<lolz a="&lol9;"/>')
+    with pytest.raises(ExpatError, match="entity expansion size limit exceeded"):
+        xmltodict_rs.parse(xml)
+
+
+def test_billion_laughs_ignored_when_entities_disabled():
+    assert xmltodict_rs.parse(billion_laughs()) == {"lolz": None}
+
+
+def test_max_entity_expansion_counts_whole_document():
+    xml = '<!DOCTYPE r [<!ENTITY e "abcd">]><r><a>&e;</a><b>&e;</b></r>'
+    result = xmltodict_rs.parse(xml, disable_entities=False, max_entity_expansion=8)
+    assert result == {"r": {"a": "abcd", "b": "abcd"}}
+    with pytest.raises(ExpatError, match="entity expansion size limit exceeded"):
+        xmltodict_rs.parse(xml, disable_entities=False, max_entity_expansion=7)
+
+
+def test_markup_in_replacement_text_stays_text():
+    # expat, and so xmltodict, reads the markup as a <b> child element.
+    xml = '<!DOCTYPE r [<!ENTITY e "<b>x</b>">]><r a="&e;">&e;</r>'
+    result = xmltodict_rs.parse(xml, disable_entities=False)
+    assert result == {"r": {"@a": "<b>x</b>", "#text": "<b>x</b>"}}
+
+
+def test_max_entity_depth():
+    xml = '<!DOCTYPE r [<!ENTITY a "&b;"><!ENTITY b "&c;"><!ENTITY c "x">]><r>&a;</r>'
+    assert xmltodict_rs.parse(xml, disable_entities=False, max_entity_depth=3) == {"r": "x"}
+    with pytest.raises(ExpatError, match="entity nesting depth limit exceeded"):
+        xmltodict_rs.parse(xml, disable_entities=False, max_entity_depth=2)
+
+
+def test_entity_limits_raise_depth_and_size_limit_exceeded():
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded):
+        xmltodict_rs.parse(billion_laughs(), disable_entities=False)
+    xml = '<!DOCTYPE r [<!ENTITY a "&b;"><!ENTITY b "x">]><r>&a;</r>'
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.parse(xml, disable_entities=False, max_entity_depth=1)
+
+
+# forbid_dtd / forbid_entities
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        '<!DOCTYPE r SYSTEM "r.dtd"><r/>',
+        "<!DOCTYPE r><r/>",
+        DOCTYPE + "<r>&e;</r>",
+    ],
+)
+def test_forbid_dtd_raises(xml):
+    with pytest.raises(xmltodict_rs.EntitiesForbidden, match="DTD is forbidden"):
+        xmltodict_rs.parse(xml, forbid_dtd=True)
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        DOCTYPE + "<r/>",
+        '<!DOCTYPE r [<!ENTITY ext SYSTEM "file:///etc/passwd">]><r/>',
+        '<!DOCTYPE r [<!ENTITY % p "x">]><r/>',
+        billion_laughs(),
+    ],
+)
+def test_forbid_entities_raises(xml):
+    with pytest.raises(xmltodict_rs.EntitiesForbidden, match="entity declarations are forbidden"):
+        xmltodict_rs.parse(xml, forbid_entities=True)
+
+
+def test_forbid_entities_allows_dtd_without_entities():
+    xml = '<!DOCTYPE r [<!ELEMENT r (#PCDATA)>]><r>&amp;</r>'
+    assert xmltodict_rs.parse(xml, forbid_entities=True) == {"r": "&"}
+
+
+def test_forbid_options_allow_plain_documents():
+    assert xmltodict_rs.parse("<r>x</r>", forbid_dtd=True, forbid_entities=True) == {"r": "x"}
+
+
+def test_entities_forbidden_is_value_error():
+    assert issubclass(xmltodict_rs.EntitiesForbidden, ValueError)
+    with pytest.raises(ValueError):
+        xmltodict_rs.parse(DOCTYPE + "<r/>", forbid_entities=True)
+
+
+# resolve_entities
+
+XHTML = '<p class="a&reg;b">one&nbsp;two &amp; &#169;</p>'
+
+
+def test_unknown_entities_skipped():
+    assert xmltodict_rs.parse(XHTML, resolve_entities="skip") == {
+        "p": {"@class": "ab", "#text": "onetwo & ©"}
+    }
+
+
+def test_unknown_entities_preserved():
+    assert xmltodict_rs.parse(XHTML, resolve_entities="preserve") == {
+        "p": {"@class": "a&reg;b", "#text": "one&nbsp;two & ©"}
+    }
+
+
+def test_unknown_entities_strict_by_default():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError) as info:
+        xmltodict_rs.parse(XHTML)
+    strict = info.value
+    with pytest.raises(xmltodict_rs.XmlSyntaxError) as info:
+        xmltodict_rs.parse(XHTML, resolve_entities="strict")
+    assert (info.value.code, str(info.value)) == (strict.code, str(strict))
+
+
+def test_declared_entities_still_expand():
+    xml = DOCTYPE + "<r>&e;&nbsp;</r>"
+    assert xmltodict_rs.parse(xml, disable_entities=False, resolve_entities="skip") == {"r": "v&w"}
+    assert xmltodict_rs.parse(xml, resolve_entities="preserve") == {"r": "&nbsp;"}
+
+
+def test_malformed_references_still_raise():
+    for xml in ["<p>&nbsp; & co</p>", "<p>&nbsp;&#xZZ;</p>"]:
+        with pytest.raises(xmltodict_rs.XmlSyntaxError):
+            xmltodict_rs.parse(xml, resolve_entities="preserve")
+
+
+def test_resolve_entities_with_recover():
+    warnings = []
+    result = xmltodict_rs.parse(
+        "<p>&nbsp;AT&T</p>", resolve_entities="skip", recover=True, warnings=warnings
+    )
+    assert result == {"p": "AT&T"}
+    assert warnings == ["stray & kept as text (line 1)"]
+
+
+def test_resolve_entities_must_be_known():
+    with pytest.raises(ValueError, match="resolve_entities must be 'strict', 'skip' or 'preserve'"):
+        xmltodict_rs.parse("<p/>", resolve_entities="drop")
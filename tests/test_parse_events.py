@@ -0,0 +1,216 @@
+import io
+import xml.etree.ElementTree as ET
+
+import pytest
+
+import xmltodict_rs
+
+
+class Recorder:
+    def __init__(self):
+        self.events = []
+
+    def start(self, tag, attrs):
+        self.events.append(("start", tag, attrs))
+
+    def end(self, tag):
+        self.events.append(("end", tag))
+
+    def data(self, text):
+        self.events.append(("data", text))
+
+    def comment(self, text):
+        self.events.append(("comment", text))
+
+    def close(self):
+        return self.events
+
+
+# Handler calls
+
+
+def test_handler_receives_events_in_order():
+    xml = '<root a="1"><item>x &amp; y</item><!-- note --><empty/></root>'
+    assert xmltodict_rs.parse_events(xml, Recorder()) == [
+        ("start", "root", {"a": "1"}),
+        ("start", "item", {}),
+        ("data", "x & y"),
+        ("end", "item"),
+        ("comment", " note "),
+        ("start", "empty", {}),
+        ("end", "empty"),
+        ("end", "root"),
+    ]
+
+
+def test_handler_builds_element_tree():
+    xml = '<root a="1"><item>x</item><item>y</item><!--c--></root>'
+    tree = xmltodict_rs.parse_events(xml, ET.TreeBuilder())
+    assert ET.tostring(tree) == ET.tostring(ET.fromstring(xml))
+
+
+def test_handler_methods_are_optional():
+    class Tags:
+        def __init__(self):
+            self.tags = []
+
+        def start(self, tag, attrs):
+            self.tags.append(tag)
+
+    handler = Tags()
+    assert xmltodict_rs.parse_events("<a><!--c--><b>t</b></a>", handler) is None
+    assert handler.tags == ["a", "b"]
+
+
+def test_handler_processing_instructions():
+    class Pis:
+        def __init__(self):
+            self.pis = []
+
+        def pi(self, target, data):
+            self.pis.append((target, data))
+
+    handler = Pis()
+    xml = '<?xml version="1.0"?><?style href="a.xsl"?><r><?empty?></r>'
+    xmltodict_rs.parse_events(xml, handler)
+    assert handler.pis == [("style", 'href="a.xsl"'), ("empty", "")]
+
+
+def test_handler_keeps_whitespace_when_asked():
+    xml = "<r>\n  <a>x</a>\n</r>"
+    events = xmltodict_rs.parse_events(xml, Recorder(), strip_whitespace=False)
+    assert [event for event in events if event[0] == "data"] == [
+        ("data", "\n  "),
+        ("data", "x"),
+        ("data", "\n"),
+    ]
+
+
+# Inputs
+
+
+@pytest.mark.parametrize(
+    "factory",
+    [
+        lambda: b"<r><a>x</a></r>",
+        lambda: io.BytesIO(b"<r><a>x</a></r>"),
+        lambda: iter([b"<r><a>", "x</a></r>"]),
+    ],
+)
+def test_handler_accepts_parse_inputs(factory):
+    assert xmltodict_rs.parse_events(factory(), Recorder()) == [
+        ("start", "r", {}),
+        ("start", "a", {}),
+        ("data", "x"),
+        ("end", "a"),
+        ("end", "r"),
+    ]
+
+
+def test_handler_decodes_bytes():
+    xml = '<?xml version="1.0" encoding="cp1251"?><r>Привет</r>'.encode("cp1251")
+    assert ("data", "Привет") in xmltodict_rs.parse_events(xml, Recorder())
+
+
+# Errors
+
+
+def test_handler_errors_propagate_with_line():
+    class Broken(Recorder):
+        def end(self, tag):
+            raise KeyError(tag)
+
+    with pytest.raises(KeyError, match="a") as error:
+        xmltodict_rs.parse_events("<r>\n<a/></r>", Broken())
+    assert error.value.__notes__ == ["while calling end() (line 2)"]
+
+
+def test_malformed_input_raises_before_handler_calls():
+    handler = Recorder()
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse_events("<r><a></r>", handler)
+    assert handler.events == []
+
+
+def test_handler_limits():
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.parse_events("<a><b><c/></b></a>", Recorder(), max_depth=2)
+    with pytest.raises(xmltodict_rs.EntitiesForbidden):
+        xmltodict_rs.parse_events("<!DOCTYPE r><r/>", Recorder(), forbid_dtd=True)
+
+
+# iterparse
+
+
+def test_iterparse_yields_paths_and_values():
+    xml = '<feed v="2"><title>T</title><entry id="1"/></feed>'
+    assert list(xmltodict_rs.iterparse(xml)) == [
+        ("start", ("feed",), {"v": "2"}),
+        ("start", ("feed", "title"), {}),
+        ("data", ("feed", "title"), "T"),
+        ("end", ("feed", "title"), None),
+        ("start", ("feed", "entry"), {"id": "1"}),
+        ("end", ("feed", "entry"), None),
+        ("end", ("feed",), None),
+    ]
+
+
+def test_iterparse_selected_events():
+    xml = '<?xml version="1.0"?><!-- head --><r><?pi data?><a>x</a></r>'
+    assert list(xmltodict_rs.iterparse(xml, events=("end", "comment", "pi"))) == [
+        ("comment", (), " head "),
+        ("pi", ("r",), "pi data"),
+        ("end", ("r", "a"), None),
+        ("end", ("r",), None),
+    ]
+
+
+def test_iterparse_reads_lazily():
+    consumed = []
+
+    def chunks():
+        for chunk in ["<feed><header>H</header>", "<entry/>" * 3, "<broken"]:
+            consumed.append(chunk)
+            yield chunk
+
+    events = xmltodict_rs.iterparse(chunks(), events=("end",))
+    assert next(events) == ("end", ("feed", "header"), None)
+    assert consumed == ["<feed><header>H</header>"]
+
+
+def test_iterparse_raises_where_the_document_breaks():
+    events = xmltodict_rs.iterparse("<r><a>1</a><b></r>", events=("end",))
+    assert next(events) == ("end", ("r", "a"), None)
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        next(events)
+    with pytest.raises(StopIteration):
+        next(events)
+
+
+def test_iterparse_file_and_encoding():
+    source = io.BytesIO('<r>Привет</r>'.encode("cp1251"))
+    events = list(xmltodict_rs.iterparse(source, events=["data"], encoding="cp1251"))
+    assert events == [("data", ("r",), "Привет")]
+
+
+def test_iterparse_limits():
+    events = xmltodict_rs.iterparse("<a><b><c/></b></a>", max_depth=2)
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        list(events)
+
+
+@pytest.mark.parametrize(("events", "error"), [("end", TypeError), (["stop"], ValueError)])
+def test_iterparse_rejects_bad_events(events, error):
+    with pytest.raises(error):
+        xmltodict_rs.iterparse("<r/>", events=events)
+
+
+def test_iterparse_is_not_reentrant():
+    def chunks():
+        yield "<r>"
+        next(events)
+        yield "</r>"
+
+    events = xmltodict_rs.iterparse(chunks())
+    with pytest.raises(ValueError, match="already executing"):
+        list(events)
@@ -1,4 +1,12 @@
+import gzip
 import io
+import subprocess
+import sys
+import textwrap
+import zipfile
+import zlib
+from concurrent.futures import Future, ThreadPoolExecutor
+from xml.parsers.expat import ExpatError
 
 import pytest
 import xmltodict
@@ -102,12 +110,21 @@ def test_parse_accepts_generator_of_bytes():
     assert rust_impl == original
 
 
-def test_parse_accepts_iterable_of_strings():
+def test_parse_accepts_iterator_of_strings():
     chunks = ["<root>", "<item>1</item>", "<item>2</item>", "</root>"]
+    result = xmltodict_rs.parse(iter(chunks))
+    assert result == {"root": {"item": ["1", "2"]}}
+
+
+def test_parse_accepts_iterator_of_mixed_chunks():
+    chunks = [b"<root>", bytearray(b"<item>1</item>"), memoryview(b"<item>2</item>"), "</root>"]
+    result = xmltodict_rs.parse(iter(chunks))
+    assert result == {"root": {"item": ["1", "2"]}}
+
+
+def test_parse_rejects_non_iterator_iterable():
     with pytest.raises(TypeError):
-        xmltodict.parse(iter(chunks))
-    with pytest.raises(TypeError):
-        xmltodict_rs.parse(iter(chunks))
+        xmltodict_rs.parse(["<root>", "</root>"])
 
 
 # Generator edge cases
@@ -462,3 +479,298 @@ def test_generator_exception_with_multiple_args():
         xmltodict_rs.parse(gen())
     except ValueError as e:
         assert e.args == expected_args
+
+
+# parse_file
+
+
+def test_parse_file_accepts_str_and_pathlike(tmp_path):
+    path = tmp_path / "doc.xml"
+    path.write_bytes(b"<root><item>a</item><item>b</item></root>")
+    expected = {"root": {"item": ["a", "b"]}}
+    assert xmltodict_rs.parse_file(path) == expected
+    assert xmltodict_rs.parse_file(str(path)) == expected
+
+
+def test_parse_file_passes_options_through(tmp_path):
+    path = tmp_path / "doc.xml"
+    path.write_bytes(b'<root id="1"><!-- c --><item>a</item></root>')
+    result = xmltodict_rs.parse_file(
+        path, force_list=("item",), process_comments=True, attr_prefix="_"
+    )
+    assert result == xmltodict.parse(
+        path.read_bytes(), force_list=("item",), process_comments=True, attr_prefix="_"
+    )
+
+
+def test_parse_file_detects_encoding(tmp_path):
+    path = tmp_path / "doc.xml"
+    path.write_bytes('<?xml version="1.0" encoding="cp1251"?><r>привет</r>'.encode("cp1251"))
+    assert xmltodict_rs.parse_file(path) == {"r": "привет"}
+    path.write_bytes("<r>привет</r>".encode("utf-16"))
+    assert xmltodict_rs.parse_file(path) == {"r": "привет"}
+
+
+def test_parse_file_explicit_encoding(tmp_path):
+    path = tmp_path / "doc.xml"
+    path.write_bytes("<r>привет</r>".encode("cp1251"))
+    assert xmltodict_rs.parse_file(path, encoding="cp1251") == {"r": "привет"}
+
+
+def test_parse_file_missing_file(tmp_path):
+    path = tmp_path / "missing.xml"
+    with pytest.raises(FileNotFoundError, match="missing.xml"):
+        xmltodict_rs.parse_file(path)
+
+
+def test_parse_file_directory(tmp_path):
+    with pytest.raises(IsADirectoryError):
+        xmltodict_rs.parse_file(tmp_path)
+
+
+def test_parse_file_empty_file(tmp_path):
+    path = tmp_path / "empty.xml"
+    path.write_bytes(b"")
+    with pytest.raises(ExpatError, match="no element found"):
+        xmltodict_rs.parse_file(path)
+
+
+def test_parse_file_rejects_non_path():
+    with pytest.raises(TypeError):
+        xmltodict_rs.parse_file(42)
+
+
+# decompress
+
+XML = "<root><item>a</item><item>é</item></root>".encode("utf-16")
+EXPECTED = {"root": {"item": ["a", "é"]}}
+ZSTD = b"\x28\xb5\x2f\xfd\x00\x58\x41\x00\x00<a>1</a>"
+
+
+@pytest.mark.parametrize("compress", [gzip.compress, zlib.compress])
+def test_compressed_bytes_detected(compress):
+    assert xmltodict_rs.parse(compress(XML)) == EXPECTED
+    assert xmltodict_rs.parse(io.BytesIO(compress(XML))) == EXPECTED
+    assert xmltodict_rs.parse(iter([compress(XML)])) == EXPECTED
+
+
+def test_zstd_frame_detected():
+    assert xmltodict_rs.parse(ZSTD) == {"a": "1"}
+    assert xmltodict_rs.parse(ZSTD, decompress="zstd") == {"a": "1"}
+
+
+def test_concatenated_gzip_members():
+    data = gzip.compress(b"<root><item>a</item>") + gzip.compress(b"<item>b</item></root>")
+    assert xmltodict_rs.parse(data) == {"root": {"item": ["a", "b"]}}
+
+
+def test_compressed_file(tmp_path):
+    path = tmp_path / "doc.xml.gz"
+    path.write_bytes(gzip.compress(XML))
+    assert xmltodict_rs.parse_file(path) == EXPECTED
+    assert xmltodict_rs.parse_file(path, decompress="gzip", encoding="utf-16") == EXPECTED
+
+
+def test_compressed_iterparse_and_parse_many():
+    data = gzip.compress(b"<e>1</e><e>2</e>")
+    assert list(xmltodict_rs.parse_many(data)) == [{"e": "1"}, {"e": "2"}]
+    assert list(xmltodict_rs.parse_many(io.BytesIO(data))) == [{"e": "1"}, {"e": "2"}]
+    events = xmltodict_rs.iterparse(gzip.compress(b"<r><e>1</e></r>"), events=("end",))
+    assert [path for _, path, _ in events] == [("r", "e"), ("r",)]
+
+
+def test_decompress_none_reads_input_as_is():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse(gzip.compress(XML), decompress="none")
+
+
+def test_forced_format_on_plain_input():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse(b"<a/>", decompress="gzip")
+
+
+def test_corrupt_compressed_input():
+    data = gzip.compress(XML)
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse(data[: len(data) // 2])
+
+
+def test_invalid_decompress():
+    with pytest.raises(ValueError, match="decompress must be"):
+        xmltodict_rs.parse(b"<a/>", decompress="bz2")
+
+
+# parse_zip_member
+
+CONTENT = (
+    '<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0">'
+    + "<office:body>" + "<p>text</p>" * 2000 + "</office:body></office:document-content>"
+)
+
+
+def make_zip(target, compression=zipfile.ZIP_DEFLATED):
+    with zipfile.ZipFile(target, "w", compression) as archive:
+        archive.writestr("mimetype", "application/vnd.oasis.opendocument.text")
+        archive.writestr("content.xml", CONTENT)
+    return target
+
+
+def test_parse_zip_member(tmp_path):
+    path = make_zip(tmp_path / "doc.odt")
+    result = xmltodict_rs.parse_zip_member(path, "content.xml")
+    assert result == xmltodict_rs.parse(CONTENT)
+    assert xmltodict_rs.parse_zip_member(str(path), "content.xml") == result
+
+
+def test_parse_zip_member_from_file_object_and_zipinfo():
+    archive = make_zip(io.BytesIO(), zipfile.ZIP_STORED)
+    info = zipfile.ZipFile(archive).getinfo("content.xml")
+    result = xmltodict_rs.parse_zip_member(archive, info, force_list=("p",))
+    assert len(result["office:document-content"]["office:body"]["p"]) == 2000
+
+
+def test_parse_zip_member_passes_options_through(tmp_path):
+    path = make_zip(tmp_path / "doc.odt")
+    result = xmltodict_rs.parse_zip_member(path, "content.xml", process_namespaces=True)
+    assert "urn:oasis:names:tc:opendocument:xmlns:office:1.0:document-content" in result
+
+
+def test_parse_zip_member_errors(tmp_path):
+    path = make_zip(tmp_path / "doc.odt")
+    with pytest.raises(KeyError, match="styles.xml"):
+        xmltodict_rs.parse_zip_member(path, "styles.xml")
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse_zip_member(path, "mimetype")
+    with pytest.raises(zipfile.BadZipFile):
+        xmltodict_rs.parse_zip_member(io.BytesIO(b"<a/>"), "content.xml")
+    with pytest.raises(FileNotFoundError):
+        xmltodict_rs.parse_zip_member(tmp_path / "missing.odt", "content.xml")
+
+
+def test_zip_ext_file_with_partial_reads():
+    class ShortReads:
+        def __init__(self, file):
+            self.file = file
+            self.sizes = []
+
+        def read(self, size=-1):
+            self.sizes.append(size)
+            return self.file.read1(min(size, 7))
+
+    archive = zipfile.ZipFile(make_zip(io.BytesIO()))
+    with archive.open("content.xml") as member:
+        assert xmltodict_rs.parse(member) == xmltodict_rs.parse(CONTENT)
+    with archive.open("content.xml") as member:
+        reader = ShortReads(member)
+        assert xmltodict_rs.parse(reader) == xmltodict_rs.parse(CONTENT)
+    assert len(reader.sizes) > len(CONTENT) // 7
+    with archive.open("content.xml") as member:
+        events = xmltodict_rs.iterparse(member, events=("data",))
+        assert next(events) == ("data", ("office:document-content", "office:body", "p"), "text")
+
+
+# Threads
+
+
+def test_parse_from_many_threads():
+    docs = [f"<root><n>{i}</n>{'<item>x</item>' * 500}</root>" for i in range(16)]
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(xmltodict_rs.parse, docs))
+    for i, result in enumerate(results):
+        assert result["root"]["n"] == str(i)
+        assert len(result["root"]["item"]) == 500
+
+
+def test_parse_from_threads_with_generator_input():
+    def chunks(i):
+        yield "<root>"
+        yield from (f"<item>{i}</item>" for _ in range(100))
+        yield "</root>"
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(lambda i: xmltodict_rs.parse(chunks(i)), range(8)))
+    assert results == [{"root": {"item": [str(i)] * 100}} for i in range(8)]
+
+
+def test_parse_in_thread_returns_future():
+    xml = "<root>" + "<item>x</item>" * 1000 + "</root>"
+    future = xmltodict_rs.parse_in_thread(xml.encode(), force_list=("root",))
+    assert isinstance(future, Future)
+    assert future.result() == {"root": [{"item": ["x"] * 1000}]}
+
+
+def test_parse_in_thread_many_documents():
+    docs = [f"<root><n>{i}</n></root>".encode() for i in range(16)]
+    futures = [xmltodict_rs.parse_in_thread(doc) for doc in docs]
+    assert [future.result()["root"]["n"] for future in futures] == [str(i) for i in range(16)]
+
+
+def test_parse_in_thread_with_own_executor():
+    with ThreadPoolExecutor(max_workers=2) as pool:
+        future = xmltodict_rs.parse_in_thread(io.BytesIO(b"<a>1</a>"), executor=pool)
+        assert future.result() == {"a": "1"}
+
+
+def test_parse_in_thread_errors_are_set_on_the_future():
+    future = xmltodict_rs.parse_in_thread("<a><b></a>")
+    assert isinstance(future.exception(), xmltodict_rs.XmlSyntaxError)
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        future.result()
+
+
+# Documents larger than a batch of events
+
+
+BIG_XML = "<r>" + "".join(f"<i n='{n}'>{n}<!--c--></i>" for n in range(5000)) + "<k>z</k></r>"
+
+
+@pytest.mark.parametrize(
+    "factory",
+    [
+        lambda: BIG_XML,
+        lambda: BIG_XML.encode("utf-16"),
+        lambda: gzip.compress(BIG_XML.encode()),
+        lambda: io.BytesIO(BIG_XML.encode()),
+        lambda: iter([BIG_XML[:70000], BIG_XML[70000:]]),
+    ],
+    ids=["str", "bytes", "gzip", "file", "chunks"],
+)
+def test_documents_larger_than_a_batch(factory):
+    expected = xmltodict.parse(BIG_XML, process_comments=True)
+    assert xmltodict_rs.parse(factory(), process_comments=True) == expected
+    assert xmltodict_rs.parse(factory(), only_paths=["r/k"]) == {"r": {"k": "z"}}
+
+
+def test_recovered_mistakes_of_a_large_document_are_warned_once():
+    warnings = []
+    xml = "<r>" + "<i>1</i>" * 5000 + "<b></r>"
+    result = xmltodict_rs.parse(xml, recover=True, warnings=warnings)
+    assert result == {"r": {"i": ["1"] * 5000, "b": None}}
+    assert len(warnings) == 1
+
+
+def test_large_document_is_not_held_as_events_whole():
+    """Peak memory stays well below what a million elements take as tokenized events."""
+    pytest.importorskip("resource")
+    script = textwrap.dedent(
+        """
+        import resource
+        import sys
+
+        import xmltodict_rs
+
+        def drop_items(path, key, value):
+            return None if key == "i" else (key, value)
+
+        xml = "<r>" + "<i>1</i>" * 1_000_000 + "</r>"
+        before = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+        xmltodict_rs.parse(xml, postprocessor=drop_items)
+        after = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+        # ru_maxrss is in bytes on macOS and in KiB elsewhere.
+        print((after - before) // (1024 if sys.platform == "darwin" else 1))
+        """
+    )
+    run = subprocess.run([sys.executable, "-c", script], capture_output=True, text=True)
+    assert run.returncode == 0, run.stderr
+    growth_kib = int(run.stdout)
+    assert growth_kib < 64 * 1024, f"peak memory grew by {growth_kib} KiB"
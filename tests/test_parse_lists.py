@@ -136,6 +136,10 @@ def test_force_list_invalid_types():
         ("<config><servers><server>test</server></servers></config>", ("server",)),
         ("<detail><msg>error</msg><type>value_error</type></detail>", ("detail",)),
         ("<item>value</item>", ("item",)),
+        ("<r><a>1</a><b>2</b></r>", frozenset({"a"})),
+        ("<r><a>1</a><b>2</b></r>", {"a": True}),
+        ("<r><a>1</a><b>2</b></r>", {1, "b"}),
+        ("<r><it>1</it><b>2</b></r>", "item"),
     ],
 )
 def test_force_list_compatibility_with_original(xml, force_list):
@@ -195,3 +199,132 @@ def test_force_list_with_attributes():
     assert isinstance(result["root"]["item"], list)
     assert result["root"]["item"][0]["@id"] == "1"
     assert result["root"]["item"][0]["#text"] == "value"
+
+
+def test_force_list_container_subclass_is_consulted():
+    class Everything(set):
+        def __contains__(self, key):
+            return True
+
+    result = xmltodict_rs.parse("<r><a>1</a></r>", force_list=Everything())
+    assert result == {"r": [{"a": ["1"]}]}
+
+
+
+# Path patterns
+
+
+def test_force_list_path_pattern():
+    xml = "<catalog><items><item>1</item></items><extra><item>2</item></extra></catalog>"
+    result = xmltodict_rs.parse(xml, force_list=("catalog/items/item",))
+    assert result == {"catalog": {"items": {"item": ["1"]}, "extra": {"item": "2"}}}
+
+
+@pytest.mark.parametrize(
+    ("force_list", "expected"),
+    [
+        (("*/row",), {"t": {"row": ["1"], "g": {"row": "2"}}}),
+        (["**/row"], {"t": {"row": ["1"], "g": {"row": ["2"]}}}),
+        ({"t/*/row"}, {"t": {"row": "1", "g": {"row": ["2"]}}}),
+        (frozenset({"/t"}), {"t": [{"row": "1", "g": {"row": "2"}}]}),
+    ],
+)
+def test_force_list_path_wildcards(force_list, expected):
+    xml = "<t><row>1</row><g><row>2</row></g></t>"
+    assert xmltodict_rs.parse(xml, force_list=force_list) == expected
+
+
+def test_force_list_mixes_names_and_paths():
+    xml = "<r><a><b>1</b></a><c>2</c></r>"
+    result = xmltodict_rs.parse(xml, force_list=("c", "r/a/b"))
+    assert result == {"r": {"a": {"b": ["1"]}, "c": ["2"]}}
+
+
+def test_force_list_namespaced_names_are_not_paths():
+    xml = '<r xmlns="http://a.com/"><i>1</i></r>'
+    result = xmltodict_rs.parse(xml, process_namespaces=True, force_list={"http://a.com/:i"})
+    assert result == {"http://a.com/:r": {"http://a.com/:i": ["1"]}}
+
+
+# Key interning
+
+
+def test_repeated_rows_share_key_objects():
+    xml = '<t><row id="1"><v>a</v></row><row id="2"><v>b</v></row></t>'
+    first, second = xmltodict_rs.parse(xml)["t"]["row"]
+    for key in ("@id", "v"):
+        assert next(k for k in first if k == key) is next(k for k in second if k == key)
+
+
+def test_interned_keys_with_postprocessor():
+    xml = "<t><row>1</row><row>2</row></t>"
+    result = xmltodict_rs.parse(xml, postprocessor=lambda path, key, value: (key.upper(), value))
+    assert result == {"T": {"ROW": ["1", "2"]}}
+
+
+# Duplicate children
+
+
+XML_DUPLICATES = "<order><id>1</id><note>a</note><id>2</id></order>"
+
+
+@pytest.mark.parametrize(
+    ("on_duplicate", "expected"),
+    [("list", ["1", "2"]), ("first", "1"), ("last", "2")],
+)
+def test_on_duplicate_keeps_one_value_or_all(on_duplicate, expected):
+    result = xmltodict_rs.parse(XML_DUPLICATES, on_duplicate=on_duplicate)
+    assert result == {"order": {"id": expected, "note": "a"}}
+
+
+def test_on_duplicate_error_names_the_element():
+    xml = "<order>\n  <id>1</id>\n  <id>2</id>\n</order>"
+    with pytest.raises(ValueError, match=r'"id" is repeated in order \(line 3\)'):
+        xmltodict_rs.parse(xml, on_duplicate="error")
+    assert xmltodict_rs.parse("<order><id>1</id></order>", on_duplicate="error") == {
+        "order": {"id": "1"}
+    }
+
+
+def test_on_duplicate_leaves_forced_lists_alone():
+    result = xmltodict_rs.parse(XML_DUPLICATES, force_list=("id",), on_duplicate="error")
+    assert result == {"order": {"id": ["1", "2"], "note": "a"}}
+
+
+def test_on_duplicate_rejects_unknown_strategies():
+    with pytest.raises(ValueError, match="on_duplicate must be"):
+        xmltodict_rs.parse(XML_DUPLICATES, on_duplicate="merge")
+
+
+# Sibling positions
+
+
+def test_index_key_keeps_sibling_positions():
+    xml = '<msg><debit>10</debit><credit c="x">5</credit><debit/></msg>'
+    result = xmltodict_rs.parse(xml, force_list=True, index_key="#index")
+    assert result == {
+        "msg": [
+            {
+                "debit": [{"#index": 0, "#text": "10"}, {"#index": 2}],
+                "credit": [{"@c": "x", "#index": 1, "#text": "5"}],
+            }
+        ]
+    }
+    children = result["msg"][0]
+    ordered = sorted(
+        ((name, item) for name, items in children.items() for item in items),
+        key=lambda pair: pair[1]["#index"],
+    )
+    assert [name for name, _ in ordered] == ["debit", "credit", "debit"]
+
+
+def test_index_key_counts_within_each_parent():
+    xml = "<r><a><x/><y/></a><b><x/></b><!--c--></r>"
+    result = xmltodict_rs.parse(xml, index_key="i", process_comments=True)
+    assert result == {
+        "r": {
+            "a": {"i": 0, "x": {"i": 0}, "y": {"i": 1}},
+            "b": {"i": 1, "x": {"i": 0}},
+            "#comment": "c",
+        }
+    }
@@ -0,0 +1,125 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+LOG = """<?xml version="1.0"?>
+<event id="1"><level>info</level></event>
+<event id="2"><level>warn</level><tag>a</tag><tag>b</tag></event>
+<?xml version="1.0"?>
+<event id="3"/>
+"""
+
+
+def test_one_dict_per_document():
+    assert list(xmltodict_rs.parse_many(LOG)) == [
+        {"event": {"@id": "1", "level": "info"}},
+        {"event": {"@id": "2", "level": "warn", "tag": ["a", "b"]}},
+        {"event": {"@id": "3"}},
+    ]
+
+
+def test_documents_match_parse():
+    documents = ["<a><b>1</b></a>", '<c x="y">text</c>', "<d/>"]
+    assert list(xmltodict_rs.parse_many("\n".join(documents))) == [
+        xmltodict_rs.parse(document) for document in documents
+    ]
+
+
+def test_single_document():
+    assert list(xmltodict_rs.parse_many("<a>1</a>")) == [{"a": "1"}]
+
+
+def test_options():
+    result = xmltodict_rs.parse_many(
+        LOG.encode(), attr_prefix="_", force_list=["level"], infer_types=True
+    )
+    assert [event["event"].get("level") for event in result] == [["info"], ["warn"], None]
+    first = next(xmltodict_rs.parse_many(LOG, attr_prefix="_", infer_types=True))
+    assert first == {"event": {"_id": 1, "level": "info"}}
+
+
+@pytest.mark.parametrize(
+    "options",
+    [
+        {"process_namespaces": True, "namespaces": {"urn:x": "x"}},
+        {"process_comments": True, "xml_attribs": False},
+        {"postprocessor": lambda path, key, value: (key.upper(), value)},
+        {"only_paths": ["e/b"], "transforms": {"b": "int"}},
+        {"on_duplicate": "last", "key_map": {"c": "see"}},
+        {"convention": "badgerfish"},
+        {"preserve_sibling_order": True},
+    ],
+)
+def test_every_parse_option_applies(options):
+    documents = [f'<e xmlns="urn:x" id="{i}"><!-- c --><b>{i}</b><c/><b>9</b></e>' for i in "123"]
+    assert list(xmltodict_rs.parse_many("\n".join(documents), **options)) == [
+        xmltodict_rs.parse(document, **options) for document in documents
+    ]
+
+
+def test_spans_are_offsets_into_the_source():
+    source = '<e n="1"/>\n<e n="2"/>'
+    documents = list(xmltodict_rs.parse_many(source, with_spans=True))
+    start, end = documents[1]["e"]["#span"]
+    assert source[start:end] == '<e n="2"/>'
+
+
+def test_invalid_options():
+    with pytest.raises(ValueError):
+        xmltodict_rs.parse_many("<e/>", on_duplicate="sometimes")
+    with pytest.raises(TypeError):
+        xmltodict_rs.parse_many("<e/>", threads=2)
+
+
+def test_file_is_read_lazily():
+    class Source(io.BytesIO):
+        reads = 0
+
+        def read(self, size=-1):
+            self.reads += 1
+            return super().read(min(size, 16) if size > 0 else 16)
+
+    source = Source(("<e>x</e>" * 1000).encode())
+    documents = xmltodict_rs.parse_many(source)
+    assert next(documents) == {"e": "x"}
+    assert source.reads < 100
+
+
+def test_chunks_split_inside_documents():
+    chunks = iter([b"<e>1</e><e", b">2</e>\n<e>", b"3</e>"])
+    assert list(xmltodict_rs.parse_many(chunks)) == [{"e": "1"}, {"e": "2"}, {"e": "3"}]
+
+
+def test_entities_do_not_carry_over():
+    xml = '<!DOCTYPE e [<!ENTITY x "X">]><e>&x;</e><e>&x;</e>'
+    documents = xmltodict_rs.parse_many(xml, disable_entities=False)
+    assert next(documents) == {"e": "X"}
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        next(documents)
+
+
+def test_node_limit_counts_each_document():
+    xml = "<a><b/></a><a><b/></a>"
+    assert len(list(xmltodict_rs.parse_many(xml, max_total_nodes=2))) == 2
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded):
+        list(xmltodict_rs.parse_many(xml, max_total_nodes=1))
+
+
+def test_documents_before_an_error_are_yielded():
+    documents = xmltodict_rs.parse_many("<e>1</e><e>2</e><e>")
+    assert next(documents) == {"e": "1"}
+    assert next(documents) == {"e": "2"}
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="unclosed element"):
+        next(documents)
+    assert list(documents) == []
+
+
+def test_trailing_comments_and_whitespace():
+    assert list(xmltodict_rs.parse_many("<e/>\n<!-- end -->\n\n")) == [{"e": None}]
+
+
+def test_no_document():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="no element found"):
+        list(xmltodict_rs.parse_many(""))
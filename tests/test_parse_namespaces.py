@@ -159,3 +159,110 @@ def test_empty_namespace_prefix():
     """
     namespaces = {"http://example.com/": ""}
     compare_parsers(xml, process_namespaces=True, namespaces=namespaces)
+
+
+def test_collect_namespaces():
+    xml = """
+<root xmlns="http://example.com/" xmlns:a="http://a.com/">
+    <a:item xmlns:b="http://b.com/" b:attr="1">data</a:item>
+    <a:item xmlns:a="http://other.com/">more</a:item>
+</root>
+    """
+    result = xmltodict_rs.parse(xml, collect_namespaces=True)
+    assert result["#namespaces"] == {
+        "": "http://example.com/",
+        "a": "http://a.com/",
+        "b": "http://b.com/",
+    }
+    assert list(result) == ["#namespaces", "root"]
+    assert result["root"]["@xmlns:a"] == "http://a.com/"
+
+
+def test_collect_namespaces_with_process_namespaces():
+    xml = '<a:root xmlns:a="http://a.com/"><a:item>1</a:item></a:root>'
+    result = xmltodict_rs.parse(xml, process_namespaces=True, collect_namespaces=True)
+    assert result == {
+        "#namespaces": {"a": "http://a.com/"},
+        "http://a.com/:root": {"http://a.com/:item": "1"},
+    }
+
+
+def test_collect_namespaces_custom_key_and_no_declarations():
+    result = xmltodict_rs.parse("<r/>", collect_namespaces=True, namespaces_key="!ns")
+    assert result == {"!ns": {}, "r": None}
+    assert xmltodict_rs.parse("<r xmlns='urn:r'/>") == {"r": {"@xmlns": "urn:r"}}
+
+
+QNAME_XML = """
+<root xmlns="http://d.com/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+      xmlns:ns2="http://types.com/">
+    <item xsi:type="ns2:MyType" ref="ns2:other">1</item>
+    <item xsi:type="Plain">2</item>
+    <item xsi:type="unbound:T">3</item>
+</root>
+"""
+
+
+def test_qname_attributes_resolved():
+    result = xmltodict_rs.parse(QNAME_XML, process_namespaces=True, qname_attributes=["xsi:type"])
+    items = result["http://d.com/:root"]["http://d.com/:item"]
+    xsi_type = "@http://www.w3.org/2001/XMLSchema-instance:type"
+    assert [item[xsi_type] for item in items] == [
+        "http://types.com/:MyType",
+        "http://d.com/:Plain",
+        "unbound:T",
+    ]
+    assert items[0]["@ref"] == "ns2:other"
+
+
+def test_qname_attributes_use_namespace_map():
+    namespaces = {
+        "http://d.com/": "",
+        "http://types.com/": "t",
+        "http://www.w3.org/2001/XMLSchema-instance": "xsi",
+    }
+    result = xmltodict_rs.parse(
+        QNAME_XML,
+        process_namespaces=True,
+        namespaces=namespaces,
+        qname_attributes=["xsi:type", "ref"],
+    )
+    first = result["root"]["item"][0]
+    assert first["@xsi:type"] == "t:MyType"
+    assert first["@ref"] == "t:other"
+
+
+def test_qname_attributes_need_process_namespaces():
+    result = xmltodict_rs.parse(QNAME_XML, qname_attributes=["xsi:type"])
+    assert result["root"]["item"][0]["@xsi:type"] == "ns2:MyType"
+
+
+SOAP_XML = """
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"
+               xmlns:b="http://books.com/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+    <soap:Body>
+        <b:book xsi:nil="false" id="1"><b:title>Dune</b:title></b:book>
+    </soap:Body>
+</soap:Envelope>
+"""
+
+
+@pytest.mark.parametrize("process_namespaces", [False, True])
+def test_strip_namespace_prefixes(process_namespaces):
+    result = xmltodict_rs.parse(
+        SOAP_XML, strip_namespace_prefixes=True, process_namespaces=process_namespaces
+    )
+    envelope = result["Envelope"]
+    assert envelope["Body"] == {"book": {"@nil": "false", "@id": "1", "title": "Dune"}}
+    declarations = {key for key in envelope if key.startswith("@xmlns")}
+    expected = {"@xmlns:soap", "@xmlns:b", "@xmlns:xsi"}
+    assert declarations == (set() if process_namespaces else expected)
+
+
+def test_namespaces_none_drops_namespace():
+    namespaces = {"http://books.com/": None, "http://schemas.xmlsoap.org/soap/envelope/": "soap"}
+    result = xmltodict_rs.parse(SOAP_XML, process_namespaces=True, namespaces=namespaces)
+    book = result["soap:Envelope"]["soap:Body"]["book"]
+    assert book["title"] == "Dune"
+    with pytest.raises(TypeError, match="strings or None"):
+        xmltodict_rs.parse(SOAP_XML, process_namespaces=True, namespaces={"http://books.com/": 1})
@@ -1,3 +1,6 @@
+from collections import OrderedDict
+from collections.abc import MutableMapping
+
 import pytest
 import xmltodict
 
@@ -116,6 +119,12 @@ def test_xml_attribs_false():
         "<root>  </root>",
         "<a>   text   </a>",
         "<element>\n  content  \n</element>",
+        "<a> x <b/> y </a>",
+        "<a>\n  text\n  <b>1</b>\n</a>",
+        "<a>\n  <b>1</b>\n  text\n</a>",
+        "<a> x <b/>  <c/> y </a>",
+        "<a>\n  <![CDATA[ c ]]>\n</a>",
+        "<a>p<![CDATA[ ]]>q</a>",
     ],
 )
 @pytest.mark.parametrize("strip_whitespace", [True, False])
@@ -135,6 +144,62 @@ def test_strip_whitespace_false():
     assert result == {"root": "  spaces  "}
 
 
+@pytest.mark.parametrize(
+    "strip,expected",
+    [
+        ("both", {"a": {"b": "y", "#text": "x  z"}}),
+        ("leading", {"a": {"b": "y ", "#text": "x  z "}}),
+        ("trailing", {"a": {"b": " y", "#text": " x  z"}}),
+        ("none", {"a": {"b": " y ", "#text": " x  z "}}),
+    ],
+)
+def test_strip(strip, expected):
+    xml = "<a> x <b> y </b> z </a>"
+    assert xmltodict_rs.parse(xml, strip=strip) == expected
+    assert xmltodict_rs.Parser(strip=strip).parse(xml) == expected
+
+
+def test_strip_overrides_strip_whitespace():
+    xml = "<a> x </a>"
+    assert xmltodict_rs.parse(xml, strip_whitespace=False, strip="both") == {"a": "x"}
+    assert xmltodict_rs.parse(xml, strip_whitespace=True, strip="none") == {"a": " x "}
+
+
+def test_strip_rejects_unknown_values():
+    with pytest.raises(ValueError, match="strip must be"):
+        xmltodict_rs.parse("<a/>", strip="all")
+
+
+@pytest.mark.parametrize("separator", ["|", " | "])
+def test_cdata_separator_without_strip_whitespace(separator):
+    xml = "<a>\n  <![CDATA[ c ]]>\n</a>"
+    compare_parsers(xml, cdata_separator=separator, strip_whitespace=False)
+
+
+def test_honor_xml_space():
+    xml = '<a xml:space="preserve"> x <b> y </b><c xml:space="default"> z </c></a>'
+    assert xmltodict_rs.parse(xml, xml_attribs=False) == {
+        "a": {"b": " y ", "c": "z", "#text": " x "}
+    }
+    assert xmltodict_rs.parse(xml, honor_xml_space=False, xml_attribs=False) == {
+        "a": {"b": "y", "c": "z", "#text": "x"}
+    }
+
+
+def test_honor_xml_space_with_strip_whitespace():
+    xml = '<a>\n  <b xml:space="preserve">\n    <c> x </c>\n  </b>\n  <d> y </d>\n</a>'
+    assert xmltodict_rs.parse(xml, strip_whitespace=True, xml_attribs=False) == {
+        "a": {"b": {"c": " x ", "#text": "\n    \n  "}, "d": "y"}
+    }
+    parser = xmltodict_rs.Parser(xml_attribs=False)
+    assert parser.parse(xml) == xmltodict_rs.parse(xml, xml_attribs=False)
+
+
+def test_honor_xml_space_keeps_blank_text():
+    xml = '<p xml:space="preserve">  </p>'
+    assert xmltodict_rs.parse(xml) == {"p": {"@xml:space": "preserve", "#text": "  "}}
+
+
 @pytest.mark.parametrize(
     "separator",
     [
@@ -226,3 +291,437 @@ def test_all_custom_parameters():
         strip_whitespace=True,
         force_cdata=True,
     )
+
+
+# Document limits
+
+
+def test_max_depth():
+    xml = "<a><b><c>x</c></b></a>"
+    assert xmltodict_rs.parse(xml, max_depth=3) == {"a": {"b": {"c": "x"}}}
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded, match="max_depth=2") as error:
+        xmltodict_rs.parse(xml, max_depth=2)
+    assert error.value.lineno == 1
+
+
+def test_max_depth_stops_deeply_nested_input():
+    xml = "<a>" * 100_000
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.parse(xml, max_depth=1000)
+
+
+def test_max_attributes():
+    xml = '<r a="1" b="2"><c d="3"/></r>'
+    assert xmltodict_rs.parse(xml, max_attributes=2)["r"]["@b"] == "2"
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded, match="max_attributes=1"):
+        xmltodict_rs.parse(xml, max_attributes=1)
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded):
+        xmltodict_rs.parse(xml, max_attributes=1, xml_attribs=False)
+
+
+@pytest.mark.parametrize("xml", ["<r>abcdef</r>", "<r><![CDATA[abcdef]]></r>"])
+def test_max_text_length(xml):
+    assert xmltodict_rs.parse(xml, max_text_length=6) == {"r": "abcdef"}
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded, match="max_text_length=5"):
+        xmltodict_rs.parse(xml, max_text_length=5)
+
+
+@pytest.mark.parametrize(
+    "xml", ["<r>12<![CDATA[345]]></r>", "<r>12<!--c-->345</r>", "<r>12<i/>345</r>"]
+)
+def test_max_text_length_counts_all_text_of_an_element(xml):
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded, match="max_text_length=4"):
+        xmltodict_rs.parse(xml, max_text_length=4)
+    assert xmltodict_rs.parse("<r><i>1234</i><i>1234</i></r>", max_text_length=4) == {
+        "r": {"i": ["1234", "1234"]}
+    }
+
+
+def test_max_total_nodes():
+    xml = "<r><i/><i/><i/></r>"
+    assert xmltodict_rs.parse(xml, max_total_nodes=4) == {"r": {"i": [None, None, None]}}
+    with pytest.raises(xmltodict_rs.SizeLimitExceeded, match="max_total_nodes=3"):
+        xmltodict_rs.parse(xml, max_total_nodes=3)
+
+
+def test_limit_exceptions_share_a_base():
+    assert issubclass(xmltodict_rs.DepthLimitExceeded, xmltodict_rs.LimitExceeded)
+    assert issubclass(xmltodict_rs.SizeLimitExceeded, xmltodict_rs.LimitExceeded)
+    assert issubclass(xmltodict_rs.LimitExceeded, xmltodict_rs.XmlSyntaxError)
+    with pytest.raises(xmltodict_rs.LimitExceeded):
+        xmltodict_rs.parse(iter([b"<r>", b"<a>" * 10]), max_depth=5)
+
+
+# Attribute dicts
+
+
+def test_attr_dict_key():
+    xml = '<a id="1">x<b c="2"/><d>y</d></a>'
+    assert xmltodict_rs.parse(xml, attr_dict_key="#attrs") == {
+        "a": {"#attrs": {"id": "1"}, "#text": "x", "b": {"#attrs": {"c": "2"}}, "d": "y"}
+    }
+
+
+def test_attr_dict_key_ignores_attr_prefix():
+    result = xmltodict_rs.parse('<a id="1"/>', attr_dict_key="attrs", attr_prefix="!")
+    assert result == {"a": {"attrs": {"id": "1"}}}
+
+
+def test_attr_dict_key_without_attributes():
+    assert xmltodict_rs.parse("<a><b>x</b></a>", attr_dict_key="#attrs") == {"a": {"b": "x"}}
+    assert xmltodict_rs.parse('<a id="1"/>', attr_dict_key="#attrs", xml_attribs=False) == {
+        "a": None
+    }
+
+
+def test_attr_dict_key_with_namespaces():
+    xml = '<r xmlns:p="urn:p" p:x="1"/>'
+    result = xmltodict_rs.parse(
+        xml, process_namespaces=True, namespaces={"urn:p": "q"}, attr_dict_key="#attrs"
+    )
+    assert result == {"r": {"#attrs": {"q:x": "1"}}}
+    result = xmltodict_rs.parse(
+        xml, process_namespaces=True, namespaces={"urn:o": "o"}, attr_dict_key="#attrs"
+    )
+    assert result == {"r": {"#attrs": {"xmlns": {"p": "urn:p"}, "urn:p:x": "1"}}}
+
+
+def test_attr_dict_key_postprocessor_sees_bare_names():
+    seen = []
+
+    def postprocessor(path, key, value):
+        seen.append(key)
+        return key.upper(), value
+
+    result = xmltodict_rs.parse('<a id="1"/>', attr_dict_key="#attrs", postprocessor=postprocessor)
+    assert seen[0] == "id"
+    assert result == {"A": {"#attrs": {"ID": "1"}}}
+
+
+# Type inference
+
+
+def test_infer_types():
+    xml = (
+        '<r id="7"><i>-12</i><f>1.5e3</f><t>true</t><n>null</n>'
+        "<s>abc</s><z>007</z><x>nan</x></r>"
+    )
+    assert xmltodict_rs.parse(xml, infer_types=True) == {
+        "r": {
+            "@id": 7,
+            "i": -12,
+            "f": 1500.0,
+            "t": True,
+            "n": None,
+            "s": "abc",
+            "z": "007",
+            "x": "nan",
+        }
+    }
+
+
+def test_infer_types_selected_kinds():
+    xml = "<r><i>1</i><f>1.5</f><t>False</t><n>NULL</n></r>"
+    assert xmltodict_rs.parse(xml, infer_types={"int", "bool"}) == {
+        "r": {"i": 1, "f": "1.5", "t": False, "n": "NULL"}
+    }
+    assert xmltodict_rs.parse(xml, infer_types=False) == xmltodict_rs.parse(xml)
+
+
+def test_infer_types_big_integers():
+    value = "123456789012345678901234567890"
+    assert xmltodict_rs.parse(f"<r>{value}</r>", infer_types=["int"]) == {"r": int(value)}
+
+
+def test_infer_types_with_text_and_attributes():
+    xml = '<r a="2.5">3</r>'
+    assert xmltodict_rs.parse(xml, infer_types=True) == {"r": {"@a": 2.5, "#text": 3}}
+    assert xmltodict_rs.parse("<r>3</r>", infer_types=True, force_cdata=True) == {
+        "r": {"#text": 3}
+    }
+
+
+def test_infer_types_runs_before_postprocessor():
+    def postprocessor(path, key, value):
+        return key, (type(value).__name__, value)
+
+    assert xmltodict_rs.parse("<r>4</r>", infer_types=True, postprocessor=postprocessor) == {
+        "r": ("int", 4)
+    }
+
+
+def test_infer_types_keeps_mixed_content_text():
+    xml = "<p>1<b>2</b>3</p>"
+    result = xmltodict_rs.parse(xml, infer_types=True, preserve_mixed_content=True)
+    assert result == {"p": ["1", {"b": 2}, "3"]}
+
+
+@pytest.mark.parametrize(
+    ("infer_types", "error"),
+    [("int", TypeError), (["int", "date"], ValueError), ([1], TypeError)],
+)
+def test_infer_types_rejects_bad_kinds(infer_types, error):
+    with pytest.raises(error):
+        xmltodict_rs.parse("<r/>", infer_types=infer_types)
+
+
+# Dict constructor
+
+
+def _assert_all_ordered(value):
+    if isinstance(value, dict):
+        assert type(value) is OrderedDict
+        for item in value.values():
+            _assert_all_ordered(item)
+    elif isinstance(value, list):
+        for item in value:
+            _assert_all_ordered(item)
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        '<r a="1"><b>x</b><b>y</b><c d="2">t</c><e/></r>',
+        '<r xmlns:p="urn:p"><p:b/></r>',
+        "<r><!-- note --><b/></r>",
+    ],
+)
+def test_dict_constructor_matches_xmltodict(xml):
+    options = {"process_comments": True}
+    expected = xmltodict.parse(xml, dict_constructor=OrderedDict, **options)
+    result = xmltodict_rs.parse(xml, dict_constructor=OrderedDict, **options)
+    assert result == expected
+    _assert_all_ordered(result)
+
+
+def test_dict_constructor_covers_attribute_dicts_and_mixed_content():
+    xml = '<p a="1">x<b c="2">y</b>z</p>'
+    result = xmltodict_rs.parse(
+        xml, dict_constructor=OrderedDict, attr_dict_key="@attrs", preserve_mixed_content=True
+    )
+    _assert_all_ordered(result)
+    result = xmltodict_rs.parse(
+        '<r a="1"><b/></r>', dict_constructor=OrderedDict, attr_dict_key="@attrs"
+    )
+    assert result == {"r": {"@attrs": {"a": "1"}, "b": None}}
+    _assert_all_ordered(result)
+
+
+def test_dict_constructor_reaches_postprocessor():
+    seen = []
+
+    def postprocessor(path, key, value):
+        seen.append(type(value))
+        return key, value
+
+    xmltodict_rs.parse(
+        "<r><b a='1'/></r>", dict_constructor=OrderedDict, postprocessor=postprocessor
+    )
+    assert seen == [str, OrderedDict, OrderedDict]
+
+
+def test_dict_constructor_dict_is_default():
+    xml = '<r a="1"><b/></r>'
+    assert type(xmltodict_rs.parse(xml, dict_constructor=dict)) is dict
+
+
+def test_dict_constructor_accepts_custom_mapping():
+    class Record(MutableMapping):
+        def __init__(self):
+            self.data = {}
+
+        def __getitem__(self, key):
+            return self.data[key]
+
+        def __setitem__(self, key, value):
+            self.data[key] = value
+
+        def __delitem__(self, key):
+            del self.data[key]
+
+        def __iter__(self):
+            return iter(self.data)
+
+        def __len__(self):
+            return len(self.data)
+
+    result = xmltodict_rs.parse('<r a="1"><b>x</b></r>', dict_constructor=Record)
+    assert isinstance(result, Record)
+    assert isinstance(result["r"], Record)
+    assert dict(result["r"]) == {"@a": "1", "b": "x"}
+
+
+def test_dict_constructor_errors_propagate():
+    def broken():
+        raise RuntimeError("no mapping")
+
+    with pytest.raises(RuntimeError, match="no mapping"):
+        xmltodict_rs.parse("<r><b/></r>", dict_constructor=broken)
+
+
+# Path selection
+
+
+RSS = """<rss version="2.0">
+  <channel>
+    <title>News</title>
+    <link>https://example.com</link>
+    <item><title>A</title><description>long</description></item>
+    <item><title>B</title><description>long</description></item>
+  </channel>
+</rss>"""
+
+
+def test_only_paths_builds_selected_subtrees():
+    result = xmltodict_rs.parse(RSS, only_paths=["rss/channel/item", "rss/channel/title"])
+    assert result == {
+        "rss": {
+            "channel": {
+                "title": "News",
+                "item": [
+                    {"title": "A", "description": "long"},
+                    {"title": "B", "description": "long"},
+                ],
+            }
+        }
+    }
+
+
+def test_only_paths_wildcards():
+    result = xmltodict_rs.parse(RSS, only_paths=["**/item/title"], force_list=("item",))
+    assert result == {"rss": {"channel": {"item": [{"title": "A"}, {"title": "B"}]}}}
+
+
+def test_only_paths_drops_ancestor_attributes_and_text():
+    xml = '<r a="1">text<b c="2">x</b><!-- c --></r>'
+    assert xmltodict_rs.parse(xml, only_paths=["r/b"], process_comments=True) == {
+        "r": {"b": {"@c": "2", "#text": "x"}}
+    }
+    assert xmltodict_rs.parse(xml, only_paths=["r"]) == xmltodict_rs.parse(xml)
+
+
+def test_only_paths_keeps_ancestor_namespace_declarations():
+    xml = '<r xmlns="urn:r" xmlns:p="urn:p" a="1"><p:a><p:b>1</p:b></p:a><c/></r>'
+    result = xmltodict_rs.parse(xml, process_namespaces=True, only_paths=["r/p:a/p:b"])
+    assert result == {"urn:r:r": {"urn:p:a": {"urn:p:b": "1"}}}
+    result = xmltodict_rs.parse(
+        xml,
+        process_namespaces=True,
+        namespaces={"urn:r": None, "urn:p": "q"},
+        only_paths=["r/p:a/p:b"],
+    )
+    assert result == {"r": {"q:a": {"q:b": "1"}}}
+    result = xmltodict_rs.parse(xml, collect_namespaces=True, only_paths=["r/p:a"])
+    assert result["#namespaces"] == {"": "urn:r", "p": "urn:p"}
+    assert xmltodict_rs.parse(xml, only_paths=["r/p:a/p:b"]) == {"r": {"p:a": {"p:b": "1"}}}
+
+
+def test_only_paths_without_matches_keeps_root():
+    assert xmltodict_rs.parse(RSS, only_paths=["feed/entry"]) == {"rss": None}
+    assert xmltodict_rs.parse(RSS, only_paths=[]) == {"rss": None}
+
+
+def test_only_paths_still_checks_skipped_content():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse("<r><a><b></a></r>", only_paths=["r/x"])
+
+
+def test_only_paths_rejects_a_string():
+    with pytest.raises(TypeError):
+        xmltodict_rs.parse(RSS, only_paths="rss/channel")
+
+
+def test_with_spans_marks_element_dicts():
+    xml = '<order id="5"><line n="1">a</line><line n="2"/><note>x</note></order>'
+    result = xmltodict_rs.parse(xml, with_spans=True)
+    order = result["order"]
+    assert order["#span"] == (0, len(xml))
+    for line in order["line"]:
+        start, end = line["#span"]
+        assert xml[start:end].startswith("<line")
+    assert xml[slice(*order["line"][0]["#span"])] == '<line n="1">a</line>'
+    assert xml[slice(*order["line"][1]["#span"])] == '<line n="2"/>'
+    assert order["note"] == "x"
+
+
+def test_with_spans_force_cdata_and_custom_key():
+    xml = "<r>\n  <a>x</a>\n  <b/>\n</r>"
+    result = xmltodict_rs.parse(xml, with_spans=True, force_cdata=True, span_key="_at")
+    start, end = result["r"]["a"]["_at"]
+    assert xml[start:end] == "<a>x</a>"
+    assert result["r"]["b"] is None
+
+
+def test_with_spans_are_byte_offsets():
+    xml = "<r><a k='1'>é</a><b k='2'>x</b></r>"
+    data = xml.encode()
+    result = xmltodict_rs.parse(xml, with_spans=True)
+    assert data[slice(*result["r"]["b"]["#span"])] == b"<b k='2'>x</b>"
+
+
+def test_with_spans_void_and_recovered_elements():
+    xml = '<p><br x="1"><img x="2"/><b x="3">t</p>'
+    result = xmltodict_rs.parse(
+        xml, with_spans=True, recover=True, void_elements={"br", "img"}
+    )
+    p = result["p"]
+    assert xml[slice(*p["br"]["#span"])] == '<br x="1">'
+    assert xml[slice(*p["img"]["#span"])] == '<img x="2"/>'
+    assert xml[slice(*p["b"]["#span"])] == '<b x="3">t</p>'
+
+
+@pytest.mark.parametrize(
+    "kwargs",
+    [
+        {"only_paths": ["r/a"]},
+        {"convention": "badgerfish"},
+        {"node_factory": "ordered"},
+    ],
+)
+def test_with_spans_rejects_other_shapes(kwargs):
+    with pytest.raises(ValueError, match="with_spans"):
+        xmltodict_rs.parse("<r><a/></r>", with_spans=True, **kwargs)
+
+
+SOAP = """<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Header><id>7</id></soap:Header>
+  <soap:Body>
+    <m:Pay xmlns:m="urn:pay" amount='1'>&lt;1&gt;<![CDATA[x]]><!-- c --></m:Pay>
+  </soap:Body>
+</soap:Envelope>"""
+
+
+def test_raw_paths_return_the_markup_as_written():
+    result = xmltodict_rs.parse(SOAP, raw_paths=["soap:Envelope/soap:Body"])
+    envelope = result["soap:Envelope"]
+    assert envelope["soap:Header"] == {"id": "7"}
+    body = envelope["soap:Body"]
+    assert body == SOAP[SOAP.index("<soap:Body>") : SOAP.index("</soap:Envelope>")].rstrip()
+
+
+def test_raw_paths_match_patterns_and_every_occurrence():
+    xml = '<r><item k="1"><b>x</b></item><item/><other><item>y</item></other></r>'
+    result = xmltodict_rs.parse(xml, raw_paths=["r/item"], attr_prefix="")
+    assert result["r"]["item"] == ['<item k="1"><b>x</b></item>', "<item/>"]
+    assert result["r"]["other"] == {"item": "y"}
+    result = xmltodict_rs.parse(xml, raw_paths=["**/item"])
+    assert result["r"]["other"] == {"item": "<item>y</item>"}
+
+
+def test_raw_paths_still_check_the_markup():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse("<r><a><b></a></r>", raw_paths=["r/a"])
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse("<r><a><b></b>", raw_paths=["r/a"])
+
+
+def test_raw_paths_with_spans():
+    xml = "<r><a><b/></a></r>"
+    result = xmltodict_rs.parse(xml, raw_paths=["r/a"], with_spans=True)
+    assert result["r"]["#span"] == (0, len(xml))
+    assert result["r"]["a"] == "<a><b/></a>"
+
+
+def test_raw_paths_rejects_a_string():
+    with pytest.raises(TypeError):
+        xmltodict_rs.parse("<r/>", raw_paths="r")
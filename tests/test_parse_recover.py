@@ -0,0 +1,138 @@
+import pytest
+
+import xmltodict_rs
+
+
+def parse_recovering(xml, **kwargs):
+    warnings = []
+    result = xmltodict_rs.parse(xml, recover=True, warnings=warnings, **kwargs)
+    return result, warnings
+
+
+def test_unknown_entities_kept_as_text():
+    result, warnings = parse_recovering("<p>a&nbsp;b &copy; &lt;c&gt;</p>")
+    assert result == {"p": "a&nbsp;b &copy; <c>"}
+    assert warnings == [
+        "unknown entity &nbsp; kept as text (line 1)",
+        "unknown entity &copy; kept as text (line 1)",
+    ]
+
+
+def test_stray_ampersand():
+    result, warnings = parse_recovering('<p q="R&D">AT&T & co</p>')
+    assert result == {"p": {"@q": "R&D", "#text": "AT&T & co"}}
+    assert len(warnings) == 3
+    assert all(warning.startswith("stray & kept as text") for warning in warnings)
+
+
+def test_invalid_character_reference():
+    result, warnings = parse_recovering("<p>&#xZZ;&#65;</p>")
+    assert result == {"p": "&#xZZ;A"}
+    assert warnings == ["invalid character reference &#xZZ; kept as text (line 1)"]
+
+
+def test_declared_entities_still_expand():
+    xml = '<!DOCTYPE p [<!ENTITY e "E">]><p>&e; &f;</p>'
+    result, warnings = parse_recovering(xml, disable_entities=False)
+    assert result == {"p": "E &f;"}
+    assert warnings == ["unknown entity &f; kept as text (line 1)"]
+
+
+def test_mismatched_end_tag_closes_inner_elements():
+    result, warnings = parse_recovering("<ul>\n<li>one<b>bold</li>\n<li>two</li>\n</ul>")
+    assert result == {"ul": {"li": [{"b": "bold", "#text": "one"}, "two"]}}
+    assert warnings == ["<b> closed by </li> (line 2)"]
+
+
+def test_end_tag_without_start_tag_is_ignored():
+    result, warnings = parse_recovering("<a><b>x</b></c></a>")
+    assert result == {"a": {"b": "x"}}
+    assert warnings == ["</c> without a matching start tag ignored (line 1)"]
+
+
+def test_unclosed_elements_closed_at_end():
+    result, warnings = parse_recovering("<a>\n<b>x</b>\n<c>y")
+    assert result == {"a": {"b": "x", "c": "y"}}
+    assert warnings == [
+        "<c> closed at the end of the input (line 3)",
+        "<a> closed at the end of the input (line 3)",
+    ]
+
+
+def test_well_formed_document_has_no_warnings():
+    xml = '<a id="1"><b>x &amp; y</b></a>'
+    result, warnings = parse_recovering(xml)
+    assert result == xmltodict_rs.parse(xml)
+    assert warnings == []
+
+
+def test_recover_without_warnings_list():
+    assert xmltodict_rs.parse("<a>&nbsp;", recover=True) == {"a": "&nbsp;"}
+
+
+def test_warnings_are_appended():
+    warnings = ["earlier"]
+    xmltodict_rs.parse("<a>&x;</a>", recover=True, warnings=warnings)
+    assert warnings == ["earlier", "unknown entity &x; kept as text (line 1)"]
+
+
+def test_without_recover_mistakes_raise():
+    for xml in ["<a>&nbsp;</a>", "<a><b></a>", "<a>"]:
+        with pytest.raises(xmltodict_rs.XmlSyntaxError):
+            xmltodict_rs.parse(xml)
+
+
+def test_other_errors_still_raise():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="no element found"):
+        xmltodict_rs.parse("", recover=True)
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse('<a b="1" b="2"/>', recover=True)
+
+
+# void_elements
+
+VOID = {"br", "hr", "img"}
+
+
+def test_void_elements_without_end_tags():
+    xml = '<p>one<br>two<hr><img src="a.png"></p>'
+    assert xmltodict_rs.parse(xml, void_elements=VOID) == {
+        "p": {"br": None, "hr": None, "img": {"@src": "a.png"}, "#text": "onetwo"}
+    }
+
+
+def test_void_elements_closed_anyway():
+    xml = "<p><br/><br></br><br></p>"
+    assert xmltodict_rs.parse(xml, void_elements=VOID) == {"p": {"br": [None, None, None]}}
+
+
+def test_void_elements_in_feed():
+    xml = "<rss><item><description>a<br>b</description></item></rss>"
+    result = xmltodict_rs.parse(xml, void_elements=["br"])
+    assert result == {"rss": {"item": {"description": {"br": None, "#text": "ab"}}}}
+
+
+def test_other_mismatches_still_raise():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="Expecting </b> found </p>") as info:
+        xmltodict_rs.parse("<p><b><br></p>", void_elements=VOID)
+    assert info.value.code == 7
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="unclosed element"):
+        xmltodict_rs.parse("<p><br>", void_elements=VOID)
+
+
+def test_void_elements_are_case_sensitive():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse("<p><BR></p>", void_elements=VOID)
+
+
+def test_void_elements_with_recover():
+    result, warnings = parse_recovering("<p><b>x<br></p>", void_elements=VOID)
+    assert result == {"p": {"b": {"br": None, "#text": "x"}}}
+    assert warnings == ["<b> closed by </p> (line 1)"]
+
+
+def test_void_elements_must_be_names():
+    with pytest.raises(TypeError, match="not a string"):
+        xmltodict_rs.parse("<p/>", void_elements="br")
+    with pytest.raises(TypeError, match="must contain strings"):
+        xmltodict_rs.parse("<p/>", void_elements=[1])
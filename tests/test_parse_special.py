@@ -128,6 +128,22 @@ def test_processing_instructions():
     assert result == {"root": "content"}
 
 
+def test_process_pis():
+    xml = '<?xml-stylesheet href="a.xsl"?><root><?target data?>content</root>'
+    result = xmltodict_rs.parse(xml, process_pis=True)
+    assert result == {"#pi": 'xml-stylesheet href="a.xsl"', "root": {"#pi": "target data", "#text": "content"}}
+
+
+def test_process_pis_custom_key():
+    result = xmltodict_rs.parse("<root><?a b?><?c d?></root>", process_pis=True, pi_key="!pi")
+    assert result == {"root": {"!pi": ["a b", "c d"]}}
+
+
+def test_comments_before_root_are_kept():
+    xml = "<!-- a --><root>x</root><!-- b -->"
+    compare_parsers(xml, process_comments=True)
+
+
 def test_doctype_declaration():
     xml = """<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN">
     <root>content</root>"""
@@ -135,6 +151,31 @@ def test_doctype_declaration():
     assert result == {"root": "content"}
 
 
+XHTML_DOCTYPE = (
+    'html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" '
+    '"http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"'
+)
+
+
+def test_process_doctype():
+    xml = f"<?xml version='1.0'?>\n<!DOCTYPE {XHTML_DOCTYPE}>\n<html><body>x</body></html>"
+    result = xmltodict_rs.parse(xml, process_doctype=True)
+    assert result == {"#doctype": XHTML_DOCTYPE, "html": {"body": "x"}}
+    assert list(result) == ["#doctype", "html"]
+
+
+def test_process_doctype_custom_key_and_internal_subset():
+    xml = '<!DOCTYPE r [<!ENTITY e "v">]><r>&e;</r>'
+    result = xmltodict_rs.parse(
+        xml, process_doctype=True, doctype_key="!doctype", disable_entities=False
+    )
+    assert result == {"!doctype": 'r [<!ENTITY e "v">]', "r": "v"}
+
+
+def test_process_doctype_without_doctype():
+    assert xmltodict_rs.parse("<r/>", process_doctype=True) == {"r": None}
+
+
 # Long content tests
 
 
@@ -0,0 +1,162 @@
+import copy
+import io
+import pickle
+import threading
+from collections import OrderedDict
+
+import pytest
+
+import xmltodict_rs
+
+MESSAGES = [
+    '<order id="1"><item>a</item></order>',
+    '<order id="2"><item>b</item><item>c</item></order>',
+    "<order><note/></order>",
+]
+
+
+def test_results_match_parse():
+    parser = xmltodict_rs.Parser()
+    for message in MESSAGES * 3:
+        assert parser.parse(message) == xmltodict_rs.parse(message)
+
+
+def test_options_apply_to_every_parse():
+    parser = xmltodict_rs.Parser(attr_prefix="_", force_list={"item"}, infer_types=True)
+    assert parser.parse(MESSAGES[0]) == {"order": {"_id": 1, "item": ["a"]}}
+    assert parser.parse(MESSAGES[1]) == {"order": {"_id": 2, "item": ["b", "c"]}}
+
+
+def test_keys_are_kept_between_parses():
+    parser = xmltodict_rs.Parser()
+    first = parser.parse(MESSAGES[0])
+    second = parser.parse(MESSAGES[1])
+    assert next(iter(first)) is next(iter(second))
+    assert next(iter(first["order"])) is next(iter(second["order"]))
+
+
+def test_inputs():
+    parser = xmltodict_rs.Parser(encoding="cp1251")
+    assert parser.parse("<a>привет</a>".encode("cp1251")) == {"a": "привет"}
+    assert parser.parse(io.BytesIO("<a>мир</a>".encode("cp1251"))) == {"a": "мир"}
+    assert parser.parse(iter([b"<a>", b"1</a>"])) == {"a": "1"}
+    assert parser.parse("<a>текст</a>") == {"a": "текст"}
+
+
+def test_callbacks():
+    parser = xmltodict_rs.Parser(
+        postprocessor=lambda path, key, value: (key.upper(), value),
+        dict_constructor=OrderedDict,
+    )
+    result = parser.parse("<a><b>1</b></a>")
+    assert result == {"A": {"B": "1"}}
+    assert type(result) is OrderedDict
+    assert type(result["A"]) is OrderedDict
+
+
+def test_errors_leave_the_parser_usable():
+    def check(path, key, value):
+        if key == "bad":
+            raise ValueError("boom")
+        return key, value
+
+    parser = xmltodict_rs.Parser(max_depth=2, postprocessor=check)
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        parser.parse("<a><b>")
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        parser.parse("<a><b><c/></b></a>")
+    with pytest.raises(ValueError, match="boom"):
+        parser.parse("<a><bad/></a>")
+    assert parser.parse("<a><b>1</b></a>") == {"a": {"b": "1"}}
+
+
+def test_parses_on_several_threads():
+    parser = xmltodict_rs.Parser()
+    expected = [xmltodict_rs.parse(message) for message in MESSAGES]
+    results = []
+
+    def work():
+        results.append([parser.parse(message) for message in MESSAGES * 50])
+
+    threads = [threading.Thread(target=work) for _ in range(4)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+    assert results == [expected * 50] * 4
+
+
+@pytest.mark.parametrize(
+    ("options", "xml"),
+    [
+        ({"process_comments": True}, "<a><!-- c --><b>1</b></a>"),
+        (
+            {"process_namespaces": True, "namespaces": {"urn:x": "x"}},
+            '<a xmlns="urn:x"><b>1</b></a>',
+        ),
+        ({"only_paths": ["a/b"]}, "<a><b>1</b><c>2</c></a>"),
+        ({"on_duplicate": "last"}, "<a><b>1</b><b>2</b></a>"),
+        ({"convention": "badgerfish"}, '<a id="1"><b>x</b></a>'),
+        ({"transforms": {"b": "int"}, "key_map": {"c": "see"}}, "<a><b>1</b><c>2</c></a>"),
+        ({"dialect": "plist"}, "<plist><dict><key>k</key><integer>1</integer></dict></plist>"),
+        ({"preserve_sibling_order": True}, "<a><b>1</b><c>2</c><b>3</b></a>"),
+    ],
+)
+def test_every_parse_option_is_taken(options, xml):
+    parser = xmltodict_rs.Parser(**options)
+    for _ in range(2):
+        assert parser.parse(xml) == xmltodict_rs.parse(xml, **options)
+
+
+def test_ordered_nodes():
+    parser = xmltodict_rs.Parser(node_factory="ordered")
+    for _ in range(2):
+        (root,) = parser.parse("<a><b>1</b><c>2</c><b>3</b></a>").children
+        assert [(child.tag, child.text) for child in root.children] == [
+            ("b", "1"),
+            ("c", "2"),
+            ("b", "3"),
+        ]
+
+
+def test_warnings_and_validation_apply_to_every_parse():
+    xsd = """<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        <xs:element name="a" type="xs:int"/>
+    </xs:schema>"""
+    warnings = []
+    parser = xmltodict_rs.Parser(recover=True, warnings=warnings, validate_against=xsd)
+    assert parser.parse("<a>1</a>") == {"a": "1"}
+    with pytest.raises(xmltodict_rs.XmlValidationError):
+        parser.parse("<a>x</a>")
+    assert parser.parse("<a>2") == {"a": "2"}
+    assert warnings
+
+
+def test_options_are_keywords():
+    with pytest.raises(TypeError):
+        xmltodict_rs.Parser("utf-8")
+    with pytest.raises(LookupError, match="unknown encoding: nope"):
+        xmltodict_rs.Parser(encoding="nope")
+    with pytest.raises(TypeError):
+        xmltodict_rs.Parser(xml_input="<a/>")
+    with pytest.raises(ValueError):
+        xmltodict_rs.Parser(on_duplicate="sometimes")
+
+
+def test_repr_shows_the_options():
+    assert repr(xmltodict_rs.Parser()) == "Parser()"
+    parser = xmltodict_rs.Parser(force_list=("item",), attr_prefix="@", max_depth=8)
+    assert repr(parser) == "Parser(force_list=('item',), max_depth=8)"
+    parser = xmltodict_rs.Parser(process_comments=True, max_entity_expansion=10_000_000)
+    assert repr(parser) == "Parser(process_comments=True)"
+
+
+def test_pickle_and_copy_keep_the_options():
+    parser = xmltodict_rs.Parser(force_list=("item",), dict_constructor=OrderedDict)
+    parser.parse(MESSAGES[0])
+    for clone in (pickle.loads(pickle.dumps(parser)), copy.copy(parser), copy.deepcopy(parser)):
+        assert type(clone) is xmltodict_rs.Parser
+        assert repr(clone) == repr(parser)
+        result = clone.parse(MESSAGES[0])
+        assert result == {"order": {"@id": "1", "item": ["a"]}}
+        assert type(result) is OrderedDict
@@ -0,0 +1,87 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+TEMPLATE = (
+    '<order id=""><!-- filled in by the client -->'
+    "<customer><name/></customer><line>1</line><line>2</line><total/></order>"
+)
+
+
+def patch(template, updates, **kwargs):
+    return xmltodict_rs.patch(template, updates, full_document=False, **kwargs)
+
+
+def test_untouched_content_keeps_its_order():
+    assert patch(TEMPLATE, {}) == TEMPLATE.replace("<name/>", "<name></name>").replace(
+        "<total/>", "<total></total>"
+    )
+
+
+def test_updates_elements_and_attributes():
+    result = patch(TEMPLATE, {"order/@id": 42, "order/customer/name": "Ada", "order/total": 9.5})
+    assert result == (
+        '<order id="42"><!-- filled in by the client -->'
+        "<customer><name>Ada</name></customer><line>1</line><line>2</line>"
+        "<total>9.5</total></order>"
+    )
+
+
+def test_positions_select_repeated_elements():
+    result = patch(TEMPLATE, {"order/line[2]": "two", "order/line": "one"})
+    assert "<line>one</line><line>two</line>" in result
+
+
+def test_missing_elements_are_added_at_the_end_of_their_parent():
+    result = patch(
+        TEMPLATE,
+        {
+            "order/customer/email": "ada@example.com",
+            "order/shipping/city": "Paris",
+            "order/shipping/@method": "air",
+            "order/line[3]": "3",
+        },
+    )
+    assert result == (
+        '<order id=""><!-- filled in by the client -->'
+        "<customer><name></name><email>ada@example.com</email></customer>"
+        "<line>1</line><line>2</line><total></total>"
+        '<shipping method="air"><city>Paris</city></shipping><line>3</line></order>'
+    )
+
+
+def test_dict_values_replace_content_and_keep_attributes():
+    template = '<r><item sku="A1" qty="1"><note>old</note></item></r>'
+    result = patch(template, {"r/item": {"@qty": 2, "price": "5", "note": "new"}})
+    assert result == '<r><item sku="A1" qty="2"><price>5</price><note>new</note></item></r>'
+
+
+def test_none_empties_elements_and_drops_attributes():
+    template = '<r a="1" b="2"><x>text</x></r>'
+    assert patch(template, {"r/@a": None, "r/x": None}) == '<r b="2"><x></x></r>'
+
+
+def test_writes_to_output():
+    output = io.BytesIO()
+    assert xmltodict_rs.patch("<r><a/></r>", {"r/a": "é"}, output) is None
+    assert output.getvalue() == b'<?xml version="1.0" encoding="utf-8"?>\n<r><a>\xc3\xa9</a></r>'
+
+
+@pytest.mark.parametrize(
+    ("updates", "message"),
+    [
+        ({"order/": "x"}, "invalid update path"),
+        ({"@id": "x"}, "invalid update path"),
+        ({"order/@id/x": "x"}, "invalid update path"),
+        ({"order/line[x]": "x"}, "invalid update path"),
+        ({"order/line[0]": "x"}, "positions start at 1"),
+        ({"order/customer": "x", "order/customer/name": "y"}, "inside an element"),
+        ({"order/customer/name": "y", "order/customer": "x"}, "inside an element"),
+        ({"invoice/total": "1"}, "root element <order>, not <invoice>"),
+    ],
+)
+def test_invalid_updates(updates, message):
+    with pytest.raises(ValueError, match=message):
+        patch(TEMPLATE, updates)
@@ -0,0 +1,146 @@
+import datetime
+import io
+import plistlib
+from collections import OrderedDict
+
+import pytest
+
+import xmltodict_rs
+
+VALUE = {
+    "name": "Example",
+    "version": 3,
+    "ratio": 0.25,
+    "big": 1e20,
+    "limits": [-(2**63), 2**64 - 1],
+    "enabled": True,
+    "hidden": False,
+    "tags": ["a", "b < c & d", "line\r\nbreak"],
+    "empty": {"list": [], "dict": {}, "data": b""},
+    "payload": bytes(range(256)),
+    "created": datetime.datetime(2024, 1, 2, 3, 4, 5),
+    "nested": {"tuple": (1, (2,))},
+}
+
+
+# Reading
+
+
+def test_parse_plist_matches_plistlib():
+    xml = plistlib.dumps(VALUE)
+    assert xmltodict_rs.parse(xml, dialect="plist") == plistlib.loads(xml)
+
+
+def test_parse_plist_value_types():
+    xml = """<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>hex</key><integer>0x1F</integer>
+    <key>text</key><string>  spaced  </string>
+    <key>blank</key><string/>
+    <key>month</key><date>2024-05Z</date>
+    <key>data</key><data>
+        aGVs
+        bG8=
+    </data>
+</dict>
+</plist>"""
+    assert xmltodict_rs.parse(xml, dialect="plist") == {
+        "hex": 31,
+        "text": "  spaced  ",
+        "blank": "",
+        "month": datetime.datetime(2024, 5, 1),
+        "data": b"hello",
+    }
+
+
+def test_parse_plist_without_plist_element_and_dict_constructor():
+    result = xmltodict_rs.parse(
+        "<dict><key>b</key><true/><key>a</key><false/></dict>",
+        dialect="plist",
+        dict_constructor=OrderedDict,
+    )
+    assert result == OrderedDict([("b", True), ("a", False)])
+    assert type(result) is OrderedDict
+
+
+def test_parse_plist_applies_reading_options():
+    xml = '<!DOCTYPE plist [<!ENTITY e "x">]><plist><string>&e;</string></plist>'
+    assert xmltodict_rs.parse(xml, dialect="plist", disable_entities=False) == "x"
+    with pytest.raises(xmltodict_rs.EntitiesForbidden):
+        xmltodict_rs.parse(xml, dialect="plist", forbid_entities=True)
+
+
+@pytest.mark.parametrize(
+    ("xml", "message"),
+    [
+        ("<plist><integer>x</integer></plist>", 'bad integer value "x"'),
+        ("<plist><real>x</real></plist>", 'bad real value "x"'),
+        ("<plist><date>2024-01-02</date></plist>", 'bad date value "2024-01-02"'),
+        ("<plist><foo/></plist>", 'unknown plist tag "foo"'),
+        ("<plist><dict><key>a</key></dict></plist>", "key followed by a value"),
+        ("<plist><dict><true/></dict></plist>", "key followed by a value"),
+        ("<plist><array><key>a</key></array></plist>", "unexpected key in array"),
+        ("<plist/>", "exactly one value"),
+        ("<plist><true/><false/></plist>", "exactly one value"),
+    ],
+)
+def test_parse_plist_rejects_malformed_values(xml, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.parse(xml, dialect="plist")
+
+
+def test_parse_rejects_unknown_dialect():
+    with pytest.raises(ValueError, match="dialect must be 'xml' or 'plist', not \"json\""):
+        xmltodict_rs.parse("<a/>", dialect="json")
+
+
+# Writing
+
+
+def test_unparse_plist_matches_plistlib():
+    assert xmltodict_rs.unparse(VALUE, dialect="plist") == plistlib.dumps(VALUE).decode()
+
+
+def test_unparse_plist_roundtrip():
+    xml = xmltodict_rs.unparse(VALUE, dialect="plist")
+    assert xmltodict_rs.parse(xml, dialect="plist") == plistlib.loads(xml.encode())
+
+
+def test_unparse_plist_output_options():
+    assert xmltodict_rs.unparse([1], dialect="plist", full_document=False) == (
+        '<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" '
+        '"http://www.apple.com/DTDs/PropertyList-1.0.dtd">\n'
+        '<plist version="1.0">\n<array>\n\t<integer>1</integer>\n</array>\n</plist>\n'
+    )
+    output = io.BytesIO()
+    assert xmltodict_rs.unparse({"a": "é"}, output, dialect="plist") is None
+    assert output.getvalue() == plistlib.dumps({"a": "é"})
+
+
+@pytest.mark.parametrize(
+    ("value", "error", "message"),
+    [
+        (None, TypeError, "unsupported type: <class 'NoneType'>"),
+        ({1: "a"}, TypeError, "keys must be strings"),
+        (2**64, OverflowError, "int exceeds plist limits"),
+        ("\x01", ValueError, "strings can't contain control characters"),
+    ],
+)
+def test_unparse_plist_rejects_unsupported_values(value, error, message):
+    with pytest.raises(error, match=message):
+        xmltodict_rs.unparse(value, dialect="plist")
+
+
+def test_unparse_plist_rejects_recursive_values():
+    items = []
+    items.append(items)
+    with pytest.raises(TypeError, match="recursive lists"):
+        xmltodict_rs.unparse(items, dialect="plist")
+
+
+def test_roundtrip_plist():
+    xml = plistlib.dumps(VALUE)
+    assert xmltodict_rs.roundtrip(xml, dialect="plist") == plistlib.dumps(VALUE).decode().split(
+        "\n", 1
+    )[1]
@@ -0,0 +1,73 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+XML = (
+    '<?xml version="1.0"?><!DOCTYPE r><!-- top --><r a="x &amp; y"><i>1 &lt; 2</i>\n'
+    "   <e/><g><h><![CDATA[<raw>]]></h><?pi data?></g></r>"
+)
+
+
+def test_indents_and_keeps_markup_as_written():
+    assert xmltodict_rs.pretty(XML) == (
+        '<?xml version="1.0"?>\n'
+        "<!DOCTYPE r>\n"
+        "<!-- top -->\n"
+        '<r a="x &amp; y">\n'
+        "  <i>1 &lt; 2</i>\n"
+        "  <e/>\n"
+        "  <g>\n"
+        "    <h><![CDATA[<raw>]]></h>\n"
+        "    <?pi data?>\n"
+        "  </g>\n"
+        "</r>"
+    )
+
+
+def test_layout_options():
+    assert xmltodict_rs.pretty("<r><a/></r>", indent="\t", newl="\r\n") == "<r>\r\n\t<a/>\r\n</r>"
+
+
+def test_result_parses_like_the_input():
+    xml = "<r><a>1</a><b><c> two </c></b><a>3</a></r>"
+    assert xmltodict_rs.parse(xmltodict_rs.pretty(xml)) == xmltodict_rs.parse(xml)
+    assert xmltodict_rs.pretty(xmltodict_rs.pretty(xml)) == xmltodict_rs.pretty(xml)
+
+
+def test_mixed_and_preserved_content_is_left_alone():
+    xml = '<r><p>Hello <b>big</b> world</p><pre xml:space="preserve">  <x/>\n</pre></r>'
+    assert xmltodict_rs.pretty(xml) == (
+        '<r>\n  <p>Hello <b>big</b> world</p>\n  <pre xml:space="preserve">  <x/>\n</pre>\n</r>'
+    )
+
+
+def test_inputs_and_errors():
+    assert xmltodict_rs.pretty(io.BytesIO(b"<r>\xe9</r>"), encoding="latin-1") == "<r>é</r>"
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.pretty("<r><a></r>")
+    with pytest.raises(xmltodict_rs.XmlSyntaxError, match="no element found"):
+        xmltodict_rs.pretty("<!-- only a comment -->")
+
+
+def test_minify_removes_whitespace_between_elements():
+    pretty = xmltodict_rs.pretty(XML)
+    assert xmltodict_rs.minify(pretty) == (
+        '<?xml version="1.0"?><!DOCTYPE r><!-- top --><r a="x &amp; y"><i>1 &lt; 2</i><e/>'
+        "<g><h><![CDATA[<raw>]]></h><?pi data?></g></r>"
+    )
+    assert xmltodict_rs.pretty(xmltodict_rs.minify(pretty)) == pretty
+
+
+def test_minify_keeps_significant_whitespace():
+    xml = '<r>\n <t> x </t>\n <p>a <b>b</b> c</p>\n <s xml:space="preserve"> <i/> </s>\n</r>'
+    assert xmltodict_rs.minify(xml) == (
+        '<r><t> x </t><p>a <b>b</b> c</p><s xml:space="preserve"> <i/> </s></r>'
+    )
+
+
+def test_minify_strips_comments():
+    xml = "<!-- head -->\n<r>\n  <!-- inside -->\n  <a>x<!-- in text --></a>\n</r>"
+    assert xmltodict_rs.minify(xml) == "<!-- head --><r><!-- inside --><a>x<!-- in text --></a></r>"
+    assert xmltodict_rs.minify(xml, strip_comments=True) == "<r><a>x</a></r>"
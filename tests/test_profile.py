@@ -0,0 +1,42 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+FEED = """<?xml version="1.0"?>
+<!-- a feed -->
+<feed xmlns:a="urn:a">
+  <entry id="1"><title>One</title><link href="/1"/><link href="/1.pdf" rel="alt"/></entry>
+  <entry id="2"><title>Two</title><link href="/2"/></entry>
+  <a:meta><a:count>2</a:count></a:meta>
+</feed>"""
+
+
+def test_reports_counts_and_shape():
+    assert xmltodict_rs.profile(FEED) == {
+        "elements": 10,
+        "attributes": 7,
+        "max_depth": 3,
+        "max_siblings": 2,
+        "tags": {"feed": 1, "entry": 2, "title": 2, "link": 3, "a:meta": 1, "a:count": 1},
+        "attribute_names": {"xmlns:a": 1, "id": 2, "href": 3, "rel": 1},
+        "repeated": {"link": 2, "entry": 2},
+    }
+
+
+def test_repeated_names_are_candidates_for_force_list():
+    profile = xmltodict_rs.profile(FEED)
+    parsed = xmltodict_rs.parse(FEED)["feed"]
+    assert isinstance(parsed["entry"], list)
+    assert isinstance(parsed["entry"][0]["link"], list)
+    assert not isinstance(parsed["entry"][1]["link"], list)
+    assert set(profile["repeated"]) == {"entry", "link"}
+
+
+def test_inputs_and_errors():
+    assert xmltodict_rs.profile(io.BytesIO(b"<r/>"))["max_siblings"] == 1
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.profile("<r><i></r>")
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.profile("<a><b><c/></b></a>", max_depth=2)
@@ -0,0 +1,62 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+XML = """<export>
+  <rows>
+    <row id="1"><name>Tea</name><price>3.5</price><tags><tag>hot</tag></tags></row>
+    <row id="2"><name> Coffee </name><price/><vendor code="V7">Acme</vendor></row>
+  </rows>
+  <row id="outside"><name>ignored</name></row>
+</export>"""
+
+
+def test_infers_columns_in_the_order_found():
+    assert xmltodict_rs.read_records(XML, "export/rows/row") == {
+        "@id": ["1", "2"],
+        "name": ["Tea", "Coffee"],
+        "price": ["3.5", None],
+        "tags": [None, None],
+        "vendor": [None, "Acme"],
+    }
+
+
+def test_given_columns_reach_inside_records():
+    columns = ["name", "tags/tag", "vendor/@code", "missing", "@id"]
+    assert xmltodict_rs.read_records(XML, "**/rows/row", columns=columns) == {
+        "name": ["Tea", "Coffee"],
+        "tags/tag": ["hot", None],
+        "vendor/@code": [None, "V7"],
+        "missing": [None, None],
+        "@id": ["1", "2"],
+    }
+
+
+def test_patterns_and_inputs():
+    records = xmltodict_rs.read_records(io.BytesIO(XML.encode()), "**/row", columns=["@id"])
+    assert records == {"@id": ["1", "2", "outside"]}
+    assert xmltodict_rs.read_records("<r/>", "r/row") == {}
+    assert xmltodict_rs.read_records("<r/>", "r/row", columns=["a"]) == {"a": []}
+
+
+def test_records_inside_records_are_not_read():
+    xml = "<r><row><a>1</a><row><a>2</a></row></row></r>"
+    assert xmltodict_rs.read_records(xml, "**/row", columns=["a"]) == {"a": ["1"]}
+
+
+def test_arrow_table():
+    pyarrow = pytest.importorskip("pyarrow")
+    table = xmltodict_rs.read_records(XML, "export/rows/row", columns=["@id", "price"], arrow=True)
+    assert isinstance(table, pyarrow.Table)
+    assert table.to_pydict() == {"@id": ["1", "2"], "price": ["3.5", None]}
+
+
+def test_errors():
+    with pytest.raises(ValueError, match="given twice"):
+        xmltodict_rs.read_records(XML, "**/row", columns=["a", "a"])
+    with pytest.raises(ValueError, match="invalid column"):
+        xmltodict_rs.read_records(XML, "**/row", columns=["a/"])
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.read_records("<r><row></r>", "r/row")
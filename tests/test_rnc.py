@@ -0,0 +1,95 @@
+import pytest
+
+import xmltodict_rs
+
+RNC = """
+# A library catalog
+default namespace = "urn:library"
+start = library
+
+library = element library {
+  attribute name { text },
+  book*
+}
+
+book = element book {
+  attribute id { xsd:positiveInteger },
+  attribute lang { "en" | "de" }?,
+  element title { text },
+  element author { text }+,
+  (element isbn { xsd:string { length = "13" } } | element issn { text })?
+}
+"""
+
+VALID = """<library name="City" xmlns="urn:library">
+  <book id="1" lang="en"><title>A</title><author>X</author><author>Y</author></book>
+  <book id="2"><title>B</title><author>Z</author><isbn>9780000000000</isbn></book>
+</library>"""
+
+
+@pytest.mark.parametrize("schema", [RNC, RNC.encode()])
+def test_valid_document_has_no_errors(schema):
+    assert xmltodict_rs.validate_rnc(VALID, schema) == []
+
+
+def test_empty_repetition():
+    assert xmltodict_rs.validate_rnc('<library name="Empty"/>', RNC) == []
+
+
+def test_errors_match_validate():
+    xml = """<library>
+  <book id="0" lang="fr"><author>X</author></book>
+  <book id="3"><title>C</title><author>Y</author><issn/><isbn>1</isbn></book>
+</library>"""
+    errors = xmltodict_rs.validate_rnc(xml, RNC)
+    found = [(error["path"], error["line"], error["expected"], error["found"]) for error in errors]
+    assert found == [
+        ("library/@name", 1, "attribute name", None),
+        ("library/book/@id", 2, "xsd:positiveInteger", "0"),
+        ("library/book/@lang", 2, 'one of "en", "de"', "fr"),
+        ("library/book/author", 2, "<title>", "<author>"),
+        ("library/book/isbn", 3, "exactly 13 characters", "1"),
+        ("library/book/isbn", 3, "end of <book>", "<isbn>"),
+    ]
+    assert errors[1]["message"] == (
+        'library/book/@id: expected xsd:positiveInteger, found "0" (line 2)'
+    )
+
+
+def test_single_pattern_schema():
+    rnc = "element point { attribute x { xsd:int }, attribute y { xsd:int }, empty }"
+    assert xmltodict_rs.validate_rnc('<point x="1" y="2"/>', rnc) == []
+    errors = xmltodict_rs.validate_rnc('<point x="1" y="2">3</point>', rnc)
+    assert errors[0]["expected"] == "no text"
+    assert xmltodict_rs.validate_rnc("<line/>", rnc)[0]["expected"] == "<point>"
+
+
+def test_mixed_and_text_choices():
+    rnc = "start = element p { mixed { (element b { text } | element i { text })* } }"
+    assert xmltodict_rs.validate_rnc("<p>a <b>bold</b> and <i>it</i>.</p>", rnc) == []
+    assert xmltodict_rs.validate_rnc("<p><u/></p>", rnc)[0]["found"] == "<u>"
+
+
+@pytest.mark.parametrize(
+    ("rnc", "message"),
+    [
+        ("element a { text } & element b { text }", "'&' is not supported"),
+        ("start = a", "line 1: a is not defined"),
+        ("element a {\n  text", "line 2: expected '}'"),
+        ('element a { "x }', "unterminated literal"),
+        ('include "other.rnc"', "include is not supported"),
+    ],
+)
+def test_rejects_invalid_schema(rnc, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.validate_rnc("<a/>", rnc)
+
+
+def test_rejects_schema_of_other_types():
+    with pytest.raises(TypeError, match="rnc_schema must be str or bytes"):
+        xmltodict_rs.validate_rnc("<a/>", 1)
+
+
+def test_malformed_document():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.validate_rnc("<library>", RNC)
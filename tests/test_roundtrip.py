@@ -184,3 +184,255 @@ def test_special_chars_in_attr_roundtrip():
     rust_parsed = xmltodict_rs.parse(rust_xml)
 
     assert original_parsed == rust_parsed
+
+
+# Mixed content
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        "<p>hello <b>world</b> again</p>",
+        '<p class="lead">a <b>1</b> <i>2</i> z</p>',
+        "<p>x <b>bold <i>and italic</i> text</b>.</p>",
+        "<p>a &amp; <br/> b</p>",
+        "<r><p>one <b>1</b></p><p>two <b>2</b></p><q>plain</q></r>",
+    ],
+)
+def test_mixed_content_roundtrip(xml):
+    parsed = xmltodict_rs.parse(xml, preserve_mixed_content=True)
+    xml_out = xmltodict_rs.unparse(parsed, full_document=False, preserve_mixed_content=True)
+    assert xmltodict_rs.parse(xml_out, preserve_mixed_content=True) == parsed
+
+
+def test_mixed_content_ordered_nodes():
+    xml = '<p class="lead">hello <b>world</b> again</p>'
+    assert xmltodict_rs.parse(xml, preserve_mixed_content=True) == {
+        "p": [{"@class": "lead"}, "hello ", {"b": "world"}, " again"]
+    }
+
+
+def test_mixed_content_keeps_whitespace_between_elements():
+    xml = "<p><b>1</b> <i>2</i>!</p>"
+    assert xmltodict_rs.parse(xml, preserve_mixed_content=True) == {
+        "p": [{"b": "1"}, " ", {"i": "2"}, "!"]
+    }
+    assert xmltodict_rs.unparse(
+        {"p": [{"b": "1"}, " ", {"i": "2"}, "!"]}, full_document=False, preserve_mixed_content=True
+    ) == xml
+
+
+def test_mixed_content_leaves_other_elements_unchanged():
+    xml = "<r>\n  <a> x </a>\n  <a>y</a>\n  <b n='1'>z</b>\n</r>"
+    assert xmltodict_rs.parse(xml, preserve_mixed_content=True) == xmltodict_rs.parse(xml)
+
+
+def test_mixed_content_repeated_elements():
+    xml = "<r><p>a <b>1</b></p><p>b <b>2</b></p></r>"
+    assert xmltodict_rs.parse(xml, preserve_mixed_content=True) == {
+        "r": {"p": [["a ", {"b": "1"}], ["b ", {"b": "2"}]]}
+    }
+
+
+def test_mixed_content_with_comments():
+    xml = "<p>a <!-- note --><b>1</b></p>"
+    result = xmltodict_rs.parse(xml, preserve_mixed_content=True, process_comments=True)
+    assert result == {"p": ["a ", {"#comment": "note"}, {"b": "1"}]}
+
+
+def test_mixed_content_without_strip_whitespace():
+    xml = "<r>\n <a>1</a>\n</r>"
+    result = xmltodict_rs.parse(xml, preserve_mixed_content=True, strip_whitespace=False)
+    assert result == {"r": ["\n ", {"a": "1"}, "\n"]}
+
+
+def test_mixed_content_lists_are_repeated_elements_by_default():
+    obj = {"p": ["hello ", {"b": "world"}]}
+    assert xmltodict_rs.unparse(obj, full_document=False) == "<p>hello </p><p><b>world</b></p>"
+
+
+# roundtrip()
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        "<r></r>",
+        '<r a="1" b="&lt;&amp;&quot;">text</r>',
+        "<r><a>1</a><a>2</a><b>x</b></r>",
+        "<r>\n  <a>1</a>\n  <b>2</b>\n</r>",
+        "<p>hello <b>world</b>, bye</p>",
+        "<!--head--><r><!--inner--><a>1</a></r>",
+        '<?xml-stylesheet href="a.xsl"?><r><?target data?><a>1</a></r>',
+        "<!DOCTYPE r>\n<r>x</r>",
+        '<r xmlns:p="urn:p"><p:a p:b="1">x</p:a></r>',
+    ],
+)
+def test_roundtrip_canonical_documents_are_unchanged(xml):
+    assert xmltodict_rs.roundtrip(xml) == xml
+
+
+@pytest.mark.parametrize(
+    ("xml", "canonical"),
+    [
+        ('<?xml version="1.0"?><r/>', "<r></r>"),
+        ("<r a='1'>x</r>", '<r a="1">x</r>'),
+        ("<r><![CDATA[<x>]]></r>", "<r>&lt;x&gt;</r>"),
+        ("<r>&#65;&#x42;</r>", "<r>AB</r>"),
+        ('<!DOCTYPE r [<!ENTITY e "x">]><r>&e;</r>', '<!DOCTYPE r [<!ENTITY e "x">]>\n<r>x</r>'),
+        ('<r a="x&#10;y"></r>', '<r a="x&#10;y"></r>'),
+    ],
+)
+def test_roundtrip_canonicalizes(xml, canonical):
+    assert xmltodict_rs.roundtrip(xml) == canonical
+
+
+def test_roundtrip_is_idempotent():
+    xml = "<?xml version='1.0'?><!--c--><r a='1'><![CDATA[x]]><b/><?p q?></r>"
+    once = xmltodict_rs.roundtrip(xml)
+    assert xmltodict_rs.roundtrip(once) == once
+
+
+def test_roundtrip_passes_options_through():
+    assert xmltodict_rs.roundtrip("<r><a>1</a></r>", short_empty_elements=True) == "<r><a>1</a></r>"
+    assert xmltodict_rs.roundtrip("<r><a/></r>", short_empty_elements=True) == "<r><a/></r>"
+    assert xmltodict_rs.roundtrip(b"<r>\xe9</r>", encoding="latin-1") == "<r>\xe9</r>"
+    assert xmltodict_rs.roundtrip("<r>x</r>", full_document=True) == (
+        '<?xml version="1.0" encoding="utf-8"?>\n<r>x</r>'
+    )
+
+
+def test_roundtrip_refuses_deep_documents_unless_allowed():
+    deep = "<a>" * 300_000 + "</a>" * 300_000
+    with pytest.raises(xmltodict_rs.DepthLimitExceeded):
+        xmltodict_rs.roundtrip(deep)
+    xml = "<a>" * 600 + "</a>" * 600
+    assert xmltodict_rs.roundtrip(xml, max_depth=600) == xml
+
+
+def test_roundtrip_shared_keys_apply_to_both_sides():
+    xml = '<r a="1">x<!--c--></r>'
+    assert xmltodict_rs.roundtrip(xml, attr_prefix="_", comment_key="!c", cdata_key="$") == xml
+
+
+def test_roundtrip_rejects_unknown_options():
+    with pytest.raises(TypeError, match="unexpected keyword argument"):
+        xmltodict_rs.roundtrip("<r/>", bogus=True)
+
+
+def test_roundtrip_attr_dict_key():
+    xml = '<r a="1">x<b c="2"></b>y</r>'
+    assert xmltodict_rs.roundtrip(xml, attr_dict_key="#attrs") == xml
+    data = xmltodict_rs.parse('<r a="1"><b c="2">t</b></r>', attr_dict_key="#attrs")
+    assert xmltodict_rs.unparse(data, full_document=False, attr_dict_key="#attrs") == (
+        '<r a="1"><b c="2">t</b></r>'
+    )
+
+
+def test_sibling_order_is_written_back():
+    xml = "<msg><debit>10</debit><credit>5</credit><!--x--><debit>3</debit></msg>"
+    data = xmltodict_rs.parse(xml, preserve_sibling_order=True, process_comments=True)
+    assert data == {
+        "msg": {
+            "debit": ["10", "3"],
+            "credit": "5",
+            "#comment": "x",
+            "#order": ["debit", "credit", "#comment", "debit"],
+        }
+    }
+    assert xmltodict_rs.unparse(data, full_document=False, preserve_sibling_order=True) == xml
+    assert xmltodict_rs.roundtrip(xml, preserve_sibling_order=True) == xml
+
+
+def test_sibling_order_keeps_text_elements():
+    data = xmltodict_rs.parse("<r><a>1</a></r>", preserve_sibling_order=True, order_key="!o")
+    assert data == {"r": {"a": "1", "!o": ["a"]}}
+    data = {"r": {"a": ["1", "2"], "b": "x", "#order": ["a", "b", "a"]}}
+    assert xmltodict_rs.unparse(data, full_document=False, preserve_sibling_order=True) == (
+        "<r><a>1</a><b>x</b><a>2</a></r>"
+    )
+
+
+def test_sibling_order_applies_to_every_writer():
+    data = {"r": {"a": ["1", "2"], "b": "x", "!o": ["a", "b", "a"]}}
+    options = {"full_document": False, "preserve_sibling_order": True, "order_key": "!o"}
+    expected = "<r><a>1</a><b>x</b><a>2</a></r>"
+    assert "".join(xmltodict_rs.unparse_iter(data, chunk_size=4, **options)) == expected
+    assert xmltodict_rs.unparse_batch([data, data], **options) == [expected] * 2
+    serializer = xmltodict_rs.Serializer(**options)
+    assert serializer.unparse(data) == expected
+    assert repr(serializer) == (
+        "Serializer(full_document=False, preserve_sibling_order=True, order_key='!o')"
+    )
+    with pytest.raises(ValueError, match="leaves out children"):
+        xmltodict_rs.unparse_batch([{"r": {"a": "1", "b": "2", "!o": ["a"]}}], **options)
+
+
+def test_sibling_positions_are_written_back():
+    xml = "<msg><debit>10</debit><credit>5</credit><debit>3</debit></msg>"
+    data = xmltodict_rs.parse(xml, force_list=True, index_key="#index")
+    options = {"full_document": False, "index_key": "#index"}
+    assert xmltodict_rs.unparse(data, **options) == xml
+    assert "".join(xmltodict_rs.unparse_iter(data, chunk_size=8, **options)) == xml
+    assert xmltodict_rs.unparse_batch([data], **options) == [xml]
+    assert xmltodict_rs.Serializer(**options).unparse(data) == xml
+    assert xmltodict_rs.roundtrip(xml, force_list=True, index_key="#index") == xml
+
+
+def test_sibling_positions_leave_unpositioned_children_last():
+    data = {"r": {"#comment": "c", "b": {"i": 1}, "a": [{"i": 2}, {"i": 0}]}}
+    assert xmltodict_rs.unparse(data, full_document=False, index_key="i") == (
+        "<r><a></a><b></b><a></a><!--c--></r>"
+    )
+    with pytest.raises(ValueError, match="i of <a> must be a position"):
+        xmltodict_rs.unparse({"r": {"a": {"i": "first"}}}, index_key="i")
+
+
+@pytest.mark.parametrize(
+    ("data", "message"),
+    [
+        ({"a": {"b": ["1"], "#order": ["b", "b"]}}, 'lists "b" more times'),
+        ({"a": {"b": "1", "#order": ["c"]}}, 'lists "c" more times'),
+        ({"a": {"b": ["1", "2"], "#order": ["b"]}}, 'leaves out children "b"'),
+        ({"a": {"b": "1", "c": "2", "#order": ["b"]}}, 'leaves out children "c"'),
+    ],
+)
+def test_sibling_order_must_match_the_children(data, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.unparse(data, preserve_sibling_order=True)
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        "<r><a>1</a><b>2</b><a>3</a></r>",
+        "<r><a>1</a><!--c--><a>2</a></r>",
+        "<r><a>x<!--c-->y</a></r>",
+        "<r><!--c-->x</r>",
+        '<r a="1">t<?p d?></r>',
+        "<r></r><!--c-->",
+        "<!--a--><r></r><!--b-->",
+    ],
+    ids=[
+        "interleaved",
+        "comment-between-siblings",
+        "text-around-comment",
+        "comment-then-text",
+        "text-then-pi",
+        "epilogue-comment",
+        "comments-around-root",
+    ],
+)
+def test_roundtrip_keeps_order(xml):
+    assert xmltodict_rs.roundtrip(xml) == xml
+
+
+def test_roundtrip_losses():
+    assert xmltodict_rs.roundtrip("<!DOCTYPE r><r/>") == "<!DOCTYPE r>\n<r></r>"
+    assert xmltodict_rs.roundtrip("<r a='1'><![CDATA[<x>]]></r>") == '<r a="1">&lt;x&gt;</r>'
+
+
+def test_comments_count_as_mixed_content():
+    assert xmltodict_rs.parse(
+        "<r>x<!--c-->y</r>", preserve_mixed_content=True, process_comments=True
+    ) == {"r": ["x", {"#comment": "c"}, "y"]}
@@ -0,0 +1,147 @@
+import pytest
+
+import xmltodict_rs
+
+SOAP_11 = "http://schemas.xmlsoap.org/soap/envelope/"
+SOAP_12 = "http://www.w3.org/2003/05/soap-envelope"
+
+RESPONSE = f"""<?xml version="1.0"?>
+<env:Envelope xmlns:env="{SOAP_11}">
+  <env:Header><auth>token</auth></env:Header>
+  <env:Body>
+    <b:GetBookResponse xmlns:b="urn:books">
+      <b:title>Dune</b:title>
+      <b:price>9.5</b:price>
+    </b:GetBookResponse>
+  </env:Body>
+</env:Envelope>"""
+
+FAULT_11 = f"""<soap:Envelope xmlns:soap="{SOAP_11}">
+  <soap:Body>
+    <soap:Fault>
+      <faultcode>soap:Server</faultcode>
+      <faultstring>Book not found</faultstring>
+      <detail><id>7</id></detail>
+    </soap:Fault>
+  </soap:Body>
+</soap:Envelope>"""
+
+FAULT_12 = f"""<env:Envelope xmlns:env="{SOAP_12}">
+  <env:Body>
+    <env:Fault>
+      <env:Code><env:Value>env:Sender</env:Value></env:Code>
+      <env:Reason>
+        <env:Text xml:lang="en">Bad request</env:Text>
+        <env:Text xml:lang="fr">Mauvaise requête</env:Text>
+      </env:Reason>
+    </env:Fault>
+  </env:Body>
+</env:Envelope>"""
+
+
+# Parsing
+
+
+def test_parse_soap_returns_body():
+    assert xmltodict_rs.parse_soap(RESPONSE) == {
+        "urn:books:GetBookResponse": {"urn:books:title": "Dune", "urn:books:price": "9.5"}
+    }
+
+
+def test_parse_soap_passes_options_to_parse():
+    body = xmltodict_rs.parse_soap(RESPONSE, namespaces={"urn:books": None}, infer_types=True)
+    assert body == {"GetBookResponse": {"title": "Dune", "price": 9.5}}
+    body = xmltodict_rs.parse_soap(RESPONSE, strip_namespace_prefixes=True)
+    assert body == {"GetBookResponse": {"title": "Dune", "price": "9.5"}}
+
+
+@pytest.mark.parametrize(
+    "namespaces",
+    [{SOAP_11: "s"}, {SOAP_11: ""}, {SOAP_11: None}],
+)
+def test_parse_soap_with_mapped_envelope_namespace(namespaces):
+    body = xmltodict_rs.parse_soap(RESPONSE, namespaces={**namespaces, "urn:books": "b"})
+    assert body == {"b:GetBookResponse": {"b:title": "Dune", "b:price": "9.5"}}
+
+
+def test_parse_soap_12_and_custom_separator():
+    xml = f'<e:Envelope xmlns:e="{SOAP_12}"><e:Body><ping/></e:Body></e:Envelope>'
+    assert xmltodict_rs.parse_soap(xml, namespace_separator="|") == {"ping": None}
+
+
+def test_parse_soap_empty_body():
+    xml = f'<s:Envelope xmlns:s="{SOAP_11}"><s:Body/></s:Envelope>'
+    assert xmltodict_rs.parse_soap(xml) == {}
+
+
+@pytest.mark.parametrize(
+    ("xml", "message"),
+    [
+        ("<Envelope><Body/></Envelope>", "not a SOAP envelope"),
+        ('<s:Envelope xmlns:s="urn:other"><s:Body/></s:Envelope>', "not a SOAP envelope"),
+        (f'<s:Envelope xmlns:s="{SOAP_11}"><s:Header/></s:Envelope>', "has no Body"),
+    ],
+)
+def test_parse_soap_rejects_other_documents(xml, message):
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.parse_soap(xml)
+
+
+# Faults
+
+
+def test_parse_soap_11_fault():
+    with pytest.raises(xmltodict_rs.SoapFault, match="soap:Server: Book not found") as info:
+        xmltodict_rs.parse_soap(FAULT_11)
+    assert info.value.code == "soap:Server"
+    assert info.value.reason == "Book not found"
+    assert info.value.detail == {"id": "7"}
+
+
+def test_parse_soap_12_fault():
+    with pytest.raises(xmltodict_rs.SoapFault) as info:
+        xmltodict_rs.parse_soap(FAULT_12)
+    assert info.value.code == "env:Sender"
+    assert info.value.reason == "Bad request"
+    assert info.value.detail is None
+
+
+def test_parse_soap_fault_with_stripped_names():
+    with pytest.raises(xmltodict_rs.SoapFault) as info:
+        xmltodict_rs.parse_soap(FAULT_12, strip_namespace_prefixes=True)
+    assert (info.value.code, info.value.reason) == ("env:Sender", "Bad request")
+
+
+# Building
+
+
+def test_build_soap():
+    body = {"m:GetBook": {"@xmlns:m": "urn:books", "m:id": 7}}
+    result = xmltodict_rs.build_soap(body, full_document=False)
+    assert result == (
+        f'<soap:Envelope xmlns:soap="{SOAP_11}"><soap:Body>'
+        '<m:GetBook xmlns:m="urn:books"><m:id>7</m:id></m:GetBook>'
+        "</soap:Body></soap:Envelope>"
+    )
+
+
+def test_build_soap_headers_version_and_options():
+    result = xmltodict_rs.build_soap(
+        {"ping": None}, headers={"auth": "token"}, soap_version="1.2", attr_prefix="_"
+    )
+    assert result.startswith('<?xml version="1.0" encoding="utf-8"?>\n')
+    assert result.endswith(
+        f'<soap:Envelope xmlns:soap="{SOAP_12}"><soap:Header><auth>token</auth></soap:Header>'
+        "<soap:Body><ping></ping></soap:Body></soap:Envelope>"
+    )
+
+
+def test_build_soap_rejects_unknown_version():
+    with pytest.raises(ValueError, match="soap_version must be '1.1' or '1.2'"):
+        xmltodict_rs.build_soap({}, soap_version="2.0")
+
+
+def test_build_soap_roundtrip():
+    body = {"urn:books:GetBook": {"urn:books:id": "7"}}
+    xml = xmltodict_rs.build_soap(body, namespaces={"urn:books": "b"})
+    assert xmltodict_rs.parse_soap(xml) == body
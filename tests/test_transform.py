@@ -0,0 +1,85 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+
+def transform(xml, **kwargs):
+    return xmltodict_rs.transform(xml, full_document=False, **kwargs)
+
+
+def test_document_is_written_back_without_rules():
+    xml = '<r a="1"><!--c--><b>x &amp; y</b><?pi data?><c/>tail</r>'
+    assert transform(xml) == '<r a="1"><!--c--><b>x &amp; y</b><?pi data?><c></c>tail</r>'
+
+
+def test_xml_declaration_and_doctype():
+    result = xmltodict_rs.transform("<!DOCTYPE r><r/>", short_empty_elements=True)
+    assert result == '<?xml version="1.0" encoding="utf-8"?>\n<!DOCTYPE r>\n<r/>'
+
+
+def test_drop_removes_elements_with_their_content():
+    xml = "<r><user><name>a</name><password>p</password></user><debug><x/></debug></r>"
+    result = transform(xml, rules={"drop": ["**/password", "r/debug"]})
+    assert result == "<r><user><name>a</name></user></r>"
+
+
+def test_rename_to_names_with_non_ascii_letters():
+    assert transform("<r><a/></r>", rules={"rename": {"a": "étape_1.x"}}) == (
+        "<r><étape_1.x></étape_1.x></r>"
+    )
+
+
+def test_rename_by_name_and_path():
+    xml = "<r><item>1</item><group><item>2</item></group></r>"
+    result = transform(xml, rules={"rename": {"item": "entry", "r/group/item": "member"}})
+    assert result == "<r><entry>1</entry><group><member>2</member></group></r>"
+
+
+def test_rules_match_original_names():
+    xml = "<r><a><b/></a></r>"
+    result = transform(xml, rules={"rename": {"a": "x"}, "drop": ["r/a/b"]})
+    assert result == "<r><x></x></r>"
+
+
+def test_attribute_callbacks_rewrite_and_drop_values():
+    xml = '<r><p price="2" email="a@b" id="7"/></r>'
+    rules = {
+        "attributes": {
+            "price": lambda value: f"{float(value):.2f}",
+            "email": lambda value: None,
+        }
+    }
+    assert transform(xml, rules=rules) == '<r><p price="2.00" id="7"></p></r>'
+
+
+def test_writes_to_text_and_binary_streams():
+    text = io.StringIO()
+    assert xmltodict_rs.transform("<r>é</r>", text, full_document=False) is None
+    assert text.getvalue() == "<r>é</r>"
+    binary = io.BytesIO()
+    xmltodict_rs.transform(io.BytesIO(b"<r>\xc3\xa9</r>"), binary, output_encoding="latin-1")
+    assert binary.getvalue() == b'<?xml version="1.0" encoding="latin-1"?>\n<r>\xe9</r>'
+
+
+def test_malformed_input_raises():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        transform("<r><a></r>")
+
+
+@pytest.mark.parametrize(
+    ("rules", "error"),
+    [
+        ({"keep": ["r"]}, ValueError),
+        ({"rename": {"a": "not a name"}}, ValueError),
+        ({"rename": {"a": "1bad"}}, ValueError),
+        ({"rename": {"a": "-x"}}, ValueError),
+        ({"rename": {"a": ""}}, ValueError),
+        ({"attributes": {"a": "x"}}, TypeError),
+        ({"drop": "r/a"}, TypeError),
+    ],
+)
+def test_invalid_rules(rules, error):
+    with pytest.raises(error):
+        transform("<r/>", rules=rules)
@@ -1,6 +1,16 @@
+import copy
+import dataclasses
+import datetime
+import decimal
 import enum
+import io
+import pickle
 import re
+import types
+import uuid
 from collections import OrderedDict
+from collections.abc import Mapping
+from typing import NamedTuple
 
 import pytest
 import xmltodict
@@ -144,6 +154,63 @@ def test_iterators(iterator):
     compare_unparse(obj, short_empty_elements=True)
 
 
+@pytest.mark.parametrize(
+    "make_items",
+    [
+        lambda: (1, 2, 3),
+        lambda: (n for n in (1, 2, 3)),
+        lambda: range(1, 4),
+        lambda: {"x": 1, "y": 2, "z": 3}.values(),
+    ],
+    ids=["tuple", "generator", "range", "dict_values"],
+)
+def test_iterables_repeat_elements(make_items):
+    expected = xmltodict.unparse({"a": {"item": make_items()}})
+    assert xmltodict_rs.unparse({"a": {"item": make_items()}}) == expected
+    result = xmltodict_rs.unparse({"item": make_items()}, full_document=False)
+    assert result == "<item>1</item><item>2</item><item>3</item>"
+
+
+EXPAND_ITER_OBJECTS = [
+    {"files": {"file": [[1, 2], 3]}},
+    {"a": {"b": [(1,), ["x", ["y"]], {"c": [1]}]}},
+    {"a": {"b": [[None, True], []]}},
+    {"a": {"b": ["x", "y"]}},
+    {"a": {"b": [{"@id": "1"}, ("p", "q")]}},
+]
+
+
+@pytest.mark.parametrize("obj", EXPAND_ITER_OBJECTS)
+def test_unparse_expand_iter(obj):
+    compare_unparse(obj, expand_iter="item")
+
+
+def test_unparse_expand_iter_pretty():
+    compare_unparse({"a": {"b": [["x", "y"]]}}, expand_iter="v", pretty=True)
+
+
+def test_unparse_iter_expand_iter():
+    data = {"files": {"file": [["a.txt", "b.txt"]]}}
+    assert "".join(xmltodict_rs.unparse_iter(data, expand_iter="name")) == (
+        xmltodict_rs.unparse(data, expand_iter="name")
+    )
+
+
+@pytest.mark.parametrize("value", [b"ab", bytearray(b"ab")])
+def test_unparse_bytes_are_one_element(value):
+    assert xmltodict_rs.unparse({"r": value}, full_document=False) == "<r>ab</r>"
+    assert xmltodict_rs.unparse({"r": {"@a": value}}, full_document=False) == '<r a="ab"></r>'
+    result = xmltodict_rs.unparse(
+        {"r": value}, full_document=False, default=lambda data: data.hex()
+    )
+    assert result == "<r>6162</r>"
+
+
+def test_unparse_bytes_must_be_utf8():
+    with pytest.raises(ValueError, match="UTF-8"):
+        xmltodict_rs.unparse({"r": b"\xff"})
+
+
 @pytest.mark.parametrize("obj", SIMPLE_OBJECTS[:3])
 def test_short_empty_elements(obj):
     if obj[next(iter(obj.keys()))] is None:
@@ -205,6 +272,10 @@ def test_pretty_printing():
     assert original == rust_impl
 
 
+def test_pretty_printing_lists_without_blank_lines():
+    compare_unparse({"a": {"b": [{"c": [1, 2]}, 3], "x": "y"}}, pretty=True)
+
+
 # Preprocessor tests
 
 PREPROCESSOR_DATA = [
@@ -384,6 +455,37 @@ def test_unparse_float_value():
     compare_unparse(obj)
 
 
+class Celsius(float):
+    def __str__(self):
+        return f"{float(self)} C"
+
+
+NUMBERS = [
+    0,
+    -7,
+    2**63 - 1,
+    -(2**63),
+    2**64,
+    0.0,
+    -0.0,
+    0.1,
+    1e-4,
+    9.99e-5,
+    1e15 + 0.5,
+    1e16,
+    -1.5e300,
+    5e-324,
+    float("inf"),
+    float("nan"),
+    Celsius(21.5),
+]
+
+
+@pytest.mark.parametrize("number", NUMBERS)
+def test_unparse_numbers_match_str(number):
+    compare_unparse({"root": {"@n": number, "v": [number, number]}})
+
+
 class Color(str, enum.Enum):
     RED = "red"
     BLUE = "blue"
@@ -406,3 +508,1408 @@ STR_SUBCLASS_OBJECTS = [
 @pytest.mark.parametrize("obj", STR_SUBCLASS_OBJECTS)
 def test_unparse_str_subclass(obj):
     compare_unparse(obj)
+
+
+# Output stream tests
+
+
+@pytest.mark.parametrize("obj", SIMPLE_OBJECTS + ATTRIBUTE_OBJECTS + LIST_OBJECTS)
+def test_unparse_to_text_stream(obj):
+    original = io.StringIO()
+    rust_impl = io.StringIO()
+    assert xmltodict.unparse(obj, output=original) is None
+    assert xmltodict_rs.unparse(obj, output=rust_impl) is None
+    assert _strip(rust_impl.getvalue()) == _strip(original.getvalue())
+
+
+@pytest.mark.parametrize("encoding", ["utf-8", "iso-8859-1", "ascii"])
+def test_unparse_to_binary_stream_encodes_output(encoding):
+    obj = {"root": {"@name": "café", "#text": "naïve 世界"}}
+    original = io.BytesIO()
+    rust_impl = io.BytesIO()
+    xmltodict.unparse(obj, output=original, encoding=encoding)
+    xmltodict_rs.unparse(obj, output=rust_impl, encoding=encoding)
+    assert rust_impl.getvalue().split(b"\n", 1)[1] == original.getvalue().split(b"\n", 1)[1]
+
+
+def test_unparse_to_stream_writes_incrementally():
+    class Recorder:
+        def __init__(self) -> None:
+            self.chunks = []
+
+        def write(self, data):
+            self.chunks.append(data)
+
+    obj = {"root": {"item": [{"@id": str(i), "#text": "x" * 100} for i in range(5000)]}}
+    recorder = Recorder()
+    xmltodict_rs.unparse(obj, output=recorder)
+    assert len(recorder.chunks) > 1
+    assert b"".join(recorder.chunks).decode() == xmltodict_rs.unparse(obj)
+
+
+def test_unparse_to_stream_propagates_write_errors():
+    class Broken:
+        def write(self, data):
+            raise OSError("disk full")
+
+    with pytest.raises(OSError, match="disk full"):
+        xmltodict_rs.unparse({"root": "x"}, output=Broken())
+
+
+# Chunked unparse tests
+
+
+@pytest.mark.parametrize("obj", SIMPLE_OBJECTS + ATTRIBUTE_OBJECTS + LIST_OBJECTS)
+def test_unparse_iter_matches_unparse(obj):
+    assert "".join(xmltodict_rs.unparse_iter(obj)) == xmltodict_rs.unparse(obj)
+
+
+def test_unparse_iter_yields_bounded_chunks():
+    obj = {"root": {"item": [{"@id": str(i), "#text": "x" * 100} for i in range(5000)]}}
+    chunks = list(xmltodict_rs.unparse_iter(obj, chunk_size=4096, pretty=True))
+    assert len(chunks) > 10
+    assert all(len(chunk) < 4096 * 2 for chunk in chunks)
+    assert "".join(chunks) == xmltodict_rs.unparse(obj, pretty=True)
+
+
+def test_unparse_iter_is_lazy():
+    produced = []
+
+    def preprocessor(key, value):
+        produced.append(key)
+        return key, value
+
+    obj = {"root": {"item": ["x" * 100 for _ in range(1000)]}}
+    chunks = xmltodict_rs.unparse_iter(obj, chunk_size=1024, preprocessor=preprocessor)
+    first = next(chunks)
+    assert first.startswith("<?xml")
+    assert len(produced) < 100
+
+
+def test_unparse_iter_early_close():
+    obj = {"root": {"item": ["x" * 100 for _ in range(10000)]}}
+    chunks = xmltodict_rs.unparse_iter(obj, chunk_size=512)
+    next(chunks)
+    del chunks
+
+
+def test_unparse_iter_validates_roots_eagerly():
+    with pytest.raises(ValueError):
+        xmltodict_rs.unparse_iter({"a": 1, "b": 2})
+
+
+def test_unparse_iter_rejects_zero_chunk_size():
+    with pytest.raises(ValueError):
+        xmltodict_rs.unparse_iter({"a": 1}, chunk_size=0)
+
+
+def test_unparse_iter_propagates_preprocessor_errors():
+    def preprocessor(key, value):
+        if key == "bad":
+            raise KeyError("boom")
+        return key, value
+
+    chunks = xmltodict_rs.unparse_iter({"root": {"ok": "1", "bad": "2"}}, preprocessor=preprocessor)
+    with pytest.raises(KeyError, match="boom"):
+        list(chunks)
+
+
+# Batch unparse tests
+
+
+def test_unparse_batch_matches_unparse():
+    objects = SIMPLE_OBJECTS + ATTRIBUTE_OBJECTS + LIST_OBJECTS
+    assert xmltodict_rs.unparse_batch(objects) == [xmltodict_rs.unparse(obj) for obj in objects]
+
+
+def test_unparse_batch_options():
+    messages = ({"event": {"@id": i, "name": f"n{i}"}} for i in range(3))
+    result = xmltodict_rs.unparse_batch(messages, full_document=False, pretty=True, indent="  ")
+    assert result[2] == '<event id="2">\n  <name>n2</name>\n</event>'
+    assert len(result) == 3
+
+
+def test_unparse_batch_documents_do_not_share_state():
+    namespaces = {"http://a/": "a"}
+    objects = [{"http://a/:r": {"x": str(i)}} for i in range(2)]
+    result = xmltodict_rs.unparse_batch(objects, namespaces=namespaces, full_document=False)
+    assert result == [
+        xmltodict_rs.unparse(obj, namespaces=namespaces, full_document=False) for obj in objects
+    ]
+
+
+def test_unparse_batch_return_bytes():
+    result = xmltodict_rs.unparse_batch([{"a": "é"}], encoding="utf-16", return_bytes=True)
+    assert result == [xmltodict_rs.unparse({"a": "é"}, encoding="utf-16", return_bytes=True)]
+
+
+def test_unparse_batch_empty():
+    assert xmltodict_rs.unparse_batch([]) == []
+
+
+def test_unparse_batch_error_names_the_document():
+    with pytest.raises(ValueError, match="exactly one root") as info:
+        xmltodict_rs.unparse_batch([{"a": "1"}, {"a": "1", "b": "2"}])
+    assert "in document 1" in getattr(info.value, "__notes__", [])
+    with pytest.raises(TypeError):
+        xmltodict_rs.unparse_batch([{"a": "1"}, "<a/>"])
+
+
+# Serializer tests
+
+
+def test_serializer_matches_unparse():
+    serializer = xmltodict_rs.Serializer()
+    objects = SIMPLE_OBJECTS + ATTRIBUTE_OBJECTS + LIST_OBJECTS
+    for obj in objects * 2:
+        assert serializer.unparse(obj) == xmltodict_rs.unparse(obj)
+
+
+def test_serializer_options():
+    serializer = xmltodict_rs.Serializer(full_document=False, pretty=True, indent="  ")
+    assert serializer.unparse({"e": {"@id": 1, "n": "x"}}) == '<e id="1">\n  <n>x</n>\n</e>'
+    assert serializer.unparse({"e": None}) == "<e></e>"
+    serializer = xmltodict_rs.Serializer(encoding="utf-16", return_bytes=True)
+    assert serializer.unparse({"a": "é"}) == xmltodict_rs.unparse(
+        {"a": "é"}, encoding="utf-16", return_bytes=True
+    )
+
+
+def test_serializer_hooks():
+    serializer = xmltodict_rs.Serializer(
+        preprocessor=lambda key, value: (key.upper(), value),
+        default=str,
+        full_document=False,
+    )
+    assert serializer.unparse({"a": decimal.Decimal("1.5")}) == "<A>1.5</A>"
+    assert serializer.unparse({"b": uuid.UUID(int=0)}) == f"<B>{uuid.UUID(int=0)}</B>"
+
+
+def test_serializer_errors_leave_it_usable():
+    serializer = xmltodict_rs.Serializer()
+    with pytest.raises(ValueError, match="exactly one root"):
+        serializer.unparse({"a": "1", "b": "2"})
+    with pytest.raises(ValueError):
+        serializer.unparse({"a": {"b": "\x00"}})
+    obj = {"a": {"b": "1"}}
+    assert serializer.unparse(obj) == xmltodict_rs.unparse(obj)
+
+
+def test_serializer_options_are_checked_once():
+    with pytest.raises(TypeError):
+        xmltodict_rs.Serializer("utf-8")
+    with pytest.raises(ValueError):
+        xmltodict_rs.Serializer(invalid_chars="nope")
+    with pytest.raises(ValueError):
+        xmltodict_rs.Serializer(xml_version="2.0")
+
+
+def test_serializer_repr_pickle_and_copy():
+    serializer = xmltodict_rs.Serializer(full_document=False, newl="\n", indent="  ", pretty=True)
+    assert repr(serializer) == "Serializer(full_document=False, pretty=True, indent='  ')"
+    obj = {"e": {"n": "x"}}
+    for clone in (
+        pickle.loads(pickle.dumps(serializer)),
+        copy.copy(serializer),
+        copy.deepcopy(serializer),
+    ):
+        assert repr(clone) == repr(serializer)
+        assert clone.unparse(obj) == serializer.unparse(obj) == "<e>\n  <n>x</n>\n</e>"
+
+
+# Namespace-aware unparsing
+
+NAMESPACES = {"http://d/": None, "http://a/": "a"}
+
+
+def test_unparse_namespaces_roundtrip():
+    xml = '<root xmlns="http://d/" xmlns:a="http://a/"><a:x>t</a:x><y></y></root>'
+    parsed = xmltodict_rs.parse(xml, process_namespaces=True)
+    assert parsed == {"http://d/:root": {"http://a/:x": "t", "http://d/:y": None}}
+
+    result = xmltodict_rs.unparse(parsed, namespaces=NAMESPACES, full_document=False)
+    assert result == xml
+    assert xmltodict_rs.parse(result, process_namespaces=True) == parsed
+
+
+def test_unparse_namespaces_attributes():
+    obj = {"http://a/:r": {"@http://a/:id": "1", "@plain": "2"}}
+    result = xmltodict_rs.unparse(obj, namespaces=NAMESPACES, full_document=False)
+    assert result == '<a:r xmlns="http://d/" xmlns:a="http://a/" a:id="1" plain="2"></a:r>'
+
+
+def test_unparse_namespaces_simple_root():
+    result = xmltodict_rs.unparse(
+        {"http://a/:r": "v"}, namespaces={"http://a/": "a"}, full_document=False
+    )
+    assert result == '<a:r xmlns:a="http://a/">v</a:r>'
+    result = xmltodict_rs.unparse(
+        {"r": None}, namespaces={"http://a/": "a"}, full_document=False, short_empty_elements=True
+    )
+    assert result == '<r xmlns:a="http://a/"/>'
+
+
+def test_unparse_namespaces_keep_explicit_declarations():
+    obj = {"r": {"@xmlns:a": "http://other/", "http://a/:c": ["1", "2"]}}
+    result = xmltodict_rs.unparse(obj, namespaces={"http://a/": "a"}, full_document=False)
+    assert result == '<r xmlns:a="http://other/"><a:c>1</a:c><a:c>2</a:c></r>'
+
+
+def test_unparse_xmlns_dict_expands_into_declarations():
+    obj = {"r": {"@xmlns": {"": "urn:d", "p": "urn:p"}, "@p:k": "v", "p:a": "1"}}
+    result = xmltodict_rs.unparse(obj, full_document=False)
+    assert result == '<r xmlns="urn:d" xmlns:p="urn:p" p:k="v"><p:a>1</p:a></r>'
+    compare_unparse(obj, full_document=False)
+
+
+def test_unparse_xmlns_dict_roundtrip():
+    xml = '<r xmlns="urn:d" xmlns:p="urn:p"><p:a p:k="v">1</p:a></r>'
+    kwargs = {"process_namespaces": True, "namespaces": {"urn:x": "x"}}
+    parsed = xmltodict_rs.parse(xml, **kwargs)
+    assert parsed["urn:d:r"]["@xmlns"] == {"": "urn:d", "p": "urn:p"}
+
+    namespaces = {"urn:d": "", "urn:p": "p"}
+    result = xmltodict_rs.unparse(parsed, namespaces=namespaces, full_document=False)
+    assert result.startswith("<r ") and '<p:a xmlns' in result and 'p:k="v">1</p:a></r>' in result
+    assert xmltodict_rs.parse(result, **kwargs) == parsed
+
+
+def test_unparse_xmlns_mapping_in_attr_dict():
+    obj = {"r": {"#attrs": {"xmlns": types.MappingProxyType({"p": "urn:p"}), "id": "1"}}}
+    result = xmltodict_rs.unparse(obj, attr_dict_key="#attrs", full_document=False)
+    assert result == '<r xmlns:p="urn:p" id="1"></r>'
+
+
+def test_unparse_xmlns_dict_duplicates_explicit_declaration():
+    obj = {"r": {"@xmlns": {"p": "urn:p"}, "@xmlns:p": "urn:q"}}
+    with pytest.raises(ValueError, match='duplicate attribute "xmlns:p"'):
+        xmltodict_rs.unparse(obj, full_document=False)
+    with pytest.warns(UserWarning, match='duplicate attribute "xmlns:p"'):
+        result = xmltodict_rs.unparse(obj, duplicate_attributes="last", full_document=False)
+    assert result == '<r xmlns:p="urn:q"></r>'
+
+
+def test_unparse_namespaces_prefixed_names_unchanged():
+    obj = {"soap:Envelope": {"@xml:lang": "en", "soap:Body": None}}
+    result = xmltodict_rs.unparse(obj, namespaces={"http://a/": "a"}, full_document=False)
+    expected = (
+        '<soap:Envelope xmlns:a="http://a/" xml:lang="en"><soap:Body></soap:Body></soap:Envelope>'
+    )
+    assert result == expected
+
+
+@pytest.mark.parametrize(
+    ("obj", "kwargs", "message"),
+    [
+        ({"r": {"http://x/:b": None}}, {}, 'element "http://x/:b" is in the namespace "http://x/"'),
+        ({"urn:d:r": None}, {"namespaces": {"urn:x": "x"}}, 'element "urn:d:r" is in the'),
+        ({"r": {"@http://x/:id": "1"}}, {}, 'attribute "http://x/:id" is in the namespace'),
+        ({"a|r": None}, {"namespace_separator": "|"}, 'element "a|r" is in the namespace "a"'),
+    ],
+)
+def test_unparse_rejects_unmapped_namespaces(obj, kwargs, message):
+    with pytest.raises(ValueError, match=re.escape(message)):
+        xmltodict_rs.unparse(obj, full_document=False, **kwargs)
+
+
+def test_unparse_namespaces_custom_separator():
+    obj = {"http://a/|r": {"http://a/|c": "1"}}
+    result = xmltodict_rs.unparse(
+        obj, namespaces={"http://a/": "a"}, namespace_separator="|", full_document=False
+    )
+    assert result == '<a:r xmlns:a="http://a/"><a:c>1</a:c></a:r>'
+
+
+def test_unparse_namespaces_preprocessor_sees_short_names():
+    seen = []
+
+    def preprocessor(key, value):
+        seen.append(key)
+        return key, value
+
+    xmltodict_rs.unparse(
+        {"http://a/:r": {"http://a/:c": "1"}},
+        namespaces={"http://a/": "a"},
+        preprocessor=preprocessor,
+    )
+    assert seen == ["a:r", "a:c"]
+
+
+def test_unparse_iter_namespaces():
+    obj = {"http://a/:r": {"http://a/:c": "1"}}
+    kwargs = {"namespaces": {"http://a/": "a"}}
+    assert "".join(xmltodict_rs.unparse_iter(obj, **kwargs)) == xmltodict_rs.unparse(obj, **kwargs)
+
+
+def test_unparse_namespaces_rejects_non_string_prefix():
+    with pytest.raises(TypeError):
+        xmltodict_rs.unparse({"r": None}, namespaces={"http://a/": 1})
+
+
+# DOCTYPE
+
+
+def test_unparse_doctype_key_roundtrip():
+    xml = (
+        '<?xml version="1.0" encoding="utf-8"?>\n'
+        '<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "xhtml1-strict.dtd">\n'
+        "<html><body>x</body></html>"
+    )
+    parsed = xmltodict_rs.parse(xml, process_doctype=True)
+    assert xmltodict_rs.unparse(parsed) == xml
+    assert "".join(xmltodict_rs.unparse_iter(parsed)) == xml
+
+
+def test_unparse_doctype_argument():
+    result = xmltodict_rs.unparse({"r": "1"}, doctype="r SYSTEM 'r.dtd'", full_document=False)
+    assert result == "<!DOCTYPE r SYSTEM 'r.dtd'>\n<r>1</r>"
+
+
+def test_unparse_doctype_argument_overrides_key():
+    data = {"#doctype": "old", "r": None}
+    assert xmltodict_rs.unparse(data, doctype="new", full_document=False) == (
+        "<!DOCTYPE new>\n<r></r>"
+    )
+
+
+def test_unparse_doctype_custom_key():
+    data = {"!dt": "r", "r": None}
+    assert xmltodict_rs.unparse(data, doctype_key="!dt", full_document=False) == (
+        "<!DOCTYPE r>\n<r></r>"
+    )
+
+
+def test_unparse_doctype_key_is_not_a_root():
+    with pytest.raises(ValueError, match="exactly one root"):
+        xmltodict_rs.unparse({"#doctype": "r"})
+
+
+# Default serializer
+
+
+def _default(value):
+    if isinstance(value, enum.Enum):
+        return value.value
+    if dataclasses.is_dataclass(value):
+        return dataclasses.asdict(value)
+    if isinstance(value, uuid.UUID):
+        return value.hex
+    raise TypeError(f"cannot serialize {type(value).__name__}")
+
+
+@dataclasses.dataclass
+class Point:
+    x: int
+    y: int
+
+
+class Level(enum.IntEnum):
+    LOW = 1
+
+
+def test_unparse_default_converts_values():
+    data = {
+        "r": {
+            "@id": uuid.UUID(int=1),
+            "level": Level.LOW,
+            "#text": Color.RED,
+        }
+    }
+    result = xmltodict_rs.unparse(data, full_document=False, default=_default)
+    assert result == '<r id="00000000000000000000000000000001">red<level>1</level></r>'
+
+
+def test_unparse_default_result_can_be_a_dict():
+    result = xmltodict_rs.unparse({"p": Point(1, 2)}, full_document=False, default=_default)
+    assert result == "<p><x>1</x><y>2</y></p>"
+
+
+@pytest.mark.parametrize("value", [Color.RED, [Color.RED]])
+def test_unparse_default_receives_str_enums(value):
+    result = xmltodict_rs.unparse({"c": value}, full_document=False, default=_default)
+    assert result == "<c>red</c>"
+
+
+def test_unparse_default_skips_native_values():
+    seen = []
+    data = {"r": {"@a": 1, "b": 2.5, "c": True, "d": None, "e": ["x"], "f": ("y",)}}
+    result = xmltodict_rs.unparse(data, full_document=False, default=seen.append)
+    assert seen == []
+    assert result == xmltodict_rs.unparse(data, full_document=False)
+
+
+def test_unparse_default_errors_propagate():
+    with pytest.raises(TypeError, match="cannot serialize object"):
+        xmltodict_rs.unparse({"r": object()}, default=_default)
+
+
+def test_unparse_without_default_uses_str():
+    result = xmltodict_rs.unparse({"r": decimal.Decimal("1.50")}, full_document=False)
+    assert result == "<r>1.50</r>"
+
+
+def test_unparse_iter_default():
+    data = {"r": {"p": Point(1, 2), "@id": uuid.UUID(int=2)}}
+    assert "".join(xmltodict_rs.unparse_iter(data, default=_default)) == xmltodict_rs.unparse(
+        data, default=_default
+    )
+
+
+# Mappings and sequences
+
+
+class FrozenDict(Mapping):
+    def __init__(self, *args, **kwargs):
+        self._data = dict(*args, **kwargs)
+
+    def __getitem__(self, key):
+        return self._data[key]
+
+    def __iter__(self):
+        return iter(self._data)
+
+    def __len__(self):
+        return len(self._data)
+
+
+@pytest.mark.parametrize("mapping", [FrozenDict, types.MappingProxyType])
+def test_unparse_accepts_mappings(mapping):
+    data = mapping({"r": mapping({"@id": "1", "a": mapping({"#text": "x"}), "b": None})})
+    expected = xmltodict_rs.unparse({"r": {"@id": "1", "a": {"#text": "x"}, "b": None}})
+    assert xmltodict_rs.unparse(data) == expected
+    assert "".join(xmltodict_rs.unparse_iter(data)) == expected
+
+
+def test_unparse_mapping_in_a_list():
+    data = {"r": {"i": [FrozenDict(a="1"), "x", FrozenDict()]}}
+    result = xmltodict_rs.unparse(data, full_document=False)
+    assert result == "<r><i><a>1</a></i><i>x</i><i></i></r>"
+
+
+def test_unparse_attr_dict_key_accepts_mappings():
+    data = {"r": {"#attrs": FrozenDict(id="1")}}
+    assert xmltodict_rs.unparse(data, full_document=False, attr_dict_key="#attrs") == (
+        '<r id="1"></r>'
+    )
+
+
+@pytest.mark.parametrize(
+    "items",
+    [("a", "b"), ["a", "b"], (item for item in "ab"), iter(["a", "b"])],
+    ids=["tuple", "list", "generator", "iterator"],
+)
+def test_unparse_sequences_repeat_elements(items):
+    result = xmltodict_rs.unparse({"r": {"i": items}}, full_document=False)
+    assert result == "<r><i>a</i><i>b</i></r>"
+
+
+def test_unparse_rejects_non_mapping_documents():
+    with pytest.raises(TypeError):
+        xmltodict_rs.unparse([("r", "x")])
+
+
+# Dates and decimals
+
+_WHEN = datetime.datetime(2024, 1, 2, 3, 4, 5, tzinfo=datetime.timezone.utc)
+
+
+@pytest.mark.parametrize(
+    "value",
+    [_WHEN, datetime.datetime(2024, 1, 2), datetime.date(2024, 1, 2), decimal.Decimal("1.50")],
+)
+def test_unparse_dates_and_decimals_match_xmltodict(value):
+    compare_unparse({"r": {"@at": value, "v": value}})
+
+
+@pytest.mark.parametrize(
+    ("datetime_format", "expected"),
+    [
+        (None, "2024-01-02 03:04:05+00:00"),
+        ("iso", "2024-01-02T03:04:05+00:00"),
+        ("%d.%m.%Y", "02.01.2024"),
+    ],
+)
+def test_unparse_datetime_format(datetime_format, expected):
+    data = {"r": {"@at": _WHEN, "#text": _WHEN, "d": _WHEN.date()}}
+    result = xmltodict_rs.unparse(data, full_document=False, datetime_format=datetime_format)
+    date = _WHEN.date().isoformat() if datetime_format != "%d.%m.%Y" else "02.01.2024"
+    assert result == f'<r at="{expected}">{expected}<d>{date}</d></r>'
+
+
+def test_unparse_dates_and_decimals_skip_default():
+    seen = []
+    data = {"r": {"@at": _WHEN, "price": decimal.Decimal("1.50")}}
+    xmltodict_rs.unparse(data, default=seen.append)
+    assert seen == []
+
+
+def test_unparse_iter_datetime_format():
+    data = {"r": {"@at": _WHEN, "d": datetime.date(2024, 1, 2)}}
+    assert "".join(
+        xmltodict_rs.unparse_iter(data, datetime_format="iso")
+    ) == xmltodict_rs.unparse(data, datetime_format="iso")
+
+
+# Depth-limited pretty printing
+
+_NESTED = {"r": {"a": {"b": {"c": ["1", "2"]}}, "d": "x"}}
+
+
+def test_unparse_pretty_depth():
+    result = xmltodict_rs.unparse(
+        _NESTED, full_document=False, pretty=True, indent="  ", pretty_depth=2
+    )
+    assert result == "<r>\n  <a>\n    <b><c>1</c><c>2</c></b>\n  </a>\n  <d>x</d>\n</r>"
+
+
+def test_unparse_pretty_depth_zero_is_compact():
+    result = xmltodict_rs.unparse(_NESTED, pretty=True, pretty_depth=0)
+    assert result == xmltodict_rs.unparse(_NESTED)
+
+
+def test_unparse_pretty_depth_beyond_document_matches_pretty():
+    assert xmltodict_rs.unparse(_NESTED, pretty=True, pretty_depth=10) == xmltodict_rs.unparse(
+        _NESTED, pretty=True
+    )
+
+
+def test_unparse_pretty_depth_requires_pretty():
+    assert xmltodict_rs.unparse(_NESTED, pretty_depth=1) == xmltodict_rs.unparse(_NESTED)
+
+
+def test_unparse_iter_pretty_depth():
+    kwargs = {"pretty": True, "pretty_depth": 1}
+    assert "".join(xmltodict_rs.unparse_iter(_NESTED, **kwargs)) == xmltodict_rs.unparse(
+        _NESTED, **kwargs
+    )
+
+
+# XML declaration
+
+
+@pytest.mark.parametrize(
+    ("standalone", "expected"),
+    [(None, ""), (True, ' standalone="yes"'), (False, ' standalone="no"')],
+)
+def test_unparse_standalone(standalone, expected):
+    result = xmltodict_rs.unparse({"r": "x"}, standalone=standalone)
+    assert result == f'<?xml version="1.0" encoding="utf-8"{expected}?>\n<r>x</r>'
+
+
+def test_unparse_xml_version():
+    result = xmltodict_rs.unparse({"r": None}, xml_version="1.1", encoding="latin-1")
+    assert result == '<?xml version="1.1" encoding="latin-1"?>\n<r></r>'
+
+
+@pytest.mark.parametrize("version", ["", "1", "1.", "2.0", "1.0a", '1.0"'])
+def test_unparse_rejects_invalid_xml_version(version):
+    with pytest.raises(ValueError, match="xml_version"):
+        xmltodict_rs.unparse({"r": None}, xml_version=version)
+
+
+def test_unparse_declaration_options_ignored_without_full_document():
+    result = xmltodict_rs.unparse(
+        {"r": None}, full_document=False, standalone=True, xml_version="1.1"
+    )
+    assert result == "<r></r>"
+
+
+def test_unparse_iter_declaration_options():
+    data = {"r": "x"}
+    kwargs = {"standalone": False, "xml_version": "1.1"}
+    assert "".join(xmltodict_rs.unparse_iter(data, **kwargs)) == xmltodict_rs.unparse(
+        data, **kwargs
+    )
+
+
+# Attribute control characters
+
+
+def test_unparse_escapes_attribute_control_chars():
+    data = {"r": {"@a": "x\ny\tz\r", "#text": "t\nq"}}
+    assert xmltodict_rs.unparse(data) == xmltodict.unparse(data)
+    assert xmltodict_rs.unparse(data, full_document=False) == '<r a="x&#10;y&#9;z&#13;">t\nq</r>'
+
+
+def test_unparse_attribute_control_chars_survive_roundtrip():
+    data = {"r": {"@a": "line1\nline2\tend"}}
+    assert xmltodict_rs.parse(xmltodict_rs.unparse(data)) == data
+    assert "".join(xmltodict_rs.unparse_iter(data)) == xmltodict_rs.unparse(data)
+
+
+def test_unparse_escape_control_chars_disabled():
+    data = {"r": {"@a": "x\ny\tz"}}
+    result = xmltodict_rs.unparse(data, full_document=False, escape_control_chars=False)
+    assert result == '<r a="x\ny\tz"></r>'
+
+
+def test_unparse_escape_apos():
+    data = {"r": {"@a": "it's \"quoted\" & <odd>", "#text": "it's"}}
+    assert xmltodict_rs.unparse(data, full_document=False) == (
+        '<r a="it\'s &quot;quoted&quot; &amp; &lt;odd&gt;">it\'s</r>'
+    )
+    result = xmltodict_rs.unparse(data, full_document=False, escape_apos=True)
+    assert result == '<r a="it&apos;s &quot;quoted&quot; &amp; &lt;odd&gt;">it\'s</r>'
+    assert xmltodict_rs.parse(result) == data
+    serializer = xmltodict_rs.Serializer(full_document=False, escape_apos=True)
+    assert serializer.unparse(data) == result
+
+
+# Invalid XML characters
+
+
+@pytest.mark.parametrize("char", ["\x00", "\x0b", "\x1f", "\ufffe", "\uffff"])
+def test_unparse_rejects_invalid_chars_in_text(char):
+    with pytest.raises(ValueError, match="invalid XML character"):
+        xmltodict_rs.unparse({"r": f"a{char}b"})
+
+
+def test_unparse_rejects_invalid_chars_in_attributes():
+    with pytest.raises(ValueError, match=r"invalid XML character U\+0001"):
+        xmltodict_rs.unparse({"r": {"@a": "\x01"}})
+
+
+def test_unparse_iter_rejects_invalid_chars():
+    with pytest.raises(ValueError, match="invalid XML character"):
+        "".join(xmltodict_rs.unparse_iter({"r": {"#text": "\x00"}}))
+
+
+def test_unparse_strips_invalid_chars():
+    data = {"r": {"@a": "x\x00y", "#text": "a\x0bb", "c": ["\x1f", "d\ufffe"]}}
+    result = xmltodict_rs.unparse(data, full_document=False, invalid_chars="strip")
+    assert result == '<r a="xy">ab<c></c><c>d</c></r>'
+
+
+def test_unparse_replaces_invalid_chars():
+    data = {"r": {"@a": "x\x00", "#text": "a\x0bb"}}
+    result = xmltodict_rs.unparse(data, full_document=False, invalid_chars="replace")
+    assert result == '<r a="x\ufffd">a\ufffdb</r>'
+    assert xmltodict_rs.parse(result) == {"r": {"@a": "x\ufffd", "#text": "a\ufffdb"}}
+
+
+@pytest.mark.parametrize(
+    "data", [{"a": "\ud800"}, {"a": {"@b": "x\udfff"}}, {"a": {"#text": "\ud800"}}]
+)
+def test_unparse_rejects_lone_surrogates(data):
+    with pytest.raises(ValueError, match=r"invalid XML character U\+D[8F]"):
+        xmltodict_rs.unparse(data)
+
+
+def test_unparse_strips_or_replaces_lone_surrogates():
+    data = {"r": {"@a": "x\ud800", "#text": "a\udfffb"}}
+    result = xmltodict_rs.unparse(data, full_document=False, invalid_chars="strip")
+    assert result == '<r a="x">ab</r>'
+    result = xmltodict_rs.unparse(data, full_document=False, invalid_chars="replace")
+    assert result == '<r a="x\ufffd">a\ufffdb</r>'
+
+
+def test_unparse_keeps_valid_chars():
+    text = "tab\tnl\ncr\r \ud7ff \ue000 \ufffd \U0001f600"
+    result = xmltodict_rs.unparse({"r": text}, full_document=False)
+    assert result == f"<r>{text}</r>"
+
+
+def test_unparse_unknown_invalid_chars_mode():
+    with pytest.raises(ValueError, match="invalid_chars must be"):
+        xmltodict_rs.unparse({"r": "x"}, invalid_chars="ignore")
+
+
+# escape_non_ascii
+
+
+def test_unparse_escape_non_ascii():
+    data = {"r": {"@a": "caf\xe9 <", "#text": "na\xefve \U0001f600 & \u4e16"}}
+    result = xmltodict_rs.unparse(data, escape_non_ascii=True)
+    assert result == (
+        '<?xml version="1.0" encoding="utf-8"?>\n'
+        '<r a="caf&#233; &lt;">na&#239;ve &#128512; &amp; &#19990;</r>'
+    )
+    assert result.encode("latin-1").decode("latin-1") == result
+    assert xmltodict_rs.parse(result) == data
+
+
+def test_unparse_escape_non_ascii_to_latin1_stream():
+    output = io.BytesIO()
+    xmltodict_rs.unparse({"r": "\u20ac5"}, output, encoding="latin-1", escape_non_ascii=True)
+    assert output.getvalue() == b'<?xml version="1.0" encoding="latin-1"?>\n<r>&#8364;5</r>'
+
+
+def test_unparse_escape_non_ascii_leaves_cdata_for_ascii_only():
+    data = {"r": {"a": "x < y", "b": "\xe9 < y"}}
+    result = xmltodict_rs.unparse(
+        data, full_document=False, use_cdata=True, escape_non_ascii=True
+    )
+    assert result == "<r><a><![CDATA[x < y]]></a><b>&#233; &lt; y</b></r>"
+
+
+def test_unparse_iter_and_json_to_xml_escape_non_ascii():
+    parts = xmltodict_rs.unparse_iter({"r": {"i": ["\xe9", "\xfc"]}}, escape_non_ascii=True)
+    assert "".join(parts).endswith("<r><i>&#233;</i><i>&#252;</i></r>")
+    result = xmltodict_rs.json_to_xml('{"r": "\\u00e9"}', escape_non_ascii=True)
+    assert result.endswith("<r>&#233;</r>")
+
+
+def test_unparse_escape_non_ascii_off_by_default():
+    assert xmltodict_rs.unparse({"r": "\xe9"}, full_document=False) == "<r>\xe9</r>"
+
+
+# return_bytes
+
+
+def test_unparse_return_bytes_latin1():
+    data = {"r": {"@a": "\xe9", "#text": "\u20ac \U0001f600 \xff"}}
+    result = xmltodict_rs.unparse(data, encoding="iso-8859-1", return_bytes=True)
+    assert result == (
+        b'<?xml version="1.0" encoding="iso-8859-1"?>\n<r a="\xe9">&#8364; &#128512; \xff</r>'
+    )
+    assert xmltodict_rs.parse(result) == data
+
+
+@pytest.mark.parametrize("encoding", ["utf-8", "cp1251", "shift_jis", "ascii", "latin-1"])
+def test_unparse_return_bytes_matches_xmlcharrefreplace(encoding):
+    data = {"r": {"@a": "\u0416", "#text": "\u65e5\u672c \xe9 \U0001f600"}}
+    text = xmltodict_rs.unparse(data, encoding=encoding)
+    result = xmltodict_rs.unparse(data, encoding=encoding, return_bytes=True)
+    assert result == text.encode(encoding, "xmlcharrefreplace")
+    assert xmltodict_rs.parse(result) == data
+
+
+def test_unparse_return_bytes_splits_cdata_around_unencodable_characters():
+    data = {"r": "a \u20ac b"}
+    result = xmltodict_rs.unparse(
+        data, encoding="latin-1", return_bytes=True, full_document=False, use_cdata=True
+    )
+    assert result == b"<r><![CDATA[a ]]>&#8364;<![CDATA[ b]]></r>"
+    assert xmltodict_rs.parse(result) == data
+
+
+@pytest.mark.parametrize(
+    ("data", "kind"),
+    [
+        ({"\u20ac": "x"}, "element name"),
+        ({"r": {"@\u20ac": "x"}}, "attribute name"),
+        ({"r": {"#comment": "\u20ac"}}, "comment"),
+        ({"r": {"#pi": "p \u20ac"}}, "processing instruction"),
+    ],
+)
+def test_unparse_refuses_unencodable_names_and_markup(data, kind):
+    with pytest.raises(ValueError, match=f"{kind} .* which latin-1 cannot encode"):
+        xmltodict_rs.unparse(data, encoding="latin-1", return_bytes=True)
+    with pytest.raises(ValueError, match=kind):
+        xmltodict_rs.unparse(data, io.BytesIO(), encoding="latin-1")
+    with pytest.raises(ValueError, match=kind):
+        xmltodict_rs.unparse(data, io.BytesIO(), encoding="cp437")
+    # Text output is not encoded, so it holds any character.
+    assert "\u20ac" in xmltodict_rs.unparse(data, encoding="latin-1")
+
+
+def test_unparse_return_bytes_utf16():
+    result = xmltodict_rs.unparse({"r": "\U0001f600"}, encoding="utf-16", return_bytes=True)
+    assert result.startswith(b"\xff\xfe<\x00?\x00")
+    assert result.decode("utf-16").endswith("<r>\U0001f600</r>")
+    assert xmltodict_rs.parse(result) == {"r": "\U0001f600"}
+
+
+@pytest.mark.parametrize(
+    ("encoding", "bom"),
+    [("utf-16", b"\xff\xfe"), ("utf-16-le", b""), ("UTF-16BE", b""), ("utf_16", b"\xff\xfe")],
+)
+def test_unparse_return_bytes_utf16_byte_orders(encoding, bom):
+    data = {"r": {"@a": "\u0416", "#text": "x"}}
+    result = xmltodict_rs.unparse(data, encoding=encoding, return_bytes=True)
+    text = xmltodict_rs.unparse(data, encoding=encoding)
+    assert text.startswith(f'<?xml version="1.0" encoding="{encoding}"?>')
+    assert result == bom + text.encode("utf-16-be" if "BE" in encoding else "utf-16-le")
+    assert xmltodict_rs.parse(result) == data
+
+
+def test_unparse_utf16_stream_has_one_bom():
+    class Recorder:
+        def __init__(self) -> None:
+            self.chunks = []
+
+        def write(self, data):
+            self.chunks.append(data)
+
+    obj = {"root": {"item": [{"@id": str(i), "#text": "\u0416" * 50} for i in range(5000)]}}
+    recorder = Recorder()
+    xmltodict_rs.unparse(obj, output=recorder, encoding="utf-16")
+    assert len(recorder.chunks) > 1
+    assert recorder.chunks[0].startswith(b"\xff\xfe")
+    assert not any(chunk.startswith(b"\xff\xfe") for chunk in recorder.chunks[1:])
+    result = b"".join(recorder.chunks)
+    assert result == xmltodict_rs.unparse(obj, encoding="utf-16", return_bytes=True)
+    assert xmltodict_rs.parse(result) == xmltodict_rs.parse(xmltodict_rs.unparse(obj))
+
+
+def test_unparse_stream_falls_back_to_python_codecs():
+    output = io.BytesIO()
+    xmltodict_rs.unparse({"r": "\xe9\u20ac"}, output, encoding="cp437", full_document=False)
+    assert output.getvalue() == b"<r>\x82&#8364;</r>"
+
+
+def test_unparse_return_bytes_errors():
+    with pytest.raises(LookupError, match="unknown encoding: bogus"):
+        xmltodict_rs.unparse({"r": "x"}, encoding="bogus", return_bytes=True)
+    with pytest.raises(ValueError, match="return_bytes cannot be combined with output"):
+        xmltodict_rs.unparse({"r": "x"}, io.BytesIO(), return_bytes=True)
+
+
+# Output buffer size
+
+
+def test_output_does_not_depend_on_buffer_size():
+    rows = [{"@id": str(i), "name": f"name {i}", "tags": {"tag": ["a", "b"]}} for i in range(5000)]
+    data = {"root": {"row": rows, "empty": [], "nested": [[1, 2]], "n": 1.5}}
+    result = xmltodict_rs.unparse(data)
+    assert xmltodict_rs.parse(result)["root"]["row"][4999]["name"] == "name 4999"
+    for size in [0, 1, 1 << 20]:
+        assert xmltodict_rs.unparse(data, initial_buffer_size=size) == result
+
+
+def test_initial_buffer_size_with_other_outputs():
+    data = {"a": {"b": ["1", "2"]}}
+    expected = xmltodict_rs.unparse(data)
+    output = io.StringIO()
+    xmltodict_rs.unparse(data, output, initial_buffer_size=1024)
+    assert output.getvalue() == expected
+    assert xmltodict_rs.unparse_batch([data] * 3, initial_buffer_size=16) == [expected] * 3
+    serializer = xmltodict_rs.Serializer(initial_buffer_size=4096)
+    assert [serializer.unparse(data) for _ in range(3)] == [expected] * 3
+
+
+def test_initial_buffer_size_must_be_a_size():
+    with pytest.raises(OverflowError):
+        xmltodict_rs.unparse({"a": "1"}, initial_buffer_size=-1)
+    with pytest.raises(TypeError):
+        xmltodict_rs.unparse({"a": "1"}, initial_buffer_size="big")
+
+
+# CDATA sections
+
+
+def test_unparse_use_cdata_wraps_all_text():
+    data = {"r": {"@a": "<1>", "#text": "a < b", "c": ["x & y", None], "d": 5}}
+    result = xmltodict_rs.unparse(data, full_document=False, use_cdata=True)
+    assert result == (
+        '<r a="&lt;1&gt;"><![CDATA[a < b]]><c><![CDATA[x & y]]></c><c></c>'
+        "<d><![CDATA[5]]></d></r>"
+    )
+    assert xmltodict_rs.parse(result) == xmltodict_rs.parse(xmltodict_rs.unparse(data))
+
+
+def test_unparse_cdata_for_keys():
+    data = {"r": {"script": "if (a < b) {}", "p": "a < b"}}
+    result = xmltodict_rs.unparse(data, full_document=False, cdata_for_keys=["script"])
+    assert result == "<r><script><![CDATA[if (a < b) {}]]></script><p>a &lt; b</p></r>"
+
+
+def test_unparse_cdata_for_keys_accepts_any_iterable():
+    data = {"r": {"a": "1", "b": "2"}}
+    for keys in ({"a"}, ("a",), iter(["a"])):
+        result = xmltodict_rs.unparse(data, full_document=False, cdata_for_keys=keys)
+        assert result == "<r><a><![CDATA[1]]></a><b>2</b></r>"
+
+
+def test_unparse_cdata_splits_terminator():
+    result = xmltodict_rs.unparse({"r": "x]]>y"}, full_document=False, use_cdata=True)
+    assert result == "<r><![CDATA[x]]]]><![CDATA[>y]]></r>"
+    assert xmltodict_rs.parse(result) == {"r": "x]]>y"}
+
+
+def test_unparse_cdata_rejects_invalid_chars():
+    with pytest.raises(ValueError, match="invalid XML character"):
+        xmltodict_rs.unparse({"r": "\x00"}, use_cdata=True)
+
+
+def test_unparse_cdata_for_keys_rejects_string():
+    with pytest.raises(TypeError, match="not a string"):
+        xmltodict_rs.unparse({"r": "x"}, cdata_for_keys="r")
+
+
+def test_unparse_iter_cdata():
+    data = {"r": {"s": "<x>"}}
+    expected = xmltodict_rs.unparse(data, cdata_for_keys=["s"])
+    assert "".join(xmltodict_rs.unparse_iter(data, cdata_for_keys=["s"])) == expected
+    assert "<s><![CDATA[<x>]]></s>" in expected
+
+
+# Comments
+
+
+def test_unparse_writes_comments():
+    data = {"r": {"#comment": "note", "x": "1"}}
+    assert xmltodict_rs.unparse(data, full_document=False) == "<r><!--note--><x>1</x></r>"
+
+
+def test_unparse_writes_comment_lists():
+    data = {"r": {"#comment": ["a", "b"], "x": "1"}}
+    assert xmltodict_rs.unparse(data, full_document=False) == "<r><!--a--><!--b--><x>1</x></r>"
+
+
+def test_unparse_comments_roundtrip():
+    xml = "<r><!-- a --><x>1</x><!-- b --></r>"
+    parsed = xmltodict_rs.parse(xml, process_comments=True)
+    result = xmltodict_rs.unparse(parsed, full_document=False)
+    assert xmltodict_rs.parse(result, process_comments=True) == parsed
+
+
+def test_unparse_comments_pretty():
+    data = {"r": {"#comment": ["a", "b"], "x": "1"}}
+    result = xmltodict_rs.unparse(data, full_document=False, pretty=True, indent="  ")
+    assert result == "<r>\n  <!--a-->\n  <!--b-->\n  <x>1</x>\n</r>"
+
+
+def test_unparse_comment_custom_key():
+    data = {"r": {"!c": "a", "x": "b"}}
+    result = xmltodict_rs.unparse(data, full_document=False, comment_key="!c")
+    assert result == "<r><!--a--><x>b</x></r>"
+    # Under another comment_key, "#comment" is an element name, which XML does not allow.
+    with pytest.raises(ValueError, match='element name "#comment" is not a valid XML name'):
+        xmltodict_rs.unparse({"r": {"#comment": "b"}}, comment_key="!c")
+
+
+def test_unparse_top_level_comment_is_not_a_root():
+    data = {"#comment": "generated", "r": "1"}
+    assert xmltodict_rs.unparse(data) == (
+        '<?xml version="1.0" encoding="utf-8"?>\n<!--generated--><r>1</r>'
+    )
+    with pytest.raises(ValueError, match="exactly one root"):
+        xmltodict_rs.unparse({"#comment": "x"})
+
+
+def test_unparse_document_level_comments_roundtrip():
+    xml = "<!--a--><r>x</r>"
+    parsed = xmltodict_rs.parse(xml, process_comments=True)
+    assert parsed == {"#comment": "a", "r": "x"}
+    assert xmltodict_rs.unparse(parsed, full_document=False) == xml
+    parsed = xmltodict_rs.parse("<r>x</r><!--b--><!--c-->", process_comments=True)
+    assert parsed == {"r": "x", "#comment": ["b", "c"]}
+    assert xmltodict_rs.unparse(parsed) == (
+        '<?xml version="1.0" encoding="utf-8"?>\n<r>x</r><!--b--><!--c-->'
+    )
+    xml = "<!--a--><?p?><r>x</r><!--b--><?q?>"
+    assert xmltodict_rs.parse(xml, process_comments=True, process_pis=True) == {
+        "#comment": ["a", "b"],
+        "#pi": ["p", "q"],
+        "r": "x",
+    }
+    parsed = xmltodict_rs.parse(
+        xml, process_comments=True, process_pis=True, preserve_sibling_order=True
+    )
+    assert parsed == {
+        "#comment": ["a", "b"],
+        "#pi": ["p", "q"],
+        "r": "x",
+        "#order": ["#comment", "#pi", "r", "#comment", "#pi"],
+    }
+    assert xmltodict_rs.unparse(parsed, full_document=False) == xml
+    assert xmltodict_rs.roundtrip(xml) == xml
+    with pytest.raises(ValueError, match="#order of the document leaves out"):
+        xmltodict_rs.unparse({"r": "x", "#comment": "a", "#order": ["r"]})
+
+
+def test_unparse_rejects_double_hyphen_in_comment():
+    with pytest.raises(ValueError, match="comment must not contain"):
+        xmltodict_rs.unparse({"r": {"#comment": "a -- b"}})
+    with pytest.raises(ValueError, match="comment must not contain"):
+        xmltodict_rs.unparse({"r": {"#comment": "a-"}})
+
+
+def test_unparse_comments_in_mixed_content():
+    xml = "<p>a <!--c--><b>x</b> d</p>"
+    parsed = xmltodict_rs.parse(xml, process_comments=True, preserve_mixed_content=True)
+    result = xmltodict_rs.unparse(parsed, full_document=False, preserve_mixed_content=True)
+    assert result == xml
+
+
+def test_unparse_iter_comments():
+    data = {"r": {"#comment": ["a", "b"]}}
+    assert "".join(xmltodict_rs.unparse_iter(data)) == xmltodict_rs.unparse(data)
+
+
+# Processing instructions
+
+
+def test_unparse_writes_processing_instructions():
+    data = {"#pi": 'xml-stylesheet href="a.xsl"', "r": {"#pi": ["a b", "c"], "x": "1"}}
+    assert xmltodict_rs.unparse(data) == (
+        '<?xml version="1.0" encoding="utf-8"?>\n'
+        '<?xml-stylesheet href="a.xsl"?><r><?a b?><?c?><x>1</x></r>'
+    )
+
+
+def test_unparse_pi_custom_key():
+    data = {"r": {"!pi": "a b", "x": "c"}}
+    result = xmltodict_rs.unparse(data, full_document=False, pi_key="!pi")
+    assert result == "<r><?a b?><x>c</x></r>"
+    with pytest.raises(ValueError, match='element name "#pi" is not a valid XML name'):
+        xmltodict_rs.unparse({"r": {"#pi": "c"}}, pi_key="!pi")
+
+
+@pytest.mark.parametrize("text", ["", " a", "a ?> b"])
+def test_unparse_rejects_malformed_pi(text):
+    with pytest.raises(ValueError, match="processing instruction must"):
+        xmltodict_rs.unparse({"r": {"#pi": text}})
+
+
+def test_unparse_iter_processing_instructions():
+    data = {"#pi": "p", "r": {"#pi": ["a", "b"]}}
+    assert "".join(xmltodict_rs.unparse_iter(data)) == xmltodict_rs.unparse(data)
+
+
+# Names
+
+
+@pytest.mark.parametrize(
+    ("obj", "message"),
+    [
+        ({"two words": "x"}, 'element name "two words"'),
+        ({"r": {"1st": "x"}}, 'element name "1st"'),
+        ({"r": {"a<b": None}}, 'element name "a<b"'),
+        ({"": "x"}, 'element name ""'),
+        ({"r": {"@a b": "1"}}, 'attribute name "a b"'),
+        ({"r": {"@x=": "1"}}, 'attribute name "x="'),
+        ({"r": {"@xmlns": {"p q": "urn:p"}}}, 'attribute name "xmlns:p q"'),
+    ],
+)
+def test_unparse_rejects_invalid_names(obj, message):
+    with pytest.raises(ValueError, match=re.escape(f"{message} is not a valid XML name")):
+        xmltodict_rs.unparse(obj, full_document=False)
+
+
+def test_unparse_accepts_xml_names():
+    obj = {"_r.1": {"@xml:lang": "en", "@d-e": "1", "\u00e9l\u00e9ment": None}}
+    result = xmltodict_rs.unparse(obj, full_document=False)
+    assert result == '<_r.1 xml:lang="en" d-e="1"><\u00e9l\u00e9ment></\u00e9l\u00e9ment></_r.1>'
+
+
+# Attribute dicts
+
+
+def test_unparse_attr_dict_key():
+    data = {"a": {"#attrs": {"id": "1", "k": "<"}, "#text": "x", "b": {"#attrs": {"c": "2"}}}}
+    result = xmltodict_rs.unparse(data, full_document=False, attr_dict_key="#attrs")
+    assert result == '<a id="1" k="&lt;">x<b c="2"></b></a>'
+
+
+def test_unparse_attr_dict_key_keeps_prefixed_attributes():
+    data = {"a": {"@x": "1", "#attrs": {"y": "2"}}}
+    result = xmltodict_rs.unparse(data, full_document=False, attr_dict_key="#attrs")
+    assert result == '<a x="1" y="2"></a>'
+    with pytest.raises(ValueError, match='element name "#attrs" is not a valid XML name'):
+        xmltodict_rs.unparse(data, full_document=False, attr_dict_key="#attrs2")
+
+
+def test_unparse_attr_dict_key_requires_a_dict():
+    with pytest.raises(TypeError):
+        xmltodict_rs.unparse({"a": {"#attrs": "x"}}, attr_dict_key="#attrs")
+
+
+def test_unparse_iter_attr_dict_key():
+    data = {"a": {"#attrs": {"id": "1"}, "b": ["x", "y"]}}
+    assert "".join(xmltodict_rs.unparse_iter(data, attr_dict_key="#attrs")) == xmltodict_rs.unparse(
+        data, attr_dict_key="#attrs"
+    )
+
+
+# Duplicate attributes
+
+
+DUPLICATE_ATTRIBUTE_OBJECTS = [
+    ({"r": {"@id": "1", "#attrs": {"id": "2"}}}, {"attr_dict_key": "#attrs"}),
+    (
+        {"r": {"@a:id": "1", "@urn:a:id": "2"}},
+        {"namespaces": {"urn:a": "a"}, "full_document": False},
+    ),
+    ({"r": [{"@id": "1"}, "x", {"@id": "2"}]}, {"preserve_mixed_content": True}),
+]
+
+
+@pytest.mark.parametrize(("obj", "kwargs"), DUPLICATE_ATTRIBUTE_OBJECTS)
+def test_unparse_rejects_duplicate_attributes(obj, kwargs):
+    with pytest.raises(ValueError, match='duplicate attribute "[a:]*id" on <r>'):
+        xmltodict_rs.unparse(obj, **kwargs)
+    with pytest.raises(ValueError, match="duplicate attribute"):
+        "".join(xmltodict_rs.unparse_iter(obj, **kwargs))
+
+
+def test_unparse_duplicate_attributes_last_wins():
+    data = {"r": {"@id": "1", "@k": "v", "#attrs": {"id": "2"}}}
+    with pytest.warns(UserWarning, match='duplicate attribute "id" on <r>'):
+        result = xmltodict_rs.unparse(
+            data, full_document=False, attr_dict_key="#attrs", duplicate_attributes="last"
+        )
+    assert result == '<r id="2" k="v"></r>'
+
+
+def test_unparse_unknown_duplicate_attributes_mode():
+    with pytest.raises(ValueError, match="duplicate_attributes must be"):
+        xmltodict_rs.unparse({"r": "x"}, duplicate_attributes="first")
+
+
+# Canonical output
+
+
+def test_unparse_canonical_sorts_attributes():
+    data = {"r": {"@z": "1", "@xmlns:b": "urn:b", "@a": "2", "@xmlns": "urn:r", "b:c": "x"}}
+    result = xmltodict_rs.unparse(data, canonical=True)
+    assert result == '<r xmlns="urn:r" xmlns:b="urn:b" a="2" z="1"><b:c>x</b:c></r>'
+
+
+def test_unparse_canonical_ignores_attribute_order():
+    first = {"r": {"@a": "1", "@b": "2", "x": None}}
+    second = {"r": {"@b": "2", "@a": "1", "x": None}}
+    assert xmltodict_rs.unparse(first, canonical=True) == xmltodict_rs.unparse(
+        second, canonical=True
+    )
+
+
+def test_unparse_canonical_expands_empty_elements():
+    data = {"r": {"e": None, "f": ""}}
+    result = xmltodict_rs.unparse(data, canonical=True, short_empty_elements=True)
+    assert result == "<r><e></e><f></f></r>"
+
+
+def test_unparse_canonical_drops_declaration_doctype_and_formatting():
+    data = {"#doctype": "html", "r": {"a": "1"}}
+    result = xmltodict_rs.unparse(data, canonical=True, pretty=True, indent="  ")
+    assert result == "<r><a>1</a></r>"
+
+
+def test_unparse_canonical_escaping():
+    data = {"r": {"@a": 'x>"\ty\n', "#text": "a>b\r&"}}
+    result = xmltodict_rs.unparse(data, canonical=True)
+    assert result == '<r a="x>&quot;&#x9;y&#xA;">a&gt;b&#xD;&amp;</r>'
+
+
+def test_unparse_canonical_writes_text_instead_of_cdata():
+    result = xmltodict_rs.unparse({"r": "a<b"}, canonical=True, use_cdata=True)
+    assert result == "<r>a&lt;b</r>"
+
+
+def test_unparse_canonical_separates_top_level_nodes():
+    data = {"#comment": "c", "r": "x"}
+    assert xmltodict_rs.unparse(data, canonical=True) == "<!--c-->\n<r>x</r>"
+
+
+def test_unparse_canonical_is_stable_across_roundtrips():
+    xml = '<r b="2" a="1"><x/>\n  <y k="v">t</y></r>'
+    canonical = xmltodict_rs.unparse(xmltodict_rs.parse(xml), canonical=True)
+    assert xmltodict_rs.unparse(xmltodict_rs.parse(canonical), canonical=True) == canonical
+
+
+def test_unparse_iter_canonical():
+    data = {"r": {"@b": "2", "@a": "1", "e": None}}
+    assert "".join(xmltodict_rs.unparse_iter(data, canonical=True)) == xmltodict_rs.unparse(
+        data, canonical=True
+    )
+
+
+def test_roundtrip_canonical():
+    assert xmltodict_rs.roundtrip("<r b='2' a='1'><e/></r>", canonical=True) == (
+        '<r a="1" b="2"><e></e></r>'
+    )
+
+
+# Models
+
+
+@dataclasses.dataclass
+class Author:
+    name: str
+    born: int | None = None
+
+
+@dataclasses.dataclass
+class Book:
+    title: str
+    authors: list[Author]
+    tags: tuple[str, ...] = ()
+
+
+class Isbn:
+    def __init__(self, value):
+        self.value = value
+
+    def __xml__(self):
+        return {"@type": "isbn13", "#text": self.value}
+
+
+class Shelf(NamedTuple):
+    row: int
+    side: str
+
+
+def test_unparse_dataclasses():
+    book = Book("Dune", [Author("Frank Herbert", 1920), Author("Brian Herbert")], ("sf",))
+    assert xmltodict_rs.unparse({"book": book}, full_document=False) == (
+        "<book><title>Dune</title>"
+        "<authors><name>Frank Herbert</name><born>1920</born></authors>"
+        "<authors><name>Brian Herbert</name><born></born></authors>"
+        "<tags>sf</tags></book>"
+    )
+
+
+def test_unparse_xml_method_and_named_tuples():
+    data = {"r": {"id": Isbn("978-0441013593"), "shelf": Shelf(2, "left")}}
+    assert xmltodict_rs.unparse(data, full_document=False) == (
+        '<r><id type="isbn13">978-0441013593</id><shelf><row>2</row><side>left</side></shelf></r>'
+    )
+
+
+def test_unparse_model_document():
+    class Document:
+        def __xml__(self):
+            return {"r": {"author": Author("A")}}
+
+    assert xmltodict_rs.unparse(Document(), full_document=False) == (
+        "<r><author><name>A</name><born></born></author></r>"
+    )
+    assert xmltodict_rs.unparse(Author("A", 1), full_document=False) == (
+        "<name>A</name><born>1</born>"
+    )
+    assert "".join(xmltodict_rs.unparse_iter(Document(), full_document=False)) == (
+        xmltodict_rs.unparse(Document(), full_document=False)
+    )
+
+
+def test_unparse_model_classes_are_not_models():
+    result = xmltodict_rs.unparse({"r": Author, "s": Shelf}, full_document=False)
+    assert result == xmltodict_rs.unparse(
+        {"r": str(Author), "s": str(Shelf)}, full_document=False
+    )
+
+
+def test_unparse_models_before_default():
+    seen = []
+    result = xmltodict_rs.unparse(
+        {"r": Author("A")}, full_document=False, default=lambda value: seen.append(value) or ""
+    )
+    assert seen == []
+    assert result == "<r><name>A</name><born></born></r>"
+
+
+def test_unparse_model_errors_propagate():
+    class Broken:
+        def __xml__(self):
+            raise RuntimeError("no xml")
+
+    with pytest.raises(RuntimeError, match="no xml"):
+        xmltodict_rs.unparse({"r": Broken()})
+
+
+# Pydantic models
+
+
+def _base_model(module):
+    # Stands in for pydantic's BaseModel, which is recognized by its name and module
+    return type("BaseModel", (), {"__module__": module})
+
+
+class OrderV2(_base_model("pydantic.main")):
+    def __init__(self, **fields):
+        self.fields = fields
+
+    def model_dump(self):
+        return dict(self.fields)
+
+
+class OrderV1(_base_model("pydantic.v1.main")):
+    def __init__(self, **fields):
+        self.fields = fields
+
+    def dict(self):
+        return dict(self.fields)
+
+
+@pytest.mark.parametrize("model", [OrderV2, OrderV1])
+def test_unparse_pydantic_models(model):
+    data = {"order": model(id=7, lines=[{"sku": "a"}, {"sku": "b"}])}
+    expected = "<order><id>7</id><lines><sku>a</sku></lines><lines><sku>b</sku></lines></order>"
+    assert xmltodict_rs.unparse(data, full_document=False, pydantic_support=True) == expected
+    assert "".join(
+        xmltodict_rs.unparse_iter(data, full_document=False, pydantic_support=True)
+    ) == expected
+
+
+def test_unparse_pydantic_document():
+    order = OrderV2(order={"id": 7})
+    assert xmltodict_rs.unparse(order, full_document=False, pydantic_support=True) == (
+        "<order><id>7</id></order>"
+    )
+
+
+def test_unparse_pydantic_models_need_the_flag():
+    result = xmltodict_rs.unparse({"order": OrderV2(id=7)}, full_document=False)
+    assert result.startswith("<order>&lt;")
+    assert "<id>" not in result
+
+
+def test_unparse_pydantic_support_checks_the_module():
+    other = _base_model("mylib.models")()
+    result = xmltodict_rs.unparse({"r": other}, full_document=False, pydantic_support=True)
+    assert result.startswith("<r>&lt;mylib.models.BaseModel object")
+
+
+def test_unparse_pydantic():
+    pydantic = pytest.importorskip("pydantic")
+
+    class Line(pydantic.BaseModel):
+        sku: str
+
+    class Order(pydantic.BaseModel):
+        id: int
+        lines: list[Line]
+
+    order = Order(id=7, lines=[Line(sku="a")])
+    assert xmltodict_rs.unparse({"order": order}, full_document=False, pydantic_support=True) == (
+        "<order><id>7</id><lines><sku>a</sku></lines></order>"
+    )
+
+
+ENVELOPE = (
+    '<soap:Envelope xmlns:soap="urn:soap" xmlns:m="urn:m">'
+    '<soap:Body><m:Pay id="1">5</m:Pay></soap:Body>'
+    "</soap:Envelope>"
+)
+
+
+def test_unparse_fragment_declares_inherited_namespaces():
+    doc = xmltodict_rs.parse(ENVELOPE, collect_namespaces=True)
+    body = doc["soap:Envelope"]["soap:Body"]
+    result = xmltodict_rs.unparse_fragment(body, "soap:Body", namespaces=doc["#namespaces"])
+    assert result == (
+        '<soap:Body xmlns:soap="urn:soap" xmlns:m="urn:m"><m:Pay id="1">5</m:Pay></soap:Body>'
+    )
+    assert xmltodict_rs.parse(result) == {
+        "soap:Body": {"@xmlns:soap": "urn:soap", "@xmlns:m": "urn:m", "m:Pay": body["m:Pay"]}
+    }
+
+
+def test_unparse_fragment_keeps_declarations_of_its_own():
+    result = xmltodict_rs.unparse_fragment(
+        {"@xmlns:m": "urn:other", "#text": "x"},
+        "m:a",
+        namespaces={"m": "urn:m", "": "urn:d"},
+    )
+    assert result == '<m:a xmlns="urn:d" xmlns:m="urn:other">x</m:a>'
+
+
+def test_unparse_fragment_writes_expanded_names_with_prefixes():
+    doc = xmltodict_rs.parse(ENVELOPE, process_namespaces=True)
+    pay = doc["urn:soap:Envelope"]["urn:soap:Body"]["urn:m:Pay"]
+    result = xmltodict_rs.unparse_fragment(pay, "urn:m:Pay", namespaces={"m": "urn:m"})
+    assert result == '<m:Pay xmlns:m="urn:m" id="1">5</m:Pay>'
+
+
+def test_unparse_fragment_options():
+    assert xmltodict_rs.unparse_fragment("x", "a") == "<a>x</a>"
+    assert xmltodict_rs.unparse_fragment(None, "a", short_empty_elements=True) == "<a/>"
+    assert xmltodict_rs.unparse_fragment("x", "a", full_document=True).startswith("<?xml")
+    assert xmltodict_rs.unparse_fragment("x", "a", return_bytes=True) == b"<a>x</a>"
+
+
+def test_unparse_fragment_rejects_several_elements():
+    with pytest.raises(ValueError, match="single element"):
+        xmltodict_rs.unparse_fragment(["a", "b"], "a")
@@ -0,0 +1,223 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+XSD = """<?xml version="1.0"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="order">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="id" type="xs:int"/>
+        <xs:element name="status" type="Status"/>
+        <xs:element name="line" type="Line" maxOccurs="unbounded"/>
+        <xs:element name="note" type="xs:string" minOccurs="0"/>
+      </xs:sequence>
+      <xs:attribute name="version" type="xs:decimal" use="required"/>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="Line">
+    <xs:all>
+      <xs:element name="sku" type="Sku"/>
+      <xs:element name="qty" type="xs:positiveInteger"/>
+    </xs:all>
+  </xs:complexType>
+  <xs:simpleType name="Status">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="open"/>
+      <xs:enumeration value="paid"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Sku">
+    <xs:restriction base="xs:string">
+      <xs:minLength value="2"/>
+      <xs:maxLength value="8"/>
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>"""
+
+VALID = """<order version="1">
+  <id>42</id>
+  <status>paid</status>
+  <line><qty>2</qty><sku>A1</sku></line>
+  <line><sku>B2</sku><qty>1</qty></line>
+</order>"""
+
+
+# validate()
+
+
+@pytest.mark.parametrize("xml", [VALID, VALID.encode(), io.BytesIO(VALID.encode())])
+def test_valid_document_has_no_errors(xml):
+    assert xmltodict_rs.validate(xml, XSD) == []
+
+
+def test_xsd_may_be_bytes_or_a_file():
+    assert xmltodict_rs.validate(VALID, XSD.encode()) == []
+    assert xmltodict_rs.validate(VALID, io.BytesIO(XSD.encode())) == []
+
+
+def test_errors_have_path_line_expected_and_found():
+    xml = """<order version="1">
+  <id>x</id>
+  <status>new</status>
+  <line><sku>A</sku></line>
+</order>"""
+    assert xmltodict_rs.validate(xml, XSD) == [
+        {
+            "path": "order/id",
+            "line": 2,
+            "expected": "xs:int",
+            "found": "x",
+            "message": 'order/id: expected xs:int, found "x" (line 2)',
+        },
+        {
+            "path": "order/status",
+            "line": 3,
+            "expected": 'one of "open", "paid"',
+            "found": "new",
+            "message": 'order/status: expected one of "open", "paid", found "new" (line 3)',
+        },
+        {
+            "path": "order/line/sku",
+            "line": 4,
+            "expected": "at least 2 characters",
+            "found": "A",
+            "message": 'order/line/sku: expected at least 2 characters, found "A" (line 4)',
+        },
+        {
+            "path": "order/line",
+            "line": 4,
+            "expected": "<qty>",
+            "found": None,
+            "message": "order/line: expected <qty>, found nothing (line 4)",
+        },
+    ]
+
+
+def test_element_and_attribute_errors():
+    xml = '<order extra="1"><id>1</id><line><sku>AB</sku><qty>1</qty></line></order>'
+    errors = xmltodict_rs.validate(xml, XSD)
+    assert [(error["path"], error["expected"], error["found"]) for error in errors] == [
+        ("order/@extra", "one of the attributes version", "attribute extra"),
+        ("order/@version", "attribute version", None),
+        ("order/line", "<status>", "<line>"),
+    ]
+
+
+def test_unknown_root():
+    assert xmltodict_rs.validate("<invoice/>", XSD) == [
+        {
+            "path": "invoice",
+            "line": 1,
+            "expected": "<order>",
+            "found": "<invoice>",
+            "message": "invoice: expected <order>, found <invoice> (line 1)",
+        }
+    ]
+
+
+def test_xsi_nil_and_namespaces():
+    xsd = """<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:t"
+        elementFormDefault="qualified">
+      <xs:element name="a"><xs:complexType><xs:sequence>
+        <xs:element name="b" type="xs:int" nillable="true"/>
+      </xs:sequence></xs:complexType></xs:element>
+    </xs:schema>"""
+    xml = """<t:a xmlns:t="urn:t" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <t:b xsi:nil="true"/>
+    </t:a>"""
+    assert xmltodict_rs.validate(xml, xsd) == []
+
+
+TARGET_XSD = """<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:t">
+  <xs:element name="a"><xs:complexType><xs:sequence>
+    <xs:element name="b"/>
+  </xs:sequence></xs:complexType></xs:element>
+</xs:schema>"""
+
+
+@pytest.mark.parametrize(
+    "xml",
+    [
+        '<t:a xmlns:t="urn:t"><b/></t:a>',
+        '<a xmlns="urn:t"><b xmlns=""/></a>',
+    ],
+)
+def test_names_match_in_the_target_namespace(xml):
+    assert xmltodict_rs.validate(xml, TARGET_XSD) == []
+
+
+@pytest.mark.parametrize(
+    ("xml", "message"),
+    [
+        ("<a><b/></a>", "a: expected <{urn:t}a>, found <a> (line 1)"),
+        ('<a xmlns="urn:o"><b/></a>', "a: expected <{urn:t}a>, found <{urn:o}a> (line 1)"),
+        ('<a xmlns="urn:t"><b/></a>', "a/b: expected <b>, found <{urn:t}b> (line 1)"),
+    ],
+)
+def test_names_in_another_namespace_are_reported(xml, message):
+    assert [issue["message"] for issue in xmltodict_rs.validate(xml, TARGET_XSD)] == [message]
+
+
+@pytest.mark.parametrize(
+    "declaration",
+    [
+        '<xs:include schemaLocation="other.xsd"/>',
+        '<xs:import namespace="urn:o" schemaLocation="other.xsd"/>',
+        '<xs:redefine schemaLocation="other.xsd"/>',
+        '<xs:element name="c" substitutionGroup="t:a"/>',
+    ],
+)
+def test_unsupported_schema_constructs_raise(declaration):
+    xsd = TARGET_XSD.replace("</xs:schema>", declaration + "</xs:schema>")
+    with pytest.raises(ValueError, match="not supported"):
+        xmltodict_rs.validate("<a/>", xsd)
+
+
+# parse(validate_against=...)
+
+
+def test_parse_valid_document():
+    assert xmltodict_rs.parse(VALID, validate_against=XSD) == xmltodict_rs.parse(VALID)
+
+
+def test_parse_raises_for_invalid_document():
+    xml = VALID.replace("<id>42</id>", "<id>x</id>").replace("paid", "new")
+    with pytest.raises(xmltodict_rs.XmlValidationError) as info:
+        xmltodict_rs.parse(xml, validate_against=XSD)
+    assert str(info.value) == 'order/id: expected xs:int, found "x" (line 2) (and 1 more)'
+    assert [error["path"] for error in info.value.errors] == ["order/id", "order/status"]
+    assert isinstance(info.value, ValueError)
+
+
+def test_parse_validates_attributes_without_xml_attribs():
+    assert xmltodict_rs.parse(VALID, validate_against=XSD, xml_attribs=False) == (
+        xmltodict_rs.parse(VALID, xml_attribs=False)
+    )
+    with pytest.raises(xmltodict_rs.XmlValidationError, match="attribute version"):
+        xml = VALID.replace(' version="1"', "")
+        xmltodict_rs.parse(xml, validate_against=XSD, xml_attribs=False)
+
+
+def test_parse_with_xsd_and_validate_against():
+    parsed = xmltodict_rs.parse(VALID, xsd=XSD, validate_against=XSD)
+    assert parsed["order"]["id"] == 42
+    assert parsed["order"]["line"][1] == {"sku": "B2", "qty": 1}
+
+
+# Errors
+
+
+def test_invalid_xsd():
+    with pytest.raises(ValueError, match="xs:schema root element") as info:
+        xmltodict_rs.validate(VALID, "<a/>")
+    assert "while reading the xsd" in info.value.__notes__
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.parse(VALID, validate_against="<xs:schema>")
+
+
+def test_malformed_document():
+    with pytest.raises(xmltodict_rs.XmlSyntaxError):
+        xmltodict_rs.validate("<order>", XSD)
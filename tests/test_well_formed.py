@@ -0,0 +1,65 @@
+import io
+
+import pytest
+
+import xmltodict_rs
+
+
+def test_well_formed_documents():
+    assert xmltodict_rs.is_well_formed("<a><b x='1'>t</b><!--c--></a>") == (True, None)
+    assert xmltodict_rs.is_well_formed(io.BytesIO(b"<a/>")) == (True, None)
+    assert xmltodict_rs.is_well_formed(iter(["<a>", "</a>"])) == (True, None)
+
+
+def test_reports_the_first_error():
+    ok, error = xmltodict_rs.is_well_formed("<a>\n  <b></a>")
+    assert not ok
+    assert error["code"] == 7
+    assert error["line"] == 2
+    assert error["message"].startswith("Expecting </b>")
+    with pytest.raises(xmltodict_rs.XmlSyntaxError) as raised:
+        xmltodict_rs.parse("<a>\n  <b></a>")
+    assert str(raised.value) == error["message"]
+
+
+@pytest.mark.parametrize("xml", ["", "<a>", "<a></b>", "<a x='1' x='2'/>", "<a>&bogus;</a>"])
+def test_malformed_documents(xml):
+    ok, error = xmltodict_rs.is_well_formed(xml)
+    assert not ok
+    assert set(error) == {"message", "code", "line", "column", "offset"}
+
+
+@pytest.mark.parametrize(
+    ("xml", "code", "column"),
+    [
+        ("<a/><b/>", 9, 4),
+        ("<a/>junk", 9, 4),
+        ("<a></a>\n<![CDATA[x]]>", 9, 0),
+        ("text<a/>", 2, 0),
+    ],
+)
+def test_one_root_and_no_text_outside_it(xml, code, column):
+    ok, error = xmltodict_rs.is_well_formed(xml)
+    assert not ok
+    assert (error["code"], error["column"]) == (code, column)
+
+
+def test_whitespace_and_markup_outside_the_root():
+    doc = '<?xml version="1.0"?>\n<!--c-->\n<a/>\n<?pi x?>\n'
+    assert xmltodict_rs.is_well_formed(doc) == (True, None)
+
+
+def test_limits_and_forbidden_markup():
+    assert not xmltodict_rs.is_well_formed("<a><b><c/></b></a>", max_depth=2)[0]
+    assert xmltodict_rs.is_well_formed("<a><b><c/></b></a>", max_depth=3) == (True, None)
+    doc = '<!DOCTYPE a [<!ENTITY e "x">]><a>&e;</a>'
+    assert not xmltodict_rs.is_well_formed(doc, forbid_dtd=True)[0]
+
+
+def test_read_errors_are_raised():
+    def chunks():
+        yield "<a>"
+        raise RuntimeError("boom")
+
+    with pytest.raises(RuntimeError, match="boom"):
+        xmltodict_rs.is_well_formed(chunks())
@@ -0,0 +1,122 @@
+import datetime
+import decimal
+import xmlrpc.client
+
+import pytest
+
+import xmltodict_rs
+
+PARAMS = (
+    1,
+    -7,
+    2.5,
+    1e20,
+    "a < b & c",
+    "",
+    True,
+    False,
+    [1, "x", [2.0]],
+    (),
+    {"k": "v", "nested": {"n": 1}},
+    {},
+    b"\x00binary" * 20,
+    bytearray(b"xy"),
+    datetime.datetime(2024, 1, 2, 3, 4, 5),
+)
+
+
+# Writing
+
+
+@pytest.mark.parametrize("param", PARAMS, ids=repr)
+def test_dumps_matches_xmlrpc_client(param):
+    assert xmltodict_rs.dumps_xmlrpc((param,)) == xmlrpc.client.dumps((param,))
+
+
+def test_dumps_call_and_response():
+    assert xmltodict_rs.dumps_xmlrpc(PARAMS, "books.find") == xmlrpc.client.dumps(
+        PARAMS, "books.find"
+    )
+    assert xmltodict_rs.dumps_xmlrpc((5,), methodresponse=True) == xmlrpc.client.dumps(
+        (5,), methodresponse=True
+    )
+    with pytest.raises(ValueError, match="exactly one parameter"):
+        xmltodict_rs.dumps_xmlrpc((1, 2), methodresponse=True)
+
+
+def test_dumps_none_and_xmlrpc_types():
+    params = ({"k": None}, xmlrpc.client.DateTime("20200101T00:00:00"), xmlrpc.client.Binary(b"z"))
+    assert xmltodict_rs.dumps_xmlrpc(params, allow_none=True) == xmlrpc.client.dumps(
+        params, allow_none=True
+    )
+
+
+def test_dumps_fault():
+    fault = xmlrpc.client.Fault(4, "Too many parameters")
+    assert xmltodict_rs.dumps_xmlrpc(fault) == xmlrpc.client.dumps(fault)
+
+
+@pytest.mark.parametrize(
+    ("params", "error", "message"),
+    [
+        ((2**31,), OverflowError, "int exceeds XML-RPC limits"),
+        ((None,), TypeError, "unless allow_none is enabled"),
+        (({1: "a"},), TypeError, "dictionary key must be string"),
+        ((object(),), TypeError, "cannot marshal <class 'object'> objects"),
+        ([1], TypeError, "params must be a tuple"),
+    ],
+)
+def test_dumps_rejects_unsupported_values(params, error, message):
+    with pytest.raises(error, match=message):
+        xmltodict_rs.dumps_xmlrpc(params)
+
+
+def test_dumps_rejects_recursive_values():
+    items = []
+    items.append(items)
+    with pytest.raises(TypeError, match="recursive lists"):
+        xmltodict_rs.dumps_xmlrpc((items,))
+
+
+# Reading
+
+
+def test_loads_roundtrip():
+    xml = xmlrpc.client.dumps(PARAMS, "books.find", allow_none=True)
+    expected = xmlrpc.client.loads(xml, use_builtin_types=True)
+    assert xmltodict_rs.loads_xmlrpc(xml) == expected
+    assert xmltodict_rs.loads_xmlrpc(xml.encode()) == expected
+
+
+def test_loads_untyped_and_extension_values():
+    xml = """<methodResponse><params><param><value><array><data>
+        <value>  plain </value>
+        <value/>
+        <value><i8>9000000000</i8></value>
+        <value><bigdecimal>1.10</bigdecimal></value>
+        <value><nil/></value>
+    </data></array></value></param></params></methodResponse>"""
+    params, methodname = xmltodict_rs.loads_xmlrpc(xml)
+    assert params == (["  plain ", "", 9000000000, decimal.Decimal("1.10"), None],)
+    assert methodname is None
+
+
+def test_loads_fault():
+    xml = xmlrpc.client.dumps(xmlrpc.client.Fault(4, "Too many parameters"))
+    with pytest.raises(xmlrpc.client.Fault) as info:
+        xmltodict_rs.loads_xmlrpc(xml)
+    assert (info.value.faultCode, info.value.faultString) == (4, "Too many parameters")
+
+
+@pytest.mark.parametrize(
+    ("value", "message"),
+    [
+        ("<boolean>2</boolean>", "bad boolean value"),
+        ("<dateTime.iso8601>2024-01-02</dateTime.iso8601>", "bad dateTime.iso8601 value"),
+        ("<date>1</date>", "unknown XML-RPC tag"),
+    ],
+)
+def test_loads_rejects_bad_values(value, message):
+    xml = f"<params><param><value>{value}</value></param></params>"
+    with pytest.raises(ValueError, match=message):
+        xmltodict_rs.loads_xmlrpc(xml)
@@ -0,0 +1,175 @@
+import datetime
+
+import pytest
+
+import xmltodict_rs
+
+XSD = """<?xml version="1.0"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="order">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="id" type="xs:int"/>
+        <xs:element name="paid" type="xs:boolean"/>
+        <xs:element name="created" type="xs:dateTime"/>
+        <xs:element name="note" type="xs:string" minOccurs="0"/>
+        <xs:element name="line" type="Line" maxOccurs="unbounded"/>
+      </xs:sequence>
+      <xs:attribute name="version" type="xs:decimal"/>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="Line">
+    <xs:sequence>
+      <xs:element name="sku" type="xs:string"/>
+      <xs:element name="qty" type="Quantity"/>
+      <xs:element name="price" type="Price"/>
+    </xs:sequence>
+  </xs:complexType>
+  <xs:simpleType name="Quantity">
+    <xs:restriction base="xs:positiveInteger">
+      <xs:maxInclusive value="100"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:complexType name="Price">
+    <xs:simpleContent>
+      <xs:extension base="xs:decimal">
+        <xs:attribute name="currency" type="xs:string"/>
+      </xs:extension>
+    </xs:simpleContent>
+  </xs:complexType>
+</xs:schema>"""
+
+XML = """<order version="1.1">
+  <id>42</id>
+  <paid>true</paid>
+  <created>2024-01-02T03:04:05Z</created>
+  <note>007</note>
+  <line><sku>A1</sku><qty>2</qty><price currency="EUR">9.5</price></line>
+</order>"""
+
+EXPECTED = {
+    "order": {
+        "@version": 1.1,
+        "id": 42,
+        "paid": True,
+        "created": datetime.datetime(2024, 1, 2, 3, 4, 5, tzinfo=datetime.timezone.utc),
+        "note": "007",
+        "line": [{"sku": "A1", "qty": 2, "price": {"@currency": "EUR", "#text": 9.5}}],
+    }
+}
+
+
+# Types and lists
+
+
+@pytest.mark.parametrize("xsd", [XSD, XSD.encode()])
+def test_parse_with_xsd(xsd):
+    assert xmltodict_rs.parse(XML, xsd=xsd) == EXPECTED
+
+
+def test_xsd_follows_attr_prefix_and_cdata_key():
+    parsed = xmltodict_rs.parse(XML, xsd=XSD, attr_prefix="_", cdata_key="text", force_cdata=True)
+    order = parsed["order"]
+    assert order["_version"] == 1.1
+    assert order["id"] == {"text": 42}
+    assert order["line"][0]["price"] == {"_currency": "EUR", "text": 9.5}
+
+
+def test_xsd_references_groups_and_recursive_types():
+    xsd = """<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="tree" type="Node"/>
+      <xs:element name="size" type="xs:long"/>
+      <xs:complexType name="Node">
+        <xs:sequence>
+          <xs:element ref="size"/>
+          <xs:choice minOccurs="0" maxOccurs="unbounded">
+            <xs:element name="node" type="Node"/>
+            <xs:element name="leaf" type="xs:boolean"/>
+          </xs:choice>
+        </xs:sequence>
+      </xs:complexType>
+    </xs:schema>"""
+    xml = (
+        "<tree><size>1</size>"
+        "<node><size>2</size><node><size>3</size><leaf>1</leaf></node></node>"
+        "</tree>"
+    )
+    assert xmltodict_rs.parse(xml, xsd=xsd) == {
+        "tree": {
+            "size": 1,
+            "node": [{"size": 2, "node": [{"size": 3, "leaf": [True]}]}],
+        }
+    }
+
+
+def test_xsd_with_unprefixed_schema_and_default_namespace():
+    xsd = """<schema xmlns="http://www.w3.org/2001/XMLSchema" xmlns:t="urn:t"
+                     targetNamespace="urn:t">
+      <element name="a"><complexType><sequence>
+        <element name="b" type="t:Count" maxOccurs="2"/>
+      </sequence></complexType></element>
+      <simpleType name="Count"><restriction base="int"/></simpleType>
+    </schema>"""
+    assert xmltodict_rs.parse('<a xmlns="urn:t"><b>1</b></a>', xsd=xsd) == {
+        "a": {"@xmlns": "urn:t", "b": [1]}
+    }
+
+
+# Options
+
+
+def test_transforms_and_force_list_apply_with_xsd():
+    parsed = xmltodict_rs.parse(
+        XML, xsd=XSD, transforms={"id": "strip", "**/note": "int"}, force_list=("paid",)
+    )
+    order = parsed["order"]
+    assert order["id"] == "42"
+    assert order["note"] == 7
+    assert order["paid"] == [True]
+
+
+def test_xsd_values_reach_postprocessor():
+    seen = []
+
+    def postprocessor(path, key, value):
+        seen.append((key, value))
+        return key, value
+
+    xmltodict_rs.parse("<order><id>1</id></order>", xsd=XSD, postprocessor=postprocessor)
+    assert ("id", 1) in seen
+
+
+def test_xsd_with_convention():
+    parsed = xmltodict_rs.parse(XML, xsd=XSD, convention="badgerfish")
+    assert parsed["order"]["@version"] == 1.1
+    assert parsed["order"]["line"][0]["qty"] == {"$": 2}
+
+
+# Errors
+
+
+def test_xsd_conversion_errors_note_the_path():
+    with pytest.raises(ValueError) as info:
+        xmltodict_rs.parse("<order><id>x</id></order>", xsd=XSD)
+    assert "while transforming order/id (line 1)" in info.value.__notes__
+
+
+@pytest.mark.parametrize(
+    ("xsd", "error", "message"),
+    [
+        ("<a/>", ValueError, 'xs:schema root element, not "a"'),
+        (
+            '<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">'
+            '<xs:element name="a"><xs:complexType><xs:sequence>'
+            '<xs:element name="b" maxOccurs="many"/>'
+            "</xs:sequence></xs:complexType></xs:element></xs:schema>",
+            ValueError,
+            "maxOccurs in xsd must be a number or 'unbounded'",
+        ),
+        ("<xs:schema>", xmltodict_rs.XmlSyntaxError, "unclosed"),
+        (1, TypeError, "cannot be converted"),
+    ],
+)
+def test_rejects_invalid_xsd(xsd, error, message):
+    with pytest.raises(error, match=message):
+        xmltodict_rs.parse(XML, xsd=xsd)